@@ -4,7 +4,14 @@ pragma solidity ^0.8.19;
 import "./interfaces/IFlashLoanReceiver.sol";
 import "./interfaces/ILendingPool.sol";
 import "./interfaces/IUniswapV2Router.sol";
+import "./interfaces/IUniswapV2Pair.sol";
+import "./interfaces/IUniswapV3Pool.sol";
+import "./interfaces/IUniswapV3Factory.sol";
+import "./interfaces/IBalancerVault.sol";
+import "./interfaces/IMorpho.sol";
 import "./interfaces/ICurveRouter.sol";
+import "./interfaces/IBentoBox.sol";
+import "./interfaces/ITridentPool.sol";
 import "./interfaces/IERC20.sol";
 import "./libraries/Ownable.sol";
 import "./libraries/ReentrancyGuard.sol";
@@ -22,12 +29,18 @@ contract ArbitrageExecutor is IFlashLoanReceiver, Ownable, ReentrancyGuard {
     uint256 private constant BASIS_POINTS = 10000; // 100%
     uint256 private constant MAX_SLIPPAGE = 300; // 3% max slippage
     uint256 private constant MAX_PRICE_IMPACT = 500; // 5% max price impact
+    uint256 private constant V2_FEE_BPS = 30; // 0.3% Uniswap V2 / Sushiswap pair fee
+    uint8 private constant LENDER_BALANCER = 0; // Balancer Vault, calls receiveFlashLoan
+    uint8 private constant LENDER_UNISWAP_V3 = 1; // Uniswap V3 pool, calls uniswapV3FlashCallback
+    uint8 private constant LENDER_MORPHO = 2; // Morpho Blue, calls onMorphoFlashLoan
+    uint8 private constant LENDER_AAVE_POOL = 3; // Aave V3 fork pool such as Spark, calls executeOperation
     
     // State variables
     address public lendingPoolAddress;
     address public uniswapRouterAddress;
     address public sushiswapRouterAddress;
     address public curveRouterAddress;
+    address public bentoBoxAddress; // Sushi BentoBox, for Trident legs (unset disables them)
     
     // Authorized callers
     mapping(address => bool) public authorizedCallers;
@@ -35,6 +48,13 @@ contract ArbitrageExecutor is IFlashLoanReceiver, Ownable, ReentrancyGuard {
     // Circuit breaker
     bool public emergencyStop;
     
+    // Lenders executeFlashLoan may borrow from (Balancer Vaults, Morpho Blue, Aave V3 fork pools,
+    // and Uniswap V3 factories for their pools)
+    mapping(address => bool) public flashLenders;
+    
+    // Lender whose callback is expected while the contract takes a flash loan or flash swap
+    address private activeLender;
+    
     // Events
     event ArbitrageExecuted(
         address[] path,
@@ -50,6 +70,8 @@ contract ArbitrageExecutor is IFlashLoanReceiver, Ownable, ReentrancyGuard {
     event CallerUnauthorized(address indexed caller);
     event TokensRecovered(address indexed token, uint256 amount);
     event ETHRecovered(uint256 amount);
+    event BentoBoxUpdated(address indexed bentoBox);
+    event FlashLenderUpdated(address indexed lender, bool allowed);
     
     /**
      * @dev Constructor
@@ -132,6 +154,40 @@ contract ArbitrageExecutor is IFlashLoanReceiver, Ownable, ReentrancyGuard {
         emit EmergencyStopDeactivated(msg.sender);
     }
     
+    /**
+     * @dev Set the BentoBox that Trident legs deposit into
+     * @param bentoBox Address of the BentoBox (address(0) disables Trident legs)
+     */
+    function setBentoBox(address bentoBox) external onlyOwner {
+        bentoBoxAddress = bentoBox;
+        emit BentoBoxUpdated(bentoBox);
+    }
+    
+    /**
+     * @dev Allow or disallow a lender executeFlashLoan borrows from
+     * @param lender Address of a Balancer Vault, Morpho Blue or an Aave V3 fork pool, or of a
+     * Uniswap V3 factory whose pools may lend
+     * @param allowed Whether the lender is allowed
+     */
+    function setFlashLender(address lender, bool allowed) external onlyOwner {
+        require(lender != address(0), "ArbitrageExecutor: lender address cannot be zero");
+        flashLenders[lender] = allowed;
+        emit FlashLenderUpdated(lender, allowed);
+    }
+    
+    /**
+     * @dev Set standing approvals, so trades and repayments need not approve each time
+     * @param tokens Addresses of the tokens to approve
+     * @param spenders Address allowed to spend each token (a router, the BentoBox or a lender)
+     */
+    function approveTokens(address[] calldata tokens, address[] calldata spenders) external onlyOwner {
+        require(tokens.length == spenders.length, "ArbitrageExecutor: tokens and spenders length mismatch");
+        
+        for (uint256 i = 0; i < tokens.length; i++) {
+            IERC20(tokens[i]).approve(spenders[i], type(uint256).max);
+        }
+    }
+    
     /**
      * @dev Recover ERC20 tokens sent to the contract by mistake
      * @param token Address of the token to recover
@@ -162,7 +218,10 @@ contract ArbitrageExecutor is IFlashLoanReceiver, Ownable, ReentrancyGuard {
      * @param modes The modes of the flash loan (0 = no debt, 1 = stable, 2 = variable)
      * @param tokenPath The path of tokens to trade through
      * @param dexPath The path of DEXes to use for each trade
+     * @param poolPath The pool to use for each trade (address(0) lets the DEX router pick one)
      * @param slippage The slippage tolerance in basis points
+     * @param minProfit The minimum profit in the borrowed asset, below which the arbitrage reverts
+     * @param deadline The last block timestamp the arbitrage may execute at
      */
     function executeArbitrage(
         address[] calldata assets,
@@ -170,17 +229,22 @@ contract ArbitrageExecutor is IFlashLoanReceiver, Ownable, ReentrancyGuard {
         uint256[] calldata modes,
         address[] calldata tokenPath,
         string[] calldata dexPath,
-        uint256 slippage
+        address[] calldata poolPath,
+        uint256 slippage,
+        uint256 minProfit,
+        uint256 deadline
     ) external onlyAuthorized whenNotStopped nonReentrant {
         require(assets.length == 1, "ArbitrageExecutor: only single asset flash loans supported");
         require(amounts.length == 1, "ArbitrageExecutor: only single amount flash loans supported");
         require(modes.length == 1, "ArbitrageExecutor: only single mode flash loans supported");
         require(tokenPath.length >= 2, "ArbitrageExecutor: token path must have at least 2 tokens");
         require(dexPath.length == tokenPath.length - 1, "ArbitrageExecutor: dex path length must be token path length - 1");
+        require(poolPath.length == dexPath.length, "ArbitrageExecutor: pool path length must match dex path length");
         require(slippage <= MAX_SLIPPAGE, "ArbitrageExecutor: slippage too high");
+        require(block.timestamp <= deadline, "ArbitrageExecutor: deadline passed");
         
         // Encode the parameters for the flash loan
-        bytes memory params = abi.encode(tokenPath, dexPath, slippage);
+        bytes memory params = abi.encode(tokenPath, dexPath, poolPath, slippage, minProfit, deadline);
         
         // Execute the flash loan
         ILendingPool(lendingPoolAddress).flashLoan(
@@ -194,6 +258,235 @@ contract ArbitrageExecutor is IFlashLoanReceiver, Ownable, ReentrancyGuard {
         );
     }
     
+    /**
+     * @dev Execute a two-leg arbitrage funded by a flash swap from the pair of its first swap
+     * @param pair The Uniswap V2 style pair of the first swap, which lends the swap's output
+     * @param amountIn The amount of the first token the pair is repaid, which sizes the loan
+     * @param tokenPath The path of tokens to trade through (the first token, the borrowed token, the first token)
+     * @param dexPath The path of DEXes to use for each trade
+     * @param poolPath The pool to use for each trade (the first is the pair)
+     * @param slippage The slippage tolerance in basis points
+     * @param minProfit The minimum profit in the first token, below which the arbitrage reverts
+     * @param deadline The last block timestamp the arbitrage may execute at
+     */
+    function executeFlashSwap(
+        address pair,
+        uint256 amountIn,
+        address[] calldata tokenPath,
+        string[] calldata dexPath,
+        address[] calldata poolPath,
+        uint256 slippage,
+        uint256 minProfit,
+        uint256 deadline
+    ) external onlyAuthorized whenNotStopped nonReentrant {
+        require(tokenPath.length == 3, "ArbitrageExecutor: flash swaps fund two-leg routes");
+        require(tokenPath[2] == tokenPath[0], "ArbitrageExecutor: final token mismatch");
+        require(dexPath.length == 2, "ArbitrageExecutor: dex path length must be token path length - 1");
+        require(poolPath.length == 2 && poolPath[0] == pair, "ArbitrageExecutor: pair is not the first pool");
+        require(slippage <= MAX_SLIPPAGE, "ArbitrageExecutor: slippage too high");
+        require(block.timestamp <= deadline, "ArbitrageExecutor: deadline passed");
+        
+        // Borrow what amountIn buys at the pair's reserves in this block
+        (uint256 amount0Out, uint256 amount1Out) = getFlashSwapAmounts(pair, tokenPath[0], tokenPath[1], amountIn);
+        
+        activeLender = pair;
+        IUniswapV2Pair(pair).swap(
+            amount0Out,
+            amount1Out,
+            address(this),
+            abi.encode(amountIn, tokenPath, dexPath, poolPath, slippage, minProfit)
+        );
+        activeLender = address(0);
+    }
+    
+    /**
+     * @dev Execute an arbitrage funded by a flash loan from a lender other than Aave
+     * @param lenderKind The kind of lender (0 = Balancer Vault, 1 = Uniswap V3 pool, 2 = Morpho Blue, 3 = Aave V3 fork pool)
+     * @param lender The address to borrow from
+     * @param asset The address of the asset to borrow
+     * @param amount The amount of the asset to borrow
+     * @param tokenPath The path of tokens to trade through
+     * @param dexPath The path of DEXes to use for each trade
+     * @param poolPath The pool to use for each trade (address(0) lets the DEX router pick one)
+     * @param slippage The slippage tolerance in basis points
+     * @param minProfit The minimum profit in the borrowed asset, below which the arbitrage reverts
+     * @param deadline The last block timestamp the arbitrage may execute at
+     */
+    function executeFlashLoan(
+        uint8 lenderKind,
+        address lender,
+        address asset,
+        uint256 amount,
+        address[] calldata tokenPath,
+        string[] calldata dexPath,
+        address[] calldata poolPath,
+        uint256 slippage,
+        uint256 minProfit,
+        uint256 deadline
+    ) external onlyAuthorized whenNotStopped nonReentrant {
+        require(tokenPath.length >= 2, "ArbitrageExecutor: token path must have at least 2 tokens");
+        require(dexPath.length == tokenPath.length - 1, "ArbitrageExecutor: dex path length must be token path length - 1");
+        require(poolPath.length == dexPath.length, "ArbitrageExecutor: pool path length must match dex path length");
+        require(slippage <= MAX_SLIPPAGE, "ArbitrageExecutor: slippage too high");
+        require(block.timestamp <= deadline, "ArbitrageExecutor: deadline passed");
+        
+        // The lender's callback gets the same parameters the contract passes to Aave
+        bytes memory params = abi.encode(tokenPath, dexPath, poolPath, slippage, minProfit, deadline);
+        
+        activeLender = lender;
+        if (lenderKind == LENDER_BALANCER) {
+            require(flashLenders[lender], "ArbitrageExecutor: lender is not allowed");
+            
+            address[] memory tokens = new address[](1);
+            tokens[0] = asset;
+            uint256[] memory amounts = new uint256[](1);
+            amounts[0] = amount;
+            IBalancerVault(lender).flashLoan(address(this), tokens, amounts, params);
+        } else if (lenderKind == LENDER_UNISWAP_V3) {
+            require(isAllowedV3Pool(lender), "ArbitrageExecutor: lender is not allowed");
+            
+            bool isToken0 = asset == IUniswapV3Pool(lender).token0();
+            require(isToken0 || asset == IUniswapV3Pool(lender).token1(), "ArbitrageExecutor: pool does not hold the asset");
+            
+            // The pool calls back with the fees only, so the loan is passed along with the parameters
+            IUniswapV3Pool(lender).flash(
+                address(this),
+                isToken0 ? amount : 0,
+                isToken0 ? 0 : amount,
+                abi.encode(asset, amount, params)
+            );
+        } else if (lenderKind == LENDER_MORPHO) {
+            require(flashLenders[lender], "ArbitrageExecutor: lender is not allowed");
+            
+            // Morpho calls back with the amount only
+            IMorpho(lender).flashLoan(asset, amount, abi.encode(asset, params));
+        } else if (lenderKind == LENDER_AAVE_POOL) {
+            require(flashLenders[lender], "ArbitrageExecutor: lender is not allowed");
+            
+            address[] memory assets = new address[](1);
+            assets[0] = asset;
+            uint256[] memory amounts = new uint256[](1);
+            amounts[0] = amount;
+            uint256[] memory modes = new uint256[](1);
+            ILendingPool(lender).flashLoan(address(this), assets, amounts, modes, address(this), params, 0);
+        } else {
+            revert("ArbitrageExecutor: unsupported lender");
+        }
+        activeLender = address(0);
+    }
+    
+    /**
+     * @dev Callback function for Balancer Vault flash loans
+     * @param tokens The addresses of the assets borrowed
+     * @param amounts The amounts of the assets borrowed
+     * @param feeAmounts The fees to pay for the flash loan
+     * @param userData The encoded parameters for the arbitrage
+     */
+    function receiveFlashLoan(
+        address[] calldata tokens,
+        uint256[] calldata amounts,
+        uint256[] calldata feeAmounts,
+        bytes calldata userData
+    ) external {
+        // Only the lender executeFlashLoan is borrowing from may call back, so loans anyone
+        // else takes out for the contract revert
+        require(activeLender != address(0) && msg.sender == activeLender, "ArbitrageExecutor: caller is not the flash lender");
+        require(tokens.length == 1, "ArbitrageExecutor: only single asset flash loans supported");
+        
+        uint256 totalToRepay = settleFlashLoan(tokens[0], amounts[0], feeAmounts[0], userData);
+        
+        // The Vault is repaid by transfer
+        IERC20(tokens[0]).transfer(msg.sender, totalToRepay);
+    }
+    
+    /**
+     * @dev Callback function for Uniswap V3 pool flash loans
+     * @param fee0 The fee to pay on the token0 borrowed
+     * @param fee1 The fee to pay on the token1 borrowed
+     * @param data The asset and amount borrowed, and the encoded parameters for the arbitrage
+     */
+    function uniswapV3FlashCallback(
+        uint256 fee0,
+        uint256 fee1,
+        bytes calldata data
+    ) external {
+        // Only the pool executeFlashLoan is borrowing from may call back, for a loan it started
+        require(activeLender != address(0) && msg.sender == activeLender, "ArbitrageExecutor: caller is not the flash lender");
+        
+        (address asset, uint256 amount, bytes memory params) = abi.decode(data, (address, uint256, bytes));
+        
+        // Only one of the pool's tokens is borrowed, so the other's fee is zero
+        uint256 totalToRepay = settleFlashLoan(asset, amount, fee0.add(fee1), params);
+        
+        // The pool is repaid by transfer
+        IERC20(asset).transfer(msg.sender, totalToRepay);
+    }
+    
+    /**
+     * @dev Callback function for Morpho Blue flash loans, which are free of fees
+     * @param assets The amount borrowed
+     * @param data The asset borrowed, and the encoded parameters for the arbitrage
+     */
+    function onMorphoFlashLoan(uint256 assets, bytes calldata data) external {
+        // Morpho calls back the account taking the loan, so a caller other than the lender
+        // executeFlashLoan is borrowing from is refused
+        require(activeLender != address(0) && msg.sender == activeLender, "ArbitrageExecutor: caller is not the flash lender");
+        
+        (address asset, bytes memory params) = abi.decode(data, (address, bytes));
+        uint256 totalToRepay = settleFlashLoan(asset, assets, 0, params);
+        
+        // Morpho pulls the repayment, unless a standing approval covers it
+        approveIfNeeded(asset, msg.sender, totalToRepay);
+    }
+    
+    /**
+     * @dev Callback function for the flash swap, which sells the borrowed tokens through the
+     * route's second swap and repays the pair in the route's first token
+     * @param sender The address that called the pair's swap
+     * @param amount0 The amount of token0 lent
+     * @param amount1 The amount of token1 lent
+     * @param data The encoded parameters for the arbitrage
+     */
+    function uniswapV2Call(
+        address sender,
+        uint256 amount0,
+        uint256 amount1,
+        bytes calldata data
+    ) external {
+        // Only the pair executeFlashSwap is borrowing from may call back, for a swap it started
+        require(activeLender != address(0) && msg.sender == activeLender, "ArbitrageExecutor: caller is not the flash swap pair");
+        require(sender == address(this), "ArbitrageExecutor: initiator is not this contract");
+        
+        (
+            uint256 amountIn,
+            address[] memory tokenPath,
+            string[] memory dexPath,
+            address[] memory poolPath,
+            uint256 slippage,
+            uint256 minProfit
+        ) = abi.decode(data, (uint256, address[], string[], address[], uint256, uint256));
+        
+        // Run the route from its second swap
+        uint256 finalAmount = executeLegs(tokenPath, dexPath, poolPath, slippage, 1, amount0.add(amount1));
+        
+        // Ensure we have enough to repay the pair
+        require(finalAmount >= amountIn, "ArbitrageExecutor: insufficient funds to repay flash swap");
+        
+        // Revert when the pools moved against us between simulation and inclusion
+        uint256 profit = finalAmount.sub(amountIn);
+        require(profit >= minProfit, "ArbitrageExecutor: profit below minimum");
+        
+        IERC20(tokenPath[0]).transfer(msg.sender, amountIn);
+        
+        emit ArbitrageExecuted(
+            tokenPath,
+            amountIn,
+            finalAmount,
+            profit,
+            dexPath
+        );
+    }
+    
     /**
      * @dev Callback function for the flash loan
      * @param assets The addresses of the assets borrowed
@@ -210,41 +503,55 @@ contract ArbitrageExecutor is IFlashLoanReceiver, Ownable, ReentrancyGuard {
         address initiator,
         bytes calldata params
     ) external override returns (bool) {
-        // Ensure the caller is the lending pool
-        require(msg.sender == lendingPoolAddress, "ArbitrageExecutor: caller is not lending pool");
+        // Ensure the caller is the lending pool, or the Aave V3 fork executeFlashLoan is borrowing from
+        require(
+            msg.sender == lendingPoolAddress || (activeLender != address(0) && msg.sender == activeLender),
+            "ArbitrageExecutor: caller is not lending pool"
+        );
         require(initiator == address(this), "ArbitrageExecutor: initiator is not this contract");
         
-        // Decode the parameters
+        uint256 totalToRepay = settleFlashLoan(assets[0], amounts[0], premiums[0], params);
+        
+        // Approve the lending pool to take the repayment, unless a standing approval covers it
+        approveIfNeeded(assets[0], msg.sender, totalToRepay);
+        
+        return true;
+    }
+    
+    /**
+     * @dev Internal function to execute the arbitrage a flash loan funds and check that it repays the loan
+     * @param asset The address of the asset borrowed
+     * @param borrowedAmount The amount borrowed
+     * @param fee The fee to pay for the flash loan
+     * @param params The encoded parameters for the arbitrage
+     * @return The amount to repay the lender
+     */
+    function settleFlashLoan(
+        address asset,
+        uint256 borrowedAmount,
+        uint256 fee,
+        bytes memory params
+    ) internal returns (uint256) {
         (
             address[] memory tokenPath,
             string[] memory dexPath,
-            uint256 slippage
-        ) = abi.decode(params, (address[], string[], uint256));
+            address[] memory poolPath,
+            uint256 slippage,
+            uint256 minProfit,
+            uint256 deadline
+        ) = abi.decode(params, (address[], string[], address[], uint256, uint256, uint256));
+        require(block.timestamp <= deadline, "ArbitrageExecutor: deadline passed");
         
-        // Get the borrowed amount
-        uint256 borrowedAmount = amounts[0];
-        uint256 fee = premiums[0];
         uint256 totalToRepay = borrowedAmount.add(fee);
-        
-        // Execute the arbitrage
-        uint256 finalAmount = executeArbitrageInternal(
-            assets[0],
-            borrowedAmount,
-            tokenPath,
-            dexPath,
-            slippage
-        );
+        uint256 finalAmount = executeArbitrageInternal(asset, borrowedAmount, tokenPath, dexPath, poolPath, slippage);
         
         // Ensure we have enough to repay the loan
         require(finalAmount >= totalToRepay, "ArbitrageExecutor: insufficient funds to repay flash loan");
         
-        // Calculate profit
+        // Revert when the pools moved against us between simulation and inclusion
         uint256 profit = finalAmount.sub(totalToRepay);
+        require(profit >= minProfit, "ArbitrageExecutor: profit below minimum");
         
-        // Approve the lending pool to take the repayment
-        IERC20(assets[0]).approve(lendingPoolAddress, totalToRepay);
-        
-        // Emit event
         emit ArbitrageExecuted(
             tokenPath,
             borrowedAmount,
@@ -253,7 +560,7 @@ contract ArbitrageExecutor is IFlashLoanReceiver, Ownable, ReentrancyGuard {
             dexPath
         );
         
-        return true;
+        return totalToRepay;
     }
     
     /**
@@ -262,6 +569,7 @@ contract ArbitrageExecutor is IFlashLoanReceiver, Ownable, ReentrancyGuard {
      * @param initialAmount The initial amount of tokens
      * @param tokenPath The path of tokens to trade through
      * @param dexPath The path of DEXes to use for each trade
+     * @param poolPath The pool to use for each trade (address(0) lets the DEX router pick one)
      * @param slippage The slippage tolerance in basis points
      * @return The final amount of tokens after all trades
      */
@@ -270,23 +578,51 @@ contract ArbitrageExecutor is IFlashLoanReceiver, Ownable, ReentrancyGuard {
         uint256 initialAmount,
         address[] memory tokenPath,
         string[] memory dexPath,
+        address[] memory poolPath,
         uint256 slippage
     ) internal returns (uint256) {
         require(tokenPath[0] == initialToken, "ArbitrageExecutor: initial token mismatch");
         
-        uint256 currentAmount = initialAmount;
+        uint256 finalAmount = executeLegs(tokenPath, dexPath, poolPath, slippage, 0, initialAmount);
+        
+        // Ensure the final token is the same as the initial token
+        require(tokenPath[tokenPath.length - 1] == initialToken, "ArbitrageExecutor: final token mismatch");
+        
+        return finalAmount;
+    }
+    
+    /**
+     * @dev Internal function to execute the trades of a path from one of its legs on
+     * @param tokenPath The path of tokens to trade through
+     * @param dexPath The path of DEXes to use for each trade
+     * @param poolPath The pool to use for each trade (address(0) lets the DEX router pick one)
+     * @param slippage The slippage tolerance in basis points
+     * @param firstLeg The index of the first trade to execute
+     * @param amount The amount of the first trade's input token
+     * @return The final amount of tokens after the trades
+     */
+    function executeLegs(
+        address[] memory tokenPath,
+        string[] memory dexPath,
+        address[] memory poolPath,
+        uint256 slippage,
+        uint256 firstLeg,
+        uint256 amount
+    ) internal returns (uint256) {
+        uint256 currentAmount = amount;
         
         // Execute each trade in the path
-        for (uint256 i = 0; i < dexPath.length; i++) {
+        for (uint256 i = firstLeg; i < dexPath.length; i++) {
             address fromToken = tokenPath[i];
             address toToken = tokenPath[i + 1];
             
-            // Approve the router to spend the tokens
-            IERC20(fromToken).approve(getRouterAddress(dexPath[i]), currentAmount);
+            // Approve the router to spend the tokens, unless a standing approval covers it
+            approveIfNeeded(fromToken, getRouterAddress(dexPath[i]), currentAmount);
             
             // Execute the trade based on the DEX
             currentAmount = executeTrade(
                 dexPath[i],
+                poolPath[i],
                 fromToken,
                 toToken,
                 currentAmount,
@@ -294,15 +630,13 @@ contract ArbitrageExecutor is IFlashLoanReceiver, Ownable, ReentrancyGuard {
             );
         }
         
-        // Ensure the final token is the same as the initial token
-        require(tokenPath[tokenPath.length - 1] == initialToken, "ArbitrageExecutor: final token mismatch");
-        
         return currentAmount;
     }
     
     /**
      * @dev Execute a trade on a specific DEX
      * @param dex The name of the DEX to use
+     * @param pool The pool to trade through (address(0) lets the DEX router pick one)
      * @param fromToken The token to trade from
      * @param toToken The token to trade to
      * @param amount The amount of tokens to trade
@@ -311,6 +645,7 @@ contract ArbitrageExecutor is IFlashLoanReceiver, Ownable, ReentrancyGuard {
      */
     function executeTrade(
         string memory dex,
+        address pool,
         address fromToken,
         address toToken,
         uint256 amount,
@@ -324,6 +659,11 @@ contract ArbitrageExecutor is IFlashLoanReceiver, Ownable, ReentrancyGuard {
         
         if (keccak256(bytes(dex)) == keccak256(bytes("uniswap")) || 
             keccak256(bytes(dex)) == keccak256(bytes("sushiswap"))) {
+            // Trade directly against the pair the bot quoted
+            if (pool != address(0)) {
+                return swapOnPair(pool, fromToken, toToken, amount);
+            }
+            
             // For Uniswap and Sushiswap
             address[] memory path = new address[](2);
             path[0] = fromToken;
@@ -347,12 +687,25 @@ contract ArbitrageExecutor is IFlashLoanReceiver, Ownable, ReentrancyGuard {
             
             return amounts[amounts.length - 1];
         } else if (keccak256(bytes(dex)) == keccak256(bytes("curve"))) {
-            // For Curve
-            (address bestPool, uint256 expectedAmountOut) = ICurveRouter(routerAddress).get_best_rate(
-                fromToken,
-                toToken,
-                amount
-            );
+            // For Curve, through the pool the bot quoted or the router's best one
+            address bestPool = pool;
+            uint256 expectedAmountOut;
+            if (pool != address(0)) {
+                address[8] memory candidates;
+                candidates[0] = pool;
+                expectedAmountOut = ICurveRouter(routerAddress).get_exchange_amount(
+                    fromToken,
+                    toToken,
+                    amount,
+                    candidates
+                );
+            } else {
+                (bestPool, expectedAmountOut) = ICurveRouter(routerAddress).get_best_rate(
+                    fromToken,
+                    toToken,
+                    amount
+                );
+            }
             
             // Calculate minimum amount out with slippage
             amountOutMin = SlippageProtection.calculateMinimumAmountOut(expectedAmountOut, slippage);
@@ -384,12 +737,136 @@ contract ArbitrageExecutor is IFlashLoanReceiver, Ownable, ReentrancyGuard {
                 address(this)
             );
             
+            return received;
+        } else if (keccak256(bytes(dex)) == keccak256(bytes("trident"))) {
+            // Trident pools trade BentoBox shares and have no router to pick one
+            require(pool != address(0), "ArbitrageExecutor: trident leg needs a pool");
+            
+            // Deposit the input as shares of the pool, then swap and unwrap the output
+            IBentoBox(routerAddress).deposit(fromToken, address(this), pool, amount, 0);
+            uint256 received = ITridentPool(pool).swap(abi.encode(fromToken, address(this), true));
+            require(received > 0, "ArbitrageExecutor: insufficient output amount");
+            
             return received;
         } else {
             revert("ArbitrageExecutor: unsupported DEX");
         }
     }
     
+    /**
+     * @dev Swap an exact amount through a Uniswap V2 style pair
+     * @param pair The pair to trade through
+     * @param fromToken The token to trade from
+     * @param toToken The token to trade to
+     * @param amount The amount of tokens to trade
+     * @return The amount of tokens received
+     */
+    function swapOnPair(
+        address pair,
+        address fromToken,
+        address toToken,
+        uint256 amount
+    ) internal returns (uint256) {
+        address token0 = IUniswapV2Pair(pair).token0();
+        require(
+            (fromToken == token0 && toToken == IUniswapV2Pair(pair).token1()) ||
+                (toToken == token0 && fromToken == IUniswapV2Pair(pair).token1()),
+            "ArbitrageExecutor: pool does not trade the pair"
+        );
+        
+        // The reserves are read in the same transaction, so the output needs no slippage bound
+        uint256 amountOut = getPairAmountOut(pair, fromToken == token0, amount);
+        require(amountOut > 0, "ArbitrageExecutor: insufficient output amount");
+        
+        IERC20(fromToken).transfer(pair, amount);
+        if (fromToken == token0) {
+            IUniswapV2Pair(pair).swap(0, amountOut, address(this), new bytes(0));
+        } else {
+            IUniswapV2Pair(pair).swap(amountOut, 0, address(this), new bytes(0));
+        }
+        
+        return amountOut;
+    }
+    
+    /**
+     * @dev Get the amounts a flash swap borrows from a pair for an input repaid in another token
+     * @param pair The pair to borrow from
+     * @param tokenIn The token the pair is repaid in
+     * @param tokenOut The token the pair lends
+     * @param amountIn The amount the pair is repaid
+     * @return amount0Out The amount of token0 to borrow
+     * @return amount1Out The amount of token1 to borrow
+     */
+    function getFlashSwapAmounts(
+        address pair,
+        address tokenIn,
+        address tokenOut,
+        uint256 amountIn
+    ) internal view returns (uint256 amount0Out, uint256 amount1Out) {
+        address token0 = IUniswapV2Pair(pair).token0();
+        require(
+            (tokenIn == token0 && tokenOut == IUniswapV2Pair(pair).token1()) ||
+                (tokenOut == token0 && tokenIn == IUniswapV2Pair(pair).token1()),
+            "ArbitrageExecutor: pool does not trade the pair"
+        );
+        
+        uint256 amountOut = getPairAmountOut(pair, tokenIn == token0, amountIn);
+        require(amountOut > 0, "ArbitrageExecutor: insufficient output amount");
+        
+        return tokenIn == token0 ? (uint256(0), amountOut) : (amountOut, uint256(0));
+    }
+    
+    /**
+     * @dev Get the output of a swap through a Uniswap V2 style pair at its current reserves
+     * @param pair The pair to trade through
+     * @param zeroForOne Whether token0 is sold for token1
+     * @param amount The amount of tokens to trade
+     * @return The amount of tokens the swap returns
+     */
+    function getPairAmountOut(
+        address pair,
+        bool zeroForOne,
+        uint256 amount
+    ) internal view returns (uint256) {
+        (uint112 reserve0, uint112 reserve1, ) = IUniswapV2Pair(pair).getReserves();
+        uint256 reserveIn = zeroForOne ? uint256(reserve0) : uint256(reserve1);
+        uint256 reserveOut = zeroForOne ? uint256(reserve1) : uint256(reserve0);
+        
+        uint256 amountInWithFee = amount.mul(BASIS_POINTS - V2_FEE_BPS);
+        return amountInWithFee.mul(reserveOut).div(reserveIn.mul(BASIS_POINTS).add(amountInWithFee));
+    }
+    
+    /**
+     * @dev Check that a Uniswap V3 pool was deployed by an allowed factory
+     * @param pool The pool to check
+     * @return Whether the pool may lend
+     */
+    function isAllowedV3Pool(address pool) internal view returns (bool) {
+        address factory = IUniswapV3Pool(pool).factory();
+        if (!flashLenders[factory]) {
+            return false;
+        }
+        
+        // A pool naming an allowed factory must also be the one the factory deployed
+        return IUniswapV3Factory(factory).getPool(
+            IUniswapV3Pool(pool).token0(),
+            IUniswapV3Pool(pool).token1(),
+            IUniswapV3Pool(pool).fee()
+        ) == pool;
+    }
+    
+    /**
+     * @dev Approve a spender for an amount, keeping an allowance that already covers it
+     * @param token The token to approve
+     * @param spender The address allowed to spend the token
+     * @param amount The amount the spender needs
+     */
+    function approveIfNeeded(address token, address spender, uint256 amount) internal {
+        if (IERC20(token).allowance(address(this), spender) < amount) {
+            IERC20(token).approve(spender, amount);
+        }
+    }
+    
     /**
      * @dev Get the router address for a specific DEX
      * @param dex The name of the DEX
@@ -402,6 +879,9 @@ contract ArbitrageExecutor is IFlashLoanReceiver, Ownable, ReentrancyGuard {
             return sushiswapRouterAddress;
         } else if (keccak256(bytes(dex)) == keccak256(bytes("curve"))) {
             return curveRouterAddress;
+        } else if (keccak256(bytes(dex)) == keccak256(bytes("trident"))) {
+            require(bentoBoxAddress != address(0), "ArbitrageExecutor: BentoBox not set");
+            return bentoBoxAddress;
         } else {
             revert("ArbitrageExecutor: unsupported DEX");
         }
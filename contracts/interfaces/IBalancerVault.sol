@@ -0,0 +1,23 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.19;
+
+/**
+ * @title IBalancerVault
+ * @dev Interface for the flash loans of the Balancer V2 Vault
+ */
+interface IBalancerVault {
+    /**
+     * @notice Lend tokens to a recipient, which is called back with `receiveFlashLoan` and
+     * must transfer the loans and their fees back to the Vault before it returns
+     * @param recipient The address receiving the loans and the callback
+     * @param tokens The tokens to borrow, in strictly ascending order
+     * @param amounts The amount of each token to borrow
+     * @param userData Data passed to the callback
+     */
+    function flashLoan(
+        address recipient,
+        address[] memory tokens,
+        uint256[] memory amounts,
+        bytes memory userData
+    ) external;
+}
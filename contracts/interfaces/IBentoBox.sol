@@ -0,0 +1,40 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.19;
+
+/**
+ * @title IBentoBox
+ * @dev Interface for the Sushi BentoBox vault, which holds the balances of Trident pools as shares
+ */
+interface IBentoBox {
+    /**
+     * @notice Deposit tokens into the vault, credited as shares to a vault account
+     * @param token The token to deposit
+     * @param from The address the tokens are pulled from (it must have approved the vault)
+     * @param to The vault account credited with the shares
+     * @param amount The amount of tokens to deposit (0 to deposit `share` instead)
+     * @param share The shares to deposit, if `amount` is 0
+     */
+    function deposit(
+        address token,
+        address from,
+        address to,
+        uint256 amount,
+        uint256 share
+    ) external payable returns (uint256 amountOut, uint256 shareOut);
+
+    /**
+     * @notice Convert a token amount to vault shares
+     * @param token The token
+     * @param amount The amount of tokens
+     * @param roundUp Whether to round the result up
+     */
+    function toShare(address token, uint256 amount, bool roundUp) external view returns (uint256 share);
+
+    /**
+     * @notice Convert vault shares to a token amount
+     * @param token The token
+     * @param share The shares
+     * @param roundUp Whether to round the result up
+     */
+    function toAmount(address token, uint256 share, bool roundUp) external view returns (uint256 amount);
+}
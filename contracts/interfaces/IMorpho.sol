@@ -0,0 +1,17 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.19;
+
+/**
+ * @title IMorpho
+ * @dev Interface for the flash loans of the Morpho Blue singleton
+ */
+interface IMorpho {
+    /**
+     * @notice Lend a token to the caller, which is called back with `onMorphoFlashLoan` and
+     * must approve Morpho to pull the loan back before it returns (there is no fee)
+     * @param token The token to borrow
+     * @param assets The amount of the token to borrow
+     * @param data Data passed to the callback
+     */
+    function flashLoan(address token, uint256 assets, bytes calldata data) external;
+}
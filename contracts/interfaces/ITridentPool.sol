@@ -0,0 +1,21 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.19;
+
+/**
+ * @title ITridentPool
+ * @dev Interface for a Sushi Trident pool (constant product or stable), trading BentoBox shares
+ */
+interface ITridentPool {
+    /**
+     * @notice Swap the shares deposited to the pool since the last swap
+     * @param data ABI-encoded (address tokenIn, address recipient, bool unwrapBento)
+     * @return amountOut The output, in tokens if unwrapped or shares otherwise
+     */
+    function swap(bytes calldata data) external returns (uint256 amountOut);
+
+    /**
+     * @notice Get the output shares of a swap at the current reserves
+     * @param data ABI-encoded (address tokenIn, uint256 shareIn)
+     */
+    function getAmountOut(bytes calldata data) external view returns (uint256 finalAmountOut);
+}
@@ -0,0 +1,32 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.19;
+
+/**
+ * @title IUniswapV2Pair
+ * @dev Interface for a Uniswap V2 (or Sushiswap) pair
+ */
+interface IUniswapV2Pair {
+    /**
+     * @notice Get the first token of the pair (the lower address)
+     */
+    function token0() external view returns (address);
+
+    /**
+     * @notice Get the second token of the pair
+     */
+    function token1() external view returns (address);
+
+    /**
+     * @notice Get the reserves of the pair and the timestamp of the last update
+     */
+    function getReserves() external view returns (uint112 reserve0, uint112 reserve1, uint32 blockTimestampLast);
+
+    /**
+     * @notice Send output tokens, after the input tokens have been transferred to the pair
+     * @param amount0Out The amount of token0 to send
+     * @param amount1Out The amount of token1 to send
+     * @param to The address to receive the output tokens
+     * @param data Callback data for flash swaps (empty for plain swaps)
+     */
+    function swap(uint amount0Out, uint amount1Out, address to, bytes calldata data) external;
+}
@@ -0,0 +1,16 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.19;
+
+/**
+ * @title IUniswapV3Factory
+ * @dev Interface for looking up the pools a Uniswap V3 factory deployed
+ */
+interface IUniswapV3Factory {
+    /**
+     * @notice Get the pool of two tokens and a fee (address(0) if there is none)
+     * @param tokenA One token of the pool
+     * @param tokenB The other token of the pool
+     * @param fee The fee of the pool (in hundredths of a basis point)
+     */
+    function getPool(address tokenA, address tokenB, uint24 fee) external view returns (address pool);
+}
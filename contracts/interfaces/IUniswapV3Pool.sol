@@ -0,0 +1,38 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.19;
+
+/**
+ * @title IUniswapV3Pool
+ * @dev Interface for the flash loans of a Uniswap V3 pool
+ */
+interface IUniswapV3Pool {
+    /**
+     * @notice Get the factory that deployed the pool
+     */
+    function factory() external view returns (address);
+
+    /**
+     * @notice Get the first token of the pool (the lower address)
+     */
+    function token0() external view returns (address);
+
+    /**
+     * @notice Get the second token of the pool
+     */
+    function token1() external view returns (address);
+
+    /**
+     * @notice Get the fee of the pool (in hundredths of a basis point)
+     */
+    function fee() external view returns (uint24);
+
+    /**
+     * @notice Lend tokens to a recipient, which is called back with `uniswapV3FlashCallback`
+     * and must transfer the loans and their fees back to the pool before it returns
+     * @param recipient The address receiving the loans
+     * @param amount0 The amount of token0 to borrow
+     * @param amount1 The amount of token1 to borrow
+     * @param data Data passed to the callback
+     */
+    function flash(address recipient, uint256 amount0, uint256 amount1, bytes calldata data) external;
+}
@@ -0,0 +1,407 @@
+//! Aggregator Module
+//!
+//! This module is responsible for sanity checking opportunities against DEX aggregators before
+//! they are executed. Each swap of the route is re-quoted from the pool state the bot holds and
+//! from the 1inch or 0x quote API for the same tokens and amount. Aggregators split orders across
+//! every pool they know, so they should never return much less than one of those pools: a swap
+//! the bot expects to return more than the aggregator by over `max_deviation_pct` points at a
+//! stale pool state, and the opportunity is flagged before any gas is spent on it.
+
+use anyhow::{Context, Result};
+use ethers::types::{Address, U256};
+use log::{debug, warn};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::{Config, ConfigSection};
+use crate::dex::DexInterfaces;
+use crate::metrics;
+use crate::scanner::ArbitrageOpportunity;
+use crate::tokens;
+use crate::trace::{self, TraceStage};
+use crate::utils::u256_to_decimal;
+
+/// Default largest amount by which a swap may beat the aggregator quote (in percent)
+pub const DEFAULT_MAX_DEVIATION_PCT: f64 = 5.0;
+
+/// Default timeout of an aggregator quote request (in milliseconds)
+pub const DEFAULT_REQUEST_TIMEOUT_MS: u64 = 2_000;
+
+/// Default base URL of the 1inch swap API
+const DEFAULT_ONEINCH_URL: &str = "https://api.1inch.dev/swap/v6.0";
+
+/// Default base URL of the 0x swap API
+const DEFAULT_ZEROX_URL: &str = "https://api.0x.org";
+
+/// Aggregator sanity check configuration (`[aggregator]`)
+///
+/// API keys are read from `ONEINCH_API_KEY` and `ZEROX_API_KEY`; a source without its key is
+/// skipped.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AggregatorConfig {
+    /// Whether opportunities are checked against aggregator quotes before execution
+    pub enabled: bool,
+
+    /// Largest amount by which a swap may beat the aggregator quote (in percent, defaults to 5)
+    pub max_deviation_pct: Option<f64>,
+
+    /// Whether flagged opportunities are skipped, or only logged
+    pub reject_deviating: bool,
+
+    /// Timeout of a quote request (in milliseconds, defaults to 2000)
+    pub request_timeout_ms: Option<u64>,
+
+    /// 1inch quote API
+    pub oneinch: AggregatorApiConfig,
+
+    /// 0x quote API
+    pub zerox: AggregatorApiConfig,
+}
+
+impl Default for AggregatorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_deviation_pct: None,
+            reject_deviating: true,
+            request_timeout_ms: None,
+            oneinch: AggregatorApiConfig::default(),
+            zerox: AggregatorApiConfig::default(),
+        }
+    }
+}
+
+impl ConfigSection for AggregatorConfig {
+    const NAME: &'static str = "aggregator";
+
+    fn validate(&self) -> Result<()> {
+        if self.max_deviation_pct.is_some_and(|pct| pct <= 0.0) {
+            anyhow::bail!("Aggregator max_deviation_pct must be greater than zero");
+        }
+        if self.enabled && !self.oneinch.enabled && !self.zerox.enabled {
+            anyhow::bail!("Aggregator checks need 1inch or 0x enabled");
+        }
+
+        Ok(())
+    }
+}
+
+/// Settings of one aggregator quote API
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AggregatorApiConfig {
+    /// Whether quotes are requested from this aggregator
+    pub enabled: bool,
+
+    /// Base URL of the API (defaults to the public endpoint)
+    pub base_url: Option<String>,
+}
+
+/// An aggregator with a quote API
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregatorSource {
+    /// 1inch
+    OneInch,
+
+    /// 0x
+    ZeroX,
+}
+
+/// 1inch `quote` response
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OneInchQuote {
+    dst_amount: String,
+}
+
+/// 0x `price` response
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ZeroXPrice {
+    liquidity_available: Option<bool>,
+    buy_amount: Option<String>,
+}
+
+/// Client of one aggregator quote API
+struct AggregatorClient {
+    source: AggregatorSource,
+    base_url: String,
+    api_key: String,
+    chain_id: u64,
+    http_client: Client,
+}
+
+impl AggregatorClient {
+    /// Get the output of selling `amount_in` of `token_in` for `token_out`
+    async fn quote(&self, token_in: Address, token_out: Address, amount_in: U256) -> Result<U256> {
+        let (token_in, token_out) = (format!("{:?}", token_in), format!("{:?}", token_out));
+        let amount_in = amount_in.to_string();
+
+        let amount_out = match self.source {
+            AggregatorSource::OneInch => {
+                let quote: OneInchQuote = self
+                    .http_client
+                    .get(format!("{}/{}/quote", self.base_url, self.chain_id))
+                    .query(&[
+                        ("src", token_in.as_str()),
+                        ("dst", token_out.as_str()),
+                        ("amount", amount_in.as_str()),
+                    ])
+                    .bearer_auth(&self.api_key)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await
+                    .context("Failed to parse 1inch quote")?;
+                quote.dst_amount
+            }
+            AggregatorSource::ZeroX => {
+                let price: ZeroXPrice = self
+                    .http_client
+                    .get(format!("{}/swap/permit2/price", self.base_url))
+                    .query(&[
+                        ("chainId", self.chain_id.to_string().as_str()),
+                        ("sellToken", token_in.as_str()),
+                        ("buyToken", token_out.as_str()),
+                        ("sellAmount", amount_in.as_str()),
+                    ])
+                    .header("0x-api-key", &self.api_key)
+                    .header("0x-version", "v2")
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await
+                    .context("Failed to parse 0x price")?;
+                if price.liquidity_available == Some(false) {
+                    anyhow::bail!("0x has no liquidity for the pair");
+                }
+                price.buy_amount.context("0x price has no buy amount")?
+            }
+        };
+
+        U256::from_dec_str(&amount_out)
+            .with_context(|| format!("Invalid {:?} output amount {}", self.source, amount_out))
+    }
+}
+
+/// A swap the bot expects to return more than the aggregator
+#[derive(Debug, Clone)]
+pub struct QuoteDeviation {
+    /// Index of the swap in the route
+    pub hop: usize,
+
+    /// Aggregator the swap was compared with
+    pub source: AggregatorSource,
+
+    /// Output the bot expects
+    pub expected_output: U256,
+
+    /// Output the aggregator quoted
+    pub aggregator_output: U256,
+
+    /// How much the expected output exceeds the aggregator's (in percent)
+    pub deviation_pct: f64,
+}
+
+/// Checks opportunities against aggregator quotes
+pub struct QuoteChecker {
+    clients: Vec<AggregatorClient>,
+    dex_interfaces: Arc<DexInterfaces>,
+    max_deviation_pct: f64,
+    reject_deviating: bool,
+}
+
+/// Create a quote checker from the aggregators with an API key
+pub fn create_checker(
+    config: &Config,
+    aggregator_config: &AggregatorConfig,
+    dex_interfaces: Arc<DexInterfaces>,
+) -> Result<Arc<QuoteChecker>> {
+    let http_client = Client::builder()
+        .timeout(Duration::from_millis(
+            aggregator_config
+                .request_timeout_ms
+                .unwrap_or(DEFAULT_REQUEST_TIMEOUT_MS),
+        ))
+        .build()?;
+
+    let mut clients = Vec::new();
+    for (source, settings, default_url, key_var) in [
+        (
+            AggregatorSource::OneInch,
+            &aggregator_config.oneinch,
+            DEFAULT_ONEINCH_URL,
+            "ONEINCH_API_KEY",
+        ),
+        (
+            AggregatorSource::ZeroX,
+            &aggregator_config.zerox,
+            DEFAULT_ZEROX_URL,
+            "ZEROX_API_KEY",
+        ),
+    ] {
+        if !settings.enabled {
+            continue;
+        }
+        let Ok(api_key) = std::env::var(key_var) else {
+            warn!("{:?} quote checks disabled: {} is not set", source, key_var);
+            continue;
+        };
+
+        clients.push(AggregatorClient {
+            source,
+            base_url: settings
+                .base_url
+                .as_deref()
+                .unwrap_or(default_url)
+                .trim_end_matches('/')
+                .to_string(),
+            api_key,
+            chain_id: config.ethereum.chain_id,
+            http_client: http_client.clone(),
+        });
+    }
+
+    Ok(Arc::new(QuoteChecker {
+        clients,
+        dex_interfaces,
+        max_deviation_pct: aggregator_config
+            .max_deviation_pct
+            .unwrap_or(DEFAULT_MAX_DEVIATION_PCT),
+        reject_deviating: aggregator_config.reject_deviating,
+    }))
+}
+
+impl QuoteChecker {
+    /// Whether an opportunity may be executed: no swap of it deviates from the aggregators, or
+    /// deviating opportunities are only logged
+    ///
+    /// An opportunity that cannot be checked, because a quote failed, is allowed.
+    pub async fn allows(&self, opportunity: &ArbitrageOpportunity) -> bool {
+        let deviation = match self.find_deviation(opportunity).await {
+            Ok(deviation) => deviation,
+            Err(e) => {
+                debug!(
+                    "Could not check {} against aggregators: {:#}",
+                    opportunity.id, e
+                );
+                metrics::global().increment_counter("aggregator_check_failures", 1);
+                return true;
+            }
+        };
+        let Some(deviation) = deviation else {
+            return true;
+        };
+
+        warn!(
+            "{} swap {} expects {} but {:?} quotes {} ({:.1}% more); pool state is likely stale",
+            opportunity,
+            deviation.hop + 1,
+            deviation.expected_output,
+            deviation.source,
+            deviation.aggregator_output,
+            deviation.deviation_pct
+        );
+        metrics::global().increment_counter("aggregator_deviations", 1);
+        trace::record(TraceStage::Rejection, || {
+            format!(
+                "{} swap {} is {:.1}% above the {:?} quote",
+                opportunity.id,
+                deviation.hop + 1,
+                deviation.deviation_pct,
+                deviation.source
+            )
+        });
+
+        !self.reject_deviating
+    }
+
+    /// Find the first swap of an opportunity that returns more than the aggregators quote
+    ///
+    /// Swaps are quoted in order from the borrowed amount, each from the bot's expected output
+    /// of the previous one. A swap the bot expects to return less than the aggregator is fine:
+    /// the aggregator may split it across pools the route does not use.
+    pub async fn find_deviation(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+    ) -> Result<Option<QuoteDeviation>> {
+        if self.clients.is_empty() {
+            return Ok(None);
+        }
+
+        let mut amount_in = opportunity.flash_loan_amount();
+        for (hop, (pair, &(dex_type, _))) in opportunity
+            .token_path
+            .windows(2)
+            .zip(&opportunity.hops)
+            .enumerate()
+        {
+            let (token_in, token_out) = (pair[0], pair[1]);
+            let expected_output = self
+                .dex_interfaces
+                .get_interface(dex_type)
+                .with_context(|| format!("{} is not enabled", dex_type.name()))?
+                .get_quote(token_in, token_out, amount_in, opportunity.quote_block)
+                .await?
+                .output_amount;
+
+            let (source, aggregator_output) = self.quote(token_in, token_out, amount_in).await?;
+            let deviation_pct = if aggregator_output.is_zero() {
+                f64::INFINITY
+            } else {
+                (u256_to_decimal(expected_output, 0) / u256_to_decimal(aggregator_output, 0) - 1.0)
+                    * 100.0
+            };
+            debug!(
+                "Swap {} of {} ({}): expected {}, {:?} {} ({:+.2}%)",
+                hop + 1,
+                opportunity.id,
+                tokens::path_label(pair),
+                expected_output,
+                source,
+                aggregator_output,
+                deviation_pct
+            );
+
+            if deviation_pct > self.max_deviation_pct {
+                return Ok(Some(QuoteDeviation {
+                    hop,
+                    source,
+                    expected_output,
+                    aggregator_output,
+                    deviation_pct,
+                }));
+            }
+
+            amount_in = expected_output;
+        }
+
+        Ok(None)
+    }
+
+    /// Get a quote from the first aggregator that answers
+    async fn quote(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Result<(AggregatorSource, U256)> {
+        let mut last_error = None;
+        for client in &self.clients {
+            match client.quote(token_in, token_out, amount_in).await {
+                Ok(amount_out) => return Ok((client.source, amount_out)),
+                Err(e) => {
+                    debug!("{:?} quote failed: {:#}", client.source, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| anyhow::anyhow!("No aggregator configured")))
+    }
+}
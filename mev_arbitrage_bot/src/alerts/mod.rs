@@ -0,0 +1,94 @@
+//! Alerts Module
+//!
+//! This module is responsible for notifying operators of conditions that need attention,
+//! by logging them and optionally posting them to a webhook.
+
+use anyhow::Result;
+use log::{error, info, warn};
+use reqwest::Client;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::{AlertSeverity, Config};
+use crate::logging;
+use crate::metrics;
+
+/// An alert raised by one of the bot's components
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    /// Alert severity
+    pub severity: AlertSeverity,
+
+    /// Component that raised the alert
+    pub source: String,
+
+    /// Human-readable message
+    pub message: String,
+
+    /// Unix timestamp when the alert was raised
+    pub timestamp: u64,
+}
+
+/// Dispatches alerts to the log and the configured webhook
+pub struct AlertManager {
+    http_client: Client,
+    webhook_url: Option<String>,
+    min_severity: AlertSeverity,
+}
+
+/// Create a new alert manager
+pub fn create_manager(config: &Arc<Config>) -> Result<Arc<AlertManager>> {
+    let http_client = Client::builder().timeout(Duration::from_secs(5)).build()?;
+
+    let manager = AlertManager {
+        http_client,
+        webhook_url: config.alerts.webhook_url.clone(),
+        min_severity: config.alerts.min_severity,
+    };
+
+    Ok(Arc::new(manager))
+}
+
+impl AlertManager {
+    /// Raise an alert
+    ///
+    /// The alert is always logged; webhook delivery happens in the background so callers on
+    /// the hot path are never blocked on it. The message is redacted like log output.
+    pub fn raise(&self, severity: AlertSeverity, source: &str, message: impl Into<String>) {
+        let alert = Alert {
+            severity,
+            source: source.to_string(),
+            message: logging::redact(&message.into()).into_owned(),
+            timestamp: crate::utils::current_timestamp(),
+        };
+
+        match severity {
+            AlertSeverity::Info => info!("[alert:{}] {}", alert.source, alert.message),
+            AlertSeverity::Warning => warn!("[alert:{}] {}", alert.source, alert.message),
+            AlertSeverity::Critical => error!("[alert:{}] {}", alert.source, alert.message),
+        }
+        metrics::global().increment_counter("alerts_raised_total", 1);
+
+        if severity < self.min_severity {
+            return;
+        }
+
+        if let Some(webhook_url) = &self.webhook_url {
+            let http_client = self.http_client.clone();
+            let webhook_url = webhook_url.clone();
+            tokio::spawn(async move {
+                let result = http_client
+                    .post(&webhook_url)
+                    .json(&alert)
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status());
+
+                if let Err(e) = result {
+                    warn!("Failed to deliver alert to webhook: {}", e);
+                }
+            });
+        }
+    }
+}
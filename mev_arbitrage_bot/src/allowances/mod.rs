@@ -0,0 +1,341 @@
+//! Allowances Module
+//!
+//! This module is responsible for making sure the approvals a path spends exist before it is
+//! executed. The tokens each swap sells and the flash loan repaid are matched with the address
+//! that pulls them: the DEX router, the BentoBox of Trident legs, and the lender of providers
+//! that take repayments with `transferFrom`. The allowances of the holder (the executor
+//! contract, or the wallet when none is configured) are read in one Multicall3 batch, and a
+//! path whose allowances are missing is refused.
+//!
+//! Unless `check_only` is set, missing approvals are set once, as standing approvals of the
+//! whole supply: the contract's in a single `approveTokens` owner call, the wallet's with one
+//! `approve` per token. They are not sent again while pending, and once confirmed, like any
+//! standing allowance found, they are not read again.
+
+use anyhow::{Context, Result};
+use ethers::types::{Address, BlockId, BlockNumber, Bytes, TransactionRequest, U256};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::assets::ContractAbi;
+use crate::blockchain::RpcClient;
+use crate::config::{Config, ConfigSection};
+use crate::dex::DexType;
+use crate::flash_loan::FlashLoanRouter;
+use crate::metrics;
+use crate::scanner::ArbitrageOpportunity;
+use crate::tokens;
+use crate::trace::{self, TraceStage};
+use crate::transaction::TransactionExecutor;
+use crate::utils::{validate_and_parse_address, Call, Multicall};
+
+/// Time an approval transaction is followed for before it may be sent again
+const APPROVAL_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// ERC-20 allowance check configuration (`[allowances]`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AllowancesConfig {
+    /// Whether paths are checked for the approvals they spend before execution
+    pub enabled: bool,
+
+    /// Whether missing approvals are only reported, instead of set once from the wallet
+    pub check_only: bool,
+}
+
+impl ConfigSection for AllowancesConfig {
+    const NAME: &'static str = "allowances";
+}
+
+/// An approval a path needs: the token and the address spending it
+type Approval = (Address, Address);
+
+/// Approvals known to exist, and those being set
+#[derive(Default)]
+struct Approvals {
+    approved: HashSet<Approval>,
+    pending: HashSet<Approval>,
+}
+
+/// Checks and sets the approvals paths spend
+pub struct AllowanceManager {
+    multicall: Multicall,
+    flash_loans: Arc<FlashLoanRouter>,
+    tx_executor: Arc<dyn TransactionExecutor>,
+
+    /// Address whose allowances paths spend
+    holder: Address,
+
+    /// Whether the holder is the executor contract rather than the wallet
+    holder_is_contract: bool,
+
+    check_only: bool,
+    uniswap_router: Option<Address>,
+    sushiswap_router: Option<Address>,
+    curve_router: Option<Address>,
+    bento_box: Option<Address>,
+    approvals: Mutex<Approvals>,
+}
+
+/// Create a new allowance manager for the executor contract, or the wallet without one
+pub fn create_manager(
+    config: &Arc<Config>,
+    allowances_config: &AllowancesConfig,
+    blockchain_client: Arc<RpcClient>,
+    contract_address: Option<Address>,
+    flash_loans: Arc<FlashLoanRouter>,
+    tx_executor: Arc<dyn TransactionExecutor>,
+) -> Result<Arc<AllowanceManager>> {
+    let (holder, holder_is_contract) = match contract_address {
+        Some(address) => (address, true),
+        None => (
+            validate_and_parse_address(&config.ethereum.wallet_address)
+                .context("Allowance checks need the contract or wallet address")?,
+            false,
+        ),
+    };
+
+    let trident = &config.dex.trident;
+    let bento_box = if trident.enabled {
+        validate_and_parse_address(&trident.bento_box_address).ok()
+    } else {
+        None
+    };
+
+    info!(
+        "Checking allowances of the {} {:?}",
+        if holder_is_contract {
+            "executor contract"
+        } else {
+            "wallet"
+        },
+        holder
+    );
+
+    Ok(Arc::new(AllowanceManager {
+        multicall: Multicall::new(config, blockchain_client)?,
+        flash_loans,
+        tx_executor,
+        holder,
+        holder_is_contract,
+        check_only: allowances_config.check_only,
+        uniswap_router: validate_and_parse_address(&config.dex.uniswap.router_address).ok(),
+        sushiswap_router: validate_and_parse_address(&config.dex.sushiswap.router_address).ok(),
+        curve_router: validate_and_parse_address(&config.dex.curve.router_address).ok(),
+        bento_box,
+        approvals: Mutex::new(Approvals::default()),
+    }))
+}
+
+impl AllowanceManager {
+    /// Whether every approval an opportunity's path spends exists
+    ///
+    /// Missing approvals are set in the background unless only checked, and the path is
+    /// refused until they are confirmed. So is a path whose allowances cannot be read.
+    pub async fn allows(self: &Arc<Self>, opportunity: &ArbitrageOpportunity) -> bool {
+        let unknown: Vec<Approval> = {
+            let approvals = self.lock();
+            self.required(opportunity)
+                .into_iter()
+                .filter(|approval| !approvals.approved.contains(approval))
+                .collect()
+        };
+        if unknown.is_empty() {
+            return true;
+        }
+
+        let missing = match self.find_missing(&unknown).await {
+            Ok(missing) => missing,
+            Err(e) => {
+                warn!("Could not check allowances of {}: {:#}", opportunity.id, e);
+                metrics::global().increment_counter("allowance_check_failures", 1);
+                return false;
+            }
+        };
+        if missing.is_empty() {
+            return true;
+        }
+
+        warn!(
+            "{} needs {} missing approval(s): {}",
+            opportunity,
+            missing.len(),
+            missing
+                .iter()
+                .map(|(token, spender)| format!("{} to {:?}", tokens::token_label(*token), spender))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        metrics::global().increment_counter("allowance_missing", 1);
+        trace::record(TraceStage::Rejection, || {
+            format!(
+                "{} is missing {} approval(s)",
+                opportunity.id,
+                missing.len()
+            )
+        });
+
+        if !self.check_only {
+            self.approve(missing);
+        }
+
+        false
+    }
+
+    /// Get the approvals an opportunity's path spends
+    fn required(&self, opportunity: &ArbitrageOpportunity) -> Vec<Approval> {
+        let mut required = Vec::new();
+        for (&token, &(dex_type, pool)) in opportunity.token_path.iter().zip(&opportunity.hops) {
+            let spender = match dex_type {
+                // Swaps through a known pair transfer the input to it instead
+                DexType::UniswapV2 | DexType::Sushiswap if !pool.is_zero() => None,
+                DexType::UniswapV2 => self.uniswap_router,
+                DexType::Sushiswap => self.sushiswap_router,
+                DexType::Curve => self.curve_router,
+                DexType::Trident => self.bento_box,
+            };
+            if let Some(spender) = spender {
+                required.push((token, spender));
+            }
+        }
+
+        if let (Some(&token), Some(spender)) = (
+            opportunity.token_path.first(),
+            self.flash_loans
+                .repayment_spender(opportunity.flash_loan_provider),
+        ) {
+            required.push((token, spender));
+        }
+
+        let mut seen = HashSet::new();
+        required.retain(|approval| seen.insert(*approval));
+        required
+    }
+
+    /// Read the holder's allowances, returning the approvals missing
+    ///
+    /// Only standing approvals (above half the maximum) count, since trades spend exact
+    /// allowances down to nothing. Those found are remembered.
+    async fn find_missing(&self, approvals: &[Approval]) -> Result<Vec<Approval>> {
+        let erc20 = ContractAbi::Erc20.contract();
+        let calls = approvals
+            .iter()
+            .map(|&(token, spender)| {
+                Ok(Call {
+                    target: token,
+                    data: erc20.encode("allowance", (self.holder, spender))?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let outputs = self
+            .multicall
+            .aggregate(&calls, BlockId::Number(BlockNumber::Latest))
+            .await?;
+
+        let standing = U256::MAX / 2;
+        let mut missing = Vec::new();
+        let mut state = self.lock();
+        for (&approval, output) in approvals.iter().zip(outputs) {
+            let allowance = output
+                .and_then(|output: Bytes| erc20.decode_output::<U256, _>("allowance", output).ok())
+                .unwrap_or_default();
+            if allowance >= standing {
+                state.approved.insert(approval);
+            } else {
+                missing.push(approval);
+            }
+        }
+
+        Ok(missing)
+    }
+
+    /// Send the approvals not already pending, and remember them once confirmed
+    fn approve(self: &Arc<Self>, missing: Vec<Approval>) {
+        let approvals: Vec<Approval> = {
+            let mut state = self.lock();
+            missing
+                .into_iter()
+                .filter(|approval| state.pending.insert(*approval))
+                .collect()
+        };
+        if approvals.is_empty() {
+            return;
+        }
+
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let batches = match manager.approval_requests(&approvals) {
+                Ok(batches) => batches,
+                Err(e) => {
+                    warn!("Failed to encode approvals: {:#}", e);
+                    Vec::new()
+                }
+            };
+            for (request, batch) in batches {
+                match manager.send_approval(request).await {
+                    Ok(true) => {
+                        manager.lock().approved.extend(batch.iter().copied());
+                        metrics::global()
+                            .increment_counter("allowance_approvals", batch.len() as u64);
+                    }
+                    Ok(false) => warn!("Approval of {} token(s) failed on chain", batch.len()),
+                    Err(e) => warn!("Failed to approve {} token(s): {:#}", batch.len(), e),
+                }
+            }
+
+            let mut state = manager.lock();
+            for approval in &approvals {
+                state.pending.remove(approval);
+            }
+        });
+    }
+
+    /// Build the transactions setting approvals, with the approvals each one sets
+    fn approval_requests(
+        &self,
+        approvals: &[Approval],
+    ) -> Result<Vec<(TransactionRequest, Vec<Approval>)>> {
+        if self.holder_is_contract {
+            let (tokens, spenders): (Vec<Address>, Vec<Address>) =
+                approvals.iter().copied().unzip();
+            let data = ContractAbi::ArbitrageExecutor
+                .contract()
+                .encode("approveTokens", (tokens, spenders))?;
+            let request = TransactionRequest::new().to(self.holder).data(data);
+            return Ok(vec![(request, approvals.to_vec())]);
+        }
+
+        approvals
+            .iter()
+            .map(|&(token, spender)| {
+                let data = ContractAbi::Erc20
+                    .contract()
+                    .encode("approve", (spender, U256::MAX))?;
+                let request = TransactionRequest::new().to(token).data(data);
+                Ok((request, vec![(token, spender)]))
+            })
+            .collect()
+    }
+
+    /// Send an approval transaction and wait for it, returning whether it succeeded
+    async fn send_approval(&self, request: TransactionRequest) -> Result<bool> {
+        let tx_hash = self.tx_executor.send_wallet_transaction(request).await?;
+        info!("Setting approvals in {:?}", tx_hash);
+
+        let result = self
+            .tx_executor
+            .wait_for_transaction(tx_hash, APPROVAL_TIMEOUT)
+            .await?;
+        debug!("Approval {:?} included: {}", tx_hash, result.success);
+
+        Ok(result.success)
+    }
+
+    /// Lock the known approvals
+    fn lock(&self) -> std::sync::MutexGuard<'_, Approvals> {
+        self.approvals.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,277 @@
+//! Bundle Analytics Module
+//!
+//! This module is responsible for explaining why submitted bundles land or miss. Once the blocks
+//! a submission targeted have passed, the relay's statistics of the bundle tell whether it was
+//! simulated, considered by builders and sealed into a block; on a miss, the targeted blocks are
+//! searched for the transaction from another sender that went through the bundle's pools. Its
+//! priority fee, plus one wei, is the tip that would have won: the rebid of the bundle bids at
+//! least that, and later bundles through the same pools outbid it through the gas optimizer's
+//! competing tips. Each outcome is counted in the `bundle_outcome_<outcome>` metrics and stored
+//! for the `bundle_outcome_stats` report.
+
+use ethers::types::U256;
+use log::{debug, info, warn};
+use std::sync::Arc;
+
+use crate::blockchain::RpcClient;
+use crate::competition::{find_route_winner, RouteWinner};
+use crate::gas::GasOptimizer;
+use crate::metrics;
+use crate::mev_share::{BundleStats, MevShareClient};
+use crate::storage::Storage;
+use crate::transaction::SubmittedBundle;
+
+/// Why a bundle submission landed or missed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundleOutcome {
+    /// Included in one of its target blocks
+    Included,
+
+    /// Its nonce was used by another transaction
+    NonceUsed,
+
+    /// Another sender's transaction went through its pools first
+    Outbid,
+
+    /// The relay never simulated it
+    NotSimulated,
+
+    /// No builder considered it
+    NotConsidered,
+
+    /// Builders considered it but sealed no block with it
+    NotSealed,
+
+    /// Sealed into blocks that lost the slot, or the relay reported nothing
+    NotLanded,
+}
+
+impl BundleOutcome {
+    /// Get the name of the outcome, used in metrics and storage
+    pub fn name(self) -> &'static str {
+        match self {
+            BundleOutcome::Included => "included",
+            BundleOutcome::NonceUsed => "nonce_used",
+            BundleOutcome::Outbid => "outbid",
+            BundleOutcome::NotSimulated => "not_simulated",
+            BundleOutcome::NotConsidered => "not_considered",
+            BundleOutcome::NotSealed => "not_sealed",
+            BundleOutcome::NotLanded => "not_landed",
+        }
+    }
+}
+
+/// What happened to one submission of a bundle
+#[derive(Debug, Clone)]
+pub struct BundleReport {
+    /// Relay hash of the submission
+    pub bundle_hash: String,
+
+    /// First block the submission targeted
+    pub first_block: u64,
+
+    /// Last block the submission targeted
+    pub last_block: u64,
+
+    /// Number of rebids before the submission
+    pub rebids: u32,
+
+    /// Why the submission landed or missed
+    pub outcome: BundleOutcome,
+
+    /// Relay statistics of the submission, if the relay reported them
+    pub stats: Option<BundleStats>,
+
+    /// Highest priority fee per gas the submission bid (in wei)
+    pub priority_fee: U256,
+
+    /// Transaction that went through the bundle's pools instead, on a miss
+    pub winner: Option<RouteWinner>,
+
+    /// Priority fee per gas that would have won (in wei), when the winner outbid us on tips
+    pub winning_tip: Option<U256>,
+}
+
+/// Explains bundle outcomes and feeds the tips that would have won back into bidding
+pub struct BundleAnalytics {
+    blockchain_client: Arc<RpcClient>,
+    mev_share_client: Arc<MevShareClient>,
+    gas_optimizer: Arc<dyn GasOptimizer>,
+    storage: Option<Arc<Storage>>,
+}
+
+impl BundleAnalytics {
+    /// Create the analytics, recording reports to `storage` when given
+    pub fn new(
+        blockchain_client: Arc<RpcClient>,
+        mev_share_client: Arc<MevShareClient>,
+        gas_optimizer: Arc<dyn GasOptimizer>,
+        storage: Option<Arc<Storage>>,
+    ) -> Self {
+        Self {
+            blockchain_client,
+            mev_share_client,
+            gas_optimizer,
+            storage,
+        }
+    }
+
+    /// Record a submission included in one of its target blocks
+    pub async fn record_inclusion(&self, bundle: &SubmittedBundle, rebids: u32) -> BundleReport {
+        let report = BundleReport {
+            bundle_hash: bundle.bundle_hash.clone(),
+            first_block: bundle.first_block,
+            last_block: bundle.last_block,
+            rebids,
+            outcome: BundleOutcome::Included,
+            stats: self.stats(bundle).await,
+            priority_fee: bundle.priority_fee,
+            winner: None,
+            winning_tip: None,
+        };
+        self.record(&report);
+
+        report
+    }
+
+    /// Explain a submission that missed its target blocks, and make later bids outbid the
+    /// transaction that won instead
+    pub async fn explain_miss(
+        &self,
+        bundle: &SubmittedBundle,
+        rebids: u32,
+        nonce_used: bool,
+    ) -> BundleReport {
+        let stats = self.stats(bundle).await;
+        let winner = match find_route_winner(
+            &self.blockchain_client,
+            &bundle.pools,
+            bundle.first_block,
+            bundle.last_block,
+        )
+        .await
+        {
+            Ok(winner) => winner.filter(|winner| winner.sender != bundle.sender),
+            Err(e) => {
+                debug!(
+                    "Failed to search the winner over bundle {}: {}",
+                    bundle.bundle_hash, e
+                );
+                None
+            }
+        };
+
+        // Direct payments to the builder are not seen, so a winner tipping less than the bundle
+        // says nothing about the tip that would have won
+        let winning_tip = winner
+            .as_ref()
+            .map(|winner| winner.priority_fee.saturating_add(U256::one()))
+            .filter(|&tip| tip > bundle.priority_fee);
+        if let Some(tip) = winning_tip {
+            self.gas_optimizer.record_winning_tip(&bundle.pools, tip);
+        }
+
+        let outcome = if nonce_used {
+            BundleOutcome::NonceUsed
+        } else if winner.is_some() {
+            BundleOutcome::Outbid
+        } else {
+            match &stats {
+                Some(stats) if !stats.is_simulated => BundleOutcome::NotSimulated,
+                Some(stats) if stats.considered_by_builders_at.is_empty() => {
+                    BundleOutcome::NotConsidered
+                }
+                Some(stats) if stats.sealed_by_builders_at.is_empty() => BundleOutcome::NotSealed,
+                _ => BundleOutcome::NotLanded,
+            }
+        };
+
+        let report = BundleReport {
+            bundle_hash: bundle.bundle_hash.clone(),
+            first_block: bundle.first_block,
+            last_block: bundle.last_block,
+            rebids,
+            outcome,
+            stats,
+            priority_fee: bundle.priority_fee,
+            winner,
+            winning_tip,
+        };
+        self.record(&report);
+
+        report
+    }
+
+    /// Get the relay statistics of a submission for its first target block
+    async fn stats(&self, bundle: &SubmittedBundle) -> Option<BundleStats> {
+        match self
+            .mev_share_client
+            .get_bundle_stats_for(&bundle.bundle_hash, bundle.first_block)
+            .await
+        {
+            Ok(stats) => Some(stats),
+            Err(e) => {
+                debug!(
+                    "Failed to get stats of bundle {}: {}",
+                    bundle.bundle_hash, e
+                );
+                None
+            }
+        }
+    }
+
+    /// Log, count and store a report
+    fn record(&self, report: &BundleReport) {
+        match (&report.winner, report.winning_tip) {
+            (Some(winner), Some(tip)) => info!(
+                "Bundle {} {} for blocks {}-{}: {:?} from {:?} won in block {} at {} gwei; {} gwei would have won (we bid {} gwei)",
+                report.bundle_hash,
+                report.outcome.name(),
+                report.first_block,
+                report.last_block,
+                winner.tx_hash,
+                winner.sender,
+                winner.block_number,
+                gwei(winner.priority_fee),
+                gwei(tip),
+                gwei(report.priority_fee)
+            ),
+            (Some(winner), None) => info!(
+                "Bundle {} {} for blocks {}-{}: {:?} from {:?} won in block {} at {} gwei, below our {} gwei",
+                report.bundle_hash,
+                report.outcome.name(),
+                report.first_block,
+                report.last_block,
+                winner.tx_hash,
+                winner.sender,
+                winner.block_number,
+                gwei(winner.priority_fee),
+                gwei(report.priority_fee)
+            ),
+            _ => debug!(
+                "Bundle {} {} for blocks {}-{}",
+                report.bundle_hash,
+                report.outcome.name(),
+                report.first_block,
+                report.last_block
+            ),
+        }
+
+        metrics::global()
+            .increment_counter(&format!("bundle_outcome_{}", report.outcome.name()), 1);
+
+        if let Some(storage) = &self.storage {
+            if let Err(e) = storage.record_bundle_outcome(report) {
+                warn!(
+                    "Failed to record outcome of bundle {}: {}",
+                    report.bundle_hash, e
+                );
+            }
+        }
+    }
+}
+
+/// Convert wei per gas to gwei, for logs
+fn gwei(fee: U256) -> f64 {
+    fee.as_u128() as f64 / 1e9
+}
@@ -0,0 +1,480 @@
+//! API Module
+//!
+//! This module is responsible for the admin API: an HTTP endpoint through which the operator
+//! controls the running bot without restarting it. Every request must carry the configured
+//! token as `Authorization: Bearer <token>`.
+//!
+//! Pausing and the minimum profit threshold override apply to every chain the process trades
+//! on. The emergency stop, the opportunity history, the DEX and pool overrides and the route
+//! quarantine are those of the primary chain.
+//!
+//! Endpoints:
+//! - `GET /health`: health of the supervised tasks, as JSON (`503` while unhealthy)
+//! - `POST /pause`: stop scanning for opportunities; submitted transactions still settle
+//! - `POST /resume`: scan again
+//! - `GET /min_profit_threshold`: the configured threshold and the override, as JSON
+//! - `POST /min_profit_threshold`: replace the threshold of every strategy variant with the
+//!   body (in USD), or restore the configured thresholds with an empty body or `default`
+//! - `GET /opportunities`: the most recent opportunities, as JSON
+//! - `POST /emergency_stop`: pause scanning and activate the contract's emergency stop
+//! - `GET /metrics`: the process-wide metrics, in the Prometheus text format
+//! - `GET /dexes`: which configured DEXes are enabled and which pools are blacklisted, as JSON
+//! - `POST /dexes/enable` and `/dexes/disable`: enable or disable the DEX named by the body
+//!   (e.g. `Curve`) from the next scan
+//! - `POST /pools/blacklist` and `/pools/unblacklist`: take the pool whose address is the body
+//!   out of the scan, or return it
+//! - `GET /routes/quarantined`: the routes quarantined after repeated reverts and when each is
+//!   re-enabled, as JSON
+//! - `POST /trace/<block>`: record every decision made while the future block `<block>` is the
+//!   chain head
+//! - `GET /trace/<block>`: the trace of a block, in progress or complete, as JSON
+//! - `GET /traces`: the block numbers with a trace available, as JSON
+//!
+//! Pausing and the threshold override live in the process-wide `runtime::controls()`; they and
+//! the DEX and pool overrides are lost on restart.
+
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::sync::CancellationToken;
+
+use crate::alerts::AlertManager;
+use crate::config::{AlertSeverity, Config, ConfigSection};
+use crate::contract::ContractManager;
+use crate::dex::{DexInterfaces, DexType};
+use crate::metrics;
+use crate::observer::{self, Response, MAX_REQUEST_BYTES};
+use crate::runtime::{self, HealthMonitor};
+use crate::storage::Storage;
+use crate::strategy::RouteGuard;
+use crate::trace;
+use crate::transaction::TransactionExecutor;
+use crate::utils::validate_and_parse_address;
+
+/// Default address the admin API listens on
+pub const DEFAULT_LISTEN_ADDRESS: &str = "127.0.0.1:9471";
+
+/// Default number of records returned by the recent opportunities endpoint
+pub const DEFAULT_RECENT_LIMIT: usize = 50;
+
+/// Admin API configuration (`[api]`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiConfig {
+    /// Whether the admin API is served
+    pub enabled: bool,
+
+    /// Address the admin API listens on (defaults to 127.0.0.1:9471)
+    pub listen_address: Option<String>,
+
+    /// Bearer token every request must carry
+    pub token: Option<String>,
+
+    /// Number of records returned by the recent opportunities endpoint (defaults to 50)
+    pub recent_limit: Option<usize>,
+}
+
+impl ConfigSection for ApiConfig {
+    const NAME: &'static str = "api";
+
+    fn validate(&self) -> Result<()> {
+        if self.enabled
+            && self
+                .token
+                .as_deref()
+                .is_none_or(|token| token.trim().is_empty())
+        {
+            anyhow::bail!("The admin API requires a token");
+        }
+        if self.recent_limit == Some(0) {
+            anyhow::bail!("API recent_limit must be greater than zero");
+        }
+
+        Ok(())
+    }
+}
+
+/// The admin API of a running bot
+pub struct AdminApi {
+    listener: TcpListener,
+    token: String,
+    recent_limit: usize,
+    configured_threshold: f64,
+    storage: Option<Arc<Storage>>,
+    contract_manager: Arc<dyn ContractManager>,
+    tx_executor: Arc<dyn TransactionExecutor>,
+    dex_interfaces: Arc<DexInterfaces>,
+    route_guard: Arc<RouteGuard>,
+    health: HealthMonitor,
+    alert_manager: Arc<AlertManager>,
+}
+
+/// Components of a started chain that the admin API acts through
+pub struct ChainHandles {
+    pub contract_manager: Arc<dyn ContractManager>,
+    pub tx_executor: Arc<dyn TransactionExecutor>,
+    pub storage: Option<Arc<Storage>>,
+    pub dex_interfaces: Arc<DexInterfaces>,
+    pub route_guard: Arc<RouteGuard>,
+}
+
+/// Create the admin API of the primary chain and start listening
+///
+/// Emergency stop transactions are sent through the chain's executor, so they take the next
+/// nonce of the bot's wallet instead of racing its trades for one.
+pub async fn create_server(
+    config: &Arc<Config>,
+    api_config: &ApiConfig,
+    handles: ChainHandles,
+    health: HealthMonitor,
+    alert_manager: Arc<AlertManager>,
+) -> Result<Arc<AdminApi>> {
+    let listen_address = api_config
+        .listen_address
+        .as_deref()
+        .unwrap_or(DEFAULT_LISTEN_ADDRESS);
+    let listener = TcpListener::bind(listen_address)
+        .await
+        .with_context(|| format!("Failed to listen on {}", listen_address))?;
+    info!("Admin API listening on http://{}", listen_address);
+
+    Ok(Arc::new(AdminApi {
+        listener,
+        token: api_config.token.clone().unwrap_or_default(),
+        recent_limit: api_config.recent_limit.unwrap_or(DEFAULT_RECENT_LIMIT),
+        configured_threshold: config.arbitrage.min_profit_threshold,
+        storage: handles.storage,
+        contract_manager: handles.contract_manager,
+        tx_executor: handles.tx_executor,
+        dex_interfaces: handles.dex_interfaces,
+        route_guard: handles.route_guard,
+        health,
+        alert_manager,
+    }))
+}
+
+impl AdminApi {
+    /// Accept connections until shutdown, serving each on its own task
+    pub async fn run(self: Arc<Self>, shutdown: CancellationToken) {
+        while let Some(accepted) = shutdown.run_until_cancelled(self.listener.accept()).await {
+            match accepted {
+                Ok((stream, peer)) => {
+                    debug!("Admin API connection from {}", peer);
+                    let api = self.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = api.serve(stream).await {
+                            debug!("Admin API connection from {} failed: {}", peer, e);
+                        }
+                    });
+                }
+                Err(e) => warn!("Failed to accept admin API connection: {}", e),
+            }
+        }
+    }
+
+    /// Read one request from a connection and answer it
+    async fn serve(&self, mut stream: TcpStream) -> Result<()> {
+        let mut request = Vec::new();
+        let mut buffer = [0u8; 1024];
+        let head_end = loop {
+            if let Some(position) = request.windows(4).position(|window| window == b"\r\n\r\n") {
+                break position + 4;
+            }
+            let read = stream.read(&mut buffer).await?;
+            if read == 0 {
+                break request.len();
+            }
+            request.extend_from_slice(&buffer[..read]);
+            if request.len() > MAX_REQUEST_BYTES {
+                anyhow::bail!("Request exceeds {} bytes", MAX_REQUEST_BYTES);
+            }
+        };
+
+        let head = String::from_utf8_lossy(&request[..head_end]).into_owned();
+        let mut lines = head.lines();
+        let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+        let (method, target) = (request_line.next(), request_line.next());
+
+        let mut authorization = None;
+        let mut content_length = 0;
+        for line in lines {
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            if name.eq_ignore_ascii_case("authorization") {
+                authorization = Some(value.trim().to_string());
+            } else if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+
+        // Read the rest of the body
+        if head_end.saturating_add(content_length) > MAX_REQUEST_BYTES {
+            anyhow::bail!("Request exceeds {} bytes", MAX_REQUEST_BYTES);
+        }
+        while request.len() < head_end + content_length {
+            let read = stream.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            request.extend_from_slice(&buffer[..read]);
+        }
+        let body_end = request.len().min(head_end + content_length);
+        let body = String::from_utf8_lossy(&request[head_end..body_end]).into_owned();
+
+        let response = match (method, target) {
+            (Some(_), Some(_)) if !self.authorized(authorization.as_deref()) => {
+                warn!("Rejected an admin API request without a valid token");
+                Response::text("401 Unauthorized", "Unauthorized\n".into())
+            }
+            (Some(method), Some(target)) => self.route(method, target, body.trim()).await,
+            _ => Response::text("400 Bad Request", "Malformed request\n".into()),
+        };
+
+        observer::write_response(&mut stream, &response).await
+    }
+
+    /// Whether an `Authorization` header carries the configured token
+    fn authorized(&self, authorization: Option<&str>) -> bool {
+        let Some(token) = authorization.and_then(|value| value.strip_prefix("Bearer ")) else {
+            return false;
+        };
+
+        // Compare in constant time, so the token cannot be guessed a byte at a time
+        token.len() == self.token.len()
+            && token
+                .bytes()
+                .zip(self.token.bytes())
+                .fold(0, |difference, (a, b)| difference | (a ^ b))
+                == 0
+    }
+
+    /// Answer a request for a path
+    async fn route(&self, method: &str, target: &str, body: &str) -> Response {
+        let path = target.split('?').next().unwrap_or_default();
+        let result = match (method, path.trim_end_matches('/')) {
+            ("GET", "/health") => Ok(self.health()),
+            ("POST", "/pause") => Ok(set_scanning_paused(true)),
+            ("POST", "/resume") => Ok(set_scanning_paused(false)),
+            ("GET", "/min_profit_threshold") => Ok(self.min_profit_threshold()),
+            ("POST", "/min_profit_threshold") => Ok(self.set_min_profit_threshold(body)),
+            ("GET", "/opportunities") => match &self.storage {
+                Some(storage) => observer::recent_opportunities(storage, self.recent_limit),
+                None => Ok(Response::text(
+                    "404 Not Found",
+                    "Trade history storage is disabled\n".into(),
+                )),
+            },
+            ("POST", "/emergency_stop") => self.emergency_stop().await,
+            ("GET", "/metrics") => Ok(Response::text("200 OK", metrics::global().render())),
+            ("GET", "/dexes") => Ok(self.dexes()),
+            ("POST", "/dexes/enable") => Ok(self.set_dex_enabled(body, true)),
+            ("POST", "/dexes/disable") => Ok(self.set_dex_enabled(body, false)),
+            ("POST", "/pools/blacklist") => Ok(self.set_pool_blacklisted(body, true)),
+            ("POST", "/pools/unblacklist") => Ok(self.set_pool_blacklisted(body, false)),
+            ("GET", "/routes/quarantined") => Ok(Response::json(json!({
+                "quarantined_routes": self.route_guard.quarantined(),
+            }))),
+            ("GET", "/traces") => Ok(Response::json(json!({
+                "traced_blocks": trace::global().traced_blocks(),
+            }))),
+            (_, "/health" | "/pause" | "/resume" | "/min_profit_threshold")
+            | (_, "/opportunities" | "/emergency_stop" | "/metrics" | "/dexes")
+            | (_, "/dexes/enable" | "/dexes/disable" | "/pools/blacklist" | "/pools/unblacklist")
+            | (_, "/routes/quarantined" | "/traces") => Ok(Response::text(
+                "405 Method Not Allowed",
+                "Method not allowed\n".into(),
+            )),
+            (method, path) => match path.strip_prefix("/trace/") {
+                Some(block) => Ok(block_trace(method, block)),
+                None => Ok(Response::text("404 Not Found", "Not found\n".into())),
+            },
+        };
+
+        result.unwrap_or_else(|e| {
+            warn!("Admin API request {} {} failed: {:#}", method, path, e);
+            Response::text("500 Internal Server Error", format!("{:#}\n", e))
+        })
+    }
+
+    /// Render the health of the supervised tasks
+    fn health(&self) -> Response {
+        let health = self.health.health();
+        let healthy = health.is_healthy();
+        let mut response = Response::json(json!({
+            "healthy": healthy,
+            "scanning_paused": runtime::controls().scanning_paused(),
+            "min_profit_threshold": runtime::controls().min_profit_threshold(),
+            "health": health,
+        }));
+        if !healthy {
+            response.status = "503 Service Unavailable";
+        }
+
+        response
+    }
+
+    /// Render the configured minimum profit threshold and its override
+    fn min_profit_threshold(&self) -> Response {
+        Response::json(json!({
+            "configured": self.configured_threshold,
+            "override": runtime::controls().min_profit_threshold(),
+        }))
+    }
+
+    /// Set or clear the minimum profit threshold override
+    fn set_min_profit_threshold(&self, body: &str) -> Response {
+        let threshold = match body {
+            "" | "default" => None,
+            value => match value.parse::<f64>() {
+                Ok(threshold) if threshold.is_finite() && threshold >= 0.0 => Some(threshold),
+                _ => {
+                    return Response::text(
+                        "400 Bad Request",
+                        format!("Invalid threshold {:?}\n", value),
+                    )
+                }
+            },
+        };
+
+        runtime::controls().set_min_profit_threshold(threshold);
+        match threshold {
+            Some(threshold) => info!(
+                "Minimum profit threshold set to ${:.2} through the admin API",
+                threshold
+            ),
+            None => info!(
+                "Minimum profit threshold restored to the configured ${:.2} through the admin API",
+                self.configured_threshold
+            ),
+        }
+
+        self.min_profit_threshold()
+    }
+
+    /// Render which configured DEXes are enabled and which pools are blacklisted
+    fn dexes(&self) -> Response {
+        let overrides = self.dex_interfaces.overrides();
+        let mut dex_types: Vec<DexType> = self
+            .dex_interfaces
+            .get_all_interfaces()
+            .iter()
+            .map(|interface| interface.dex_type())
+            .collect();
+        dex_types.sort();
+        let dexes: Vec<_> = dex_types
+            .into_iter()
+            .map(|dex_type| {
+                json!({
+                    "name": dex_type.name(),
+                    "enabled": overrides.is_dex_enabled(dex_type),
+                })
+            })
+            .collect();
+
+        Response::json(json!({
+            "dexes": dexes,
+            "blacklisted_pools": overrides.blacklisted_pools(),
+        }))
+    }
+
+    /// Enable or disable the configured DEX named by the body
+    fn set_dex_enabled(&self, body: &str, enabled: bool) -> Response {
+        let dex_type = self
+            .dex_interfaces
+            .get_all_interfaces()
+            .iter()
+            .map(|interface| interface.dex_type())
+            .find(|dex_type| dex_type.name().eq_ignore_ascii_case(body));
+        let Some(dex_type) = dex_type else {
+            return Response::text(
+                "400 Bad Request",
+                format!("No configured DEX is named {:?}\n", body),
+            );
+        };
+
+        // The change itself is logged by the overrides
+        if let Err(e) = self.dex_interfaces.set_dex_enabled(dex_type, enabled) {
+            return Response::text("400 Bad Request", format!("{:#}\n", e));
+        }
+
+        self.dexes()
+    }
+
+    /// Blacklist the pool whose address is the body, or lift its blacklisting
+    fn set_pool_blacklisted(&self, body: &str, blacklisted: bool) -> Response {
+        let Ok(pool) = validate_and_parse_address(body) else {
+            return Response::text(
+                "400 Bad Request",
+                format!("Invalid pool address {:?}\n", body),
+            );
+        };
+
+        self.dex_interfaces.set_pool_blacklisted(pool, blacklisted);
+
+        self.dexes()
+    }
+
+    /// Pause scanning and activate the emergency stop of the primary chain's contract
+    async fn emergency_stop(&self) -> Result<Response> {
+        runtime::controls().set_scanning_paused(true);
+
+        let tx = self.contract_manager.activate_emergency_stop().await?;
+        let tx_hash = self.tx_executor.send_wallet_transaction(tx).await?;
+        self.alert_manager.raise(
+            AlertSeverity::Critical,
+            "api",
+            format!(
+                "Emergency stop activated through the admin API in {:?}; scanning is paused",
+                tx_hash
+            ),
+        );
+
+        Ok(Response::json(json!({
+            "scanning_paused": true,
+            "tx_hash": format!("{:?}", tx_hash),
+        })))
+    }
+}
+
+/// Pause or resume scanning
+fn set_scanning_paused(paused: bool) -> Response {
+    runtime::controls().set_scanning_paused(paused);
+    if paused {
+        info!("Scanning paused through the admin API");
+    } else {
+        info!("Scanning resumed through the admin API");
+    }
+
+    Response::json(json!({ "scanning_paused": paused }))
+}
+
+/// Request the trace of a future block, or render the trace of a block
+fn block_trace(method: &str, block: &str) -> Response {
+    let Ok(block_number) = block.parse::<u64>() else {
+        return Response::text(
+            "400 Bad Request",
+            format!("Invalid block number {:?}\n", block),
+        );
+    };
+
+    match method {
+        // The tracer logs the request itself
+        "POST" => {
+            trace::global().request(block_number);
+            Response::json(json!({ "requested": block_number }))
+        }
+        "GET" => match trace::global().trace(block_number) {
+            Some(trace) => Response::json(json!(trace)),
+            None => Response::text(
+                "404 Not Found",
+                format!("No trace of block {}\n", block_number),
+            ),
+        },
+        _ => Response::text("405 Method Not Allowed", "Method not allowed\n".into()),
+    }
+}
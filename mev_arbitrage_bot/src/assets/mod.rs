@@ -0,0 +1,171 @@
+//! Assets Module
+//!
+//! This module is responsible for the contract ABIs embedded in the binary: each is parsed
+//! once, on first use, and all of them are validated at startup so a malformed artifact stops
+//! the bot before it trades.
+
+use anyhow::{Context, Result};
+use ethers::abi::Abi;
+use ethers::contract::BaseContract;
+use log::debug;
+use std::sync::OnceLock;
+
+/// An embedded contract ABI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContractAbi {
+    /// ERC20 token
+    Erc20,
+
+    /// ArbitrageExecutor contract
+    ArbitrageExecutor,
+
+    /// Uniswap V2 (and Sushiswap) factory
+    UniswapV2Factory,
+
+    /// Uniswap V2 (and Sushiswap) router
+    UniswapV2Router,
+
+    /// Uniswap V2 (and Sushiswap) pair
+    UniswapV2Pair,
+
+    /// Sushi Trident pool deployers and pools, and the BentoBox they hold their balances in
+    SushiTrident,
+
+    /// Curve factory
+    CurveFactory,
+
+    /// Curve router
+    CurveRouter,
+
+    /// Aave V3 Pool
+    AaveV3Pool,
+
+    /// Balancer Vault
+    BalancerVault,
+
+    /// Morpho Blue singleton
+    MorphoBlue,
+
+    /// Uniswap V3 factory and pool
+    UniswapV3,
+
+    /// OP Stack `GasPriceOracle` predeploy
+    OpGasPriceOracle,
+
+    /// Arbitrum `NodeInterface` precompile
+    ArbitrumNodeInterface,
+
+    /// Multicall3 batching contract
+    Multicall3,
+}
+
+/// Number of embedded ABIs
+const ABI_COUNT: usize = ContractAbi::ALL.len();
+
+/// Parsed ABIs, indexed by `ContractAbi`
+static PARSED: [OnceLock<Abi>; ABI_COUNT] = [const { OnceLock::new() }; ABI_COUNT];
+
+/// Call encoders and decoders built from the parsed ABIs, indexed by `ContractAbi`
+static CONTRACTS: [OnceLock<BaseContract>; ABI_COUNT] = [const { OnceLock::new() }; ABI_COUNT];
+
+impl ContractAbi {
+    /// Every embedded ABI
+    pub const ALL: [ContractAbi; 15] = [
+        ContractAbi::Erc20,
+        ContractAbi::ArbitrageExecutor,
+        ContractAbi::UniswapV2Factory,
+        ContractAbi::UniswapV2Router,
+        ContractAbi::UniswapV2Pair,
+        ContractAbi::SushiTrident,
+        ContractAbi::CurveFactory,
+        ContractAbi::CurveRouter,
+        ContractAbi::AaveV3Pool,
+        ContractAbi::BalancerVault,
+        ContractAbi::MorphoBlue,
+        ContractAbi::UniswapV3,
+        ContractAbi::OpGasPriceOracle,
+        ContractAbi::ArbitrumNodeInterface,
+        ContractAbi::Multicall3,
+    ];
+
+    /// Get the contract name
+    pub fn name(self) -> &'static str {
+        match self {
+            ContractAbi::Erc20 => "ERC20",
+            ContractAbi::ArbitrageExecutor => "ArbitrageExecutor",
+            ContractAbi::UniswapV2Factory => "Uniswap V2 factory",
+            ContractAbi::UniswapV2Router => "Uniswap V2 router",
+            ContractAbi::UniswapV2Pair => "Uniswap V2 pair",
+            ContractAbi::SushiTrident => "Sushi Trident",
+            ContractAbi::CurveFactory => "Curve factory",
+            ContractAbi::CurveRouter => "Curve router",
+            ContractAbi::AaveV3Pool => "Aave V3 Pool",
+            ContractAbi::BalancerVault => "Balancer Vault",
+            ContractAbi::MorphoBlue => "Morpho Blue",
+            ContractAbi::UniswapV3 => "Uniswap V3",
+            ContractAbi::OpGasPriceOracle => "OP Stack GasPriceOracle",
+            ContractAbi::ArbitrumNodeInterface => "Arbitrum NodeInterface",
+            ContractAbi::Multicall3 => "Multicall3",
+        }
+    }
+
+    /// Get the embedded JSON
+    fn json(self) -> &'static str {
+        match self {
+            ContractAbi::Erc20 => include_str!("./abi/ERC20.json"),
+            ContractAbi::ArbitrageExecutor => include_str!("./abi/ArbitrageExecutor.json"),
+            ContractAbi::UniswapV2Factory => include_str!("./abi/uniswap_v2_factory.json"),
+            ContractAbi::UniswapV2Router => include_str!("./abi/uniswap_v2_router.json"),
+            ContractAbi::UniswapV2Pair => include_str!("./abi/uniswap_v2_pair.json"),
+            ContractAbi::SushiTrident => include_str!("./abi/sushi_trident.json"),
+            ContractAbi::CurveFactory => include_str!("./abi/curve_factory.json"),
+            ContractAbi::CurveRouter => include_str!("./abi/curve_router.json"),
+            ContractAbi::AaveV3Pool => include_str!("./abi/aave_v3_pool.json"),
+            ContractAbi::BalancerVault => include_str!("./abi/balancer.json"),
+            ContractAbi::MorphoBlue => include_str!("./abi/morpho_blue.json"),
+            ContractAbi::UniswapV3 => include_str!("./abi/uniswap_v3.json"),
+            ContractAbi::OpGasPriceOracle => include_str!("./abi/op_gas_price_oracle.json"),
+            ContractAbi::ArbitrumNodeInterface => {
+                include_str!("./abi/arbitrum_node_interface.json")
+            }
+            ContractAbi::Multicall3 => include_str!("./abi/multicall3.json"),
+        }
+    }
+
+    /// Parse the embedded JSON
+    fn parse(self) -> Result<Abi> {
+        serde_json::from_str(self.json())
+            .with_context(|| format!("Failed to parse the embedded {} ABI", self.name()))
+    }
+
+    /// Get the parsed ABI, parsing it on first use
+    ///
+    /// Panics if the embedded JSON is malformed, which `validate` reports at startup instead.
+    pub fn abi(self) -> &'static Abi {
+        PARSED[self as usize].get_or_init(|| self.parse().unwrap_or_else(|e| panic!("{:#}", e)))
+    }
+
+    /// Get an encoder and decoder for the contract's calls, built on first use
+    ///
+    /// Hot paths encode calldata with this instead of building a contract instance, which
+    /// would clone the whole ABI, per call.
+    pub fn contract(self) -> &'static BaseContract {
+        CONTRACTS[self as usize].get_or_init(|| BaseContract::from(self.abi().clone()))
+    }
+}
+
+/// Parse every embedded ABI, failing on the first malformed one
+pub fn validate() -> Result<()> {
+    for contract in ContractAbi::ALL {
+        let abi = contract.parse()?;
+        debug!(
+            "Loaded {} ABI ({} functions, {} events)",
+            contract.name(),
+            abi.functions().count(),
+            abi.events().count()
+        );
+        let _ = PARSED[contract as usize].set(abi);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,134 @@
+//! Chain Module
+//!
+//! This module identifies the supported chains by chain ID and holds what differs between
+//! them beyond the configuration: canonical token and factory deployments, and how a rollup
+//! charges for posting transaction data to L1.
+
+/// How a chain charges for the L1 data of a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum L1FeeModel {
+    /// No L1 data fee (L1 chains and sidechains)
+    None,
+
+    /// OP Stack rollups: quoted by the `GasPriceOracle` predeploy
+    OpStack,
+
+    /// Arbitrum: quoted by the `NodeInterface` precompile, in L2 gas
+    Arbitrum,
+}
+
+/// A chain the bot knows the deployments of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Chain {
+    /// Ethereum mainnet
+    Ethereum,
+
+    /// Arbitrum One
+    Arbitrum,
+
+    /// OP Mainnet
+    Optimism,
+
+    /// Base
+    Base,
+
+    /// Polygon PoS
+    Polygon,
+
+    /// Any other chain, by chain ID
+    Other(u64),
+}
+
+impl Chain {
+    /// Identify a chain by its chain ID
+    pub fn from_id(chain_id: u64) -> Self {
+        match chain_id {
+            1 => Chain::Ethereum,
+            42161 => Chain::Arbitrum,
+            10 => Chain::Optimism,
+            8453 => Chain::Base,
+            137 => Chain::Polygon,
+            chain_id => Chain::Other(chain_id),
+        }
+    }
+
+    /// Get the chain ID
+    pub fn id(self) -> u64 {
+        match self {
+            Chain::Ethereum => 1,
+            Chain::Arbitrum => 42161,
+            Chain::Optimism => 10,
+            Chain::Base => 8453,
+            Chain::Polygon => 137,
+            Chain::Other(chain_id) => chain_id,
+        }
+    }
+
+    /// Get the chain name
+    pub fn name(self) -> String {
+        match self {
+            Chain::Ethereum => "Ethereum".to_string(),
+            Chain::Arbitrum => "Arbitrum".to_string(),
+            Chain::Optimism => "Optimism".to_string(),
+            Chain::Base => "Base".to_string(),
+            Chain::Polygon => "Polygon".to_string(),
+            Chain::Other(chain_id) => format!("chain {}", chain_id),
+        }
+    }
+
+    /// Get how the chain charges for L1 data
+    pub fn l1_fee_model(self) -> L1FeeModel {
+        match self {
+            Chain::Optimism | Chain::Base => L1FeeModel::OpStack,
+            Chain::Arbitrum => L1FeeModel::Arbitrum,
+            Chain::Ethereum | Chain::Polygon | Chain::Other(_) => L1FeeModel::None,
+        }
+    }
+
+    /// Get the network name of the chain in Alchemy URLs, if Alchemy serves it
+    pub fn alchemy_network(self) -> Option<&'static str> {
+        match self {
+            Chain::Ethereum => Some("eth-mainnet"),
+            Chain::Arbitrum => Some("arb-mainnet"),
+            Chain::Optimism => Some("opt-mainnet"),
+            Chain::Base => Some("base-mainnet"),
+            Chain::Polygon => Some("polygon-mainnet"),
+            Chain::Other(_) => None,
+        }
+    }
+
+    /// Get the canonical WETH address (falls back to mainnet for unknown chains)
+    pub fn weth_address(self) -> &'static str {
+        match self {
+            Chain::Arbitrum => "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1",
+            Chain::Optimism | Chain::Base => "0x4200000000000000000000000000000000000006",
+            Chain::Polygon => "0x7ceB23fD6bC0adD59E62ac25578270cFf1b9f619",
+            Chain::Ethereum | Chain::Other(_) => "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+        }
+    }
+
+    /// Get the native USDC address (falls back to mainnet for unknown chains)
+    pub fn usdc_address(self) -> &'static str {
+        match self {
+            Chain::Arbitrum => "0xaf88d065e77c8cC2239327C5EDb3A432268e5831",
+            Chain::Optimism => "0x0b2C639c533813f4Aa9D7837cAf62653d097Ff85",
+            Chain::Base => "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+            Chain::Polygon => "0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359",
+            Chain::Ethereum | Chain::Other(_) => "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+        }
+    }
+
+    /// Get the Uniswap V3 factory address (Base has its own deployment)
+    pub fn uniswap_v3_factory(self) -> &'static str {
+        match self {
+            Chain::Base => "0x33128a8fC17869897dcE68Ed026d694621f6FDfD",
+            _ => "0x1F98431c8aD98523631AE4a59f26a0Ba4DC8F984",
+        }
+    }
+}
+
+impl std::fmt::Display for Chain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
@@ -0,0 +1,321 @@
+//! RPC Failover Module
+//!
+//! This module is responsible for the transport every RPC client sends its requests through.
+//! A transport holds the primary RPC endpoint and its fallbacks, each served by any
+//! [`Transport`] (HTTP in production): requests go to the
+//! first healthy one and move on to the next when an endpoint fails or is rate limited, which
+//! benches it until a health check sees it answer again. Heavy reads (calls, gas estimates and
+//! traces) prefer a dedicated read endpoint when one is configured, which keeps them off the
+//! primary's rate limit; a read the endpoint cannot serve yet because it trails the chain head
+//! moves on without benching it. Logs and blocks are never read from it, since a trailing
+//! endpoint answers them with nothing rather than an error. Transactions are only ever
+//! submitted through the primary. Every request waits for the rate limit budget of its
+//! endpoint, and a request every endpoint failed transiently is retried by the transport's
+//! retry policy.
+
+use async_trait::async_trait;
+use ethers::providers::JsonRpcClient;
+use log::{info, warn};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use super::retry::RpcRetryConfig;
+use super::transport::{Transport, TransportError};
+use crate::metrics;
+use crate::ratelimit::{self, ApiEndpoint};
+use crate::utils::retry_with_backoff;
+
+/// Methods that submit transactions, which stay on the primary endpoint
+pub(super) const SUBMIT_METHODS: [&str; 2] = ["eth_sendRawTransaction", "eth_sendTransaction"];
+
+/// Methods whose requests are heavy enough to prefer the read endpoint
+const HEAVY_READ_METHODS: [&str; 6] = [
+    "eth_call",
+    "eth_estimateGas",
+    "eth_createAccessList",
+    "debug_traceCall",
+    "debug_traceTransaction",
+    "trace_call",
+];
+
+/// JSON-RPC error codes providers answer rate limited requests with
+const RATE_LIMIT_CODES: [i64; 2] = [429, -32005];
+
+/// How a failed request reflects on the endpoint that served it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Failure {
+    /// The request itself failed (e.g. a reverted call); other endpoints would fail it too
+    Request,
+
+    /// The endpoint is unreachable, failing or rate limiting, and is benched
+    Endpoint,
+
+    /// The endpoint does not have the requested block yet
+    Behind,
+}
+
+/// One RPC endpoint of a transport
+#[derive(Debug)]
+struct Endpoint {
+    /// Name used in logs, which never include the URL and the API key it may hold
+    name: String,
+    transport: Box<dyn Transport>,
+    healthy: AtomicBool,
+
+    /// Rate limit budget requests count against, if any
+    budget: Option<ApiEndpoint>,
+}
+
+impl Endpoint {
+    fn new(name: String, transport: impl Transport + 'static, budget: Option<ApiEndpoint>) -> Self {
+        Self {
+            name,
+            transport: Box::new(transport),
+            healthy: AtomicBool::new(true),
+            budget,
+        }
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+    }
+
+    /// Mark the endpoint unhealthy, until a health check sees it answer again
+    fn bench(&self, reason: &str) {
+        if self.healthy.swap(false, Ordering::Relaxed) {
+            warn!("RPC endpoint {} benched: {}", self.name, reason);
+            metrics::global().increment_counter("rpc_endpoints_benched", 1);
+        }
+    }
+
+    /// Mark the endpoint healthy again
+    fn restore(&self) {
+        if !self.healthy.swap(true, Ordering::Relaxed) {
+            info!("RPC endpoint {} is healthy again", self.name);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Endpoints {
+    /// The primary endpoint first, then the fallbacks in failover order
+    failover: Vec<Endpoint>,
+
+    /// Endpoint heavy reads are sent to first
+    read: Option<Endpoint>,
+}
+
+/// Transport that fails over between RPC endpoints
+#[derive(Debug, Clone)]
+pub struct FailoverTransport {
+    endpoints: Arc<Endpoints>,
+
+    /// Retry policies of transient failures, if they are retried
+    retry: Option<Arc<RpcRetryConfig>>,
+}
+
+impl FailoverTransport {
+    /// Create a transport for a single endpoint, without failover or a rate limit
+    ///
+    /// Any transport can be wrapped, e.g. a WebSocket connection or a mock provider in tests.
+    pub fn single(transport: impl Transport + 'static) -> Self {
+        Self {
+            endpoints: Arc::new(Endpoints {
+                failover: vec![Endpoint::new("primary".to_string(), transport, None)],
+                read: None,
+            }),
+            retry: None,
+        }
+    }
+
+    /// Create a transport for a primary endpoint, its fallbacks in failover order and an
+    /// optional endpoint for heavy reads, limited by the RPC rate limit budgets
+    pub fn new<T: Transport + 'static>(primary: T, fallbacks: Vec<T>, read: Option<T>) -> Self {
+        let failover = std::iter::once(Endpoint::new(
+            "primary".to_string(),
+            primary,
+            Some(ApiEndpoint::Rpc),
+        ))
+        .chain(fallbacks.into_iter().enumerate().map(|(index, transport)| {
+            Endpoint::new(
+                format!("fallback-{}", index),
+                transport,
+                Some(ApiEndpoint::Rpc),
+            )
+        }))
+        .collect();
+
+        Self {
+            endpoints: Arc::new(Endpoints {
+                failover,
+                read: read.map(|transport| {
+                    Endpoint::new("read".to_string(), transport, Some(ApiEndpoint::ReadRpc))
+                }),
+            }),
+            retry: None,
+        }
+    }
+
+    /// Retry requests that fail transiently on every endpoint by `retry`'s policies
+    pub fn with_retries(mut self, retry: RpcRetryConfig) -> Self {
+        self.retry = retry.enabled.then(|| Arc::new(retry));
+        self
+    }
+
+    /// Whether the transport has endpoints besides the primary
+    pub fn has_alternatives(&self) -> bool {
+        self.endpoints.failover.len() > 1 || self.endpoints.read.is_some()
+    }
+
+    /// Check every endpoint in the background, benching the ones that stop answering and
+    /// restoring the ones that answer again, until the transport is dropped
+    pub fn spawn_health_checks(&self, interval: Duration) {
+        if !self.has_alternatives() {
+            return;
+        }
+
+        let endpoints: Weak<Endpoints> = Arc::downgrade(&self.endpoints);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(endpoints) = endpoints.upgrade() else {
+                    return;
+                };
+
+                for endpoint in endpoints.failover.iter().chain(&endpoints.read) {
+                    match endpoint
+                        .transport
+                        .request_json("eth_blockNumber", None)
+                        .await
+                    {
+                        Ok(_) => endpoint.restore(),
+                        Err(e) => endpoint.bench(&format!("health check failed: {}", e)),
+                    }
+                }
+            }
+        });
+    }
+
+    /// Get the endpoints to try a method on, in order
+    ///
+    /// Healthy endpoints come first; benched ones are still tried as a last resort.
+    fn route(&self, method: &str) -> Vec<&Endpoint> {
+        let endpoints = &self.endpoints;
+        if SUBMIT_METHODS.contains(&method) {
+            return vec![&endpoints.failover[0]];
+        }
+
+        let mut route: Vec<&Endpoint> = Vec::with_capacity(endpoints.failover.len() + 1);
+        if HEAVY_READ_METHODS.contains(&method) {
+            route.extend(&endpoints.read);
+        }
+        route.extend(&endpoints.failover);
+        route.sort_by_key(|endpoint| !endpoint.is_healthy());
+
+        route
+    }
+
+    /// Send a request along its route once, moving on to the next endpoint when one fails
+    async fn request_once(
+        &self,
+        method: &str,
+        params: &Option<serde_json::Value>,
+    ) -> Result<serde_json::Value, TransportError> {
+        let route = self.route(method);
+        let last = route.len() - 1;
+        for (index, endpoint) in route.into_iter().enumerate() {
+            if let Some(budget) = endpoint.budget {
+                ratelimit::global().acquire(budget).await;
+            }
+
+            let e = match endpoint
+                .transport
+                .request_json(method, params.clone())
+                .await
+            {
+                Ok(result) => return Ok(result),
+                Err(e) => e,
+            };
+
+            let failure = classify(&e);
+            if failure == Failure::Endpoint {
+                endpoint.bench(&e.to_string());
+            }
+            if failure == Failure::Request || index == last {
+                return Err(e);
+            }
+            metrics::global().increment_counter("rpc_failovers", 1);
+        }
+
+        unreachable!("every route ends at an endpoint")
+    }
+
+    /// Send a request along its route, retrying it while every endpoint fails transiently
+    async fn request_with_retries(
+        &self,
+        method: &str,
+        params: &Option<serde_json::Value>,
+        retry: &RpcRetryConfig,
+    ) -> Result<serde_json::Value, TransportError> {
+        retry_with_backoff(
+            || self.request_once(method, params),
+            retry.backoff(method),
+            |e| {
+                let transient = classify(e) != Failure::Request;
+                if transient {
+                    metrics::global().increment_counter("rpc_retries", 1);
+                }
+                transient
+            },
+        )
+        .await
+    }
+}
+
+/// Classify a failed request
+fn classify(error: &TransportError) -> Failure {
+    match error {
+        TransportError::Connection(_) | TransportError::Serde(_) => Failure::Endpoint,
+        TransportError::JsonRpc(error) => {
+            let message = error.message.to_lowercase();
+            if RATE_LIMIT_CODES.contains(&error.code)
+                || message.contains("rate limit")
+                || message.contains("too many requests")
+            {
+                Failure::Endpoint
+            } else if message.contains("header not found") || message.contains("unknown block") {
+                Failure::Behind
+            } else {
+                Failure::Request
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for FailoverTransport {
+    type Error = TransportError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        // Zero-sized parameters (`()`) mean the request has none
+        let params = if std::mem::size_of::<T>() == 0 {
+            None
+        } else {
+            Some(serde_json::to_value(params)?)
+        };
+        let result = match &self.retry {
+            None => self.request_once(method, &params).await?,
+            Some(retry) => self.request_with_retries(method, &params, retry).await?,
+        };
+
+        Ok(serde_json::from_value(result)?)
+    }
+}
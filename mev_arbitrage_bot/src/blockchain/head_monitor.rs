@@ -0,0 +1,245 @@
+//! Chain Head Monitor Module
+//!
+//! This module compares the chain head seen by the primary provider and the WebSocket
+//! subscription against peer providers, and switches the block data source to the most
+//! up-to-date peer when the primary keeps lagging.
+
+use anyhow::{Context, Result};
+use ethers::providers::Middleware;
+use log::{debug, info};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::time::Instant;
+
+use crate::alerts::AlertManager;
+use crate::blockchain::RpcClient;
+use crate::config::{AlertSeverity, Config};
+use crate::metrics;
+
+/// Default number of blocks the active source may trail its peers
+const DEFAULT_LAG_THRESHOLD_BLOCKS: u64 = 1;
+
+/// Default number of seconds the active source must keep lagging before switching
+const DEFAULT_LAG_GRACE_SECONDS: u64 = 5;
+
+/// A provider whose chain head is being tracked
+struct HeadSource {
+    name: String,
+    client: Arc<RpcClient>,
+    head: AtomicU64,
+}
+
+/// Result of comparing chain heads across sources
+#[derive(Debug, Clone, Copy)]
+pub struct HeadComparison {
+    /// Head of the active source
+    pub active_head: u64,
+
+    /// Highest head seen across all sources (including the WebSocket subscription)
+    pub best_head: u64,
+
+    /// Index of the source reporting the best head
+    pub best_source: usize,
+}
+
+impl HeadComparison {
+    /// Number of blocks the active source trails the best head by
+    pub fn lag(&self) -> u64 {
+        self.best_head.saturating_sub(self.active_head)
+    }
+}
+
+/// Tracks chain heads across providers and selects the active block data source
+pub struct HeadMonitor {
+    sources: Vec<HeadSource>,
+    active: AtomicUsize,
+    ws_head: AtomicU64,
+    lagging_since: RwLock<Option<Instant>>,
+    lag_threshold: u64,
+    lag_grace: Duration,
+    alerts: Arc<AlertManager>,
+}
+
+/// Create a new head monitor for the primary provider and the configured peers
+pub fn create_head_monitor(
+    config: &Arc<Config>,
+    primary: Arc<RpcClient>,
+    alerts: Arc<AlertManager>,
+) -> Result<Arc<HeadMonitor>> {
+    let mut sources = vec![HeadSource {
+        name: "primary".to_string(),
+        client: primary,
+        head: AtomicU64::new(0),
+    }];
+
+    for (index, url) in config.ethereum.peer_rpc_urls.iter().enumerate() {
+        let client = super::http_provider(url, &config.ethereum.peer_rpc_http)
+            .with_context(|| format!("Failed to create peer provider {}", index))?;
+        sources.push(HeadSource {
+            name: format!("peer-{}", index),
+            client: Arc::new(client),
+            head: AtomicU64::new(0),
+        });
+    }
+
+    let monitor = HeadMonitor {
+        sources,
+        active: AtomicUsize::new(0),
+        ws_head: AtomicU64::new(0),
+        lagging_since: RwLock::new(None),
+        lag_threshold: config
+            .ethereum
+            .head_lag_threshold_blocks
+            .unwrap_or(DEFAULT_LAG_THRESHOLD_BLOCKS),
+        lag_grace: Duration::from_secs(
+            config
+                .ethereum
+                .head_lag_grace_seconds
+                .unwrap_or(DEFAULT_LAG_GRACE_SECONDS),
+        ),
+        alerts,
+    };
+
+    Ok(Arc::new(monitor))
+}
+
+impl HeadMonitor {
+    /// Whether there are peers to compare against
+    pub fn has_peers(&self) -> bool {
+        self.sources.len() > 1
+    }
+
+    /// Get the client of the active block data source
+    pub fn active_client(&self) -> Arc<RpcClient> {
+        self.sources[self.active.load(Ordering::Relaxed)]
+            .client
+            .clone()
+    }
+
+    /// Whether the active source is a peer rather than the primary
+    pub fn is_failed_over(&self) -> bool {
+        self.active.load(Ordering::Relaxed) != 0
+    }
+
+    /// Record a head received from the WebSocket subscription
+    pub fn record_ws_head(&self, block_number: u64) {
+        self.ws_head.fetch_max(block_number, Ordering::Relaxed);
+    }
+
+    /// Poll every source for its head and compare them
+    pub async fn poll(&self) -> HeadComparison {
+        let requests = self
+            .sources
+            .iter()
+            .map(|source| source.client.get_block_number());
+        let results = futures::future::join_all(requests).await;
+
+        for (source, result) in self.sources.iter().zip(results) {
+            match result {
+                Ok(block_number) => {
+                    source
+                        .head
+                        .fetch_max(block_number.as_u64(), Ordering::Relaxed);
+                }
+                Err(e) => debug!("Failed to get head from {}: {}", source.name, e),
+            }
+        }
+
+        self.compare()
+    }
+
+    /// Compare the last known heads of all sources
+    pub fn compare(&self) -> HeadComparison {
+        let active = self.active.load(Ordering::Relaxed);
+        let mut active_head = self.sources[active].head.load(Ordering::Relaxed);
+
+        // The WebSocket subscription rides on the primary, so it counts towards its head
+        let ws_head = self.ws_head.load(Ordering::Relaxed);
+        if active == 0 {
+            active_head = active_head.max(ws_head);
+        }
+
+        let (best_source, best_head) = self
+            .sources
+            .iter()
+            .enumerate()
+            .map(|(index, source)| (index, source.head.load(Ordering::Relaxed)))
+            .max_by_key(|&(index, head)| (head, index == active))
+            .unwrap_or((active, active_head));
+
+        HeadComparison {
+            active_head,
+            best_head: best_head.max(ws_head),
+            best_source,
+        }
+    }
+
+    /// Poll the sources and switch the active source if it has been lagging for too long
+    ///
+    /// Returns the comparison, and whether the active source changed.
+    pub async fn check(&self) -> (HeadComparison, bool) {
+        let comparison = self.poll().await;
+        let lag = comparison.lag();
+
+        metrics::global().set_gauge("chain_head_lag_blocks", lag as f64);
+        metrics::global().set_gauge("chain_head_block", comparison.best_head as f64);
+
+        let mut lagging_since = self.lagging_since.write().await;
+        if lag <= self.lag_threshold {
+            *lagging_since = None;
+
+            // Return to the primary once it has caught up again
+            if self.is_failed_over() {
+                let primary = self.sources[0]
+                    .head
+                    .load(Ordering::Relaxed)
+                    .max(self.ws_head.load(Ordering::Relaxed));
+                if comparison.best_head.saturating_sub(primary) <= self.lag_threshold {
+                    self.active.store(0, Ordering::Relaxed);
+                    info!("Primary provider caught up, switching block source back to it");
+                    self.alerts.raise(
+                        AlertSeverity::Info,
+                        "head_monitor",
+                        "Primary provider caught up with its peers; using it as block source again",
+                    );
+                    return (comparison, true);
+                }
+            }
+
+            return (comparison, false);
+        }
+
+        let since = *lagging_since.get_or_insert_with(Instant::now);
+        if since.elapsed() < self.lag_grace {
+            return (comparison, false);
+        }
+
+        let active = self.active.load(Ordering::Relaxed);
+        if comparison.best_source == active {
+            // Only the WebSocket is ahead; nothing better to switch to
+            return (comparison, false);
+        }
+
+        self.active.store(comparison.best_source, Ordering::Relaxed);
+        *lagging_since = None;
+        metrics::global().increment_counter("chain_head_source_switches_total", 1);
+
+        self.alerts.raise(
+            AlertSeverity::Warning,
+            "head_monitor",
+            format!(
+                "Block source {} lagged {} blocks behind for over {}s (head {} vs {}); switched to {}",
+                self.sources[active].name,
+                lag,
+                self.lag_grace.as_secs(),
+                comparison.active_head,
+                comparison.best_head,
+                self.sources[comparison.best_source].name
+            ),
+        );
+
+        (comparison, true)
+    }
+}
@@ -0,0 +1,60 @@
+//! HTTP Endpoint Module
+//!
+//! This module is responsible for building the HTTP clients of configured endpoints, adding
+//! their custom headers and basic auth to every request and routing them through their proxy.
+
+use anyhow::{Context, Result};
+use ethers::providers::{Authorization, Http, Provider};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::{Client, Proxy, Url};
+use std::time::Duration;
+
+use super::{FailoverTransport, RpcClient};
+use crate::config::HttpEndpointConfig;
+
+/// Build an HTTP client for an endpoint, with an optional request timeout
+pub fn http_client(endpoint: &HttpEndpointConfig, timeout: Option<Duration>) -> Result<Client> {
+    let mut headers = HeaderMap::new();
+    for (name, value) in &endpoint.headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .with_context(|| format!("Invalid header name {}", name))?;
+        let mut value = HeaderValue::from_str(value)
+            .with_context(|| format!("Invalid value for header {}", name))?;
+        value.set_sensitive(true);
+        headers.insert(name, value);
+    }
+
+    if let Some(username) = &endpoint.basic_auth_username {
+        let password = endpoint.basic_auth_password.as_deref().unwrap_or_default();
+        let mut value =
+            HeaderValue::from_str(&Authorization::basic(username, password).to_string())
+                .context("Invalid basic auth credentials")?;
+        value.set_sensitive(true);
+        headers.insert(AUTHORIZATION, value);
+    }
+
+    let mut builder = Client::builder().default_headers(headers);
+    if let Some(timeout) = timeout {
+        builder = builder.timeout(timeout);
+    }
+    if let Some(proxy) = &endpoint.proxy {
+        // Credentials in the proxy URL are sent as Proxy-Authorization
+        builder = builder.proxy(Proxy::all(proxy).context("Invalid proxy URL")?);
+    }
+
+    builder.build().context("Failed to build HTTP client")
+}
+
+/// Create an HTTP transport for an RPC URL with the endpoint's client settings
+pub fn rpc_transport(url: &str, endpoint: &HttpEndpointConfig) -> Result<Http> {
+    let url = Url::parse(url).context("Invalid RPC URL")?;
+    let client = http_client(endpoint, None)?;
+    Ok(Http::new_with_client(url, client))
+}
+
+/// Create an HTTP provider for a single RPC URL with the endpoint's client settings
+pub fn http_provider(url: &str, endpoint: &HttpEndpointConfig) -> Result<RpcClient> {
+    Ok(Provider::new(FailoverTransport::single(rpc_transport(
+        url, endpoint,
+    )?)))
+}
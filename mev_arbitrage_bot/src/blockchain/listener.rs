@@ -1,24 +1,46 @@
 //! Blockchain Event Listener Module
 //!
 //! This module is responsible for listening to blockchain events and processing them.
+//! The logs the configured ArbitrageExecutor contract emits in each block are dispatched, by
+//! event name, to the handlers registered for them.
+//! A block that updates watched pools requests a scan of their token pairs once its pool
+//! updates are applied and prices refreshed. Chains trading pools that emit no `Sync` events
+//! (Curve, Trident) request a full sweep every block.
+//! Stopping the listener cancels its tasks instead of aborting them: the block sources stop at
+//! their next await, and a block being processed has its pool updates applied in full before
+//! its price update is interrupted.
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use ethers::abi::RawLog;
 use ethers::contract::{Contract, Event};
-use ethers::providers::{Http, Middleware, Provider, StreamExt, Ws};
+use ethers::providers::{Middleware, Provider, StreamExt, Ws};
 use ethers::types::{Address, BlockNumber, Filter, Log, H256, U64};
 use log::{debug, error, info, warn};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{mpsc, RwLock};
 use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
 
-use crate::blockchain::AlchemyProvider;
+use crate::blockchain::{AlchemyProvider, HeadMonitor, RpcClient};
 use crate::config::Config;
+use crate::contract::ExecutorEvent;
+use crate::dex::PoolStateCache;
+use crate::metrics;
 use crate::price::{PriceOracle, PriceOracleInterface};
-use crate::scanner::OpportunityScanner;
+use crate::scanner::ScanTrigger;
+use crate::tokens;
+use crate::trace::{self, TraceStage};
+use crate::utils::validate_and_parse_address;
+
+/// Delay before the first attempt to reconnect a dropped WebSocket subscription
+const RECONNECT_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Default longest delay between WebSocket reconnection attempts (in seconds)
+const DEFAULT_RECONNECT_MAX_BACKOFF_SECONDS: u64 = 60;
 
 /// Event handler function type
 type EventHandlerFn = Box<dyn Fn(Log) -> Result<()> + Send + Sync>;
@@ -32,7 +54,8 @@ pub trait BlockchainEventListener: Send + Sync {
     /// Stop listening for events
     async fn stop(&self) -> Result<()>;
 
-    /// Register an event handler
+    /// Register a handler for an ArbitrageExecutor event (e.g. `ArbitrageExecuted`), called
+    /// with every log of the event the contract emits
     async fn register_event_handler(&self, event_name: &str, handler: EventHandlerFn)
         -> Result<()>;
 
@@ -43,23 +66,37 @@ pub trait BlockchainEventListener: Send + Sync {
 /// Implementation of the blockchain event listener
 pub struct BlockchainEventListenerImpl {
     config: Arc<Config>,
-    blockchain_client_http: Arc<Provider<Http>>,
+    blockchain_client_http: Arc<RpcClient>,
     blockchain_client_ws: Option<Arc<Provider<Ws>>>,
     alchemy_provider: Option<Arc<AlchemyProvider>>,
-    scanner: Arc<dyn OpportunityScanner>,
+    scan_trigger: Arc<ScanTrigger>,
+
+    /// Whether every block requests a full sweep, since some traded pools are not in the pool
+    /// cache
+    scan_every_block: bool,
     price_oracle: Arc<PriceOracle>,
-    event_handlers: RwLock<HashMap<String, Vec<EventHandlerFn>>>,
-    is_running: RwLock<bool>,
-    task_handle: RwLock<Option<JoinHandle<()>>>,
+    pool_cache: Option<Arc<PoolStateCache>>,
+    head_monitor: Option<Arc<HeadMonitor>>,
+    contract_address: Option<Address>,
+
+    /// Handlers per event name, shared with the processing task
+    event_handlers: Arc<RwLock<HashMap<String, Vec<EventHandlerFn>>>>,
+    cancel: CancellationToken,
+    running: RwLock<Option<CancellationToken>>,
+    task_handles: RwLock<Vec<JoinHandle<()>>>,
     polling_interval: Duration,
 }
 
-/// Start a new blockchain event listener
+/// Start a new blockchain event listener, which requests scans through `scan_trigger` and stops
+/// when `cancel` is cancelled
 pub async fn start_listener(
     config: &Arc<Config>,
-    blockchain_client: Arc<Provider<ethers::providers::Http>>,
-    scanner: Arc<dyn OpportunityScanner>,
+    blockchain_client: Arc<RpcClient>,
+    scan_trigger: Arc<ScanTrigger>,
     price_oracle: Arc<PriceOracle>,
+    pool_cache: Option<Arc<PoolStateCache>>,
+    head_monitor: Option<Arc<HeadMonitor>>,
+    cancel: CancellationToken,
 ) -> Result<Arc<dyn BlockchainEventListener>> {
     // Check if WebSocket connections are enabled in the config
     let use_websocket = config.ethereum.use_websocket.unwrap_or(true);
@@ -100,6 +137,18 @@ pub async fn start_listener(
         None
     };
 
+    // Dispatch the events of the ArbitrageExecutor contract, once one is configured
+    let contract_address = match &config.arbitrage.contract.contract_address {
+        Some(address) => Some(
+            validate_and_parse_address(address).context("Invalid arbitrage contract address")?,
+        ),
+        None => None,
+    };
+
+    // Curve and Trident pools emit no Sync events the pool cache could follow
+    let scan_every_block =
+        pool_cache.is_none() || config.dex.curve.enabled || config.dex.trident.enabled;
+
     // Set polling interval for HTTP fallback (default to 2 seconds)
     let polling_interval =
         Duration::from_millis(config.ethereum.polling_interval_ms.unwrap_or(2000));
@@ -109,11 +158,16 @@ pub async fn start_listener(
         blockchain_client_http: blockchain_client,
         blockchain_client_ws: ws_client,
         alchemy_provider,
-        scanner,
+        scan_trigger,
+        scan_every_block,
         price_oracle,
-        event_handlers: RwLock::new(HashMap::new()),
-        is_running: RwLock::new(false),
-        task_handle: RwLock::new(None),
+        pool_cache,
+        head_monitor,
+        contract_address,
+        event_handlers: Arc::new(RwLock::new(HashMap::new())),
+        cancel,
+        running: RwLock::new(None),
+        task_handles: RwLock::new(Vec::new()),
         polling_interval,
     };
 
@@ -128,118 +182,81 @@ pub async fn start_listener(
 #[async_trait]
 impl BlockchainEventListener for BlockchainEventListenerImpl {
     async fn start(&self) -> Result<()> {
-        let mut is_running = self.is_running.write().await;
-        if *is_running {
+        let mut running = self.running.write().await;
+        if running.is_some() {
             return Ok(());
         }
 
-        *is_running = true;
+        // Every start gets its own token, so the listener can be started again after a stop
+        let cancel = self.cancel.child_token();
+        *running = Some(cancel.clone());
+        let worker = Arc::new(Self {
+            cancel,
+            ..self.clone()
+        });
+        let mut task_handles = self.task_handles.write().await;
 
         // Create a channel for new block notifications
         let (tx, mut rx) = mpsc::channel(100);
 
-        // Clone the Arc for the task
-        let self_clone = Arc::new(self.clone());
-
-        // Start a task to listen for new blocks
-        let task_handle = tokio::spawn(async move {
-            // Use Alchemy's enhanced WebSocket API if available
-            if let Some(alchemy_provider) = &self_clone.alchemy_provider {
-                if let Some(ws_provider) = alchemy_provider.ws() {
-                    info!("Using Alchemy WebSocket provider for block subscription");
-                    let mut stream = ws_provider.subscribe_blocks().await.unwrap();
-
-                    while let Some(block) = stream.next().await {
-                        let block_number = block.number.unwrap_or_default().as_u64();
-                        debug!("New block from Alchemy: {}", block_number);
-
-                        // Send the block number to the processing task
-                        if let Err(e) = tx.send(block_number).await {
-                            error!("Failed to send block number to processing task: {}", e);
-                            break;
-                        }
-                    }
-
-                    warn!("Alchemy block subscription stream ended");
-                    return;
-                }
-            }
-
-            // Check if we have a WebSocket client
-            if let Some(ws_client) = &self_clone.blockchain_client_ws {
-                info!("Using WebSocket provider for block subscription");
-                match ws_client.subscribe_blocks().await {
-                    Ok(mut stream) => {
-                        while let Some(block) = stream.next().await {
-                            let block_number = block.number.unwrap_or_default().as_u64();
-                            debug!("New block: {}", block_number);
-
-                            // Send the block number to the processing task
-                            if let Err(e) = tx.send(block_number).await {
-                                error!("Failed to send block number to processing task: {}", e);
-                                break;
-                            }
-                        }
-                        warn!("WebSocket block subscription stream ended");
-                    }
-                    Err(e) => {
-                        error!("Failed to subscribe to blocks via WebSocket: {}", e);
-                    }
-                }
-            } else {
-                // Fallback to HTTP polling
-                info!(
-                    "Using HTTP polling for block updates (interval: {} ms)",
-                    self_clone.polling_interval.as_millis()
-                );
-
-                let mut last_block_number = 0u64;
-
-                loop {
-                    match self_clone.blockchain_client_http.get_block_number().await {
-                        Ok(block_number) => {
-                            let block_number = block_number.as_u64();
-
-                            // Only process if it's a new block
-                            if block_number > last_block_number {
-                                debug!("New block from HTTP polling: {}", block_number);
-                                last_block_number = block_number;
-
-                                // Send the block number to the processing task
-                                if let Err(e) = tx.send(block_number).await {
+        // Compare chain heads across providers and feed blocks from a peer while failed over
+        if let Some(head_monitor) = self.head_monitor.clone().filter(|m| m.has_peers()) {
+            let tx = tx.clone();
+            let interval = self.polling_interval.min(Duration::from_secs(1));
+            let cancel = worker.cancel.clone();
+            task_handles.push(tokio::spawn(async move {
+                cancel
+                    .run_until_cancelled(async {
+                        loop {
+                            let (comparison, _) = head_monitor.check().await;
+
+                            if head_monitor.is_failed_over() && comparison.active_head > 0 {
+                                if let Err(e) = tx.send(comparison.active_head).await {
                                     error!("Failed to send block number to processing task: {}", e);
                                     break;
                                 }
                             }
+
+                            tokio::time::sleep(interval).await;
                         }
-                        Err(e) => {
-                            error!("Failed to get block number via HTTP: {}", e);
-                        }
+                    })
+                    .await;
+            }));
+        }
+
+        // Start a task to listen for new blocks
+        let source = worker.clone();
+        task_handles.push(tokio::spawn(async move {
+            source
+                .cancel
+                .run_until_cancelled(source.run_block_source(tx))
+                .await;
+        }));
+
+        // Start a task to process new blocks
+        task_handles.push(tokio::spawn(async move {
+            let mut last_processed = 0u64;
+            loop {
+                let block_number = match worker.cancel.run_until_cancelled(rx.recv()).await {
+                    Some(Some(block_number)) => block_number,
+                    Some(None) => {
+                        warn!("Block processing task ended");
+                        break;
                     }
+                    None => break,
+                };
 
-                    // Sleep for the polling interval
-                    tokio::time::sleep(self_clone.polling_interval).await;
+                // Several sources may report the same head
+                if block_number <= last_processed {
+                    continue;
                 }
-            }
-        });
+                last_processed = block_number;
 
-        // Clone the Arc for the processing task
-        let self_clone = Arc::new(self.clone());
-
-        // Start a task to process new blocks
-        let processing_handle = tokio::spawn(async move {
-            while let Some(block_number) = rx.recv().await {
-                if let Err(e) = self_clone.process_block(block_number).await {
+                if let Err(e) = worker.process_block(block_number).await {
                     error!("Failed to process block {}: {}", block_number, e);
                 }
             }
-
-            warn!("Block processing task ended");
-        });
-
-        // Store the task handle
-        let mut task_handle_lock = self.task_handle.write().await;
-        *task_handle_lock = Some(task_handle);
+        }));
 
         info!("Blockchain event listener started");
 
@@ -247,17 +264,17 @@ impl BlockchainEventListener for BlockchainEventListenerImpl {
     }
 
     async fn stop(&self) -> Result<()> {
-        let mut is_running = self.is_running.write().await;
-        if !*is_running {
+        let Some(cancel) = self.running.write().await.take() else {
             return Ok(());
-        }
-
-        *is_running = false;
+        };
 
-        // Abort the task
-        let mut task_handle = self.task_handle.write().await;
-        if let Some(handle) = task_handle.take() {
-            handle.abort();
+        // Cancel the tasks and wait for them to return from their current await
+        cancel.cancel();
+        let task_handles = std::mem::take(&mut *self.task_handles.write().await);
+        for handle in task_handles {
+            if let Err(e) = handle.await {
+                warn!("Blockchain event listener task failed: {}", e);
+            }
         }
 
         info!("Blockchain event listener stopped");
@@ -284,6 +301,9 @@ impl BlockchainEventListener for BlockchainEventListenerImpl {
     }
 
     async fn process_block(&self, block_number: u64) -> Result<()> {
+        // Decisions from here until the next block belong to this block's trace
+        trace::global().begin_block(block_number);
+
         // Get the block details
         let block = if let Some(alchemy_provider) = &self.alchemy_provider {
             alchemy_provider
@@ -291,9 +311,7 @@ impl BlockchainEventListener for BlockchainEventListenerImpl {
                 .get_block_with_txs(block_number)
                 .await?
         } else {
-            self.blockchain_client_http
-                .get_block_with_txs(block_number)
-                .await?
+            self.http_client().get_block_with_txs(block_number).await?
         };
 
         if let Some(block) = block {
@@ -303,21 +321,50 @@ impl BlockchainEventListener for BlockchainEventListenerImpl {
                 block.transactions.len()
             );
 
-            // Process any relevant events
-            // In a real implementation, we would process events from the block
+            // Apply pool reserve updates before scanning so quotes reflect this block. A block
+            // whose logs could not be read may have updated any pool.
+            let updated_pairs = match self.sync_pool_cache(block_number).await {
+                Ok(pairs) => Some(pairs),
+                Err(e) => {
+                    warn!(
+                        "Failed to sync pool cache for block {}: {}",
+                        block_number, e
+                    );
+                    None
+                }
+            };
 
-            // Update the price oracle
-            self.price_oracle.update_prices().await?;
+            // Hand the contract's events to their handlers
+            if let Err(e) = self.dispatch_contract_events(block_number).await {
+                warn!(
+                    "Failed to dispatch contract events of block {}: {}",
+                    block_number, e
+                );
+            }
 
-            // Scan for arbitrage opportunities
-            let opportunities = self.scanner.scan().await?;
+            // Update the price oracle, unless cancelled
+            let updated = self
+                .cancel
+                .run_until_cancelled(self.price_oracle.update_prices())
+                .await;
+            if updated.transpose()?.is_none() {
+                debug!("Price update of block {} cancelled", block_number);
+                return Ok(());
+            }
 
-            if !opportunities.is_empty() {
-                info!(
-                    "Found {} potential arbitrage opportunities in block {}",
-                    opportunities.len(),
-                    block_number
-                );
+            // Scan the pairs the block moved, or every pair when it may have moved any
+            match updated_pairs {
+                Some(pairs) if !self.scan_every_block => {
+                    if pairs.is_empty() {
+                        debug!(
+                            "Block {} updated no watched pool, skipping scan",
+                            block_number
+                        );
+                    } else {
+                        self.scan_trigger.block_updated(block_number, &pairs);
+                    }
+                }
+                _ => self.scan_trigger.sweep_block(block_number),
             }
         } else {
             warn!("Block {} not found", block_number);
@@ -327,6 +374,276 @@ impl BlockchainEventListener for BlockchainEventListenerImpl {
     }
 }
 
+impl BlockchainEventListenerImpl {
+    /// Feed new block numbers to the processing task until it stops
+    ///
+    /// Blocks come from the WebSocket subscription when it is up. When the subscription ends
+    /// or cannot be established, blocks are polled over HTTP while the connection is retried
+    /// with exponential backoff, and the subscription resumes once it is back.
+    async fn run_block_source(&self, tx: mpsc::Sender<u64>) {
+        let mut last_block = 0u64;
+
+        if !self.config.ethereum.use_websocket.unwrap_or(true) {
+            info!(
+                "Using HTTP polling for block updates (interval: {} ms)",
+                self.polling_interval.as_millis()
+            );
+            self.poll_blocks(&tx, None, &mut last_block).await;
+            return;
+        }
+
+        // Prefer Alchemy's WebSocket provider for the first subscription
+        let mut ws_client = self
+            .alchemy_provider
+            .as_ref()
+            .and_then(|provider| provider.ws())
+            .or_else(|| self.blockchain_client_ws.clone());
+        let max_backoff = Duration::from_secs(
+            self.config
+                .ethereum
+                .ws_reconnect_max_backoff_seconds
+                .unwrap_or(DEFAULT_RECONNECT_MAX_BACKOFF_SECONDS),
+        );
+        let mut backoff = RECONNECT_INITIAL_BACKOFF;
+
+        loop {
+            if let Some(client) = ws_client.take() {
+                match self.forward_ws_blocks(&client, &tx, &mut last_block).await {
+                    Ok(received) => {
+                        // Only a subscription that delivered blocks resets the backoff
+                        if received {
+                            backoff = RECONNECT_INITIAL_BACKOFF;
+                        }
+                    }
+                    Err(e) => {
+                        error!("Failed to send block number to processing task: {}", e);
+                        return;
+                    }
+                }
+
+                metrics::global().increment_counter("ws_disconnects", 1);
+                metrics::global().set_gauge("ws_connected", 0.0);
+                warn!(
+                    "WebSocket block subscription lost, polling over HTTP and reconnecting in {}s",
+                    backoff.as_secs()
+                );
+            }
+
+            // Poll over HTTP until the next reconnection attempt
+            if !self
+                .poll_blocks(&tx, Some(Instant::now() + backoff), &mut last_block)
+                .await
+            {
+                return;
+            }
+
+            match crate::blockchain::create_ws_client(&self.config).await {
+                Ok(client) => {
+                    info!("WebSocket connection re-established");
+                    metrics::global().increment_counter("ws_reconnects", 1);
+                    ws_client = Some(client);
+                }
+                Err(e) => {
+                    backoff = (backoff * 2).min(max_backoff);
+                    warn!(
+                        "Failed to reconnect WebSocket: {}; retrying in {}s",
+                        e,
+                        backoff.as_secs()
+                    );
+                }
+            }
+        }
+    }
+
+    /// Forward blocks from a WebSocket subscription until it ends
+    ///
+    /// Returns whether any block was received, or an error once the processing task is gone.
+    async fn forward_ws_blocks(
+        &self,
+        ws_client: &Provider<Ws>,
+        tx: &mpsc::Sender<u64>,
+        last_block: &mut u64,
+    ) -> Result<bool> {
+        let mut stream = match ws_client.subscribe_blocks().await {
+            Ok(stream) => stream,
+            Err(e) => {
+                error!("Failed to subscribe to blocks via WebSocket: {}", e);
+                return Ok(false);
+            }
+        };
+        info!("Using WebSocket provider for block subscription");
+        metrics::global().set_gauge("ws_connected", 1.0);
+
+        let mut received = false;
+        while let Some(block) = stream.next().await {
+            let block_number = block.number.unwrap_or_default().as_u64();
+            debug!("New block: {}", block_number);
+            received = true;
+
+            if let Some(head_monitor) = &self.head_monitor {
+                head_monitor.record_ws_head(block_number);
+            }
+
+            *last_block = (*last_block).max(block_number);
+            tx.send(block_number).await?;
+        }
+
+        Ok(received)
+    }
+
+    /// Poll the block number over HTTP until `until` (forever if `None`), sending new blocks
+    ///
+    /// Returns false once the processing task is gone.
+    async fn poll_blocks(
+        &self,
+        tx: &mpsc::Sender<u64>,
+        until: Option<Instant>,
+        last_block: &mut u64,
+    ) -> bool {
+        while until.is_none_or(|until| Instant::now() < until) {
+            match self.http_client().get_block_number().await {
+                Ok(block_number) => {
+                    let block_number = block_number.as_u64();
+
+                    // Only process if it's a new block
+                    if block_number > *last_block {
+                        debug!("New block from HTTP polling: {}", block_number);
+                        *last_block = block_number;
+
+                        // Send the block number to the processing task
+                        if let Err(e) = tx.send(block_number).await {
+                            error!("Failed to send block number to processing task: {}", e);
+                            return false;
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to get block number via HTTP: {}", e);
+                }
+            }
+
+            // Sleep for the polling interval, but no later than the deadline
+            let sleep = match until {
+                Some(until) => self
+                    .polling_interval
+                    .min(until.saturating_duration_since(Instant::now())),
+                None => self.polling_interval,
+            };
+            tokio::time::sleep(sleep).await;
+        }
+
+        true
+    }
+
+    /// Get the HTTP client of the active block data source
+    fn http_client(&self) -> Arc<RpcClient> {
+        match &self.head_monitor {
+            Some(head_monitor) => head_monitor.active_client(),
+            None => self.blockchain_client_http.clone(),
+        }
+    }
+
+    /// Call the registered handlers with the ArbitrageExecutor logs of a block
+    async fn dispatch_contract_events(&self, block_number: u64) -> Result<()> {
+        let Some(contract_address) = self.contract_address else {
+            return Ok(());
+        };
+        let event_handlers = self.event_handlers.read().await;
+        if event_handlers.is_empty() {
+            return Ok(());
+        }
+
+        let filter = Filter::new()
+            .address(contract_address)
+            .topic0(ExecutorEvent::topics())
+            .from_block(block_number)
+            .to_block(block_number);
+        let logs = self
+            .http_client()
+            .get_logs(&filter)
+            .await
+            .context("Failed to fetch contract logs")?;
+
+        for log in logs {
+            let Some(name) = ExecutorEvent::name_of(&log) else {
+                continue;
+            };
+            debug!(
+                "{} emitted by tx {:?} in block {}",
+                name,
+                log.transaction_hash.unwrap_or_default(),
+                block_number
+            );
+            for handler in event_handlers.get(name).into_iter().flatten() {
+                if let Err(e) = handler(log.clone()) {
+                    warn!("{} handler failed: {}", name, e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Apply the Sync and Swap logs of the watched pools in a block to the pool cache
+    ///
+    /// Returns the token pairs of the pools the logs updated.
+    async fn sync_pool_cache(&self, block_number: u64) -> Result<Vec<(Address, Address)>> {
+        let pool_cache = match &self.pool_cache {
+            Some(pool_cache) => pool_cache,
+            None => return Ok(Vec::new()),
+        };
+
+        let pools = pool_cache.watched_pools().await;
+        if pools.is_empty() {
+            pool_cache.mark_synced(block_number);
+            return Ok(Vec::new());
+        }
+
+        let filter = Filter::new()
+            .address(pools)
+            .topic0(vec![
+                PoolStateCache::sync_topic(),
+                PoolStateCache::swap_topic(),
+            ])
+            .from_block(block_number)
+            .to_block(block_number);
+
+        let logs = self
+            .http_client()
+            .get_logs(&filter)
+            .await
+            .context("Failed to fetch pool logs")?;
+
+        let mut updated = 0;
+        let mut pairs = HashSet::new();
+        for log in &logs {
+            if pool_cache.apply_log(log).await {
+                updated += 1;
+                if let Some(state) = pool_cache.get(log.address).await {
+                    pairs.insert((state.token0, state.token1));
+                }
+                trace::record(TraceStage::PoolUpdate, || {
+                    format!(
+                        "{} updated by tx {:?}",
+                        tokens::pool_label(log.address),
+                        log.transaction_hash.unwrap_or_default()
+                    )
+                });
+            }
+        }
+
+        if updated > 0 {
+            debug!(
+                "Applied {} pool events from block {}",
+                updated, block_number
+            );
+        }
+        pool_cache.mark_synced(block_number);
+
+        Ok(pairs.into_iter().collect())
+    }
+}
+
 impl Clone for BlockchainEventListenerImpl {
     fn clone(&self) -> Self {
         Self {
@@ -334,11 +651,16 @@ impl Clone for BlockchainEventListenerImpl {
             blockchain_client_http: self.blockchain_client_http.clone(),
             blockchain_client_ws: self.blockchain_client_ws.clone(),
             alchemy_provider: self.alchemy_provider.clone(),
-            scanner: self.scanner.clone(),
+            scan_trigger: self.scan_trigger.clone(),
+            scan_every_block: self.scan_every_block,
             price_oracle: self.price_oracle.clone(),
-            event_handlers: RwLock::new(HashMap::new()),
-            is_running: RwLock::new(false),
-            task_handle: RwLock::new(None),
+            pool_cache: self.pool_cache.clone(),
+            head_monitor: self.head_monitor.clone(),
+            contract_address: self.contract_address,
+            event_handlers: self.event_handlers.clone(),
+            cancel: self.cancel.clone(),
+            running: RwLock::new(None),
+            task_handles: RwLock::new(Vec::new()),
             polling_interval: self.polling_interval,
         }
     }
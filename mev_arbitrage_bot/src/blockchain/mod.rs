@@ -2,25 +2,46 @@
 //!
 //! This module is responsible for interacting with the Ethereum blockchain and listening for events.
 
+mod chain;
+mod failover;
+mod head_monitor;
+mod http;
 mod listener;
+mod retry;
+mod transport;
 
+pub use chain::{Chain, L1FeeModel};
+pub use failover::FailoverTransport;
+pub use head_monitor::{create_head_monitor, HeadMonitor};
+pub use http::{http_client, http_provider, rpc_transport};
 pub use listener::{start_listener, BlockchainEventListener};
+pub use retry::{MethodRetryPolicy, RpcRetryConfig};
+pub use transport::{Transport, TransportError};
 
 use anyhow::{Context, Result};
-use ethers::providers::{Http, Middleware, Provider, Ws};
-use ethers::types::{Address, BlockNumber, Filter, H256, U64};
+use ethers::providers::{Http, JsonRpcClient, Middleware, Provider, SubscriptionStream, Ws};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Transaction, TransactionRequest, U256, U64};
 use log::{debug, error, info, warn};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::config::Config;
+use crate::assets::ContractAbi;
+use crate::config::{Config, ConfigReport, FlashLoanProvider};
+use crate::ratelimit::{self, ApiEndpoint};
 use crate::utils::validate_and_parse_address;
 
+/// Client every component reads the chain and sends transactions through
+///
+/// Its endpoints can be served by any [`Transport`], so components take this one type whether
+/// they talk to HTTP endpoints with failover, a WebSocket connection or a mock provider.
+pub type RpcClient = Provider<FailoverTransport>;
+
 /// Alchemy-specific provider with enhanced capabilities
 pub struct AlchemyProvider {
     /// The underlying HTTP provider
-    pub http_provider: Arc<Provider<Http>>,
+    pub http_provider: Arc<RpcClient>,
 
     /// The underlying WebSocket provider
     pub ws_provider: Option<Arc<Provider<Ws>>>,
@@ -35,7 +56,7 @@ pub struct AlchemyProvider {
 impl AlchemyProvider {
     /// Create a new Alchemy provider
     pub fn new(
-        http_provider: Arc<Provider<Http>>,
+        http_provider: Arc<RpcClient>,
         ws_provider: Option<Arc<Provider<Ws>>>,
         api_key: Option<String>,
         chain_id: u64,
@@ -49,7 +70,7 @@ impl AlchemyProvider {
     }
 
     /// Get the HTTP provider
-    pub fn http(&self) -> Arc<Provider<Http>> {
+    pub fn http(&self) -> Arc<RpcClient> {
         self.http_provider.clone()
     }
 
@@ -58,48 +79,28 @@ impl AlchemyProvider {
         self.ws_provider.clone()
     }
 
-    /// Get the gas price with Alchemy's enhanced gas API
-    pub async fn get_gas_price(&self) -> Result<(u64, u64, u64)> {
-        // If we have an Alchemy API key, use the enhanced gas API
-        if let Some(api_key) = &self.api_key {
-            let url = format!("https://eth-mainnet.g.alchemy.com/v2/{}/gas-price", api_key);
-
-            let client = reqwest::Client::new();
-            let response = client.get(&url).send().await?;
-
-            if response.status().is_success() {
-                let gas_data: serde_json::Value = response.json().await?;
-
-                // Extract the gas prices
-                let safe_gas_price = gas_data["result"]["safeLow"]["maxFee"]
-                    .as_u64()
-                    .unwrap_or(0);
-                let average_gas_price = gas_data["result"]["standard"]["maxFee"]
-                    .as_u64()
-                    .unwrap_or(0);
-                let fast_gas_price = gas_data["result"]["fast"]["maxFee"].as_u64().unwrap_or(0);
-
-                return Ok((safe_gas_price, average_gas_price, fast_gas_price));
-            }
-        }
-
-        // Fallback to standard gas price
-        let gas_price = self.http_provider.get_gas_price().await?;
-        let gas_price_gwei = gas_price.as_u64() / 1_000_000_000;
-
-        Ok((gas_price_gwei, gas_price_gwei, gas_price_gwei))
-    }
-
-    /// Subscribe to pending transactions with Alchemy's enhanced API
-    pub async fn subscribe_pending_transactions(&self) -> Result<H256> {
-        if let Some(ws_provider) = &self.ws_provider {
-            // Just return a dummy hash for now
-            // In a real implementation, we would handle the subscription properly
-            Ok(H256::zero())
-        } else {
-            // Return a dummy hash if WebSocket provider is not available
-            Ok(H256::zero())
-        }
+    /// Subscribe to pending transactions sent to any of `to_addresses` with Alchemy's
+    /// `alchemy_pendingTransactions` subscription
+    pub async fn subscribe_pending_transactions(
+        &self,
+        to_addresses: &[Address],
+    ) -> Result<SubscriptionStream<'_, Ws, Transaction>> {
+        let ws_provider = self
+            .ws_provider
+            .as_ref()
+            .context("Pending transactions need a WebSocket provider")?;
+
+        let params = (
+            "alchemy_pendingTransactions",
+            serde_json::json!({
+                "toAddress": to_addresses,
+                "hashesOnly": false,
+            }),
+        );
+        ws_provider
+            .subscribe(params)
+            .await
+            .context("Failed to subscribe to pending transactions")
     }
 
     /// Get token balances for an address using Alchemy's getTokenBalances API
@@ -129,6 +130,7 @@ impl AlchemyProvider {
                 "params": params
             });
 
+            ratelimit::global().acquire(ApiEndpoint::Alchemy).await;
             let response = client.post(&url).json(&request).send().await?;
 
             if response.status().is_success() {
@@ -165,17 +167,15 @@ impl AlchemyProvider {
         // Fallback to standard token balance queries
         let mut balances = HashMap::new();
 
+        let erc20 = ContractAbi::Erc20.contract();
         for token in tokens {
-            // Create an ERC20 contract instance
-            let abi_json = include_str!("../contract/abi/ERC20.json");
-            let abi: ethers::abi::Abi = serde_json::from_str(abi_json)?;
-            let contract = ethers::contract::Contract::new(token, abi, self.http_provider.clone());
-
             // Call the balanceOf function
-            let balance: ethers::types::U256 = contract
-                .method::<_, ethers::types::U256>("balanceOf", address)?
-                .call()
-                .await?;
+            let call: TypedTransaction = TransactionRequest::new()
+                .to(token)
+                .data(erc20.encode("balanceOf", address)?)
+                .into();
+            let output = self.http_provider.call(&call, None).await?;
+            let balance: ethers::types::U256 = erc20.decode_output("balanceOf", output)?;
 
             balances.insert(token, balance);
         }
@@ -184,11 +184,67 @@ impl AlchemyProvider {
     }
 }
 
+/// Seconds between health checks of the RPC endpoints when not configured
+const DEFAULT_RPC_HEALTH_CHECK_INTERVAL_SECONDS: u64 = 15;
+
+/// Refuse a fallback or read endpoint on another chain than configured
+///
+/// An endpoint that does not answer is kept, as it may recover; it is benched on its first
+/// failed request.
+async fn verify_endpoint(name: &str, transport: &Http, chain_id: u64) -> Result<()> {
+    let endpoint_chain_id: Result<U256, _> = transport.request("eth_chainId", ()).await;
+    match endpoint_chain_id {
+        Ok(endpoint_chain_id) if endpoint_chain_id != chain_id.into() => anyhow::bail!(
+            "RPC endpoint {} is on chain {}, expected {}",
+            name,
+            endpoint_chain_id,
+            chain_id
+        ),
+        Ok(_) => Ok(()),
+        Err(e) => {
+            warn!("RPC endpoint {} is unreachable: {}", name, e);
+            Ok(())
+        }
+    }
+}
+
 /// Create a new blockchain client
-pub async fn create_client(config: &Arc<Config>) -> Result<Arc<Provider<Http>>> {
-    // Create the HTTP provider
-    let provider = Provider::<Http>::try_from(&config.ethereum.rpc_url)
+pub async fn create_client(config: &Arc<Config>) -> Result<Arc<RpcClient>> {
+    let ethereum = &config.ethereum;
+
+    // Create the transports of the primary, fallback and read endpoints
+    let primary = rpc_transport(&ethereum.rpc_url, &ethereum.rpc_http)
         .context("Failed to create HTTP provider")?;
+    let mut fallbacks = Vec::with_capacity(ethereum.fallback_rpc_urls.len());
+    for (index, url) in ethereum.fallback_rpc_urls.iter().enumerate() {
+        let transport = rpc_transport(url, &ethereum.fallback_rpc_http)
+            .with_context(|| format!("Failed to create fallback RPC endpoint {}", index))?;
+        verify_endpoint(
+            &format!("fallback-{}", index),
+            &transport,
+            ethereum.chain_id,
+        )
+        .await?;
+        fallbacks.push(transport);
+    }
+    let read = match &ethereum.read_rpc_url {
+        Some(url) => {
+            let transport = rpc_transport(url, &ethereum.fallback_rpc_http)
+                .context("Failed to create read RPC endpoint")?;
+            verify_endpoint("read", &transport, ethereum.chain_id).await?;
+            Some(transport)
+        }
+        None => None,
+    };
+
+    let transport = FailoverTransport::new(primary, fallbacks, read)
+        .with_retries(config.section::<RpcRetryConfig>()?);
+    transport.spawn_health_checks(Duration::from_secs(
+        ethereum
+            .rpc_health_check_interval_seconds
+            .unwrap_or(DEFAULT_RPC_HEALTH_CHECK_INTERVAL_SECONDS),
+    ));
+    let provider = Provider::new(transport);
 
     // Set the polling interval
     let provider = provider.interval(Duration::from_millis(2000));
@@ -199,7 +255,24 @@ pub async fn create_client(config: &Arc<Config>) -> Result<Arc<Provider<Http>>>
         .await
         .context("Failed to connect to Ethereum node")?;
 
-    info!("Connected to Ethereum node at block {}", block_number);
+    // Refuse a node on another chain than configured, which would sign for the wrong chain
+    let chain_id = provider
+        .get_chainid()
+        .await
+        .context("Failed to get the node's chain ID")?;
+    if chain_id != config.ethereum.chain_id.into() {
+        anyhow::bail!(
+            "Node is on chain {}, expected {}",
+            chain_id,
+            config.ethereum.chain_id
+        );
+    }
+
+    info!(
+        "Connected to {} node at block {}",
+        Chain::from_id(config.ethereum.chain_id),
+        block_number
+    );
 
     // Check if we're using Alchemy
     if config.ethereum.rpc_url.contains("alchemyapi.io") {
@@ -209,6 +282,97 @@ pub async fn create_client(config: &Arc<Config>) -> Result<Arc<Provider<Http>>>
     Ok(Arc::new(provider))
 }
 
+/// Check that the configured DEX routers and factories and flash loan pools have code on the
+/// client's chain
+///
+/// An address copied from another chain's deployment would otherwise only fail at the first
+/// quote or trade. Every address without code is reported at once.
+pub async fn verify_deployments(config: &Config, client: &RpcClient) -> Result<()> {
+    let chain = Chain::from_id(config.ethereum.chain_id);
+    let mut report = ConfigReport::new();
+    for (field, address) in deployment_addresses(config) {
+        // Malformed addresses are reported when the configuration is loaded
+        let Ok(address) = validate_and_parse_address(address) else {
+            continue;
+        };
+        match client.get_code(address, None).await {
+            Ok(code) if code.is_empty() => {
+                report.problem(format!("{} {:?} has no code on {}", field, address, chain))
+            }
+            Ok(_) => {}
+            Err(e) => report.problem(format!(
+                "Failed to get the code of {} {:?}: {}",
+                field, address, e
+            )),
+        }
+    }
+
+    report.finish(&format!("Configuration for {}", chain))
+}
+
+/// Get the contracts the configuration trades through, by the field configuring them
+fn deployment_addresses(config: &Config) -> Vec<(String, &str)> {
+    let mut addresses = Vec::new();
+    for (name, dex) in [
+        ("uniswap", &config.dex.uniswap),
+        ("sushiswap", &config.dex.sushiswap),
+        ("curve", &config.dex.curve),
+    ] {
+        if dex.enabled {
+            addresses.push((
+                format!("dex.{}.factory_address", name),
+                dex.factory_address.as_str(),
+            ));
+            addresses.push((
+                format!("dex.{}.router_address", name),
+                dex.router_address.as_str(),
+            ));
+        }
+    }
+
+    let trident = &config.dex.trident;
+    if trident.enabled {
+        addresses.push((
+            "dex.trident.bento_box_address".to_string(),
+            trident.bento_box_address.as_str(),
+        ));
+        for (field, factory) in [
+            (
+                "dex.trident.constant_product_factory",
+                &trident.constant_product_factory,
+            ),
+            ("dex.trident.stable_factory", &trident.stable_factory),
+        ] {
+            if !factory.is_empty() {
+                addresses.push((field.to_string(), factory.as_str()));
+            }
+        }
+    }
+
+    let flash_loan = &config.flash_loan;
+    if flash_loan.providers.contains(&FlashLoanProvider::Aave) {
+        addresses.push((
+            "flash_loan.aave_lending_pool".to_string(),
+            flash_loan.aave_lending_pool.as_str(),
+        ));
+    }
+    for (field, address) in [
+        ("flash_loan.balancer_vault", &flash_loan.balancer_vault),
+        (
+            "flash_loan.uniswap_v3_factory",
+            &flash_loan.uniswap_v3_factory,
+        ),
+        ("flash_loan.morpho_blue", &flash_loan.morpho_blue),
+        ("flash_loan.spark_pool", &flash_loan.spark_pool),
+    ] {
+        if let Some(address) = address {
+            addresses.push((field.to_string(), address.as_str()));
+        }
+    }
+
+    addresses
+}
+
 /// Create a new websocket client
 pub async fn create_ws_client(config: &Arc<Config>) -> Result<Arc<Provider<Ws>>> {
     // Check if WebSocket is disabled in the config
@@ -304,102 +468,6 @@ pub async fn create_alchemy_provider(config: &Arc<Config>) -> Result<Arc<Alchemy
     Ok(Arc::new(provider))
 }
 
-/// Get the contract ABI from a file or embedded resource
-pub fn get_contract_abi(name: &str) -> Result<ethers::abi::Abi> {
-    // This is a placeholder implementation
-    // In a real implementation, we would load the ABI from a file or embedded resource
-
-    // For now, just return a minimal ABI
-    let json = match name {
-        "uniswap_v2_factory" => {
-            r#"[
-            {
-                "anonymous": false,
-                "inputs": [
-                    {
-                        "indexed": true,
-                        "internalType": "address",
-                        "name": "token0",
-                        "type": "address"
-                    },
-                    {
-                        "indexed": true,
-                        "internalType": "address",
-                        "name": "token1",
-                        "type": "address"
-                    },
-                    {
-                        "indexed": false,
-                        "internalType": "address",
-                        "name": "pair",
-                        "type": "address"
-                    },
-                    {
-                        "indexed": false,
-                        "internalType": "uint256",
-                        "name": "",
-                        "type": "uint256"
-                    }
-                ],
-                "name": "PairCreated",
-                "type": "event"
-            }
-        ]"#
-        }
-        "uniswap_v2_pair" => {
-            r#"[
-            {
-                "anonymous": false,
-                "inputs": [
-                    {
-                        "indexed": true,
-                        "internalType": "address",
-                        "name": "sender",
-                        "type": "address"
-                    },
-                    {
-                        "indexed": false,
-                        "internalType": "uint256",
-                        "name": "amount0In",
-                        "type": "uint256"
-                    },
-                    {
-                        "indexed": false,
-                        "internalType": "uint256",
-                        "name": "amount1In",
-                        "type": "uint256"
-                    },
-                    {
-                        "indexed": false,
-                        "internalType": "uint256",
-                        "name": "amount0Out",
-                        "type": "uint256"
-                    },
-                    {
-                        "indexed": false,
-                        "internalType": "uint256",
-                        "name": "amount1Out",
-                        "type": "uint256"
-                    },
-                    {
-                        "indexed": true,
-                        "internalType": "address",
-                        "name": "to",
-                        "type": "address"
-                    }
-                ],
-                "name": "Swap",
-                "type": "event"
-            }
-        ]"#
-        }
-        _ => return Err(anyhow::anyhow!("Unknown contract ABI: {}", name)),
-    };
-
-    let abi = serde_json::from_str(json)?;
-    Ok(abi)
-}
-
 /// Parse an Ethereum address
 pub fn parse_address(address: &str) -> Result<Address> {
     validate_and_parse_address(address)
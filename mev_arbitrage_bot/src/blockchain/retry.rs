@@ -0,0 +1,119 @@
+//! RPC Retry Module
+//!
+//! This module is responsible for the retry policies of RPC requests. A request that fails on
+//! every endpoint of its transport for a transient reason (a rate limit, a connection reset or
+//! an endpoint still behind the requested block) is sent again after an exponential backoff
+//! with jitter. Failures of the request itself, such as a reverted call, are never retried.
+//! Transactions are not retried unless configured, since a submission that reached the node
+//! before its connection dropped would be answered as already known.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use super::failover::SUBMIT_METHODS;
+use crate::config::ConfigSection;
+use crate::utils::Backoff;
+
+/// Retry policy of a method, overriding the section's defaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MethodRetryPolicy {
+    /// Retries after the first attempt
+    pub max_retries: Option<u32>,
+
+    /// Delay before the first retry (in milliseconds)
+    pub initial_backoff_ms: Option<u64>,
+}
+
+/// RPC retry configuration (`[rpc_retry]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RpcRetryConfig {
+    /// Whether transient RPC failures are retried
+    pub enabled: bool,
+
+    /// Retries after the first attempt
+    pub max_retries: u32,
+
+    /// Delay before the first retry (in milliseconds), doubled before each next one
+    pub initial_backoff_ms: u64,
+
+    /// Longest delay between two attempts (in milliseconds)
+    pub max_backoff_ms: u64,
+
+    /// Share of each delay added at random (0 to 1)
+    pub jitter: f64,
+
+    /// Policies of specific methods (e.g. `eth_call`)
+    pub methods: BTreeMap<String, MethodRetryPolicy>,
+}
+
+impl Default for RpcRetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_retries: 3,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 2000,
+            jitter: 0.5,
+            methods: BTreeMap::new(),
+        }
+    }
+}
+
+impl ConfigSection for RpcRetryConfig {
+    const NAME: &'static str = "rpc_retry";
+
+    fn validate(&self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.jitter) {
+            anyhow::bail!("RPC retry jitter must be between 0 and 1");
+        }
+        if self.initial_backoff_ms > self.max_backoff_ms {
+            anyhow::bail!("RPC retry initial_backoff_ms must not exceed max_backoff_ms");
+        }
+        for (method, policy) in &self.methods {
+            if policy
+                .initial_backoff_ms
+                .is_some_and(|initial_backoff_ms| initial_backoff_ms > self.max_backoff_ms)
+            {
+                anyhow::bail!(
+                    "RPC retry initial_backoff_ms of {} must not exceed max_backoff_ms",
+                    method
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl RpcRetryConfig {
+    /// Get the backoff of a method's retries
+    pub fn backoff(&self, method: &str) -> Backoff {
+        let policy = self.methods.get(method);
+        let default_retries = if SUBMIT_METHODS.contains(&method) {
+            0
+        } else {
+            self.max_retries
+        };
+
+        Backoff {
+            max_retries: if self.enabled {
+                policy
+                    .and_then(|policy| policy.max_retries)
+                    .unwrap_or(default_retries)
+            } else {
+                0
+            },
+            initial: Duration::from_millis(
+                policy
+                    .and_then(|policy| policy.initial_backoff_ms)
+                    .unwrap_or(self.initial_backoff_ms),
+            ),
+            max: Duration::from_millis(self.max_backoff_ms),
+            jitter: self.jitter,
+        }
+    }
+}
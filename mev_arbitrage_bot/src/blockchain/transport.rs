@@ -0,0 +1,94 @@
+//! RPC Transport Module
+//!
+//! This module is responsible for the transports an RPC client's endpoints can be served by.
+//! Every JSON-RPC client ethers provides (HTTP, WebSocket, IPC, its retrying client and its
+//! mock provider) is a [`Transport`], so any of them can sit behind the failover transport and
+//! reach every component through the same client type. Their errors are reduced to what
+//! failover decides on: a JSON-RPC error response, a malformed answer, or an endpoint that could
+//! not be reached.
+
+use async_trait::async_trait;
+use ethers::providers::{JsonRpcClient, JsonRpcError, ProviderError, RpcError};
+use serde_json::Value;
+use std::fmt::Debug;
+use thiserror::Error;
+
+/// Error of a request sent through a transport
+#[derive(Debug, Error)]
+pub enum TransportError {
+    /// The endpoint answered with a JSON-RPC error
+    #[error(transparent)]
+    JsonRpc(JsonRpcError),
+
+    /// The request or its answer could not be (de)serialized
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+
+    /// The endpoint could not be reached or failed to answer
+    #[error("{0}")]
+    Connection(String),
+}
+
+impl RpcError for TransportError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            Self::JsonRpc(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            Self::Serde(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<TransportError> for ProviderError {
+    fn from(error: TransportError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(error))
+    }
+}
+
+/// A JSON-RPC transport an endpoint is served by
+///
+/// Unlike `JsonRpcClient`, transports can be boxed, so endpoints of different kinds can back
+/// one client.
+#[async_trait]
+pub trait Transport: Debug + Send + Sync {
+    /// Send a request with JSON parameters, or none, returning the JSON result
+    async fn request_json(
+        &self,
+        method: &str,
+        params: Option<Value>,
+    ) -> Result<Value, TransportError>;
+}
+
+#[async_trait]
+impl<C> Transport for C
+where
+    C: JsonRpcClient + 'static,
+{
+    async fn request_json(
+        &self,
+        method: &str,
+        params: Option<Value>,
+    ) -> Result<Value, TransportError> {
+        // Requests without parameters leave them out, rather than sending null
+        let result = match params {
+            Some(params) => JsonRpcClient::request(self, method, params).await,
+            None => JsonRpcClient::request(self, method, ()).await,
+        };
+
+        result.map_err(|error: C::Error| {
+            if let Some(response) = error.as_error_response() {
+                TransportError::JsonRpc(response.clone())
+            } else if error.is_serde_error() {
+                TransportError::Connection(format!("malformed response: {}", error))
+            } else {
+                TransportError::Connection(error.to_string())
+            }
+        })
+    }
+}
@@ -0,0 +1,1359 @@
+//! Bot Module
+//!
+//! This module is responsible for wiring the components together from the configuration,
+//! running the scan, evaluate and execute loop, and following submitted trades to their
+//! outcome, so the binary only has to load the configuration and pick a command.
+//! The loop runs whenever the chain's scan trigger asks for it: on the pairs a block or a
+//! pending swap moved, and over every pair at each periodic sweep.
+
+use anyhow::{Context, Result};
+use ethers::providers::Middleware;
+use ethers::types::{Address, H256};
+use futures::future::join_all;
+use log::{debug, error, info, warn};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::signal;
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
+
+use crate::aggregator::{AggregatorConfig, QuoteChecker};
+use crate::alerts::{self, AlertManager};
+use crate::allowances::{AllowanceManager, AllowancesConfig};
+use crate::api::{ApiConfig, ChainHandles};
+use crate::blockchain::{BlockchainEventListener, Chain, RpcClient};
+use crate::competition::{CompetitionConfig, RaceObserver};
+use crate::config::{self, AlertSeverity, Config};
+use crate::contract::{ContractManager, ExecutorEvent};
+use crate::coordination::{Claim, CoordinationConfig, Coordinator};
+use crate::feed::{ExecutionUpdate, FeedConfig};
+use crate::latency::{self, Latency, Stage};
+use crate::mempool::MempoolConfig;
+use crate::pnl::PnlAccountant;
+use crate::price::{PriceOracle, PriceOracleInterface};
+use crate::runtime::{BotSupervisor, ChainSubsystems, InFlight};
+use crate::scanner::{
+    ArbitrageOpportunity, OpportunityQueue, OpportunityScanner, PairTiers, ScanRequest, ScanTrigger,
+};
+use crate::storage::Storage;
+use crate::strategy::{route_key, RouteGuard, StrategyEngine};
+use crate::trace::TraceStage;
+use crate::transaction::{
+    ArbitrageTransaction, BundleComposer, ProfitAttribution, SettlementCosts, TransactionBuilder,
+    TransactionExecutor, DEFAULT_MIN_CONFIRMATIONS,
+};
+use crate::treasury::{Treasury, TreasuryConfig};
+use crate::utils::{measure_time_async, validate_and_parse_address};
+use crate::{
+    aggregator, allowances, api, blockchain, competition, contract, coordination, dex, feed,
+    flash_loan, gas, mempool, metrics, mev_share, pnl, price, risk, runtime, scanner, simulation,
+    storage, strategy, tokens, trace, transaction, treasury,
+};
+
+/// Run the bot on every configured chain until a shutdown signal is received
+///
+/// Each chain gets its own components and scan, evaluate and execute loop, owned by one
+/// supervisor that shuts them down in order. The bot fails if the primary chain cannot be
+/// started; an additional chain that fails to start is alerted on and left out.
+pub async fn run(config: Arc<Config>) -> Result<()> {
+    // Arm block traces requested in the configuration
+    trace::global().configure(&config);
+
+    // Initialize alerting
+    let alert_manager = alerts::create_manager(&config)?;
+
+    // Apply the risk limits shared by every chain
+    risk::global().configure(&config, alert_manager.clone())?;
+
+    let mut supervisor = BotSupervisor::new(&config, alert_manager.clone());
+    let mut primary = None;
+    for (index, chain_config) in config::chain_configs(&config).into_iter().enumerate() {
+        let chain = Chain::from_id(chain_config.ethereum.chain_id);
+        match run_chain(chain_config.clone(), alert_manager.clone(), &mut supervisor).await {
+            Ok(handles) if index == 0 => primary = Some((chain_config, handles)),
+            Ok(_) => {}
+            Err(e) if index == 0 => {
+                supervisor.shutdown().await;
+                return Err(e);
+            }
+            Err(e) => alert_manager.raise(
+                AlertSeverity::Critical,
+                "chain",
+                format!("Failed to start on {}, skipping it: {:#}", chain, e),
+            ),
+        }
+    }
+
+    // Serve the admin API of the primary chain once every chain is running
+    let api_config: ApiConfig = config.section()?;
+    if let (true, Some((primary_config, handles))) = (api_config.enabled, primary) {
+        let server = match api::create_server(
+            &primary_config,
+            &api_config,
+            handles,
+            supervisor.health_monitor(),
+            alert_manager.clone(),
+        )
+        .await
+        {
+            Ok(server) => server,
+            Err(e) => {
+                supervisor.shutdown().await;
+                return Err(e);
+            }
+        };
+        supervisor.supervise("admin API", move |shutdown| server.clone().run(shutdown));
+    }
+
+    // Stream opportunities and trade outcomes of every chain to external consumers
+    let feed_config: FeedConfig = config.section()?;
+    if feed_config.enabled {
+        let server = match feed::create_server(&feed_config).await {
+            Ok(server) => server,
+            Err(e) => {
+                supervisor.shutdown().await;
+                return Err(e);
+            }
+        };
+        supervisor.supervise("opportunity feed", move |shutdown| {
+            server.clone().run(shutdown)
+        });
+    }
+
+    // Wait for Ctrl+C signal
+    match signal::ctrl_c().await {
+        Ok(()) => info!("Shutdown signal received, stopping bot..."),
+        Err(e) => error!("Failed to listen for shutdown signal, stopping bot: {}", e),
+    }
+    supervisor.shutdown().await;
+
+    Ok(())
+}
+
+/// Start the bot on one chain
+///
+/// Initializes every component from the chain's configuration, then hands the supervisor the
+/// loop that scans, evaluates and executes its opportunities, and the subsystems to stop.
+async fn run_chain(
+    config: Arc<Config>,
+    alert_manager: Arc<AlertManager>,
+    supervisor: &mut BotSupervisor,
+) -> Result<ChainHandles> {
+    let chain = Chain::from_id(config.ethereum.chain_id);
+    info!(
+        "Starting on {} (chain ID {})",
+        chain, config.ethereum.chain_id
+    );
+
+    // Name the configured tokens in logs and records
+    tokens::global().register_config_tokens(&config);
+
+    // Open the trade history storage
+    let storage = if config.storage.enabled {
+        Some(storage::create_storage(&config).await?)
+    } else {
+        info!("Trade history storage disabled");
+        None
+    };
+
+    // Trace behavior changes back to configuration edits
+    match &storage {
+        Some(storage) => report_config_changes(&config, storage, &alert_manager),
+        None => info!("Configuration hash: {}", config::config_hash(&config)),
+    }
+
+    // Count the day's losses from before a restart against the daily loss limit
+    if let Some(storage) = &storage {
+        if let Err(e) = risk::global().seed_daily_pnl(storage) {
+            warn!("Failed to read the day's realized PnL: {}", e);
+        }
+    }
+
+    // Initialize blockchain connection
+    let blockchain_client = blockchain::create_client(&config).await?;
+    info!("Connected to blockchain provider");
+
+    // Refuse routers, factories and pools deployed on another chain
+    blockchain::verify_deployments(&config, &blockchain_client).await?;
+
+    // Initialize chain head monitoring across providers
+    let head_monitor =
+        blockchain::create_head_monitor(&config, blockchain_client.clone(), alert_manager.clone())?;
+
+    // Initialize MEV-Share client
+    let mev_share_client = mev_share::create_client(&config).await?;
+    info!("Connected to MEV-Share network");
+
+    // Initialize contract manager, deploying the executor contract if none is configured
+    let contract_manager = contract::create_manager(&config, blockchain_client.clone()).await?;
+    let contract_address = contract::resolve_contract(&config, &contract_manager, storage.as_ref())
+        .await
+        .context("Failed to set up the ArbitrageExecutor contract")?;
+
+    // Refuse to trade through a contract that is not ours before the first execution
+    if contract_address.is_some() {
+        contract_manager
+            .verify_contract()
+            .await
+            .context("ArbitrageExecutor contract failed verification")?;
+    }
+    info!("Contract manager initialized");
+
+    // Initialize DEX interfaces
+    let dex_interfaces =
+        dex::create_interfaces(&config, blockchain_client.clone(), storage.as_ref()).await?;
+    info!("DEX interfaces initialized");
+
+    // Initialize price oracle
+    let price_oracle =
+        price::create_oracle(&config, dex_interfaces.clone(), storage.as_ref()).await?;
+    info!("Price oracle initialized");
+
+    // Initialize flash loan manager
+    let flash_loan_manager = flash_loan::create_manager(&config, blockchain_client.clone()).await?;
+    if contract_address.is_some() {
+        // Loans the contract takes itself revert from lenders its owner has not allowed
+        for (provider, lender) in flash_loan_manager.allowed_lenders() {
+            match contract_manager.is_flash_lender(lender).await {
+                Ok(true) => {}
+                Ok(false) => warn!(
+                    "{} flash loans revert until the contract's setFlashLender allows {:?}",
+                    provider.as_str(),
+                    lender
+                ),
+                Err(e) => warn!("Failed to check flash lender {:?}: {}", lender, e),
+            }
+        }
+    }
+    info!("Flash loan manager initialized");
+
+    // Initialize gas price optimizer
+    let gas_optimizer = gas::create_optimizer(&config, blockchain_client.clone()).await?;
+    info!("Gas price optimizer initialized");
+
+    // Initialize transaction simulator
+    let simulator =
+        simulation::create_simulator(&config, blockchain_client.clone(), price_oracle.clone())
+            .await?;
+    info!("Transaction simulator initialized");
+
+    // Initialize transaction builder and executor
+    let tx_builder = transaction::create_builder(
+        &config,
+        blockchain_client.clone(),
+        Some(contract_manager.clone()),
+        flash_loan_manager.clone(),
+        gas_optimizer.clone(),
+    )
+    .await?;
+    let tx_executor = transaction::create_executor(
+        &config,
+        blockchain_client.clone(),
+        mev_share_client.clone(),
+        gas_optimizer.clone(),
+        simulator.clone(),
+        storage.clone(),
+        supervisor.settlement_token(),
+    )
+    .await?;
+    info!("Transaction components initialized");
+
+    // Bring the wallet's nonce and trade history in line with the chain after a restart
+    if config.wallet.reconcile_on_startup {
+        if let Err(e) = tx_executor.reconcile_wallet(&alert_manager).await {
+            alert_manager.raise(
+                AlertSeverity::Warning,
+                "wallet",
+                format!("Wallet reconciliation failed: {}", e),
+            );
+        }
+    }
+
+    // Watch the wallet's balances, refusing submissions it cannot pay gas for
+    let treasury_config: TreasuryConfig = config.section()?;
+    let treasury = if treasury_config.enabled {
+        let alchemy_provider = blockchain::create_alchemy_provider(&config).await?;
+        let treasury = treasury::create_treasury(
+            &config,
+            &treasury_config,
+            alchemy_provider,
+            tx_executor.clone(),
+            alert_manager.clone(),
+        )?;
+        let task = treasury.clone();
+        supervisor.supervise(format!("treasury on {}", chain), move |shutdown| {
+            task.clone().run(shutdown)
+        });
+        info!("Wallet balance monitor started");
+        Some(treasury)
+    } else {
+        None
+    };
+
+    // Refuse paths whose approvals are missing, and set them once
+    let allowances_config: AllowancesConfig = config.section()?;
+    let allowances = if allowances_config.enabled {
+        Some(allowances::create_manager(
+            &config,
+            &allowances_config,
+            blockchain_client.clone(),
+            contract_manager.get_contract_address(),
+            flash_loan_manager.clone(),
+            tx_executor.clone(),
+        )?)
+    } else {
+        None
+    };
+
+    // Claim opportunities so instances running side by side do not compete for them
+    let coordination_config: CoordinationConfig = config.section()?;
+    let coordinator = if coordination_config.enabled {
+        Some(coordination::create_coordinator(
+            &config,
+            &coordination_config,
+        )?)
+    } else {
+        None
+    };
+
+    // Initialize yield-based scan tiers
+    let pair_tiers = scanner::create_pair_tiers(&config, storage.as_ref())?;
+
+    // Scan the pairs blocks and pending swaps move, and sweep every pair periodically
+    let scan_trigger = Arc::new(ScanTrigger::new(Duration::from_millis(
+        config.arbitrage.sweep_interval_ms,
+    )));
+
+    // Watch pending swaps to anticipate opportunities a block ahead
+    let mempool_config: MempoolConfig = config.section()?;
+    let mempool = if mempool_config.enabled {
+        let monitor = mempool::create_monitor(
+            &config,
+            &mempool_config,
+            dex_interfaces.pool_cache(),
+            scan_trigger.clone(),
+        )?;
+        let alchemy_provider = blockchain::create_alchemy_provider(&config).await?;
+        let task = monitor.clone();
+        supervisor.supervise(format!("mempool monitor on {}", chain), move |shutdown| {
+            task.clone().run(alchemy_provider.clone(), shutdown)
+        });
+        info!("Mempool monitor started");
+        Some(monitor)
+    } else {
+        None
+    };
+
+    // Initialize opportunity scanner
+    let scanner = scanner::create_scanner(
+        &config,
+        blockchain_client.clone(),
+        dex_interfaces.clone(),
+        price_oracle.clone(),
+        gas_optimizer.clone(),
+        pair_tiers.clone(),
+        mempool,
+    )
+    .await?;
+    info!("Opportunity scanner initialized");
+
+    // Initialize arbitrage strategy engine
+    let strategy_engine = strategy::create_engine(
+        &config,
+        price_oracle.clone(),
+        dex_interfaces.clone(),
+        flash_loan_manager.clone(),
+        gas_optimizer.clone(),
+    )
+    .await?;
+    info!("Strategy engine initialized");
+
+    // Initialize per-route loss throttling
+    let route_guard =
+        strategy::create_route_guard(&config, storage.clone(), alert_manager.clone())?;
+
+    // Sanity check opportunities against aggregator quotes before executing them
+    let aggregator_config: AggregatorConfig = config.section()?;
+    let quote_checker = if aggregator_config.enabled {
+        Some(aggregator::create_checker(
+            &config,
+            &aggregator_config,
+            dex_interfaces.clone(),
+        )?)
+    } else {
+        None
+    };
+
+    // Follow detected opportunities to the competitors that take them
+    let competition_config: CompetitionConfig = config.section()?;
+    let race_observer = if competition_config.enabled {
+        Some(competition::create_observer(
+            &config,
+            &competition_config,
+            blockchain_client.clone(),
+            gas_optimizer.clone(),
+            storage.clone(),
+            supervisor.shutdown_token(),
+        )?)
+    } else {
+        None
+    };
+
+    // Start the blockchain event listener
+    let event_listener = blockchain::start_listener(
+        &config,
+        blockchain_client.clone(),
+        scan_trigger.clone(),
+        price_oracle.clone(),
+        Some(dex_interfaces.pool_cache()),
+        Some(head_monitor.clone()),
+        supervisor.shutdown_token(),
+    )
+    .await?;
+    info!("Blockchain event listener started");
+
+    // Measure realized profit, and follow the contract's events into it and the alerts
+    let pnl_accountant =
+        pnl::create_accountant(&config, blockchain_client.clone(), price_oracle.clone())?;
+    register_contract_handlers(
+        &*event_listener,
+        pnl_accountant.clone(),
+        storage.clone(),
+        alert_manager.clone(),
+    )
+    .await?;
+
+    // Merge independent opportunities into one bundle per block if enabled
+    let bundle_composer = if config.mev_share.enabled && config.mev_share.merge_bundles {
+        Some(transaction::BundleComposer::new(
+            config
+                .mev_share
+                .max_bundle_size
+                .unwrap_or(transaction::DEFAULT_MAX_BUNDLE_SIZE),
+            config.mev_share.revert_tolerant_legs,
+        ))
+    } else {
+        None
+    };
+
+    // Bundles sent side by side would all be signed from the wallet's next nonce, so a batch
+    // of independent opportunities goes out as one bundle with consecutive nonces instead
+    let batch_composer = if config.mev_share.enabled
+        && config.mev_share.bundle_execution
+        && bundle_composer.is_none()
+    {
+        Some(transaction::BundleComposer::new(
+            config.arbitrage.max_parallel_executions,
+            config.mev_share.revert_tolerant_legs,
+        ))
+    } else {
+        None
+    };
+
+    // Start the main arbitrage loop
+    info!("Starting main arbitrage loop on {}", chain);
+    let outcome_tracker = Arc::new(TradeOutcomeTracker {
+        config: config.clone(),
+        tx_executor: tx_executor.clone(),
+        price_oracle: price_oracle.clone(),
+        pnl_accountant,
+        storage: storage.clone(),
+        route_guard: route_guard.clone(),
+        pair_tiers: pair_tiers.clone(),
+    });
+    let arbitrage_loop = Arc::new(ArbitrageLoop {
+        chain,
+        blockchain_client: blockchain_client.clone(),
+        scanner,
+        scan_trigger,
+        opportunity_queue: OpportunityQueue::new(),
+        max_parallel_executions: config.arbitrage.max_parallel_executions,
+        strategy_engine,
+        route_guard: route_guard.clone(),
+        pair_tiers,
+        tx_builder,
+        tx_executor: tx_executor.clone(),
+        bundle_composer,
+        batch_composer,
+        quote_checker,
+        allowances,
+        coordinator,
+        race_observer,
+        treasury,
+        storage: storage.clone(),
+        outcome_tracker,
+        in_flight: supervisor.in_flight(),
+    });
+    supervisor.supervise(format!("arbitrage loop on {}", chain), move |shutdown| {
+        arbitrage_loop.clone().run(shutdown)
+    });
+    supervisor.add_chain(ChainSubsystems {
+        name: chain.to_string(),
+        event_listener,
+        mev_share_client,
+        storage: storage.clone(),
+    });
+
+    Ok(ChainHandles {
+        contract_manager,
+        tx_executor,
+        storage,
+        dex_interfaces,
+        route_guard,
+    })
+}
+
+/// Forward the ArbitrageExecutor contract's events to PnL and alerts
+///
+/// The profit each `ArbitrageExecuted` event reports is priced and stored next to the trade's
+/// measured PnL; emergency stops and changes to the contract's callers or owner are alerted on.
+async fn register_contract_handlers(
+    event_listener: &dyn BlockchainEventListener,
+    pnl_accountant: Arc<PnlAccountant>,
+    storage: Option<Arc<Storage>>,
+    alert_manager: Arc<AlertManager>,
+) -> Result<()> {
+    event_listener
+        .register_event_handler(
+            "ArbitrageExecuted",
+            Box::new(move |log| {
+                let tx_hash = log.transaction_hash.unwrap_or_default();
+                let ExecutorEvent::ArbitrageExecuted {
+                    path,
+                    amount_in,
+                    profit,
+                    ..
+                } = ExecutorEvent::decode(&log)?
+                else {
+                    return Ok(());
+                };
+                let token = *path
+                    .first()
+                    .context("ArbitrageExecuted with an empty path")?;
+                metrics::global().increment_counter("contract_arbitrages", 1);
+
+                let (pnl_accountant, storage) = (pnl_accountant.clone(), storage.clone());
+                tokio::spawn(async move {
+                    let Some(profit_usd) = pnl_accountant.contract_profit_usd(token, profit).await
+                    else {
+                        return;
+                    };
+                    info!(
+                        "Contract reported ${:.2} of profit on a {} {} loan in {:?}",
+                        profit_usd,
+                        amount_in,
+                        tokens::token_label(token),
+                        tx_hash
+                    );
+                    store(&storage, |s| s.record_contract_profit(tx_hash, profit_usd));
+                });
+
+                Ok(())
+            }),
+        )
+        .await?;
+
+    for event_name in [
+        "EmergencyStopActivated",
+        "EmergencyStopDeactivated",
+        "CallerAuthorized",
+        "CallerUnauthorized",
+        "OwnershipTransferred",
+        "FlashLenderUpdated",
+    ] {
+        let alert_manager = alert_manager.clone();
+        event_listener
+            .register_event_handler(
+                event_name,
+                Box::new(move |log| {
+                    let tx_hash = log.transaction_hash.unwrap_or_default();
+                    let (severity, message) = match ExecutorEvent::decode(&log)? {
+                        ExecutorEvent::EmergencyStopActivated { activator } => (
+                            AlertSeverity::Critical,
+                            format!("Emergency stop activated by {:?}", activator),
+                        ),
+                        ExecutorEvent::EmergencyStopDeactivated { deactivator } => (
+                            AlertSeverity::Warning,
+                            format!("Emergency stop deactivated by {:?}", deactivator),
+                        ),
+                        ExecutorEvent::CallerAuthorized { caller } => (
+                            AlertSeverity::Warning,
+                            format!("Caller {:?} authorized", caller),
+                        ),
+                        ExecutorEvent::CallerUnauthorized { caller } => (
+                            AlertSeverity::Warning,
+                            format!("Caller {:?} unauthorized", caller),
+                        ),
+                        ExecutorEvent::FlashLenderUpdated { lender, allowed } => (
+                            AlertSeverity::Warning,
+                            if allowed {
+                                format!("Flash lender {:?} allowed", lender)
+                            } else {
+                                format!("Flash lender {:?} disallowed", lender)
+                            },
+                        ),
+                        ExecutorEvent::OwnershipTransferred {
+                            previous_owner,
+                            new_owner,
+                        } => (
+                            AlertSeverity::Critical,
+                            format!(
+                                "Ownership transferred from {:?} to {:?}",
+                                previous_owner, new_owner
+                            ),
+                        ),
+                        _ => return Ok(()),
+                    };
+                    alert_manager.raise(
+                        severity,
+                        "contract",
+                        format!("{} in {:?}", message, tx_hash),
+                    );
+
+                    Ok(())
+                }),
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// The scan, evaluate and execute loop of one chain
+struct ArbitrageLoop {
+    chain: Chain,
+    blockchain_client: Arc<RpcClient>,
+    scanner: Arc<dyn OpportunityScanner>,
+    scan_trigger: Arc<ScanTrigger>,
+    opportunity_queue: OpportunityQueue,
+    max_parallel_executions: usize,
+    strategy_engine: Arc<dyn StrategyEngine>,
+    route_guard: Arc<RouteGuard>,
+    pair_tiers: Arc<PairTiers>,
+    tx_builder: Arc<dyn TransactionBuilder>,
+    tx_executor: Arc<dyn TransactionExecutor>,
+    bundle_composer: Option<BundleComposer>,
+    batch_composer: Option<BundleComposer>,
+    quote_checker: Option<Arc<QuoteChecker>>,
+    allowances: Option<Arc<AllowanceManager>>,
+    coordinator: Option<Arc<Coordinator>>,
+    race_observer: Option<Arc<RaceObserver>>,
+    treasury: Option<Arc<Treasury>>,
+    storage: Option<Arc<Storage>>,
+    outcome_tracker: Arc<TradeOutcomeTracker>,
+    in_flight: InFlight,
+}
+
+impl ArbitrageLoop {
+    /// Scan, evaluate and execute whenever a scan is requested, until shutdown
+    async fn run(self: Arc<Self>, shutdown: CancellationToken) {
+        while let Some(request) = shutdown.run_until_cancelled(self.scan_trigger.wait()).await {
+            debug!("Scan requested at block {}", request.block_number);
+            self.iterate(&request, &shutdown).await;
+        }
+    }
+
+    /// Run a requested scan and act on the opportunities found
+    ///
+    /// Cancellation interrupts the scan, but an opportunity already being executed is submitted
+    /// and recorded in full.
+    async fn iterate(&self, request: &ScanRequest, shutdown: &CancellationToken) {
+        // Scanning paused through the admin API
+        if runtime::controls().scanning_paused() {
+            return;
+        }
+
+        // Scan the changed pairs, or sweep every pair
+        let scan = async {
+            match &request.pairs {
+                Some(pairs) => self.scanner.scan_pairs(pairs).await,
+                None => self.scanner.scan().await,
+            }
+        };
+        let Some(scanned) = shutdown.run_until_cancelled(scan).await else {
+            return;
+        };
+        match scanned {
+            Ok(mut opportunities) => {
+                // Skip routes suspended after consecutive losses
+                opportunities.retain(|opportunity| self.route_guard.allows(opportunity));
+                for opportunity in &opportunities {
+                    feed::global().publish_opportunity(self.chain, opportunity);
+                }
+
+                if !opportunities.is_empty() {
+                    info!(
+                        "Found {} potential arbitrage opportunities",
+                        opportunities.len()
+                    );
+
+                    // Find out who takes the opportunities we do not
+                    if let Some(observer) = &self.race_observer {
+                        observer.watch(&opportunities);
+                    }
+
+                    let blocked = self.strategy_engine.execution_blocked().or_else(|| {
+                        self.treasury
+                            .as_ref()
+                            .and_then(|treasury| treasury.execution_blocked())
+                    });
+                    if let Some(reason) = blocked {
+                        // Keep recording what the scanner finds while execution is paused
+                        for opportunity in &opportunities {
+                            store(&self.storage, |s| s.record_opportunity(opportunity));
+                        }
+                        trace::record(TraceStage::Action, || {
+                            format!(
+                                "Recorded {} opportunities without executing ({})",
+                                opportunities.len(),
+                                reason
+                            )
+                        });
+                    } else {
+                        let ranked = self.strategy_engine.rank_opportunities(opportunities).await;
+                        self.opportunity_queue.push(ranked, request.block_number);
+                        self.execute_queued(request.block_number).await;
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Error scanning for arbitrage opportunities: {}", e);
+            }
+        }
+    }
+
+    /// Execute the queued opportunities of a block: all of them merged into a bundle, or the
+    /// best ones that trade through no common pair side by side (in one bundle when sent as
+    /// bundles, so each gets its own nonce)
+    async fn execute_queued(&self, block_number: u64) {
+        if let Some(composer) = &self.bundle_composer {
+            let submitted = self
+                .execute_merged_bundle(composer, self.opportunity_queue.take_all(block_number))
+                .await;
+            for token_path in &submitted {
+                self.pair_tiers.record_execution(token_path);
+            }
+            return;
+        }
+
+        let batch = self
+            .opportunity_queue
+            .take_independent(block_number, self.max_parallel_executions);
+        if batch.is_empty() {
+            info!("No profitable arbitrage opportunities found after evaluation");
+            trace::record(TraceStage::Action, || {
+                "No profitable opportunity after evaluation".to_string()
+            });
+            return;
+        }
+
+        if batch.len() > 1 {
+            info!("Executing {} independent opportunities", batch.len());
+
+            if let Some(composer) = &self.batch_composer {
+                let submitted = self.execute_merged_bundle(composer, batch).await;
+                for token_path in &submitted {
+                    self.pair_tiers.record_execution(token_path);
+                }
+                return;
+            }
+        }
+        join_all(batch.into_iter().map(|opportunity| {
+            let span = opportunity.span.clone();
+            self.execute_opportunity(opportunity).instrument(span)
+        }))
+        .await;
+    }
+
+    /// Build and submit the transaction of an opportunity, then track its outcome
+    async fn execute_opportunity(&self, opportunity: ArbitrageOpportunity) {
+        info!("Selected arbitrage opportunity: {}", opportunity);
+        trace::record(TraceStage::Action, || format!("Selected {}", opportunity));
+
+        store(&self.storage, |s| s.record_opportunity(&opportunity));
+
+        if let Some(checker) = &self.quote_checker {
+            if !checker.allows(&opportunity).await {
+                return;
+            }
+        }
+
+        if let Some(allowances) = &self.allowances {
+            if !allowances.allows(&opportunity).await {
+                return;
+            }
+        }
+
+        // Leave the opportunity to the instance that claimed it first
+        let claim = match &self.coordinator {
+            Some(coordinator) => coordinator.claim(&opportunity.id).await,
+            None => Claim::Uncoordinated,
+        };
+        if claim == Claim::Refused {
+            return;
+        }
+
+        let submitted = self.submit_opportunity(&opportunity).await;
+        if let (Claim::Claimed, false, Some(coordinator)) = (claim, submitted, &self.coordinator) {
+            coordinator.release(&opportunity.id).await;
+        }
+    }
+
+    /// Build and submit the transaction of a checked opportunity, then track its outcome
+    ///
+    /// Returns whether the transaction was submitted.
+    async fn submit_opportunity(&self, opportunity: &ArbitrageOpportunity) -> bool {
+        if !within_latency_budget(&opportunity.latency, &opportunity.id, Stage::Building) {
+            return false;
+        }
+
+        // Build the transaction
+        match latency::time_stage(Stage::Building, || {
+            self.tx_builder.build_arbitrage_transaction(opportunity)
+        })
+        .await
+        {
+            Ok(transaction) => {
+                if !within_latency_budget(&transaction.latency, &opportunity.id, Stage::Submission)
+                {
+                    return false;
+                }
+
+                let trade_id = record_trade(&self.storage, &transaction);
+                let opportunity_id = transaction.opportunity_id.clone();
+                let estimated_profit = transaction.estimated_profit;
+                let route = route_key(&transaction.dex_path(), &transaction.token_path);
+                let token_path = transaction.token_path.clone();
+                let path = tokens::path_label(&token_path);
+
+                // Execute the transaction
+                let latency = transaction.latency;
+                match latency::time_stage(Stage::Submission, || {
+                    self.tx_executor.execute_transaction(transaction)
+                })
+                .await
+                {
+                    Ok(tx_hash) => {
+                        latency.finish();
+                        self.pair_tiers.record_execution(&token_path);
+                        info!(
+                            "Arbitrage transaction for {} executed successfully: {}",
+                            path, tx_hash
+                        );
+                        trace::record(TraceStage::Action, || {
+                            format!("Submitted {} as {:?}", path, tx_hash)
+                        });
+
+                        if let Some(trade_id) = trade_id {
+                            store(&self.storage, |s| {
+                                s.mark_submitted(trade_id, Some(tx_hash), None)
+                            });
+                        }
+
+                        // Shutdown waits for the outcome to be recorded
+                        let in_flight = self.in_flight.enter();
+                        let tracking = self.outcome_tracker.clone().track(
+                            opportunity_id,
+                            tx_hash,
+                            route,
+                            token_path,
+                            estimated_profit,
+                        );
+                        tokio::spawn(
+                            async move {
+                                tracking.await;
+                                drop(in_flight);
+                            }
+                            .in_current_span(),
+                        );
+                        true
+                    }
+                    Err(e) => {
+                        error!(
+                            "Failed to execute arbitrage transaction for {}: {}",
+                            path, e
+                        );
+                        trace::record(TraceStage::Action, || {
+                            format!("Execution of {} failed: {}", path, e)
+                        });
+
+                        if let Some(trade_id) = trade_id {
+                            store(&self.storage, |s| s.mark_failed(trade_id, &e.to_string()));
+                        }
+                        false
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Failed to build arbitrage transaction: {}", e);
+                trace::record(TraceStage::Action, || {
+                    format!("Building the transaction failed: {}", e)
+                });
+                false
+            }
+        }
+    }
+
+    /// Build all ranked opportunities, merge the non-conflicting ones and submit them as one
+    /// bundle
+    ///
+    /// Returns the token paths of the submitted transactions.
+    async fn execute_merged_bundle(
+        &self,
+        composer: &BundleComposer,
+        ranked: Vec<ArbitrageOpportunity>,
+    ) -> Vec<Vec<Address>> {
+        if ranked.is_empty() {
+            info!("No profitable arbitrage opportunities found after evaluation");
+            trace::record(TraceStage::Action, || {
+                "No profitable opportunity after evaluation".to_string()
+            });
+            return Vec::new();
+        }
+
+        let mut transactions = Vec::with_capacity(ranked.len());
+        let mut claimed = Vec::new();
+        for opportunity in &ranked {
+            store(&self.storage, |s| s.record_opportunity(opportunity));
+
+            if let Some(checker) = &self.quote_checker {
+                if !checker
+                    .allows(opportunity)
+                    .instrument(opportunity.span.clone())
+                    .await
+                {
+                    continue;
+                }
+            }
+
+            if let Some(allowances) = &self.allowances {
+                if !allowances
+                    .allows(opportunity)
+                    .instrument(opportunity.span.clone())
+                    .await
+                {
+                    continue;
+                }
+            }
+
+            if !within_latency_budget(&opportunity.latency, &opportunity.id, Stage::Building) {
+                continue;
+            }
+
+            if let Some(coordinator) = &self.coordinator {
+                match coordinator.claim(&opportunity.id).await {
+                    Claim::Refused => continue,
+                    Claim::Claimed => claimed.push(opportunity.id.clone()),
+                    Claim::Renewed | Claim::Uncoordinated => {}
+                }
+            }
+
+            match latency::time_stage(Stage::Building, || {
+                self.tx_builder.build_arbitrage_transaction(opportunity)
+            })
+            .instrument(opportunity.span.clone())
+            .await
+            {
+                Ok(transaction) => transactions.push(transaction),
+                Err(e) => {
+                    error!("Failed to build arbitrage transaction: {}", e);
+                    trace::record(TraceStage::Action, || {
+                        format!(
+                            "Building the transaction for {} failed: {}",
+                            opportunity.id, e
+                        )
+                    });
+                }
+            }
+        }
+
+        // Opportunities that ran out of budget while the others were built are left out
+        transactions
+            .retain(|tx| within_latency_budget(&tx.latency, &tx.opportunity_id, Stage::Submission));
+
+        let bundle = composer.compose(transactions);
+        if bundle.is_empty() {
+            self.release_claims(&claimed, &[]).await;
+            return Vec::new();
+        }
+
+        let target_block = match self.blockchain_client.get_block_number().await {
+            Ok(block_number) => block_number.as_u64() + 1,
+            Err(e) => {
+                error!("Failed to get block number for bundle: {}", e);
+                self.release_claims(&claimed, &[]).await;
+                return Vec::new();
+            }
+        };
+
+        info!(
+            "Merged {} of {} opportunities into a bundle for block {}",
+            bundle.len(),
+            ranked.len(),
+            target_block
+        );
+
+        let trade_ids: Vec<i64> = bundle
+            .iter()
+            .filter_map(|tx| record_trade(&self.storage, tx))
+            .collect();
+
+        let bundle_size = bundle.len();
+        let opportunity_ids: Vec<String> =
+            bundle.iter().map(|tx| tx.opportunity_id.clone()).collect();
+        let token_paths: Vec<Vec<Address>> =
+            bundle.iter().map(|tx| tx.token_path.clone()).collect();
+        let latencies: Vec<Latency> = bundle.iter().map(|tx| tx.latency).collect();
+        let (result, duration) =
+            measure_time_async(|| self.tx_executor.execute_bundle(bundle, target_block)).await;
+        for _ in &latencies {
+            latency::record(Stage::Submission, duration);
+        }
+        match result {
+            Ok(bundle_hash) => {
+                for latency in &latencies {
+                    latency.finish();
+                }
+                info!("Arbitrage bundle submitted successfully: {}", bundle_hash);
+                trace::record(TraceStage::Action, || {
+                    format!(
+                        "Submitted bundle of {} transactions for block {}: {}",
+                        bundle_size, target_block, bundle_hash
+                    )
+                });
+                for &trade_id in &trade_ids {
+                    store(&self.storage, |s| {
+                        s.mark_submitted(trade_id, None, Some(&bundle_hash))
+                    });
+                }
+                self.release_claims(&claimed, &opportunity_ids).await;
+                token_paths
+            }
+            Err(e) => {
+                error!("Failed to execute arbitrage bundle: {}", e);
+                trace::record(TraceStage::Action, || {
+                    format!("Bundle for block {} failed: {}", target_block, e)
+                });
+                for &trade_id in &trade_ids {
+                    store(&self.storage, |s| s.mark_failed(trade_id, &e.to_string()));
+                }
+                self.release_claims(&claimed, &[]).await;
+                Vec::new()
+            }
+        }
+    }
+
+    /// Release the claims of opportunities that were not submitted after all
+    async fn release_claims(&self, claimed: &[String], submitted: &[String]) {
+        let Some(coordinator) = &self.coordinator else {
+            return;
+        };
+
+        for opportunity_id in claimed.iter().filter(|id| !submitted.contains(id)) {
+            coordinator.release(opportunity_id).await;
+        }
+    }
+}
+
+/// Print the requested analytics views (all of them if none are named)
+pub async fn run_report(config: &Arc<Config>, views: &[String]) -> Result<()> {
+    let storage = storage::create_storage(config).await?;
+
+    let views: Vec<&str> = if views.is_empty() {
+        storage::ANALYTICS_VIEWS
+            .iter()
+            .map(|(name, _, _)| *name)
+            .collect()
+    } else {
+        views.iter().map(String::as_str).collect()
+    };
+
+    for view in views {
+        let table = storage.report(view)?;
+        let description = storage::ANALYTICS_VIEWS
+            .iter()
+            .find(|(name, _, _)| *name == view)
+            .map(|(_, description, _)| *description)
+            .unwrap_or_default();
+        println!("{}\n{}\n", description, table.render());
+    }
+
+    Ok(())
+}
+
+/// Run a storage operation if storage is enabled, logging failures instead of propagating them
+fn store(storage: &Option<Arc<Storage>>, operation: impl FnOnce(&Storage) -> Result<()>) {
+    if let Some(storage) = storage {
+        if let Err(e) = operation(storage) {
+            warn!("Failed to record to storage: {}", e);
+        }
+    }
+}
+
+/// Record the configuration in effect and report what changed since the previous run
+fn report_config_changes(config: &Config, storage: &Storage, alerts: &AlertManager) {
+    let snapshot = config::redacted_snapshot(config);
+    let previous = match storage.record_config(&snapshot.to_string()) {
+        Ok(Some(previous)) => previous,
+        Ok(None) => {
+            info!(
+                "Configuration {} unchanged since the last run",
+                storage.config_hash()
+            );
+            return;
+        }
+        Err(e) => {
+            warn!("Failed to record configuration to storage: {}", e);
+            return;
+        }
+    };
+
+    let old_snapshot = serde_json::from_str(&previous.snapshot).unwrap_or_default();
+    let changes = config::diff_snapshots(&old_snapshot, &snapshot);
+    for change in &changes {
+        info!("Configuration changed: {}", change);
+    }
+
+    alerts.raise(
+        AlertSeverity::Info,
+        "config",
+        format!(
+            "Configuration changed from {} to {}: {}",
+            previous.hash,
+            storage.config_hash(),
+            changes
+                .iter()
+                .map(|change| change.to_string())
+                .collect::<Vec<_>>()
+                .join("; ")
+        ),
+    );
+}
+
+/// Check an opportunity's latency budget before its next stage, logging why it is dropped if
+/// the budget has run out
+fn within_latency_budget(latency: &Latency, opportunity_id: &str, next: Stage) -> bool {
+    match latency.check(next) {
+        Ok(()) => true,
+        Err(e) => {
+            warn!("Dropping opportunity {}: {}", opportunity_id, e);
+            trace::record(TraceStage::Action, || {
+                format!("Dropped {}: {}", opportunity_id, e)
+            });
+            false
+        }
+    }
+}
+
+/// Record a built transaction, returning its trade ID if storage is enabled
+fn record_trade(storage: &Option<Arc<Storage>>, tx: &ArbitrageTransaction) -> Option<i64> {
+    let storage = storage.as_ref()?;
+    match storage.record_transaction(tx) {
+        Ok(trade_id) => Some(trade_id),
+        Err(e) => {
+            warn!("Failed to record transaction to storage: {}", e);
+            None
+        }
+    }
+}
+
+/// Follows submitted transactions to confirmation and records their realized outcome
+struct TradeOutcomeTracker {
+    config: Arc<Config>,
+    tx_executor: Arc<dyn TransactionExecutor>,
+    price_oracle: Arc<PriceOracle>,
+    pnl_accountant: Arc<PnlAccountant>,
+    storage: Option<Arc<Storage>>,
+    route_guard: Arc<RouteGuard>,
+    pair_tiers: Arc<PairTiers>,
+}
+
+impl TradeOutcomeTracker {
+    /// Wait for a submitted transaction to confirm, then record its outcome and realized PnL
+    ///
+    /// An included trade stays provisional until it is `min_confirmations` deep, so a shallow
+    /// reorg never books profit that did not happen.
+    async fn track(
+        self: Arc<Self>,
+        opportunity_id: String,
+        tx_hash: H256,
+        route: String,
+        token_path: Vec<Address>,
+        estimated_profit: f64,
+    ) {
+        let timeout = Duration::from_secs(self.config.security.transaction_timeout);
+        let included = match self
+            .tx_executor
+            .wait_for_transaction(tx_hash, timeout)
+            .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                warn!("Stopped tracking transaction {:?}: {}", tx_hash, e);
+                return;
+            }
+        };
+
+        // A bundle rebid with escalated tips lands under a new hash
+        let tx_hash = if included.tx_hash != tx_hash {
+            info!(
+                "Transaction {:?} was included as its rebid {:?}",
+                tx_hash, included.tx_hash
+            );
+            store(&self.storage, |s| {
+                s.replace_tx_hash(tx_hash, included.tx_hash)
+            });
+            included.tx_hash
+        } else {
+            tx_hash
+        };
+
+        let confirmations = self
+            .config
+            .security
+            .min_confirmations
+            .unwrap_or(DEFAULT_MIN_CONFIRMATIONS);
+        let result = if confirmations > 1 {
+            store(&self.storage, |s| s.record_inclusion(&included));
+            match self
+                .tx_executor
+                .wait_for_confirmations(tx_hash, confirmations, timeout)
+                .await
+            {
+                Ok(result) => result,
+                Err(e) => {
+                    warn!(
+                        "Transaction {:?} left provisional (included in block {}): {}",
+                        tx_hash,
+                        included.block_number.unwrap_or_default(),
+                        e
+                    );
+                    return;
+                }
+            }
+        } else {
+            included.clone()
+        };
+
+        if result.block_number != included.block_number {
+            warn!(
+                "Transaction {:?} was reorged from block {} into block {}",
+                tx_hash,
+                included.block_number.unwrap_or_default(),
+                result.block_number.unwrap_or_default()
+            );
+            metrics::global().increment_counter("trade_reorgs", 1);
+        }
+
+        // Measure what a successful trade earned from the token transfers in its receipt
+        let trade_pnl = if result.success {
+            match self.pnl_accountant.settle(tx_hash, estimated_profit).await {
+                Ok(trade_pnl) => Some(trade_pnl),
+                Err(e) => {
+                    warn!(
+                        "Failed to read the token deltas of {:?}, booking its estimated profit: {}",
+                        tx_hash, e
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        // Realized PnL: the priced token deltas if the trade succeeded (its estimated profit if
+        // they could not be priced), plus refunds, minus the gas and tip actually paid
+        let attribution = match self.eth_price_usd().await {
+            Some(eth_price_usd) if result.actual_cost.is_some() => {
+                let costs = self
+                    .tx_executor
+                    .settlement_costs(&result)
+                    .await
+                    .unwrap_or_else(|e| {
+                        warn!(
+                            "Failed to read settlement of {:?}, attributing all gas to the base \
+                             fee: {}",
+                            tx_hash, e
+                        );
+                        SettlementCosts {
+                            base_cost: result.actual_cost.unwrap_or_default(),
+                            ..Default::default()
+                        }
+                    });
+                let spread_capture = if result.success {
+                    trade_pnl
+                        .as_ref()
+                        .and_then(|trade_pnl| trade_pnl.realized_usd)
+                        .unwrap_or(estimated_profit)
+                } else {
+                    0.0
+                };
+                Some(ProfitAttribution::new(
+                    spread_capture,
+                    &costs,
+                    eth_price_usd,
+                ))
+            }
+            _ => None,
+        };
+        let realized_pnl = attribution.map(|attribution| attribution.realized_pnl());
+        if let Some(attribution) = &attribution {
+            if attribution.mev_refund_usd > 0.0 {
+                info!(
+                    "Transaction {:?} earned ${:.2} in refunds on ${:.2} of spread",
+                    tx_hash, attribution.mev_refund_usd, attribution.spread_capture_usd
+                );
+            }
+        }
+
+        risk::global().record_outcome(
+            result.success,
+            attribution.map(|attribution| attribution.gas_cost_usd + attribution.tip_cost_usd),
+            realized_pnl,
+        );
+        self.route_guard
+            .record_outcome(&route, result.success, realized_pnl);
+        self.pair_tiers
+            .record_outcome(&token_path, result.success, realized_pnl);
+        store(&self.storage, |s| {
+            s.record_result(&result, attribution.as_ref())
+        });
+        feed::global().publish_execution(
+            Chain::from_id(self.config.ethereum.chain_id),
+            &ExecutionUpdate {
+                opportunity_id,
+                tx_hash,
+                success: result.success,
+                block_number: result.block_number,
+                gas_used: result.gas_used,
+                estimated_profit,
+                realized_pnl,
+            },
+        );
+
+        if let Some(trade_pnl) = &trade_pnl {
+            if let (Some(realized), Some(slippage)) =
+                (trade_pnl.realized_usd, trade_pnl.slippage_usd())
+            {
+                info!(
+                    "Transaction {:?} realized ${:.2} of ${:.2} estimated (slippage ${:.2})",
+                    tx_hash, realized, estimated_profit, slippage
+                );
+            }
+            store(&self.storage, |s| s.record_trade_pnl(trade_pnl));
+        }
+    }
+
+    /// Get the current ETH price in USD, via the configured WETH token
+    async fn eth_price_usd(&self) -> Option<f64> {
+        let weth = self
+            .config
+            .flash_loan
+            .tokens
+            .iter()
+            .find(|token| token.symbol == "WETH")
+            .and_then(|token| validate_and_parse_address(&token.address).ok())?;
+
+        self.price_oracle.get_price_usd(weth).await.ok()
+    }
+}
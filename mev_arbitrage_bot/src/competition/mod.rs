@@ -0,0 +1,459 @@
+//! Competition Module
+//!
+//! This module is responsible for finding out why detected opportunities do not turn into wins.
+//! After an opportunity is detected, the next few blocks are searched for a transaction from
+//! another sender that went through two or more of the route's pools: the competitor that took
+//! the opportunity. Its priority fee and the pools it traded through are recorded as a lost race
+//! for the `lost_race_stats` report. Each pool also keeps the tips that recently won races through
+//! it, and the bot's own priority fee on later routes through the pool outbids their median.
+//!
+//! Direct payments to the block builder are not seen, so bundles that tip through a coinbase
+//! transfer show a low priority fee.
+
+use anyhow::{Context, Result};
+use ethers::middleware::Middleware;
+use ethers::types::{Address, Filter, H256, U256};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::blockchain::RpcClient;
+use crate::config::{Config, ConfigSection};
+use crate::dex::{BlockTag, DexType, PoolStateCache};
+use crate::gas::GasOptimizer;
+use crate::metrics;
+use crate::scanner::ArbitrageOpportunity;
+use crate::storage::Storage;
+use crate::tokens;
+use crate::trace::{self, TraceStage};
+use crate::utils::validate_and_parse_address;
+
+/// Default number of blocks after detection searched for the winning transaction
+pub const DEFAULT_RACE_WINDOW_BLOCKS: u64 = 2;
+
+/// Default number of winning tips remembered per pool
+pub const DEFAULT_TIP_HISTORY: usize = 20;
+
+/// Default margin by which the median winning tip on a route's pools is outbid (in percent)
+pub const DEFAULT_OUTBID_PCT: f64 = 10.0;
+
+/// How often the chain head is polled while a race window is open
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Lost race detection configuration (`[competition]`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompetitionConfig {
+    /// Whether detected opportunities are followed to the transaction that took them
+    pub enabled: bool,
+
+    /// Blocks after detection searched for the winning transaction (defaults to 2)
+    pub race_window_blocks: Option<u64>,
+
+    /// Winning tips remembered per pool (defaults to 20)
+    pub tip_history: Option<usize>,
+
+    /// Margin by which the median winning tip on a route's pools is outbid (in percent,
+    /// defaults to 10)
+    pub outbid_pct: Option<f64>,
+}
+
+impl ConfigSection for CompetitionConfig {
+    const NAME: &'static str = "competition";
+
+    fn validate(&self) -> Result<()> {
+        if self.race_window_blocks == Some(0) {
+            anyhow::bail!("Competition race_window_blocks must be greater than zero");
+        }
+        if self.tip_history == Some(0) {
+            anyhow::bail!("Competition tip_history must be greater than zero");
+        }
+        if self.outbid_pct.is_some_and(|pct| pct < 0.0) {
+            anyhow::bail!("Competition outbid_pct must not be negative");
+        }
+
+        Ok(())
+    }
+}
+
+/// A detected opportunity taken by another sender's transaction
+#[derive(Debug, Clone)]
+pub struct LostRace {
+    /// ID of the opportunity
+    pub opportunity_id: String,
+
+    /// Token path of the opportunity
+    pub token_path: Vec<Address>,
+
+    /// DEX and pool of each swap of the opportunity
+    pub hops: Vec<(DexType, Address)>,
+
+    /// Net profit the opportunity was estimated at (in USD)
+    pub estimated_profit: f64,
+
+    /// Unix timestamp when the opportunity was identified
+    pub detected_at: u64,
+
+    /// Block the opportunity was detected at
+    pub detected_block: u64,
+
+    /// Block the winning transaction was included in
+    pub block_number: u64,
+
+    /// Hash of the winning transaction
+    pub tx_hash: H256,
+
+    /// Sender of the winning transaction
+    pub sender: Address,
+
+    /// Contract the winning transaction called
+    pub contract: Option<Address>,
+
+    /// Position of the winning transaction in its block
+    pub tx_index: u64,
+
+    /// Priority fee per gas the winning transaction paid (in wei)
+    pub priority_fee: U256,
+
+    /// Pools the winning transaction swapped through, in execution order
+    pub pools: Vec<Address>,
+}
+
+/// Tips that recently won races through each pool
+pub struct CompetingTips {
+    history: usize,
+    outbid_pct: f64,
+    tips: Mutex<HashMap<Address, VecDeque<U256>>>,
+}
+
+impl CompetingTips {
+    /// Create an empty tip history with the configured length and outbid margin
+    pub fn new(config: &CompetitionConfig) -> Self {
+        Self {
+            history: config.tip_history.unwrap_or(DEFAULT_TIP_HISTORY).max(1),
+            outbid_pct: config.outbid_pct.unwrap_or(DEFAULT_OUTBID_PCT),
+            tips: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Remember the priority fee of a transaction that won a race through `pools`
+    pub fn record(&self, pools: &[Address], priority_fee: U256) {
+        let mut tips = self.tips.lock().unwrap_or_else(|e| e.into_inner());
+        for &pool in pools {
+            let window = tips.entry(pool).or_default();
+            if window.len() == self.history {
+                window.pop_front();
+            }
+            window.push_back(priority_fee);
+        }
+    }
+
+    /// Get the priority fee that outbids the median winning tip of the most contested of a
+    /// route's pools, or `None` if no race through them was lost
+    pub fn outbid(&self, pools: &[Address]) -> Option<U256> {
+        let tips = self.tips.lock().unwrap_or_else(|e| e.into_inner());
+        let median = pools
+            .iter()
+            .filter_map(|pool| tips.get(pool))
+            .filter_map(|window| {
+                let mut sorted: Vec<U256> = window.iter().copied().collect();
+                sorted.sort();
+                sorted.get(sorted.len() / 2).copied()
+            })
+            .max()?;
+
+        Some(
+            median
+                .saturating_mul(U256::from(((100.0 + self.outbid_pct) * 100.0) as u64))
+                .checked_div(U256::from(10_000))
+                .unwrap_or_default(),
+        )
+    }
+}
+
+/// Follows detected opportunities to the competitor transactions that take them
+pub struct RaceObserver {
+    blockchain_client: Arc<RpcClient>,
+    gas_optimizer: Arc<dyn GasOptimizer>,
+    storage: Option<Arc<Storage>>,
+    wallet: Address,
+    window: u64,
+    following: Mutex<HashSet<String>>,
+    cancel: CancellationToken,
+}
+
+/// Create a race observer, which stops following opportunities when `cancel` is cancelled
+pub fn create_observer(
+    config: &Arc<Config>,
+    competition_config: &CompetitionConfig,
+    blockchain_client: Arc<RpcClient>,
+    gas_optimizer: Arc<dyn GasOptimizer>,
+    storage: Option<Arc<Storage>>,
+    cancel: CancellationToken,
+) -> Result<Arc<RaceObserver>> {
+    let wallet = validate_and_parse_address(&config.ethereum.wallet_address)
+        .context("Lost race detection needs the wallet address")?;
+
+    Ok(Arc::new(RaceObserver {
+        blockchain_client,
+        gas_optimizer,
+        storage,
+        wallet,
+        window: competition_config
+            .race_window_blocks
+            .unwrap_or(DEFAULT_RACE_WINDOW_BLOCKS)
+            .max(1),
+        following: Mutex::new(HashSet::new()),
+        cancel,
+    }))
+}
+
+impl RaceObserver {
+    /// Follow each opportunity in the background until its race window has passed
+    ///
+    /// Opportunities still followed from an earlier scan are skipped.
+    pub fn watch(self: &Arc<Self>, opportunities: &[ArbitrageOpportunity]) {
+        for opportunity in opportunities {
+            let new = self
+                .following
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(opportunity.id.clone());
+            if !new {
+                continue;
+            }
+
+            let observer = self.clone();
+            let opportunity = opportunity.clone();
+            tokio::spawn(async move {
+                if let Some(Err(e)) = observer
+                    .cancel
+                    .run_until_cancelled(observer.follow(&opportunity))
+                    .await
+                {
+                    debug!("Failed to follow the race for {}: {}", opportunity.id, e);
+                }
+                observer
+                    .following
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .remove(&opportunity.id);
+            });
+        }
+    }
+
+    /// Wait for the race window to pass and record the race if another sender won it
+    async fn follow(&self, opportunity: &ArbitrageOpportunity) -> Result<()> {
+        let detected_block = match opportunity.quote_block {
+            BlockTag::Number(number) => number,
+            BlockTag::Latest | BlockTag::Pending => {
+                self.blockchain_client.get_block_number().await?.as_u64()
+            }
+        };
+        let last_block = detected_block + self.window;
+        self.wait_for_block(last_block).await?;
+
+        let Some(race) = self
+            .find_winner(opportunity, detected_block, last_block)
+            .await?
+        else {
+            return Ok(());
+        };
+
+        info!(
+            "Lost {} to {:?} from {:?} in block {} (priority fee {} gwei, via {})",
+            opportunity,
+            race.tx_hash,
+            race.sender,
+            race.block_number,
+            race.priority_fee.as_u128() as f64 / 1e9,
+            race.pools
+                .iter()
+                .map(|&pool| tokens::pool_label(pool))
+                .collect::<Vec<_>>()
+                .join(" -> ")
+        );
+        trace::record(TraceStage::Rejection, || {
+            format!(
+                "{} taken by {:?} in block {}",
+                opportunity.id, race.tx_hash, race.block_number
+            )
+        });
+        metrics::global().increment_counter("lost_races", 1);
+
+        // Later routes through the same pools outbid the winner
+        let route_pools: Vec<Address> = race.hops.iter().map(|&(_, pool)| pool).collect();
+        self.gas_optimizer
+            .record_winning_tip(&route_pools, race.priority_fee);
+
+        if let Some(storage) = &self.storage {
+            storage.record_lost_race(&race)?;
+        }
+
+        Ok(())
+    }
+
+    /// Wait until the chain head reaches `block_number`
+    async fn wait_for_block(&self, block_number: u64) -> Result<()> {
+        while self.blockchain_client.get_block_number().await?.as_u64() < block_number {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+
+        Ok(())
+    }
+
+    /// Find the first transaction after detection that went through two or more of the route's
+    /// pools, returning `None` if there is none or it was the bot's own
+    async fn find_winner(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        detected_block: u64,
+        last_block: u64,
+    ) -> Result<Option<LostRace>> {
+        let route_pools: Vec<Address> = opportunity.hops.iter().map(|&(_, pool)| pool).collect();
+        let Some(winner) = find_route_winner(
+            &self.blockchain_client,
+            &route_pools,
+            detected_block + 1,
+            last_block,
+        )
+        .await?
+        else {
+            return Ok(None);
+        };
+        if winner.sender == self.wallet {
+            debug!(
+                "Won the race for {} with {:?}",
+                opportunity.id, winner.tx_hash
+            );
+            return Ok(None);
+        }
+
+        Ok(Some(LostRace {
+            opportunity_id: opportunity.id.clone(),
+            token_path: opportunity.token_path.clone(),
+            hops: opportunity.hops.clone(),
+            estimated_profit: opportunity.net_profit,
+            detected_at: opportunity.timestamp,
+            detected_block,
+            block_number: winner.block_number,
+            tx_hash: winner.tx_hash,
+            sender: winner.sender,
+            contract: winner.contract,
+            tx_index: winner.tx_index,
+            priority_fee: winner.priority_fee,
+            pools: winner.pools,
+        }))
+    }
+}
+
+/// A transaction that went through two or more of a route's pools
+#[derive(Debug, Clone)]
+pub struct RouteWinner {
+    /// Hash of the transaction
+    pub tx_hash: H256,
+
+    /// Sender of the transaction
+    pub sender: Address,
+
+    /// Contract the transaction called
+    pub contract: Option<Address>,
+
+    /// Block the transaction was included in
+    pub block_number: u64,
+
+    /// Position of the transaction in its block
+    pub tx_index: u64,
+
+    /// Priority fee per gas the transaction paid (in wei)
+    pub priority_fee: U256,
+
+    /// Pools the transaction swapped through, in execution order
+    pub pools: Vec<Address>,
+}
+
+/// Find the first transaction in blocks `from_block` to `to_block` that went through two or
+/// more of a route's pools, whoever sent it
+pub async fn find_route_winner(
+    blockchain_client: &RpcClient,
+    route_pools: &[Address],
+    from_block: u64,
+    to_block: u64,
+) -> Result<Option<RouteWinner>> {
+    let filter = Filter::new()
+        .address(route_pools.to_vec())
+        .from_block(from_block)
+        .to_block(to_block);
+    let logs = blockchain_client
+        .get_logs(&filter)
+        .await
+        .context("Failed to fetch route pool logs")?;
+
+    // Route pools each transaction touched, in the order the transactions executed
+    let mut touched: Vec<(H256, HashSet<Address>)> = Vec::new();
+    for log in &logs {
+        let Some(tx_hash) = log.transaction_hash else {
+            continue;
+        };
+        match touched.iter_mut().find(|(hash, _)| *hash == tx_hash) {
+            Some((_, pools)) => {
+                pools.insert(log.address);
+            }
+            None => touched.push((tx_hash, HashSet::from([log.address]))),
+        }
+    }
+
+    // A single pool is an ordinary swap; an arbitrage goes through at least two
+    let Some(tx_hash) = touched
+        .into_iter()
+        .find(|(_, pools)| pools.len() >= 2)
+        .map(|(tx_hash, _)| tx_hash)
+    else {
+        return Ok(None);
+    };
+
+    let tx = blockchain_client
+        .get_transaction(tx_hash)
+        .await?
+        .with_context(|| format!("Transaction {:?} not found", tx_hash))?;
+    let receipt = blockchain_client
+        .get_transaction_receipt(tx_hash)
+        .await?
+        .with_context(|| format!("Receipt of {:?} not found", tx_hash))?;
+    let block_number = receipt
+        .block_number
+        .with_context(|| format!("Transaction {:?} is not included", tx_hash))?
+        .as_u64();
+    let base_fee = blockchain_client
+        .get_block(block_number)
+        .await?
+        .and_then(|block| block.base_fee_per_gas)
+        .unwrap_or_default();
+    let gas_price = receipt
+        .effective_gas_price
+        .or(tx.gas_price)
+        .unwrap_or_default();
+
+    // Every pool the transaction swapped through, including those outside the route
+    let swap_topic = PoolStateCache::swap_topic();
+    let mut pools = Vec::new();
+    for log in &receipt.logs {
+        let is_pool = route_pools.contains(&log.address) || log.topics.first() == Some(&swap_topic);
+        if is_pool && !pools.contains(&log.address) {
+            pools.push(log.address);
+        }
+    }
+
+    Ok(Some(RouteWinner {
+        tx_hash,
+        sender: tx.from,
+        contract: tx.to,
+        block_number,
+        tx_index: receipt.transaction_index.as_u64(),
+        priority_fee: gas_price.saturating_sub(base_fee),
+        pools,
+    }))
+}
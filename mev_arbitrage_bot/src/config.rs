@@ -5,11 +5,18 @@
 use anyhow::{Context, Result};
 use dotenv::dotenv;
 use ethers::types::{Address, U256};
+use log::warn;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
+use crate::blockchain::Chain;
+use crate::mev_share::BUNDLE_HINTS;
+use crate::utils::validate_and_parse_address;
+
 /// Main configuration structure for the MEV arbitrage bot
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -34,12 +41,135 @@ pub struct Config {
     /// Security configuration
     pub security: SecurityConfig,
 
+    /// Off-chain price API configuration
+    #[serde(default)]
+    pub price_apis: PriceApisConfig,
+
+    /// Strategy experiment configuration
+    #[serde(default)]
+    pub experiments: ExperimentConfig,
+
+    /// Alerting configuration
+    #[serde(default)]
+    pub alerts: AlertsConfig,
+
+    /// Trade history storage configuration
+    #[serde(default)]
+    pub storage: StorageConfig,
+
+    /// Per-route loss throttling configuration
+    #[serde(default)]
+    pub route_guard: RouteGuardConfig,
+
+    /// Yield-based scan tier configuration
+    #[serde(default)]
+    pub pair_tiers: PairTiersConfig,
+
+    /// Trading window configuration
+    #[serde(default)]
+    pub schedule: ScheduleConfig,
+
+    /// Wallet reconciliation configuration
+    #[serde(default)]
+    pub wallet: WalletConfig,
+
+    /// Debugging configuration
+    #[serde(default)]
+    pub debug: DebugConfig,
+
+    /// Log redaction configuration
+    #[serde(default)]
+    pub logging: LoggingConfig,
+
+    /// Read-only observer configuration
+    #[serde(default)]
+    pub observer: ObserverConfig,
+
+    /// Task supervision and shutdown configuration
+    #[serde(default)]
+    pub runtime: RuntimeConfig,
+
+    /// Additional chains scanned alongside the one configured in `ethereum`
+    #[serde(default)]
+    pub chains: Vec<ChainConfig>,
+
+    /// Sections owned by the modules that use them (see `ConfigSection`)
+    #[serde(flatten)]
+    pub sections: ConfigSections,
+
     /// Test mode configuration
     /// When enabled, reduces log verbosity and slows down scanning frequency
     #[serde(default)]
     pub test_mode: bool,
 }
 
+/// A typed configuration section owned by the module that uses it
+///
+/// Modules define their section next to their code instead of adding it to `Config`, and read
+/// it with `Config::section`. Sections are listed in `MODULE_SECTIONS` so they are validated
+/// when the configuration is loaded.
+pub trait ConfigSection: DeserializeOwned + Default {
+    /// Name of the section's table (e.g. `mempool` for `[mempool]`)
+    const NAME: &'static str;
+
+    /// Check the section's values
+    fn validate(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Tables of the configuration not known to `Config`, kept as raw values until a module reads
+/// its section
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ConfigSections(BTreeMap<String, serde_json::Value>);
+
+impl Config {
+    /// Read a module's section, or its defaults if the section is not configured
+    pub fn section<T: ConfigSection>(&self) -> Result<T> {
+        match self.sections.0.get(T::NAME) {
+            Some(value) => serde_json::from_value(value.clone())
+                .with_context(|| format!("Invalid [{}] configuration", T::NAME)),
+            None => Ok(T::default()),
+        }
+    }
+}
+
+/// A module section, as registered for validation
+struct RegisteredSection {
+    name: &'static str,
+    validate: fn(&Config) -> Result<()>,
+}
+
+impl RegisteredSection {
+    /// Register the section type `T`
+    const fn of<T: ConfigSection>() -> Self {
+        Self {
+            name: T::NAME,
+            validate: |config| config.section::<T>()?.validate(),
+        }
+    }
+}
+
+/// Sections owned by other modules
+const MODULE_SECTIONS: [RegisteredSection; 15] = [
+    RegisteredSection::of::<crate::mempool::MempoolConfig>(),
+    RegisteredSection::of::<crate::aggregator::AggregatorConfig>(),
+    RegisteredSection::of::<crate::competition::CompetitionConfig>(),
+    RegisteredSection::of::<crate::api::ApiConfig>(),
+    RegisteredSection::of::<crate::treasury::TreasuryConfig>(),
+    RegisteredSection::of::<crate::risk::RiskConfig>(),
+    RegisteredSection::of::<crate::ratelimit::RateLimitConfig>(),
+    RegisteredSection::of::<crate::strategy::DepegConfig>(),
+    RegisteredSection::of::<crate::screening::ScreeningConfig>(),
+    RegisteredSection::of::<crate::mev_share::BuildersConfig>(),
+    RegisteredSection::of::<crate::transaction::PrivateTxConfig>(),
+    RegisteredSection::of::<crate::blockchain::RpcRetryConfig>(),
+    RegisteredSection::of::<crate::allowances::AllowancesConfig>(),
+    RegisteredSection::of::<crate::feed::FeedConfig>(),
+    RegisteredSection::of::<crate::coordination::CoordinationConfig>(),
+];
+
 /// Ethereum network configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EthereumConfig {
@@ -62,7 +192,8 @@ pub struct EthereumConfig {
     #[serde(skip_serializing)]
     pub private_key: Option<String>,
 
-    /// Public address of the bot's wallet
+    /// Public address of the bot's wallet (additional chains default to the primary's)
+    #[serde(default)]
     pub wallet_address: String,
 
     /// Maximum number of blocks to look back for events
@@ -71,9 +202,82 @@ pub struct EthereumConfig {
     /// Websocket connection timeout in seconds
     pub ws_timeout_seconds: u64,
 
+    /// Longest delay between attempts to reconnect a dropped websocket (defaults to 60)
+    pub ws_reconnect_max_backoff_seconds: Option<u64>,
+
     /// Alchemy API key
     #[serde(skip_serializing)]
     pub alchemy_api_key: Option<String>,
+
+    /// RPC URLs requests fail over to, in order, when `rpc_url` fails or is rate limited
+    #[serde(default)]
+    pub fallback_rpc_urls: Vec<String>,
+
+    /// RPC URL heavy reads (calls, gas estimates and traces) are sent to first
+    #[serde(default)]
+    pub read_rpc_url: Option<String>,
+
+    /// Seconds between health checks of the fallback and read endpoints (defaults to 15)
+    #[serde(default)]
+    pub rpc_health_check_interval_seconds: Option<u64>,
+
+    /// Additional RPC URLs whose chain heads are compared against the primary
+    #[serde(default)]
+    pub peer_rpc_urls: Vec<String>,
+
+    /// Number of blocks the primary may trail its peers before it is considered lagging (defaults to 1)
+    pub head_lag_threshold_blocks: Option<u64>,
+
+    /// Seconds the primary must keep lagging before switching data source (defaults to 5)
+    pub head_lag_grace_seconds: Option<u64>,
+
+    /// Multicall3 contract reads are batched through (defaults to its canonical deployment)
+    #[serde(default)]
+    pub multicall_address: Option<String>,
+
+    /// Headers, basic auth and proxy for requests to `rpc_url`
+    #[serde(default)]
+    pub rpc_http: HttpEndpointConfig,
+
+    /// Headers, basic auth and proxy for requests to the peer RPC endpoints
+    #[serde(default)]
+    pub peer_rpc_http: HttpEndpointConfig,
+
+    /// Headers, basic auth and proxy for requests to the fallback and read RPC endpoints
+    #[serde(default)]
+    pub fallback_rpc_http: HttpEndpointConfig,
+}
+
+/// Configuration of an additional chain, which runs its own scanner and strategy loop
+///
+/// Sections that are not set are taken from the primary chain's configuration, except
+/// MEV-Share, which is disabled unless configured. The wallet key and Alchemy API key are
+/// shared with the primary chain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainConfig {
+    /// Chain name, used in logs and to name the chain's trade history database
+    pub name: String,
+
+    /// Network configuration
+    pub ethereum: EthereumConfig,
+
+    /// DEX configuration (router, factory and pool addresses of the chain)
+    pub dex: DexConfig,
+
+    /// Flash loan configuration (lending pool and token addresses of the chain)
+    pub flash_loan: FlashLoanConfig,
+
+    /// Smart contract configuration (defaults to deploying a new executor)
+    #[serde(default)]
+    pub contract: Option<ContractConfig>,
+
+    /// Gas price configuration
+    #[serde(default)]
+    pub gas: Option<GasConfig>,
+
+    /// MEV-Share configuration
+    #[serde(default)]
+    pub mev_share: Option<MevShareConfig>,
 }
 
 /// MEV-Share configuration
@@ -82,23 +286,174 @@ pub struct MevShareConfig {
     /// MEV-Share API URL
     pub api_url: String,
 
-    /// MEV-Share API key
+    /// MEV-Share API key (deprecated: the relay authenticates requests by signature)
     #[serde(skip_serializing)]
     pub api_key: Option<String>,
 
+    /// Private key used to sign relay requests (X-Flashbots-Signature); not a funded wallet
+    #[serde(skip_serializing)]
+    pub signing_key: Option<String>,
+
     /// Whether to use MEV-Share for transaction protection
     pub enabled: bool,
 
     /// Maximum tip to pay to validators (in gwei)
     pub max_validator_tip: u64,
+
+    /// Whether to merge opportunities for the same block that trade through disjoint pools into
+    /// one bundle
+    #[serde(default)]
+    pub merge_bundles: bool,
+
+    /// Maximum number of transactions in a merged bundle
+    pub max_bundle_size: Option<usize>,
+
+    /// Whether the legs of a merged bundle after the most profitable one may revert without
+    /// dropping the bundle (listed in `revertingTxHashes`)
+    #[serde(default)]
+    pub revert_tolerant_legs: bool,
+
+    /// Whether single arbitrage transactions are sent as bundles instead of via `send_transaction`
+    #[serde(default)]
+    pub bundle_execution: bool,
+
+    /// Number of consecutive blocks each bundle submission targets
+    pub bundle_block_range: Option<u64>,
+
+    /// Number of times a missed bundle is resubmitted for the following blocks
+    pub max_bundle_rebids: Option<u32>,
+
+    /// Tips of bundle transactions, bid from their expected profit
+    #[serde(default)]
+    pub bid: BidConfig,
+
+    /// Privacy and refunds of submitted bundles
+    #[serde(default)]
+    pub bundle: BundleConfig,
+
+    /// Headers, basic auth and proxy for requests to the relay
+    #[serde(default)]
+    pub api_http: HttpEndpointConfig,
+}
+
+/// Bundle tip bidding configuration
+///
+/// When enabled, the priority fee of each bundle transaction is bid as a share of the profit it
+/// is expected to keep after the base fee, raised by `escalation_factor` on every rebid and
+/// capped at `max_validator_tip`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BidConfig {
+    /// Whether bundle tips are bid from expected profit instead of the market priority fee
+    pub enabled: bool,
+
+    /// Share of the expected profit bid as the tip of the first submission
+    pub profit_share: f64,
+
+    /// Factor the tip is raised by on each rebid
+    pub escalation_factor: f64,
+}
+
+impl Default for BidConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            profit_share: 0.5,
+            escalation_factor: 1.25,
+        }
+    }
+}
+
+/// Bundle privacy and refund configuration
+///
+/// Bundles are shared with the relay's default builders and hint nothing to other searchers
+/// unless `builders` and `hints` are set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BundleConfig {
+    /// Builders bundles are shared with (e.g. "flashbots", "rsync", "beaverbuild.org")
+    pub builders: Vec<String>,
+
+    /// Parts of each bundle hinted to other searchers: `calldata`, `contract_address`, `logs`,
+    /// `function_selector`, `hash` or `tx_hash`
+    pub hints: Vec<String>,
+
+    /// Recipients of the refunds paid when other searchers backrun our bundles
+    pub refund_config: Vec<RefundRecipientConfig>,
+
+    /// Share of a backrun's profit refunded to the sender of the transaction it backruns (in
+    /// percent, the relay's default when unset)
+    pub backrun_refund_percent: Option<u8>,
+}
+
+/// Recipient of a share of a bundle's refunds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefundRecipientConfig {
+    /// Address receiving the refund
+    pub address: String,
+
+    /// Share of the refund received (in percent)
+    pub percent: u8,
+}
+
+/// HTTP client settings of an endpoint, for routing through private gateways or proxies
+///
+/// Only `http://` and `https://` proxies are supported; SOCKS proxies need reqwest's `socks`
+/// feature, which this build does not enable. WebSocket connections are not routed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpEndpointConfig {
+    /// Headers added to every request (e.g. a gateway API key)
+    #[serde(skip_serializing)]
+    pub headers: BTreeMap<String, String>,
+
+    /// Username for HTTP basic auth
+    pub basic_auth_username: Option<String>,
+
+    /// Password for HTTP basic auth
+    #[serde(skip_serializing)]
+    pub basic_auth_password: Option<String>,
+
+    /// Proxy URL requests are sent through (credentials may be given in the URL)
+    pub proxy: Option<String>,
+}
+
+impl HttpEndpointConfig {
+    /// Secret values of these settings, which must be kept out of the logs
+    pub fn secrets(&self) -> impl Iterator<Item = &str> {
+        self.headers
+            .values()
+            .map(String::as_str)
+            .chain(self.basic_auth_password.as_deref())
+    }
 }
 
 /// Flash loan configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlashLoanConfig {
-    /// Address of the Aave lending pool
+    /// Address of the Aave V3 Pool
     pub aave_lending_pool: String,
 
+    /// Flash loan providers the strategy engine may pick from, cheapest first
+    #[serde(default = "default_flash_loan_providers")]
+    pub providers: Vec<FlashLoanProvider>,
+
+    /// Address of the Balancer Vault (defaults to the canonical deployment)
+    #[serde(default)]
+    pub balancer_vault: Option<String>,
+
+    /// Address of the Uniswap V3 factory (defaults to the chain's canonical deployment)
+    #[serde(default)]
+    pub uniswap_v3_factory: Option<String>,
+
+    /// Address of the Morpho Blue singleton (defaults to the canonical deployment)
+    #[serde(default)]
+    pub morpho_blue: Option<String>,
+
+    /// Address of the Spark Pool (defaults to the Ethereum deployment)
+    #[serde(default)]
+    pub spark_pool: Option<String>,
+
     /// Maximum amount to borrow (in ETH)
     pub max_borrow_amount: f64,
 
@@ -106,6 +461,53 @@ pub struct FlashLoanConfig {
     pub tokens: Vec<TokenConfig>,
 }
 
+/// Source of flash loans
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlashLoanProvider {
+    /// Aave V3 Pool (`flashLoan`)
+    Aave,
+
+    /// Balancer Vault `flashLoan`, taken by the executor contract's `executeFlashLoan` (calls
+    /// back its `receiveFlashLoan`)
+    Balancer,
+
+    /// Uniswap V3 pool `flash`, taken by the executor contract's `executeFlashLoan` (calls back
+    /// its `uniswapV3FlashCallback`)
+    UniswapV3,
+
+    /// Morpho Blue `flashLoan`, without a fee, taken by the executor contract's
+    /// `executeFlashLoan` (calls back its `onMorphoFlashLoan`)
+    Morpho,
+
+    /// Spark Pool, an Aave V3 fork (`flashLoan`, taken by the executor contract's
+    /// `executeFlashLoan` and calling back its `executeOperation`)
+    Spark,
+
+    /// Flash swap from the first pair of a two-leg route (`executeFlashSwap` on the executor
+    /// contract, whose `uniswapV2Call` repays the pair in the route's first token)
+    UniswapV2,
+}
+
+impl FlashLoanProvider {
+    /// Get the provider name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FlashLoanProvider::Aave => "aave",
+            FlashLoanProvider::Balancer => "balancer",
+            FlashLoanProvider::UniswapV3 => "uniswap_v3",
+            FlashLoanProvider::Morpho => "morpho",
+            FlashLoanProvider::Spark => "spark",
+            FlashLoanProvider::UniswapV2 => "uniswap_v2",
+        }
+    }
+}
+
+/// Default flash loan providers: Aave only, which the ArbitrageExecutor contract supports
+fn default_flash_loan_providers() -> Vec<FlashLoanProvider> {
+    vec![FlashLoanProvider::Aave]
+}
+
 /// Token configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenConfig {
@@ -130,6 +532,89 @@ pub struct DexConfig {
 
     /// Curve configuration
     pub curve: DexInstanceConfig,
+
+    /// Sushi Trident pools, traded through the BentoBox
+    #[serde(default)]
+    pub trident: TridentConfig,
+
+    /// Uniswap V2 pair discovery configuration
+    #[serde(default)]
+    pub pair_discovery: PairDiscoveryConfig,
+
+    /// Quarantine of pools that stop quoting
+    #[serde(default)]
+    pub quarantine: PoolQuarantineConfig,
+}
+
+/// Configuration for Sushi Trident constant-product and stable pools
+///
+/// Trident is not deployed on every chain Sushiswap is, so it has no default addresses.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TridentConfig {
+    /// Whether Trident pools are quoted and traded
+    pub enabled: bool,
+
+    /// BentoBox vault holding the pools' balances
+    pub bento_box_address: String,
+
+    /// Deployer of constant-product pools (empty to skip them)
+    pub constant_product_factory: String,
+
+    /// Deployer of stable pools (empty to skip them)
+    pub stable_factory: String,
+}
+
+/// Configuration for quarantining paused, migrated or drained pools
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PoolQuarantineConfig {
+    /// Consecutive failed quotes before a pool is quarantined (defaults to 3)
+    pub failure_threshold: Option<u32>,
+
+    /// Seconds between re-checks of a quarantined pool (defaults to 300)
+    pub recheck_interval_secs: Option<u64>,
+}
+
+/// Configuration for discovering Uniswap V2 pairs from factory `PairCreated` events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PairDiscoveryConfig {
+    /// Whether pairs are discovered from factory events
+    pub enabled: bool,
+
+    /// First block scanned (defaults to `lookback_blocks` before the chain head)
+    pub from_block: Option<u64>,
+
+    /// Number of blocks scanned at startup when `from_block` is not set
+    pub lookback_blocks: u64,
+
+    /// Number of blocks requested per `eth_getLogs` call
+    pub chunk_size: u64,
+
+    /// Minimum reserve of each token in a pair (in whole tokens)
+    pub min_reserve: f64,
+
+    /// Whether pairs of a whitelisted token with an unlisted one are discovered too, once the
+    /// unlisted token passes `[screening]`
+    pub include_unlisted_tokens: bool,
+
+    /// How often new factory events are polled (in milliseconds)
+    pub refresh_interval_ms: u64,
+}
+
+impl Default for PairDiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            from_block: None,
+            lookback_blocks: 100_000,
+            chunk_size: 2_000,
+            min_reserve: 1.0,
+            include_unlisted_tokens: false,
+            refresh_interval_ms: 60_000,
+        }
+    }
 }
 
 /// Configuration for a specific DEX
@@ -160,14 +645,198 @@ pub struct ArbitrageConfig {
     /// Slippage tolerance percentage
     pub slippage_tolerance: f64,
 
+    /// Share of an opportunity's expected profit it may lose on chain before the executor
+    /// contract reverts (percentage; 100 disables the guard)
+    #[serde(default = "default_profit_tolerance")]
+    pub profit_tolerance: f64,
+
     /// Timeout for opportunity evaluation (in milliseconds)
     pub evaluation_timeout_ms: u64,
 
     /// Maximum number of concurrent evaluations
     pub max_concurrent_evaluations: u8,
 
+    /// Longest an opportunity may take from the start of its scan to submission (in
+    /// milliseconds) before it is aborted as stale
+    #[serde(default = "default_latency_budget_ms")]
+    pub latency_budget_ms: u64,
+
+    /// Interval between full sweeps of every pair (in milliseconds); between sweeps only the
+    /// pairs whose pools changed are scanned
+    #[serde(default = "default_sweep_interval_ms")]
+    pub sweep_interval_ms: u64,
+
+    /// Most opportunities of one block executed side by side, when they trade through no
+    /// common token pair (sent as one bundle when transactions are sent as bundles)
+    #[serde(default = "default_max_parallel_executions")]
+    pub max_parallel_executions: usize,
+
     /// Smart contract configuration
     pub contract: ContractConfig,
+
+    /// Trade sizing configuration
+    #[serde(default)]
+    pub sizing: SizingConfig,
+
+    /// Triangular (single-DEX, three-pool) scanning configuration
+    #[serde(default)]
+    pub triangular: TriangularConfig,
+
+    /// Block the scanner reads quotes and reserves at
+    #[serde(default)]
+    pub quote_block: QuoteBlock,
+
+    /// Token pairs the scanner quotes, and tokens it skips
+    #[serde(default)]
+    pub pairs: PairsConfig,
+}
+
+fn default_latency_budget_ms() -> u64 {
+    2000
+}
+
+fn default_sweep_interval_ms() -> u64 {
+    30_000
+}
+
+fn default_max_parallel_executions() -> usize {
+    3
+}
+
+fn default_profit_tolerance() -> f64 {
+    50.0
+}
+
+/// Block the scanner reads quotes and reserves at
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuoteBlock {
+    /// Read every quote of a scan, and every later re-quote of its opportunities, at one block
+    #[default]
+    Pinned,
+
+    /// Read each quote at the latest block when it is made
+    Latest,
+
+    /// Read each quote against the pending block
+    Pending,
+}
+
+/// Triangular scanning configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TriangularConfig {
+    /// Whether the scanner also searches A -> B -> C -> A cycles within each DEX
+    pub enabled: bool,
+
+    /// Maximum number of cycles reported per scan, most profitable first
+    pub max_cycles_per_scan: usize,
+}
+
+impl Default for TriangularConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_cycles_per_scan: 10,
+        }
+    }
+}
+
+/// Token pair configuration of the scanner
+///
+/// Tokens are referred to by the symbol or address of a flash loan token; blacklisted tokens
+/// may also be given by the address of any other token.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PairsConfig {
+    /// Pairs to scan; when empty, every ordered pair of the flash loan tokens is scanned
+    pub whitelist: Vec<PairConfig>,
+
+    /// Tokens no scanned pair, cycle or pending route may touch (e.g. fee-on-transfer and
+    /// rebasing tokens, whose transfers do not move the quoted amounts)
+    pub blacklist: Vec<String>,
+}
+
+/// A whitelisted pair: borrow `token_a`, swap it to `token_b` and back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairConfig {
+    /// Token borrowed and repaid
+    pub token_a: String,
+
+    /// Token swapped through
+    pub token_b: String,
+
+    /// Amount of `token_a` quoted (in whole tokens, defaults to one)
+    #[serde(default)]
+    pub input_amount: Option<f64>,
+}
+
+impl PairsConfig {
+    /// Get the addresses of the blacklisted tokens
+    pub fn blacklisted(&self, flash_loan: &FlashLoanConfig) -> HashSet<Address> {
+        self.blacklist
+            .iter()
+            .filter_map(|reference| match flash_loan.token(reference) {
+                Some(token) => validate_and_parse_address(&token.address).ok(),
+                None => validate_and_parse_address(reference).ok(),
+            })
+            .collect()
+    }
+}
+
+impl FlashLoanConfig {
+    /// Find a flash loan token by its symbol (case-insensitively) or address
+    pub fn token(&self, reference: &str) -> Option<&TokenConfig> {
+        self.tokens.iter().find(|token| {
+            token.symbol.eq_ignore_ascii_case(reference)
+                || token.address.eq_ignore_ascii_case(reference)
+        })
+    }
+}
+
+/// Trade sizing configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SizingConfig {
+    /// Whether opportunities are sized by simulating profit across a grid of input sizes
+    pub enabled: bool,
+
+    /// Number of input sizes simulated
+    pub grid_points: usize,
+
+    /// Smallest input size simulated (in units of the borrowed token)
+    pub min_input: f64,
+
+    /// Ratio between consecutive input sizes
+    pub growth_factor: f64,
+
+    /// Fraction of the grid's peak profit the chosen size must reach
+    pub peak_tolerance: f64,
+
+    /// Relative increase in marginal profit tolerated before a profile counts as non-concave
+    pub concavity_tolerance: f64,
+
+    /// Ternary search iterations when solving for the optimal size between grid points
+    #[serde(default = "default_search_iterations")]
+    pub search_iterations: usize,
+}
+
+/// Default number of ternary search iterations
+fn default_search_iterations() -> usize {
+    12
+}
+
+impl Default for SizingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            grid_points: 8,
+            min_input: 0.25,
+            growth_factor: 2.0,
+            peak_tolerance: 0.9,
+            concavity_tolerance: 0.01,
+            search_iterations: default_search_iterations(),
+        }
+    }
 }
 
 /// Smart contract configuration
@@ -198,32 +867,178 @@ pub struct GasConfig {
     /// Priority fee for EIP-1559 transactions (in gwei)
     pub priority_fee: u64,
 
-    /// Gas limit for arbitrage transactions
+    /// Gas limit for arbitrage transactions (caps the padded gas estimate)
     pub gas_limit: u64,
-}
 
-/// Gas price calculation strategy
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum GasStrategy {
-    /// Fixed gas price
-    #[serde(rename = "fixed")]
-    Fixed,
+    /// Safety factor applied to `eth_estimateGas` results (defaults to 1.2)
+    pub gas_estimate_multiplier: Option<f64>,
 
-    /// EIP-1559 transaction type
-    #[serde(rename = "eip1559")]
-    Eip1559,
+    /// Blob base fee update fraction (defaults to the Prague value, 5007716)
+    pub blob_base_fee_update_fraction: Option<u64>,
 
-    /// Dynamic gas price based on network conditions
-    #[serde(rename = "dynamic")]
-    Dynamic,
+    /// Fee history base and priority fees are predicted from
+    #[serde(default)]
+    pub fee_history: FeeHistoryConfig,
+
+    /// Sources base and priority fees are estimated from
+    #[serde(default)]
+    pub sources: GasSourcesConfig,
 }
 
-/// Security configuration
+/// Sources base and priority fees are estimated from, aggregated by their median
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GasSourcesConfig {
+    /// Sources queried at every gas price update
+    pub sources: Vec<GasSourceKind>,
+
+    /// Blocknative API key (set via environment variable BLOCKNATIVE_API_KEY)
+    #[serde(skip_serializing)]
+    pub blocknative_api_key: Option<String>,
+
+    /// Consecutive failures after which a source is skipped
+    pub failure_threshold: u32,
+
+    /// Seconds a failing source is skipped before it is asked again
+    pub recheck_interval_secs: u64,
+}
+
+impl Default for GasSourcesConfig {
+    fn default() -> Self {
+        Self {
+            sources: vec![GasSourceKind::FeeHistory],
+            blocknative_api_key: None,
+            failure_threshold: 3,
+            recheck_interval_secs: 60,
+        }
+    }
+}
+
+/// A source of base and priority fee estimates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GasSourceKind {
+    /// The node's fee history (`eth_feeHistory`)
+    FeeHistory,
+
+    /// Alchemy's priority fee suggestion (`eth_maxPriorityFeePerGas`)
+    Alchemy,
+
+    /// Blocknative's gas price API
+    Blocknative,
+}
+
+/// Fee history base and priority fees are predicted from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeeHistoryConfig {
+    /// Number of recent blocks whose fees are kept
+    pub window_blocks: u64,
+
+    /// Percentile of a block's priority fees bid at low urgency
+    pub low_percentile: f64,
+
+    /// Percentile of a block's priority fees bid at normal urgency
+    pub normal_percentile: f64,
+
+    /// Percentile of a block's priority fees bid at high urgency
+    pub high_percentile: f64,
+
+    /// Urgency new transactions are bid at (lingering transactions are replaced at high urgency)
+    pub urgency: InclusionUrgency,
+}
+
+impl Default for FeeHistoryConfig {
+    fn default() -> Self {
+        Self {
+            window_blocks: 20,
+            low_percentile: 25.0,
+            normal_percentile: 50.0,
+            high_percentile: 90.0,
+            urgency: InclusionUrgency::Normal,
+        }
+    }
+}
+
+impl FeeHistoryConfig {
+    /// Get the percentile of a block's priority fees bid at an urgency
+    pub fn percentile(&self, urgency: InclusionUrgency) -> f64 {
+        match urgency {
+            InclusionUrgency::Low => self.low_percentile,
+            InclusionUrgency::Normal => self.normal_percentile,
+            InclusionUrgency::High => self.high_percentile,
+        }
+    }
+}
+
+/// How soon a transaction needs to be included
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InclusionUrgency {
+    /// Within a few blocks
+    Low,
+
+    /// In the next block or so
+    Normal,
+
+    /// In the next block, ahead of most other transactions
+    High,
+}
+
+impl InclusionUrgency {
+    /// Every urgency, from the lowest
+    pub const ALL: [InclusionUrgency; 3] = [
+        InclusionUrgency::Low,
+        InclusionUrgency::Normal,
+        InclusionUrgency::High,
+    ];
+
+    /// Get the name of the urgency, used in metric names
+    pub fn name(self) -> &'static str {
+        match self {
+            InclusionUrgency::Low => "low",
+            InclusionUrgency::Normal => "normal",
+            InclusionUrgency::High => "high",
+        }
+    }
+}
+
+/// Gas price calculation strategy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum GasStrategy {
+    /// Fixed gas price
+    #[serde(rename = "fixed")]
+    Fixed,
+
+    /// EIP-1559 transaction type
+    #[serde(rename = "eip1559")]
+    Eip1559,
+
+    /// Dynamic gas price based on network conditions
+    #[serde(rename = "dynamic")]
+    Dynamic,
+}
+
+/// Security configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SecurityConfig {
     /// Transaction timeout (in seconds)
     pub transaction_timeout: u64,
 
+    /// Confirmations required before a trade's profit counts as realized (the inclusion block
+    /// is the first)
+    #[serde(default)]
+    pub min_confirmations: Option<u64>,
+
+    /// Blocks a directly sent transaction may stay pending before it is replaced with the same
+    /// nonce at a bumped fee (never replaced when unset)
+    #[serde(default)]
+    pub replace_after_blocks: Option<u64>,
+
+    /// Most replacements sent for one transaction
+    #[serde(default)]
+    pub max_replacements: Option<u32>,
+
     /// Number of price sources required for validation
     pub min_price_sources: u8,
 
@@ -235,6 +1050,367 @@ pub struct SecurityConfig {
 
     /// Maximum slippage allowed during execution (percentage)
     pub max_execution_slippage: f64,
+
+    /// Additional addresses allowed to receive tokens during a simulated execution
+    #[serde(default)]
+    pub allowed_token_recipients: Vec<String>,
+}
+
+/// Off-chain price API configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PriceApisConfig {
+    /// CoinGecko client
+    pub coingecko: PriceApiConfig,
+
+    /// CoinMarketCap client (requires an API key)
+    pub coinmarketcap: PriceApiConfig,
+
+    /// How long fetched prices are served before refetching (in milliseconds)
+    pub cache_ttl_ms: u64,
+
+    /// Timeout of one API request (in milliseconds)
+    pub request_timeout_ms: u64,
+
+    /// How long a source is skipped after a failed request (in seconds)
+    pub failure_backoff_seconds: u64,
+}
+
+impl Default for PriceApisConfig {
+    fn default() -> Self {
+        Self {
+            coingecko: PriceApiConfig {
+                enabled: true,
+                base_url: "https://api.coingecko.com/api/v3".to_string(),
+                api_key: None,
+                min_request_interval_ms: 10_000,
+                daily_request_budget: None,
+            },
+            coinmarketcap: PriceApiConfig {
+                enabled: false,
+                base_url: "https://pro-api.coinmarketcap.com".to_string(),
+                api_key: None,
+                min_request_interval_ms: 10_000,
+                daily_request_budget: None,
+            },
+            cache_ttl_ms: 30_000,
+            request_timeout_ms: 5_000,
+            failure_backoff_seconds: 60,
+        }
+    }
+}
+
+/// Configuration of one price API client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceApiConfig {
+    /// Whether the API is used as a price source
+    pub enabled: bool,
+
+    /// Base URL of the API
+    pub base_url: String,
+
+    /// API key (loaded from the environment)
+    #[serde(skip_serializing)]
+    pub api_key: Option<String>,
+
+    /// Minimum time between requests, to stay within the API's rate limit (in milliseconds)
+    pub min_request_interval_ms: u64,
+
+    /// Requests allowed per UTC day, spread over the hours the bot trades in (unlimited if unset)
+    #[serde(default)]
+    pub daily_request_budget: Option<u64>,
+}
+
+/// Trading window configuration
+///
+/// Outside the trading windows opportunities are still scanned and recorded, but not executed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScheduleConfig {
+    /// Recurring UTC hour ranges during which execution is disabled
+    #[serde(default)]
+    pub blackout_hours: Vec<BlackoutHoursConfig>,
+
+    /// One-off time ranges (e.g. around known high-risk events) during which execution is disabled
+    #[serde(default)]
+    pub blackout_events: Vec<BlackoutEventConfig>,
+}
+
+/// A recurring range of UTC hours during which execution is disabled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlackoutHoursConfig {
+    /// First blocked hour (0-23, UTC)
+    pub start_hour: u8,
+
+    /// Hour at which execution resumes (1-24, UTC); ranges may wrap past midnight
+    pub end_hour: u8,
+
+    /// Days on which the range applies (every day if empty)
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+}
+
+/// A one-off time range during which execution is disabled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlackoutEventConfig {
+    /// Event name, used in logs
+    pub name: String,
+
+    /// Start of the range (Unix timestamp, seconds)
+    pub start: u64,
+
+    /// End of the range (Unix timestamp, seconds)
+    pub end: u64,
+}
+
+/// Day of the week
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+/// Per-route loss throttling configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteGuardConfig {
+    /// Whether losing routes are suspended
+    pub enabled: bool,
+
+    /// Consecutive realized losses or reverts before a route is suspended
+    pub max_consecutive_losses: u32,
+
+    /// How long a suspended route is skipped (in seconds)
+    pub cooldown_seconds: u64,
+
+    /// Reverts within an hour before a route is quarantined (defaults to 3, 0 to never
+    /// quarantine)
+    pub max_reverts_per_hour: Option<u32>,
+
+    /// How long a quarantined route is skipped (in seconds, defaults to 3600)
+    pub quarantine_seconds: Option<u64>,
+}
+
+impl Default for RouteGuardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_consecutive_losses: 3,
+            cooldown_seconds: 1800,
+            max_reverts_per_hour: None,
+            quarantine_seconds: None,
+        }
+    }
+}
+
+/// Yield-based scan tier configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairTiersConfig {
+    /// Whether unproductive pairs are demoted to the low-frequency tier
+    pub enabled: bool,
+
+    /// Days without an executed opportunity before a pair is demoted
+    pub demote_after_days: u64,
+
+    /// Demoted pairs are scanned once every this many scans
+    pub dormant_scan_interval: u64,
+}
+
+impl Default for PairTiersConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            demote_after_days: 7,
+            dormant_scan_interval: 50,
+        }
+    }
+}
+
+/// Wallet reconciliation configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletConfig {
+    /// Whether the wallet's nonce, balance and recent transactions are checked on startup
+    pub reconcile_on_startup: bool,
+
+    /// Number of recent blocks searched for transactions the bot did not send
+    pub reconcile_lookback_blocks: u64,
+
+    /// Balance below which an alert is raised (in ETH)
+    pub min_balance_eth: f64,
+}
+
+impl Default for WalletConfig {
+    fn default() -> Self {
+        Self {
+            reconcile_on_startup: true,
+            reconcile_lookback_blocks: 100,
+            min_balance_eth: 0.05,
+        }
+    }
+}
+
+/// Debugging configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DebugConfig {
+    /// Block numbers for which every decision is recorded into a block trace
+    #[serde(default)]
+    pub trace_blocks: Vec<u64>,
+
+    /// Directory finished block traces are written to as JSON (kept in memory only if unset)
+    pub trace_dir: Option<String>,
+}
+
+/// Log redaction configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Whether configured private keys and API keys are masked in log output
+    pub redact_secrets: bool,
+
+    /// Whether calldata, signatures and other hex blobs longer than a hash are cut down to
+    /// their leading four bytes
+    pub redact_calldata: bool,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            redact_secrets: true,
+            redact_calldata: true,
+        }
+    }
+}
+
+/// Read-only observer configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ObserverConfig {
+    /// Address the observer serves its read endpoints on
+    pub listen_address: String,
+
+    /// Number of records returned by the recent trades and opportunities endpoints
+    pub recent_limit: usize,
+}
+
+impl Default for ObserverConfig {
+    fn default() -> Self {
+        Self {
+            listen_address: "127.0.0.1:9470".to_string(),
+            recent_limit: 50,
+        }
+    }
+}
+
+/// Task supervision and shutdown configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuntimeConfig {
+    /// Time allowed for a graceful shutdown to drain in-flight transactions (in seconds,
+    /// defaults to 120)
+    pub shutdown_timeout_secs: Option<u64>,
+
+    /// Times a panicking task is restarted before it is left stopped (defaults to 5)
+    pub max_task_restarts: Option<u32>,
+}
+
+/// Trade history storage configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageConfig {
+    /// Whether opportunities and trades are persisted
+    pub enabled: bool,
+
+    /// Path of the SQLite database file
+    pub path: String,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: "data/arbitrage.db".to_string(),
+        }
+    }
+}
+
+/// Alerting configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertsConfig {
+    /// Webhook URL that receives alerts as JSON (alerts are only logged if unset)
+    pub webhook_url: Option<String>,
+
+    /// Minimum severity delivered to the webhook
+    #[serde(default = "default_alert_severity")]
+    pub min_severity: AlertSeverity,
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self {
+            webhook_url: None,
+            min_severity: default_alert_severity(),
+        }
+    }
+}
+
+/// Severity of an alert
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum AlertSeverity {
+    /// Informational, no action needed
+    #[serde(rename = "info")]
+    Info,
+
+    /// Degraded operation, should be looked at
+    #[serde(rename = "warning")]
+    Warning,
+
+    /// Requires immediate attention
+    #[serde(rename = "critical")]
+    Critical,
+}
+
+/// Default minimum severity delivered to the alert webhook
+fn default_alert_severity() -> AlertSeverity {
+    AlertSeverity::Warning
+}
+
+/// Strategy experiment (A/B test) configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExperimentConfig {
+    /// Whether opportunities are randomly assigned to the variants below
+    pub enabled: bool,
+
+    /// Strategy parameter sets to compare
+    #[serde(default)]
+    pub variants: Vec<StrategyVariantConfig>,
+}
+
+/// A named set of strategy parameters; unset values fall back to the base configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyVariantConfig {
+    /// Variant name, recorded with every execution it handles
+    pub name: String,
+
+    /// Relative share of opportunities assigned to this variant
+    #[serde(default = "default_variant_weight")]
+    pub weight: f64,
+
+    /// Minimum profit threshold (in USD)
+    pub min_profit_threshold: Option<f64>,
+
+    /// Flash loan size as a multiple of the estimated profit (sizing aggressiveness)
+    pub sizing_multiplier: Option<f64>,
+
+    /// Multiplier applied to the optimized gas price (tip curve)
+    pub tip_multiplier: Option<f64>,
+}
+
+/// Default weight of a strategy variant
+fn default_variant_weight() -> f64 {
+    1.0
 }
 
 /// Load configuration from file and environment variables
@@ -256,68 +1432,742 @@ pub fn load_config() -> Result<Arc<Config>> {
     config.ethereum.private_key = std::env::var("ETHEREUM_PRIVATE_KEY").ok();
     config.ethereum.alchemy_api_key = std::env::var("ALCHEMY_API_KEY").ok();
     config.mev_share.api_key = std::env::var("MEV_SHARE_API_KEY").ok();
+    config.mev_share.signing_key = std::env::var("MEV_SHARE_SIGNING_KEY").ok();
+    config.price_apis.coingecko.api_key = std::env::var("COINGECKO_API_KEY").ok();
+    config.price_apis.coinmarketcap.api_key = std::env::var("COINMARKETCAP_API_KEY").ok();
+    config.gas.sources.blocknative_api_key = std::env::var("BLOCKNATIVE_API_KEY").ok();
 
-    // Set the websocket URL based on the RPC URL and Alchemy API key if not provided
-    if config.ethereum.ws_url.is_none() {
-        if let Some(api_key) = &config.ethereum.alchemy_api_key {
-            if config.ethereum.rpc_url.contains("alchemyapi.io") {
-                config.ethereum.ws_url =
-                    Some(format!("wss://eth-mainnet.ws.alchemyapi.io/v2/{}", api_key));
-            }
-        } else {
-            // Default to converting http to ws
-            config.ethereum.ws_url = Some(config.ethereum.rpc_url.replace("http", "ws"));
-        }
-    }
+    derive_ws_url(&mut config.ethereum);
 
     // Validate configuration
     validate_config(&config)?;
 
+    // Tables no module reads are most likely misspelled section names
+    for name in config.sections.0.keys() {
+        if !MODULE_SECTIONS.iter().any(|section| section.name == name) {
+            warn!("Unknown configuration section [{}] is ignored", name);
+        }
+    }
+
     Ok(Arc::new(config))
 }
 
-/// Validate the configuration
+/// Set the websocket URL based on the RPC URL and Alchemy API key if not provided
+fn derive_ws_url(ethereum: &mut EthereumConfig) {
+    if ethereum.ws_url.is_some() {
+        return;
+    }
+
+    if let Some(api_key) = &ethereum.alchemy_api_key {
+        if ethereum.rpc_url.contains("alchemyapi.io") && ethereum.chain_id == 1 {
+            ethereum.ws_url = Some(format!("wss://eth-mainnet.ws.alchemyapi.io/v2/{}", api_key));
+        }
+    } else {
+        // Default to converting http to ws
+        ethereum.ws_url = Some(ethereum.rpc_url.replace("http", "ws"));
+    }
+}
+
+/// Build the configuration of every chain the bot runs on, the primary chain first
+///
+/// Each chain gets a full configuration of its own, so the modules stay unaware of other
+/// chains. Trade history is kept in one database per chain, since nonces and trades are only
+/// meaningful on the chain they were recorded on.
+pub fn chain_configs(config: &Config) -> Vec<Arc<Config>> {
+    let mut primary = config.clone();
+    primary.chains.clear();
+
+    let mut configs = vec![Arc::new(primary.clone())];
+    for chain in &config.chains {
+        configs.push(Arc::new(chain_config(&primary, chain)));
+    }
+
+    configs
+}
+
+/// Derive the configuration of an additional chain from the primary chain's
+fn chain_config(primary: &Config, chain: &ChainConfig) -> Config {
+    let mut config = primary.clone();
+
+    config.ethereum = chain.ethereum.clone();
+    config.ethereum.private_key = primary.ethereum.private_key.clone();
+    config.ethereum.alchemy_api_key = primary.ethereum.alchemy_api_key.clone();
+    if config.ethereum.wallet_address.is_empty() {
+        config.ethereum.wallet_address = primary.ethereum.wallet_address.clone();
+    }
+    derive_ws_url(&mut config.ethereum);
+
+    config.dex = chain.dex.clone();
+    config.flash_loan = chain.flash_loan.clone();
+    config.arbitrage.contract = chain.contract.clone().unwrap_or(ContractConfig {
+        contract_address: None,
+        ..primary.arbitrage.contract.clone()
+    });
+    if let Some(gas) = &chain.gas {
+        config.gas = gas.clone();
+        config.gas.sources.blocknative_api_key = primary.gas.sources.blocknative_api_key.clone();
+    }
+    match &chain.mev_share {
+        Some(mev_share) => {
+            config.mev_share = MevShareConfig {
+                api_key: primary.mev_share.api_key.clone(),
+                signing_key: primary.mev_share.signing_key.clone(),
+                ..mev_share.clone()
+            }
+        }
+        None => config.mev_share.enabled = false,
+    }
+
+    config.storage.path = chain_storage_path(&primary.storage.path, &chain.name);
+
+    config
+}
+
+/// Name a chain's trade history database after the primary one (`arbitrage.db` becomes
+/// `arbitrage-<chain>.db`)
+fn chain_storage_path(path: &str, chain_name: &str) -> String {
+    let path = std::path::Path::new(path);
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let file_name = match path.extension() {
+        Some(extension) => format!("{}-{}.{}", stem, chain_name, extension.to_string_lossy()),
+        None => format!("{}-{}", stem, chain_name),
+    };
+
+    path.with_file_name(file_name)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Problems found in a configuration, collected so they are all reported at once
+#[derive(Debug, Default)]
+pub struct ConfigReport {
+    problems: Vec<String>,
+}
+
+impl ConfigReport {
+    /// Create an empty report
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a problem
+    pub fn problem(&mut self, problem: impl Into<String>) {
+        self.problems.push(problem.into());
+    }
+
+    /// Record the error of a check, returning its value if it passed
+    pub fn check<T>(&mut self, result: Result<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.problem(format!("{:#}", e));
+                None
+            }
+        }
+    }
+
+    /// Check that a field holds an address, returning it if it does
+    pub fn address(&mut self, field: &str, address: &str) -> Option<Address> {
+        match validate_and_parse_address(address) {
+            Ok(address) => Some(address),
+            Err(_) => {
+                self.problem(format!("{} is not a valid address: '{}'", field, address));
+                None
+            }
+        }
+    }
+
+    /// Fail with every problem found, one per line
+    pub fn finish(self, subject: &str) -> Result<()> {
+        if self.problems.is_empty() {
+            return Ok(());
+        }
+
+        anyhow::bail!(
+            "{} has {} problem{}:\n  - {}",
+            subject,
+            self.problems.len(),
+            if self.problems.len() == 1 { "" } else { "s" },
+            self.problems.join("\n  - ")
+        )
+    }
+}
+
+/// Validate the configuration, reporting every problem found rather than the first
 fn validate_config(config: &Config) -> Result<()> {
+    let mut report = ConfigReport::new();
+    collect_problems(config, &mut report);
+
+    report.finish("Configuration")
+}
+
+/// Check the configuration, recording its problems in `report`
+fn collect_problems(config: &Config, report: &mut ConfigReport) {
     // Validate Ethereum configuration
     if config.ethereum.rpc_url.is_empty() {
-        anyhow::bail!("Ethereum RPC URL is required");
+        report.problem("ethereum.rpc_url is required");
     }
 
     if config.ethereum.chain_id == 0 {
-        anyhow::bail!("Ethereum chain ID is required");
+        report.problem("ethereum.chain_id is required");
+    }
+
+    if config
+        .ethereum
+        .fallback_rpc_urls
+        .iter()
+        .chain(&config.ethereum.read_rpc_url)
+        .any(|url| url.is_empty())
+    {
+        report.problem("ethereum.fallback_rpc_urls and ethereum.read_rpc_url must not be empty");
+    }
+
+    if config.ethereum.rpc_health_check_interval_seconds == Some(0) {
+        report.problem("ethereum.rpc_health_check_interval_seconds must be greater than zero");
     }
 
     // Validate wallet configuration
-    if config.ethereum.private_key.is_none() && config.ethereum.wallet_address.is_empty() {
-        anyhow::bail!("Either private key or wallet address is required");
+    if config.ethereum.wallet_address.is_empty() {
+        if config.ethereum.private_key.is_none() {
+            report.problem(
+                "Either a private key (ETHEREUM_PRIVATE_KEY) or ethereum.wallet_address is required",
+            );
+        }
+    } else {
+        report.address("ethereum.wallet_address", &config.ethereum.wallet_address);
+    }
+
+    if let Some(multicall_address) = &config.ethereum.multicall_address {
+        report.address("ethereum.multicall_address", multicall_address);
     }
 
     // Validate MEV-Share configuration
     if config.mev_share.enabled && config.mev_share.api_url.is_empty() {
-        anyhow::bail!("MEV-Share API URL is required when MEV-Share is enabled");
+        report.problem("mev_share.api_url is required when MEV-Share is enabled");
+    }
+
+    let bid = &config.mev_share.bid;
+    if bid.enabled {
+        if bid.profit_share <= 0.0 || bid.profit_share >= 1.0 || bid.profit_share.is_nan() {
+            report.problem("mev_share.bid.profit_share must be between 0 and 1");
+        }
+        if bid.escalation_factor < 1.0 || !bid.escalation_factor.is_finite() {
+            report.problem("mev_share.bid.escalation_factor must be at least 1");
+        }
+        if config.mev_share.max_validator_tip == 0 {
+            report.problem("Bidding tips needs a mev_share.max_validator_tip above zero");
+        }
+    }
+
+    let bundle = &config.mev_share.bundle;
+    for hint in &bundle.hints {
+        if !BUNDLE_HINTS.contains(&hint.as_str()) {
+            report.problem(format!(
+                "Unknown mev_share.bundle.hints entry {:?} (expected one of {})",
+                hint,
+                BUNDLE_HINTS.join(", ")
+            ));
+        }
+    }
+    for recipient in &bundle.refund_config {
+        report.address("mev_share.bundle.refund_config.address", &recipient.address);
+        if recipient.percent == 0 || recipient.percent > 100 {
+            report.problem("mev_share.bundle.refund_config percent must be between 1 and 100");
+        }
+    }
+    let refund_percent: u32 = bundle
+        .refund_config
+        .iter()
+        .map(|recipient| u32::from(recipient.percent))
+        .sum();
+    if refund_percent > 100 {
+        report.problem(format!(
+            "mev_share.bundle.refund_config percents add up to {}, more than 100",
+            refund_percent
+        ));
+    }
+    if bundle
+        .backrun_refund_percent
+        .is_some_and(|percent| percent > 100)
+    {
+        report.problem("mev_share.bundle.backrun_refund_percent must be at most 100");
+    }
+
+    if config.security.replace_after_blocks == Some(0) {
+        report.problem("security.replace_after_blocks must be greater than zero");
+    }
+
+    for (name, endpoint) in [
+        ("ethereum.rpc_http", &config.ethereum.rpc_http),
+        ("ethereum.peer_rpc_http", &config.ethereum.peer_rpc_http),
+        (
+            "ethereum.fallback_rpc_http",
+            &config.ethereum.fallback_rpc_http,
+        ),
+        ("mev_share.api_http", &config.mev_share.api_http),
+    ] {
+        report.check(validate_http_endpoint(endpoint).with_context(|| format!("Invalid {}", name)));
+    }
+
+    for section in &MODULE_SECTIONS {
+        report.check((section.validate)(config).with_context(|| format!("[{}]", section.name)));
+    }
+
+    if config.runtime.shutdown_timeout_secs == Some(0) {
+        report.problem("runtime.shutdown_timeout_secs must be greater than zero");
+    }
+
+    // Validate DEX configuration
+    for (name, dex) in [
+        ("uniswap", &config.dex.uniswap),
+        ("sushiswap", &config.dex.sushiswap),
+        ("curve", &config.dex.curve),
+    ] {
+        if !dex.enabled {
+            continue;
+        }
+        report.address(
+            &format!("dex.{}.factory_address", name),
+            &dex.factory_address,
+        );
+        report.address(&format!("dex.{}.router_address", name), &dex.router_address);
+        for pool in &dex.pools {
+            report.address(&format!("dex.{}.pools entry", name), pool);
+        }
+    }
+
+    let discovery = &config.dex.pair_discovery;
+    if discovery.enabled && discovery.chunk_size == 0 {
+        report.problem("dex.pair_discovery.chunk_size must be greater than zero");
+    }
+
+    let trident = &config.dex.trident;
+    if trident.enabled {
+        report.address("dex.trident.bento_box_address", &trident.bento_box_address);
+        let factories = [
+            (
+                "dex.trident.constant_product_factory",
+                &trident.constant_product_factory,
+            ),
+            ("dex.trident.stable_factory", &trident.stable_factory),
+        ];
+        if factories.iter().all(|(_, factory)| factory.is_empty()) {
+            report.problem("Trident needs a constant-product or stable pool deployer");
+        }
+        for (field, factory) in factories
+            .into_iter()
+            .filter(|(_, factory)| !factory.is_empty())
+        {
+            report.address(field, factory);
+        }
     }
 
     // Validate arbitrage configuration
     if config.arbitrage.min_profit_threshold <= 0.0 {
-        anyhow::bail!("Minimum profit threshold must be greater than zero");
+        report.problem("arbitrage.min_profit_threshold must be greater than zero");
     }
 
     if config.arbitrage.max_hops == 0 {
-        anyhow::bail!("Maximum hops must be greater than zero");
+        report.problem("arbitrage.max_hops must be greater than zero");
+    }
+
+    if config.arbitrage.triangular.enabled && config.arbitrage.max_hops < 3 {
+        report.problem("Triangular scanning needs an arbitrage.max_hops of at least 3");
+    }
+
+    if !(0.0..=100.0).contains(&config.arbitrage.profit_tolerance) {
+        report.problem("arbitrage.profit_tolerance must be between 0 and 100");
+    }
+
+    if config.arbitrage.latency_budget_ms == 0 {
+        report.problem("arbitrage.latency_budget_ms must be greater than zero");
+    }
+
+    if config.arbitrage.sweep_interval_ms == 0 {
+        report.problem("arbitrage.sweep_interval_ms must be greater than zero");
+    }
+
+    if config.arbitrage.max_parallel_executions == 0 {
+        report.problem("arbitrage.max_parallel_executions must be greater than zero");
+    }
+
+    if let Some(contract_address) = &config.arbitrage.contract.contract_address {
+        report.address("arbitrage.contract.contract_address", contract_address);
+    }
+
+    let sizing = &config.arbitrage.sizing;
+    if sizing.enabled {
+        if sizing.grid_points < 3 {
+            report.problem("arbitrage.sizing needs at least 3 grid_points");
+        }
+        if sizing.min_input <= 0.0 || sizing.growth_factor <= 1.0 {
+            report
+                .problem("arbitrage.sizing needs a positive min_input and a growth_factor above 1");
+        }
+        if !(0.0..=1.0).contains(&sizing.peak_tolerance) {
+            report.problem("arbitrage.sizing.peak_tolerance must be between 0 and 1");
+        }
+    }
+
+    let pairs = &config.arbitrage.pairs;
+    for reference in &pairs.blacklist {
+        if config.flash_loan.token(reference).is_none()
+            && validate_and_parse_address(reference).is_err()
+        {
+            report.problem(format!(
+                "Blacklisted token {} is neither a flash loan token nor an address",
+                reference
+            ));
+        }
+    }
+    let blacklisted = pairs.blacklisted(&config.flash_loan);
+    for pair in &pairs.whitelist {
+        let mut addresses = Vec::new();
+        for reference in [&pair.token_a, &pair.token_b] {
+            let Some(token) = config.flash_loan.token(reference) else {
+                report.problem(format!(
+                    "Whitelisted pair token {} is not a flash loan token",
+                    reference
+                ));
+                continue;
+            };
+            // Token addresses are reported with the flash loan tokens
+            let Ok(address) = validate_and_parse_address(&token.address) else {
+                continue;
+            };
+            if blacklisted.contains(&address) {
+                report.problem(format!(
+                    "Whitelisted pair token {} is blacklisted",
+                    reference
+                ));
+            }
+            addresses.push(address);
+        }
+        if addresses.len() == 2 && addresses[0] == addresses[1] {
+            report.problem(format!(
+                "Whitelisted pair {}/{} needs two different tokens",
+                pair.token_a, pair.token_b
+            ));
+        }
+        if let Some(input_amount) = pair.input_amount {
+            if !input_amount.is_finite() || input_amount <= 0.0 {
+                report.problem(format!(
+                    "Input amount of pair {}/{} must be greater than zero",
+                    pair.token_a, pair.token_b
+                ));
+            }
+        }
     }
 
     // Validate gas configuration
     if config.gas.max_gas_price == 0 {
-        anyhow::bail!("Maximum gas price must be greater than zero");
+        report.problem("gas.max_gas_price must be greater than zero");
     }
 
     if config.gas.gas_limit == 0 {
-        anyhow::bail!("Gas limit must be greater than zero");
+        report.problem("gas.gas_limit must be greater than zero");
+    }
+
+    if let Some(multiplier) = config.gas.gas_estimate_multiplier {
+        if multiplier < 1.0 {
+            report.problem("gas.gas_estimate_multiplier must be at least 1");
+        }
+    }
+
+    let gas_sources = &config.gas.sources;
+    if gas_sources.sources.is_empty() {
+        report.problem("gas.sources.sources needs at least one gas source");
+    }
+    for (index, source) in gas_sources.sources.iter().enumerate() {
+        if gas_sources.sources[..index].contains(source) {
+            report.problem(format!("gas.sources.sources lists {:?} twice", source));
+        }
+    }
+    if gas_sources.sources.contains(&GasSourceKind::Alchemy) {
+        if config.ethereum.alchemy_api_key.is_none() {
+            report.problem("The Alchemy gas source needs ALCHEMY_API_KEY");
+        }
+        if Chain::from_id(config.ethereum.chain_id)
+            .alchemy_network()
+            .is_none()
+        {
+            report.problem(format!(
+                "The Alchemy gas source does not support chain {}",
+                config.ethereum.chain_id
+            ));
+        }
+    }
+    if gas_sources.sources.contains(&GasSourceKind::Blocknative)
+        && gas_sources.blocknative_api_key.is_none()
+    {
+        report.problem("The Blocknative gas source needs BLOCKNATIVE_API_KEY");
+    }
+    if gas_sources.failure_threshold == 0 {
+        report.problem("gas.sources.failure_threshold must be greater than zero");
+    }
+
+    let fee_history = &config.gas.fee_history;
+    if fee_history.window_blocks == 0 || fee_history.window_blocks > 1024 {
+        report.problem("gas.fee_history.window_blocks must be between 1 and 1024");
+    }
+    let percentiles = InclusionUrgency::ALL.map(|urgency| fee_history.percentile(urgency));
+    if percentiles
+        .iter()
+        .any(|percentile| !(0.0..=100.0).contains(percentile))
+        || percentiles.windows(2).any(|pair| pair[0] > pair[1])
+    {
+        report.problem(
+            "gas.fee_history percentiles must be between 0 and 100, from low to high urgency",
+        );
+    }
+
+    // Validate flash loan configuration
+    let flash_loan = &config.flash_loan;
+    if flash_loan.providers.is_empty() {
+        report.problem("flash_loan.providers needs at least one flash loan provider");
+    }
+
+    if flash_loan.providers.contains(&FlashLoanProvider::Aave) {
+        report.address(
+            "flash_loan.aave_lending_pool",
+            &flash_loan.aave_lending_pool,
+        );
+    }
+    for (field, address) in [
+        ("flash_loan.balancer_vault", &flash_loan.balancer_vault),
+        (
+            "flash_loan.uniswap_v3_factory",
+            &flash_loan.uniswap_v3_factory,
+        ),
+        ("flash_loan.morpho_blue", &flash_loan.morpho_blue),
+        ("flash_loan.spark_pool", &flash_loan.spark_pool),
+    ] {
+        if let Some(address) = address {
+            report.address(field, address);
+        }
+    }
+
+    for token in &flash_loan.tokens {
+        report.address(
+            &format!("Address of flash loan token {}", token.symbol),
+            &token.address,
+        );
+    }
+
+    // Validate trading windows
+    for window in &config.schedule.blackout_hours {
+        if window.start_hour > 23 || window.end_hour > 24 || window.start_hour == window.end_hour {
+            report.problem(format!(
+                "Invalid blackout hours {}-{}: start must be 0-23, end 1-24 and different from start",
+                window.start_hour, window.end_hour
+            ));
+        }
+    }
+
+    for event in &config.schedule.blackout_events {
+        if event.end <= event.start {
+            report.problem(format!(
+                "Blackout event {} must end after it starts",
+                event.name
+            ));
+        }
+    }
+
+    // Validate price API configuration
+    if config.price_apis.cache_ttl_ms == 0 {
+        report.problem("price_apis.cache_ttl_ms must be greater than zero");
+    }
+
+    // Validate the additional chains as complete configurations of their own
+    let mut chain_ids = vec![config.ethereum.chain_id];
+    let mut chain_names: Vec<&str> = Vec::new();
+    for chain in &config.chains {
+        if chain.name.is_empty()
+            || !chain
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            report.problem(format!(
+                "Chain name '{}' must be non-empty and only contain letters, digits, '-' and '_'",
+                chain.name
+            ));
+        }
+        if chain_names.contains(&chain.name.as_str()) {
+            report.problem(format!(
+                "Chain name '{}' is configured more than once",
+                chain.name
+            ));
+        }
+        if chain_ids.contains(&chain.ethereum.chain_id) {
+            report.problem(format!(
+                "Chain ID {} is configured more than once",
+                chain.ethereum.chain_id
+            ));
+        }
+        chain_names.push(&chain.name);
+        chain_ids.push(chain.ethereum.chain_id);
+
+        let mut primary = config.clone();
+        primary.chains.clear();
+        let mut chain_report = ConfigReport::new();
+        collect_problems(&chain_config(&primary, chain), &mut chain_report);
+        // Settings the chain shares with the primary chain are reported once
+        for problem in chain_report.problems {
+            if !report.problems.contains(&problem) {
+                report.problem(format!("chain {}: {}", chain.name, problem));
+            }
+        }
+    }
+}
+
+/// Validate the HTTP client settings of an endpoint
+fn validate_http_endpoint(endpoint: &HttpEndpointConfig) -> Result<()> {
+    if endpoint.basic_auth_password.is_some() && endpoint.basic_auth_username.is_none() {
+        anyhow::bail!("Basic auth password is set without a username");
+    }
+
+    if let Some(proxy) = &endpoint.proxy {
+        let scheme = proxy.split("://").next().unwrap_or_default();
+        match scheme {
+            "http" | "https" => {}
+            "socks5" | "socks5h" => anyhow::bail!(
+                "SOCKS proxy {} is not supported by this build (needs reqwest's socks feature)",
+                scheme
+            ),
+            _ => anyhow::bail!("Proxy URL must start with http:// or https://"),
+        }
     }
 
     Ok(())
 }
 
+/// Fields that may embed credentials (e.g. API keys in RPC URLs); diffs only say they changed
+const REDACTED_FIELDS: [&str; 12] = [
+    "ethereum.rpc_url",
+    "ethereum.ws_url",
+    "ethereum.fallback_rpc_urls",
+    "ethereum.read_rpc_url",
+    "ethereum.peer_rpc_urls",
+    "ethereum.rpc_http",
+    "ethereum.peer_rpc_http",
+    "ethereum.fallback_rpc_http",
+    "mev_share.api_url",
+    "mev_share.api_http",
+    "alerts.webhook_url",
+    "api.token",
+];
+
+/// Placeholder for redacted values
+const REDACTED: &str = "<redacted>";
+
+/// A change of one configuration value
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigChange {
+    /// Dotted path of the changed field (e.g. `arbitrage.min_profit_threshold`)
+    pub path: String,
+
+    /// Previous value, as JSON (`None` if the field is new)
+    pub old: Option<String>,
+
+    /// New value, as JSON (`None` if the field was removed)
+    pub new: Option<String>,
+}
+
+impl std::fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => write!(f, "{}: {} -> {}", self.path, old, new),
+            (None, Some(new)) => write!(f, "{}: added {}", self.path, new),
+            (Some(old), None) => write!(f, "{}: removed {}", self.path, old),
+            (None, None) => write!(f, "{}: unchanged", self.path),
+        }
+    }
+}
+
+/// Serialize the configuration with credential-bearing fields redacted
+///
+/// Secrets are already skipped during serialization; the URLs in `REDACTED_FIELDS` are
+/// replaced by a digest so changes to them are still detected without revealing them.
+pub fn redacted_snapshot(config: &Config) -> serde_json::Value {
+    let mut snapshot = serde_json::to_value(config).unwrap_or_default();
+    for path in REDACTED_FIELDS {
+        let mut segments = path.split('.');
+        let section = segments.next().unwrap_or_default();
+        let field = segments.next().unwrap_or_default();
+        if let Some(value) = snapshot
+            .get_mut(section)
+            .and_then(|section| section.get_mut(field))
+        {
+            if !value.is_null() {
+                *value = serde_json::Value::String(format!(
+                    "{} {}",
+                    REDACTED,
+                    &digest(value.to_string().as_bytes())[..8]
+                ));
+            }
+        }
+    }
+
+    snapshot
+}
+
+/// Get a short hash identifying a configuration, recorded with opportunities and trades
+pub fn config_hash(config: &Config) -> String {
+    digest(redacted_snapshot(config).to_string().as_bytes())[..16].to_string()
+}
+
+/// List the values that differ between two configuration snapshots
+pub fn diff_snapshots(old: &serde_json::Value, new: &serde_json::Value) -> Vec<ConfigChange> {
+    let mut changes = Vec::new();
+    diff_values("", old, new, &mut changes);
+    changes
+}
+
+/// Recursively compare two JSON values, descending into objects
+fn diff_values(
+    path: &str,
+    old: &serde_json::Value,
+    new: &serde_json::Value,
+    changes: &mut Vec<ConfigChange>,
+) {
+    match (old, new) {
+        (serde_json::Value::Object(old), serde_json::Value::Object(new)) => {
+            let keys: std::collections::BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+            for key in keys {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                match (old.get(key), new.get(key)) {
+                    (Some(old), Some(new)) => diff_values(&child, old, new, changes),
+                    (old, new) => changes.push(ConfigChange {
+                        path: child,
+                        old: old.map(|value| value.to_string()),
+                        new: new.map(|value| value.to_string()),
+                    }),
+                }
+            }
+        }
+        (old, new) if old != new => changes.push(ConfigChange {
+            path: path.to_string(),
+            old: Some(old.to_string()),
+            new: Some(new.to_string()),
+        }),
+        _ => {}
+    }
+}
+
+/// Hex-encoded Keccak-256 digest
+fn digest(data: &[u8]) -> String {
+    use sha3::{Digest, Keccak256};
+    hex::encode(Keccak256::digest(data))
+}
+
 /// Create a default configuration
 pub fn create_default_config() -> Config {
     Config {
@@ -331,18 +2181,58 @@ pub fn create_default_config() -> Config {
             wallet_address: "".to_string(),
             max_block_lookback: 10,
             ws_timeout_seconds: 30,
+            ws_reconnect_max_backoff_seconds: None,
             alchemy_api_key: None,
+            fallback_rpc_urls: vec![],
+            read_rpc_url: None,
+            rpc_health_check_interval_seconds: None,
+            peer_rpc_urls: vec![],
+            head_lag_threshold_blocks: None,
+            head_lag_grace_seconds: None,
+            multicall_address: None,
+            rpc_http: HttpEndpointConfig::default(),
+            peer_rpc_http: HttpEndpointConfig::default(),
+            fallback_rpc_http: HttpEndpointConfig::default(),
         },
         test_mode: false,
+        chains: Vec::new(),
+        price_apis: PriceApisConfig::default(),
+        experiments: ExperimentConfig::default(),
+        alerts: AlertsConfig::default(),
+        storage: StorageConfig::default(),
+        route_guard: RouteGuardConfig::default(),
+        pair_tiers: PairTiersConfig::default(),
+        schedule: ScheduleConfig::default(),
+        wallet: WalletConfig::default(),
+        debug: DebugConfig::default(),
+        logging: LoggingConfig::default(),
+        observer: ObserverConfig::default(),
+        runtime: RuntimeConfig::default(),
+        sections: ConfigSections::default(),
         mev_share: MevShareConfig {
             api_url: "https://mev-share.flashbots.net".to_string(),
             api_key: None,
+            signing_key: None,
             enabled: true,
             max_validator_tip: 2, // 2 gwei
+            merge_bundles: false,
+            max_bundle_size: None,
+            revert_tolerant_legs: false,
+            bundle_execution: true,
+            bundle_block_range: None,
+            max_bundle_rebids: None,
+            bid: BidConfig::default(),
+            bundle: BundleConfig::default(),
+            api_http: HttpEndpointConfig::default(),
         },
         flash_loan: FlashLoanConfig {
-            aave_lending_pool: "0x7d2768dE32b0b80b7a3454c06BdAc94A69DDc7A9".to_string(), // Aave V2 lending pool
-            max_borrow_amount: 100.0,                                                    // 100 ETH
+            aave_lending_pool: "0x87870Bca3F3fD6335C3F4ce8392D69350B4fA4E2".to_string(), // Aave V3 Pool
+            providers: default_flash_loan_providers(),
+            balancer_vault: None,
+            uniswap_v3_factory: None,
+            morpho_blue: None,
+            spark_pool: None,
+            max_borrow_amount: 100.0, // 100 ETH
             tokens: vec![
                 TokenConfig {
                     symbol: "WETH".to_string(),
@@ -380,18 +2270,29 @@ pub fn create_default_config() -> Config {
                 router_address: "0x8e764bE4288B842791989DB5b8ec067279829809".to_string(), // Curve router
                 pools: vec![],
             },
+            trident: TridentConfig::default(),
+            pair_discovery: PairDiscoveryConfig::default(),
+            quarantine: PoolQuarantineConfig::default(),
         },
         arbitrage: ArbitrageConfig {
             min_profit_threshold: 50.0, // $50
             max_hops: 3,
             slippage_tolerance: 0.5, // 0.5%
+            profit_tolerance: default_profit_tolerance(),
             evaluation_timeout_ms: 500,
             max_concurrent_evaluations: 5,
+            latency_budget_ms: default_latency_budget_ms(),
+            sweep_interval_ms: default_sweep_interval_ms(),
+            max_parallel_executions: default_max_parallel_executions(),
             contract: ContractConfig {
                 contract_address: None,
                 deploy_if_missing: true,
                 deployment_gas_limit: 5000000,
             },
+            sizing: SizingConfig::default(),
+            triangular: TriangularConfig::default(),
+            quote_block: QuoteBlock::default(),
+            pairs: PairsConfig::default(),
         },
         gas: GasConfig {
             strategy: GasStrategy::Eip1559,
@@ -399,13 +2300,21 @@ pub fn create_default_config() -> Config {
             base_fee_multiplier: 1.2,
             priority_fee: 2, // 2 gwei
             gas_limit: 500000,
+            gas_estimate_multiplier: None,
+            blob_base_fee_update_fraction: None,
+            fee_history: FeeHistoryConfig::default(),
+            sources: GasSourcesConfig::default(),
         },
         security: SecurityConfig {
             transaction_timeout: 60, // 60 seconds
+            min_confirmations: Some(2),
+            replace_after_blocks: None,
+            max_replacements: None,
             min_price_sources: 2,
             max_price_deviation: 1.0, // 1%
             simulate_transactions: true,
             max_execution_slippage: 1.0, // 1%
+            allowed_token_recipients: vec![],
         },
     }
 }
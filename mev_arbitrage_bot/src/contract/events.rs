@@ -0,0 +1,200 @@
+//! Contract Events Module
+//!
+//! This module is responsible for decoding the events the ArbitrageExecutor contract emits.
+//! The contract emits no event of its own when it receives a flash loan; the loan of a trade
+//! is its `ArbitrageExecuted` event's `amount_in`.
+
+use anyhow::{Context, Result};
+use ethers::abi::{LogParam, RawLog, Token};
+use ethers::types::{Address, Log, H256, U256};
+
+use crate::assets::ContractAbi;
+
+/// Event emitted by the ArbitrageExecutor contract
+#[derive(Debug, Clone)]
+pub enum ExecutorEvent {
+    /// A trade was executed
+    ArbitrageExecuted {
+        /// Token path of the trade
+        path: Vec<Address>,
+
+        /// Amount borrowed, in the first token of the path
+        amount_in: U256,
+
+        /// Amount the route returned, in the first token of the path
+        amount_out: U256,
+
+        /// Profit kept by the contract after repaying the loan, in the first token of the path
+        profit: U256,
+
+        /// DEX used for each swap
+        dex_path: Vec<String>,
+    },
+
+    /// The emergency stop was activated
+    EmergencyStopActivated {
+        /// Account that activated it
+        activator: Address,
+    },
+
+    /// The emergency stop was deactivated
+    EmergencyStopDeactivated {
+        /// Account that deactivated it
+        deactivator: Address,
+    },
+
+    /// A caller was authorized to execute trades
+    CallerAuthorized {
+        /// The authorized caller
+        caller: Address,
+    },
+
+    /// A caller's authorization was revoked
+    CallerUnauthorized {
+        /// The revoked caller
+        caller: Address,
+    },
+
+    /// Ownership of the contract changed
+    OwnershipTransferred {
+        /// Previous owner
+        previous_owner: Address,
+
+        /// New owner
+        new_owner: Address,
+    },
+
+    /// Tokens were recovered by the owner
+    TokensRecovered {
+        /// Token recovered
+        token: Address,
+
+        /// Amount recovered, in the token's smallest unit
+        amount: U256,
+    },
+
+    /// Ether was recovered by the owner
+    EthRecovered {
+        /// Amount recovered (in wei)
+        amount: U256,
+    },
+
+    /// The BentoBox used for Trident swaps changed
+    BentoBoxUpdated {
+        /// New BentoBox
+        bento_box: Address,
+    },
+
+    /// A lender was allowed or disallowed for the flash loans the contract takes itself
+    FlashLenderUpdated {
+        /// The lender (a Balancer Vault, Morpho Blue or an Aave V3 fork pool, or a Uniswap V3
+        /// factory whose pools lend)
+        lender: Address,
+
+        /// Whether the lender is allowed
+        allowed: bool,
+    },
+}
+
+impl ExecutorEvent {
+    /// Get the name of the ArbitrageExecutor event a log was emitted as, if it is one
+    pub fn name_of(log: &Log) -> Option<&'static str> {
+        let topic = *log.topics.first()?;
+        ContractAbi::ArbitrageExecutor
+            .abi()
+            .events()
+            .find(|event| event.signature() == topic)
+            .map(|event| event.name.as_str())
+    }
+
+    /// Get the topics of every ArbitrageExecutor event, for log filters
+    pub fn topics() -> Vec<H256> {
+        ContractAbi::ArbitrageExecutor
+            .abi()
+            .events()
+            .map(|event| event.signature())
+            .collect()
+    }
+
+    /// Decode an ArbitrageExecutor log
+    pub fn decode(log: &Log) -> Result<Self> {
+        let name = Self::name_of(log).context("Not an ArbitrageExecutor event")?;
+        let event = ContractAbi::ArbitrageExecutor.abi().event(name)?;
+        let params = event
+            .parse_log(RawLog {
+                topics: log.topics.clone(),
+                data: log.data.to_vec(),
+            })?
+            .params;
+        let param = |name: &str| -> Result<Token> {
+            params
+                .iter()
+                .find(|param: &&LogParam| param.name == name)
+                .map(|param| param.value.clone())
+                .with_context(|| format!("{} event without {}", event.name, name))
+        };
+        let address = |name: &str| -> Result<Address> {
+            param(name)?
+                .into_address()
+                .with_context(|| format!("{} of {} is not an address", name, event.name))
+        };
+        let uint = |name: &str| -> Result<U256> {
+            param(name)?
+                .into_uint()
+                .with_context(|| format!("{} of {} is not an integer", name, event.name))
+        };
+
+        Ok(match name {
+            "ArbitrageExecuted" => ExecutorEvent::ArbitrageExecuted {
+                path: param("path")?
+                    .into_array()
+                    .context("path of ArbitrageExecuted is not an array")?
+                    .into_iter()
+                    .filter_map(Token::into_address)
+                    .collect(),
+                amount_in: uint("amountIn")?,
+                amount_out: uint("amountOut")?,
+                profit: uint("profit")?,
+                dex_path: param("dexPath")?
+                    .into_array()
+                    .context("dexPath of ArbitrageExecuted is not an array")?
+                    .into_iter()
+                    .filter_map(Token::into_string)
+                    .collect(),
+            },
+            "EmergencyStopActivated" => ExecutorEvent::EmergencyStopActivated {
+                activator: address("activator")?,
+            },
+            "EmergencyStopDeactivated" => ExecutorEvent::EmergencyStopDeactivated {
+                deactivator: address("deactivator")?,
+            },
+            "CallerAuthorized" => ExecutorEvent::CallerAuthorized {
+                caller: address("caller")?,
+            },
+            "CallerUnauthorized" => ExecutorEvent::CallerUnauthorized {
+                caller: address("caller")?,
+            },
+            "OwnershipTransferred" => ExecutorEvent::OwnershipTransferred {
+                previous_owner: address("previousOwner")?,
+                new_owner: address("newOwner")?,
+            },
+            "TokensRecovered" => ExecutorEvent::TokensRecovered {
+                token: address("token")?,
+                amount: uint("amount")?,
+            },
+            "ETHRecovered" => ExecutorEvent::EthRecovered {
+                amount: uint("amount")?,
+            },
+            "BentoBoxUpdated" => ExecutorEvent::BentoBoxUpdated {
+                bento_box: address("bentoBox")?,
+            },
+            "FlashLenderUpdated" => ExecutorEvent::FlashLenderUpdated {
+                lender: address("lender")?,
+                allowed: param("allowed")?
+                    .into_bool()
+                    .context("allowed of FlashLenderUpdated is not a boolean")?,
+            },
+            other => anyhow::bail!("Unhandled ArbitrageExecutor event {}", other),
+        })
+    }
+}
@@ -0,0 +1,33 @@
+//! Execution Limits Module
+//!
+//! This module is responsible for the bounds the executor contract holds a trade to: the
+//! slippage each swap may take, the least profit the whole route must realize and the time
+//! after which it may no longer execute. A transaction that lands after the pools have moved,
+//! or too late, reverts instead of trading at unprofitable prices.
+
+use ethers::abi::Token;
+use ethers::types::U256;
+
+/// Bounds a route is executed within
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutionLimits {
+    /// Slippage tolerance of each swap (in basis points)
+    pub slippage: U256,
+
+    /// Least profit the route must realize, in its first token
+    pub min_profit: U256,
+
+    /// Unix timestamp of the last block the transaction may execute in
+    pub deadline: U256,
+}
+
+impl ExecutionLimits {
+    /// Encode the limits as the contract's `(slippage, minProfit, deadline)` arguments
+    pub fn tokens(&self) -> [Token; 3] {
+        [
+            Token::Uint(self.slippage),
+            Token::Uint(self.min_profit),
+            Token::Uint(self.deadline),
+        ]
+    }
+}
@@ -1,21 +1,33 @@
 //! Smart Contract Module
 //!
-//! This module is responsible for interacting with the ArbitrageExecutor smart contract.
+//! This module is responsible for interacting with the ArbitrageExecutor smart contract, and
+//! for deploying it at startup when none is configured and `deploy_if_missing` is set.
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use ethers::abi::{Abi, Token};
 use ethers::contract::{Contract, ContractFactory};
 use ethers::middleware::SignerMiddleware;
-use ethers::providers::{Http, Provider};
+use ethers::providers::Middleware;
 use ethers::signers::{LocalWallet, Signer};
 use ethers::types::{Address, Bytes, TransactionRequest, H256, U256};
 use log::{debug, error, info, warn};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
-use crate::config::Config;
+use crate::assets::ContractAbi;
+use crate::blockchain::RpcClient;
+use crate::config::{Config, FlashLoanProvider};
+use crate::storage::Storage;
 use crate::utils::validate_and_parse_address;
 
+mod events;
+mod limits;
+mod route;
+
+pub use events::ExecutorEvent;
+pub use limits::ExecutionLimits;
+pub use route::ArbitrageRoute;
+
 /// Interface for smart contract managers
 #[async_trait]
 pub trait ContractManager: Send + Sync {
@@ -34,9 +46,29 @@ pub trait ContractManager: Send + Sync {
         assets: Vec<Address>,
         amounts: Vec<U256>,
         modes: Vec<U256>,
-        token_path: Vec<Address>,
-        dex_path: Vec<String>,
-        slippage: U256,
+        route: &ArbitrageRoute,
+        limits: ExecutionLimits,
+    ) -> Result<TransactionRequest>;
+
+    /// Execute a two-leg arbitrage funded by a flash swap from `pair`, the pair of its first
+    /// swap, which is repaid `amount_in` of the route's first token
+    async fn execute_flash_swap(
+        &self,
+        pair: Address,
+        amount_in: U256,
+        route: &ArbitrageRoute,
+        limits: ExecutionLimits,
+    ) -> Result<TransactionRequest>;
+
+    /// Execute an arbitrage funded by a flash loan of `amount` of the route's first token, which
+    /// the contract takes from `lender` of a provider other than Aave
+    async fn execute_flash_loan(
+        &self,
+        provider: FlashLoanProvider,
+        lender: Address,
+        amount: U256,
+        route: &ArbitrageRoute,
+        limits: ExecutionLimits,
     ) -> Result<TransactionRequest>;
 
     /// Authorize a caller
@@ -45,6 +77,12 @@ pub trait ContractManager: Send + Sync {
     /// Unauthorize a caller
     async fn unauthorize_caller(&self, caller: Address) -> Result<TransactionRequest>;
 
+    /// Allow or disallow a lender of the flash loans the contract takes itself
+    async fn set_flash_lender(&self, lender: Address, allowed: bool) -> Result<TransactionRequest>;
+
+    /// Check whether the contract may take flash loans from a lender
+    async fn is_flash_lender(&self, lender: Address) -> Result<bool>;
+
     /// Activate emergency stop
     async fn activate_emergency_stop(&self) -> Result<TransactionRequest>;
 
@@ -61,7 +99,12 @@ pub trait ContractManager: Send + Sync {
     fn get_contract_address(&self) -> Option<Address>;
 
     /// Set the contract address
-    fn set_contract_address(&mut self, address: Address);
+    fn set_contract_address(&self, address: Address);
+
+    /// Check that the contract at the address is an ArbitrageExecutor the wallet may trade
+    /// through: it has code implementing every function of the ABI, and the wallet is its owner
+    /// or an authorized caller
+    async fn verify_contract(&self) -> Result<()>;
 
     /// Get the contract ABI
     fn get_contract_abi(&self) -> Abi;
@@ -70,16 +113,16 @@ pub trait ContractManager: Send + Sync {
 /// Implementation of the smart contract manager
 pub struct ContractManagerImpl {
     config: Arc<Config>,
-    blockchain_client: Arc<Provider<Http>>,
+    blockchain_client: Arc<RpcClient>,
     wallet: Option<LocalWallet>,
-    contract_address: Option<Address>,
+    contract_address: RwLock<Option<Address>>,
     contract_abi: Abi,
 }
 
 /// Create a new smart contract manager
 pub async fn create_manager(
     config: &Arc<Config>,
-    blockchain_client: Arc<Provider<Http>>,
+    blockchain_client: Arc<RpcClient>,
 ) -> Result<Arc<ContractManagerImpl>> {
     // Initialize the wallet if a private key is provided
     let wallet = if let Some(private_key) = &config.ethereum.private_key {
@@ -88,29 +131,139 @@ pub async fn create_manager(
         None
     };
 
-    // Load the contract ABI
-    let contract_abi = load_contract_abi()?;
+    let contract_abi = ContractAbi::ArbitrageExecutor.abi().clone();
 
     // Create the contract manager
     let manager = ContractManagerImpl {
         config: config.clone(),
         blockchain_client,
         wallet,
-        contract_address: None,
+        contract_address: RwLock::new(None),
         contract_abi,
     };
 
     Ok(Arc::new(manager))
 }
 
-/// Load the contract ABI from the embedded JSON file
-fn load_contract_abi() -> Result<Abi> {
-    // Load the ABI from the embedded JSON file
-    let abi_json = include_str!("./abi/ArbitrageExecutor.json");
-    let abi: Abi =
-        serde_json::from_str(abi_json).context("Failed to parse ArbitrageExecutor ABI")?;
+/// Get the lender kind `executeFlashLoan` borrows from a provider with (the contract's
+/// `LENDER_*` constants)
+fn lender_kind(provider: FlashLoanProvider) -> Result<u8> {
+    match provider {
+        FlashLoanProvider::Balancer => Ok(0),
+        FlashLoanProvider::UniswapV3 => Ok(1),
+        FlashLoanProvider::Morpho => Ok(2),
+        FlashLoanProvider::Spark => Ok(3),
+        other => anyhow::bail!(
+            "The contract does not take {} flash loans itself",
+            other.as_str()
+        ),
+    }
+}
+
+/// Get the functions of an ABI whose selectors a contract's code lacks
+///
+/// Every function is dispatched on its selector, so code implementing the ABI, and creation
+/// code embedding it, contains each of them.
+fn missing_functions<'a>(abi: &'a Abi, code: &[u8]) -> Vec<&'a str> {
+    abi.functions()
+        .filter(|function| {
+            !code
+                .windows(4)
+                .any(|window| window == function.short_signature())
+        })
+        .map(|function| function.name.as_str())
+        .collect()
+}
+
+/// Point a contract manager at the ArbitrageExecutor contract
+///
+/// The configured `contract_address` is used if set. Otherwise the contract an earlier run
+/// deployed on the chain, as recorded in storage, is reused while it still has code; failing
+/// that, with `deploy_if_missing`, a new contract is deployed with the lending pool and routers
+/// of the configuration and recorded for the next run. Returns `None` if no contract is used.
+pub async fn resolve_contract(
+    config: &Config,
+    manager: &ContractManagerImpl,
+    storage: Option<&Arc<Storage>>,
+) -> Result<Option<Address>> {
+    let chain_id = config.ethereum.chain_id;
+    let contract_config = &config.arbitrage.contract;
+    let address = if let Some(address) = &contract_config.contract_address {
+        validate_and_parse_address(address).context("Invalid contract address")?
+    } else if let Some(address) = deployed_contract(manager, storage, chain_id).await {
+        info!(
+            "Using the ArbitrageExecutor contract deployed at {:?}",
+            address
+        );
+        address
+    } else if contract_config.deploy_if_missing {
+        let address = manager
+            .deploy_contract(
+                validate_and_parse_address(&config.flash_loan.aave_lending_pool)
+                    .context("Invalid Aave lending pool address")?,
+                validate_and_parse_address(&config.dex.uniswap.router_address)
+                    .context("Invalid Uniswap router address")?,
+                validate_and_parse_address(&config.dex.sushiswap.router_address)
+                    .context("Invalid Sushiswap router address")?,
+                validate_and_parse_address(&config.dex.curve.router_address)
+                    .context("Invalid Curve router address")?,
+            )
+            .await?;
+
+        match storage {
+            Some(storage) => {
+                if let Err(e) = storage.save_deployed_contract(chain_id, address) {
+                    warn!("Failed to record the deployed contract: {}", e);
+                }
+            }
+            None => warn!(
+                "Storage is disabled, so the next run deploys again unless \
+                 arbitrage.contract.contract_address is set to {:?}",
+                address
+            ),
+        }
+        if config.dex.trident.enabled {
+            warn!("Trident legs revert until the new contract's setBentoBox is called");
+        }
+        address
+    } else {
+        warn!("No ArbitrageExecutor contract configured and deploy_if_missing is off");
+        return Ok(None);
+    };
 
-    Ok(abi)
+    manager.set_contract_address(address);
+    Ok(Some(address))
+}
+
+/// Get the contract recorded as deployed on a chain, if it still has code there
+async fn deployed_contract(
+    manager: &ContractManagerImpl,
+    storage: Option<&Arc<Storage>>,
+    chain_id: u64,
+) -> Option<Address> {
+    let address = match storage?.deployed_contract(chain_id) {
+        Ok(address) => address?,
+        Err(e) => {
+            warn!("Failed to read the deployed contract: {}", e);
+            return None;
+        }
+    };
+
+    // A chain reset (e.g. a local fork) leaves the record without a contract behind it
+    match manager.blockchain_client.get_code(address, None).await {
+        Ok(code) if !code.is_empty() => Some(address),
+        Ok(_) => {
+            warn!(
+                "Recorded ArbitrageExecutor contract {:?} has no code on the chain",
+                address
+            );
+            None
+        }
+        Err(e) => {
+            warn!("Failed to get the code of contract {:?}: {}", address, e);
+            None
+        }
+    }
 }
 
 #[async_trait]
@@ -139,6 +292,18 @@ impl ContractManager for ContractManagerImpl {
         let bytecode =
             hex::decode(bytecode.trim()).context("Failed to decode ArbitrageExecutor bytecode")?;
 
+        // A placeholder artifact deploys a contract without code, which every start would
+        // deploy again
+        let missing = missing_functions(&self.contract_abi, &bytecode);
+        if !missing.is_empty() {
+            anyhow::bail!(
+                "The embedded ArbitrageExecutor bytecode is not a build of the contract (it lacks {}); \
+                 compile contracts/ArbitrageExecutor.sol into src/contract/bytecode/ArbitrageExecutor.bin \
+                 or set arbitrage.contract.contract_address",
+                missing.join(", ")
+            );
+        }
+
         // Create the contract factory
         let factory = ContractFactory::new(
             self.contract_abi.clone(),
@@ -155,14 +320,30 @@ impl ContractManager for ContractManagerImpl {
             curve_router_address,
         );
 
-        let contract = factory
+        let mut deployer = factory
             .deploy(constructor_args)
-            .context("Failed to deploy contract")?
+            .context("Failed to deploy contract")?;
+        deployer
+            .tx
+            .set_gas(self.config.arbitrage.contract.deployment_gas_limit);
+
+        let contract = deployer
             .send()
             .await
             .context("Failed to send contract deployment transaction")?;
 
         let contract_address = contract.address();
+        let code = self
+            .blockchain_client
+            .get_code(contract_address, None)
+            .await
+            .context("Failed to get the deployed contract's code")?;
+        if code.is_empty() {
+            anyhow::bail!(
+                "ArbitrageExecutor deployment at {:?} left no code",
+                contract_address
+            );
+        }
         info!(
             "ArbitrageExecutor contract deployed at: {}",
             contract_address
@@ -176,12 +357,13 @@ impl ContractManager for ContractManagerImpl {
         assets: Vec<Address>,
         amounts: Vec<U256>,
         modes: Vec<U256>,
-        token_path: Vec<Address>,
-        dex_path: Vec<String>,
-        slippage: U256,
+        route: &ArbitrageRoute,
+        limits: ExecutionLimits,
     ) -> Result<TransactionRequest> {
         // Check if we have a contract address
-        let contract_address = self.contract_address.context("Contract address not set")?;
+        let contract_address = self
+            .get_contract_address()
+            .context("Contract address not set")?;
 
         // Create the contract instance
         let contract = Contract::new(
@@ -196,21 +378,18 @@ impl ContractManager for ContractManagerImpl {
             .function("executeArbitrage")
             .context("Failed to find executeArbitrage function")?;
 
-        let params = (assets, amounts, modes, token_path, dex_path, slippage);
+        let [slippage, min_profit, deadline] = limits.tokens();
         let data = function
             .encode_input(&[
-                Token::Array(params.0.iter().map(|&addr| Token::Address(addr)).collect()),
-                Token::Array(params.1.iter().map(|&amount| Token::Uint(amount)).collect()),
-                Token::Array(params.2.iter().map(|&mode| Token::Uint(mode)).collect()),
-                Token::Array(params.3.iter().map(|&addr| Token::Address(addr)).collect()),
-                Token::Array(
-                    params
-                        .4
-                        .iter()
-                        .map(|dex| Token::String(dex.clone()))
-                        .collect(),
-                ),
-                Token::Uint(params.5),
+                Token::Array(assets.iter().map(|&addr| Token::Address(addr)).collect()),
+                Token::Array(amounts.iter().map(|&amount| Token::Uint(amount)).collect()),
+                Token::Array(modes.iter().map(|&mode| Token::Uint(mode)).collect()),
+                route.token_path_token(),
+                route.dex_path_token(),
+                route.pool_path_token(),
+                slippage,
+                min_profit,
+                deadline,
             ])
             .context("Failed to encode executeArbitrage function call")?;
 
@@ -222,9 +401,88 @@ impl ContractManager for ContractManagerImpl {
         Ok(tx)
     }
 
+    async fn execute_flash_swap(
+        &self,
+        pair: Address,
+        amount_in: U256,
+        route: &ArbitrageRoute,
+        limits: ExecutionLimits,
+    ) -> Result<TransactionRequest> {
+        let contract_address = self
+            .get_contract_address()
+            .context("Contract address not set")?;
+
+        let function = self
+            .contract_abi
+            .function("executeFlashSwap")
+            .context("Failed to find executeFlashSwap function")?;
+
+        let [slippage, min_profit, deadline] = limits.tokens();
+        let data = function
+            .encode_input(&[
+                Token::Address(pair),
+                Token::Uint(amount_in),
+                route.token_path_token(),
+                route.dex_path_token(),
+                route.pool_path_token(),
+                slippage,
+                min_profit,
+                deadline,
+            ])
+            .context("Failed to encode executeFlashSwap function call")?;
+
+        let tx = TransactionRequest::new()
+            .to(contract_address)
+            .data(Bytes::from(data));
+
+        Ok(tx)
+    }
+
+    async fn execute_flash_loan(
+        &self,
+        provider: FlashLoanProvider,
+        lender: Address,
+        amount: U256,
+        route: &ArbitrageRoute,
+        limits: ExecutionLimits,
+    ) -> Result<TransactionRequest> {
+        let contract_address = self
+            .get_contract_address()
+            .context("Contract address not set")?;
+
+        let function = self
+            .contract_abi
+            .function("executeFlashLoan")
+            .context("Failed to find executeFlashLoan function")?;
+
+        let [slippage, min_profit, deadline] = limits.tokens();
+        let data = function
+            .encode_input(&[
+                Token::Uint(U256::from(lender_kind(provider)?)),
+                Token::Address(lender),
+                Token::Address(route.initial_token()),
+                Token::Uint(amount),
+                route.token_path_token(),
+                route.dex_path_token(),
+                route.pool_path_token(),
+                slippage,
+                min_profit,
+                deadline,
+            ])
+            .context("Failed to encode executeFlashLoan function call")?;
+
+        let tx = TransactionRequest::new()
+            .to(contract_address)
+            .data(Bytes::from(data));
+
+        Ok(tx)
+    }
+
     async fn authorize_caller(&self, caller: Address) -> Result<TransactionRequest> {
         // Check if we have a contract address
-        let contract_address = self.contract_address.context("Contract address not set")?;
+        let contract_address = self
+            .get_contract_address()
+            .context("Contract address not set")?;
 
         // Create the contract instance
         let contract = Contract::new(
@@ -253,7 +511,9 @@ impl ContractManager for ContractManagerImpl {
 
     async fn unauthorize_caller(&self, caller: Address) -> Result<TransactionRequest> {
         // Check if we have a contract address
-        let contract_address = self.contract_address.context("Contract address not set")?;
+        let contract_address = self
+            .get_contract_address()
+            .context("Contract address not set")?;
 
         // Create the contract instance
         let contract = Contract::new(
@@ -280,9 +540,51 @@ impl ContractManager for ContractManagerImpl {
         Ok(tx)
     }
 
+    async fn set_flash_lender(&self, lender: Address, allowed: bool) -> Result<TransactionRequest> {
+        let contract_address = self
+            .get_contract_address()
+            .context("Contract address not set")?;
+
+        let function = self
+            .contract_abi
+            .function("setFlashLender")
+            .context("Failed to find setFlashLender function")?;
+
+        let data = function
+            .encode_input(&[Token::Address(lender), Token::Bool(allowed)])
+            .context("Failed to encode setFlashLender function call")?;
+
+        let tx = TransactionRequest::new()
+            .to(contract_address)
+            .data(Bytes::from(data));
+
+        Ok(tx)
+    }
+
+    async fn is_flash_lender(&self, lender: Address) -> Result<bool> {
+        let contract_address = self
+            .get_contract_address()
+            .context("Contract address not set")?;
+
+        let contract = Contract::new(
+            contract_address,
+            self.contract_abi.clone(),
+            self.blockchain_client.clone(),
+        );
+        let allowed: bool = contract
+            .method::<_, bool>("flashLenders", lender)?
+            .call()
+            .await
+            .context("Failed to check the contract's flash lenders")?;
+
+        Ok(allowed)
+    }
+
     async fn activate_emergency_stop(&self) -> Result<TransactionRequest> {
         // Check if we have a contract address
-        let contract_address = self.contract_address.context("Contract address not set")?;
+        let contract_address = self
+            .get_contract_address()
+            .context("Contract address not set")?;
 
         // Create the contract instance
         let contract = Contract::new(
@@ -311,7 +613,9 @@ impl ContractManager for ContractManagerImpl {
 
     async fn deactivate_emergency_stop(&self) -> Result<TransactionRequest> {
         // Check if we have a contract address
-        let contract_address = self.contract_address.context("Contract address not set")?;
+        let contract_address = self
+            .get_contract_address()
+            .context("Contract address not set")?;
 
         // Create the contract instance
         let contract = Contract::new(
@@ -340,7 +644,9 @@ impl ContractManager for ContractManagerImpl {
 
     async fn recover_erc20(&self, token: Address, amount: U256) -> Result<TransactionRequest> {
         // Check if we have a contract address
-        let contract_address = self.contract_address.context("Contract address not set")?;
+        let contract_address = self
+            .get_contract_address()
+            .context("Contract address not set")?;
 
         // Create the contract instance
         let contract = Contract::new(
@@ -369,7 +675,9 @@ impl ContractManager for ContractManagerImpl {
 
     async fn recover_eth(&self) -> Result<TransactionRequest> {
         // Check if we have a contract address
-        let contract_address = self.contract_address.context("Contract address not set")?;
+        let contract_address = self
+            .get_contract_address()
+            .context("Contract address not set")?;
 
         // Create the contract instance
         let contract = Contract::new(
@@ -397,11 +705,110 @@ impl ContractManager for ContractManagerImpl {
     }
 
     fn get_contract_address(&self) -> Option<Address> {
-        self.contract_address
+        *self
+            .contract_address
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn set_contract_address(&self, address: Address) {
+        *self
+            .contract_address
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = Some(address);
     }
 
-    fn set_contract_address(&mut self, address: Address) {
-        self.contract_address = Some(address);
+    async fn verify_contract(&self) -> Result<()> {
+        let contract_address = self
+            .get_contract_address()
+            .context("Contract address not set")?;
+
+        // Every function of the ABI is dispatched on its selector, which the code must contain
+        let code = self
+            .blockchain_client
+            .get_code(contract_address, None)
+            .await
+            .context("Failed to get the contract code")?;
+        if code.is_empty() {
+            anyhow::bail!("Contract {:?} has no code", contract_address);
+        }
+        let missing = missing_functions(&self.contract_abi, &code);
+        if !missing.is_empty() {
+            anyhow::bail!(
+                "Contract {:?} is not an ArbitrageExecutor: it lacks {}",
+                contract_address,
+                missing.join(", ")
+            );
+        }
+
+        let contract = Contract::new(
+            contract_address,
+            self.contract_abi.clone(),
+            self.blockchain_client.clone(),
+        );
+        let wallet_address = match &self.wallet {
+            Some(wallet) => wallet.address(),
+            None => validate_and_parse_address(&self.config.ethereum.wallet_address)
+                .context("Invalid wallet address")?,
+        };
+        let owner: Address = contract
+            .method::<_, Address>("owner", ())?
+            .call()
+            .await
+            .context("Failed to get the contract owner")?;
+        if owner != wallet_address {
+            let authorized: bool = contract
+                .method::<_, bool>("authorizedCallers", wallet_address)?
+                .call()
+                .await
+                .context("Failed to check the authorized callers")?;
+            if !authorized {
+                anyhow::bail!(
+                    "Wallet {:?} is neither the owner ({:?}) nor an authorized caller of contract {:?}",
+                    wallet_address,
+                    owner,
+                    contract_address
+                );
+            }
+            warn!(
+                "Wallet {:?} is an authorized caller but not the owner ({:?}) of contract {:?}; \
+                 the emergency stop and recoveries will revert",
+                wallet_address, owner, contract_address
+            );
+        }
+
+        // Flash loans are taken from the pool the contract was deployed with
+        let lending_pool: Address = contract
+            .method::<_, Address>("lendingPoolAddress", ())?
+            .call()
+            .await
+            .context("Failed to get the contract lending pool")?;
+        if validate_and_parse_address(&self.config.flash_loan.aave_lending_pool)
+            .is_ok_and(|configured| configured != lending_pool)
+        {
+            warn!(
+                "Contract {:?} borrows from lending pool {:?}, not the configured {}",
+                contract_address, lending_pool, self.config.flash_loan.aave_lending_pool
+            );
+        }
+
+        let emergency_stop: bool = contract
+            .method::<_, bool>("emergencyStop", ())?
+            .call()
+            .await
+            .context("Failed to get the contract emergency stop")?;
+        if emergency_stop {
+            warn!(
+                "Contract {:?} has its emergency stop active; trades revert until it is lifted",
+                contract_address
+            );
+        }
+
+        info!(
+            "Verified ArbitrageExecutor contract {:?} (owner {:?})",
+            contract_address, owner
+        );
+        Ok(())
     }
 
     fn get_contract_abi(&self) -> Abi {
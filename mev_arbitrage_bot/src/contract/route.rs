@@ -0,0 +1,137 @@
+//! Arbitrage Route Module
+//!
+//! This module is responsible for turning a token path and the DEX and pool used for each swap
+//! into the leg list the ArbitrageExecutor contract executes, for routes of any length up to
+//! `max_hops`. Legs of one route may use different DEXes.
+
+use anyhow::Result;
+use ethers::abi::Token;
+use ethers::types::Address;
+
+use crate::dex::DexType;
+
+/// One swap of an arbitrage route
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteLeg {
+    /// DEX name as understood by the contract ("uniswap", "sushiswap", "trident" or "curve")
+    pub dex: String,
+
+    /// Pool the swap goes through (the zero address lets the contract pick one)
+    pub pool: Address,
+
+    /// Token sold in this swap
+    pub token_in: Address,
+
+    /// Token bought in this swap
+    pub token_out: Address,
+}
+
+/// A validated circular route, one leg per consecutive token pair
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArbitrageRoute {
+    legs: Vec<RouteLeg>,
+}
+
+impl ArbitrageRoute {
+    /// Build a route from a token path and the DEX and pool used for each swap
+    ///
+    /// The path must start and end at the borrowed token, have exactly one hop per swap and
+    /// use at most `max_hops` swaps.
+    pub fn new(
+        token_path: &[Address],
+        hops: &[(DexType, Address)],
+        max_hops: usize,
+    ) -> Result<Self> {
+        if token_path.len() < 2 {
+            anyhow::bail!("Route needs at least 2 tokens, got {}", token_path.len());
+        }
+        if hops.len() != token_path.len() - 1 {
+            anyhow::bail!(
+                "Route has {} swaps but {} DEXes",
+                token_path.len() - 1,
+                hops.len()
+            );
+        }
+        if hops.len() > max_hops {
+            anyhow::bail!(
+                "Route has {} swaps, more than the maximum of {}",
+                hops.len(),
+                max_hops
+            );
+        }
+        if token_path.first() != token_path.last() {
+            anyhow::bail!("Route must end at the token it starts from");
+        }
+        if hops
+            .iter()
+            .any(|&(dex_type, pool)| dex_type == DexType::Trident && pool.is_zero())
+        {
+            anyhow::bail!("Trident swaps have no router and must name their pool");
+        }
+
+        let legs = token_path
+            .windows(2)
+            .zip(hops)
+            .map(|(pair, &(dex_type, pool))| RouteLeg {
+                dex: contract_dex_name(dex_type).to_string(),
+                pool,
+                token_in: pair[0],
+                token_out: pair[1],
+            })
+            .collect();
+
+        Ok(Self { legs })
+    }
+
+    /// Get the legs of the route, in execution order
+    pub fn legs(&self) -> &[RouteLeg] {
+        &self.legs
+    }
+
+    /// Get the token borrowed at the start of the route and repaid at the end
+    pub fn initial_token(&self) -> Address {
+        self.legs[0].token_in
+    }
+
+    /// Get the tokens visited by the route, including the final return to the initial token
+    pub fn token_path(&self) -> Vec<Address> {
+        std::iter::once(self.initial_token())
+            .chain(self.legs.iter().map(|leg| leg.token_out))
+            .collect()
+    }
+
+    /// Encode the token path as the contract's `address[] tokenPath` argument
+    pub fn token_path_token(&self) -> Token {
+        Token::Array(self.token_path().into_iter().map(Token::Address).collect())
+    }
+
+    /// Encode the DEX path as the contract's `string[] dexPath` argument
+    pub fn dex_path_token(&self) -> Token {
+        Token::Array(
+            self.legs
+                .iter()
+                .map(|leg| Token::String(leg.dex.clone()))
+                .collect(),
+        )
+    }
+
+    /// Encode the pools as the contract's `address[] poolPath` argument
+    pub fn pool_path_token(&self) -> Token {
+        Token::Array(
+            self.legs
+                .iter()
+                .map(|leg| Token::Address(leg.pool))
+                .collect(),
+        )
+    }
+}
+
+/// Map a DEX to the name the contract routes on
+fn contract_dex_name(dex_type: DexType) -> &'static str {
+    match dex_type {
+        DexType::UniswapV2 => "uniswap",
+        DexType::Sushiswap => "sushiswap",
+        DexType::Trident => "trident",
+        DexType::Curve => "curve",
+    }
+}
@@ -0,0 +1,240 @@
+//! Coordination Module
+//!
+//! This module is responsible for keeping bot instances running side by side from competing
+//! for the same opportunity. Before an opportunity is built, the instance claims its
+//! ID in Redis with a lease; an instance that finds the ID claimed by another skips it, so two
+//! instances never send competing bundles and burn gas against each other.
+//!
+//! A submitted opportunity keeps its claim until the lease runs out, covering the time its
+//! transaction is pending. An instance claiming an ID it already holds, as when it detects the
+//! opportunity again in a later block, renews its lease instead of being refused. Claims made
+//! for opportunities that were not submitted after all are released at once, and only by the
+//! instance holding them. When Redis cannot be reached the
+//! opportunity is skipped, unless `fail_open` lets instances trade uncoordinated meanwhile.
+
+mod redis;
+
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+use self::redis::{RedisClient, Reply};
+use crate::config::{Config, ConfigSection};
+use crate::metrics;
+
+/// Default Redis server claims are made on
+pub const DEFAULT_REDIS_URL: &str = "redis://127.0.0.1:6379";
+
+/// Default time a claim is held for (in seconds)
+pub const DEFAULT_LEASE_SECS: u64 = 30;
+
+/// Default prefix of claim keys
+pub const DEFAULT_KEY_PREFIX: &str = "mev_arbitrage_bot";
+
+/// Default time Redis has to answer (in milliseconds)
+pub const DEFAULT_TIMEOUT_MS: u64 = 500;
+
+/// Claims an opportunity unless another instance holds it, renewing the lease of a claim the
+/// instance already holds (returns 1 when claimed, 2 when renewed and 0 when refused)
+const CLAIM_SCRIPT: &str = "local holder = redis.call('get', KEYS[1]) \
+    if holder == ARGV[1] then redis.call('pexpire', KEYS[1], ARGV[2]) return 2 end \
+    if holder then return 0 end \
+    redis.call('set', KEYS[1], ARGV[1], 'PX', ARGV[2]) return 1";
+
+/// Deletes a claim only if the instance still holds it
+const RELEASE_SCRIPT: &str =
+    "if redis.call('get', KEYS[1]) == ARGV[1] then return redis.call('del', KEYS[1]) else return 0 end";
+
+/// Multi-instance coordination configuration (`[coordination]`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CoordinationConfig {
+    /// Whether opportunities are claimed in Redis before they are executed
+    pub enabled: bool,
+
+    /// Redis server claims are made on (defaults to redis://127.0.0.1:6379)
+    pub redis_url: Option<String>,
+
+    /// Time a claim is held for (in seconds, defaults to 30)
+    pub lease_secs: Option<u64>,
+
+    /// Name of this instance in its claims (defaults to the host name and process ID)
+    pub instance_id: Option<String>,
+
+    /// Prefix of claim keys, shared by the instances coordinating (defaults to mev_arbitrage_bot)
+    pub key_prefix: Option<String>,
+
+    /// Time Redis has to answer (in milliseconds, defaults to 500)
+    pub timeout_ms: Option<u64>,
+
+    /// Whether opportunities are executed unclaimed while Redis cannot be reached
+    pub fail_open: bool,
+}
+
+impl ConfigSection for CoordinationConfig {
+    const NAME: &'static str = "coordination";
+
+    fn validate(&self) -> Result<()> {
+        if let Some(url) = &self.redis_url {
+            RedisClient::validate_url(url).context("Invalid coordination redis_url")?;
+        }
+        if self.lease_secs == Some(0) {
+            anyhow::bail!("Coordination lease_secs must be greater than zero");
+        }
+        if self.timeout_ms == Some(0) {
+            anyhow::bail!("Coordination timeout_ms must be greater than zero");
+        }
+        if self
+            .instance_id
+            .as_deref()
+            .is_some_and(|instance_id| instance_id.trim().is_empty())
+        {
+            anyhow::bail!("Coordination instance_id must not be empty");
+        }
+
+        Ok(())
+    }
+}
+
+/// Outcome of claiming an opportunity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Claim {
+    /// The instance claimed the opportunity and releases it if it is not submitted
+    Claimed,
+
+    /// The instance already held the claim, whose lease was renewed
+    Renewed,
+
+    /// No claim was made: coordination is off, or Redis could not be reached and it fails open
+    Uncoordinated,
+
+    /// Another instance holds the claim, or Redis could not be reached
+    Refused,
+}
+
+/// Claims opportunities for this instance
+pub struct Coordinator {
+    redis: RedisClient,
+    instance_id: String,
+
+    /// Prefix of this chain's claim keys
+    key_prefix: String,
+
+    lease_ms: String,
+    fail_open: bool,
+}
+
+/// Create the coordinator of a chain
+pub fn create_coordinator(
+    config: &Config,
+    coordination_config: &CoordinationConfig,
+) -> Result<Arc<Coordinator>> {
+    let redis_url = coordination_config
+        .redis_url
+        .as_deref()
+        .unwrap_or(DEFAULT_REDIS_URL);
+    let timeout =
+        Duration::from_millis(coordination_config.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS));
+    let redis = RedisClient::new(redis_url, timeout)?;
+
+    let instance_id = coordination_config.instance_id.clone().unwrap_or_else(|| {
+        let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "localhost".to_string());
+        format!("{}-{}", host, std::process::id())
+    });
+    let lease_secs = coordination_config.lease_secs.unwrap_or(DEFAULT_LEASE_SECS);
+
+    info!(
+        "Coordinating opportunities as {} with {}s leases",
+        instance_id, lease_secs
+    );
+
+    Ok(Arc::new(Coordinator {
+        redis,
+        instance_id,
+        key_prefix: format!(
+            "{}:{}",
+            coordination_config
+                .key_prefix
+                .as_deref()
+                .unwrap_or(DEFAULT_KEY_PREFIX),
+            config.ethereum.chain_id
+        ),
+        lease_ms: (lease_secs * 1000).to_string(),
+        fail_open: coordination_config.fail_open,
+    }))
+}
+
+impl Coordinator {
+    /// Claim an opportunity for this instance, or renew the claim it already holds
+    pub async fn claim(&self, opportunity_id: &str) -> Claim {
+        let key = self.key(opportunity_id);
+        let claimed = self
+            .redis
+            .command(&[
+                b"EVAL",
+                CLAIM_SCRIPT.as_bytes(),
+                b"1",
+                key.as_bytes(),
+                self.instance_id.as_bytes(),
+                self.lease_ms.as_bytes(),
+            ])
+            .await;
+
+        match claimed {
+            Ok(Reply::Integer(1)) => Claim::Claimed,
+            Ok(Reply::Integer(2)) => {
+                debug!("Renewed the claim of {}", opportunity_id);
+                Claim::Renewed
+            }
+            Ok(Reply::Integer(0)) => {
+                debug!("{} is claimed by another instance", opportunity_id);
+                metrics::global().increment_counter("opportunities_claimed_elsewhere", 1);
+                Claim::Refused
+            }
+            Ok(reply) => {
+                warn!("Unexpected reply claiming {}: {:?}", opportunity_id, reply);
+                self.unclaimed()
+            }
+            Err(e) => {
+                warn!("Could not claim {}: {:#}", opportunity_id, e);
+                metrics::global().increment_counter("coordination_failures", 1);
+                self.unclaimed()
+            }
+        }
+    }
+
+    /// Release the claim of an opportunity that was not submitted, if this instance holds it
+    pub async fn release(&self, opportunity_id: &str) {
+        let key = self.key(opportunity_id);
+        let released = self
+            .redis
+            .command(&[
+                b"EVAL",
+                RELEASE_SCRIPT.as_bytes(),
+                b"1",
+                key.as_bytes(),
+                self.instance_id.as_bytes(),
+            ])
+            .await;
+
+        // An unreleased claim still expires with its lease
+        if let Err(e) = released {
+            warn!("Could not release the claim of {}: {:#}", opportunity_id, e);
+        }
+    }
+
+    /// Outcome of a claim Redis did not answer
+    fn unclaimed(&self) -> Claim {
+        if self.fail_open {
+            Claim::Uncoordinated
+        } else {
+            Claim::Refused
+        }
+    }
+
+    fn key(&self, opportunity_id: &str) -> String {
+        format!("{}:{}", self.key_prefix, opportunity_id)
+    }
+}
@@ -0,0 +1,200 @@
+//! Redis Client Module
+//!
+//! This module is responsible for the few Redis commands coordination sends, over one
+//! connection speaking RESP2. The connection is opened on first use, authenticated and set to
+//! the database of the URL, and dropped after any error, so the next command reconnects.
+
+use anyhow::{Context, Result};
+use reqwest::Url;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufStream};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+
+/// Port Redis listens on when the URL names none
+const DEFAULT_PORT: u16 = 6379;
+
+/// Reply of a Redis command
+#[derive(Debug, PartialEq)]
+pub(crate) enum Reply {
+    /// Simple string, such as `OK`
+    Status(String),
+
+    /// Integer
+    Integer(i64),
+
+    /// Bulk string, or nil
+    Bulk(Option<Vec<u8>>),
+}
+
+/// Where a Redis server is and how to log in to it
+#[derive(Debug, Clone)]
+struct RedisAddress {
+    host: String,
+    port: u16,
+    username: Option<String>,
+    password: Option<String>,
+    database: Option<u32>,
+}
+
+impl RedisAddress {
+    /// Parse a `redis://[[username]:password@]host[:port][/database]` URL
+    fn parse(url: &str) -> Result<Self> {
+        let url = Url::parse(url).context("Invalid Redis URL")?;
+        if url.scheme() != "redis" {
+            anyhow::bail!(
+                "Unsupported Redis URL scheme '{}' (only redis:// is supported)",
+                url.scheme()
+            );
+        }
+
+        let host = url.host_str().context("Redis URL has no host")?.to_string();
+        let database = match url.path().trim_start_matches('/') {
+            "" => None,
+            database => Some(
+                database
+                    .parse()
+                    .with_context(|| format!("Invalid Redis database '{}'", database))?,
+            ),
+        };
+
+        Ok(Self {
+            host,
+            port: url.port().unwrap_or(DEFAULT_PORT),
+            username: Some(url.username())
+                .filter(|username| !username.is_empty())
+                .map(str::to_string),
+            password: url.password().map(str::to_string),
+            database,
+        })
+    }
+}
+
+/// Client sending commands to one Redis server
+pub(crate) struct RedisClient {
+    address: RedisAddress,
+    timeout: Duration,
+    connection: Mutex<Option<BufStream<TcpStream>>>,
+}
+
+impl RedisClient {
+    /// Create a client for a Redis URL, connecting on the first command
+    pub(crate) fn new(url: &str, timeout: Duration) -> Result<Self> {
+        Ok(Self {
+            address: RedisAddress::parse(url)?,
+            timeout,
+            connection: Mutex::new(None),
+        })
+    }
+
+    /// Check a Redis URL without connecting
+    pub(crate) fn validate_url(url: &str) -> Result<()> {
+        RedisAddress::parse(url).map(|_| ())
+    }
+
+    /// Send a command, failing if it is not answered within the timeout
+    pub(crate) async fn command(&self, args: &[&[u8]]) -> Result<Reply> {
+        let mut connection = self.connection.lock().await;
+        let sent = tokio::time::timeout(self.timeout, async {
+            if connection.is_none() {
+                *connection = Some(self.connect().await?);
+            }
+            let stream = connection.as_mut().context("Redis connection missing")?;
+            send(stream, args).await
+        })
+        .await
+        .unwrap_or_else(|_| Err(anyhow::anyhow!("Redis did not answer in time")));
+
+        // A failed connection may hold a partial reply, so it is not reused
+        if sent.is_err() {
+            *connection = None;
+        }
+        sent
+    }
+
+    /// Open a connection, authenticated and on the URL's database
+    async fn connect(&self) -> Result<BufStream<TcpStream>> {
+        let address = &self.address;
+        let stream = TcpStream::connect((address.host.as_str(), address.port))
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to connect to Redis at {}:{}",
+                    address.host, address.port
+                )
+            })?;
+        let mut stream = BufStream::new(stream);
+
+        if let Some(password) = &address.password {
+            match &address.username {
+                Some(username) => {
+                    send(
+                        &mut stream,
+                        &[b"AUTH", username.as_bytes(), password.as_bytes()],
+                    )
+                    .await
+                }
+                None => send(&mut stream, &[b"AUTH", password.as_bytes()]).await,
+            }
+            .context("Redis authentication failed")?;
+        }
+        if let Some(database) = address.database {
+            send(&mut stream, &[b"SELECT", database.to_string().as_bytes()])
+                .await
+                .context("Failed to select the Redis database")?;
+        }
+
+        Ok(stream)
+    }
+}
+
+/// Write a command as an array of bulk strings and read its reply
+async fn send(stream: &mut BufStream<TcpStream>, args: &[&[u8]]) -> Result<Reply> {
+    let mut command = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        command.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        command.extend_from_slice(arg);
+        command.extend_from_slice(b"\r\n");
+    }
+    stream.write_all(&command).await?;
+    stream.flush().await?;
+
+    read_reply(stream).await
+}
+
+/// Read one reply, turning an error reply into an error
+async fn read_reply(stream: &mut BufStream<TcpStream>) -> Result<Reply> {
+    let line = read_line(stream).await?;
+    let (kind, value) = line.split_at(line.chars().next().map_or(0, char::len_utf8));
+
+    match kind {
+        "+" => Ok(Reply::Status(value.to_string())),
+        "-" => Err(anyhow::anyhow!("Redis error: {}", value)),
+        ":" => Ok(Reply::Integer(
+            value.parse().context("Invalid Redis integer reply")?,
+        )),
+        "$" => {
+            let length: i64 = value.parse().context("Invalid Redis bulk reply")?;
+            if length < 0 {
+                return Ok(Reply::Bulk(None));
+            }
+
+            // The string is followed by CRLF
+            let mut bulk = vec![0; length as usize + 2];
+            stream.read_exact(&mut bulk).await?;
+            bulk.truncate(length as usize);
+            Ok(Reply::Bulk(Some(bulk)))
+        }
+        _ => Err(anyhow::anyhow!("Unexpected Redis reply: {}", line)),
+    }
+}
+
+/// Read a CRLF-terminated line
+async fn read_line(stream: &mut BufStream<TcpStream>) -> Result<String> {
+    let mut line = String::new();
+    if stream.read_line(&mut line).await? == 0 {
+        anyhow::bail!("Redis closed the connection");
+    }
+
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
@@ -1,6 +0,0 @@
-//! DEX ABI Module
-//!
-//! This module contains the ABI definitions for DEX contracts.
-
-// The ABI files are included directly in the code where needed.
-// This module is just a placeholder to make the directory structure valid.
@@ -4,15 +4,16 @@
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use ethers::abi::{Abi, Token};
 use ethers::contract::{Contract, ContractCall, ContractInstance};
-use ethers::providers::Provider;
 use ethers::types::{Address, Bytes, U256};
 use log::{debug, info, warn};
 use std::sync::{Arc, Mutex};
 
+use crate::assets::ContractAbi;
+use crate::blockchain::RpcClient;
 use crate::config::Config;
-use crate::dex::{DexInterface, DexType, PoolInfo, TradeQuote};
+use crate::dex::{price_impact_bps, BlockTag, DexInterface, DexType, PoolInfo, TradeQuote};
+use crate::tokens;
 use crate::utils::validate_and_parse_address;
 
 /// Curve interface
@@ -20,18 +21,15 @@ pub struct CurveInterface {
     name: String,
     factory_address: Address,
     router_address: Address,
-    blockchain_client: Arc<Provider<ethers::providers::Http>>,
-    factory_contract:
-        ContractInstance<Arc<Provider<ethers::providers::Http>>, Provider<ethers::providers::Http>>,
-    router_contract:
-        ContractInstance<Arc<Provider<ethers::providers::Http>>, Provider<ethers::providers::Http>>,
+    factory_contract: ContractInstance<Arc<RpcClient>, RpcClient>,
+    router_contract: ContractInstance<Arc<RpcClient>, RpcClient>,
     pools: Mutex<Vec<PoolInfo>>,
 }
 
 /// Create a new Curve interface
 pub async fn create_interface(
     config: &Arc<Config>,
-    blockchain_client: Arc<Provider<ethers::providers::Http>>,
+    blockchain_client: Arc<RpcClient>,
 ) -> Result<Arc<dyn DexInterface>> {
     // Parse addresses
     let factory_address = match validate_and_parse_address(&config.dex.curve.factory_address) {
@@ -53,81 +51,17 @@ pub async fn create_interface(
     };
 
     // Load ABIs
-    let factory_abi = include_str!("./abi/curve_factory.json");
-    let factory_abi: Abi = serde_json::from_str(factory_abi).unwrap_or_else(|_| {
-        // If the ABI file is not available, use a minimal ABI
-        let json = r#"[
-            {
-                "name": "find_pool_for_coins",
-                "outputs": [
-                    {
-                        "type": "address",
-                        "name": ""
-                    }
-                ],
-                "inputs": [
-                    {
-                        "type": "address",
-                        "name": "_from"
-                    },
-                    {
-                        "type": "address",
-                        "name": "_to"
-                    }
-                ],
-                "stateMutability": "view",
-                "type": "function"
-            }
-        ]"#;
-        serde_json::from_str(json).expect("Failed to parse fallback ABI")
-    });
-
-    let router_abi = include_str!("./abi/curve_router.json");
-    let router_abi: Abi = serde_json::from_str(router_abi).unwrap_or_else(|_| {
-        // If the ABI file is not available, use a minimal ABI
-        let json = r#"[
-            {
-                "name": "get_best_rate",
-                "outputs": [
-                    {
-                        "type": "address",
-                        "name": ""
-                    },
-                    {
-                        "type": "uint256",
-                        "name": ""
-                    }
-                ],
-                "inputs": [
-                    {
-                        "type": "address",
-                        "name": "_from"
-                    },
-                    {
-                        "type": "address",
-                        "name": "_to"
-                    },
-                    {
-                        "type": "uint256",
-                        "name": "_amount"
-                    }
-                ],
-                "stateMutability": "view",
-                "type": "function"
-            }
-        ]"#;
-        serde_json::from_str(json).expect("Failed to parse fallback ABI")
-    });
+    let factory_abi = ContractAbi::CurveFactory.abi().clone();
+    let router_abi = ContractAbi::CurveRouter.abi().clone();
 
     // Create contracts
     let factory_contract = Contract::new(factory_address, factory_abi, blockchain_client.clone());
-    let router_contract = Contract::new(router_address, router_abi, blockchain_client.clone());
+    let router_contract = Contract::new(router_address, router_abi, blockchain_client);
 
     let interface = CurveInterface {
         name: "Curve".to_string(),
         factory_address,
         router_address,
-        blockchain_client: blockchain_client.clone(),
         factory_contract,
         router_contract,
         pools: Mutex::new(Vec::new()),
@@ -194,12 +128,17 @@ impl CurveInterface {
                 fee: 4, // 0.04%
             };
 
+            tokens::global().register_pool(pool_info.address, &self.name, &pool_info.tokens);
+
             // Add the pool to the list
             if let Ok(mut pools) = self.pools.lock() {
                 pools.push(pool_info);
             }
 
-            info!("Initialized Curve USDC-DAI pool: {:?}", pool_address);
+            info!(
+                "Initialized Curve pool: {}",
+                tokens::pool_label(pool_address)
+            );
         }
 
         Ok(())
@@ -275,7 +214,7 @@ impl DexInterface for CurveInterface {
         }
 
         // Get the reserves
-        let reserves = self.get_reserves(pool_address).await?;
+        let reserves = self.get_reserves(pool_address, BlockTag::Latest).await?;
 
         // Create the pool info
         let pool_info = PoolInfo {
@@ -286,6 +225,8 @@ impl DexInterface for CurveInterface {
             fee: 4, // 0.04%
         };
 
+        tokens::global().register_pool(pool_info.address, &self.name, &pool_info.tokens);
+
         // Add the pool to the list
         if let Ok(mut pools) = self.pools.lock() {
             pools.push(pool_info.clone());
@@ -295,7 +236,7 @@ impl DexInterface for CurveInterface {
         Err(anyhow::anyhow!("Failed to lock pools mutex"))
     }
 
-    async fn get_reserves(&self, pool: Address) -> Result<Vec<U256>> {
+    async fn get_reserves(&self, _pool: Address, _block: BlockTag) -> Result<Vec<U256>> {
         // This is a placeholder implementation
         // In a real implementation, we would call the balances function on the pool contract
 
@@ -311,16 +252,27 @@ impl DexInterface for CurveInterface {
         input_token: Address,
         output_token: Address,
         input_amount: U256,
+        block: BlockTag,
     ) -> Result<TradeQuote> {
-        // Call the get_best_rate function on the router
-        let (pool_address, output_amount): (Address, U256) = self
+        // Quote the trade and a small probe of it on the router; the probe's rate is the
+        // pool's marginal rate, which stable pools keep close to their virtual price
+        let probe_amount = (input_amount / 1_000).max(U256::one());
+        let quote_call = self
             .router_contract
             .method::<_, (Address, U256)>(
                 "get_best_rate",
                 (input_token, output_token, input_amount),
             )?
-            .call()
-            .await?;
+            .block(block);
+        let probe_call = self
+            .router_contract
+            .method::<_, (Address, U256)>(
+                "get_best_rate",
+                (input_token, output_token, probe_amount),
+            )?
+            .block(block);
+        let ((pool_address, output_amount), (_, probe_output)) =
+            futures::try_join!(quote_call.call(), probe_call.call())?;
 
         // Get the pool
         let pool = self
@@ -328,8 +280,9 @@ impl DexInterface for CurveInterface {
             .await?
             .context("Pool not found")?;
 
-        // Calculate the price impact
-        let price_impact = 0; // Placeholder
+        // Measure the price impact against the marginal rate
+        let spot_output = probe_output.saturating_mul(input_amount) / probe_amount;
+        let price_impact = price_impact_bps(spot_output, output_amount);
 
         // Create the trade quote
         let quote = TradeQuote {
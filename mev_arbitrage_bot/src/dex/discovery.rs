@@ -0,0 +1,203 @@
+//! Pair Discovery Module
+//!
+//! This module is responsible for finding Uniswap V2 style pairs by scanning a factory's
+//! `PairCreated` logs in block-range chunks, keeping only pairs between whitelisted tokens
+//! (or, with `include_unlisted_tokens`, pairs with at least one), and remembering how far it
+//! has scanned so later refreshes only read new events.
+
+use anyhow::{Context, Result};
+use ethers::middleware::Middleware;
+use ethers::types::{Address, Filter, Log, H256, U256};
+use log::debug;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::blockchain::RpcClient;
+use crate::config::{Config, PairDiscoveryConfig};
+use crate::utils::{u256_to_decimal, validate_and_parse_address};
+
+/// A pair announced by a factory `PairCreated` event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiscoveredPair {
+    /// Pair address
+    pub address: Address,
+
+    /// Lower-sorted token
+    pub token0: Address,
+
+    /// Higher-sorted token
+    pub token1: Address,
+
+    /// Block the pair was created in
+    pub block_number: u64,
+}
+
+/// Scans a factory for pairs between whitelisted tokens
+pub struct PairDiscovery {
+    factory_address: Address,
+    blockchain_client: Arc<RpcClient>,
+    settings: PairDiscoveryConfig,
+    whitelist: HashMap<Address, u8>,
+    last_scanned: Mutex<Option<u64>>,
+}
+
+impl PairDiscovery {
+    /// Create a discovery routine for a factory, whitelisting the configured flash loan tokens
+    pub fn new(
+        config: &Config,
+        factory_address: Address,
+        blockchain_client: Arc<RpcClient>,
+    ) -> Self {
+        let whitelist = config
+            .flash_loan
+            .tokens
+            .iter()
+            .filter_map(|token| {
+                validate_and_parse_address(&token.address)
+                    .ok()
+                    .map(|address| (address, token.decimals))
+            })
+            .collect();
+
+        Self {
+            factory_address,
+            blockchain_client,
+            settings: config.dex.pair_discovery.clone(),
+            whitelist,
+            last_scanned: Mutex::new(None),
+        }
+    }
+
+    /// Topic of the `PairCreated(address,address,address,uint256)` event
+    pub fn pair_created_topic() -> H256 {
+        H256::from(ethers::utils::keccak256(
+            b"PairCreated(address,address,address,uint256)",
+        ))
+    }
+
+    /// Get the configured refresh interval
+    pub fn refresh_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.settings.refresh_interval_ms.max(1_000))
+    }
+
+    /// Scan the blocks since the last scan for new whitelisted pairs
+    ///
+    /// The first scan starts at `from_block`, or `lookback_blocks` before the head. Progress is
+    /// kept chunk by chunk, so a failed scan resumes where it stopped.
+    pub async fn scan(&self) -> Result<Vec<DiscoveredPair>> {
+        if self.whitelist.len() < 2 {
+            return Ok(Vec::new());
+        }
+
+        let head = self.blockchain_client.get_block_number().await?.as_u64();
+        let last_scanned = *self.last_scanned.lock().unwrap_or_else(|e| e.into_inner());
+        let from_block = match last_scanned {
+            Some(block) => block + 1,
+            None => self
+                .settings
+                .from_block
+                .unwrap_or_else(|| head.saturating_sub(self.settings.lookback_blocks)),
+        };
+
+        // Filter on the indexed token topics at the node: both tokens must be whitelisted, or
+        // with unlisted tokens either one, which takes a filter per side
+        let tokens: Vec<H256> = self.whitelist.keys().map(|&token| token.into()).collect();
+        let base = Filter::new()
+            .address(self.factory_address)
+            .topic0(Self::pair_created_topic());
+        let filters = if self.settings.include_unlisted_tokens {
+            vec![base.clone().topic1(tokens.clone()), base.topic2(tokens)]
+        } else {
+            vec![base.topic1(tokens.clone()).topic2(tokens)]
+        };
+        let chunk_size = self.settings.chunk_size.max(1);
+
+        let mut pairs = Vec::new();
+        let mut start = from_block;
+        while start <= head {
+            let end = (start + chunk_size - 1).min(head);
+            for filter in &filters {
+                let filter = filter.clone().from_block(start).to_block(end);
+                let logs = self
+                    .blockchain_client
+                    .get_logs(&filter)
+                    .await
+                    .with_context(|| {
+                        format!("Failed to fetch PairCreated logs {}-{}", start, end)
+                    })?;
+                for pair in logs.iter().filter_map(Self::decode_pair_created) {
+                    // A pair of two whitelisted tokens matches both filters
+                    if !pairs.contains(&pair) {
+                        pairs.push(pair);
+                    }
+                }
+            }
+
+            *self.last_scanned.lock().unwrap_or_else(|e| e.into_inner()) = Some(end);
+            start = end + 1;
+        }
+
+        if from_block <= head {
+            debug!(
+                "Scanned blocks {}-{} of factory {:?}: {} whitelisted pairs",
+                from_block,
+                head,
+                self.factory_address,
+                pairs.len()
+            );
+        }
+
+        Ok(pairs)
+    }
+
+    /// Whether a pair's reserves, in on-chain token order, meet the minimum on both sides
+    ///
+    /// Only the whitelisted side of a pair with an unlisted token is checked, since the
+    /// unlisted token's decimals are unknown.
+    pub fn meets_min_reserves(&self, pair: &DiscoveredPair, reserves: &[U256]) -> bool {
+        if reserves.len() != 2 {
+            return false;
+        }
+
+        let mut checked = 0;
+        for (token, &reserve) in [pair.token0, pair.token1].iter().zip(reserves) {
+            if let Some(&decimals) = self.whitelist.get(token) {
+                if u256_to_decimal(reserve, decimals) < self.settings.min_reserve {
+                    return false;
+                }
+                checked += 1;
+            }
+        }
+
+        checked == 2 || (checked == 1 && self.settings.include_unlisted_tokens)
+    }
+
+    /// Get a pair's unlisted token and the whitelisted token it is paired with, if it has one
+    pub fn unlisted_token(&self, pair: &DiscoveredPair) -> Option<(Address, Address)> {
+        match (
+            self.whitelist.contains_key(&pair.token0),
+            self.whitelist.contains_key(&pair.token1),
+        ) {
+            (true, false) => Some((pair.token1, pair.token0)),
+            (false, true) => Some((pair.token0, pair.token1)),
+            _ => None,
+        }
+    }
+
+    /// Decode a `PairCreated` log, whose tokens are indexed and pair address is in the data
+    fn decode_pair_created(log: &Log) -> Option<DiscoveredPair> {
+        if log.topics.len() != 3 || log.data.len() < 32 {
+            return None;
+        }
+
+        Some(DiscoveredPair {
+            address: Address::from_slice(&log.data[12..32]),
+            token0: Address::from(log.topics[1]),
+            token1: Address::from(log.topics[2]),
+            block_number: log
+                .block_number
+                .map(|block| block.as_u64())
+                .unwrap_or_default(),
+        })
+    }
+}
@@ -3,20 +3,37 @@
 //! This module is responsible for interfacing with decentralized exchanges.
 
 mod curve;
+mod discovery;
+mod overrides;
+mod pool_cache;
+mod quarantine;
 mod sushiswap;
 mod uniswap;
 
+pub use discovery::PairDiscovery;
+pub use overrides::DexOverrides;
+pub use pool_cache::{get_amount_in, get_amount_out, PoolState, PoolStateCache};
+pub use quarantine::{pool_key, PoolKey, PoolQuarantine};
+
 use anyhow::Result;
 use async_trait::async_trait;
-use ethers::providers::Provider;
-use ethers::types::{Address, U256};
+use ethers::middleware::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, BlockId, BlockNumber, TransactionRequest, U256};
+use log::{debug, warn};
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use crate::assets::ContractAbi;
+use crate::blockchain::RpcClient;
 use crate::config::Config;
+use crate::metrics;
+use crate::screening::create_screener;
+use crate::storage::Storage;
+use crate::utils::{Call, Multicall};
 
 /// DEX type
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum DexType {
     /// Uniswap V2
     UniswapV2,
@@ -24,10 +41,36 @@ pub enum DexType {
     /// Sushiswap
     Sushiswap,
 
+    /// Sushi Trident (constant-product and stable pools on the BentoBox)
+    Trident,
+
     /// Curve
     Curve,
 }
 
+impl DexType {
+    /// Parse a DEX name as used in opportunity DEX paths (e.g. "UniswapV2")
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "UniswapV2" => Some(Self::UniswapV2),
+            "Sushiswap" => Some(Self::Sushiswap),
+            "Trident" => Some(Self::Trident),
+            "Curve" => Some(Self::Curve),
+            _ => None,
+        }
+    }
+
+    /// Get the DEX name as used in opportunity DEX paths
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::UniswapV2 => "UniswapV2",
+            Self::Sushiswap => "Sushiswap",
+            Self::Trident => "Trident",
+            Self::Curve => "Curve",
+        }
+    }
+}
+
 /// Pool information
 #[derive(Debug, Clone)]
 pub struct PoolInfo {
@@ -75,6 +118,131 @@ pub struct TradeQuote {
     pub dex_type: DexType,
 }
 
+impl TradeQuote {
+    /// Get the pool the quote trades through, or the zero address if it names none (the
+    /// executor contract then routes the swap itself)
+    pub fn pool(&self) -> Address {
+        self.pools.first().copied().unwrap_or_default()
+    }
+}
+
+/// Get the name of the DEX of each swap of a route
+pub fn dex_names(hops: &[(DexType, Address)]) -> Vec<String> {
+    hops.iter()
+        .map(|(dex_type, _)| dex_type.name().to_string())
+        .collect()
+}
+
+/// Calculate the price impact of a trade in basis points
+///
+/// Impact is how far the output falls short of `spot_output`, the output at the pool's
+/// marginal price after fees. A pool without a marginal price counts as full impact.
+pub fn price_impact_bps(spot_output: U256, amount_out: U256) -> u32 {
+    if spot_output.is_zero() {
+        return 10_000;
+    }
+    if amount_out >= spot_output {
+        return 0;
+    }
+
+    let shortfall = (spot_output - amount_out).saturating_mul(U256::from(10_000)) / spot_output;
+    shortfall.low_u32()
+}
+
+/// Calculate the price impact of a swap through a constant-product pool, in basis points
+pub fn constant_product_price_impact(
+    pool: &PoolInfo,
+    token_in: Address,
+    amount_in: U256,
+    amount_out: U256,
+) -> u32 {
+    let reserves = match (
+        pool.tokens.iter().position(|&token| token == token_in),
+        &pool.reserves[..],
+    ) {
+        (Some(0), &[reserve0, reserve1]) => (reserve0, reserve1),
+        (Some(1), &[reserve0, reserve1]) => (reserve1, reserve0),
+        _ => return 10_000,
+    };
+
+    price_impact_bps(
+        pool_cache::spot_amount_out(amount_in, reserves.0, reserves.1, pool.fee),
+        amount_out,
+    )
+}
+
+/// Block at which quotes and reserves are read
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BlockTag {
+    /// The latest mined block, whichever it is when the read is made
+    #[default]
+    Latest,
+
+    /// The pending block, including transactions not yet mined
+    Pending,
+
+    /// A specific block, so that every read of an evaluation sees the same state
+    Number(u64),
+}
+
+impl From<BlockTag> for BlockId {
+    fn from(block: BlockTag) -> Self {
+        match block {
+            BlockTag::Latest => BlockNumber::Latest.into(),
+            BlockTag::Pending => BlockNumber::Pending.into(),
+            BlockTag::Number(number) => number.into(),
+        }
+    }
+}
+
+/// Read the reserves of a Uniswap V2 style pair with `getReserves`, in on-chain token order
+pub async fn read_pair_reserves(
+    client: &RpcClient,
+    pool: Address,
+    block: BlockTag,
+) -> Result<Vec<U256>> {
+    let pair = ContractAbi::UniswapV2Pair.contract();
+    let call: TypedTransaction = TransactionRequest::new()
+        .to(pool)
+        .data(pair.encode("getReserves", ())?)
+        .into();
+
+    let output = client.call(&call, Some(block.into())).await?;
+    let (reserve0, reserve1, _): (U256, U256, u32) = pair.decode_output("getReserves", output)?;
+
+    Ok(vec![reserve0, reserve1])
+}
+
+/// Read the reserves of many Uniswap V2 style pairs with batched `getReserves` calls
+///
+/// Returns the reserves of each pool in order, in on-chain token order, or `None` for a pool
+/// whose call failed.
+async fn read_pairs_reserves(
+    multicall: &Multicall,
+    pools: &[Address],
+    block: BlockTag,
+) -> Result<Vec<Option<Vec<U256>>>> {
+    let pair = ContractAbi::UniswapV2Pair.contract();
+    let data = pair.encode("getReserves", ())?;
+    let calls: Vec<Call> = pools
+        .iter()
+        .map(|&pool| Call {
+            target: pool,
+            data: data.clone(),
+        })
+        .collect();
+
+    let outputs = multicall.aggregate(&calls, block.into()).await?;
+    Ok(outputs
+        .into_iter()
+        .map(|output| {
+            let (reserve0, reserve1, _): (U256, U256, u32) =
+                pair.decode_output("getReserves", output?).ok()?;
+            Some(vec![reserve0, reserve1])
+        })
+        .collect())
+}
+
 /// Interface for DEX interactions
 #[async_trait]
 pub trait DexInterface: Send + Sync {
@@ -96,15 +264,16 @@ pub trait DexInterface: Send + Sync {
     /// Get a specific pool
     async fn get_pool(&self, token_a: Address, token_b: Address) -> Result<Option<PoolInfo>>;
 
-    /// Get the reserves for a pool
-    async fn get_reserves(&self, pool: Address) -> Result<Vec<U256>>;
+    /// Get the reserves for a pool at a block
+    async fn get_reserves(&self, pool: Address, block: BlockTag) -> Result<Vec<U256>>;
 
-    /// Get a quote for a trade
+    /// Get a quote for a trade against the state at a block
     async fn get_quote(
         &self,
         input_token: Address,
         output_token: Address,
         input_amount: U256,
+        block: BlockTag,
     ) -> Result<TradeQuote>;
 
     /// Find the best path for a trade
@@ -119,18 +288,88 @@ pub trait DexInterface: Send + Sync {
 /// Collection of DEX interfaces
 pub struct DexInterfaces {
     interfaces: HashMap<DexType, Arc<dyn DexInterface>>,
-    test_mode: bool,
+    pool_cache: Arc<PoolStateCache>,
+    quarantine: PoolQuarantine,
+    overrides: DexOverrides,
+    multicall: Arc<Multicall>,
 }
 
 impl DexInterfaces {
-    /// Create a new collection of DEX interfaces
-    pub fn new(test_mode: bool) -> Self {
+    /// Create a new collection of DEX interfaces, batching pool reads through `multicall`
+    pub fn new(config: &Config, multicall: Arc<Multicall>) -> Self {
         Self {
             interfaces: HashMap::new(),
-            test_mode,
+            pool_cache: Arc::new(PoolStateCache::new()),
+            quarantine: PoolQuarantine::new(config),
+            overrides: DexOverrides::new(),
+            multicall,
         }
     }
 
+    /// Read the reserves of the cached pools the cache does not hold at a block, in batched
+    /// calls, so every quote of a scan at the block is computed locally
+    ///
+    /// Without the batch, quotes at the block read their pools one call at a time.
+    pub async fn prefetch_reserves(&self, block: BlockTag) {
+        let pools = self.pool_cache.missing_at(block).await;
+        if pools.is_empty() {
+            return;
+        }
+
+        let reserves = match read_pairs_reserves(&self.multicall, &pools, block).await {
+            Ok(reserves) => reserves,
+            Err(e) => {
+                warn!("Failed to batch pool reserves at {:?}: {}", block, e);
+                return;
+            }
+        };
+        let snapshot: HashMap<Address, (U256, U256)> = pools
+            .into_iter()
+            .zip(reserves)
+            .filter_map(|(pool, reserves)| match reserves?[..] {
+                [reserve0, reserve1] => Some((pool, (reserve0, reserve1))),
+                _ => None,
+            })
+            .collect();
+        debug!(
+            "Prefetched reserves of {} pools at {:?}",
+            snapshot.len(),
+            block
+        );
+        metrics::global().increment_counter("multicall_reserve_reads", snapshot.len() as u64);
+        self.pool_cache.load_snapshot(block, snapshot).await;
+    }
+
+    /// Get the pool state cache shared by the DEX interfaces
+    pub fn pool_cache(&self) -> Arc<PoolStateCache> {
+        self.pool_cache.clone()
+    }
+
+    /// Get the quarantine of pools that stopped quoting
+    pub fn quarantine(&self) -> &PoolQuarantine {
+        &self.quarantine
+    }
+
+    /// Get the DEXes and pools taken out of the scan at runtime
+    pub fn overrides(&self) -> &DexOverrides {
+        &self.overrides
+    }
+
+    /// Enable or disable a configured DEX at runtime, returning whether that changed anything
+    pub fn set_dex_enabled(&self, dex_type: DexType, enabled: bool) -> Result<bool> {
+        if !self.interfaces.contains_key(&dex_type) {
+            anyhow::bail!("{} is not configured", dex_type.name());
+        }
+
+        Ok(self.overrides.set_dex_enabled(dex_type, enabled))
+    }
+
+    /// Blacklist a pool at runtime or lift its blacklisting, returning whether that changed
+    /// anything
+    pub fn set_pool_blacklisted(&self, pool: Address, blacklisted: bool) -> bool {
+        self.overrides.set_pool_blacklisted(pool, blacklisted)
+    }
+
     /// Add a DEX interface
     pub fn add_interface(&mut self, interface: Arc<dyn DexInterface>) {
         self.interfaces.insert(interface.dex_type(), interface);
@@ -146,33 +385,49 @@ impl DexInterfaces {
         self.interfaces.values().cloned().collect()
     }
 
-    /// Get a quote from all DEXes
+    /// Get the interfaces of the DEXes not disabled at runtime
+    pub fn enabled_interfaces(&self) -> Vec<Arc<dyn DexInterface>> {
+        self.interfaces
+            .values()
+            .filter(|interface| self.overrides.is_dex_enabled(interface.dex_type()))
+            .cloned()
+            .collect()
+    }
+
+    /// Get a quote from all DEXes at a block
+    ///
+    /// DEXes disabled at runtime are skipped, as are those whose pool for the pair is
+    /// blacklisted, or quarantined until it is due for a re-check.
     pub async fn get_quotes(
         &self,
         input_token: Address,
         output_token: Address,
         input_amount: U256,
+        block: BlockTag,
     ) -> Result<Vec<TradeQuote>> {
         let mut quotes = Vec::new();
 
-        for interface in self.interfaces.values() {
+        for interface in self.enabled_interfaces() {
+            let key = pool_key(interface.dex_type(), input_token, output_token);
+            if !self.quarantine.should_quote(key) {
+                continue;
+            }
+
             match interface
-                .get_quote(input_token, output_token, input_amount)
+                .get_quote(input_token, output_token, input_amount, block)
                 .await
             {
+                Ok(quote) if self.overrides.is_pool_blacklisted(quote.pool()) => {}
+                Ok(quote) if quote.output_amount.is_zero() => {
+                    // A drained pool quotes nothing
+                    self.quarantine.record_failure(key, "quoted zero output");
+                }
                 Ok(quote) => {
+                    self.quarantine.record_success(key);
                     quotes.push(quote);
                 }
                 Err(e) => {
-                    // In test mode, log expected errors at debug level instead of warn
-                    if self.test_mode
-                        && (e.to_string().contains("Invalid data")
-                            || e.to_string().contains("Invalid name"))
-                    {
-                        log::debug!("Failed to get quote from {}: {}", interface.name(), e);
-                    } else {
-                        log::warn!("Failed to get quote from {}: {}", interface.name(), e);
-                    }
+                    self.quarantine.record_failure(key, &format!("{:#}", e));
                 }
             }
         }
@@ -180,15 +435,16 @@ impl DexInterfaces {
         Ok(quotes)
     }
 
-    /// Find the best quote across all DEXes
+    /// Find the best quote across all DEXes at a block
     pub async fn find_best_quote(
         &self,
         input_token: Address,
         output_token: Address,
         input_amount: U256,
+        block: BlockTag,
     ) -> Result<Option<TradeQuote>> {
         let quotes = self
-            .get_quotes(input_token, output_token, input_amount)
+            .get_quotes(input_token, output_token, input_amount, block)
             .await?;
 
         if quotes.is_empty() {
@@ -206,26 +462,54 @@ impl DexInterfaces {
 }
 
 /// Create DEX interfaces
+///
+/// Unlisted tokens found by pair discovery are screened before their pools are added, with the
+/// outcomes kept in `storage`.
 pub async fn create_interfaces(
     config: &Arc<Config>,
-    blockchain_client: Arc<Provider<ethers::providers::Http>>,
+    blockchain_client: Arc<RpcClient>,
+    storage: Option<&Arc<Storage>>,
 ) -> Result<Arc<DexInterfaces>> {
-    let mut interfaces = DexInterfaces::new(config.test_mode);
+    let multicall = Arc::new(Multicall::new(config, blockchain_client.clone())?);
+    let mut interfaces = DexInterfaces::new(config, multicall.clone());
+    let screener = if config.dex.pair_discovery.include_unlisted_tokens {
+        Some(create_screener(
+            config,
+            blockchain_client.clone(),
+            storage.cloned(),
+        )?)
+    } else {
+        None
+    };
 
     // Create Uniswap interface if enabled
     if config.dex.uniswap.enabled {
-        let uniswap_interface =
-            uniswap::create_interface(config, blockchain_client.clone()).await?;
+        let uniswap_interface = uniswap::create_interface(
+            config,
+            blockchain_client.clone(),
+            interfaces.pool_cache(),
+            multicall,
+            screener,
+        )
+        .await?;
         interfaces.add_interface(uniswap_interface);
     }
 
     // Create Sushiswap interface if enabled
     if config.dex.sushiswap.enabled {
         let sushiswap_interface =
-            sushiswap::create_interface(config, blockchain_client.clone()).await?;
+            sushiswap::create_interface(config, blockchain_client.clone(), interfaces.pool_cache())
+                .await?;
         interfaces.add_interface(sushiswap_interface);
     }
 
+    // Create Sushi Trident interface if enabled
+    if config.dex.trident.enabled {
+        let trident_interface =
+            sushiswap::create_trident_interface(config, blockchain_client.clone()).await?;
+        interfaces.add_interface(trident_interface);
+    }
+
     // Create Curve interface if enabled
     if config.dex.curve.enabled {
         let curve_interface = curve::create_interface(config, blockchain_client.clone()).await?;
@@ -0,0 +1,120 @@
+//! DEX Overrides Module
+//!
+//! This module is responsible for taking DEXes and pools out of the scan at runtime, e.g. when
+//! a DEX's quotes start reverting, without restarting the bot. Quotes and pool lists consult the
+//! overrides each time they are read, so the next scan respects them. Overrides are not
+//! persisted across restarts.
+
+use ethers::types::Address;
+use log::info;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+use crate::dex::DexType;
+use crate::metrics;
+
+/// DEXes disabled and pools blacklisted at runtime
+#[derive(Debug, Default)]
+pub struct DexOverrides {
+    disabled_dexes: Mutex<BTreeSet<DexType>>,
+    blacklisted_pools: Mutex<BTreeSet<Address>>,
+}
+
+impl DexOverrides {
+    /// Create overrides leaving every DEX and pool enabled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a DEX is quoted
+    pub fn is_dex_enabled(&self, dex_type: DexType) -> bool {
+        !self
+            .disabled_dexes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(&dex_type)
+    }
+
+    /// Enable or disable a DEX, returning whether that changed anything
+    pub fn set_dex_enabled(&self, dex_type: DexType, enabled: bool) -> bool {
+        let mut disabled = self
+            .disabled_dexes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let changed = if enabled {
+            disabled.remove(&dex_type)
+        } else {
+            disabled.insert(dex_type)
+        };
+
+        if changed {
+            info!(
+                "{} {} at runtime",
+                if enabled { "Enabled" } else { "Disabled" },
+                dex_type.name()
+            );
+            metrics::global().set_gauge("dexes_disabled", disabled.len() as f64);
+        }
+        changed
+    }
+
+    /// Get the DEXes disabled at runtime
+    pub fn disabled_dexes(&self) -> Vec<DexType> {
+        self.disabled_dexes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .copied()
+            .collect()
+    }
+
+    /// Whether a pool is blacklisted
+    pub fn is_pool_blacklisted(&self, pool: Address) -> bool {
+        self.blacklisted_pools
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(&pool)
+    }
+
+    /// Blacklist a pool or lift its blacklisting, returning whether that changed anything
+    pub fn set_pool_blacklisted(&self, pool: Address, blacklisted: bool) -> bool {
+        let mut pools = self
+            .blacklisted_pools
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let changed = if blacklisted {
+            pools.insert(pool)
+        } else {
+            pools.remove(&pool)
+        };
+
+        if changed {
+            info!(
+                "{} pool {:?} at runtime",
+                if blacklisted {
+                    "Blacklisted"
+                } else {
+                    "Lifted the blacklisting of"
+                },
+                pool
+            );
+            metrics::global().set_gauge("pools_blacklisted", pools.len() as f64);
+        }
+        changed
+    }
+
+    /// Get the pools blacklisted at runtime
+    pub fn blacklisted_pools(&self) -> Vec<Address> {
+        self.blacklisted_pools
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .copied()
+            .collect()
+    }
+
+    /// Whether a DEX's pool may be traded through
+    pub fn allows(&self, dex_type: DexType, pool: Address) -> bool {
+        self.is_dex_enabled(dex_type) && !self.is_pool_blacklisted(pool)
+    }
+}
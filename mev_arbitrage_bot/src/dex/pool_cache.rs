@@ -0,0 +1,365 @@
+//! Pool State Cache Module
+//!
+//! This module keeps the reserves of constant-product pools in memory, updated from `Sync`
+//! and `Swap` logs, so quotes can be computed locally instead of via `eth_call`.
+//!
+//! Blocks the cache does not hold (the pending block, or a pinned block it is not synced
+//! through) are served from a snapshot of reserves read in batched calls at the start of a scan.
+
+use ethers::types::{Address, Log, H256, U256};
+use log::debug;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::RwLock;
+
+use crate::dex::{BlockTag, DexType, PoolInfo};
+use crate::tokens;
+
+/// Cached state of a single constant-product pool
+#[derive(Debug, Clone)]
+pub struct PoolState {
+    /// Pool address
+    pub address: Address,
+
+    /// DEX type
+    pub dex_type: DexType,
+
+    /// Token0 (the lower of the two token addresses)
+    pub token0: Address,
+
+    /// Token1 (the higher of the two token addresses)
+    pub token1: Address,
+
+    /// Reserve of token0
+    pub reserve0: U256,
+
+    /// Reserve of token1
+    pub reserve1: U256,
+
+    /// Pool fee (in basis points)
+    pub fee: u32,
+
+    /// Block number of the last reserve update
+    ///
+    /// Pools seeded via RPC count from the block after the last synced one (0 before the first).
+    pub last_block: u64,
+
+    /// Number of swaps observed since the pool was registered
+    pub swap_count: u64,
+}
+
+impl PoolState {
+    /// Get the reserves ordered as (reserve_in, reserve_out) for a swap from `token_in`
+    pub fn reserves_for(&self, token_in: Address) -> Option<(U256, U256)> {
+        if token_in == self.token0 {
+            Some((self.reserve0, self.reserve1))
+        } else if token_in == self.token1 {
+            Some((self.reserve1, self.reserve0))
+        } else {
+            None
+        }
+    }
+}
+
+/// Number of recent swap transactions remembered by the cache
+const RECENT_SWAPS: usize = 4_096;
+
+/// Reserves read at a block the cache does not hold
+struct ReserveSnapshot {
+    /// Block the reserves were read at
+    block: BlockTag,
+
+    /// Last synced block when the reserves were read; a pending snapshot expires past it
+    synced_block: u64,
+
+    /// Reserves of each pool, in on-chain order
+    reserves: HashMap<Address, (U256, U256)>,
+}
+
+/// In-memory cache of pool reserves, kept current from on-chain events
+pub struct PoolStateCache {
+    pools: RwLock<HashMap<Address, PoolState>>,
+    recent_swaps: RwLock<VecDeque<H256>>,
+    synced_block: AtomicU64,
+    snapshot: RwLock<Option<ReserveSnapshot>>,
+}
+
+impl Default for PoolStateCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PoolStateCache {
+    /// Create an empty pool state cache
+    pub fn new() -> Self {
+        Self {
+            pools: RwLock::new(HashMap::new()),
+            recent_swaps: RwLock::new(VecDeque::new()),
+            synced_block: AtomicU64::new(0),
+            snapshot: RwLock::new(None),
+        }
+    }
+
+    /// Topic of the Uniswap V2 `Sync(uint112,uint112)` event
+    pub fn sync_topic() -> H256 {
+        H256::from(ethers::utils::keccak256(b"Sync(uint112,uint112)"))
+    }
+
+    /// Topic of the Uniswap V2 `Swap(address,uint256,uint256,uint256,uint256,address)` event
+    pub fn swap_topic() -> H256 {
+        H256::from(ethers::utils::keccak256(
+            b"Swap(address,uint256,uint256,uint256,uint256,address)",
+        ))
+    }
+
+    /// Register a pool with its current reserves
+    ///
+    /// `reserves` must be in on-chain order (token0, token1); the token order of `pool.tokens`
+    /// does not matter since V2 pairs always sort their tokens by address.
+    pub async fn register_pool(&self, pool: &PoolInfo, reserves: (U256, U256)) {
+        if pool.tokens.len() != 2 {
+            return;
+        }
+
+        let (token0, token1) = if pool.tokens[0] < pool.tokens[1] {
+            (pool.tokens[0], pool.tokens[1])
+        } else {
+            (pool.tokens[1], pool.tokens[0])
+        };
+
+        // The reserves were read at the head, which may be past the last synced block
+        let synced_block = self.synced_block();
+        let seeded_block = if synced_block > 0 {
+            synced_block + 1
+        } else {
+            0
+        };
+
+        let mut pools = self.pools.write().await;
+        pools.entry(pool.address).or_insert_with(|| PoolState {
+            address: pool.address,
+            dex_type: pool.dex_type,
+            token0,
+            token1,
+            reserve0: reserves.0,
+            reserve1: reserves.1,
+            fee: pool.fee,
+            last_block: seeded_block,
+            swap_count: 0,
+        });
+    }
+
+    /// Get the cached state of a pool
+    pub async fn get(&self, pool: Address) -> Option<PoolState> {
+        self.pools.read().await.get(&pool).cloned()
+    }
+
+    /// Get the cached state of a pool as of a block, if the cache holds it
+    ///
+    /// The cache only keeps the current state, which is the state of a pinned block once the cache
+    /// is synced through that block and the pool has not changed since. Other blocks are served
+    /// from the reserve snapshot when it was read at the block.
+    pub async fn get_at(&self, pool: Address, block: BlockTag) -> Option<PoolState> {
+        let current = self.get(pool).await?;
+        if self.holds(&current, block) {
+            return Some(current);
+        }
+
+        let snapshot = self.snapshot.read().await;
+        let snapshot = snapshot.as_ref().filter(|snapshot| {
+            snapshot.block == block
+                && (block != BlockTag::Pending || snapshot.synced_block == self.synced_block())
+        })?;
+        let &(reserve0, reserve1) = snapshot.reserves.get(&pool)?;
+
+        Some(PoolState {
+            reserve0,
+            reserve1,
+            ..current
+        })
+    }
+
+    /// Whether a pool's current state is its state at a block
+    fn holds(&self, state: &PoolState, block: BlockTag) -> bool {
+        match block {
+            BlockTag::Latest => true,
+            BlockTag::Pending => false,
+            BlockTag::Number(number) => state.last_block <= number && number <= self.synced_block(),
+        }
+    }
+
+    /// Get the constant-product pools whose state at a block the cache does not hold
+    pub async fn missing_at(&self, block: BlockTag) -> Vec<Address> {
+        self.pools
+            .read()
+            .await
+            .values()
+            .filter(|state| {
+                matches!(state.dex_type, DexType::UniswapV2 | DexType::Sushiswap)
+                    && !self.holds(state, block)
+            })
+            .map(|state| state.address)
+            .collect()
+    }
+
+    /// Serve reads at a block from reserves read at it, replacing the previous snapshot
+    ///
+    /// `reserves` must be in on-chain order (reserve0, reserve1).
+    pub async fn load_snapshot(&self, block: BlockTag, reserves: HashMap<Address, (U256, U256)>) {
+        *self.snapshot.write().await = Some(ReserveSnapshot {
+            block,
+            synced_block: self.synced_block(),
+            reserves,
+        });
+    }
+
+    /// Record that the logs of every block up to `block_number` have been applied
+    pub fn mark_synced(&self, block_number: u64) {
+        self.synced_block.fetch_max(block_number, Ordering::Relaxed);
+    }
+
+    /// Get the last block whose logs have been applied (0 if the cache is not synced from blocks)
+    pub fn synced_block(&self) -> u64 {
+        self.synced_block.load(Ordering::Relaxed)
+    }
+
+    /// Get the cached state of every tracked pool
+    pub async fn pools(&self) -> Vec<PoolState> {
+        self.pools.read().await.values().cloned().collect()
+    }
+
+    /// Get the addresses of all tracked pools
+    pub async fn watched_pools(&self) -> Vec<Address> {
+        self.pools.read().await.keys().copied().collect()
+    }
+
+    /// Apply a `Sync` or `Swap` log to the cache
+    ///
+    /// Returns true if the log belonged to a tracked pool and updated its state.
+    pub async fn apply_log(&self, log: &Log) -> bool {
+        let topic = match log.topics.first() {
+            Some(topic) => *topic,
+            None => return false,
+        };
+
+        let block = log.block_number.map(|b| b.as_u64()).unwrap_or_default();
+        let mut pools = self.pools.write().await;
+        let state = match pools.get_mut(&log.address) {
+            Some(state) => state,
+            None => return false,
+        };
+
+        if topic == Self::sync_topic() {
+            if log.data.len() != 64 || block < state.last_block {
+                return false;
+            }
+
+            state.reserve0 = U256::from_big_endian(&log.data[0..32]);
+            state.reserve1 = U256::from_big_endian(&log.data[32..64]);
+            state.last_block = block;
+
+            debug!(
+                "Updated reserves for pool {} at block {}: {} / {}",
+                tokens::pool_label(state.address),
+                block,
+                state.reserve0,
+                state.reserve1
+            );
+            true
+        } else if topic == Self::swap_topic() {
+            // Reserves always arrive with the preceding Sync; the swap only marks activity
+            state.swap_count += 1;
+            drop(pools);
+
+            if let Some(tx_hash) = log.transaction_hash {
+                let mut recent_swaps = self.recent_swaps.write().await;
+                if recent_swaps.len() == RECENT_SWAPS {
+                    recent_swaps.pop_front();
+                }
+                recent_swaps.push_back(tx_hash);
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Whether a recently applied `Swap` log came from a transaction
+    pub async fn has_applied_swap(&self, tx_hash: H256) -> bool {
+        self.recent_swaps.read().await.contains(&tx_hash)
+    }
+
+    /// Quote a swap through a cached pool at a block using the constant-product formula
+    pub async fn quote(
+        &self,
+        pool: Address,
+        token_in: Address,
+        amount_in: U256,
+        block: BlockTag,
+    ) -> Option<U256> {
+        let state = self.get_at(pool, block).await?;
+        let (reserve_in, reserve_out) = state.reserves_for(token_in)?;
+        let amount_out = get_amount_out(amount_in, reserve_in, reserve_out, state.fee);
+
+        if amount_out.is_zero() {
+            None
+        } else {
+            Some(amount_out)
+        }
+    }
+}
+
+/// Calculate the output of a constant-product swap at the pool's marginal price, after fees
+///
+/// This is what the swap would return without moving the price, the baseline for its price impact.
+pub fn spot_amount_out(amount_in: U256, reserve_in: U256, reserve_out: U256, fee_bps: u32) -> U256 {
+    if reserve_in.is_zero() || fee_bps >= 10_000 {
+        return U256::zero();
+    }
+
+    amount_in
+        .saturating_mul(reserve_out)
+        .saturating_mul(U256::from(10_000 - fee_bps))
+        .checked_div(reserve_in.saturating_mul(U256::from(10_000)))
+        .unwrap_or_default()
+}
+
+/// Calculate the input amount a constant-product swap needs for an output (Uniswap V2
+/// `getAmountIn`), or `None` if the pool cannot pay it out
+pub fn get_amount_in(
+    amount_out: U256,
+    reserve_in: U256,
+    reserve_out: U256,
+    fee_bps: u32,
+) -> Option<U256> {
+    if amount_out.is_zero()
+        || reserve_in.is_zero()
+        || amount_out >= reserve_out
+        || fee_bps >= 10_000
+    {
+        return None;
+    }
+
+    let numerator = reserve_in
+        .saturating_mul(amount_out)
+        .saturating_mul(U256::from(10_000));
+    let denominator = (reserve_out - amount_out).saturating_mul(U256::from(10_000 - fee_bps));
+
+    numerator.checked_div(denominator).map(|amount| amount + 1)
+}
+
+/// Calculate the output amount of a constant-product swap (Uniswap V2 `getAmountOut`)
+pub fn get_amount_out(amount_in: U256, reserve_in: U256, reserve_out: U256, fee_bps: u32) -> U256 {
+    if amount_in.is_zero() || reserve_in.is_zero() || reserve_out.is_zero() || fee_bps >= 10_000 {
+        return U256::zero();
+    }
+
+    let amount_in_with_fee = amount_in.saturating_mul(U256::from(10_000 - fee_bps));
+    let numerator = amount_in_with_fee.saturating_mul(reserve_out);
+    let denominator = reserve_in
+        .saturating_mul(U256::from(10_000))
+        .saturating_add(amount_in_with_fee);
+
+    numerator.checked_div(denominator).unwrap_or_default()
+}
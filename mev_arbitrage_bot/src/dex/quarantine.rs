@@ -0,0 +1,177 @@
+//! Pool Quarantine Module
+//!
+//! This module is responsible for keeping pools that no longer quote out of the scan. A pool
+//! is quarantined after repeated failed quotes, whether its calls revert (a paused or migrated
+//! pool) or it quotes nothing (drained reserves). Quarantined pools are skipped and re-checked
+//! on an interval, and are released as soon as they quote again. Failures are only logged above
+//! debug level when a pool enters quarantine, so a dead pool does not warn on every scan.
+
+use ethers::types::Address;
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::config::Config;
+use crate::dex::DexType;
+use crate::metrics;
+use crate::tokens;
+use crate::utils::current_timestamp;
+
+/// Default number of consecutive failed quotes before a pool is quarantined
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 3;
+
+/// Default interval between re-checks of a quarantined pool (in seconds)
+pub const DEFAULT_RECHECK_INTERVAL_SECS: u64 = 300;
+
+/// A DEX's pool for a token pair, with the tokens in address order
+pub type PoolKey = (DexType, Address, Address);
+
+/// Get the key of a DEX's pool for a token pair, in either token order
+pub fn pool_key(dex_type: DexType, token_a: Address, token_b: Address) -> PoolKey {
+    let (token0, token1) = tokens::sort_tokens(token_a, token_b);
+    (dex_type, token0, token1)
+}
+
+/// Failure record of one pool
+#[derive(Debug, Default)]
+struct PoolHealth {
+    /// Consecutive failed quotes
+    failures: u32,
+
+    /// When the pool was quarantined, if it is
+    quarantined_at: Option<u64>,
+
+    /// Earliest time a quarantined pool is quoted again
+    next_check: u64,
+}
+
+/// Tracks failing pools and keeps quarantined ones out of the scan
+pub struct PoolQuarantine {
+    failure_threshold: u32,
+    recheck_interval_secs: u64,
+    pools: Mutex<HashMap<PoolKey, PoolHealth>>,
+}
+
+impl PoolQuarantine {
+    /// Create a pool quarantine from the DEX configuration
+    pub fn new(config: &Config) -> Self {
+        let quarantine = &config.dex.quarantine;
+        Self {
+            failure_threshold: quarantine
+                .failure_threshold
+                .unwrap_or(DEFAULT_FAILURE_THRESHOLD)
+                .max(1),
+            recheck_interval_secs: quarantine
+                .recheck_interval_secs
+                .unwrap_or(DEFAULT_RECHECK_INTERVAL_SECS),
+            pools: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether a pool is quarantined
+    pub fn is_quarantined(&self, key: PoolKey) -> bool {
+        let pools = self.pools.lock().unwrap_or_else(|e| e.into_inner());
+        pools
+            .get(&key)
+            .is_some_and(|health| health.quarantined_at.is_some())
+    }
+
+    /// Whether a pool may be quoted now
+    ///
+    /// A quarantined pool is let through once per re-check interval.
+    pub fn should_quote(&self, key: PoolKey) -> bool {
+        let now = current_timestamp();
+        let mut pools = self.pools.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(health) = pools.get_mut(&key) else {
+            return true;
+        };
+        if health.quarantined_at.is_none() {
+            return true;
+        }
+        if now < health.next_check {
+            return false;
+        }
+
+        health.next_check = now + self.recheck_interval_secs;
+        debug!("Re-checking quarantined pool {}", pool_label(key));
+        true
+    }
+
+    /// Record a successful quote, releasing the pool if it was quarantined
+    pub fn record_success(&self, key: PoolKey) {
+        let mut pools = self.pools.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(health) = pools.remove(&key) else {
+            return;
+        };
+
+        if let Some(quarantined_at) = health.quarantined_at {
+            info!(
+                "Pool {} quotes again, releasing it from quarantine after {}s",
+                pool_label(key),
+                current_timestamp().saturating_sub(quarantined_at)
+            );
+            metrics::global().increment_counter("pool_quarantine_releases", 1);
+            Self::update_gauge(&pools);
+        }
+    }
+
+    /// Record a failed quote, quarantining the pool once it reaches the failure threshold
+    pub fn record_failure(&self, key: PoolKey, reason: &str) {
+        let now = current_timestamp();
+        let mut pools = self.pools.lock().unwrap_or_else(|e| e.into_inner());
+        let health = pools.entry(key).or_default();
+        health.failures += 1;
+
+        if health.quarantined_at.is_some() {
+            debug!(
+                "Quarantined pool {} still fails: {}; next re-check in {}s",
+                pool_label(key),
+                reason,
+                self.recheck_interval_secs
+            );
+            return;
+        }
+
+        if health.failures < self.failure_threshold {
+            debug!(
+                "Quote from pool {} failed ({}/{}): {}",
+                pool_label(key),
+                health.failures,
+                self.failure_threshold,
+                reason
+            );
+            return;
+        }
+
+        health.quarantined_at = Some(now);
+        health.next_check = now + self.recheck_interval_secs;
+        warn!(
+            "Quarantining pool {} after {} failed quotes: {}; re-checking every {}s",
+            pool_label(key),
+            health.failures,
+            reason,
+            self.recheck_interval_secs
+        );
+        metrics::global().increment_counter("pool_quarantines", 1);
+        Self::update_gauge(&pools);
+    }
+
+    /// Publish the number of quarantined pools
+    fn update_gauge(pools: &HashMap<PoolKey, PoolHealth>) {
+        let quarantined = pools
+            .values()
+            .filter(|health| health.quarantined_at.is_some())
+            .count();
+        metrics::global().set_gauge("quarantined_pools", quarantined as f64);
+    }
+}
+
+/// Name a pool by its DEX and tokens
+fn pool_label((dex_type, token0, token1): PoolKey) -> String {
+    format!(
+        "{} {}/{}",
+        dex_type.name(),
+        tokens::token_label(token0),
+        tokens::token_label(token1)
+    )
+}
@@ -1,37 +1,49 @@
 //! Sushiswap Interface Module
 //!
-//! This module is responsible for interfacing with Sushiswap.
+//! This module is responsible for interfacing with Sushiswap: the classic pairs, which share
+//! the Uniswap V2 contracts and math, and the Trident constant-product and stable pools. Trident
+//! pools hold their balances in the BentoBox vault as shares, which are worth more than one
+//! token each once the vault's strategies have earned, so they are quoted on-chain in shares
+//! and kept out of the pool state cache's constant-product math.
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use ethers::abi::{Abi, Token};
+use ethers::abi::{Detokenize, Token, Tokenize};
 use ethers::contract::{Contract, ContractCall, ContractInstance};
-use ethers::providers::Provider;
-use ethers::types::{Address, Bytes, U256};
+use ethers::middleware::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes, TransactionRequest, U256};
 use log::{debug, info, warn};
 use std::sync::{Arc, Mutex};
 
+use crate::assets::ContractAbi;
+use crate::blockchain::{Chain, RpcClient};
 use crate::config::Config;
-use crate::dex::{DexInterface, DexType, PoolInfo, TradeQuote};
+use crate::dex::{
+    constant_product_price_impact, price_impact_bps, read_pair_reserves, BlockTag, DexInterface,
+    DexType, PoolInfo, PoolStateCache, TradeQuote,
+};
+use crate::tokens;
 use crate::utils::validate_and_parse_address;
 
 /// Sushiswap interface
 pub struct SushiswapInterface {
     name: String,
+    chain: Chain,
     factory_address: Address,
     router_address: Address,
-    blockchain_client: Arc<Provider<ethers::providers::Http>>,
-    factory_contract:
-        ContractInstance<Arc<Provider<ethers::providers::Http>>, Provider<ethers::providers::Http>>,
-    router_contract:
-        ContractInstance<Arc<Provider<ethers::providers::Http>>, Provider<ethers::providers::Http>>,
+    blockchain_client: Arc<RpcClient>,
+    factory_contract: ContractInstance<Arc<RpcClient>, RpcClient>,
+    router_contract: ContractInstance<Arc<RpcClient>, RpcClient>,
     pools: Mutex<Vec<PoolInfo>>,
+    pool_cache: Arc<PoolStateCache>,
 }
 
 /// Create a new Sushiswap interface
 pub async fn create_interface(
     config: &Arc<Config>,
-    blockchain_client: Arc<Provider<ethers::providers::Http>>,
+    blockchain_client: Arc<RpcClient>,
+    pool_cache: Arc<PoolStateCache>,
 ) -> Result<Arc<dyn DexInterface>> {
     // Parse addresses
     let factory_address = match validate_and_parse_address(&config.dex.sushiswap.factory_address) {
@@ -53,71 +65,8 @@ pub async fn create_interface(
     };
 
     // Load ABIs (Sushiswap uses the same ABI as Uniswap V2)
-    let factory_abi = include_str!("./abi/uniswap_v2_factory.json");
-    let factory_abi: Abi = serde_json::from_str(factory_abi).unwrap_or_else(|_| {
-        // If the ABI file is not available, use a minimal ABI
-        let json = r#"[
-            {
-                "constant": true,
-                "inputs": [
-                    {
-                        "internalType": "address",
-                        "name": "tokenA",
-                        "type": "address"
-                    },
-                    {
-                        "internalType": "address",
-                        "name": "tokenB",
-                        "type": "address"
-                    }
-                ],
-                "name": "getPair",
-                "outputs": [
-                    {
-                        "internalType": "address",
-                        "name": "pair",
-                        "type": "address"
-                    }
-                ],
-                "payable": false,
-                "stateMutability": "view",
-                "type": "function"
-            }
-        ]"#;
-        serde_json::from_str(json).expect("Failed to parse fallback ABI")
-    });
-
-    let router_abi = include_str!("./abi/uniswap_v2_router.json");
-    let router_abi: Abi = serde_json::from_str(router_abi).unwrap_or_else(|_| {
-        // If the ABI file is not available, use a minimal ABI
-        let json = r#"[
-            {
-                "inputs": [
-                    {
-                        "internalType": "uint256",
-                        "name": "amountIn",
-                        "type": "uint256"
-                    },
-                    {
-                        "internalType": "address[]",
-                        "name": "path",
-                        "type": "address[]"
-                    }
-                ],
-                "name": "getAmountsOut",
-                "outputs": [
-                    {
-                        "internalType": "uint256[]",
-                        "name": "amounts",
-                        "type": "uint256[]"
-                    }
-                ],
-                "stateMutability": "view",
-                "type": "function"
-            }
-        ]"#;
-        serde_json::from_str(json).expect("Failed to parse fallback ABI")
-    });
+    let factory_abi = ContractAbi::UniswapV2Factory.abi().clone();
+    let router_abi = ContractAbi::UniswapV2Router.abi().clone();
 
     // Create contracts
     let factory_contract = Contract::new(factory_address, factory_abi, blockchain_client.clone());
@@ -125,12 +74,14 @@ pub async fn create_interface(
 
     let interface = SushiswapInterface {
         name: "Sushiswap".to_string(),
+        chain: Chain::from_id(config.ethereum.chain_id),
         factory_address,
         router_address,
         blockchain_client: blockchain_client.clone(),
         factory_contract,
         router_contract,
         pools: Mutex::new(Vec::new()),
+        pool_cache,
     };
 
     let interface = Arc::new(interface);
@@ -153,25 +104,23 @@ impl SushiswapInterface {
         // 3. Get the token addresses and reserves for each pool
 
         // For now, just create a dummy pool
-        let weth_address =
-            match validate_and_parse_address("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2") {
-                Ok(address) => address,
-                Err(e) => {
-                    log::warn!("Failed to parse WETH address: {}", e);
-                    // Provide a fallback address for testing
-                    Address::from_low_u64_be(6)
-                }
-            };
+        let weth_address = match validate_and_parse_address(self.chain.weth_address()) {
+            Ok(address) => address,
+            Err(e) => {
+                log::warn!("Failed to parse WETH address: {}", e);
+                // Provide a fallback address for testing
+                Address::from_low_u64_be(6)
+            }
+        };
 
-        let usdc_address =
-            match validate_and_parse_address("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48") {
-                Ok(address) => address,
-                Err(e) => {
-                    log::warn!("Failed to parse USDC address: {}", e);
-                    // Provide a fallback address for testing
-                    Address::from_low_u64_be(7)
-                }
-            };
+        let usdc_address = match validate_and_parse_address(self.chain.usdc_address()) {
+            Ok(address) => address,
+            Err(e) => {
+                log::warn!("Failed to parse USDC address: {}", e);
+                // Provide a fallback address for testing
+                Address::from_low_u64_be(7)
+            }
+        };
 
         let pool_address = self
             .factory_contract
@@ -180,26 +129,59 @@ impl SushiswapInterface {
             .await?;
 
         if pool_address != Address::zero() {
-            let reserves = self.get_reserves(pool_address).await?;
+            let reserves = self.get_reserves(pool_address, BlockTag::Latest).await?;
+
+            // Reserves come back in on-chain order, so the tokens must match it
+            let (token0, token1) = tokens::sort_tokens(weth_address, usdc_address);
 
             let pool_info = PoolInfo {
                 address: pool_address,
                 dex_type: DexType::Sushiswap,
-                tokens: vec![weth_address, usdc_address],
+                tokens: vec![token0, token1],
                 reserves,
                 fee: 30, // 0.3%
             };
 
+            // Track the pool's reserves locally
+            self.register_with_cache(&pool_info).await;
+            tokens::global().register_pool(pool_info.address, &self.name, &pool_info.tokens);
+
             // Add the pool to the list
             if let Ok(mut pools) = self.pools.lock() {
                 pools.push(pool_info);
             }
 
-            info!("Initialized Sushiswap WETH-USDC pool: {:?}", pool_address);
+            info!(
+                "Initialized Sushiswap pool: {}",
+                tokens::pool_label(pool_address)
+            );
         }
 
         Ok(())
     }
+
+    /// Register a pool with the shared pool state cache
+    async fn register_with_cache(&self, pool: &PoolInfo) {
+        if pool.reserves.len() == 2 {
+            self.pool_cache
+                .register_pool(pool, (pool.reserves[0], pool.reserves[1]))
+                .await;
+        }
+    }
+
+    /// Overlay the latest cached reserves onto a pool, aligned with its token order
+    async fn with_cached_reserves(&self, mut pool: PoolInfo) -> PoolInfo {
+        if let Some(state) = self.pool_cache.get(pool.address).await {
+            if let Some((reserve_a, reserve_b)) = pool
+                .tokens
+                .first()
+                .and_then(|&token| state.reserves_for(token))
+            {
+                pool.reserves = vec![reserve_a, reserve_b];
+            }
+        }
+        pool
+    }
 }
 
 #[async_trait]
@@ -221,23 +203,36 @@ impl DexInterface for SushiswapInterface {
     }
 
     async fn get_pools(&self) -> Result<Vec<PoolInfo>> {
-        if let Ok(pools) = self.pools.lock() {
-            Ok(pools.clone())
+        let pools = if let Ok(pools) = self.pools.lock() {
+            pools.clone()
         } else {
-            Err(anyhow::anyhow!("Failed to lock pools mutex"))
+            return Err(anyhow::anyhow!("Failed to lock pools mutex"));
+        };
+
+        let mut refreshed = Vec::with_capacity(pools.len());
+        for pool in pools {
+            refreshed.push(self.with_cached_reserves(pool).await);
         }
+
+        Ok(refreshed)
     }
 
     async fn get_pool(&self, token_a: Address, token_b: Address) -> Result<Option<PoolInfo>> {
         // Check if the pool is already in the list
-        if let Ok(pools) = self.pools.lock() {
-            for pool in &*pools {
-                if (pool.tokens[0] == token_a && pool.tokens[1] == token_b)
-                    || (pool.tokens[0] == token_b && pool.tokens[1] == token_a)
-                {
-                    return Ok(Some(pool.clone()));
-                }
-            }
+        let known_pool = if let Ok(pools) = self.pools.lock() {
+            pools
+                .iter()
+                .find(|pool| {
+                    (pool.tokens[0] == token_a && pool.tokens[1] == token_b)
+                        || (pool.tokens[0] == token_b && pool.tokens[1] == token_a)
+                })
+                .cloned()
+        } else {
+            None
+        };
+
+        if let Some(pool) = known_pool {
+            return Ok(Some(self.with_cached_reserves(pool).await));
         }
 
         // If not, query the factory
@@ -251,18 +246,23 @@ impl DexInterface for SushiswapInterface {
             return Ok(None);
         }
 
-        // Get the reserves
-        let reserves = self.get_reserves(pool_address).await?;
+        // Get the reserves (in on-chain order, so the tokens must match it)
+        let reserves = self.get_reserves(pool_address, BlockTag::Latest).await?;
+        let (token0, token1) = tokens::sort_tokens(token_a, token_b);
 
         // Create the pool info
         let pool_info = PoolInfo {
             address: pool_address,
             dex_type: DexType::Sushiswap,
-            tokens: vec![token_a, token_b],
+            tokens: vec![token0, token1],
             reserves,
             fee: 30, // 0.3%
         };
 
+        // Track the pool's reserves locally
+        self.register_with_cache(&pool_info).await;
+        tokens::global().register_pool(pool_info.address, &self.name, &pool_info.tokens);
+
         // Add the pool to the list
         if let Ok(mut pools) = self.pools.lock() {
             pools.push(pool_info.clone());
@@ -272,51 +272,13 @@ impl DexInterface for SushiswapInterface {
         Err(anyhow::anyhow!("Failed to lock pools mutex"))
     }
 
-    async fn get_reserves(&self, pool: Address) -> Result<Vec<U256>> {
-        // Create a minimal ABI for the pool contract
-        let pool_abi = r#"[
-            {
-                "constant": true,
-                "inputs": [],
-                "name": "getReserves",
-                "outputs": [
-                    {
-                        "internalType": "uint112",
-                        "name": "_reserve0",
-                        "type": "uint112"
-                    },
-                    {
-                        "internalType": "uint112",
-                        "name": "_reserve1",
-                        "type": "uint112"
-                    },
-                    {
-                        "internalType": "uint32",
-                        "name": "_blockTimestampLast",
-                        "type": "uint32"
-                    }
-                ],
-                "payable": false,
-                "stateMutability": "view",
-                "type": "function"
-            }
-        ]"#;
-
-        let pool_abi: ethers::abi::Abi = serde_json::from_str(pool_abi)
-            .map_err(|e| anyhow::anyhow!("Failed to parse pool ABI: {}", e))?;
-
-        // Create the pool contract
-        let pool_contract =
-            ethers::contract::Contract::new(pool, pool_abi, self.blockchain_client.clone());
-
-        // Call getReserves
-        let result: (U256, U256, u32) = pool_contract
-            .method::<_, (U256, U256, u32)>("getReserves", ())?
-            .call()
-            .await?;
+    async fn get_reserves(&self, pool: Address, block: BlockTag) -> Result<Vec<U256>> {
+        // Serve from the pool state cache when it holds the pool at the block
+        if let Some(state) = self.pool_cache.get_at(pool, block).await {
+            return Ok(vec![state.reserve0, state.reserve1]);
+        }
 
-        // Return the reserves
-        Ok(vec![result.0, result.1])
+        read_pair_reserves(&self.blockchain_client, pool, block).await
     }
 
     async fn get_quote(
@@ -324,28 +286,39 @@ impl DexInterface for SushiswapInterface {
         input_token: Address,
         output_token: Address,
         input_amount: U256,
+        block: BlockTag,
     ) -> Result<TradeQuote> {
         // Create the path
         let path = vec![input_token, output_token];
 
-        // Call the getAmountsOut function on the router
-        let amounts: Vec<U256> = self
-            .router_contract
-            .method::<_, Vec<U256>>("getAmountsOut", (input_amount, path.clone()))?
-            .call()
-            .await?;
-
-        // Get the output amount
-        let output_amount = amounts[1];
-
-        // Get the pool
-        let pool = self
+        // Get the pool, with its reserves (in on-chain order, like its tokens) at the block
+        let mut pool = self
             .get_pool(input_token, output_token)
             .await?
             .context("Pool not found")?;
+        pool.reserves = self.get_reserves(pool.address, block).await?;
+
+        // Compute the output locally from cached reserves, falling back to the router
+        let output_amount = match self
+            .pool_cache
+            .quote(pool.address, input_token, input_amount, block)
+            .await
+        {
+            Some(amount) => amount,
+            None => {
+                let amounts: Vec<U256> = self
+                    .router_contract
+                    .method::<_, Vec<U256>>("getAmountsOut", (input_amount, path.clone()))?
+                    .block(block)
+                    .call()
+                    .await?;
+                amounts[1]
+            }
+        };
 
-        // Calculate the price impact
-        let price_impact = 0; // Placeholder
+        // Measure the price impact against the pool's marginal price
+        let price_impact =
+            constant_product_price_impact(&pool, input_token, input_amount, output_amount);
 
         // Create the trade quote
         let quote = TradeQuote {
@@ -378,3 +351,374 @@ impl DexInterface for SushiswapInterface {
         Ok(vec![input_token, output_token])
     }
 }
+
+/// Kind of a Trident pool, by the deployer that created it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TridentPoolKind {
+    /// Constant-product pool over BentoBox shares
+    ConstantProduct,
+
+    /// Stableswap pool, priced over the underlying token amounts
+    Stable,
+}
+
+/// A Trident pool and its kind
+#[derive(Debug, Clone)]
+struct TridentPool {
+    info: PoolInfo,
+    kind: TridentPoolKind,
+}
+
+/// Sushi Trident interface
+///
+/// A pair can have several Trident pools of different kinds and fees; quotes go through the
+/// one returning the most.
+pub struct TridentInterface {
+    name: String,
+    chain: Chain,
+    bento_box: Address,
+    factories: Vec<(Address, TridentPoolKind)>,
+    blockchain_client: Arc<RpcClient>,
+    pools: Mutex<Vec<TridentPool>>,
+}
+
+/// Create a new Sushi Trident interface
+pub async fn create_trident_interface(
+    config: &Arc<Config>,
+    blockchain_client: Arc<RpcClient>,
+) -> Result<Arc<dyn DexInterface>> {
+    let trident = &config.dex.trident;
+    let bento_box = validate_and_parse_address(&trident.bento_box_address)
+        .context("Invalid Trident BentoBox address")?;
+
+    let mut factories = Vec::new();
+    for (factory, kind) in [
+        (
+            &trident.constant_product_factory,
+            TridentPoolKind::ConstantProduct,
+        ),
+        (&trident.stable_factory, TridentPoolKind::Stable),
+    ] {
+        if !factory.is_empty() {
+            factories.push((validate_and_parse_address(factory)?, kind));
+        }
+    }
+
+    let interface = Arc::new(TridentInterface {
+        name: "Trident".to_string(),
+        chain: Chain::from_id(config.ethereum.chain_id),
+        bento_box,
+        factories,
+        blockchain_client,
+        pools: Mutex::new(Vec::new()),
+    });
+
+    // Initialize pools
+    if let Err(e) = interface.initialize_pools().await {
+        warn!("Failed to initialize Trident pools: {}", e);
+    }
+
+    Ok(interface)
+}
+
+impl TridentInterface {
+    /// Find the Trident pools of the chain's WETH/USDC pair
+    async fn initialize_pools(&self) -> Result<()> {
+        let weth_address = validate_and_parse_address(self.chain.weth_address())?;
+        let usdc_address = validate_and_parse_address(self.chain.usdc_address())?;
+
+        for pool in self.find_pools(weth_address, usdc_address).await? {
+            info!(
+                "Initialized Trident {:?} pool: {}",
+                pool.kind,
+                tokens::pool_label(pool.info.address)
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Call a view function of a Trident pool, pool deployer or the BentoBox at a block
+    async fn call<D: Detokenize>(
+        &self,
+        to: Address,
+        name: &str,
+        args: impl Tokenize,
+        block: BlockTag,
+    ) -> Result<D> {
+        let trident = ContractAbi::SushiTrident.contract();
+        let call: TypedTransaction = TransactionRequest::new()
+            .to(to)
+            .data(trident.encode(name, args)?)
+            .into();
+
+        let output = self
+            .blockchain_client
+            .call(&call, Some(block.into()))
+            .await?;
+        Ok(trident.decode_output(name, output)?)
+    }
+
+    /// Get the pools of a pair, asking the deployers the first time the pair is seen
+    async fn find_pools(&self, token_a: Address, token_b: Address) -> Result<Vec<TridentPool>> {
+        let (token0, token1) = tokens::sort_tokens(token_a, token_b);
+        let known: Vec<TridentPool> = self
+            .pools
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|pool| pool.info.tokens == [token0, token1])
+            .cloned()
+            .collect();
+        if !known.is_empty() {
+            return Ok(known);
+        }
+
+        let mut found = Vec::new();
+        for &(factory, kind) in &self.factories {
+            let count: U256 = self
+                .call(factory, "poolsCount", (token0, token1), BlockTag::Latest)
+                .await?;
+            if count.is_zero() {
+                continue;
+            }
+
+            let addresses: Vec<Address> = self
+                .call(
+                    factory,
+                    "getPools",
+                    (token0, token1, U256::zero(), count),
+                    BlockTag::Latest,
+                )
+                .await?;
+            for address in addresses {
+                let fee: U256 = self.call(address, "swapFee", (), BlockTag::Latest).await?;
+                let tokens = vec![token0, token1];
+                let pool = TridentPool {
+                    info: PoolInfo {
+                        address,
+                        dex_type: DexType::Trident,
+                        reserves: self
+                            .share_balances(address, &tokens, BlockTag::Latest)
+                            .await?,
+                        tokens,
+                        fee: fee.low_u32(), // In basis points, like every other DEX here
+                    },
+                    kind,
+                };
+                tokens::global().register_pool(address, &self.name, &pool.info.tokens);
+                found.push(pool);
+            }
+        }
+
+        self.pools
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend(found.iter().cloned());
+        Ok(found)
+    }
+
+    /// Get the BentoBox shares a pool holds of each of its tokens at a block
+    async fn share_balances(
+        &self,
+        pool: Address,
+        tokens: &[Address],
+        block: BlockTag,
+    ) -> Result<Vec<U256>> {
+        let mut balances = Vec::with_capacity(tokens.len());
+        for &token in tokens {
+            balances.push(
+                self.call(self.bento_box, "balanceOf", (token, pool), block)
+                    .await?,
+            );
+        }
+        Ok(balances)
+    }
+
+    /// Get the output shares of swapping `share_in` shares of `token_in` through a pool
+    async fn shares_out(
+        &self,
+        pool: Address,
+        token_in: Address,
+        share_in: U256,
+        block: BlockTag,
+    ) -> Result<U256> {
+        let data = ethers::abi::encode(&[Token::Address(token_in), Token::Uint(share_in)]);
+        self.call(pool, "getAmountOut", Bytes::from(data), block)
+            .await
+    }
+
+    /// Measure a swap's price impact against the pool's marginal price, in shares
+    async fn pool_price_impact(
+        &self,
+        pool: &TridentPool,
+        token_in: Address,
+        share_in: U256,
+        share_out: U256,
+        block: BlockTag,
+    ) -> Result<u32> {
+        match pool.kind {
+            TridentPoolKind::ConstantProduct => Ok(constant_product_price_impact(
+                &pool.info, token_in, share_in, share_out,
+            )),
+            TridentPoolKind::Stable => {
+                // The stableswap curve has no closed-form marginal price; probe it instead
+                let probe_in = (share_in / 1_000).max(U256::one());
+                let probe_out = self
+                    .shares_out(pool.info.address, token_in, probe_in, block)
+                    .await?;
+                let spot_output = probe_out.saturating_mul(share_in) / probe_in;
+                Ok(price_impact_bps(spot_output, share_out))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl DexInterface for TridentInterface {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn dex_type(&self) -> DexType {
+        DexType::Trident
+    }
+
+    fn factory_address(&self) -> Address {
+        self.factories
+            .first()
+            .map(|&(factory, _)| factory)
+            .unwrap_or_default()
+    }
+
+    /// Trident has no router; swaps deposit into the BentoBox, which the contract approves
+    fn router_address(&self) -> Address {
+        self.bento_box
+    }
+
+    async fn get_pools(&self) -> Result<Vec<PoolInfo>> {
+        let pools = self.pools.lock().unwrap_or_else(|e| e.into_inner()).clone();
+
+        let mut refreshed = Vec::with_capacity(pools.len());
+        for mut pool in pools {
+            pool.info.reserves = self
+                .share_balances(pool.info.address, &pool.info.tokens, BlockTag::Latest)
+                .await?;
+            refreshed.push(pool.info);
+        }
+
+        Ok(refreshed)
+    }
+
+    async fn get_pool(&self, token_a: Address, token_b: Address) -> Result<Option<PoolInfo>> {
+        // The deepest pool of the pair, by its shares of the first token
+        let pools = self.find_pools(token_a, token_b).await?;
+        let mut deepest: Option<PoolInfo> = None;
+        for pool in pools {
+            let mut info = pool.info;
+            info.reserves = self
+                .share_balances(info.address, &info.tokens, BlockTag::Latest)
+                .await?;
+            if deepest
+                .as_ref()
+                .is_none_or(|deepest| info.reserves[0] > deepest.reserves[0])
+            {
+                deepest = Some(info);
+            }
+        }
+
+        Ok(deepest)
+    }
+
+    async fn get_reserves(&self, pool: Address, block: BlockTag) -> Result<Vec<U256>> {
+        let tokens = self
+            .pools
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|known| known.info.address == pool)
+            .map(|known| known.info.tokens.clone())
+            .context("Unknown Trident pool")?;
+
+        self.share_balances(pool, &tokens, block).await
+    }
+
+    async fn get_quote(
+        &self,
+        input_token: Address,
+        output_token: Address,
+        input_amount: U256,
+        block: BlockTag,
+    ) -> Result<TradeQuote> {
+        let pools = self.find_pools(input_token, output_token).await?;
+        if pools.is_empty() {
+            anyhow::bail!("Pool not found");
+        }
+
+        // Pools trade shares, so the input is converted as the BentoBox deposit would
+        let share_in: U256 = self
+            .call(
+                self.bento_box,
+                "toShare",
+                (input_token, input_amount, false),
+                block,
+            )
+            .await?;
+
+        let mut best: Option<(TridentPool, U256)> = None;
+        for mut pool in pools {
+            match self
+                .shares_out(pool.info.address, input_token, share_in, block)
+                .await
+            {
+                Ok(share_out) if best.as_ref().is_none_or(|(_, best)| share_out > *best) => {
+                    pool.info.reserves = self
+                        .share_balances(pool.info.address, &pool.info.tokens, block)
+                        .await?;
+                    best = Some((pool, share_out));
+                }
+                Ok(_) => {}
+                Err(e) => debug!(
+                    "Trident pool {} did not quote: {}",
+                    tokens::pool_label(pool.info.address),
+                    e
+                ),
+            }
+        }
+        let (pool, share_out) = best.context("No Trident pool quoted the pair")?;
+
+        // The output is withdrawn from the BentoBox as tokens
+        let output_amount: U256 = self
+            .call(
+                self.bento_box,
+                "toAmount",
+                (output_token, share_out, false),
+                block,
+            )
+            .await?;
+        let price_impact = self
+            .pool_price_impact(&pool, input_token, share_in, share_out, block)
+            .await?;
+
+        Ok(TradeQuote {
+            input_token,
+            output_token,
+            input_amount,
+            output_amount,
+            price_impact,
+            path: vec![input_token, output_token],
+            pools: vec![pool.info.address],
+            dex_type: DexType::Trident,
+        })
+    }
+
+    async fn find_best_path(
+        &self,
+        input_token: Address,
+        output_token: Address,
+        _input_amount: U256,
+    ) -> Result<Vec<Address>> {
+        // Trident swaps are routed one pool at a time
+        Ok(vec![input_token, output_token])
+    }
+}
@@ -4,34 +4,45 @@
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use ethers::abi::{Abi, Token};
 use ethers::contract::{Contract, ContractCall, ContractInstance};
-use ethers::providers::Provider;
 use ethers::types::{Address, Bytes, U256};
 use log::{debug, info, warn};
 use std::sync::{Arc, Mutex};
 
+use crate::assets::ContractAbi;
+use crate::blockchain::{Chain, RpcClient};
 use crate::config::Config;
-use crate::dex::{DexInterface, DexType, PoolInfo, TradeQuote};
-use crate::utils::validate_and_parse_address;
+use crate::dex::{
+    constant_product_price_impact, read_pair_reserves, read_pairs_reserves, BlockTag, DexInterface,
+    DexType, PairDiscovery, PoolInfo, PoolStateCache, TradeQuote,
+};
+use crate::screening::TokenScreener;
+use crate::tokens;
+use crate::utils::{validate_and_parse_address, Multicall};
 
 /// Uniswap V2 interface
 pub struct UniswapInterface {
     name: String,
+    chain: Chain,
     factory_address: Address,
     router_address: Address,
-    blockchain_client: Arc<Provider<ethers::providers::Http>>,
-    factory_contract:
-        ContractInstance<Arc<Provider<ethers::providers::Http>>, Provider<ethers::providers::Http>>,
-    router_contract:
-        ContractInstance<Arc<Provider<ethers::providers::Http>>, Provider<ethers::providers::Http>>,
+    blockchain_client: Arc<RpcClient>,
+    factory_contract: ContractInstance<Arc<RpcClient>, RpcClient>,
+    router_contract: ContractInstance<Arc<RpcClient>, RpcClient>,
     pools: Mutex<Vec<PoolInfo>>,
+    pool_cache: Arc<PoolStateCache>,
+    multicall: Arc<Multicall>,
+    pair_discovery: Option<PairDiscovery>,
+    screener: Option<Arc<TokenScreener>>,
 }
 
 /// Create a new Uniswap interface
 pub async fn create_interface(
     config: &Arc<Config>,
-    blockchain_client: Arc<Provider<ethers::providers::Http>>,
+    blockchain_client: Arc<RpcClient>,
+    pool_cache: Arc<PoolStateCache>,
+    multicall: Arc<Multicall>,
+    screener: Option<Arc<TokenScreener>>,
 ) -> Result<Arc<dyn DexInterface>> {
     // Parse addresses
     let factory_address = match validate_and_parse_address(&config.dex.uniswap.factory_address) {
@@ -53,71 +64,8 @@ pub async fn create_interface(
     };
 
     // Load ABIs
-    let factory_abi = include_str!("./abi/uniswap_v2_factory.json");
-    let factory_abi: Abi = serde_json::from_str(factory_abi).unwrap_or_else(|_| {
-        // If the ABI file is not available, use a minimal ABI
-        let json = r#"[
-            {
-                "constant": true,
-                "inputs": [
-                    {
-                        "internalType": "address",
-                        "name": "tokenA",
-                        "type": "address"
-                    },
-                    {
-                        "internalType": "address",
-                        "name": "tokenB",
-                        "type": "address"
-                    }
-                ],
-                "name": "getPair",
-                "outputs": [
-                    {
-                        "internalType": "address",
-                        "name": "pair",
-                        "type": "address"
-                    }
-                ],
-                "payable": false,
-                "stateMutability": "view",
-                "type": "function"
-            }
-        ]"#;
-        serde_json::from_str(json).expect("Failed to parse fallback ABI")
-    });
-
-    let router_abi = include_str!("./abi/uniswap_v2_router.json");
-    let router_abi: Abi = serde_json::from_str(router_abi).unwrap_or_else(|_| {
-        // If the ABI file is not available, use a minimal ABI
-        let json = r#"[
-            {
-                "inputs": [
-                    {
-                        "internalType": "uint256",
-                        "name": "amountIn",
-                        "type": "uint256"
-                    },
-                    {
-                        "internalType": "address[]",
-                        "name": "path",
-                        "type": "address[]"
-                    }
-                ],
-                "name": "getAmountsOut",
-                "outputs": [
-                    {
-                        "internalType": "uint256[]",
-                        "name": "amounts",
-                        "type": "uint256[]"
-                    }
-                ],
-                "stateMutability": "view",
-                "type": "function"
-            }
-        ]"#;
-        serde_json::from_str(json).expect("Failed to parse fallback ABI")
-    });
+    let factory_abi = ContractAbi::UniswapV2Factory.abi().clone();
+    let router_abi = ContractAbi::UniswapV2Router.abi().clone();
 
     // Create contracts
     let factory_contract = Contract::new(factory_address, factory_abi, blockchain_client.clone());
@@ -125,12 +73,21 @@ pub async fn create_interface(
 
     let interface = UniswapInterface {
         name: "Uniswap V2".to_string(),
+        chain: Chain::from_id(config.ethereum.chain_id),
         factory_address,
         router_address,
         blockchain_client: blockchain_client.clone(),
         factory_contract,
         router_contract,
         pools: Mutex::new(Vec::new()),
+        pool_cache,
+        multicall,
+        pair_discovery: config
+            .dex
+            .pair_discovery
+            .enabled
+            .then(|| PairDiscovery::new(config, factory_address, blockchain_client.clone())),
+        screener,
     };
 
     let interface = Arc::new(interface);
@@ -140,38 +97,37 @@ pub async fn create_interface(
         warn!("Failed to initialize Uniswap pools: {}", e);
     }
 
+    // Pick up pairs created from now on
+    interface.clone().spawn_pair_discovery();
+
     Ok(interface)
 }
 
 impl UniswapInterface {
     /// Initialize pools
     async fn initialize_pools(&self) -> Result<()> {
-        // This is a placeholder implementation
-        // In a real implementation, we would:
-        // 1. Query the factory for all pair creation events
-        // 2. Get the pool addresses
-        // 3. Get the token addresses and reserves for each pool
-
-        // For now, just create a dummy pool
-        let weth_address =
-            match validate_and_parse_address("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2") {
-                Ok(address) => address,
-                Err(e) => {
-                    log::warn!("Failed to parse WETH address: {}", e);
-                    // Provide a fallback address for testing
-                    Address::from_low_u64_be(6)
-                }
-            };
+        if self.pair_discovery.is_some() {
+            return self.discover_pools().await;
+        }
 
-        let usdc_address =
-            match validate_and_parse_address("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48") {
-                Ok(address) => address,
-                Err(e) => {
-                    log::warn!("Failed to parse USDC address: {}", e);
-                    // Provide a fallback address for testing
-                    Address::from_low_u64_be(7)
-                }
-            };
+        // Without discovery, just track the WETH/USDC pool
+        let weth_address = match validate_and_parse_address(self.chain.weth_address()) {
+            Ok(address) => address,
+            Err(e) => {
+                log::warn!("Failed to parse WETH address: {}", e);
+                // Provide a fallback address for testing
+                Address::from_low_u64_be(6)
+            }
+        };
+
+        let usdc_address = match validate_and_parse_address(self.chain.usdc_address()) {
+            Ok(address) => address,
+            Err(e) => {
+                log::warn!("Failed to parse USDC address: {}", e);
+                // Provide a fallback address for testing
+                Address::from_low_u64_be(7)
+            }
+        };
 
         let pool_address = self
             .factory_contract
@@ -180,26 +136,167 @@ impl UniswapInterface {
             .await?;
 
         if pool_address != Address::zero() {
-            let reserves = self.get_reserves(pool_address).await?;
+            let reserves = self.get_reserves(pool_address, BlockTag::Latest).await?;
+
+            // Reserves come back in on-chain order, so the tokens must match it
+            let (token0, token1) = tokens::sort_tokens(weth_address, usdc_address);
 
             let pool_info = PoolInfo {
                 address: pool_address,
                 dex_type: DexType::UniswapV2,
-                tokens: vec![weth_address, usdc_address],
+                tokens: vec![token0, token1],
                 reserves,
                 fee: 30, // 0.3%
             };
+            self.track_pool(pool_info).await;
+
+            info!(
+                "Initialized Uniswap V2 pool: {}",
+                tokens::pool_label(pool_address)
+            );
+        }
+
+        Ok(())
+    }
 
-            // Add the pool to the list
-            if let Ok(mut pools) = self.pools.lock() {
-                pools.push(pool_info);
+    /// Add the whitelisted pairs created since the last discovery scan to the pool list
+    ///
+    /// Pairs below the minimum reserves when they are first seen are not revisited, nor are
+    /// pairs whose unlisted token fails screening. The
+    /// reserves of the new pairs are read in batches, falling back to one read per pair.
+    async fn discover_pools(&self) -> Result<()> {
+        let Some(discovery) = &self.pair_discovery else {
+            return Ok(());
+        };
+
+        let mut pairs = discovery.scan().await?;
+        {
+            let pools = self.pools.lock().unwrap_or_else(|e| e.into_inner());
+            pairs.retain(|pair| !pools.iter().any(|pool| pool.address == pair.address));
+        }
+        let addresses: Vec<Address> = pairs.iter().map(|pair| pair.address).collect();
+        let batched = match read_pairs_reserves(&self.multicall, &addresses, BlockTag::Latest).await
+        {
+            Ok(batched) => batched,
+            Err(e) => {
+                debug!("Failed to batch reserves of new pairs: {}", e);
+                vec![None; pairs.len()]
             }
+        };
+
+        let mut added = 0;
+        for (pair, reserves) in pairs.into_iter().zip(batched) {
+            let reserves = match reserves {
+                Some(reserves) => reserves,
+                None => match self.get_reserves(pair.address, BlockTag::Latest).await {
+                    Ok(reserves) => reserves,
+                    Err(e) => {
+                        debug!("Failed to get reserves of pair {:?}: {}", pair.address, e);
+                        continue;
+                    }
+                },
+            };
+            if !discovery.meets_min_reserves(&pair, &reserves) {
+                debug!(
+                    "Skipping pair {:?} of {} and {}: below minimum reserves",
+                    pair.address,
+                    tokens::token_label(pair.token0),
+                    tokens::token_label(pair.token1)
+                );
+                continue;
+            }
+            if let Some((token, listed)) = discovery.unlisted_token(&pair) {
+                let passed = match &self.screener {
+                    Some(screener) => screener.screen(token, listed, self.router_address).await,
+                    None => true,
+                };
+                if !passed {
+                    debug!(
+                        "Skipping pair {:?}: {} failed screening",
+                        pair.address,
+                        tokens::token_label(token)
+                    );
+                    continue;
+                }
+            }
+
+            self.track_pool(PoolInfo {
+                address: pair.address,
+                dex_type: DexType::UniswapV2,
+                tokens: vec![pair.token0, pair.token1],
+                reserves,
+                fee: 30, // 0.3%
+            })
+            .await;
+            added += 1;
+
+            info!(
+                "Discovered Uniswap V2 pool {} (created in block {})",
+                tokens::pool_label(pair.address),
+                pair.block_number
+            );
+        }
 
-            info!("Initialized Uniswap V2 WETH-USDC pool: {:?}", pool_address);
+        if added > 0 {
+            info!("Discovered {} new Uniswap V2 pools", added);
         }
 
         Ok(())
     }
+
+    /// Keep discovering pools from new factory events in the background
+    fn spawn_pair_discovery(self: Arc<Self>) {
+        let Some(interval) = self
+            .pair_discovery
+            .as_ref()
+            .map(|discovery| discovery.refresh_interval())
+        else {
+            return;
+        };
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                if let Err(e) = self.discover_pools().await {
+                    warn!("Failed to refresh Uniswap V2 pools: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Track a pool's reserves locally, name it and add it to the pool list
+    async fn track_pool(&self, pool_info: PoolInfo) {
+        self.register_with_cache(&pool_info).await;
+        tokens::global().register_pool(pool_info.address, &self.name, &pool_info.tokens);
+
+        let mut pools = self.pools.lock().unwrap_or_else(|e| e.into_inner());
+        if !pools.iter().any(|pool| pool.address == pool_info.address) {
+            pools.push(pool_info);
+        }
+    }
+
+    /// Register a pool with the shared pool state cache
+    async fn register_with_cache(&self, pool: &PoolInfo) {
+        if pool.reserves.len() == 2 {
+            self.pool_cache
+                .register_pool(pool, (pool.reserves[0], pool.reserves[1]))
+                .await;
+        }
+    }
+
+    /// Overlay the latest cached reserves onto a pool, aligned with its token order
+    async fn with_cached_reserves(&self, mut pool: PoolInfo) -> PoolInfo {
+        if let Some(state) = self.pool_cache.get(pool.address).await {
+            if let Some((reserve_a, reserve_b)) = pool
+                .tokens
+                .first()
+                .and_then(|&token| state.reserves_for(token))
+            {
+                pool.reserves = vec![reserve_a, reserve_b];
+            }
+        }
+        pool
+    }
 }
 
 #[async_trait]
@@ -221,23 +318,36 @@ impl DexInterface for UniswapInterface {
     }
 
     async fn get_pools(&self) -> Result<Vec<PoolInfo>> {
-        if let Ok(pools) = self.pools.lock() {
-            Ok(pools.clone())
+        let pools = if let Ok(pools) = self.pools.lock() {
+            pools.clone()
         } else {
-            Err(anyhow::anyhow!("Failed to lock pools mutex"))
+            return Err(anyhow::anyhow!("Failed to lock pools mutex"));
+        };
+
+        let mut refreshed = Vec::with_capacity(pools.len());
+        for pool in pools {
+            refreshed.push(self.with_cached_reserves(pool).await);
         }
+
+        Ok(refreshed)
     }
 
     async fn get_pool(&self, token_a: Address, token_b: Address) -> Result<Option<PoolInfo>> {
         // Check if the pool is already in the list
-        if let Ok(pools) = self.pools.lock() {
-            for pool in &*pools {
-                if (pool.tokens[0] == token_a && pool.tokens[1] == token_b)
-                    || (pool.tokens[0] == token_b && pool.tokens[1] == token_a)
-                {
-                    return Ok(Some(pool.clone()));
-                }
-            }
+        let known_pool = if let Ok(pools) = self.pools.lock() {
+            pools
+                .iter()
+                .find(|pool| {
+                    (pool.tokens[0] == token_a && pool.tokens[1] == token_b)
+                        || (pool.tokens[0] == token_b && pool.tokens[1] == token_a)
+                })
+                .cloned()
+        } else {
+            None
+        };
+
+        if let Some(pool) = known_pool {
+            return Ok(Some(self.with_cached_reserves(pool).await));
         }
 
         // If not, query the factory
@@ -251,18 +361,23 @@ impl DexInterface for UniswapInterface {
             return Ok(None);
         }
 
-        // Get the reserves
-        let reserves = self.get_reserves(pool_address).await?;
+        // Get the reserves (in on-chain order, so the tokens must match it)
+        let reserves = self.get_reserves(pool_address, BlockTag::Latest).await?;
+        let (token0, token1) = tokens::sort_tokens(token_a, token_b);
 
         // Create the pool info
         let pool_info = PoolInfo {
             address: pool_address,
             dex_type: DexType::UniswapV2,
-            tokens: vec![token_a, token_b],
+            tokens: vec![token0, token1],
             reserves,
             fee: 30, // 0.3%
         };
 
+        // Track the pool's reserves locally
+        self.register_with_cache(&pool_info).await;
+        tokens::global().register_pool(pool_info.address, &self.name, &pool_info.tokens);
+
         // Add the pool to the list
         if let Ok(mut pools) = self.pools.lock() {
             pools.push(pool_info.clone());
@@ -272,51 +387,13 @@ impl DexInterface for UniswapInterface {
         Err(anyhow::anyhow!("Failed to lock pools mutex"))
     }
 
-    async fn get_reserves(&self, pool: Address) -> Result<Vec<U256>> {
-        // Create a minimal ABI for the pool contract
-        let pool_abi = r#"[
-            {
-                "constant": true,
-                "inputs": [],
-                "name": "getReserves",
-                "outputs": [
-                    {
-                        "internalType": "uint112",
-                        "name": "_reserve0",
-                        "type": "uint112"
-                    },
-                    {
-                        "internalType": "uint112",
-                        "name": "_reserve1",
-                        "type": "uint112"
-                    },
-                    {
-                        "internalType": "uint32",
-                        "name": "_blockTimestampLast",
-                        "type": "uint32"
-                    }
-                ],
-                "payable": false,
-                "stateMutability": "view",
-                "type": "function"
-            }
-        ]"#;
-
-        let pool_abi: ethers::abi::Abi = serde_json::from_str(pool_abi)
-            .map_err(|e| anyhow::anyhow!("Failed to parse pool ABI: {}", e))?;
-
-        // Create the pool contract
-        let pool_contract =
-            ethers::contract::Contract::new(pool, pool_abi, self.blockchain_client.clone());
-
-        // Call getReserves
-        let result: (U256, U256, u32) = pool_contract
-            .method::<_, (U256, U256, u32)>("getReserves", ())?
-            .call()
-            .await?;
+    async fn get_reserves(&self, pool: Address, block: BlockTag) -> Result<Vec<U256>> {
+        // Serve from the pool state cache when it holds the pool at the block
+        if let Some(state) = self.pool_cache.get_at(pool, block).await {
+            return Ok(vec![state.reserve0, state.reserve1]);
+        }
 
-        // Return the reserves
-        Ok(vec![result.0, result.1])
+        read_pair_reserves(&self.blockchain_client, pool, block).await
     }
 
     async fn get_quote(
@@ -324,28 +401,39 @@ impl DexInterface for UniswapInterface {
         input_token: Address,
         output_token: Address,
         input_amount: U256,
+        block: BlockTag,
     ) -> Result<TradeQuote> {
         // Create the path
         let path = vec![input_token, output_token];
 
-        // Call the getAmountsOut function on the router
-        let amounts: Vec<U256> = self
-            .router_contract
-            .method::<_, Vec<U256>>("getAmountsOut", (input_amount, path.clone()))?
-            .call()
-            .await?;
-
-        // Get the output amount
-        let output_amount = amounts[1];
-
-        // Get the pool
-        let pool = self
+        // Get the pool, with its reserves (in on-chain order, like its tokens) at the block
+        let mut pool = self
             .get_pool(input_token, output_token)
             .await?
             .context("Pool not found")?;
+        pool.reserves = self.get_reserves(pool.address, block).await?;
+
+        // Compute the output locally from cached reserves, falling back to the router
+        let output_amount = match self
+            .pool_cache
+            .quote(pool.address, input_token, input_amount, block)
+            .await
+        {
+            Some(amount) => amount,
+            None => {
+                let amounts: Vec<U256> = self
+                    .router_contract
+                    .method::<_, Vec<U256>>("getAmountsOut", (input_amount, path.clone()))?
+                    .block(block)
+                    .call()
+                    .await?;
+                amounts[1]
+            }
+        };
 
-        // Calculate the price impact
-        let price_impact = 0; // Placeholder
+        // Measure the price impact against the pool's marginal price
+        let price_impact =
+            constant_product_price_impact(&pool, input_token, input_amount, output_amount);
 
         // Create the trade quote
         let quote = TradeQuote {
@@ -0,0 +1,338 @@
+//! Feed Module
+//!
+//! This module is responsible for publishing the bot's market view to external consumers in
+//! real time: every opportunity the scanner identifies, and the outcome of every trade, as one
+//! JSON message each over a local WebSocket. Dashboards and external strategies connect to
+//! `ws://<listen_address>`, with `Authorization: Bearer <token>` when a token is configured, and
+//! receive every message published from then on, from every chain.
+//!
+//! Messages carry a `type` of `opportunity` or `execution`. A client reading slower than the
+//! bot publishes is sent a `lagged` message with the number of messages it missed, instead of
+//! holding the bot back. Nothing is serialized while no client is connected.
+
+use anyhow::{Context, Result};
+use ethers::types::{Address, H256, U256};
+use futures::{SinkExt, StreamExt};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, OnceLock};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_tungstenite::tungstenite::handshake::server::{
+    Callback, ErrorResponse, Request, Response,
+};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_util::sync::CancellationToken;
+
+use crate::blockchain::Chain;
+use crate::config::ConfigSection;
+use crate::dex::BlockTag;
+use crate::price::PinnedPrices;
+use crate::scanner::ArbitrageOpportunity;
+use crate::tokens;
+
+/// Default address the feed listens on
+pub const DEFAULT_LISTEN_ADDRESS: &str = "127.0.0.1:9472";
+
+/// Messages a client can fall behind by before it skips ahead
+const MAX_LAG: usize = 1024;
+
+/// Opportunity feed configuration (`[feed]`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeedConfig {
+    /// Whether opportunities and trade outcomes are published over a WebSocket
+    pub enabled: bool,
+
+    /// Address the feed listens on (defaults to 127.0.0.1:9472)
+    pub listen_address: Option<String>,
+
+    /// Bearer token clients must connect with, if any
+    pub token: Option<String>,
+}
+
+impl ConfigSection for FeedConfig {
+    const NAME: &'static str = "feed";
+
+    fn validate(&self) -> Result<()> {
+        if self
+            .token
+            .as_deref()
+            .is_some_and(|token| token.trim().is_empty())
+        {
+            anyhow::bail!("Feed token must not be empty");
+        }
+
+        Ok(())
+    }
+}
+
+/// A message of the feed
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum FeedMessage<'a> {
+    /// An opportunity the scanner identified
+    Opportunity {
+        chain_id: u64,
+        chain: String,
+        opportunity: Box<OpportunityView<'a>>,
+    },
+
+    /// The outcome of a submitted trade
+    Execution {
+        chain_id: u64,
+        chain: String,
+        execution: &'a ExecutionUpdate,
+    },
+
+    /// Messages a slow client missed
+    Lagged { skipped: u64 },
+}
+
+/// Opportunity as published
+#[derive(Debug, Serialize)]
+struct OpportunityView<'a> {
+    id: &'a str,
+    timestamp: u64,
+    source_dex: &'a str,
+    target_dex: &'a str,
+    token_path: &'a [Address],
+    token_symbols: String,
+    hops: Vec<HopView>,
+    estimated_profit: f64,
+    required_loan_amount: f64,
+    estimated_gas_cost: f64,
+    net_profit: f64,
+    confidence_score: u8,
+    variant: &'a str,
+    flash_loan_provider: &'static str,
+    flash_loan_fee: f64,
+    input_amount: Option<U256>,
+    quote_block: Option<u64>,
+    pinned_prices: &'a PinnedPrices,
+}
+
+/// One swap of a published opportunity
+#[derive(Debug, Serialize)]
+struct HopView {
+    dex: &'static str,
+    pool: Address,
+}
+
+impl<'a> From<&'a ArbitrageOpportunity> for OpportunityView<'a> {
+    fn from(opportunity: &'a ArbitrageOpportunity) -> Self {
+        Self {
+            id: &opportunity.id,
+            timestamp: opportunity.timestamp,
+            source_dex: &opportunity.source_dex,
+            target_dex: &opportunity.target_dex,
+            token_path: &opportunity.token_path,
+            token_symbols: tokens::path_label(&opportunity.token_path),
+            hops: opportunity
+                .hops
+                .iter()
+                .map(|&(dex_type, pool)| HopView {
+                    dex: dex_type.name(),
+                    pool,
+                })
+                .collect(),
+            estimated_profit: opportunity.estimated_profit,
+            required_loan_amount: opportunity.required_loan_amount,
+            estimated_gas_cost: opportunity.estimated_gas_cost,
+            net_profit: opportunity.net_profit,
+            confidence_score: opportunity.confidence_score,
+            variant: &opportunity.variant.name,
+            flash_loan_provider: opportunity.flash_loan_provider.as_str(),
+            flash_loan_fee: opportunity.flash_loan_fee,
+            input_amount: opportunity.input_amount,
+            quote_block: match opportunity.quote_block {
+                BlockTag::Number(block_number) => Some(block_number),
+                BlockTag::Latest | BlockTag::Pending => None,
+            },
+            pinned_prices: &opportunity.pinned_prices,
+        }
+    }
+}
+
+/// Outcome of a submitted trade
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecutionUpdate {
+    /// ID of the opportunity traded
+    pub opportunity_id: String,
+
+    /// Hash of the transaction that was included (a rebid's, if it replaced the original)
+    pub tx_hash: H256,
+
+    /// Whether the trade succeeded on chain
+    pub success: bool,
+
+    /// Block the trade was included in
+    pub block_number: Option<u64>,
+
+    /// Gas the trade used
+    pub gas_used: Option<U256>,
+
+    /// Profit estimated before submission (in USD)
+    pub estimated_profit: f64,
+
+    /// Realized PnL after gas and tips (in USD), if it could be priced
+    pub realized_pnl: Option<f64>,
+}
+
+/// Publisher of the feed's messages to every connected client
+pub struct OpportunityFeed {
+    sender: broadcast::Sender<Arc<str>>,
+}
+
+impl OpportunityFeed {
+    fn new() -> Self {
+        let (sender, _) = broadcast::channel(MAX_LAG);
+        Self { sender }
+    }
+
+    /// Publish an opportunity the scanner identified on a chain
+    pub fn publish_opportunity(&self, chain: Chain, opportunity: &ArbitrageOpportunity) {
+        self.publish(|| FeedMessage::Opportunity {
+            chain_id: chain.id(),
+            chain: chain.name(),
+            opportunity: Box::new(opportunity.into()),
+        });
+    }
+
+    /// Publish the outcome of a trade on a chain
+    pub fn publish_execution(&self, chain: Chain, execution: &ExecutionUpdate) {
+        self.publish(|| FeedMessage::Execution {
+            chain_id: chain.id(),
+            chain: chain.name(),
+            execution,
+        });
+    }
+
+    /// Receive every message published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<str>> {
+        self.sender.subscribe()
+    }
+
+    /// Serialize and send a message, if any client is connected
+    fn publish<'a>(&self, message: impl FnOnce() -> FeedMessage<'a>) {
+        if self.sender.receiver_count() == 0 {
+            return;
+        }
+
+        match serde_json::to_string(&message()) {
+            // Clients that disconnected in the meantime are not an error
+            Ok(json) => {
+                let _ = self.sender.send(json.into());
+            }
+            Err(e) => warn!("Failed to serialize feed message: {}", e),
+        }
+    }
+}
+
+/// Get the process-wide opportunity feed
+pub fn global() -> &'static OpportunityFeed {
+    static FEED: OnceLock<OpportunityFeed> = OnceLock::new();
+    FEED.get_or_init(OpportunityFeed::new)
+}
+
+/// The WebSocket server clients receive the feed from
+pub struct FeedServer {
+    listener: TcpListener,
+    token: Option<String>,
+}
+
+/// Create the feed's WebSocket server and start listening
+pub async fn create_server(feed_config: &FeedConfig) -> Result<Arc<FeedServer>> {
+    let listen_address = feed_config
+        .listen_address
+        .as_deref()
+        .unwrap_or(DEFAULT_LISTEN_ADDRESS);
+    let listener = TcpListener::bind(listen_address)
+        .await
+        .with_context(|| format!("Failed to listen on {}", listen_address))?;
+    info!("Opportunity feed listening on ws://{}", listen_address);
+
+    Ok(Arc::new(FeedServer {
+        listener,
+        token: feed_config.token.clone(),
+    }))
+}
+
+impl FeedServer {
+    /// Accept clients until shutdown, streaming the feed to each on its own task
+    pub async fn run(self: Arc<Self>, shutdown: CancellationToken) {
+        while let Some(accepted) = shutdown.run_until_cancelled(self.listener.accept()).await {
+            match accepted {
+                Ok((stream, peer)) => {
+                    debug!("Feed connection from {}", peer);
+                    let server = self.clone();
+                    let shutdown = shutdown.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = server.serve(stream, shutdown).await {
+                            debug!("Feed connection from {} failed: {}", peer, e);
+                        }
+                    });
+                }
+                Err(e) => warn!("Failed to accept feed connection: {}", e),
+            }
+        }
+    }
+
+    /// Complete a client's handshake, then forward the feed to it until it disconnects
+    async fn serve(&self, stream: TcpStream, shutdown: CancellationToken) -> Result<()> {
+        let authorization = Authorization(self.token.as_deref());
+        let mut socket = tokio_tungstenite::accept_hdr_async(stream, authorization).await?;
+        let mut messages = global().subscribe();
+
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => {
+                    socket.close(None).await?;
+                    return Ok(());
+                }
+                message = messages.recv() => {
+                    let text = match message {
+                        Ok(json) => json.to_string(),
+                        Err(RecvError::Lagged(skipped)) => {
+                            serde_json::to_string(&FeedMessage::Lagged { skipped })?
+                        }
+                        Err(RecvError::Closed) => return Ok(()),
+                    };
+                    socket.send(Message::Text(text)).await?;
+                }
+                received = socket.next() => match received {
+                    // Pings are answered by the socket itself; nothing else is expected
+                    Some(Ok(Message::Close(_))) | None => return Ok(()),
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return Err(e.into()),
+                },
+            }
+        }
+    }
+}
+
+/// Handshake check refusing clients without the configured bearer token
+struct Authorization<'a>(Option<&'a str>);
+
+impl Callback for Authorization<'_> {
+    fn on_request(self, request: &Request, response: Response) -> Result<Response, ErrorResponse> {
+        let Some(token) = self.0 else {
+            return Ok(response);
+        };
+
+        let authorized = request
+            .headers()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|presented| presented.trim() == token);
+        if authorized {
+            return Ok(response);
+        }
+
+        let mut refusal = ErrorResponse::new(Some("Unauthorized".to_string()));
+        *refusal.status_mut() = StatusCode::UNAUTHORIZED;
+        Err(refusal)
+    }
+}
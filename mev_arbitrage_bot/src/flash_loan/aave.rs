@@ -0,0 +1,233 @@
+//! Aave Flash Loan Module
+//!
+//! This module is responsible for building flash loans from the Aave V3 Pool, with the premium
+//! read from `FLASHLOAN_PREMIUM_TOTAL` and liquidity checked via `getReserveData`. The Spark
+//! (SparkLend) Pool is an Aave V3 fork with the same interface, and is served by the same
+//! manager. The executor contract borrows from the Aave Pool it was deployed with through
+//! `executeArbitrage`, and takes Spark loans itself from a pool its owner allowed with
+//! `setFlashLender`.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::abi::{Abi, Token};
+use ethers::contract::{Contract, ContractInstance};
+use ethers::types::{Address, U256};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::assets::ContractAbi;
+use crate::blockchain::RpcClient;
+use crate::config::Config;
+use crate::flash_loan::{percent_mul, FlashLoanManager};
+use crate::utils::validate_and_parse_address;
+
+/// Bit of the reserve configuration marking the reserve as active
+const RESERVE_ACTIVE_BIT: usize = 56;
+
+/// Bit of the reserve configuration marking the reserve as paused
+const RESERVE_PAUSED_BIT: usize = 60;
+
+/// Bit of the reserve configuration enabling flash loans
+const RESERVE_FLASHLOAN_ENABLED_BIT: usize = 63;
+
+/// Index of `aTokenAddress` in the `ReserveData` struct
+const RESERVE_DATA_ATOKEN_INDEX: usize = 8;
+
+/// Spark Pool address on Ethereum
+const DEFAULT_SPARK_POOL: &str = "0xC13e21B648A5Ee794902342038FF3aDAB66BE987";
+
+/// Flash loans from the Aave V3 Pool or one of its forks
+pub struct AaveFlashLoanManager {
+    /// Name of the lending protocol, for logs and errors
+    name: &'static str,
+
+    /// Whether the executor contract takes the pool's loans through `executeFlashLoan`, rather
+    /// than from the lending pool it was deployed with
+    contract_lender: bool,
+    blockchain_client: Arc<RpcClient>,
+    lending_pool_contract: ContractInstance<Arc<RpcClient>, RpcClient>,
+    erc20_abi: Abi,
+    premium_bps: RwLock<Option<U256>>,
+}
+
+/// Create a new Aave flash loan provider
+pub fn create_provider(
+    config: &Arc<Config>,
+    blockchain_client: Arc<RpcClient>,
+) -> Result<Arc<dyn FlashLoanManager>> {
+    let lending_pool_address =
+        match validate_and_parse_address(&config.flash_loan.aave_lending_pool) {
+            Ok(address) => address,
+            Err(e) => {
+                log::error!("Failed to parse aave_lending_pool address: {}", e);
+                // Provide a fallback address for testing
+                Address::from_low_u64_be(2)
+            }
+        };
+
+    Ok(create_pool_provider(
+        "Aave",
+        false,
+        lending_pool_address,
+        blockchain_client,
+    ))
+}
+
+/// Create a new Spark flash loan provider
+pub fn create_spark_provider(
+    config: &Arc<Config>,
+    blockchain_client: Arc<RpcClient>,
+) -> Result<Arc<dyn FlashLoanManager>> {
+    let pool_address = validate_and_parse_address(
+        config
+            .flash_loan
+            .spark_pool
+            .as_deref()
+            .unwrap_or(DEFAULT_SPARK_POOL),
+    )
+    .context("Invalid Spark Pool address")?;
+
+    Ok(create_pool_provider(
+        "Spark",
+        true,
+        pool_address,
+        blockchain_client,
+    ))
+}
+
+/// Create a flash loan provider over an Aave V3 compatible pool
+fn create_pool_provider(
+    name: &'static str,
+    contract_lender: bool,
+    lending_pool_address: Address,
+    blockchain_client: Arc<RpcClient>,
+) -> Arc<dyn FlashLoanManager> {
+    // Load the Aave V3 Pool ABI
+    let lending_pool_abi = ContractAbi::AaveV3Pool.abi().clone();
+    let erc20_abi = ContractAbi::Erc20.abi().clone();
+
+    // Create the lending pool contract
+    let lending_pool_contract = Contract::new(
+        lending_pool_address,
+        lending_pool_abi,
+        blockchain_client.clone(),
+    );
+
+    let manager = AaveFlashLoanManager {
+        name,
+        contract_lender,
+        blockchain_client,
+        lending_pool_contract,
+        erc20_abi,
+        premium_bps: RwLock::new(None),
+    };
+
+    Arc::new(manager)
+}
+
+impl AaveFlashLoanManager {
+    /// Get the total flash loan premium (in basis points), read once from the pool
+    async fn premium_bps(&self) -> Result<U256> {
+        if let Some(premium) = *self.premium_bps.read().await {
+            return Ok(premium);
+        }
+
+        let premium: u128 = self
+            .lending_pool_contract
+            .method::<_, u128>("FLASHLOAN_PREMIUM_TOTAL", ())?
+            .call()
+            .await
+            .context("Failed to read FLASHLOAN_PREMIUM_TOTAL")?;
+        let premium = U256::from(premium);
+
+        log::info!("{} flash loan premium: {} bps", self.name, premium);
+        *self.premium_bps.write().await = Some(premium);
+
+        Ok(premium)
+    }
+
+    /// Get the reserve configuration bitmap and aToken address of a reserve
+    async fn get_reserve_data(&self, token: Address) -> Result<(U256, Address)> {
+        let reserve_data: Token = self
+            .lending_pool_contract
+            .method::<_, Token>("getReserveData", token)?
+            .call()
+            .await
+            .context("Failed to call getReserveData")?;
+
+        let fields = match reserve_data {
+            Token::Tuple(fields) => fields,
+            _ => return Err(anyhow::anyhow!("Unexpected getReserveData result")),
+        };
+
+        let configuration = match fields.first() {
+            Some(Token::Tuple(configuration)) => match configuration.first() {
+                Some(Token::Uint(data)) => *data,
+                _ => return Err(anyhow::anyhow!("Unexpected reserve configuration")),
+            },
+            _ => return Err(anyhow::anyhow!("Unexpected reserve configuration")),
+        };
+
+        let a_token = match fields.get(RESERVE_DATA_ATOKEN_INDEX) {
+            Some(Token::Address(address)) => *address,
+            _ => return Err(anyhow::anyhow!("Unexpected reserve aToken address")),
+        };
+
+        Ok((configuration, a_token))
+    }
+}
+
+#[async_trait]
+impl FlashLoanManager for AaveFlashLoanManager {
+    async fn calculate_fee(&self, _token: Address, amount: U256) -> Result<U256> {
+        let premium = self.premium_bps().await?;
+        Ok(percent_mul(amount, premium))
+    }
+
+    async fn get_max_borrowable_amount(&self, token: Address) -> Result<U256> {
+        let (configuration, a_token) = self.get_reserve_data(token).await?;
+
+        // The pool reverts flash loans of inactive, paused or flash-loan-disabled reserves
+        if a_token.is_zero()
+            || !configuration.bit(RESERVE_ACTIVE_BIT)
+            || configuration.bit(RESERVE_PAUSED_BIT)
+            || !configuration.bit(RESERVE_FLASHLOAN_ENABLED_BIT)
+        {
+            return Ok(U256::zero());
+        }
+
+        // The available liquidity is the underlying balance held by the aToken
+        let token_contract = Contract::new(
+            token,
+            self.erc20_abi.clone(),
+            self.blockchain_client.clone(),
+        );
+        let liquidity: U256 = token_contract
+            .method::<_, U256>("balanceOf", a_token)?
+            .call()
+            .await
+            .context("Failed to get aToken liquidity")?;
+
+        Ok(liquidity)
+    }
+
+    fn repayment_spender(&self) -> Option<Address> {
+        Some(self.lending_pool_contract.address())
+    }
+
+    async fn flash_lender(&self, _token: Address, _amount: U256) -> Result<Address> {
+        if !self.contract_lender {
+            return Err(anyhow::anyhow!(
+                "The executor contract borrows from the {} Pool through executeArbitrage",
+                self.name
+            ));
+        }
+
+        Ok(self.lending_pool_contract.address())
+    }
+
+    fn allowed_lender(&self) -> Option<Address> {
+        self.contract_lender
+            .then(|| self.lending_pool_contract.address())
+    }
+}
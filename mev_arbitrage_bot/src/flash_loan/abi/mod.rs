@@ -1,6 +0,0 @@
-//! Flash Loan ABI Module
-//!
-//! This module contains the ABI definitions for flash loan contracts.
-
-// The ABI files are included directly in the code where needed.
-// This module is just a placeholder to make the directory structure valid.
\ No newline at end of file
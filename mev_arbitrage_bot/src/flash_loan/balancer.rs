@@ -0,0 +1,127 @@
+//! Balancer Flash Loan Module
+//!
+//! This module is responsible for building flash loans from the Balancer Vault, whose fee is
+//! read from the protocol fees collector (currently zero). The executor contract takes the
+//! loans itself, from a Vault its owner allowed with `setFlashLender`.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::abi::Abi;
+use ethers::contract::{Contract, ContractInstance};
+use ethers::types::{Address, U256};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::assets::ContractAbi;
+use crate::blockchain::RpcClient;
+use crate::config::Config;
+use crate::flash_loan::{mul_div_up, FlashLoanManager};
+use crate::utils::validate_and_parse_address;
+
+/// Canonical Balancer V2 Vault address (same on every chain)
+const DEFAULT_BALANCER_VAULT: &str = "0xBA12222222228d8Ba445958a75a0704d566BF2C8";
+
+/// Scale of Balancer fee percentages (1e18 = 100%)
+const FEE_PERCENTAGE_SCALE: u128 = 1_000_000_000_000_000_000;
+
+/// Flash loans from the Balancer Vault
+pub struct BalancerFlashLoanManager {
+    blockchain_client: Arc<RpcClient>,
+    vault_contract: ContractInstance<Arc<RpcClient>, RpcClient>,
+    abi: Abi,
+    erc20_abi: Abi,
+    fee_percentage: RwLock<Option<U256>>,
+}
+
+/// Create a new Balancer flash loan provider
+pub fn create_provider(
+    config: &Arc<Config>,
+    blockchain_client: Arc<RpcClient>,
+) -> Result<Arc<dyn FlashLoanManager>> {
+    let abi = ContractAbi::BalancerVault.abi().clone();
+    let erc20_abi = ContractAbi::Erc20.abi().clone();
+
+    let vault_address = validate_and_parse_address(
+        config
+            .flash_loan
+            .balancer_vault
+            .as_deref()
+            .unwrap_or(DEFAULT_BALANCER_VAULT),
+    )
+    .context("Invalid Balancer Vault address")?;
+    let vault_contract = Contract::new(vault_address, abi.clone(), blockchain_client.clone());
+
+    let manager = BalancerFlashLoanManager {
+        blockchain_client,
+        vault_contract,
+        abi,
+        erc20_abi,
+        fee_percentage: RwLock::new(None),
+    };
+
+    Ok(Arc::new(manager))
+}
+
+impl BalancerFlashLoanManager {
+    /// Get the flash loan fee percentage (1e18 scale), read once from the fees collector
+    async fn fee_percentage(&self) -> Result<U256> {
+        if let Some(fee_percentage) = *self.fee_percentage.read().await {
+            return Ok(fee_percentage);
+        }
+
+        let collector: Address = self
+            .vault_contract
+            .method::<_, Address>("getProtocolFeesCollector", ())?
+            .call()
+            .await
+            .context("Failed to get Balancer protocol fees collector")?;
+        let collector_contract =
+            Contract::new(collector, self.abi.clone(), self.blockchain_client.clone());
+        let fee_percentage: U256 = collector_contract
+            .method::<_, U256>("getFlashLoanFeePercentage", ())?
+            .call()
+            .await
+            .context("Failed to get Balancer flash loan fee")?;
+
+        log::info!("Balancer flash loan fee percentage: {}", fee_percentage);
+        *self.fee_percentage.write().await = Some(fee_percentage);
+
+        Ok(fee_percentage)
+    }
+}
+
+#[async_trait]
+impl FlashLoanManager for BalancerFlashLoanManager {
+    async fn calculate_fee(&self, _token: Address, amount: U256) -> Result<U256> {
+        let fee_percentage = self.fee_percentage().await?;
+        Ok(mul_div_up(
+            amount,
+            fee_percentage,
+            U256::from(FEE_PERCENTAGE_SCALE),
+        ))
+    }
+
+    async fn get_max_borrowable_amount(&self, token: Address) -> Result<U256> {
+        // The Vault lends from its own balance of the token
+        let token_contract = Contract::new(
+            token,
+            self.erc20_abi.clone(),
+            self.blockchain_client.clone(),
+        );
+        let liquidity: U256 = token_contract
+            .method::<_, U256>("balanceOf", self.vault_contract.address())?
+            .call()
+            .await
+            .context("Failed to get Balancer Vault liquidity")?;
+
+        Ok(liquidity)
+    }
+
+    async fn flash_lender(&self, _token: Address, _amount: U256) -> Result<Address> {
+        Ok(self.vault_contract.address())
+    }
+
+    fn allowed_lender(&self) -> Option<Address> {
+        Some(self.vault_contract.address())
+    }
+}
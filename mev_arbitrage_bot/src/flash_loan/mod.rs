@@ -1,186 +1,264 @@
 //! Flash Loan Manager Module
 //!
-//! This module is responsible for interfacing with Aave flash loan contracts.
+//! This module is responsible for interfacing with flash loan providers (Aave, Spark, Balancer,
+//! Morpho Blue and Uniswap V3) and for picking the cheapest one able to lend a given token and
+//! amount. Two-leg routes may instead be funded by a Uniswap V2 flash swap from their first
+//! pair.
+
+mod aave;
+mod balancer;
+mod morpho;
+mod uniswap_v2;
+mod uniswap_v3;
+
+pub use uniswap_v2::FlashSwap;
 
 use anyhow::Result;
 use async_trait::async_trait;
-use ethers::abi::{Abi, Token};
-use ethers::contract::{Contract, ContractInstance};
-use ethers::providers::Provider;
-use ethers::types::{Address, Bytes, TransactionRequest, U256};
+use ethers::types::{Address, U256};
+use log::{debug, info};
 use std::sync::Arc;
 
-use crate::config::Config;
-use crate::utils::validate_and_parse_address;
-
-/// Flash loan parameters
-#[derive(Debug, Clone)]
-pub struct FlashLoanParams {
-    /// Tokens to borrow
-    pub tokens: Vec<Address>,
-
-    /// Amounts to borrow for each token
-    pub amounts: Vec<U256>,
-
-    /// Whether to use the borrowed amount as collateral
-    pub modes: Vec<u8>,
-
-    /// Address that will receive the funds
-    pub receiver_address: Address,
-
-    /// Arbitrary data to pass to the receiver
-    pub params: Bytes,
-}
+use crate::blockchain::RpcClient;
+use crate::config::{Config, FlashLoanProvider};
+use crate::dex::DexType;
+use crate::tokens;
 
 /// Interface for flash loan managers
 #[async_trait]
 pub trait FlashLoanManager: Send + Sync {
-    /// Create a flash loan transaction
-    async fn create_flash_loan_transaction(
-        &self,
-        params: FlashLoanParams,
-    ) -> Result<TransactionRequest>;
-
     /// Calculate the flash loan fee
     async fn calculate_fee(&self, token: Address, amount: U256) -> Result<U256>;
 
     /// Get the maximum borrowable amount for a token
     async fn get_max_borrowable_amount(&self, token: Address) -> Result<U256>;
+
+    /// Get the address the receiver approves to pull the repayment, if the provider pulls it
+    /// rather than being paid back by transfer
+    fn repayment_spender(&self) -> Option<Address> {
+        None
+    }
+
+    /// Get the address the executor contract borrows `amount` of `token` from, if it takes the
+    /// provider's loans itself
+    async fn flash_lender(&self, _token: Address, _amount: U256) -> Result<Address> {
+        Err(anyhow::anyhow!(
+            "The executor contract does not take this provider's flash loans itself"
+        ))
+    }
+
+    /// Get the address the executor contract's `flashLenders` must allow for it to take the
+    /// provider's loans, if it takes them itself
+    fn allowed_lender(&self) -> Option<Address> {
+        None
+    }
 }
 
-/// Implementation of the flash loan manager
-pub struct FlashLoanManagerImpl {
-    config: Arc<Config>,
-    blockchain_client: Arc<Provider<ethers::providers::Http>>,
-    lending_pool_contract:
-        ContractInstance<Arc<Provider<ethers::providers::Http>>, Provider<ethers::providers::Http>>,
+/// The cost of borrowing a token from one provider
+#[derive(Debug, Clone, Copy)]
+pub struct FlashLoanQuote {
+    /// Provider able to lend the amount
+    pub provider: FlashLoanProvider,
+
+    /// Fee charged for the loan (in token units)
+    pub fee: U256,
+
+    /// Amount the provider could lend
+    pub available: U256,
 }
 
-/// Create a new flash loan manager
+/// Routes flash loans to the cheapest configured provider
+pub struct FlashLoanRouter {
+    providers: Vec<(FlashLoanProvider, Arc<dyn FlashLoanManager>)>,
+    flash_swaps: bool,
+}
+
+/// Create a flash loan router over the configured providers
 pub async fn create_manager(
     config: &Arc<Config>,
-    blockchain_client: Arc<Provider<ethers::providers::Http>>,
-) -> Result<Arc<dyn FlashLoanManager>> {
-    // This is a placeholder implementation
-    // In a real implementation, we would initialize the flash loan manager with the provided parameters
-
-    // Load the Aave lending pool ABI
-    let lending_pool_abi = include_str!("./abi/aave_lending_pool.json");
-    let lending_pool_abi: Abi = serde_json::from_str(lending_pool_abi).unwrap_or_else(|_| {
-        // If the ABI file is not available, use a minimal ABI with just the flashLoan function
-        let json = r#"[
-            {
-                "inputs": [
-                    {
-                        "internalType": "address",
-                        "name": "receiverAddress",
-                        "type": "address"
-                    },
-                    {
-                        "internalType": "address[]",
-                        "name": "assets",
-                        "type": "address[]"
-                    },
-                    {
-                        "internalType": "uint256[]",
-                        "name": "amounts",
-                        "type": "uint256[]"
-                    },
-                    {
-                        "internalType": "uint256[]",
-                        "name": "modes",
-                        "type": "uint256[]"
-                    },
-                    {
-                        "internalType": "address",
-                        "name": "onBehalfOf",
-                        "type": "address"
-                    },
-                    {
-                        "internalType": "bytes",
-                        "name": "params",
-                        "type": "bytes"
-                    },
-                    {
-                        "internalType": "uint16",
-                        "name": "referralCode",
-                        "type": "uint16"
-                    }
-                ],
-                "name": "flashLoan",
-                "outputs": [],
-                "stateMutability": "nonpayable",
-                "type": "function"
+    blockchain_client: Arc<RpcClient>,
+) -> Result<Arc<FlashLoanRouter>> {
+    let mut providers = Vec::new();
+    let mut flash_swaps = false;
+    for &provider in &config.flash_loan.providers {
+        if providers.iter().any(|(existing, _)| *existing == provider) {
+            continue;
+        }
+
+        let manager = match provider {
+            FlashLoanProvider::Aave => aave::create_provider(config, blockchain_client.clone())?,
+            FlashLoanProvider::Balancer => {
+                balancer::create_provider(config, blockchain_client.clone())?
             }
-        ]"#;
-        serde_json::from_str(json).expect("Failed to parse fallback ABI")
-    });
-
-    // Create the lending pool contract
-    let lending_pool_address =
-        match validate_and_parse_address(&config.flash_loan.aave_lending_pool) {
-            Ok(address) => address,
-            Err(e) => {
-                log::error!("Failed to parse aave_lending_pool address: {}", e);
-                // Provide a fallback address for testing
-                Address::from_low_u64_be(2)
+            FlashLoanProvider::UniswapV3 => {
+                uniswap_v3::create_provider(config, blockchain_client.clone())?
+            }
+            FlashLoanProvider::Morpho => {
+                morpho::create_provider(config, blockchain_client.clone())?
+            }
+            FlashLoanProvider::Spark => {
+                aave::create_spark_provider(config, blockchain_client.clone())?
+            }
+            // Flash swaps depend on the route rather than the token, so they are not quoted
+            FlashLoanProvider::UniswapV2 => {
+                flash_swaps = true;
+                continue;
             }
         };
-    let lending_pool_contract = Contract::new(
-        lending_pool_address,
-        lending_pool_abi,
-        blockchain_client.clone(),
-    );
+        providers.push((provider, manager));
+    }
 
-    let manager = FlashLoanManagerImpl {
-        config: config.clone(),
-        blockchain_client,
-        lending_pool_contract,
-    };
+    info!(
+        "Flash loan providers: {}",
+        providers
+            .iter()
+            .map(|(provider, _)| provider.as_str())
+            .chain(flash_swaps.then_some(FlashLoanProvider::UniswapV2.as_str()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
 
-    Ok(Arc::new(manager))
+    Ok(Arc::new(FlashLoanRouter {
+        providers,
+        flash_swaps,
+    }))
 }
 
-#[async_trait]
-impl FlashLoanManager for FlashLoanManagerImpl {
-    async fn create_flash_loan_transaction(
+impl FlashLoanRouter {
+    /// Get the manager of a provider, if it is configured
+    pub fn provider(&self, provider: FlashLoanProvider) -> Option<Arc<dyn FlashLoanManager>> {
+        self.providers
+            .iter()
+            .find(|(candidate, _)| *candidate == provider)
+            .map(|(_, manager)| manager.clone())
+    }
+
+    /// Find the cheapest provider able to lend `amount` of `token`
+    ///
+    /// Providers that fail to answer are skipped; ties go to the provider listed first.
+    pub async fn quote(&self, token: Address, amount: U256) -> Result<FlashLoanQuote> {
+        let mut best: Option<FlashLoanQuote> = None;
+        for (provider, manager) in &self.providers {
+            let available = match manager.get_max_borrowable_amount(token).await {
+                Ok(available) => available,
+                Err(e) => {
+                    debug!("{} cannot quote flash loan: {}", provider.as_str(), e);
+                    continue;
+                }
+            };
+            if available < amount {
+                continue;
+            }
+
+            let fee = match manager.calculate_fee(token, amount).await {
+                Ok(fee) => fee,
+                Err(e) => {
+                    debug!("{} cannot quote flash loan fee: {}", provider.as_str(), e);
+                    continue;
+                }
+            };
+
+            if best.is_none_or(|best| fee < best.fee) {
+                best = Some(FlashLoanQuote {
+                    provider: *provider,
+                    fee,
+                    available,
+                });
+            }
+        }
+
+        best.ok_or_else(|| {
+            anyhow::anyhow!(
+                "No flash loan provider can lend {} of {}",
+                amount,
+                tokens::token_label(token)
+            )
+        })
+    }
+
+    /// Get the flash swap funding a route, if flash swaps are enabled and the route is a
+    /// two-leg cycle starting on a Uniswap V2 style pair
+    pub fn flash_swap(
         &self,
-        params: FlashLoanParams,
-    ) -> Result<TransactionRequest> {
-        // This is a placeholder implementation
-        // In a real implementation, we would:
-        // 1. Validate the flash loan parameters
-        // 2. Create a transaction to call the flashLoan function on the Aave lending pool
-
-        // For now, just return a dummy transaction
-        let tx = TransactionRequest::new()
-            .to(self.lending_pool_contract.address())
-            .data(Bytes::from(vec![0u8]));
-
-        Ok(tx)
+        token_path: &[Address],
+        hops: &[(DexType, Address)],
+    ) -> Option<FlashSwap> {
+        if !self.flash_swaps {
+            return None;
+        }
+
+        FlashSwap::for_route(token_path, hops)
     }
 
-    async fn calculate_fee(&self, _token: Address, amount: U256) -> Result<U256> {
-        // Aave charges a 0.09% fee on flash loans
-        let fee_percentage = U256::from(9)
-            .saturating_mul(amount)
-            .checked_div(U256::from(10000))
-            .unwrap_or_default();
-        Ok(fee_percentage)
+    /// Get the address a provider pulls repayments from the receiver with, if it is configured
+    /// and pulls them
+    pub fn repayment_spender(&self, provider: FlashLoanProvider) -> Option<Address> {
+        self.provider(provider)
+            .and_then(|manager| manager.repayment_spender())
     }
 
-    async fn get_max_borrowable_amount(&self, _token: Address) -> Result<U256> {
-        // This is a placeholder implementation
-        // In a real implementation, we would query the Aave lending pool for the available liquidity
+    /// Get the address the executor contract borrows `amount` of `token` from with a provider
+    pub async fn flash_lender(
+        &self,
+        provider: FlashLoanProvider,
+        token: Address,
+        amount: U256,
+    ) -> Result<Address> {
+        let manager = self.provider(provider).ok_or_else(|| {
+            anyhow::anyhow!("Flash loan provider {} is not enabled", provider.as_str())
+        })?;
+
+        manager.flash_lender(token, amount).await
+    }
 
-        // For now, just return a dummy amount
-        Ok(U256::from(1000000000000000000u128)) // 1 ETH
+    /// Get the address the executor contract's `flashLenders` must allow, for each enabled
+    /// provider whose loans it takes itself
+    pub fn allowed_lenders(&self) -> Vec<(FlashLoanProvider, Address)> {
+        self.providers
+            .iter()
+            .filter_map(|(provider, manager)| Some((*provider, manager.allowed_lender()?)))
+            .collect()
     }
 }
 
-// Create a directory for ABI files
-#[cfg(not(test))]
-#[path = "abi/mod.rs"]
-pub mod abi {
-    // This module will contain the ABI files for the flash loan contracts
+#[async_trait]
+impl FlashLoanManager for FlashLoanRouter {
+    async fn calculate_fee(&self, token: Address, amount: U256) -> Result<U256> {
+        Ok(self.quote(token, amount).await?.fee)
+    }
+
+    async fn get_max_borrowable_amount(&self, token: Address) -> Result<U256> {
+        let mut max = U256::zero();
+        for (_, manager) in &self.providers {
+            if let Ok(available) = manager.get_max_borrowable_amount(token).await {
+                max = max.max(available);
+            }
+        }
+
+        Ok(max)
+    }
+}
+
+/// Multiply an amount by a percentage in basis points, rounding half up (Aave `percentMul`)
+fn percent_mul(amount: U256, bps: U256) -> U256 {
+    amount
+        .saturating_mul(bps)
+        .saturating_add(U256::from(5_000))
+        .checked_div(U256::from(10_000))
+        .unwrap_or_default()
+}
+
+/// Multiply an amount by a fraction with the given denominator, rounding up
+fn mul_div_up(amount: U256, numerator: U256, denominator: U256) -> U256 {
+    if denominator.is_zero() {
+        return U256::zero();
+    }
+
+    let product = amount.saturating_mul(numerator);
+    let quotient = product / denominator;
+    if (product % denominator).is_zero() {
+        quotient
+    } else {
+        quotient.saturating_add(U256::one())
+    }
 }
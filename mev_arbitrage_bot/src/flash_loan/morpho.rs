@@ -0,0 +1,92 @@
+//! Morpho Flash Loan Module
+//!
+//! This module is responsible for building flash loans from the Morpho Blue singleton, which
+//! lends any token it holds without a fee. Morpho calls `onMorphoFlashLoan` on the caller and
+//! pulls the repayment from it, so the executor contract takes the loan itself, from a Morpho
+//! its owner allowed with `setFlashLender`.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::abi::Abi;
+use ethers::contract::{Contract, ContractInstance};
+use ethers::types::{Address, U256};
+use std::sync::Arc;
+
+use crate::assets::ContractAbi;
+use crate::blockchain::RpcClient;
+use crate::config::Config;
+use crate::flash_loan::FlashLoanManager;
+use crate::utils::validate_and_parse_address;
+
+/// Canonical Morpho Blue address (Ethereum and Base)
+const DEFAULT_MORPHO_BLUE: &str = "0xBBBBBbbBBb9cC5e90e3b3Af64bdAF62C37EEFFCb";
+
+/// Flash loans from Morpho Blue
+pub struct MorphoFlashLoanManager {
+    blockchain_client: Arc<RpcClient>,
+    morpho_contract: ContractInstance<Arc<RpcClient>, RpcClient>,
+    erc20_abi: Abi,
+}
+
+/// Create a new Morpho Blue flash loan provider
+pub fn create_provider(
+    config: &Arc<Config>,
+    blockchain_client: Arc<RpcClient>,
+) -> Result<Arc<dyn FlashLoanManager>> {
+    let abi = ContractAbi::MorphoBlue.abi().clone();
+    let erc20_abi = ContractAbi::Erc20.abi().clone();
+
+    let morpho_address = validate_and_parse_address(
+        config
+            .flash_loan
+            .morpho_blue
+            .as_deref()
+            .unwrap_or(DEFAULT_MORPHO_BLUE),
+    )
+    .context("Invalid Morpho Blue address")?;
+    let morpho_contract = Contract::new(morpho_address, abi, blockchain_client.clone());
+
+    let manager = MorphoFlashLoanManager {
+        blockchain_client,
+        morpho_contract,
+        erc20_abi,
+    };
+
+    Ok(Arc::new(manager))
+}
+
+#[async_trait]
+impl FlashLoanManager for MorphoFlashLoanManager {
+    async fn calculate_fee(&self, _token: Address, _amount: U256) -> Result<U256> {
+        // Morpho Blue charges no flash loan fee
+        Ok(U256::zero())
+    }
+
+    async fn get_max_borrowable_amount(&self, token: Address) -> Result<U256> {
+        // Morpho lends from its own balance of the token, supplied and collateral alike
+        let token_contract = Contract::new(
+            token,
+            self.erc20_abi.clone(),
+            self.blockchain_client.clone(),
+        );
+        let liquidity: U256 = token_contract
+            .method::<_, U256>("balanceOf", self.morpho_contract.address())?
+            .call()
+            .await
+            .context("Failed to get Morpho Blue liquidity")?;
+
+        Ok(liquidity)
+    }
+
+    fn repayment_spender(&self) -> Option<Address> {
+        Some(self.morpho_contract.address())
+    }
+
+    async fn flash_lender(&self, _token: Address, _amount: U256) -> Result<Address> {
+        Ok(self.morpho_contract.address())
+    }
+
+    fn allowed_lender(&self) -> Option<Address> {
+        Some(self.morpho_contract.address())
+    }
+}
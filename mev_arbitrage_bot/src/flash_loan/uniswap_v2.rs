@@ -0,0 +1,44 @@
+//! Uniswap V2 Flash Swap Module
+//!
+//! This module is responsible for funding two-leg arbitrage with a flash swap from the pair of
+//! the route's first swap instead of a flash loan. The executor contract's `executeFlashSwap`
+//! has the pair send the first swap's output up front, and its `uniswapV2Call` sells it through
+//! the second swap and repays the pair in the route's first token. The pair's swap fee is the
+//! only cost, and it is already in the route's quote, so no premium is paid on top.
+
+use ethers::types::Address;
+
+use crate::dex::DexType;
+
+/// A flash swap lending a route's first swap output from its pair
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlashSwap {
+    /// Pair of the route's first swap
+    pub pair: Address,
+
+    /// Token the pair is repaid in (the route's first token)
+    pub token_in: Address,
+
+    /// Token the pair lends (the first swap's output)
+    pub token_out: Address,
+}
+
+impl FlashSwap {
+    /// Get the flash swap funding a route, if it is a two-leg cycle whose first swap names a
+    /// Uniswap V2 style pair
+    pub fn for_route(token_path: &[Address], hops: &[(DexType, Address)]) -> Option<Self> {
+        let [token_in, token_out, last] = *token_path else {
+            return None;
+        };
+        let [(dex_type, pair), _] = *hops else {
+            return None;
+        };
+
+        let is_pair = matches!(dex_type, DexType::UniswapV2 | DexType::Sushiswap);
+        (is_pair && !pair.is_zero() && last == token_in).then_some(Self {
+            pair,
+            token_in,
+            token_out,
+        })
+    }
+}
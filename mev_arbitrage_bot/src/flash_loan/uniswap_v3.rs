@@ -0,0 +1,177 @@
+//! Uniswap V3 Flash Loan Module
+//!
+//! This module is responsible for building flash loans from Uniswap V3 pools, borrowing from
+//! the lowest-fee pool that pairs the token with another configured token and holds enough of it.
+//! The executor contract takes the loans itself, from pools of a factory its owner allowed with
+//! `setFlashLender`.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::abi::Abi;
+use ethers::contract::{Contract, ContractInstance};
+use ethers::types::{Address, U256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::assets::ContractAbi;
+use crate::blockchain::{Chain, RpcClient};
+use crate::config::Config;
+use crate::flash_loan::{mul_div_up, FlashLoanManager};
+use crate::tokens;
+use crate::utils::validate_and_parse_address;
+
+/// Fee tiers searched for pools (in hundredths of a basis point)
+const FEE_TIERS: [u32; 4] = [100, 500, 3000, 10_000];
+
+/// Denominator of Uniswap V3 fees (1e6 = 100%)
+const FEE_DENOMINATOR: u64 = 1_000_000;
+
+/// A Uniswap V3 pool holding a token
+#[derive(Debug, Clone, Copy)]
+struct FlashPool {
+    address: Address,
+    fee: u32,
+}
+
+/// Flash loans from Uniswap V3 pools
+pub struct UniswapV3FlashLoanManager {
+    blockchain_client: Arc<RpcClient>,
+    factory_contract: ContractInstance<Arc<RpcClient>, RpcClient>,
+    erc20_abi: Abi,
+    counterpart_tokens: Vec<Address>,
+    pools: RwLock<HashMap<Address, Vec<FlashPool>>>,
+}
+
+/// Create a new Uniswap V3 flash loan provider
+pub fn create_provider(
+    config: &Arc<Config>,
+    blockchain_client: Arc<RpcClient>,
+) -> Result<Arc<dyn FlashLoanManager>> {
+    let abi = ContractAbi::UniswapV3.abi().clone();
+    let erc20_abi = ContractAbi::Erc20.abi().clone();
+
+    let factory_address = validate_and_parse_address(
+        config
+            .flash_loan
+            .uniswap_v3_factory
+            .as_deref()
+            .unwrap_or(Chain::from_id(config.ethereum.chain_id).uniswap_v3_factory()),
+    )
+    .context("Invalid Uniswap V3 factory address")?;
+    let factory_contract = Contract::new(factory_address, abi, blockchain_client.clone());
+
+    // Pools are looked up against the other configured tokens
+    let counterpart_tokens = config
+        .flash_loan
+        .tokens
+        .iter()
+        .filter_map(|token| validate_and_parse_address(&token.address).ok())
+        .collect();
+
+    let manager = UniswapV3FlashLoanManager {
+        blockchain_client,
+        factory_contract,
+        erc20_abi,
+        counterpart_tokens,
+        pools: RwLock::new(HashMap::new()),
+    };
+
+    Ok(Arc::new(manager))
+}
+
+impl UniswapV3FlashLoanManager {
+    /// Get the pools holding a token, discovered once via the factory
+    async fn pools_for(&self, token: Address) -> Result<Vec<FlashPool>> {
+        if let Some(pools) = self.pools.read().await.get(&token) {
+            return Ok(pools.clone());
+        }
+
+        let mut pools = Vec::new();
+        for &other in self
+            .counterpart_tokens
+            .iter()
+            .filter(|&&other| other != token)
+        {
+            for fee in FEE_TIERS {
+                let pool: Address = self
+                    .factory_contract
+                    .method::<_, Address>("getPool", (token, other, fee))?
+                    .call()
+                    .await
+                    .context("Failed to call Uniswap V3 getPool")?;
+
+                if !pool.is_zero() {
+                    pools.push(FlashPool { address: pool, fee });
+                }
+            }
+        }
+
+        // Cheapest pools first
+        pools.sort_by_key(|pool| pool.fee);
+        self.pools.write().await.insert(token, pools.clone());
+
+        Ok(pools)
+    }
+
+    /// Get a pool's balance of a token
+    async fn pool_balance(&self, token: Address, pool: Address) -> Result<U256> {
+        let token_contract = Contract::new(
+            token,
+            self.erc20_abi.clone(),
+            self.blockchain_client.clone(),
+        );
+        let balance: U256 = token_contract
+            .method::<_, U256>("balanceOf", pool)?
+            .call()
+            .await
+            .context("Failed to get Uniswap V3 pool liquidity")?;
+
+        Ok(balance)
+    }
+
+    /// Find the lowest-fee pool able to lend `amount` of `token`
+    async fn select_pool(&self, token: Address, amount: U256) -> Result<FlashPool> {
+        for pool in self.pools_for(token).await? {
+            if self.pool_balance(token, pool.address).await? >= amount {
+                return Ok(pool);
+            }
+        }
+
+        Err(anyhow::anyhow!(
+            "No Uniswap V3 pool can lend {} of {}",
+            amount,
+            tokens::token_label(token)
+        ))
+    }
+}
+
+#[async_trait]
+impl FlashLoanManager for UniswapV3FlashLoanManager {
+    async fn calculate_fee(&self, token: Address, amount: U256) -> Result<U256> {
+        let pool = self.select_pool(token, amount).await?;
+        Ok(mul_div_up(
+            amount,
+            U256::from(pool.fee),
+            U256::from(FEE_DENOMINATOR),
+        ))
+    }
+
+    async fn get_max_borrowable_amount(&self, token: Address) -> Result<U256> {
+        let mut max = U256::zero();
+        for pool in self.pools_for(token).await? {
+            max = max.max(self.pool_balance(token, pool.address).await?);
+        }
+
+        Ok(max)
+    }
+
+    async fn flash_lender(&self, token: Address, amount: U256) -> Result<Address> {
+        Ok(self.select_pool(token, amount).await?.address)
+    }
+
+    fn allowed_lender(&self) -> Option<Address> {
+        // The contract checks that the pool it borrows from was deployed by an allowed factory
+        Some(self.factory_contract.address())
+    }
+}
@@ -1,18 +1,67 @@
 //! Gas Price Optimizer Module
 //!
 //! This module is responsible for calculating optimal gas prices.
+//!
+//! Arbitrage transactions never carry blobs, so the EIP-4844 blob base fee does not enter
+//! their cost. It is still tracked and exported as a metric for operators running
+//! rollup-related strategies alongside the bot.
+//!
+//! On rollups a transaction also pays for posting its data to L1, which is quoted by the
+//! chain's fee oracle and added to the cost of every transaction the bot builds.
+//!
+//! Opportunities are costed before a transaction exists, from the gas their route is expected
+//! to use at the live gas price.
+//!
+//! Base and priority fees are estimated by the configured gas sources (the node's fee history,
+//! Alchemy and Blocknative) and aggregated by their median, with a priority fee for each
+//! inclusion urgency.
+//!
+//! The priority fees of competitors that took opportunities the bot detected are remembered per
+//! pool, and routes through those pools bid enough to outbid them.
 
 use anyhow::Result;
 use async_trait::async_trait;
 use ethers::middleware::Middleware;
-use ethers::providers::Provider;
-use ethers::types::{BlockNumber, U256};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Block, BlockNumber, TransactionRequest, H256, U256};
 use log::{debug, info, warn};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tokio::time::{Duration, Instant};
 
-use crate::config::{Config, GasStrategy};
+use crate::assets::ContractAbi;
+use crate::blockchain::{Chain, L1FeeModel, RpcClient};
+use crate::competition::CompetingTips;
+use crate::config::{Config, FlashLoanProvider, GasStrategy, InclusionUrgency};
+use crate::dex::DexType;
+use crate::metrics;
+use crate::utils::u256_to_decimal;
+
+mod predictor;
+mod source;
+
+pub use predictor::FeePredictor;
+pub use source::{
+    AlchemyGasSource, BlocknativeSource, FeeHistorySource, GasSource, GasSources, SourceFees,
+};
+
+/// Minimum blob base fee (in wei), per EIP-4844
+const MIN_BLOB_BASE_FEE: u64 = 1;
+
+/// Default blob base fee update fraction (Prague blob schedule)
+const DEFAULT_BLOB_BASE_FEE_UPDATE_FRACTION: u64 = 5_007_716;
+
+/// Base fee max change denominator, per EIP-1559
+const BASE_FEE_MAX_CHANGE_DENOMINATOR: u64 = 8;
+
+/// Gas limit elasticity multiplier, per EIP-1559
+const ELASTICITY_MULTIPLIER: u64 = 2;
+
+/// OP Stack `GasPriceOracle` predeploy address
+const OP_GAS_PRICE_ORACLE: &str = "0x420000000000000000000000000000000000000F";
+
+/// Arbitrum `NodeInterface` precompile address (only reachable through `eth_call`)
+const ARBITRUM_NODE_INTERFACE: &str = "0x00000000000000000000000000000000000000C8";
 
 /// Interface for gas price optimizers
 #[async_trait]
@@ -20,35 +69,95 @@ pub trait GasOptimizer: Send + Sync {
     /// Get the optimal gas price
     async fn get_optimal_gas_price(&self) -> Result<U256>;
 
-    /// Get the EIP-1559 fee data (base fee, priority fee)
-    async fn get_eip1559_fee_data(&self) -> Result<(U256, U256)>;
+    /// Get the EIP-1559 fee data (base fee, priority fee) for an inclusion urgency
+    async fn get_eip1559_fee_data(&self, urgency: InclusionUrgency) -> Result<(U256, U256)>;
+
+    /// Get the blob base fee of the latest block (None before Dencun or on chains without blobs)
+    async fn get_blob_base_fee(&self) -> Result<Option<U256>>;
+
+    /// Get the fee (in wei) a rollup charges for posting a transaction's data to L1, on top
+    /// of its execution gas (zero on chains without one)
+    async fn get_l1_data_fee(&self, tx: &TransactionRequest) -> Result<U256>;
 
     /// Update the gas price estimate
     async fn update_gas_price_estimate(&self) -> Result<()>;
+
+    /// Record the priority fee of a competitor that won a race through `pools`
+    fn record_winning_tip(&self, pools: &[Address], priority_fee: U256);
+
+    /// Get the priority fee that outbids recent race winners on a route's pools (None if no
+    /// race through them was lost)
+    fn competing_priority_fee(&self, pools: &[Address]) -> Option<U256>;
+}
+
+/// Estimate the gas a flash loan route uses, from its provider and the DEX of each swap
+pub fn route_gas(provider: FlashLoanProvider, dex_types: impl IntoIterator<Item = DexType>) -> u64 {
+    let flash_loan_gas: u64 = match provider {
+        FlashLoanProvider::Aave => 150_000,
+        FlashLoanProvider::Balancer => 90_000,
+        FlashLoanProvider::UniswapV3 => 110_000,
+        FlashLoanProvider::Morpho => 70_000,
+        FlashLoanProvider::Spark => 150_000,
+        // The pair lends through the route's first swap; only the callback comes on top
+        FlashLoanProvider::UniswapV2 => 30_000,
+    };
+    let swap_gas: u64 = dex_types
+        .into_iter()
+        .map(|dex_type| match dex_type {
+            DexType::UniswapV2 | DexType::Sushiswap => 110_000,
+            // The BentoBox deposit and withdrawal come on top of the swap
+            DexType::Trident => 160_000,
+            DexType::Curve => 200_000,
+        })
+        .sum();
+
+    flash_loan_gas + swap_gas
+}
+
+/// Price an amount of gas in USD at the gas price the next transaction would pay
+///
+/// Under the EIP-1559 strategy that is the live base fee times the base fee multiplier plus the
+/// priority fee, capped at the max gas price.
+pub async fn gas_cost_usd(
+    gas_optimizer: &dyn GasOptimizer,
+    gas: u64,
+    eth_price_usd: f64,
+) -> Result<f64> {
+    let gas_price = gas_optimizer.get_optimal_gas_price().await?;
+    let cost = U256::from(gas).saturating_mul(gas_price);
+
+    Ok(u256_to_decimal(cost, 18) * eth_price_usd)
 }
 
 /// Implementation of the gas price optimizer
 pub struct GasOptimizerImpl {
     config: Arc<Config>,
-    blockchain_client: Arc<Provider<ethers::providers::Http>>,
+    blockchain_client: Arc<RpcClient>,
     current_gas_price: RwLock<U256>,
     current_base_fee: RwLock<U256>,
-    current_priority_fee: RwLock<U256>,
+    gas_sources: GasSources,
+    current_priority_fees: RwLock<[Option<U256>; 3]>,
+    current_blob_base_fee: RwLock<Option<U256>>,
     last_update: RwLock<Instant>,
+    competing_tips: CompetingTips,
 }
 
 /// Create a new gas price optimizer
 pub async fn create_optimizer(
     config: &Arc<Config>,
-    blockchain_client: Arc<Provider<ethers::providers::Http>>,
+    blockchain_client: Arc<RpcClient>,
 ) -> Result<Arc<dyn GasOptimizer>> {
+    let gas_sources = GasSources::new(config, blockchain_client.clone())?;
     let optimizer = GasOptimizerImpl {
         config: config.clone(),
         blockchain_client,
         current_gas_price: RwLock::new(U256::from(config.gas.max_gas_price * 1_000_000_000)), // Convert gwei to wei
         current_base_fee: RwLock::new(U256::zero()),
-        current_priority_fee: RwLock::new(U256::from(config.gas.priority_fee * 1_000_000_000)), // Convert gwei to wei
+        gas_sources,
+        current_priority_fees: RwLock::new([None; 3]),
+        current_blob_base_fee: RwLock::new(None),
         last_update: RwLock::new(Instant::now() - Duration::from_secs(3600)), // Force an update on first call
+        competing_tips: CompetingTips::new(&config.section()?),
     };
 
     // Initialize gas price estimates
@@ -75,7 +184,9 @@ impl GasOptimizer for GasOptimizerImpl {
             }
             GasStrategy::Eip1559 => {
                 // Use EIP-1559 fee data
-                let (base_fee, priority_fee) = self.get_eip1559_fee_data().await?;
+                let (base_fee, priority_fee) = self
+                    .get_eip1559_fee_data(self.config.gas.fee_history.urgency)
+                    .await?;
 
                 // Calculate the max fee per gas
                 let max_fee_per_gas = base_fee
@@ -105,20 +216,45 @@ impl GasOptimizer for GasOptimizerImpl {
         }
     }
 
-    async fn get_eip1559_fee_data(&self) -> Result<(U256, U256)> {
+    async fn get_eip1559_fee_data(&self, urgency: InclusionUrgency) -> Result<(U256, U256)> {
         // Check if we need to update the gas price estimate
         let last_update = *self.last_update.read().await;
         if last_update.elapsed() > Duration::from_secs(15) {
             self.update_gas_price_estimate().await?;
         }
 
-        // Get the current base fee and priority fee
+        // Get the current base fee and the priority fee of the urgency, or the configured one
+        // until a gas source estimates it
         let base_fee = *self.current_base_fee.read().await;
-        let priority_fee = *self.current_priority_fee.read().await;
+        let priority_fee = self.current_priority_fees.read().await[urgency as usize]
+            .unwrap_or_else(|| U256::from(self.config.gas.priority_fee * 1_000_000_000)); // Convert gwei to wei
 
         Ok((base_fee, priority_fee))
     }
 
+    async fn get_blob_base_fee(&self) -> Result<Option<U256>> {
+        // Check if we need to update the gas price estimate
+        let last_update = *self.last_update.read().await;
+        if last_update.elapsed() > Duration::from_secs(15) {
+            self.update_gas_price_estimate().await?;
+        }
+
+        Ok(*self.current_blob_base_fee.read().await)
+    }
+
+    async fn get_l1_data_fee(&self, tx: &TransactionRequest) -> Result<U256> {
+        let l1_data_fee = match Chain::from_id(self.config.ethereum.chain_id).l1_fee_model() {
+            L1FeeModel::None => return Ok(U256::zero()),
+            L1FeeModel::OpStack => self.op_stack_l1_fee(tx).await?,
+            L1FeeModel::Arbitrum => self.arbitrum_l1_fee(tx).await?,
+        };
+
+        metrics::global().set_gauge("gas_l1_data_fee_wei", u256_to_decimal(l1_data_fee, 0));
+        debug!("Quoted L1 data fee: {} wei", l1_data_fee);
+
+        Ok(l1_data_fee)
+    }
+
     async fn update_gas_price_estimate(&self) -> Result<()> {
         // Get the latest block
         let latest_block = self
@@ -127,42 +263,57 @@ impl GasOptimizer for GasOptimizerImpl {
             .await?;
 
         if let Some(block) = latest_block {
-            // Update the base fee
-            if let Some(base_fee) = block.base_fee_per_gas {
-                let mut current_base_fee = self.current_base_fee.write().await;
-                *current_base_fee = base_fee;
-                debug!(
-                    "Updated base fee: {} gwei",
-                    base_fee.as_u64() / 1_000_000_000
-                );
-            }
+            // Get the gas price estimate
+            let gas_price = self.blockchain_client.get_gas_price().await?;
+
+            // Ask the gas sources for the fees of the next block
+            let block_number = block.number.map_or(0, |number| number.as_u64());
+            let estimate = self.gas_sources.estimate(block_number).await;
 
-            // Get the fee history to estimate the priority fee
-            let fee_history = self
-                .blockchain_client
-                .fee_history(10, BlockNumber::Latest, &[10.0, 50.0, 90.0])
-                .await?;
-
-            // In ethers 2.0, fee_history.reward is a Vec<Vec<U256>>
-            let rewards = &fee_history.reward;
-            if !rewards.is_empty() && !rewards[0].is_empty() && rewards[0].len() > 1 {
-                // Use the 50th percentile (median) priority fee
-                let priority_fee = rewards[0][1];
-                let mut current_priority_fee = self.current_priority_fee.write().await;
-                *current_priority_fee = priority_fee;
+            // Update the base fee, projected to the block our transaction will land in.
+            // Chains without EIP-1559 report no base fee, so the legacy gas price stands in.
+            let base_fee = match block.base_fee_per_gas {
+                Some(base_fee) => estimate
+                    .base_fee
+                    .unwrap_or_else(|| next_base_fee(base_fee, block.gas_used, block.gas_limit)),
+                None => {
+                    debug!("Latest block has no base fee, using the legacy gas price");
+                    gas_price
+                }
+            };
+            *self.current_base_fee.write().await = base_fee;
+            metrics::global().set_gauge("gas_base_fee_wei", u256_to_decimal(base_fee, 0));
+            debug!(
+                "Updated base fee: {} gwei",
+                base_fee.as_u128() / 1_000_000_000
+            );
+
+            // Update the priority fee of each urgency
+            for urgency in InclusionUrgency::ALL {
+                let Some(priority_fee) = estimate.priority_fee(urgency) else {
+                    continue;
+                };
+                metrics::global().set_gauge(
+                    &format!("gas_priority_fee_{}_wei", urgency.name()),
+                    u256_to_decimal(priority_fee, 0),
+                );
                 debug!(
-                    "Updated priority fee: {} gwei",
-                    priority_fee.as_u64() / 1_000_000_000
+                    "Updated {} urgency priority fee: {} gwei",
+                    urgency.name(),
+                    priority_fee.as_u128() / 1_000_000_000
                 );
             }
+            *self.current_priority_fees.write().await = estimate.priority_fees;
+
+            // Update the blob base fee (only present on post-Dencun blocks)
+            self.update_blob_base_fee(&block).await;
 
-            // Get the gas price estimate
-            let gas_price = self.blockchain_client.get_gas_price().await?;
             let mut current_gas_price = self.current_gas_price.write().await;
             *current_gas_price = gas_price;
+            metrics::global().set_gauge("gas_price_wei", u256_to_decimal(gas_price, 0));
             debug!(
                 "Updated gas price: {} gwei",
-                gas_price.as_u64() / 1_000_000_000
+                gas_price.as_u128() / 1_000_000_000
             );
 
             // Update the last update timestamp
@@ -174,4 +325,119 @@ impl GasOptimizer for GasOptimizerImpl {
 
         Ok(())
     }
+
+    fn record_winning_tip(&self, pools: &[Address], priority_fee: U256) {
+        self.competing_tips.record(pools, priority_fee);
+    }
+
+    fn competing_priority_fee(&self, pools: &[Address]) -> Option<U256> {
+        self.competing_tips.outbid(pools)
+    }
+}
+
+impl GasOptimizerImpl {
+    /// Quote the L1 fee of an OP Stack transaction from its unsigned RLP encoding
+    async fn op_stack_l1_fee(&self, tx: &TransactionRequest) -> Result<U256> {
+        let mut unsigned: TypedTransaction = tx.clone().into();
+        unsigned.set_chain_id(self.config.ethereum.chain_id);
+
+        let oracle = ContractAbi::OpGasPriceOracle.contract();
+        let call: TypedTransaction = TransactionRequest::new()
+            .to(OP_GAS_PRICE_ORACLE.parse::<Address>()?)
+            .data(oracle.encode("getL1Fee", unsigned.rlp())?)
+            .into();
+
+        let output = self.blockchain_client.call(&call, None).await?;
+        Ok(oracle.decode_output("getL1Fee", output)?)
+    }
+
+    /// Quote the L1 fee of an Arbitrum transaction: the L2 gas charged for its L1 data, priced
+    /// at the L2 base fee
+    async fn arbitrum_l1_fee(&self, tx: &TransactionRequest) -> Result<U256> {
+        let to = tx.to.as_ref().and_then(|to| to.as_address().copied());
+        let data = tx.data.clone().unwrap_or_default();
+
+        let node_interface = ContractAbi::ArbitrumNodeInterface.contract();
+        let call: TypedTransaction = TransactionRequest::new()
+            .to(ARBITRUM_NODE_INTERFACE.parse::<Address>()?)
+            .data(node_interface.encode(
+                "gasEstimateL1Component",
+                (to.unwrap_or_default(), to.is_none(), data),
+            )?)
+            .into();
+
+        let output = self.blockchain_client.call(&call, None).await?;
+        let (gas_for_l1, base_fee, _l1_base_fee): (u64, U256, U256) =
+            node_interface.decode_output("gasEstimateL1Component", output)?;
+
+        Ok(U256::from(gas_for_l1).saturating_mul(base_fee))
+    }
+
+    /// Update the blob base fee from a block's excess blob gas
+    async fn update_blob_base_fee(&self, block: &Block<H256>) {
+        let blob_base_fee = block.excess_blob_gas.map(|excess_blob_gas| {
+            let update_fraction = self
+                .config
+                .gas
+                .blob_base_fee_update_fraction
+                .unwrap_or(DEFAULT_BLOB_BASE_FEE_UPDATE_FRACTION);
+            calculate_blob_base_fee(excess_blob_gas, update_fraction)
+        });
+
+        match blob_base_fee {
+            Some(blob_base_fee) => {
+                metrics::global()
+                    .set_gauge("gas_blob_base_fee_wei", u256_to_decimal(blob_base_fee, 0));
+                debug!("Updated blob base fee: {} wei", blob_base_fee);
+            }
+            None => metrics::global().clear_gauge("gas_blob_base_fee_wei"),
+        }
+
+        *self.current_blob_base_fee.write().await = blob_base_fee;
+    }
+}
+
+/// Project the base fee of the next block from its parent, per EIP-1559
+pub fn next_base_fee(parent_base_fee: U256, parent_gas_used: U256, parent_gas_limit: U256) -> U256 {
+    let gas_target = parent_gas_limit / ELASTICITY_MULTIPLIER;
+    if gas_target.is_zero() || parent_gas_used == gas_target {
+        return parent_base_fee;
+    }
+
+    let denominator = gas_target.saturating_mul(U256::from(BASE_FEE_MAX_CHANGE_DENOMINATOR));
+    if parent_gas_used > gas_target {
+        let delta = parent_base_fee.saturating_mul(parent_gas_used - gas_target) / denominator;
+        parent_base_fee.saturating_add(std::cmp::max(delta, U256::one()))
+    } else {
+        let delta = parent_base_fee.saturating_mul(gas_target - parent_gas_used) / denominator;
+        parent_base_fee.saturating_sub(delta)
+    }
+}
+
+/// Calculate the blob base fee from the excess blob gas, per EIP-4844
+pub fn calculate_blob_base_fee(excess_blob_gas: U256, update_fraction: u64) -> U256 {
+    fake_exponential(
+        U256::from(MIN_BLOB_BASE_FEE),
+        excess_blob_gas,
+        U256::from(update_fraction),
+    )
+}
+
+/// Approximate `factor * e ** (numerator / denominator)` using a Taylor expansion (EIP-4844)
+fn fake_exponential(factor: U256, numerator: U256, denominator: U256) -> U256 {
+    if denominator.is_zero() {
+        return factor;
+    }
+
+    let mut i = U256::one();
+    let mut output = U256::zero();
+    let mut numerator_accum = factor.saturating_mul(denominator);
+
+    while !numerator_accum.is_zero() {
+        output = output.saturating_add(numerator_accum);
+        numerator_accum = numerator_accum.saturating_mul(numerator) / denominator.saturating_mul(i);
+        i += U256::one();
+    }
+
+    output / denominator
 }
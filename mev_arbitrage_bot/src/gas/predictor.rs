@@ -0,0 +1,131 @@
+//! Fee Predictor Module
+//!
+//! This module is responsible for predicting the fees of the next block from the recent fee
+//! history. A rolling window of blocks keeps each block's base fee, how full it was and the
+//! priority fees paid at the low, normal and high urgency percentiles. The next base fee follows
+//! from the newest block by the EIP-1559 formula; the priority fee of an urgency is the median of
+//! its percentile over the window, so a single block of unusual tips does not move the bid.
+//! Empty blocks paid no tips and are left out of the median.
+
+use ethers::types::{FeeHistory, U256};
+use std::collections::VecDeque;
+
+use super::next_base_fee;
+use crate::config::{FeeHistoryConfig, InclusionUrgency};
+
+/// Scale gas used ratios are turned into integer gas amounts at
+const GAS_USED_RATIO_SCALE: u64 = 1_000_000;
+
+/// Fees of one block of the window
+#[derive(Debug, Clone)]
+struct FeeSample {
+    block_number: u64,
+    base_fee: U256,
+
+    /// Gas used over the gas limit
+    gas_used_ratio: f64,
+
+    /// Priority fees at the low, normal and high urgency percentiles
+    rewards: [U256; 3],
+}
+
+/// Predicts next-block fees from a rolling window of fee history
+#[derive(Debug)]
+pub struct FeePredictor {
+    config: FeeHistoryConfig,
+    samples: VecDeque<FeeSample>,
+}
+
+impl FeePredictor {
+    /// Create an empty predictor
+    pub fn new(config: FeeHistoryConfig) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(config.window_blocks as usize),
+            config,
+        }
+    }
+
+    /// Percentiles to request the fee history at, from low to high urgency
+    pub fn percentiles(&self) -> [f64; 3] {
+        InclusionUrgency::ALL.map(|urgency| self.config.percentile(urgency))
+    }
+
+    /// Number of blocks to request to bring the window up to `latest_block`
+    pub fn blocks_missing(&self, latest_block: u64) -> u64 {
+        let missing = match self.samples.back() {
+            Some(newest) => latest_block.saturating_sub(newest.block_number),
+            None => self.config.window_blocks,
+        };
+
+        missing.min(self.config.window_blocks)
+    }
+
+    /// Add the blocks of a fee history requested at `percentiles()` that are newer than the
+    /// window, dropping the oldest beyond its size
+    pub fn record(&mut self, history: &FeeHistory) {
+        let oldest_block = history.oldest_block.as_u64();
+        for (index, &gas_used_ratio) in history.gas_used_ratio.iter().enumerate() {
+            let block_number = oldest_block + index as u64;
+            if self
+                .samples
+                .back()
+                .is_some_and(|newest| newest.block_number >= block_number)
+            {
+                continue;
+            }
+            let Some(&base_fee) = history.base_fee_per_gas.get(index) else {
+                break;
+            };
+
+            let mut rewards = [U256::zero(); 3];
+            if let Some(block_rewards) = history.reward.get(index) {
+                for (reward, &paid) in rewards.iter_mut().zip(block_rewards) {
+                    *reward = paid;
+                }
+            }
+
+            self.samples.push_back(FeeSample {
+                block_number,
+                base_fee,
+                gas_used_ratio,
+                rewards,
+            });
+        }
+
+        while self.samples.len() > self.config.window_blocks as usize {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Predict the base fee of the block after the newest one, per EIP-1559
+    pub fn predicted_base_fee(&self) -> Option<U256> {
+        let newest = self.samples.back()?;
+        let gas_used =
+            (newest.gas_used_ratio.clamp(0.0, 1.0) * GAS_USED_RATIO_SCALE as f64).round() as u64;
+
+        Some(next_base_fee(
+            newest.base_fee,
+            U256::from(gas_used),
+            U256::from(GAS_USED_RATIO_SCALE),
+        ))
+    }
+
+    /// Recommend the priority fee for an urgency: the median over the window's non-empty
+    /// blocks of the priority fee paid at its percentile
+    pub fn priority_fee(&self, urgency: InclusionUrgency) -> Option<U256> {
+        // Rewards are kept in the order of `InclusionUrgency::ALL`
+        let index = urgency as usize;
+        let mut rewards: Vec<U256> = self
+            .samples
+            .iter()
+            .filter(|sample| sample.gas_used_ratio > 0.0)
+            .map(|sample| sample.rewards[index])
+            .collect();
+        if rewards.is_empty() {
+            return None;
+        }
+
+        rewards.sort_unstable();
+        Some(rewards[rewards.len() / 2])
+    }
+}
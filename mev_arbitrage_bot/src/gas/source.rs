@@ -0,0 +1,409 @@
+//! Gas Source Module
+//!
+//! This module is responsible for the sources base and priority fees are estimated from: the
+//! node's fee history, Alchemy's priority fee suggestion and Blocknative's gas price API. Every
+//! configured source is asked at each gas price update and their estimates are aggregated by
+//! their median, so one source off the market cannot move the bid alone. A source failing
+//! `failure_threshold` times in a row is skipped for `recheck_interval_secs`, and an urgency no
+//! source estimates falls back to the configured priority fee.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::middleware::Middleware;
+use ethers::types::{BlockNumber, U256};
+use futures::future::join_all;
+use log::{debug, warn};
+use reqwest::Client;
+use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::time::Instant;
+
+use super::FeePredictor;
+use crate::blockchain::{self, Chain, RpcClient};
+use crate::config::{
+    Config, GasSourceKind, GasSourcesConfig, HttpEndpointConfig, InclusionUrgency,
+};
+use crate::metrics;
+use crate::ratelimit::{self, ApiEndpoint};
+use crate::utils::decimal_to_u256;
+
+/// Blocknative gas price API
+const BLOCKNATIVE_URL: &str = "https://api.blocknative.com/gasprices/blockprices";
+
+/// Inclusion confidence (percent) Blocknative estimates are read at, per urgency
+const BLOCKNATIVE_CONFIDENCES: [u32; 3] = [70, 90, 99];
+
+/// Request timeout of the gas price APIs
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Fees estimated by one source
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SourceFees {
+    /// Base fee of the next block, if the source estimates it
+    pub base_fee: Option<U256>,
+
+    /// Priority fee per urgency, in the order of `InclusionUrgency::ALL`, if the source
+    /// estimates it
+    pub priority_fees: [Option<U256>; 3],
+}
+
+impl SourceFees {
+    /// Get the priority fee of an urgency
+    pub fn priority_fee(&self, urgency: InclusionUrgency) -> Option<U256> {
+        self.priority_fees[urgency as usize]
+    }
+}
+
+/// Interface for sources of fee estimates
+#[async_trait]
+pub trait GasSource: Send + Sync {
+    /// Get the name of the source, used in logs and metric names
+    fn name(&self) -> &'static str;
+
+    /// Estimate the fees of the block after `latest_block`
+    async fn fees(&self, latest_block: u64) -> Result<SourceFees>;
+}
+
+/// Fees predicted from the node's fee history
+pub struct FeeHistorySource {
+    blockchain_client: Arc<RpcClient>,
+    predictor: RwLock<FeePredictor>,
+}
+
+impl FeeHistorySource {
+    /// Create a source keeping the configured window of fee history
+    pub fn new(config: &Config, blockchain_client: Arc<RpcClient>) -> Self {
+        Self {
+            blockchain_client,
+            predictor: RwLock::new(FeePredictor::new(config.gas.fee_history.clone())),
+        }
+    }
+}
+
+#[async_trait]
+impl GasSource for FeeHistorySource {
+    fn name(&self) -> &'static str {
+        "fee_history"
+    }
+
+    async fn fees(&self, latest_block: u64) -> Result<SourceFees> {
+        // Bring the fee history window up to the block
+        let mut predictor = self.predictor.write().await;
+        let missing = predictor.blocks_missing(latest_block);
+        if missing > 0 {
+            let fee_history = self
+                .blockchain_client
+                .fee_history(
+                    missing,
+                    BlockNumber::Number(latest_block.into()),
+                    &predictor.percentiles(),
+                )
+                .await?;
+            predictor.record(&fee_history);
+        }
+
+        Ok(SourceFees {
+            base_fee: predictor.predicted_base_fee(),
+            priority_fees: InclusionUrgency::ALL.map(|urgency| predictor.priority_fee(urgency)),
+        })
+    }
+}
+
+/// JSON-RPC response
+#[derive(Debug, Deserialize)]
+struct RpcResponse {
+    result: Option<U256>,
+    error: Option<RpcError>,
+}
+
+/// JSON-RPC error
+#[derive(Debug, Deserialize)]
+struct RpcError {
+    message: String,
+}
+
+/// Alchemy's priority fee suggestion, taken as the normal urgency priority fee
+pub struct AlchemyGasSource {
+    http_client: Client,
+    url: String,
+}
+
+impl AlchemyGasSource {
+    /// Create a source for the configured chain
+    pub fn new(config: &Config) -> Result<Self> {
+        let api_key = config
+            .ethereum
+            .alchemy_api_key
+            .as_ref()
+            .context("The Alchemy gas source needs ALCHEMY_API_KEY")?;
+        let network = Chain::from_id(config.ethereum.chain_id)
+            .alchemy_network()
+            .context("Alchemy does not serve the configured chain")?;
+
+        Ok(Self {
+            http_client: blockchain::http_client(
+                &HttpEndpointConfig::default(),
+                Some(REQUEST_TIMEOUT),
+            )?,
+            url: format!("https://{}.g.alchemy.com/v2/{}", network, api_key),
+        })
+    }
+}
+
+#[async_trait]
+impl GasSource for AlchemyGasSource {
+    fn name(&self) -> &'static str {
+        "alchemy"
+    }
+
+    async fn fees(&self, _latest_block: u64) -> Result<SourceFees> {
+        ratelimit::global().acquire(ApiEndpoint::Alchemy).await;
+        let response = self
+            .http_client
+            .post(&self.url)
+            .json(&serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "eth_maxPriorityFeePerGas",
+                "params": [],
+            }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<RpcResponse>()
+            .await?;
+
+        if let Some(error) = response.error {
+            anyhow::bail!(
+                "Alchemy rejected eth_maxPriorityFeePerGas: {}",
+                error.message
+            );
+        }
+        let priority_fee = response
+            .result
+            .context("Alchemy returned no priority fee")?;
+
+        let mut priority_fees = [None; 3];
+        priority_fees[InclusionUrgency::Normal as usize] = Some(priority_fee);
+        Ok(SourceFees {
+            base_fee: None,
+            priority_fees,
+        })
+    }
+}
+
+/// Blocknative block prices response
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlockPricesResponse {
+    block_prices: Vec<BlockPrices>,
+}
+
+/// Estimated prices of the next block (in gwei)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlockPrices {
+    base_fee_per_gas: f64,
+    estimated_prices: Vec<EstimatedPrice>,
+}
+
+/// Priority fee estimated to be included at a confidence (in gwei)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EstimatedPrice {
+    confidence: u32,
+    max_priority_fee_per_gas: f64,
+}
+
+/// Blocknative's estimates of the next block, read at 70%, 90% and 99% inclusion confidence
+/// for low, normal and high urgency
+pub struct BlocknativeSource {
+    http_client: Client,
+    api_key: String,
+    chain_id: u64,
+}
+
+impl BlocknativeSource {
+    /// Create a source for the configured chain
+    pub fn new(config: &Config) -> Result<Self> {
+        let api_key = config
+            .gas
+            .sources
+            .blocknative_api_key
+            .clone()
+            .context("The Blocknative gas source needs BLOCKNATIVE_API_KEY")?;
+
+        Ok(Self {
+            http_client: blockchain::http_client(
+                &HttpEndpointConfig::default(),
+                Some(REQUEST_TIMEOUT),
+            )?,
+            api_key,
+            chain_id: config.ethereum.chain_id,
+        })
+    }
+}
+
+#[async_trait]
+impl GasSource for BlocknativeSource {
+    fn name(&self) -> &'static str {
+        "blocknative"
+    }
+
+    async fn fees(&self, _latest_block: u64) -> Result<SourceFees> {
+        ratelimit::global().acquire(ApiEndpoint::Blocknative).await;
+        let response = self
+            .http_client
+            .get(BLOCKNATIVE_URL)
+            .query(&[("chainid", self.chain_id)])
+            .header(reqwest::header::AUTHORIZATION, &self.api_key)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<BlockPricesResponse>()
+            .await?;
+
+        let block = response
+            .block_prices
+            .first()
+            .context("Blocknative returned no block prices")?;
+
+        // The least confident estimate at or above each urgency's confidence
+        let priority_fees = BLOCKNATIVE_CONFIDENCES.map(|confidence| {
+            block
+                .estimated_prices
+                .iter()
+                .filter(|estimate| estimate.confidence >= confidence)
+                .min_by_key(|estimate| estimate.confidence)
+                .map(|estimate| decimal_to_u256(estimate.max_priority_fee_per_gas, 9))
+        });
+
+        Ok(SourceFees {
+            base_fee: Some(decimal_to_u256(block.base_fee_per_gas, 9)),
+            priority_fees,
+        })
+    }
+}
+
+/// Failures of a source and when it is asked again
+#[derive(Debug, Default)]
+struct SourceHealth {
+    consecutive_failures: u32,
+    down_until: Option<Instant>,
+}
+
+/// A source and its health
+struct TrackedSource {
+    source: Box<dyn GasSource>,
+    health: Mutex<SourceHealth>,
+}
+
+/// The configured sources, aggregated by their median
+pub struct GasSources {
+    sources: Vec<TrackedSource>,
+    failure_threshold: u32,
+    recheck_interval: Duration,
+}
+
+impl GasSources {
+    /// Create the configured sources
+    pub fn new(config: &Config, blockchain_client: Arc<RpcClient>) -> Result<Self> {
+        let settings: &GasSourcesConfig = &config.gas.sources;
+        let mut sources = Vec::with_capacity(settings.sources.len());
+        for &kind in &settings.sources {
+            let source: Box<dyn GasSource> = match kind {
+                GasSourceKind::FeeHistory => {
+                    Box::new(FeeHistorySource::new(config, blockchain_client.clone()))
+                }
+                GasSourceKind::Alchemy => Box::new(AlchemyGasSource::new(config)?),
+                GasSourceKind::Blocknative => Box::new(BlocknativeSource::new(config)?),
+            };
+            sources.push(TrackedSource {
+                source,
+                health: Mutex::new(SourceHealth::default()),
+            });
+        }
+
+        Ok(Self {
+            sources,
+            failure_threshold: settings.failure_threshold,
+            recheck_interval: Duration::from_secs(settings.recheck_interval_secs),
+        })
+    }
+
+    /// Ask every healthy source for the fees of the block after `latest_block`, and take the
+    /// median of each estimate over the sources that gave one
+    pub async fn estimate(&self, latest_block: u64) -> SourceFees {
+        let now = Instant::now();
+        let asked: Vec<&TrackedSource> = self
+            .sources
+            .iter()
+            .filter(|tracked| {
+                let health = tracked.health.lock().unwrap_or_else(|e| e.into_inner());
+                health.down_until.is_none_or(|down_until| now >= down_until)
+            })
+            .collect();
+        let results = join_all(
+            asked
+                .iter()
+                .map(|tracked| tracked.source.fees(latest_block)),
+        )
+        .await;
+
+        let mut estimates = Vec::with_capacity(results.len());
+        for (tracked, result) in asked.into_iter().zip(results) {
+            let name = tracked.source.name();
+            let mut health = tracked.health.lock().unwrap_or_else(|e| e.into_inner());
+            match result {
+                Ok(fees) => {
+                    health.consecutive_failures = 0;
+                    health.down_until = None;
+                    metrics::global().set_gauge(&format!("gas_source_{}_healthy", name), 1.0);
+                    debug!("Gas source {} estimated {:?}", name, fees);
+                    estimates.push(fees);
+                }
+                Err(e) => {
+                    health.consecutive_failures += 1;
+                    warn!("Gas source {} failed: {}", name, e);
+                    if health.consecutive_failures >= self.failure_threshold {
+                        health.down_until = Some(now + self.recheck_interval);
+                        metrics::global().set_gauge(&format!("gas_source_{}_healthy", name), 0.0);
+                        warn!(
+                            "Gas source {} failed {} times in a row, skipping it for {:?}",
+                            name, health.consecutive_failures, self.recheck_interval
+                        );
+                    }
+                }
+            }
+        }
+
+        SourceFees {
+            base_fee: median(estimates.iter().filter_map(|fees| fees.base_fee)),
+            priority_fees: InclusionUrgency::ALL.map(|urgency| {
+                median(
+                    estimates
+                        .iter()
+                        .filter_map(|fees| fees.priority_fee(urgency)),
+                )
+            }),
+        }
+    }
+}
+
+/// Get the median of some fees, averaging the middle two of an even count
+fn median(fees: impl Iterator<Item = U256>) -> Option<U256> {
+    let mut fees: Vec<U256> = fees.collect();
+    if fees.is_empty() {
+        return None;
+    }
+
+    fees.sort_unstable();
+    let middle = fees.len() / 2;
+    if fees.len().is_multiple_of(2) {
+        Some((fees[middle - 1] + fees[middle]) / 2)
+    } else {
+        Some(fees[middle])
+    }
+}
@@ -0,0 +1,114 @@
+//! Latency Module
+//!
+//! This module is responsible for timing opportunities through the pipeline. The clock of an
+//! opportunity starts with the scan that found it; how long its quotes, evaluation, building and
+//! submission took is recorded in the `opportunity_<stage>_latency_ms` histograms. An opportunity
+//! that has been in the pipeline longer than `arbitrage.latency_budget_ms` is aborted before its
+//! next stage, since the state it was quoted against has likely moved on.
+
+use anyhow::Result;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use crate::metrics;
+use crate::utils::{format_duration, measure_time_async};
+
+/// Stage of the pipeline an opportunity passes through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Fetching the quotes the opportunity was found in
+    Quote,
+
+    /// Sizing, funding and costing the opportunity
+    Evaluation,
+
+    /// Building its transaction
+    Building,
+
+    /// Submitting its transaction or bundle
+    Submission,
+}
+
+impl Stage {
+    /// Get the name of the stage, used in metric names
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::Quote => "quote",
+            Stage::Evaluation => "evaluation",
+            Stage::Building => "building",
+            Stage::Submission => "submission",
+        }
+    }
+}
+
+/// Time an opportunity has spent in the pipeline, against its latency budget
+#[derive(Debug, Clone, Copy)]
+pub struct Latency {
+    started: Instant,
+    budget: Duration,
+}
+
+impl Latency {
+    /// Start the clock with a budget
+    pub fn start(budget: Duration) -> Self {
+        Self {
+            started: Instant::now(),
+            budget,
+        }
+    }
+
+    /// Get the time since the clock started
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Fail if the budget has run out before `next` starts
+    pub fn check(&self, next: Stage) -> Result<()> {
+        let elapsed = self.elapsed();
+        if elapsed <= self.budget {
+            return Ok(());
+        }
+
+        let metrics = metrics::global();
+        metrics.increment_counter("latency_budget_exceeded", 1);
+        metrics.increment_counter(
+            &format!("latency_budget_exceeded_before_{}", next.name()),
+            1,
+        );
+        anyhow::bail!(
+            "{} in the pipeline exceeds the latency budget of {} before {}",
+            format_duration(elapsed),
+            format_duration(self.budget),
+            next.name()
+        )
+    }
+
+    /// Record the total latency of a submitted opportunity
+    pub fn finish(&self) {
+        metrics::global().observe("opportunity_total_latency_ms", millis(self.elapsed()));
+    }
+}
+
+/// Record how long a stage took for one opportunity
+pub fn record(stage: Stage, duration: Duration) {
+    metrics::global().observe(
+        &format!("opportunity_{}_latency_ms", stage.name()),
+        millis(duration),
+    );
+}
+
+/// Run a stage of one opportunity, recording how long it took
+pub async fn time_stage<F, Fut, T>(stage: Stage, f: F) -> T
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = T>,
+{
+    let (result, duration) = measure_time_async(f).await;
+    record(stage, duration);
+
+    result
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
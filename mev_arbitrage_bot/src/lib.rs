@@ -0,0 +1,48 @@
+//! MEV Arbitrage Bot
+//!
+//! This crate identifies and executes arbitrage opportunities on Ethereum using flash loans
+//! and MEV-Share for protection against front-running. The scanner, strategy and execution
+//! components are exposed so other tools can embed opportunity detection without running the
+//! whole bot; `bot::run` runs it.
+
+pub mod aggregator;
+pub mod alerts;
+pub mod allowances;
+pub mod analytics;
+pub mod api;
+pub mod assets;
+pub mod blockchain;
+pub mod bot;
+pub mod competition;
+pub mod config;
+pub mod contract;
+pub mod coordination;
+pub mod dex;
+pub mod feed;
+pub mod flash_loan;
+pub mod gas;
+pub mod latency;
+pub mod logging;
+pub mod mempool;
+pub mod metrics;
+pub mod mev_share;
+pub mod observer;
+pub mod pnl;
+pub mod price;
+pub mod ratelimit;
+pub mod risk;
+pub mod runtime;
+pub mod scanner;
+pub mod screening;
+pub mod simulation;
+pub mod storage;
+pub mod strategy;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod tokens;
+pub mod trace;
+pub mod transaction;
+pub mod treasury;
+pub mod utils;
@@ -0,0 +1,218 @@
+//! Logging Module
+//!
+//! This module is responsible for initializing the `tracing` subscriber (text or JSON lines),
+//! opening the spans that follow an opportunity across tasks, and redacting sensitive values
+//! from everything it writes: configured secrets (private keys and API keys) are masked wherever
+//! they appear, and long hex blobs such as calldata, signatures and signed transactions are cut
+//! down to their leading four bytes (the function selector, for calldata).
+
+use std::borrow::Cow;
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{OnceLock, RwLock};
+use tracing::Span;
+use tracing_subscriber::EnvFilter;
+
+use crate::config::Config;
+
+/// Placeholder for a masked secret
+const REDACTED: &str = "<redacted>";
+
+/// Longest hex run left intact: a 32-byte hash or key
+const MAX_PLAIN_HEX_DIGITS: usize = 64;
+
+/// Hex digits kept from a redacted blob: the 4-byte selector
+const KEPT_HEX_DIGITS: usize = 8;
+
+/// Secrets shorter than this are not masked, since they would match ordinary text
+const MIN_SECRET_LEN: usize = 8;
+
+/// Masks sensitive values in log output
+pub struct Redactor {
+    secrets: RwLock<Vec<String>>,
+    redact_secrets: AtomicBool,
+    redact_hex: AtomicBool,
+}
+
+impl Redactor {
+    /// Create a redactor that shortens hex blobs until it is configured
+    fn new() -> Self {
+        Self {
+            secrets: RwLock::new(Vec::new()),
+            redact_secrets: AtomicBool::new(true),
+            redact_hex: AtomicBool::new(true),
+        }
+    }
+
+    /// Apply the logging configuration and register the configured secrets
+    pub fn configure(&self, config: &Config) {
+        self.redact_secrets
+            .store(config.logging.redact_secrets, Ordering::Relaxed);
+        self.redact_hex
+            .store(config.logging.redact_calldata, Ordering::Relaxed);
+
+        let configured = [
+            config.ethereum.private_key.as_deref(),
+            config.ethereum.alchemy_api_key.as_deref(),
+            config.mev_share.api_key.as_deref(),
+            config.mev_share.signing_key.as_deref(),
+            config.price_apis.coingecko.api_key.as_deref(),
+            config.price_apis.coinmarketcap.api_key.as_deref(),
+            config.gas.sources.blocknative_api_key.as_deref(),
+        ];
+        let api_token = config
+            .section::<crate::api::ApiConfig>()
+            .ok()
+            .and_then(|api| api.token);
+        let endpoint_secrets = [
+            &config.ethereum.rpc_http,
+            &config.ethereum.peer_rpc_http,
+            &config.ethereum.fallback_rpc_http,
+            &config.mev_share.api_http,
+        ]
+        .into_iter()
+        .flat_map(|endpoint| endpoint.secrets());
+
+        let mut secrets = self.secrets.write().unwrap_or_else(|e| e.into_inner());
+        secrets.clear();
+        for secret in configured
+            .into_iter()
+            .chain([api_token.as_deref()])
+            .flatten()
+            .chain(endpoint_secrets)
+        {
+            // Keys are matched with or without a 0x prefix
+            let secret = secret.trim();
+            let bare = secret.strip_prefix("0x").unwrap_or(secret);
+            for variant in [secret, bare] {
+                if variant.len() >= MIN_SECRET_LEN && !secrets.iter().any(|s| s == variant) {
+                    secrets.push(variant.to_string());
+                }
+            }
+        }
+
+        // Mask the longest variants first, so a prefixed key is not left with a dangling 0x
+        secrets.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+    }
+
+    /// Redact a message
+    pub fn redact<'a>(&self, message: &'a str) -> Cow<'a, str> {
+        let mut redacted = Cow::Borrowed(message);
+
+        if self.redact_secrets.load(Ordering::Relaxed) {
+            let secrets = self.secrets.read().unwrap_or_else(|e| e.into_inner());
+            for secret in secrets.iter() {
+                if redacted.contains(secret.as_str()) {
+                    redacted = Cow::Owned(redacted.replace(secret.as_str(), REDACTED));
+                }
+            }
+        }
+
+        if self.redact_hex.load(Ordering::Relaxed) {
+            if let Some(shortened) = shorten_hex_blobs(&redacted) {
+                redacted = Cow::Owned(shortened);
+            }
+        }
+
+        redacted
+    }
+}
+
+/// Shorten every hex run longer than a hash to its leading bytes, or `None` if there is none
+fn shorten_hex_blobs(message: &str) -> Option<String> {
+    let bytes = message.as_bytes();
+    let mut output: Option<String> = None;
+    let mut copied = 0;
+    let mut index = 0;
+
+    while index < bytes.len() {
+        if !bytes[index].is_ascii_hexdigit() {
+            index += 1;
+            continue;
+        }
+
+        let start = index;
+        while index < bytes.len() && bytes[index].is_ascii_hexdigit() {
+            index += 1;
+        }
+
+        let digits = index - start;
+        if digits > MAX_PLAIN_HEX_DIGITS {
+            let output = output.get_or_insert_with(|| String::with_capacity(message.len()));
+            let kept = start + KEPT_HEX_DIGITS;
+            output.push_str(&message[copied..kept]);
+            output.push_str(&format!(
+                "…<{} bytes redacted>",
+                (digits - KEPT_HEX_DIGITS) / 2
+            ));
+            copied = index;
+        }
+    }
+
+    output.map(|mut output| {
+        output.push_str(&message[copied..]);
+        output
+    })
+}
+
+/// Get the process-wide redactor
+pub fn global() -> &'static Redactor {
+    static REDACTOR: OnceLock<Redactor> = OnceLock::new();
+    REDACTOR.get_or_init(Redactor::new)
+}
+
+/// Redact a message with the process-wide redactor
+pub fn redact(message: &str) -> Cow<'_, str> {
+    global().redact(message)
+}
+
+/// Writes formatted log lines to stderr with every line redacted
+struct RedactingWriter;
+
+impl Write for RedactingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // The subscriber writes each formatted event in one call
+        let line = String::from_utf8_lossy(buf);
+        io::stderr().write_all(redact(&line).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+/// Open a span following one opportunity through evaluation, building and execution
+///
+/// Every span gets a process-unique `trace_id`, since the same opportunity ID recurs whenever
+/// a route is found again.
+pub fn opportunity_span(id: &str) -> Span {
+    static NEXT_TRACE_ID: AtomicU64 = AtomicU64::new(1);
+    let trace_id = NEXT_TRACE_ID.fetch_add(1, Ordering::Relaxed);
+
+    tracing::info_span!("opportunity", trace_id, id)
+}
+
+/// Initialize the logger, defaulting to the `info` level, with every line redacted
+///
+/// `RUST_LOG` sets the filter and `LOG_FORMAT=json` switches to one JSON object per line, with
+/// the fields of the enclosing spans. `log` records are forwarded as events of their current
+/// span. Secrets are only known once `Redactor::configure` has run; hex blobs are shortened
+/// from the start.
+pub fn init() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let json = std::env::var("LOG_FORMAT").is_ok_and(|format| format.eq_ignore_ascii_case("json"));
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(|| RedactingWriter);
+
+    if json {
+        builder
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .init();
+    } else {
+        builder.with_ansi(io::stderr().is_terminal()).init();
+    }
+}
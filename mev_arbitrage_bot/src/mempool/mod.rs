@@ -0,0 +1,459 @@
+//! Mempool Module
+//!
+//! This module is responsible for watching pending swaps sent to the configured DEX routers,
+//! over Alchemy's `alchemy_pendingTransactions` subscription. Each swap is decoded and played
+//! through the cached pool reserves, so the scanner can see the state the pools will be in once
+//! the swaps are mined and anticipate opportunities one block ahead. Swaps leave the pending
+//! set once their `Swap` log reaches the pool cache, or when they expire.
+
+use anyhow::Result;
+use ethers::abi::Token;
+use ethers::providers::StreamExt;
+use ethers::types::{Address, Transaction, H256, U256};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::assets::ContractAbi;
+use crate::blockchain::AlchemyProvider;
+use crate::config::{Config, ConfigSection};
+use crate::dex::{
+    get_amount_in, get_amount_out, pool_key, DexType, PoolKey, PoolState, PoolStateCache,
+};
+use crate::metrics;
+use crate::scanner::ScanTrigger;
+use crate::tokens;
+use crate::utils::{current_timestamp, validate_and_parse_address};
+
+/// Default time a pending swap is expected to stay unmined (in seconds, two mainnet blocks)
+pub const DEFAULT_PENDING_TTL_SECS: u64 = 24;
+
+/// Default largest number of pending swaps tracked at once
+pub const DEFAULT_MAX_PENDING_SWAPS: usize = 1_000;
+
+/// Delay before the first resubscription after the subscription fails, doubled on each failure
+const INITIAL_RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+/// Longest delay between resubscription attempts
+const MAX_RESUBSCRIBE_DELAY: Duration = Duration::from_secs(300);
+
+/// Pending swap monitoring configuration (`[mempool]`)
+///
+/// Needs an Alchemy WebSocket endpoint for the `alchemy_pendingTransactions` subscription.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MempoolConfig {
+    /// Whether pending swaps to the DEX routers are watched to anticipate opportunities
+    pub enabled: bool,
+
+    /// Seconds a pending swap is kept before it is assumed dropped (defaults to 24)
+    pub pending_ttl_secs: Option<u64>,
+
+    /// Largest number of pending swaps tracked at once (defaults to 1000)
+    pub max_pending_swaps: Option<usize>,
+}
+
+impl ConfigSection for MempoolConfig {
+    const NAME: &'static str = "mempool";
+
+    fn validate(&self) -> Result<()> {
+        if self.max_pending_swaps == Some(0) {
+            anyhow::bail!("Mempool max_pending_swaps must be greater than zero");
+        }
+
+        Ok(())
+    }
+}
+
+/// Router functions decoded as swaps
+const SWAP_FUNCTIONS: [&str; 6] = [
+    "swapExactTokensForTokens",
+    "swapExactTokensForETH",
+    "swapExactETHForTokens",
+    "swapTokensForExactTokens",
+    "swapTokensForExactETH",
+    "swapETHForExactTokens",
+];
+
+/// Amount fixed by a swap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapAmount {
+    /// Exact input of the first token
+    ExactIn(U256),
+
+    /// Exact output of the last token
+    ExactOut(U256),
+}
+
+/// A decoded swap waiting in the mempool
+#[derive(Debug, Clone)]
+pub struct PendingSwap {
+    /// Hash of the pending transaction
+    pub tx_hash: H256,
+
+    /// DEX whose router the swap was sent to
+    pub dex_type: DexType,
+
+    /// Tokens swapped through, one pool per consecutive pair
+    pub path: Vec<Address>,
+
+    /// Amount fixed by the swap
+    pub amount: SwapAmount,
+
+    /// When the swap was seen (Unix timestamp)
+    pub seen_at: u64,
+}
+
+/// Pool state predicted after the pending swaps are mined
+#[derive(Debug, Clone)]
+pub struct PendingState {
+    /// Every cached pool, with the reserves the pending swaps leave it with
+    pub pools: Vec<PoolState>,
+
+    /// Pools whose reserves the pending swaps change
+    pub touched: HashSet<Address>,
+
+    /// Transactions of the pending swaps applied
+    pub swaps: Vec<H256>,
+}
+
+/// Decode a router transaction into a pending swap, or `None` if it is not a known swap
+pub fn decode_swap(dex_type: DexType, tx: &Transaction) -> Option<PendingSwap> {
+    let data = tx.input.as_ref();
+    if data.len() < 4 {
+        return None;
+    }
+
+    let abi = ContractAbi::UniswapV2Router.abi();
+    let function = SWAP_FUNCTIONS
+        .iter()
+        .filter_map(|name| abi.function(name).ok())
+        .find(|function| function.short_signature() == data[..4])?;
+    let inputs = function.decode_input(&data[4..]).ok()?;
+
+    let path = inputs.iter().find_map(|input| match input {
+        Token::Array(tokens) => tokens
+            .iter()
+            .map(|token| token.clone().into_address())
+            .collect::<Option<Vec<_>>>(),
+        _ => None,
+    })?;
+    if path.len() < 2 {
+        return None;
+    }
+
+    let first_uint = inputs.first().and_then(|input| input.clone().into_uint());
+    let amount = match function.name.as_str() {
+        "swapExactETHForTokens" => SwapAmount::ExactIn(tx.value),
+        "swapExactTokensForTokens" | "swapExactTokensForETH" => SwapAmount::ExactIn(first_uint?),
+        _ => SwapAmount::ExactOut(first_uint?),
+    };
+
+    Some(PendingSwap {
+        tx_hash: tx.hash,
+        dex_type,
+        path,
+        amount,
+        seen_at: current_timestamp(),
+    })
+}
+
+/// Play a swap through the pools it trades, returning the pools it changed
+///
+/// Exact-output swaps are sized from their output first. A swap stops at the first pool that
+/// is not cached, since its output there is unknown.
+fn apply_swap(
+    pools: &mut HashMap<Address, PoolState>,
+    index: &HashMap<PoolKey, Address>,
+    swap: &PendingSwap,
+) -> Vec<Address> {
+    let hops: Vec<Option<Address>> = swap
+        .path
+        .windows(2)
+        .map(|pair| {
+            index
+                .get(&pool_key(swap.dex_type, pair[0], pair[1]))
+                .copied()
+        })
+        .collect();
+
+    let mut amount = match swap.amount {
+        SwapAmount::ExactIn(amount) => amount,
+        SwapAmount::ExactOut(amount_out) => {
+            let mut amount = amount_out;
+            for (pair, pool) in swap.path.windows(2).zip(&hops).rev() {
+                let Some(state) = pool.and_then(|pool| pools.get(&pool)) else {
+                    return Vec::new();
+                };
+                let Some((reserve_in, reserve_out)) = state.reserves_for(pair[0]) else {
+                    return Vec::new();
+                };
+                let Some(amount_in) = get_amount_in(amount, reserve_in, reserve_out, state.fee)
+                else {
+                    return Vec::new();
+                };
+                amount = amount_in;
+            }
+            amount
+        }
+    };
+
+    let mut touched = Vec::new();
+    for (pair, pool) in swap.path.windows(2).zip(&hops) {
+        let Some(state) = pool.and_then(|pool| pools.get_mut(&pool)) else {
+            break;
+        };
+        let Some((reserve_in, reserve_out)) = state.reserves_for(pair[0]) else {
+            break;
+        };
+        let amount_out = get_amount_out(amount, reserve_in, reserve_out, state.fee);
+        if amount_out.is_zero() {
+            break;
+        }
+
+        if pair[0] == state.token0 {
+            state.reserve0 = state.reserve0.saturating_add(amount);
+            state.reserve1 = state.reserve1.saturating_sub(amount_out);
+        } else {
+            state.reserve1 = state.reserve1.saturating_add(amount);
+            state.reserve0 = state.reserve0.saturating_sub(amount_out);
+        }
+        touched.push(state.address);
+        amount = amount_out;
+    }
+
+    touched
+}
+
+/// Watches pending router swaps and predicts the pool reserves they lead to
+pub struct MempoolMonitor {
+    routers: HashMap<Address, DexType>,
+    pool_cache: Arc<PoolStateCache>,
+    pending: Mutex<VecDeque<PendingSwap>>,
+    pending_ttl_secs: u64,
+    max_pending: usize,
+    updated: AtomicBool,
+    scan_trigger: Arc<ScanTrigger>,
+}
+
+/// Create a mempool monitor for the routers of the enabled Uniswap V2 style DEXes, requesting
+/// a scan of the pairs of every new pending swap through `scan_trigger`
+pub fn create_monitor(
+    config: &Arc<Config>,
+    mempool_config: &MempoolConfig,
+    pool_cache: Arc<PoolStateCache>,
+    scan_trigger: Arc<ScanTrigger>,
+) -> Result<Arc<MempoolMonitor>> {
+    let mut routers = HashMap::new();
+    for (dex, dex_type) in [
+        (&config.dex.uniswap, DexType::UniswapV2),
+        (&config.dex.sushiswap, DexType::Sushiswap),
+    ] {
+        if dex.enabled {
+            routers.insert(validate_and_parse_address(&dex.router_address)?, dex_type);
+        }
+    }
+    if routers.is_empty() {
+        anyhow::bail!("Mempool monitoring needs Uniswap V2 or Sushiswap enabled");
+    }
+
+    Ok(Arc::new(MempoolMonitor {
+        routers,
+        pool_cache,
+        pending: Mutex::new(VecDeque::new()),
+        pending_ttl_secs: mempool_config
+            .pending_ttl_secs
+            .unwrap_or(DEFAULT_PENDING_TTL_SECS),
+        max_pending: mempool_config
+            .max_pending_swaps
+            .unwrap_or(DEFAULT_MAX_PENDING_SWAPS),
+        updated: AtomicBool::new(false),
+        scan_trigger,
+    }))
+}
+
+impl MempoolMonitor {
+    /// Watch pending transactions until shutdown, resubscribing with backoff when the
+    /// subscription ends or fails
+    pub async fn run(
+        self: Arc<Self>,
+        alchemy_provider: Arc<AlchemyProvider>,
+        shutdown: CancellationToken,
+    ) {
+        let mut delay = INITIAL_RESUBSCRIBE_DELAY;
+        while !shutdown.is_cancelled() {
+            tokio::select! {
+                result = self.watch(&alchemy_provider) => match result {
+                    Ok(()) => {
+                        warn!("Pending transaction subscription ended");
+                        delay = INITIAL_RESUBSCRIBE_DELAY;
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Pending transaction subscription failed: {:#}; retrying in {}s",
+                            e,
+                            delay.as_secs()
+                        );
+                    }
+                },
+                _ = shutdown.cancelled() => break,
+            }
+
+            metrics::global().increment_counter("mempool_resubscribes", 1);
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = shutdown.cancelled() => break,
+            }
+            delay = (delay * 2).min(MAX_RESUBSCRIBE_DELAY);
+        }
+    }
+
+    /// Record the swaps of one pending transaction subscription until it ends
+    async fn watch(&self, alchemy_provider: &AlchemyProvider) -> Result<()> {
+        let routers: Vec<Address> = self.routers.keys().copied().collect();
+        let mut stream = alchemy_provider
+            .subscribe_pending_transactions(&routers)
+            .await?;
+        info!("Watching pending swaps to {} DEX routers", routers.len());
+
+        while let Some(tx) = stream.next().await {
+            let Some(&dex_type) = tx.to.and_then(|to| self.routers.get(&to)) else {
+                continue;
+            };
+
+            match decode_swap(dex_type, &tx) {
+                Some(swap) => {
+                    debug!(
+                        "Pending {} swap {:?} via {}",
+                        dex_type.name(),
+                        swap.tx_hash,
+                        tokens::path_label(&swap.path)
+                    );
+                    self.record(swap);
+                }
+                None => debug!("Pending transaction {:?} is not a known swap", tx.hash),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add a pending swap, dropping the oldest one when the set is full
+    fn record(&self, swap: PendingSwap) {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        if pending.iter().any(|known| known.tx_hash == swap.tx_hash) {
+            return;
+        }
+
+        let pairs: Vec<(Address, Address)> = swap
+            .path
+            .windows(2)
+            .map(|pair| (pair[0], pair[1]))
+            .collect();
+        pending.push_back(swap);
+        if pending.len() > self.max_pending {
+            pending.pop_front();
+        }
+        self.updated.store(true, Ordering::Relaxed);
+        self.scan_trigger.pending_updated(&pairs);
+
+        metrics::global().increment_counter("mempool_pending_swaps", 1);
+        metrics::global().set_gauge("mempool_pending_swaps_tracked", pending.len() as f64);
+    }
+
+    /// Get the seconds a pending swap is kept before it is assumed dropped
+    pub fn pending_ttl_secs(&self) -> u64 {
+        self.pending_ttl_secs
+    }
+
+    /// Count the pending swaps that trade through any of the given pools
+    ///
+    /// Each such swap may land ahead of a trade on the pools and move their prices first.
+    pub fn pending_swaps_touching(&self, pools: &HashSet<PoolKey>) -> usize {
+        let now = current_timestamp();
+        let pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending
+            .iter()
+            .filter(|swap| now.saturating_sub(swap.seen_at) <= self.pending_ttl_secs)
+            .filter(|swap| {
+                swap.path
+                    .windows(2)
+                    .any(|pair| pools.contains(&pool_key(swap.dex_type, pair[0], pair[1])))
+            })
+            .count()
+    }
+
+    /// Predict the pool state after the pending swaps, or `None` if no swap arrived since the
+    /// last prediction
+    ///
+    /// Swaps are applied in the order they were seen. Expired and mined swaps are dropped first.
+    pub async fn take_pending_state(&self) -> Option<PendingState> {
+        if !self.updated.swap(false, Ordering::Relaxed) {
+            return None;
+        }
+
+        let now = current_timestamp();
+        let candidates: Vec<PendingSwap> = {
+            let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+            pending.retain(|swap| now.saturating_sub(swap.seen_at) <= self.pending_ttl_secs);
+            pending.iter().cloned().collect()
+        };
+
+        let mut swaps = Vec::with_capacity(candidates.len());
+        let mut mined = HashSet::new();
+        for swap in candidates {
+            if self.pool_cache.has_applied_swap(swap.tx_hash).await {
+                mined.insert(swap.tx_hash);
+            } else {
+                swaps.push(swap);
+            }
+        }
+        if !mined.is_empty() {
+            let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+            pending.retain(|swap| !mined.contains(&swap.tx_hash));
+        }
+        if swaps.is_empty() {
+            return None;
+        }
+
+        let mut pools: HashMap<Address, PoolState> = self
+            .pool_cache
+            .pools()
+            .await
+            .into_iter()
+            .map(|pool| (pool.address, pool))
+            .collect();
+        let index = pools
+            .values()
+            .map(|pool| {
+                (
+                    pool_key(pool.dex_type, pool.token0, pool.token1),
+                    pool.address,
+                )
+            })
+            .collect();
+
+        let mut touched = HashSet::new();
+        let mut applied = Vec::new();
+        for swap in &swaps {
+            let changed = apply_swap(&mut pools, &index, swap);
+            if !changed.is_empty() {
+                touched.extend(changed);
+                applied.push(swap.tx_hash);
+            }
+        }
+        if applied.is_empty() {
+            return None;
+        }
+
+        Some(PendingState {
+            pools: pools.into_values().collect(),
+            touched,
+            swaps: applied,
+        })
+    }
+}
@@ -0,0 +1,148 @@
+//! Metrics Module
+//!
+//! This module is responsible for collecting runtime metrics (gauges, counters and histograms)
+//! and rendering them in the Prometheus text exposition format for operators.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{OnceLock, RwLock};
+
+/// Upper bounds of the histogram buckets, suited to latencies in milliseconds
+const HISTOGRAM_BUCKETS: [f64; 12] = [
+    1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0,
+];
+
+/// Distribution of the values observed under one name
+#[derive(Debug, Clone, Default)]
+pub struct Histogram {
+    /// Number of observations at or below each bound of `HISTOGRAM_BUCKETS`
+    buckets: [u64; HISTOGRAM_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(HISTOGRAM_BUCKETS) {
+            if value <= bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    /// Get the number of observations
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Get the mean of the observations
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+}
+
+/// Registry of named gauges, counters and histograms
+#[derive(Debug, Default)]
+pub struct MetricsRegistry {
+    gauges: RwLock<BTreeMap<String, f64>>,
+    counters: RwLock<BTreeMap<String, u64>>,
+    histograms: RwLock<BTreeMap<String, Histogram>>,
+}
+
+impl MetricsRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a gauge to a value
+    pub fn set_gauge(&self, name: &str, value: f64) {
+        if let Ok(mut gauges) = self.gauges.write() {
+            gauges.insert(name.to_string(), value);
+        }
+    }
+
+    /// Remove a gauge (e.g. when the value no longer applies)
+    pub fn clear_gauge(&self, name: &str) {
+        if let Ok(mut gauges) = self.gauges.write() {
+            gauges.remove(name);
+        }
+    }
+
+    /// Get the current value of a gauge
+    pub fn gauge(&self, name: &str) -> Option<f64> {
+        self.gauges.read().ok()?.get(name).copied()
+    }
+
+    /// Increment a counter by `amount`
+    pub fn increment_counter(&self, name: &str, amount: u64) {
+        if let Ok(mut counters) = self.counters.write() {
+            *counters.entry(name.to_string()).or_default() += amount;
+        }
+    }
+
+    /// Get the current value of a counter
+    pub fn counter(&self, name: &str) -> u64 {
+        self.counters
+            .read()
+            .ok()
+            .and_then(|counters| counters.get(name).copied())
+            .unwrap_or_default()
+    }
+
+    /// Record an observation in a histogram
+    pub fn observe(&self, name: &str, value: f64) {
+        if let Ok(mut histograms) = self.histograms.write() {
+            histograms
+                .entry(name.to_string())
+                .or_default()
+                .observe(value);
+        }
+    }
+
+    /// Get the current distribution of a histogram
+    pub fn histogram(&self, name: &str) -> Option<Histogram> {
+        self.histograms.read().ok()?.get(name).cloned()
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+
+        if let Ok(counters) = self.counters.read() {
+            for (name, value) in counters.iter() {
+                let _ = writeln!(output, "# TYPE {} counter", name);
+                let _ = writeln!(output, "{} {}", name, value);
+            }
+        }
+
+        if let Ok(gauges) = self.gauges.read() {
+            for (name, value) in gauges.iter() {
+                let _ = writeln!(output, "# TYPE {} gauge", name);
+                let _ = writeln!(output, "{} {}", name, value);
+            }
+        }
+
+        if let Ok(histograms) = self.histograms.read() {
+            for (name, histogram) in histograms.iter() {
+                let _ = writeln!(output, "# TYPE {} histogram", name);
+                for (bucket, bound) in histogram.buckets.iter().zip(HISTOGRAM_BUCKETS) {
+                    let _ = writeln!(output, "{}_bucket{{le=\"{}\"}} {}", name, bound, bucket);
+                }
+                let _ = writeln!(output, "{}_bucket{{le=\"+Inf\"}} {}", name, histogram.count);
+                let _ = writeln!(output, "{}_sum {}", name, histogram.sum);
+                let _ = writeln!(output, "{}_count {}", name, histogram.count);
+            }
+        }
+
+        output
+    }
+}
+
+/// Get the process-wide metrics registry
+pub fn global() -> &'static MetricsRegistry {
+    static REGISTRY: OnceLock<MetricsRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(MetricsRegistry::new)
+}
@@ -0,0 +1,235 @@
+//! Builder Submission Module
+//!
+//! This module is responsible for broadcasting bundles to block builders directly, besides the
+//! MEV-Share relay, so a bundle can land in blocks built by builders the relay does not reach.
+//! Each configured builder is sent the bundle's signed transactions with `eth_sendBundle` once
+//! per target block, concurrently, signed with the same `X-Flashbots-Signature` key as relay
+//! requests. Which builder built the block a bundle landed in is read from the block's extra
+//! data.
+
+use anyhow::{Context, Result};
+use ethers::types::{Bytes, H256};
+use futures::future::join_all;
+use reqwest::{header, Client};
+use serde::{Deserialize, Serialize};
+
+use super::signer::{BundleSigner, FLASHBOTS_SIGNATURE_HEADER};
+use crate::config::ConfigSection;
+
+/// Builders bundles are broadcast to (`[[builders]]`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct BuildersConfig(pub Vec<BuilderEndpoint>);
+
+/// A block builder accepting `eth_sendBundle`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuilderEndpoint {
+    /// Name of the builder, used in logs and metric names (letters, digits and underscores)
+    pub name: String,
+
+    /// RPC endpoint of the builder
+    pub url: String,
+
+    /// Text the builder puts in the extra data of its blocks (defaults to the name)
+    pub extra_data: Option<String>,
+}
+
+impl BuilderEndpoint {
+    /// Whether a block's extra data marks it as built by this builder
+    fn built(&self, extra_data: &str) -> bool {
+        let marker = self.extra_data.as_deref().unwrap_or(&self.name);
+        extra_data
+            .to_ascii_lowercase()
+            .contains(&marker.to_ascii_lowercase())
+    }
+}
+
+impl ConfigSection for BuildersConfig {
+    const NAME: &'static str = "builders";
+
+    fn validate(&self) -> Result<()> {
+        for (index, builder) in self.0.iter().enumerate() {
+            if builder.name.is_empty() {
+                anyhow::bail!("Builder {} has no name", index);
+            }
+            if !builder
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                anyhow::bail!(
+                    "Builder name {} may only contain letters, digits and underscores",
+                    builder.name
+                );
+            }
+            if !builder.url.starts_with("http://") && !builder.url.starts_with("https://") {
+                anyhow::bail!(
+                    "Builder {} url must start with http:// or https://",
+                    builder.name
+                );
+            }
+            if self.0[..index]
+                .iter()
+                .any(|other| other.name.eq_ignore_ascii_case(&builder.name))
+            {
+                anyhow::bail!("Builder {} is configured twice", builder.name);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parameters of `eth_sendBundle`
+#[derive(Debug, Clone, Serialize)]
+struct SendBundleParams<'a> {
+    txs: &'a [Bytes],
+
+    #[serde(rename = "blockNumber")]
+    block_number: String,
+
+    #[serde(rename = "revertingTxHashes")]
+    reverting_tx_hashes: &'a [H256],
+}
+
+/// JSON-RPC request
+#[derive(Debug, Clone, Serialize)]
+struct RpcRequest<'a> {
+    jsonrpc: &'static str,
+    id: u64,
+    method: &'static str,
+    params: [SendBundleParams<'a>; 1],
+}
+
+/// JSON-RPC response
+#[derive(Debug, Clone, Deserialize)]
+struct RpcResponse {
+    result: Option<serde_json::Value>,
+    error: Option<RpcError>,
+}
+
+/// JSON-RPC error
+#[derive(Debug, Clone, Deserialize)]
+struct RpcError {
+    message: String,
+}
+
+/// Outcome of sending a bundle to one builder for one block
+#[derive(Debug)]
+pub struct BuilderSubmission {
+    /// Name of the builder
+    pub builder: String,
+
+    /// Block the bundle was sent for
+    pub block_number: u64,
+
+    /// Bundle hash the builder returned, if it accepted the bundle
+    pub result: Result<Option<String>>,
+}
+
+/// Broadcasts bundles to the configured builders
+#[derive(Clone)]
+pub struct BuilderBroadcaster {
+    http_client: Client,
+    signer: BundleSigner,
+    builders: Vec<BuilderEndpoint>,
+}
+
+impl BuilderBroadcaster {
+    /// Create a broadcaster to `builders`, signing with the relay signer
+    pub fn new(http_client: Client, signer: BundleSigner, builders: Vec<BuilderEndpoint>) -> Self {
+        Self {
+            http_client,
+            signer,
+            builders,
+        }
+    }
+
+    /// Whether any builder is configured
+    pub fn is_empty(&self) -> bool {
+        self.builders.is_empty()
+    }
+
+    /// Send signed transactions as a bundle to every builder for each block from `first_block`
+    /// to `last_block`, concurrently
+    pub async fn broadcast(
+        &self,
+        signed_txs: &[Bytes],
+        reverting_tx_hashes: &[H256],
+        first_block: u64,
+        last_block: u64,
+    ) -> Vec<BuilderSubmission> {
+        let sends = self.builders.iter().flat_map(|builder| {
+            (first_block..=last_block).map(move |block_number| async move {
+                BuilderSubmission {
+                    builder: builder.name.clone(),
+                    block_number,
+                    result: self
+                        .send(builder, signed_txs, reverting_tx_hashes, block_number)
+                        .await,
+                }
+            })
+        });
+
+        join_all(sends).await
+    }
+
+    /// Get the name of the builder whose extra data a block carries
+    pub fn builder_of(&self, extra_data: &Bytes) -> Option<&str> {
+        let extra_data = String::from_utf8_lossy(extra_data);
+        self.builders
+            .iter()
+            .find(|builder| builder.built(&extra_data))
+            .map(|builder| builder.name.as_str())
+    }
+
+    /// Send a bundle to one builder for one block, returning the bundle hash it reports
+    async fn send(
+        &self,
+        builder: &BuilderEndpoint,
+        signed_txs: &[Bytes],
+        reverting_tx_hashes: &[H256],
+        block_number: u64,
+    ) -> Result<Option<String>> {
+        let request = RpcRequest {
+            jsonrpc: "2.0",
+            id: 1,
+            method: "eth_sendBundle",
+            params: [SendBundleParams {
+                txs: signed_txs,
+                block_number: format!("0x{:x}", block_number),
+                reverting_tx_hashes,
+            }],
+        };
+
+        // The signature covers the exact bytes sent, so serialize the body ourselves
+        let body = serde_json::to_vec(&request).context("Failed to serialize bundle")?;
+        let signature = self.signer.sign_payload(&body).await?;
+
+        let response = self
+            .http_client
+            .post(&builder.url)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(FLASHBOTS_SIGNATURE_HEADER, signature)
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<RpcResponse>()
+            .await?;
+
+        if let Some(error) = response.error {
+            anyhow::bail!("{}", error.message);
+        }
+
+        // Builders answer with `{"bundleHash": ...}`, a bare hash or nothing
+        Ok(response.result.and_then(|result| match result {
+            serde_json::Value::String(hash) => Some(hash),
+            serde_json::Value::Object(fields) => fields
+                .get("bundleHash")
+                .and_then(|hash| hash.as_str())
+                .map(str::to_string),
+            _ => None,
+        }))
+    }
+}
@@ -1,24 +1,37 @@
 //! MEV-Share Module
 //!
-//! This module is responsible for integrating with the MEV-Share network.
+//! This module is responsible for integrating with the MEV-Share network, and for broadcasting
+//! bundles to the builders configured in `[[builders]]`.
 //! This is a custom implementation that doesn't rely on the mev-share-rs crate.
 
+mod builders;
+mod signer;
+
+pub use builders::{BuilderBroadcaster, BuilderEndpoint, BuilderSubmission, BuildersConfig};
+pub use signer::{BundleSigner, FLASHBOTS_SIGNATURE_HEADER};
+
 use anyhow::{Context, Result};
-use ethers::types::{transaction::eip2718::TypedTransaction, Bytes, H256, U256};
+use ethers::types::{transaction::eip2718::TypedTransaction, Address, Bytes, H256};
+use ethers::utils::keccak256;
 use futures::stream::{StreamExt, TryStreamExt};
 use log::{debug, error, info, warn};
 use reqwest::{header, Client};
 use reqwest_eventsource::{Event, EventSource};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 
-use crate::config::Config;
+use crate::blockchain;
+use crate::config::{Config, HttpEndpointConfig};
+use crate::utils::validate_and_parse_address;
 
 /// MEV-Share API endpoints
 const BUNDLE_STATS_ENDPOINT: &str = "/api/v1/bundle/stats";
 const SEND_BUNDLE_ENDPOINT: &str = "/api/v1/bundle";
+const SIMULATE_BUNDLE_ENDPOINT: &str = "/api/v1/bundle/simulate";
 const BUNDLE_STATUS_ENDPOINT: &str = "/api/v1/bundle/status";
 const SEND_TX_ENDPOINT: &str = "/api/v1/tx";
 const SSE_TRANSACTIONS_ENDPOINT: &str = "/api/v1/events/transaction";
@@ -29,35 +42,109 @@ pub struct MevShareClient {
     config: Arc<Config>,
     http_client: Client,
     api_url: String,
-    api_key: Option<String>,
+    signer: BundleSigner,
+    builders: BuilderBroadcaster,
+    streams_closed: CancellationToken,
 }
 
-/// MEV-Share bundle
+/// MEV-Share bundle, as sent to `mev_sendBundle` and `mev_simBundle` (bundle schema v0.1)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MevShareBundle {
-    /// Bundle version
+    /// Bundle schema version
     pub version: String,
 
-    /// Bundle ID
-    pub id: Option<String>,
+    /// Blocks the bundle may be included in
+    pub inclusion: BundleParams,
+
+    /// Transactions and nested bundles, in execution order
+    pub body: Vec<BundleItem>,
+
+    /// Refunds the bundle pays out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validity: Option<BundleValidity>,
+
+    /// What is shared of the bundle, and with which builders
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privacy: Option<BundlePrivacy>,
+}
+
+/// Item of a bundle body
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BundleItem {
+    /// A pending transaction seen on MEV-Share, referenced by hash (e.g. the one backrun)
+    Hash { hash: H256 },
+
+    /// A signed transaction, and whether it may revert without dropping the bundle
+    Tx {
+        tx: Bytes,
+        #[serde(rename = "canRevert")]
+        can_revert: bool,
+    },
+
+    /// A nested bundle, e.g. a backrun of a pending transaction
+    Bundle { bundle: Box<MevShareBundle> },
+}
 
-    /// Bundle transactions
-    pub transactions: Vec<String>,
+/// Refunds of a bundle
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BundleValidity {
+    /// Share of the bundle's profit refunded to the senders of body items
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub refund: Vec<Refund>,
+
+    /// Recipients of the refunds paid when other searchers backrun the bundle
+    #[serde(
+        rename = "refundConfig",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub refund_config: Vec<RefundConfig>,
+}
 
-    /// Block number
-    pub block_number: String,
+/// Refund to the sender of a body item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Refund {
+    /// Index of the body item
+    #[serde(rename = "bodyIdx")]
+    pub body_idx: usize,
 
-    /// Minimum timestamp
-    pub min_timestamp: Option<u64>,
+    /// Share of the profit refunded (in percent)
+    pub percent: u8,
+}
 
-    /// Maximum timestamp
-    pub max_timestamp: Option<u64>,
+/// Recipient of a share of a bundle's refunds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefundConfig {
+    /// Address receiving the refund
+    pub address: Address,
 
-    /// Reverting transactions
-    #[serde(rename = "revertingTxHashes")]
-    pub reverting_tx_hashes: Option<Vec<String>>,
+    /// Share of the refund received (in percent)
+    pub percent: u8,
 }
 
+/// Privacy of a bundle
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BundlePrivacy {
+    /// Parts of the bundle hinted to other searchers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hints: Option<Vec<String>>,
+
+    /// Builders the bundle is shared with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub builders: Option<Vec<String>>,
+}
+
+/// Hints a bundle may share with other searchers (`mev_share.bundle.hints`)
+pub const BUNDLE_HINTS: [&str; 6] = [
+    "calldata",
+    "contract_address",
+    "logs",
+    "function_selector",
+    "hash",
+    "tx_hash",
+];
+
 /// MEV-Share transaction
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MevShareTransaction {
@@ -93,33 +180,17 @@ pub struct MevShareHints {
     pub logs: bool,
 }
 
-/// Bundle parameters
+/// Blocks a bundle may be included in
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BundleParams {
-    /// Block number
-    pub block: Option<String>,
+    /// First block
+    pub block: String,
 
-    /// Max block number
-    #[serde(rename = "maxBlock")]
+    /// Last block, if the bundle targets a range
+    #[serde(rename = "maxBlock", skip_serializing_if = "Option::is_none")]
     pub max_block: Option<String>,
 }
 
-/// Bundle request
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BundleRequest {
-    /// Bundle version
-    pub version: String,
-
-    /// Inclusion parameters
-    pub inclusion: BundleParams,
-
-    /// Bundle body (transactions)
-    pub body: Vec<String>,
-
-    /// Validity parameters
-    pub validity: BundleParams,
-}
-
 /// Transaction with hint preferences
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transaction {
@@ -180,6 +251,44 @@ pub struct BundleStatusResponse {
     pub status: String,
 }
 
+/// Inclusion state of a submitted bundle, as reported by the relay
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundleInclusion {
+    /// Still eligible for inclusion in its target blocks
+    Pending,
+
+    /// Included on chain
+    Included,
+
+    /// Dropped, expired or otherwise no longer eligible
+    Failed,
+}
+
+impl BundleInclusion {
+    /// Interpret a status string returned by the relay
+    pub fn from_status(status: &str) -> Self {
+        match status.to_ascii_lowercase().as_str() {
+            "included" | "landed" => Self::Included,
+            "pending" | "received" | "simulated" => Self::Pending,
+            _ => Self::Failed,
+        }
+    }
+}
+
+/// Bundle simulation response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleSimulationResponse {
+    /// Whether every transaction in the bundle executed successfully
+    pub success: bool,
+
+    /// Error message, if the simulation failed
+    pub error: Option<String>,
+
+    /// Total gas used by the bundle
+    #[serde(rename = "gasUsed")]
+    pub gas_used: Option<u64>,
+}
+
 /// Bundle stats response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BundleStatsResponse {
@@ -192,29 +301,85 @@ pub struct BundleStatsResponse {
     pub total_transactions: u64,
 }
 
+/// Relay statistics of one submitted bundle
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BundleStats {
+    /// Whether the relay simulated the bundle
+    #[serde(rename = "isSimulated")]
+    pub is_simulated: bool,
+
+    /// Whether the relay forwarded the bundle with high priority
+    #[serde(rename = "isHighPriority")]
+    pub is_high_priority: bool,
+
+    /// When the relay received the bundle
+    #[serde(rename = "receivedAt")]
+    pub received_at: Option<String>,
+
+    /// When the relay simulated the bundle
+    #[serde(rename = "simulatedAt")]
+    pub simulated_at: Option<String>,
+
+    /// Builders that considered the bundle for a block
+    #[serde(rename = "consideredByBuildersAt")]
+    pub considered_by_builders_at: Vec<BuilderEvent>,
+
+    /// Builders that sealed a block containing the bundle
+    #[serde(rename = "sealedByBuildersAt")]
+    pub sealed_by_builders_at: Vec<BuilderEvent>,
+}
+
+/// A builder handling a bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuilderEvent {
+    /// Public key of the builder
+    pub pubkey: String,
+
+    /// When the builder handled the bundle
+    pub timestamp: String,
+}
+
 /// Create a new MEV-Share client
 pub async fn create_client(config: &Arc<Config>) -> Result<Arc<MevShareClient>> {
-    // Create the HTTP client with appropriate headers
-    let mut headers = header::HeaderMap::new();
-
-    // Add API key if available
-    if let Some(api_key) = &config.mev_share.api_key {
-        headers.insert(
-            "X-Flashbots-Signature",
-            header::HeaderValue::from_str(api_key).context("Invalid API key format")?,
-        );
+    // Create the signer that authenticates our requests to the relay
+    let signer = match &config.mev_share.signing_key {
+        Some(signing_key) => BundleSigner::from_key(signing_key)?,
+        None => {
+            let signer = BundleSigner::random();
+            warn!(
+                "No MEV-Share signing key configured, using ephemeral signer {:?} (relay reputation will not persist)",
+                signer.address()
+            );
+            signer
+        }
+    };
+
+    if config.mev_share.api_key.is_some() {
+        warn!("MEV_SHARE_API_KEY is no longer sent to the relay; requests are signed with MEV_SHARE_SIGNING_KEY instead");
     }
 
-    let http_client = Client::builder()
-        .timeout(Duration::from_secs(10))
-        .default_headers(headers)
-        .build()?;
+    let http_client =
+        blockchain::http_client(&config.mev_share.api_http, Some(Duration::from_secs(10)))?;
+
+    // Builders are reached through the relay's proxy, without its headers or credentials
+    let builders_config: BuildersConfig = config.section()?;
+    let builders_http_client = blockchain::http_client(
+        &HttpEndpointConfig {
+            proxy: config.mev_share.api_http.proxy.clone(),
+            ..HttpEndpointConfig::default()
+        },
+        Some(Duration::from_secs(10)),
+    )?;
+    let builders = BuilderBroadcaster::new(builders_http_client, signer.clone(), builders_config.0);
 
     let client = MevShareClient {
         config: config.clone(),
         http_client,
         api_url: config.mev_share.api_url.clone(),
-        api_key: config.mev_share.api_key.clone(),
+        signer,
+        builders,
+        streams_closed: CancellationToken::new(),
     };
 
     let client = Arc::new(client);
@@ -226,6 +391,36 @@ pub async fn create_client(config: &Arc<Config>) -> Result<Arc<MevShareClient>>
 }
 
 impl MevShareClient {
+    /// POST a JSON payload signed with the bundle signer and decode the JSON response
+    async fn post_signed<T, R>(&self, endpoint: &str, payload: &T) -> Result<R>
+    where
+        T: Serialize + ?Sized,
+        R: DeserializeOwned,
+    {
+        // The signature covers the exact bytes sent, so serialize the body ourselves
+        let body = serde_json::to_vec(payload).context("Failed to serialize request payload")?;
+        let signature = self.signer.sign_payload(&body).await?;
+
+        let response = self
+            .http_client
+            .post(format!("{}{}", self.api_url, endpoint))
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(FLASHBOTS_SIGNATURE_HEADER, signature)
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<R>()
+            .await?;
+
+        Ok(response)
+    }
+
+    /// Get the builders bundles are broadcast to besides the relay
+    pub fn builders(&self) -> &BuilderBroadcaster {
+        &self.builders
+    }
+
     /// Ping the MEV-Share API
     pub async fn ping(&self) -> Result<()> {
         if !self.config.mev_share.enabled {
@@ -266,15 +461,7 @@ impl MevShareClient {
         };
 
         // Send the transaction
-        let response = self
-            .http_client
-            .post(&format!("{}{}", self.api_url, SEND_TX_ENDPOINT))
-            .json(&mev_tx)
-            .send()
-            .await?
-            .error_for_status()?
-            .json::<SendTransactionResponse>()
-            .await?;
+        let response: SendTransactionResponse = self.post_signed(SEND_TX_ENDPOINT, &mev_tx).await?;
 
         // Parse the transaction hash
         let tx_hash = H256::from_slice(&hex::decode(&response.tx_hash[2..])?);
@@ -290,36 +477,35 @@ impl MevShareClient {
             return Err(anyhow::anyhow!("MEV-Share is not enabled"));
         }
 
-        // Create the bundle request
-        let bundle_request = BundleRequest {
-            version: bundle.version,
-            inclusion: BundleParams {
-                block: Some(bundle.block_number.clone()),
-                max_block: None,
-            },
-            body: bundle.transactions,
-            validity: BundleParams {
-                block: None,
-                max_block: None,
-            },
-        };
-
         // Send the bundle
-        let response = self
-            .http_client
-            .post(&format!("{}{}", self.api_url, SEND_BUNDLE_ENDPOINT))
-            .json(&bundle_request)
-            .send()
-            .await?
-            .error_for_status()?
-            .json::<SendBundleResponse>()
-            .await?;
+        let response: SendBundleResponse = self.post_signed(SEND_BUNDLE_ENDPOINT, &bundle).await?;
 
         info!("Sent bundle via MEV-Share: {}", response.bundle_hash);
 
         Ok(response.bundle_hash)
     }
 
+    /// Simulate a bundle via MEV-Share against the state of its target block
+    pub async fn simulate_bundle(
+        &self,
+        bundle: &MevShareBundle,
+    ) -> Result<BundleSimulationResponse> {
+        if !self.config.mev_share.enabled {
+            return Err(anyhow::anyhow!("MEV-Share is not enabled"));
+        }
+
+        // Simulate the bundle
+        let simulation: BundleSimulationResponse =
+            self.post_signed(SIMULATE_BUNDLE_ENDPOINT, bundle).await?;
+
+        debug!(
+            "Simulated bundle for block {}: success={}",
+            bundle.inclusion.block, simulation.success
+        );
+
+        Ok(simulation)
+    }
+
     /// Get the status of a bundle
     pub async fn get_bundle_status(&self, bundle_id: &str) -> Result<String> {
         if !self.config.mev_share.enabled {
@@ -342,7 +528,12 @@ impl MevShareClient {
         Ok(status.status)
     }
 
-    /// Subscribe to MEV-Share events
+    /// Close every event stream opened by `subscribe`
+    pub fn close_streams(&self) {
+        self.streams_closed.cancel();
+    }
+
+    /// Subscribe to MEV-Share events, until `close_streams` is called
     pub async fn subscribe(&self) -> Result<mpsc::Receiver<serde_json::Value>> {
         if !self.config.mev_share.enabled {
             return Err(anyhow::anyhow!("MEV-Share is not enabled"));
@@ -356,23 +547,21 @@ impl MevShareClient {
 
         // Clone necessary values for the async task
         let http_client = self.http_client.clone();
-        let api_key = self.api_key.clone();
+        let closed = self.streams_closed.clone();
 
         // Spawn a task to listen for events
         tokio::spawn(async move {
-            // Create a request with appropriate headers
-            let mut request = http_client.get(&sse_url);
-
-            // Add API key if available
-            if let Some(key) = &api_key {
-                request = request.header("X-Flashbots-Signature", key);
-            }
-
-            // Add Accept header for SSE
-            request = request.header("Accept", "text/event-stream");
-
-            // Send the request and get a streaming response
-            match request.send().await {
+            // Create a request with the Accept header for SSE (the event stream is public)
+            let request = http_client
+                .get(&sse_url)
+                .header("Accept", "text/event-stream");
+
+            // Send the request and get a streaming response, unless closed while connecting
+            let response = tokio::select! {
+                response = request.send() => response,
+                _ = closed.cancelled() => return,
+            };
+            match response {
                 Ok(response) => {
                     if !response.status().is_success() {
                         error!(
@@ -388,8 +577,19 @@ impl MevShareClient {
                     // Buffer for accumulating event data
                     let mut buffer = String::new();
 
-                    // Process the stream
-                    while let Some(chunk_result) = stream.next().await {
+                    // Process the stream until it ends or is closed
+                    loop {
+                        let chunk_result = tokio::select! {
+                            chunk_result = stream.next() => match chunk_result {
+                                Some(chunk_result) => chunk_result,
+                                None => break,
+                            },
+                            _ = closed.cancelled() => {
+                                info!("MEV-Share event stream closed");
+                                return;
+                            }
+                        };
+
                         match chunk_result {
                             Ok(chunk) => {
                                 // Convert bytes to string and append to buffer
@@ -471,24 +671,99 @@ impl MevShareClient {
     }
 
     /// Create a MEV-Share bundle
-    pub fn create_bundle(&self, transactions: Vec<Bytes>, block_number: u64) -> MevShareBundle {
-        // Get the current block number
-        let block_hex = format!("0x{:x}", block_number);
+    ///
+    /// The bundle targets `block_number..=max_block_number` (just `block_number` if no maximum
+    /// is given) and none of its transactions may revert, except those in `reverting_tx_hashes`.
+    /// Its privacy and refund recipients are the configured ones.
+    pub fn create_bundle(
+        &self,
+        transactions: Vec<Bytes>,
+        block_number: u64,
+        max_block_number: Option<u64>,
+        reverting_tx_hashes: &[H256],
+    ) -> MevShareBundle {
+        let body = transactions
+            .into_iter()
+            .map(|tx| {
+                let can_revert = reverting_tx_hashes.contains(&H256::from(keccak256(&tx)));
+                BundleItem::Tx { tx, can_revert }
+            })
+            .collect();
+
+        self.bundle(body, Vec::new(), block_number, max_block_number)
+    }
 
-        // Convert transactions to hex strings
-        let tx_hexes = transactions
+    /// Create a MEV-Share bundle backrunning a pending transaction seen on MEV-Share
+    ///
+    /// The pending transaction is referenced by hash and executes first; none of `transactions`
+    /// may revert. Its sender is refunded `mev_share.bundle.backrun_refund_percent` of the
+    /// profit, or the relay's default share when unset.
+    pub fn create_backrun_bundle(
+        &self,
+        user_tx_hash: H256,
+        transactions: Vec<Bytes>,
+        block_number: u64,
+        max_block_number: Option<u64>,
+    ) -> MevShareBundle {
+        let mut body = vec![BundleItem::Hash { hash: user_tx_hash }];
+        body.extend(transactions.into_iter().map(|tx| BundleItem::Tx {
+            tx,
+            can_revert: false,
+        }));
+        let refund = self
+            .config
+            .mev_share
+            .bundle
+            .backrun_refund_percent
+            .map(|percent| Refund {
+                body_idx: 0,
+                percent,
+            })
+            .into_iter()
+            .collect();
+
+        self.bundle(body, refund, block_number, max_block_number)
+    }
+
+    /// Create a bundle of `body` with the configured privacy and refund recipients
+    fn bundle(
+        &self,
+        body: Vec<BundleItem>,
+        refund: Vec<Refund>,
+        block_number: u64,
+        max_block_number: Option<u64>,
+    ) -> MevShareBundle {
+        let settings = &self.config.mev_share.bundle;
+        let refund_config: Vec<RefundConfig> = settings
+            .refund_config
             .iter()
-            .map(|tx| format!("0x{}", hex::encode(tx)))
+            .filter_map(|recipient| {
+                Some(RefundConfig {
+                    address: validate_and_parse_address(&recipient.address).ok()?,
+                    percent: recipient.percent,
+                })
+            })
             .collect();
+        let validity =
+            (!refund.is_empty() || !refund_config.is_empty()).then_some(BundleValidity {
+                refund,
+                refund_config,
+            });
+        let privacy =
+            (!settings.hints.is_empty() || !settings.builders.is_empty()).then(|| BundlePrivacy {
+                hints: (!settings.hints.is_empty()).then(|| settings.hints.clone()),
+                builders: (!settings.builders.is_empty()).then(|| settings.builders.clone()),
+            });
 
         MevShareBundle {
             version: "v0.1".to_string(),
-            id: None,
-            transactions: tx_hexes,
-            block_number: block_hex,
-            min_timestamp: None,
-            max_timestamp: None,
-            reverting_tx_hashes: None,
+            inclusion: BundleParams {
+                block: format!("0x{:x}", block_number),
+                max_block: max_block_number.map(|block| format!("0x{:x}", block)),
+            },
+            body,
+            validity,
+            privacy,
         }
     }
 
@@ -510,4 +785,30 @@ impl MevShareClient {
 
         Ok(stats)
     }
+
+    /// Get the relay statistics of a submitted bundle for one of its target blocks
+    pub async fn get_bundle_stats_for(
+        &self,
+        bundle_hash: &str,
+        block_number: u64,
+    ) -> Result<BundleStats> {
+        if !self.config.mev_share.enabled {
+            return Err(anyhow::anyhow!("MEV-Share is not enabled"));
+        }
+
+        let stats = self
+            .http_client
+            .get(format!(
+                "{}{}/{}",
+                self.api_url, BUNDLE_STATS_ENDPOINT, bundle_hash
+            ))
+            .query(&[("block", block_number)])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<BundleStats>()
+            .await?;
+
+        Ok(stats)
+    }
 }
@@ -0,0 +1,60 @@
+//! Bundle Signer Module
+//!
+//! This module is responsible for producing the `X-Flashbots-Signature` header that
+//! authenticates requests to Flashbots relays and MEV-Share.
+
+use anyhow::{Context, Result};
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::Address;
+use ethers::utils::keccak256;
+
+/// Name of the header carrying the payload signature
+pub const FLASHBOTS_SIGNATURE_HEADER: &str = "X-Flashbots-Signature";
+
+/// Signs relay request payloads with a dedicated key
+///
+/// The key only identifies the searcher to the relay (and accrues its reputation); it never
+/// holds funds and should be different from the wallet that signs transactions.
+#[derive(Debug, Clone)]
+pub struct BundleSigner {
+    wallet: LocalWallet,
+}
+
+impl BundleSigner {
+    /// Create a signer from a hex-encoded private key
+    pub fn from_key(private_key: &str) -> Result<Self> {
+        let wallet = private_key
+            .trim_start_matches("0x")
+            .parse::<LocalWallet>()
+            .context("Invalid MEV-Share signing key")?;
+
+        Ok(Self { wallet })
+    }
+
+    /// Create a signer with a freshly generated key
+    pub fn random() -> Self {
+        Self {
+            wallet: LocalWallet::new(&mut rand::thread_rng()),
+        }
+    }
+
+    /// Get the address identifying this signer to the relay
+    pub fn address(&self) -> Address {
+        self.wallet.address()
+    }
+
+    /// Sign a request body, returning the value of the `X-Flashbots-Signature` header
+    ///
+    /// The relay expects `<address>:<signature>`, where the signature is an EIP-191 personal
+    /// signature over the hex string of `keccak256(body)`.
+    pub async fn sign_payload(&self, body: &[u8]) -> Result<String> {
+        let digest = format!("0x{}", hex::encode(keccak256(body)));
+        let signature = self
+            .wallet
+            .sign_message(digest)
+            .await
+            .context("Failed to sign request payload")?;
+
+        Ok(format!("{:?}:0x{}", self.address(), signature))
+    }
+}
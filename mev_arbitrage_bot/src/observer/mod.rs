@@ -0,0 +1,236 @@
+//! Observer Module
+//!
+//! This module is responsible for the read-only observer mode: an instance that attaches to
+//! the trade history storage of a running bot and serves its analytics reports and recent
+//! records over HTTP. Operator queries then run in a separate process that cannot trade or
+//! write, instead of competing with the scan loop for the storage connection.
+//!
+//! Endpoints (all `GET`):
+//! - `/health`: liveness check
+//! - `/reports`: the available analytics views
+//! - `/reports/<view>`: one analytics view, as a plain-text table
+//! - `/trades`: the most recent trades, as JSON
+//! - `/opportunities`: the most recent opportunities, as JSON
+
+use anyhow::{Context, Result};
+use log::{debug, error, info, warn};
+use serde_json::json;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::signal;
+
+use crate::config::{self, Config};
+use crate::storage::{self, Storage, ANALYTICS_VIEWS};
+
+/// Largest request head read from a client
+pub(crate) const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// A response to one request
+pub(crate) struct Response {
+    pub(crate) status: &'static str,
+    pub(crate) content_type: &'static str,
+    pub(crate) body: String,
+}
+
+impl Response {
+    /// A plain-text response
+    pub(crate) fn text(status: &'static str, body: String) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body,
+        }
+    }
+
+    /// A JSON response
+    pub(crate) fn json(body: serde_json::Value) -> Self {
+        Self {
+            status: "200 OK",
+            content_type: "application/json",
+            body: body.to_string(),
+        }
+    }
+}
+
+/// Serve the read endpoints of a chain's storage until a shutdown signal is received
+///
+/// `args` optionally names one of the additional chains; the primary chain is observed
+/// otherwise.
+pub async fn run(config: &Arc<Config>, args: &[String]) -> Result<()> {
+    let chain_configs = config::chain_configs(config);
+    let observed = match args.first() {
+        Some(name) => {
+            let index = config
+                .chains
+                .iter()
+                .position(|chain| &chain.name == name)
+                .with_context(|| format!("Unknown chain {}", name))?;
+            chain_configs[index + 1].clone()
+        }
+        None => chain_configs[0].clone(),
+    };
+
+    let storage = storage::open_read_only(&observed).await?;
+    let listener = TcpListener::bind(&config.observer.listen_address)
+        .await
+        .with_context(|| format!("Failed to listen on {}", config.observer.listen_address))?;
+    info!(
+        "Observing {} on http://{}",
+        observed.storage.path, config.observer.listen_address
+    );
+
+    let recent_limit = config.observer.recent_limit;
+    tokio::select! {
+        _ = accept_loop(listener, storage, recent_limit) => {}
+        result = signal::ctrl_c() => {
+            if let Err(e) = result {
+                error!("Failed to listen for shutdown signal: {}", e);
+            }
+            info!("Shutdown signal received, stopping observer");
+        }
+    }
+
+    Ok(())
+}
+
+/// Accept connections, serving each on its own task
+async fn accept_loop(listener: TcpListener, storage: Arc<Storage>, recent_limit: usize) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                debug!("Observer connection from {}", peer);
+                let storage = storage.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = serve(stream, &storage, recent_limit).await {
+                        debug!("Observer connection from {} failed: {}", peer, e);
+                    }
+                });
+            }
+            Err(e) => warn!("Failed to accept observer connection: {}", e),
+        }
+    }
+}
+
+/// Read one request from a connection and answer it
+async fn serve(mut stream: TcpStream, storage: &Storage, recent_limit: usize) -> Result<()> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+        if request.len() > MAX_REQUEST_BYTES {
+            anyhow::bail!("Request head exceeds {} bytes", MAX_REQUEST_BYTES);
+        }
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .split_whitespace();
+    let response = match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some(target)) => route(target, storage, recent_limit),
+        (Some(_), Some(_)) => Response::text("405 Method Not Allowed", "Read-only\n".into()),
+        _ => Response::text("400 Bad Request", "Malformed request\n".into()),
+    };
+
+    write_response(&mut stream, &response).await
+}
+
+/// Write a response and close the connection
+pub(crate) async fn write_response(stream: &mut TcpStream, response: &Response) -> Result<()> {
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(response.body.as_bytes()).await?;
+    stream.shutdown().await?;
+
+    Ok(())
+}
+
+/// Answer a request for a path
+fn route(target: &str, storage: &Storage, recent_limit: usize) -> Response {
+    let path = target.split('?').next().unwrap_or_default();
+    let result = match path.trim_end_matches('/') {
+        "/health" => Ok(Response::text("200 OK", "ok\n".into())),
+        "/reports" => Ok(Response::text(
+            "200 OK",
+            ANALYTICS_VIEWS
+                .iter()
+                .map(|(name, description, _)| format!("{}: {}\n", name, description))
+                .collect(),
+        )),
+        "/trades" => recent_trades(storage, recent_limit),
+        "/opportunities" => recent_opportunities(storage, recent_limit),
+        path => match path.strip_prefix("/reports/") {
+            Some(view) if ANALYTICS_VIEWS.iter().any(|(name, _, _)| *name == view) => storage
+                .report(view)
+                .map(|table| Response::text("200 OK", format!("{}\n", table.render()))),
+            _ => Ok(Response::text("404 Not Found", "Not found\n".into())),
+        },
+    };
+
+    result.unwrap_or_else(|e| {
+        warn!("Observer query for {} failed: {:#}", path, e);
+        Response::text("500 Internal Server Error", format!("{:#}\n", e))
+    })
+}
+
+/// Render the most recent trades
+fn recent_trades(storage: &Storage, limit: usize) -> Result<Response> {
+    let trades: Vec<_> = storage
+        .recent_trades(limit)?
+        .into_iter()
+        .map(|trade| {
+            json!({
+                "id": trade.id,
+                "opportunity_id": trade.opportunity_id,
+                "created_at": trade.created_at,
+                "path": trade.token_symbols,
+                "variant": trade.variant,
+                "status": trade.status,
+                "tx_hash": trade.tx_hash.map(|hash| format!("{:?}", hash)),
+                "bundle_hash": trade.bundle_hash,
+                "block_number": trade.block_number,
+                "estimated_profit": trade.estimated_profit,
+                "realized_pnl": trade.realized_pnl,
+                "error": trade.error,
+                "config_hash": trade.config_hash,
+            })
+        })
+        .collect();
+
+    Ok(Response::json(json!(trades)))
+}
+
+/// Render the most recent opportunities
+pub(crate) fn recent_opportunities(storage: &Storage, limit: usize) -> Result<Response> {
+    let opportunities: Vec<_> = storage
+        .recent_opportunities(limit)?
+        .into_iter()
+        .map(|opportunity| {
+            json!({
+                "id": opportunity.id,
+                "timestamp": opportunity.timestamp,
+                "source_dex": opportunity.source_dex,
+                "target_dex": opportunity.target_dex,
+                "path": opportunity.token_symbols,
+                "estimated_profit": opportunity.estimated_profit,
+                "net_profit": opportunity.net_profit,
+                "variant": opportunity.variant,
+                "config_hash": opportunity.config_hash,
+            })
+        })
+        .collect();
+
+    Ok(Response::json(json!(opportunities)))
+}
@@ -0,0 +1,245 @@
+//! PnL Module
+//!
+//! This module is responsible for measuring what an executed arbitrage actually earned. The
+//! ERC20 transfers in its receipt are netted into the token balance changes of the bot's
+//! wallet and executor contract, which are priced in USD and compared against the profit the
+//! opportunity was estimated at. The difference is the slippage the estimate missed: price
+//! impact, fees and reserve changes between the quote and the inclusion block.
+//!
+//! Storage keeps the token deltas of every settled trade and rolls them up into daily and
+//! weekly totals for the `realized_slippage` report.
+
+use anyhow::{Context, Result};
+use ethers::middleware::Middleware;
+use ethers::types::{Address, H256, I256, U256};
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::blockchain::RpcClient;
+use crate::config::Config;
+use crate::price::{PriceOracle, PriceOracleInterface};
+use crate::tokens;
+use crate::transaction::{transfer_topic, TokenTransfer};
+use crate::utils::{current_timestamp, u256_to_decimal, validate_and_parse_address};
+
+/// Length of a daily period (in seconds)
+pub const DAY_SECS: u64 = 86_400;
+
+/// Length of a weekly period (in seconds)
+pub const WEEK_SECS: u64 = 7 * DAY_SECS;
+
+/// A period the realized PnL is rolled up by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PnlPeriod {
+    /// UTC day
+    Day,
+
+    /// UTC week, starting on Monday
+    Week,
+}
+
+impl PnlPeriod {
+    /// Get the period name, as stored
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PnlPeriod::Day => "day",
+            PnlPeriod::Week => "week",
+        }
+    }
+
+    /// Get the start of the period containing a Unix timestamp
+    pub fn start(&self, timestamp: u64) -> u64 {
+        match self {
+            PnlPeriod::Day => timestamp - timestamp % DAY_SECS,
+            // The Unix epoch was a Thursday, three days after the Monday weeks start on
+            PnlPeriod::Week => {
+                let shifted = timestamp + 3 * DAY_SECS;
+                shifted - shifted % WEEK_SECS - 3 * DAY_SECS
+            }
+        }
+    }
+}
+
+/// Net balance change of one token across the bot's wallet and executor contract
+#[derive(Debug, Clone)]
+pub struct TokenDelta {
+    /// Token contract
+    pub token: Address,
+
+    /// Net amount received (negative if spent), in the token's smallest unit
+    pub amount: I256,
+
+    /// The amount in whole tokens, if the token's decimals are known
+    pub amount_decimal: Option<f64>,
+
+    /// The amount in USD, if the token could be priced
+    pub usd: Option<f64>,
+}
+
+/// What a settled trade earned, against what it was estimated to earn
+#[derive(Debug, Clone)]
+pub struct TradePnl {
+    /// Transaction hash of the trade
+    pub tx_hash: H256,
+
+    /// Net profit the trade was estimated at (in USD)
+    pub estimated_profit: f64,
+
+    /// Net token balance changes, one per token moved
+    pub deltas: Vec<TokenDelta>,
+
+    /// Sum of the priced token deltas (in USD), or `None` if a delta could not be priced
+    pub realized_usd: Option<f64>,
+
+    /// Unix timestamp when the trade was settled
+    pub settled_at: u64,
+}
+
+impl TradePnl {
+    /// Get the profit the estimate missed (in USD); positive when the trade earned less
+    pub fn slippage_usd(&self) -> Option<f64> {
+        self.realized_usd
+            .map(|realized| self.estimated_profit - realized)
+    }
+}
+
+/// Measures the token deltas of settled trades
+pub struct PnlAccountant {
+    blockchain_client: Arc<RpcClient>,
+    price_oracle: Arc<PriceOracle>,
+
+    /// Addresses whose balance changes are the bot's: the wallet and the executor contract
+    holders: Vec<Address>,
+}
+
+/// Create a new PnL accountant
+pub fn create_accountant(
+    config: &Arc<Config>,
+    blockchain_client: Arc<RpcClient>,
+    price_oracle: Arc<PriceOracle>,
+) -> Result<Arc<PnlAccountant>> {
+    let mut holders = Vec::new();
+    if let Ok(wallet) = validate_and_parse_address(&config.ethereum.wallet_address) {
+        holders.push(wallet);
+    }
+    if let Some(contract_address) = &config.arbitrage.contract.contract_address {
+        holders.push(
+            validate_and_parse_address(contract_address)
+                .context("Invalid arbitrage contract address")?,
+        );
+    }
+
+    Ok(Arc::new(PnlAccountant {
+        blockchain_client,
+        price_oracle,
+        holders,
+    }))
+}
+
+impl PnlAccountant {
+    /// Read the token deltas of a settled trade from its receipt and price them
+    pub async fn settle(&self, tx_hash: H256, estimated_profit: f64) -> Result<TradePnl> {
+        let receipt = self
+            .blockchain_client
+            .get_transaction_receipt(tx_hash)
+            .await?
+            .context("Transaction receipt not found")?;
+
+        let topic = transfer_topic();
+        let transfers = receipt
+            .logs
+            .iter()
+            // ERC721 transfers index the token id instead of carrying an amount
+            .filter(|log| log.topics.len() == 3 && log.topics[0] == topic && log.data.len() == 32)
+            .map(|log| TokenTransfer {
+                token: log.address,
+                from: Address::from(log.topics[1]),
+                to: Address::from(log.topics[2]),
+                amount: U256::from_big_endian(&log.data),
+            });
+
+        let mut net: HashMap<Address, I256> = HashMap::new();
+        for transfer in transfers {
+            let amount = I256::from_raw(transfer.amount);
+            if self.holders.contains(&transfer.to) {
+                *net.entry(transfer.token).or_default() += amount;
+            }
+            if self.holders.contains(&transfer.from) {
+                *net.entry(transfer.token).or_default() -= amount;
+            }
+        }
+
+        let mut deltas = Vec::new();
+        for (token, amount) in net {
+            if amount.is_zero() {
+                continue;
+            }
+            deltas.push(self.price_delta(token, amount).await);
+        }
+        deltas.sort_by_key(|delta| delta.token);
+
+        let realized_usd = deltas.iter().map(|delta| delta.usd).sum::<Option<f64>>();
+        debug!(
+            "Trade {:?} moved {} tokens, realizing {:?} USD",
+            tx_hash,
+            deltas.len(),
+            realized_usd
+        );
+
+        Ok(TradePnl {
+            tx_hash,
+            estimated_profit,
+            deltas,
+            realized_usd,
+            settled_at: current_timestamp(),
+        })
+    }
+
+    /// Price the profit an `ArbitrageExecuted` event reported, in the first token of its path
+    /// (in USD)
+    pub async fn contract_profit_usd(&self, token: Address, profit: U256) -> Option<f64> {
+        self.price_delta(token, I256::from_raw(profit)).await.usd
+    }
+
+    /// Convert a token delta to whole tokens and USD where possible
+    async fn price_delta(&self, token: Address, amount: I256) -> TokenDelta {
+        let amount_decimal = tokens::global().token(token).map(|info| {
+            let (sign, abs) = amount.into_sign_and_abs();
+            let whole = u256_to_decimal(abs, info.decimals);
+            if sign.is_negative() {
+                -whole
+            } else {
+                whole
+            }
+        });
+
+        let usd = match amount_decimal {
+            Some(amount_decimal) => match self.price_oracle.get_price_usd(token).await {
+                Ok(price) => Some(amount_decimal * price),
+                Err(e) => {
+                    warn!(
+                        "Failed to price {} for realized PnL: {}",
+                        tokens::token_label(token),
+                        e
+                    );
+                    None
+                }
+            },
+            None => {
+                warn!(
+                    "Unknown decimals of {:?}; it is left out of realized PnL",
+                    token
+                );
+                None
+            }
+        };
+
+        TokenDelta {
+            token,
+            amount,
+            amount_decimal,
+            usd,
+        }
+    }
+}
@@ -0,0 +1,312 @@
+//! Price API Module
+//!
+//! This module is responsible for fetching token prices from CoinGecko and CoinMarketCap. Each
+//! client prices every configured token in one request, spaces its requests to stay within the
+//! API's rate limit, serves responses from a cache until they expire, and skips a source for a
+//! while after it fails so the oracle falls back to the remaining sources. A client with a daily
+//! request budget spends it through a [`RequestBudget`], and requests beyond the source's
+//! rate limit budget are skipped.
+
+use anyhow::{Context, Result};
+use ethers::types::Address;
+use log::{debug, warn};
+use reqwest::Client;
+use serde::Deserialize;
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
+
+use crate::config::{Config, PriceApiConfig};
+use crate::metrics;
+use crate::price::{ApiSource, RequestBudget};
+use crate::ratelimit::{self, ApiEndpoint};
+use crate::tokens;
+use crate::utils::{current_timestamp, validate_and_parse_address};
+
+/// A configured token, priced by address on CoinGecko and by symbol on CoinMarketCap
+#[derive(Debug, Clone)]
+struct ApiToken {
+    address: Address,
+    symbol: String,
+}
+
+/// Cached response and request bookkeeping of one client
+#[derive(Debug, Default)]
+struct ApiState {
+    prices: HashMap<Address, f64>,
+    fetched_at: Option<Instant>,
+    last_request: Option<Instant>,
+    down_until: Option<Instant>,
+    budget: Option<RequestBudget>,
+}
+
+/// CoinMarketCap `quotes/latest` response
+#[derive(Debug, Deserialize)]
+struct CoinMarketCapResponse {
+    data: HashMap<String, Vec<CoinMarketCapAsset>>,
+}
+
+/// One asset of a CoinMarketCap response
+#[derive(Debug, Deserialize)]
+struct CoinMarketCapAsset {
+    quote: HashMap<String, CoinMarketCapQuote>,
+}
+
+/// One quote currency of a CoinMarketCap asset
+#[derive(Debug, Deserialize)]
+struct CoinMarketCapQuote {
+    price: Option<f64>,
+}
+
+/// Client of one off-chain price API
+pub struct PriceApiClient {
+    source: ApiSource,
+    settings: PriceApiConfig,
+    http_client: Client,
+    tokens: Vec<ApiToken>,
+    cache_ttl: Duration,
+    failure_backoff: Duration,
+    state: Mutex<ApiState>,
+}
+
+impl PriceApiClient {
+    /// Create a client for a source, or `None` if the source is disabled or lacks a required key
+    ///
+    /// `trade_hours` is the number of trades made in each UTC hour, which steers the daily
+    /// request budget if one is configured.
+    pub fn new(
+        config: &Config,
+        source: ApiSource,
+        trade_hours: &[u64; 24],
+    ) -> Result<Option<Self>> {
+        let settings = match source {
+            ApiSource::CoinGecko => &config.price_apis.coingecko,
+            ApiSource::CoinMarketCap => &config.price_apis.coinmarketcap,
+        };
+        if !settings.enabled {
+            return Ok(None);
+        }
+        if source == ApiSource::CoinMarketCap && settings.api_key.is_none() {
+            warn!("CoinMarketCap price source disabled: COINMARKETCAP_API_KEY is not set");
+            return Ok(None);
+        }
+
+        let tokens = config
+            .flash_loan
+            .tokens
+            .iter()
+            .filter_map(|token| {
+                validate_and_parse_address(&token.address)
+                    .ok()
+                    .map(|address| ApiToken {
+                        address,
+                        symbol: token.symbol.clone(),
+                    })
+            })
+            .collect();
+
+        let http_client = Client::builder()
+            .timeout(Duration::from_millis(config.price_apis.request_timeout_ms))
+            .build()?;
+
+        let budget = settings
+            .daily_request_budget
+            .map(|daily_limit| RequestBudget::new(source, daily_limit, trade_hours));
+
+        Ok(Some(Self {
+            source,
+            settings: settings.clone(),
+            http_client,
+            tokens,
+            cache_ttl: Duration::from_millis(config.price_apis.cache_ttl_ms),
+            failure_backoff: Duration::from_secs(config.price_apis.failure_backoff_seconds),
+            state: Mutex::new(ApiState {
+                budget,
+                ..ApiState::default()
+            }),
+        }))
+    }
+
+    /// Get the source this client fetches from
+    pub fn source(&self) -> ApiSource {
+        self.source
+    }
+
+    /// Whether the client can price a token without going over its request budget: its cached
+    /// prices are fresh, or the budget allows a request on behalf of the token
+    pub async fn within_budget(&self, active_token: bool) -> bool {
+        let mut state = self.state.lock().await;
+        if state
+            .fetched_at
+            .is_some_and(|fetched_at| fetched_at.elapsed() < self.cache_ttl)
+        {
+            return true;
+        }
+
+        state
+            .budget
+            .as_mut()
+            .is_none_or(|budget| budget.allows(current_timestamp(), active_token))
+    }
+
+    /// Get the USD price of a token, refetching every token if the cache has expired
+    ///
+    /// No request is made while the source is rate limited, backing off from a failure or out of
+    /// budget for the token (see [`RequestBudget::allows`]); without a cached price younger than
+    /// the TTL the call fails, so the oracle does not mix stale prices into its median.
+    pub async fn price_usd(&self, token: Address, active_token: bool) -> Result<f64> {
+        let mut state = self.state.lock().await;
+        let now = Instant::now();
+
+        let fresh = state
+            .fetched_at
+            .is_some_and(|fetched_at| now.duration_since(fetched_at) < self.cache_ttl);
+        let rate_limited = state.last_request.is_some_and(|last_request| {
+            now.duration_since(last_request)
+                < Duration::from_millis(self.settings.min_request_interval_ms)
+        });
+        let backing_off = state.down_until.is_some_and(|down_until| now < down_until);
+
+        let timestamp = current_timestamp();
+        if !fresh
+            && !rate_limited
+            && !backing_off
+            && state
+                .budget
+                .as_mut()
+                .is_none_or(|budget| budget.allows(timestamp, active_token))
+            && ratelimit::global().try_acquire(match self.source {
+                ApiSource::CoinGecko => ApiEndpoint::CoinGecko,
+                ApiSource::CoinMarketCap => ApiEndpoint::CoinMarketCap,
+            })
+        {
+            state.last_request = Some(now);
+            if let Some(budget) = state.budget.as_mut() {
+                budget.record_request(timestamp);
+            }
+            match self.fetch().await {
+                Ok(prices) => {
+                    debug!("Fetched {} prices from {:?}", prices.len(), self.source);
+                    state.prices = prices;
+                    state.fetched_at = Some(Instant::now());
+                    state.down_until = None;
+                }
+                Err(e) => {
+                    warn!(
+                        "{:?} price source unavailable, skipping it for {}s: {:#}",
+                        self.source,
+                        self.failure_backoff.as_secs(),
+                        e
+                    );
+                    metrics::global().increment_counter("price_api_failures", 1);
+                    state.down_until = Some(Instant::now() + self.failure_backoff);
+                }
+            }
+        }
+
+        let fetched_at = state
+            .fetched_at
+            .filter(|fetched_at| fetched_at.elapsed() < self.cache_ttl)
+            .with_context(|| format!("No fresh prices from {:?}", self.source))?;
+        state.prices.get(&token).copied().with_context(|| {
+            format!(
+                "{:?} has no price for {} (fetched {}s ago)",
+                self.source,
+                tokens::token_label(token),
+                fetched_at.elapsed().as_secs()
+            )
+        })
+    }
+
+    /// Fetch the USD prices of every configured token
+    async fn fetch(&self) -> Result<HashMap<Address, f64>> {
+        match self.source {
+            ApiSource::CoinGecko => self.fetch_coingecko().await,
+            ApiSource::CoinMarketCap => self.fetch_coinmarketcap().await,
+        }
+    }
+
+    /// Fetch prices by contract address from CoinGecko's `simple/token_price` endpoint
+    async fn fetch_coingecko(&self) -> Result<HashMap<Address, f64>> {
+        let addresses = self
+            .tokens
+            .iter()
+            .map(|token| format!("{:?}", token.address))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let mut request = self
+            .http_client
+            .get(format!(
+                "{}/simple/token_price/ethereum",
+                self.settings.base_url.trim_end_matches('/')
+            ))
+            .query(&[
+                ("contract_addresses", addresses.as_str()),
+                ("vs_currencies", "usd"),
+            ]);
+        if let Some(api_key) = &self.settings.api_key {
+            // Paid keys are only accepted by the pro host, demo keys only by the public one
+            let header = if self.settings.base_url.contains("pro-api") {
+                "x-cg-pro-api-key"
+            } else {
+                "x-cg-demo-api-key"
+            };
+            request = request.header(header, api_key);
+        }
+
+        let response: HashMap<String, HashMap<String, f64>> = request
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("Failed to parse CoinGecko response")?;
+
+        Ok(response
+            .into_iter()
+            .filter_map(|(address, quotes)| {
+                let address = validate_and_parse_address(&address).ok()?;
+                Some((address, *quotes.get("usd")?))
+            })
+            .collect())
+    }
+
+    /// Fetch prices by symbol from CoinMarketCap's `quotes/latest` endpoint
+    async fn fetch_coinmarketcap(&self) -> Result<HashMap<Address, f64>> {
+        let symbols = self
+            .tokens
+            .iter()
+            .map(|token| token.symbol.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let response: CoinMarketCapResponse = self
+            .http_client
+            .get(format!(
+                "{}/v2/cryptocurrency/quotes/latest",
+                self.settings.base_url.trim_end_matches('/')
+            ))
+            .query(&[("symbol", symbols.as_str()), ("convert", "USD")])
+            .header(
+                "X-CMC_PRO_API_KEY",
+                self.settings.api_key.as_deref().unwrap_or_default(),
+            )
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("Failed to parse CoinMarketCap response")?;
+
+        // A symbol may match several assets; the first is the highest ranked
+        Ok(self
+            .tokens
+            .iter()
+            .filter_map(|token| {
+                let asset = response.data.get(&token.symbol)?.first()?;
+                Some((token.address, asset.quote.get("USD")?.price?))
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,128 @@
+//! Price API Budget Module
+//!
+//! This module is responsible for spreading a price API's daily request budget over the day.
+//! Each UTC hour gets a share of what is left of the day's budget in proportion to how much the
+//! bot has traded in that hour before, and requests for tokens that are not actively traded may
+//! only use half of an hour's share. Once the day's budget is spent the source is skipped, and
+//! the oracle prices from on-chain sources only until the next UTC day.
+
+use log::{debug, info};
+
+use crate::metrics;
+use crate::price::ApiSource;
+
+/// Seconds in an hour
+const HOUR_SECONDS: u64 = 3_600;
+
+/// Seconds in a day
+const DAY_SECONDS: u64 = 86_400;
+
+/// Request budget of one price API for the current UTC day
+#[derive(Debug)]
+pub struct RequestBudget {
+    source: ApiSource,
+    daily_limit: u64,
+    hour_weights: [f64; 24],
+    day: u64,
+    hour: u64,
+    used_today: u64,
+    used_this_hour: u64,
+    hour_allowance: u64,
+    exhausted: bool,
+}
+
+impl RequestBudget {
+    /// Create a budget of `daily_limit` requests, weighting each UTC hour by its trade count
+    ///
+    /// Every hour gets one extra trade of weight, so hours without history still get a share.
+    pub fn new(source: ApiSource, daily_limit: u64, trade_hours: &[u64; 24]) -> Self {
+        let mut hour_weights = [0.0; 24];
+        for (weight, &trades) in hour_weights.iter_mut().zip(trade_hours) {
+            *weight = trades as f64 + 1.0;
+        }
+
+        Self {
+            source,
+            daily_limit,
+            hour_weights,
+            day: u64::MAX,
+            hour: u64::MAX,
+            used_today: 0,
+            used_this_hour: 0,
+            hour_allowance: 0,
+            exhausted: false,
+        }
+    }
+
+    /// Whether a request may be made at the Unix timestamp `now` on behalf of a token
+    ///
+    /// Actively traded tokens may use the whole share of the current hour; other tokens only
+    /// the first half of it, leaving the rest for prices the bot is trading on.
+    pub fn allows(&mut self, now: u64, active_token: bool) -> bool {
+        self.roll_over(now);
+        if self.exhausted {
+            return false;
+        }
+
+        let allowance = if active_token {
+            self.hour_allowance
+        } else {
+            self.hour_allowance / 2
+        };
+        if self.used_this_hour >= allowance {
+            debug!(
+                "{:?} hourly request share of {} is spent",
+                self.source, self.hour_allowance
+            );
+            metrics::global().increment_counter("price_api_budget_deferrals", 1);
+            return false;
+        }
+
+        true
+    }
+
+    /// Count a request made at `now`
+    pub fn record_request(&mut self, now: u64) {
+        self.roll_over(now);
+        self.used_today += 1;
+        self.used_this_hour += 1;
+
+        if self.used_today >= self.daily_limit && !self.exhausted {
+            self.exhausted = true;
+            info!(
+                "{:?} daily budget of {} requests spent; pricing from on-chain sources until \
+                 00:00 UTC",
+                self.source, self.daily_limit
+            );
+            metrics::global().increment_counter("price_api_budget_exhausted", 1);
+        }
+    }
+
+    /// Start a new day or hour when `now` has moved past the current one
+    fn roll_over(&mut self, now: u64) {
+        let day = now / DAY_SECONDS;
+        if day != self.day {
+            self.day = day;
+            self.used_today = 0;
+            self.exhausted = self.daily_limit == 0;
+            self.hour = u64::MAX;
+        }
+
+        let hour = (now % DAY_SECONDS) / HOUR_SECONDS;
+        if hour != self.hour {
+            self.hour = hour;
+            self.used_this_hour = 0;
+
+            // Unspent requests of earlier hours carry over to the rest of the day
+            let remaining = self.daily_limit.saturating_sub(self.used_today);
+            let remaining_weight: f64 = self.hour_weights[hour as usize..].iter().sum();
+            self.hour_allowance = (remaining as f64 * self.hour_weights[hour as usize]
+                / remaining_weight)
+                .ceil() as u64;
+            debug!(
+                "{:?} may make {} of its {} remaining requests in hour {} UTC",
+                self.source, self.hour_allowance, remaining, hour
+            );
+        }
+    }
+}
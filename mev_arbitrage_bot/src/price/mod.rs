@@ -1,19 +1,33 @@
 //! Price Oracle Module
 //!
-//! This module is responsible for maintaining price data from various sources.
+//! This module is responsible for maintaining price data from various sources. Tokens the bot
+//! evaluates opportunities on count as actively traded, and get priority for price API requests
+//! that are limited by a daily budget.
+
+mod api;
+mod budget;
+
+pub use api::PriceApiClient;
+pub use budget::RequestBudget;
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use ethers::providers::Provider;
 use ethers::types::{Address, U256};
 use log::{debug, error, info, warn};
-use std::collections::HashMap;
-use std::sync::Arc;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
 use tokio::sync::RwLock;
 use tokio::time::{Duration, Instant};
 
 use crate::config::{Config, TokenConfig};
-use crate::utils::validate_and_parse_address;
+use crate::dex::{BlockTag, DexInterface, DexInterfaces, DexType};
+use crate::storage::Storage;
+use crate::tokens;
+use crate::utils::{current_timestamp, u256_to_decimal, validate_and_parse_address};
+
+/// How long a token counts as actively traded after an opportunity on it (in seconds)
+const ACTIVE_TOKEN_WINDOW_SECONDS: u64 = 24 * 3_600;
 
 /// Price source type
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -70,6 +84,61 @@ pub struct TokenPrice {
     pub last_update: Instant,
 }
 
+/// Oracle price of a token at one moment
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PinnedPrice {
+    /// Price in USD
+    pub price_usd: f64,
+
+    /// Price in ETH
+    pub price_eth: f64,
+}
+
+/// Oracle prices read once when an opportunity is evaluated
+///
+/// Every later profit check for the opportunity uses these values rather than re-reading the
+/// oracle, which may have refreshed in between and flip the decision.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PinnedPrices {
+    prices: BTreeMap<Address, PinnedPrice>,
+}
+
+impl PinnedPrices {
+    /// Get the pinned USD price of a token
+    pub fn usd(&self, token: Address) -> Option<f64> {
+        self.prices.get(&token).map(|price| price.price_usd)
+    }
+
+    /// Get the pinned ETH price of a token
+    pub fn eth(&self, token: Address) -> Option<f64> {
+        self.prices.get(&token).map(|price| price.price_eth)
+    }
+
+    /// Pin the prices of a token
+    pub fn pin(&mut self, token: Address, price_usd: f64, price_eth: f64) {
+        self.prices.insert(
+            token,
+            PinnedPrice {
+                price_usd,
+                price_eth,
+            },
+        );
+    }
+
+    /// Get the ETH price in USD implied by the first token pinned with both prices
+    pub fn eth_usd(&self) -> Option<f64> {
+        self.prices
+            .values()
+            .find(|price| price.price_eth > 0.0)
+            .map(|price| price.price_usd / price.price_eth)
+    }
+
+    /// Serialize the pinned prices as JSON, keyed by token address
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.prices).unwrap_or_default()
+    }
+}
+
 /// Interface for price oracles
 #[async_trait]
 pub trait PriceOracleInterface: Send + Sync {
@@ -82,6 +151,11 @@ pub trait PriceOracleInterface: Send + Sync {
     /// Get the price of a token in terms of another token
     async fn get_price_in_token(&self, base_token: Address, quote_token: Address) -> Result<f64>;
 
+    /// Read the current USD and ETH prices of tokens in one pass, to pin into an opportunity
+    ///
+    /// The tokens count as actively traded from then on.
+    async fn pin_prices(&self, tokens: &[Address]) -> Result<PinnedPrices>;
+
     /// Update all prices
     async fn update_prices(&self) -> Result<()>;
 
@@ -95,26 +169,61 @@ pub trait PriceOracleInterface: Send + Sync {
 /// Implementation of the price oracle
 pub struct PriceOracle {
     config: Arc<Config>,
-    blockchain_client: Arc<Provider<ethers::providers::Http>>,
     prices: RwLock<HashMap<Address, TokenPrice>>,
     sources: RwLock<Vec<PriceSource>>,
+    dex_interfaces: Arc<DexInterfaces>,
+    api_clients: HashMap<ApiSource, PriceApiClient>,
+    active_tokens: Mutex<HashMap<Address, u64>>,
     last_update: RwLock<Instant>,
 }
 
 /// Create a new price oracle
+///
+/// The trade history in storage, if any, seeds which tokens and UTC hours are actively traded.
 pub async fn create_oracle(
     config: &Arc<Config>,
-    blockchain_client: Arc<Provider<ethers::providers::Http>>,
+    dex_interfaces: Arc<DexInterfaces>,
+    storage: Option<&Arc<Storage>>,
 ) -> Result<Arc<PriceOracle>> {
+    let mut trade_hours = [0; 24];
+    let mut active_tokens = HashMap::new();
+    if let Some(storage) = storage {
+        trade_hours = storage.trade_hours()?;
+        for record in storage.path_activity()? {
+            for &token in &record.token_path {
+                let last_trade = active_tokens.entry(token).or_insert(0);
+                *last_trade = record.last_trade.max(*last_trade);
+            }
+        }
+    }
+
+    // Query every enabled price API
+    let mut api_clients = HashMap::new();
+    for source in [ApiSource::CoinGecko, ApiSource::CoinMarketCap] {
+        if let Some(client) = PriceApiClient::new(config, source, &trade_hours)? {
+            info!("Using {:?} as a price source", source);
+            api_clients.insert(source, client);
+        }
+    }
+
+    let mut sources = vec![
+        PriceSource::Dex(DexSource::UniswapV2),
+        PriceSource::Dex(DexSource::Sushiswap),
+    ];
+    sources.extend(
+        [ApiSource::CoinGecko, ApiSource::CoinMarketCap]
+            .into_iter()
+            .filter(|source| api_clients.contains_key(source))
+            .map(PriceSource::Api),
+    );
+
     let oracle = PriceOracle {
         config: config.clone(),
-        blockchain_client,
         prices: RwLock::new(HashMap::new()),
-        sources: RwLock::new(vec![
-            PriceSource::Dex(DexSource::UniswapV2),
-            PriceSource::Dex(DexSource::Sushiswap),
-            PriceSource::Api(ApiSource::CoinGecko),
-        ]),
+        sources: RwLock::new(sources),
+        dex_interfaces,
+        api_clients,
+        active_tokens: Mutex::new(active_tokens),
         last_update: RwLock::new(Instant::now() - Duration::from_secs(3600)), // Force an update on first call
     };
 
@@ -181,37 +290,139 @@ impl PriceOracle {
     }
 
     /// Get price from a DEX
+    ///
+    /// The token is priced in WETH and WETH in USDC, both at the DEX's spot price, taking
+    /// USDC as one dollar.
     async fn get_price_from_dex(&self, token: Address, dex_source: DexSource) -> Result<f64> {
-        // This is a placeholder implementation
-        // In a real implementation, we would:
-        // 1. Get the DEX contract
-        // 2. Get the token pair
-        // 3. Get the reserves
-        // 4. Calculate the price
-
-        // For now, just return a dummy price
-        match dex_source {
-            DexSource::UniswapV2 => Ok(1000.0), // Assume 1 ETH = $1000
-            DexSource::Sushiswap => Ok(1010.0), // Slight variation
-            DexSource::Curve => Ok(990.0),      // Slight variation
+        let dex_type = match dex_source {
+            DexSource::UniswapV2 => DexType::UniswapV2,
+            DexSource::Sushiswap => DexType::Sushiswap,
+            DexSource::Curve => DexType::Curve,
+        };
+        let interface = self
+            .dex_interfaces
+            .get_interface(dex_type)
+            .with_context(|| format!("{:?} is not enabled", dex_type))?;
+
+        let weth = self.configured_token("WETH")?;
+        let usdc = self.configured_token("USDC")?;
+
+        let eth_price_usd = self.get_spot_price(&interface, weth, usdc).await?;
+        let price_weth = if token == weth || token == Address::from_low_u64_be(0) {
+            1.0
+        } else {
+            self.get_spot_price(&interface, token, weth).await?
+        };
+
+        Ok(price_weth * eth_price_usd)
+    }
+
+    /// Get the spot price of a base token in units of a quote token on one DEX
+    ///
+    /// Constant-product prices come from the pool reserves; Curve prices come from a quote of
+    /// one whole base token, which stable pools fill close to their spot price.
+    async fn get_spot_price(
+        &self,
+        interface: &Arc<dyn DexInterface>,
+        base_token: Address,
+        quote_token: Address,
+    ) -> Result<f64> {
+        let base_decimals = self.token_decimals(base_token)?;
+        let quote_decimals = self.token_decimals(quote_token)?;
+
+        if interface.dex_type() == DexType::Curve {
+            let quote = interface
+                .get_quote(
+                    base_token,
+                    quote_token,
+                    U256::exp10(base_decimals as usize),
+                    BlockTag::Latest,
+                )
+                .await?;
+            return Ok(u256_to_decimal(quote.output_amount, quote_decimals));
+        }
+
+        let pool = interface
+            .get_pool(base_token, quote_token)
+            .await?
+            .with_context(|| {
+                format!(
+                    "No {} pool for {}",
+                    interface.name(),
+                    tokens::path_label(&[base_token, quote_token])
+                )
+            })?;
+        let reserve = |token: Address| {
+            pool.tokens
+                .iter()
+                .position(|&pool_token| pool_token == token)
+                .and_then(|index| pool.reserves.get(index))
+                .copied()
+                .with_context(|| {
+                    format!("Pool {} has no reserve", tokens::pool_label(pool.address))
+                })
+        };
+
+        let base_reserve = u256_to_decimal(reserve(base_token)?, base_decimals);
+        let quote_reserve = u256_to_decimal(reserve(quote_token)?, quote_decimals);
+        if base_reserve <= 0.0 {
+            anyhow::bail!("Pool {} has no liquidity", tokens::pool_label(pool.address));
         }
+
+        Ok(quote_reserve / base_reserve)
+    }
+
+    /// Get the address of a configured token by symbol
+    fn configured_token(&self, symbol: &str) -> Result<Address> {
+        self.config
+            .flash_loan
+            .tokens
+            .iter()
+            .find(|token| token.symbol == symbol)
+            .and_then(|token| validate_and_parse_address(&token.address).ok())
+            .with_context(|| format!("{} is not a configured token", symbol))
+    }
+
+    /// Get the decimals of a configured token
+    fn token_decimals(&self, token: Address) -> Result<u8> {
+        self.config
+            .flash_loan
+            .tokens
+            .iter()
+            .find(|config| validate_and_parse_address(&config.address).ok() == Some(token))
+            .map(|config| config.decimals)
+            .with_context(|| format!("Decimals unknown for token {}", tokens::token_label(token)))
     }
 
     /// Get price from an API
     async fn get_price_from_api(&self, token: Address, api_source: ApiSource) -> Result<f64> {
-        // This is a placeholder implementation
-        // In a real implementation, we would:
-        // 1. Make an HTTP request to the API
-        // 2. Parse the response
-        // 3. Extract the price
-
-        // For now, just return a dummy price
-        match api_source {
-            ApiSource::CoinGecko => Ok(1005.0),    // Assume 1 ETH = $1005
-            ApiSource::CoinMarketCap => Ok(995.0), // Slight variation
+        self.api_clients
+            .get(&api_source)
+            .with_context(|| format!("{:?} price source is not configured", api_source))?
+            .price_usd(token, self.is_active(token))
+            .await
+    }
+
+    /// Whether a price source can be asked for a token's price without going over its request
+    /// budget (sources without a budget always can)
+    async fn within_budget(&self, token: Address, source: PriceSource) -> bool {
+        match source {
+            PriceSource::Api(api_source) => match self.api_clients.get(&api_source) {
+                Some(client) => client.within_budget(self.is_active(token)).await,
+                None => true,
+            },
+            PriceSource::Dex(_) => true,
         }
     }
 
+    /// Whether an opportunity on a token was seen in the active token window
+    fn is_active(&self, token: Address) -> bool {
+        let active_tokens = self.active_tokens.lock().unwrap_or_else(|e| e.into_inner());
+        active_tokens.get(&token).is_some_and(|&last_seen| {
+            current_timestamp().saturating_sub(last_seen) < ACTIVE_TOKEN_WINDOW_SECONDS
+        })
+    }
+
     /// Calculate the median price from multiple sources
     fn calculate_median_price(&self, prices: &[f64]) -> Option<f64> {
         if prices.is_empty() {
@@ -247,9 +458,10 @@ impl PriceOracleInterface for PriceOracle {
 
         // Get the price from the cache
         let prices = self.prices.read().await;
-        let token_price = prices
-            .get(&token)
-            .context(format!("Price not found for token: {:?}", token))?;
+        let token_price = prices.get(&token).context(format!(
+            "Price not found for token: {}",
+            tokens::token_label(token)
+        ))?;
 
         Ok(token_price.price_usd)
     }
@@ -263,9 +475,10 @@ impl PriceOracleInterface for PriceOracle {
 
         // Get the price from the cache
         let prices = self.prices.read().await;
-        let token_price = prices
-            .get(&token)
-            .context(format!("Price not found for token: {:?}", token))?;
+        let token_price = prices.get(&token).context(format!(
+            "Price not found for token: {}",
+            tokens::token_label(token)
+        ))?;
 
         Ok(token_price.price_eth)
     }
@@ -283,6 +496,37 @@ impl PriceOracleInterface for PriceOracle {
         Ok(base_price_usd / quote_price_usd)
     }
 
+    /// Read the current USD and ETH prices of tokens in one pass, to pin into an opportunity
+    ///
+    /// The tokens count as actively traded from then on.
+    async fn pin_prices(&self, tokens: &[Address]) -> Result<PinnedPrices> {
+        {
+            let now = current_timestamp();
+            let mut active_tokens = self.active_tokens.lock().unwrap_or_else(|e| e.into_inner());
+            for &token in tokens {
+                active_tokens.insert(token, now);
+            }
+        }
+
+        // Refresh at most once, so every token is read from the same update
+        let last_update = *self.last_update.read().await;
+        if last_update.elapsed() > Duration::from_secs(60) {
+            self.update_prices().await?;
+        }
+
+        let prices = self.prices.read().await;
+        let mut pinned = PinnedPrices::default();
+        for &token in tokens {
+            let token_price = prices.get(&token).context(format!(
+                "Price not found for token: {}",
+                tokens::token_label(token)
+            ))?;
+            pinned.pin(token, token_price.price_usd, token_price.price_eth);
+        }
+
+        Ok(pinned)
+    }
+
     async fn update_prices(&self) -> Result<()> {
         // Get the list of tokens
         let tokens = {
@@ -301,14 +545,26 @@ impl PriceOracleInterface for PriceOracle {
             // Get prices from all sources
             let mut token_prices = HashMap::new();
             for source in &sources {
+                // A source out of budget is left out, and the others price the token
+                if !self.within_budget(token, *source).await {
+                    debug!(
+                        "Skipping {:?} for token {}: request budget spent",
+                        source,
+                        tokens::token_label(token)
+                    );
+                    continue;
+                }
+
                 match self.get_price_from_source(token, *source).await {
                     Ok(price) => {
                         token_prices.insert(*source, price);
                     }
                     Err(e) => {
                         warn!(
-                            "Failed to get price for token {:?} from source {:?}: {}",
-                            token, source, e
+                            "Failed to get price for token {} from source {:?}: {}",
+                            tokens::token_label(token),
+                            source,
+                            e
                         );
                     }
                 }
@@ -387,8 +643,8 @@ impl PriceOracleInterface for PriceOracle {
                 }
             } else {
                 warn!(
-                    "Failed to calculate median price for token {:?}: no valid prices",
-                    token
+                    "Failed to calculate median price for token {}: no valid prices",
+                    tokens::token_label(token)
                 );
             }
         }
@@ -0,0 +1,254 @@
+//! Rate Limit Module
+//!
+//! This module is responsible for keeping the bot's requests to rate limited endpoints within
+//! their budgets, so a busy scan loop cannot get an API key temporarily banned. Each endpoint
+//! with a configured budget gets a token bucket refilled at its request rate and holding up to
+//! its burst. RPC and Alchemy requests wait for a token; price API requests are skipped without
+//! one, and the oracle serves their cached prices or its other sources meanwhile.
+
+use anyhow::Result;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::config::{Config, ConfigSection};
+use crate::metrics;
+
+/// A rate limited endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ApiEndpoint {
+    /// The primary RPC endpoint and its fallbacks
+    Rpc,
+
+    /// The read RPC endpoint
+    ReadRpc,
+
+    /// Alchemy's enhanced APIs
+    Alchemy,
+
+    /// The CoinGecko price API
+    CoinGecko,
+
+    /// The CoinMarketCap price API
+    CoinMarketCap,
+
+    /// The Blocknative gas price API
+    Blocknative,
+}
+
+/// Request budget of one endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateBudget {
+    /// Requests allowed per second on average
+    pub requests_per_second: f64,
+
+    /// Requests that may be made at once after a quiet period (defaults to one second's worth)
+    #[serde(default)]
+    pub burst: Option<u32>,
+}
+
+/// Rate limit configuration (`[rate_limits]`)
+///
+/// Endpoints without a budget are not limited.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// Budget shared by the primary and fallback RPC endpoints of every chain
+    pub rpc: Option<RateBudget>,
+
+    /// Budget of the read RPC endpoint
+    pub read_rpc: Option<RateBudget>,
+
+    /// Budget of Alchemy's enhanced APIs
+    pub alchemy: Option<RateBudget>,
+
+    /// Budget of the CoinGecko price API
+    pub coingecko: Option<RateBudget>,
+
+    /// Budget of the CoinMarketCap price API
+    pub coinmarketcap: Option<RateBudget>,
+
+    /// Budget of the Blocknative gas price API
+    pub blocknative: Option<RateBudget>,
+}
+
+impl RateLimitConfig {
+    /// Get the configured budget of every endpoint that has one
+    fn budgets(&self) -> impl Iterator<Item = (ApiEndpoint, &RateBudget)> {
+        [
+            (ApiEndpoint::Rpc, &self.rpc),
+            (ApiEndpoint::ReadRpc, &self.read_rpc),
+            (ApiEndpoint::Alchemy, &self.alchemy),
+            (ApiEndpoint::CoinGecko, &self.coingecko),
+            (ApiEndpoint::CoinMarketCap, &self.coinmarketcap),
+            (ApiEndpoint::Blocknative, &self.blocknative),
+        ]
+        .into_iter()
+        .filter_map(|(endpoint, budget)| Some((endpoint, budget.as_ref()?)))
+    }
+}
+
+impl ConfigSection for RateLimitConfig {
+    const NAME: &'static str = "rate_limits";
+
+    fn validate(&self) -> Result<()> {
+        for (endpoint, budget) in self.budgets() {
+            if !budget.requests_per_second.is_finite() || budget.requests_per_second <= 0.0 {
+                anyhow::bail!(
+                    "Rate limit requests_per_second of {:?} must be greater than zero",
+                    endpoint
+                );
+            }
+            if budget.burst == Some(0) {
+                anyhow::bail!(
+                    "Rate limit burst of {:?} must be greater than zero",
+                    endpoint
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Tokens of a bucket and when they were last refilled
+#[derive(Debug)]
+struct BucketState {
+    /// Negative while requests are waiting for tokens not yet refilled
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Token bucket of one endpoint
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    state: Mutex<BucketState>,
+}
+
+impl TokenBucket {
+    fn new(budget: &RateBudget) -> Self {
+        let capacity = budget
+            .burst
+            .map_or(budget.requests_per_second.ceil(), f64::from)
+            .max(1.0);
+
+        Self {
+            rate: budget.requests_per_second,
+            capacity,
+            state: Mutex::new(BucketState {
+                tokens: capacity,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Refill the tokens accrued since the last refill
+    fn refill(&self, state: &mut BucketState) {
+        let now = Instant::now();
+        let accrued = now.duration_since(state.refilled_at).as_secs_f64() * self.rate;
+        state.tokens = (state.tokens + accrued).min(self.capacity);
+        state.refilled_at = now;
+    }
+
+    /// Take a token, returning how long to wait until it is refilled
+    ///
+    /// The token is taken even if it is not there yet, so waiting requests are served in the
+    /// order they arrived.
+    fn reserve(&self) -> Duration {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.refill(&mut state);
+        state.tokens -= 1.0;
+
+        if state.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-state.tokens / self.rate)
+        }
+    }
+
+    /// Take a token if one is there
+    fn try_take(&self) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.refill(&mut state);
+        if state.tokens < 1.0 {
+            return false;
+        }
+
+        state.tokens -= 1.0;
+        true
+    }
+}
+
+/// Token buckets of the endpoints with a budget
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    buckets: RwLock<HashMap<ApiEndpoint, Arc<TokenBucket>>>,
+}
+
+impl RateLimiter {
+    /// Apply the budgets of the configuration
+    pub fn configure(&self, config: &Config) -> Result<()> {
+        let limits: RateLimitConfig = config.section()?;
+        let buckets: HashMap<_, _> = limits
+            .budgets()
+            .map(|(endpoint, budget)| (endpoint, Arc::new(TokenBucket::new(budget))))
+            .collect();
+        if !buckets.is_empty() {
+            info!("Rate limits: {:?}", limits);
+        }
+        *self.buckets.write().unwrap_or_else(|e| e.into_inner()) = buckets;
+
+        Ok(())
+    }
+
+    fn bucket(&self, endpoint: ApiEndpoint) -> Option<Arc<TokenBucket>> {
+        self.buckets
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&endpoint)
+            .cloned()
+    }
+
+    /// Wait until a request to an endpoint is within its budget
+    pub async fn acquire(&self, endpoint: ApiEndpoint) {
+        let Some(bucket) = self.bucket(endpoint) else {
+            return;
+        };
+
+        let wait = bucket.reserve();
+        if !wait.is_zero() {
+            debug!(
+                "{:?} request held back {}ms by its rate limit",
+                endpoint,
+                wait.as_millis()
+            );
+            metrics::global().increment_counter("rate_limit_waits", 1);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Whether a request to an endpoint may be made now, counting it against the budget if so
+    pub fn try_acquire(&self, endpoint: ApiEndpoint) -> bool {
+        let Some(bucket) = self.bucket(endpoint) else {
+            return true;
+        };
+
+        let allowed = bucket.try_take();
+        if !allowed {
+            debug!("{:?} request skipped by its rate limit", endpoint);
+            metrics::global().increment_counter("rate_limit_skips", 1);
+        }
+
+        allowed
+    }
+}
+
+/// Get the process-wide rate limiter
+pub fn global() -> &'static RateLimiter {
+    static LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+    LIMITER.get_or_init(RateLimiter::default)
+}
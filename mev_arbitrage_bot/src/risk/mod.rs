@@ -0,0 +1,335 @@
+//! Risk Module
+//!
+//! This module is responsible for the global limits every submission must stay within, across
+//! all chains: the flash loan notional of a trade, the gas spent per hour, the run of failed
+//! transactions tolerated before scanning is paused, and the realized loss per UTC day. The
+//! strategy engine skips opportunities outside the limits and the executor refuses to submit
+//! transactions outside them.
+//!
+//! After `max_consecutive_failures` failed transactions scanning is paused as through the admin
+//! API, and stays paused until it is resumed there. The daily loss is seeded from the trade
+//! history at startup, so a restart does not reset it.
+
+use anyhow::Result;
+use ethers::types::{Address, U256};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::alerts::AlertManager;
+use crate::config::{AlertSeverity, Config, ConfigSection};
+use crate::metrics;
+use crate::pnl::PnlPeriod;
+use crate::price::PinnedPrices;
+use crate::runtime;
+use crate::scanner::ArbitrageOpportunity;
+use crate::storage::Storage;
+use crate::tokens;
+use crate::transaction::ArbitrageTransaction;
+use crate::utils::{current_timestamp, u256_to_decimal};
+
+/// Window the gas spend limit applies to (in seconds)
+const GAS_WINDOW_SECS: u64 = 3_600;
+
+/// Alert source of the risk manager
+const ALERT_SOURCE: &str = "risk";
+
+/// Risk limit configuration (`[risk]`)
+///
+/// Each limit is off unless set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RiskConfig {
+    /// Largest flash loan a trade may take (in USD)
+    pub max_loan_usd: Option<f64>,
+
+    /// Most gas spent, tips included, in any hour (in USD)
+    pub max_gas_usd_per_hour: Option<f64>,
+
+    /// Failed transactions in a row after which scanning is paused
+    pub max_consecutive_failures: Option<u32>,
+
+    /// Realized loss in a UTC day after which nothing is submitted until the next day (in USD)
+    pub max_daily_loss_usd: Option<f64>,
+}
+
+impl ConfigSection for RiskConfig {
+    const NAME: &'static str = "risk";
+
+    fn validate(&self) -> Result<()> {
+        for (name, limit) in [
+            ("max_loan_usd", self.max_loan_usd),
+            ("max_gas_usd_per_hour", self.max_gas_usd_per_hour),
+            ("max_daily_loss_usd", self.max_daily_loss_usd),
+        ] {
+            if limit.is_some_and(|limit| !limit.is_finite() || limit <= 0.0) {
+                anyhow::bail!("Risk {} must be greater than zero", name);
+            }
+        }
+        if self.max_consecutive_failures == Some(0) {
+            anyhow::bail!("Risk max_consecutive_failures must be greater than zero");
+        }
+
+        Ok(())
+    }
+}
+
+/// Spend and loss the limits are checked against
+#[derive(Debug, Default)]
+struct RiskState {
+    /// Gas spent within the last hour (in USD), as (Unix timestamp, amount), oldest first
+    gas_spend: VecDeque<(u64, f64)>,
+
+    /// Failed transactions since the last successful one
+    consecutive_failures: u32,
+
+    /// Start of the UTC day `daily_pnl` covers
+    day_start: u64,
+
+    /// Realized PnL of the day (in USD)
+    daily_pnl: f64,
+}
+
+impl RiskState {
+    /// Drop the gas spend older than an hour and start a new day once the last one is over
+    fn roll(&mut self, now: u64) {
+        while self
+            .gas_spend
+            .front()
+            .is_some_and(|&(timestamp, _)| timestamp + GAS_WINDOW_SECS <= now)
+        {
+            self.gas_spend.pop_front();
+        }
+
+        let day_start = PnlPeriod::Day.start(now);
+        if day_start != self.day_start {
+            self.day_start = day_start;
+            self.daily_pnl = 0.0;
+        }
+    }
+
+    /// Gas spent within the last hour (in USD)
+    fn hourly_gas(&self) -> f64 {
+        self.gas_spend.iter().map(|&(_, amount)| amount).sum()
+    }
+}
+
+/// Enforces the risk limits of every chain
+#[derive(Default)]
+pub struct RiskManager {
+    limits: Mutex<RiskConfig>,
+    alert_manager: Mutex<Option<Arc<AlertManager>>>,
+    state: Mutex<RiskState>,
+}
+
+impl RiskManager {
+    /// Apply the limits of the configuration, alerting through `alert_manager`
+    pub fn configure(&self, config: &Config, alert_manager: Arc<AlertManager>) -> Result<()> {
+        let limits: RiskConfig = config.section()?;
+        info!("Risk limits: {:?}", limits);
+        *self.limits.lock().unwrap_or_else(|e| e.into_inner()) = limits;
+        *self.alert_manager.lock().unwrap_or_else(|e| e.into_inner()) = Some(alert_manager);
+
+        Ok(())
+    }
+
+    /// Add the realized PnL a chain's trade history already holds for the current day
+    pub fn seed_daily_pnl(&self, storage: &Storage) -> Result<()> {
+        let now = current_timestamp();
+        let daily_pnl = storage.realized_pnl_since(PnlPeriod::Day.start(now))?;
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.roll(now);
+        state.daily_pnl += daily_pnl;
+        metrics::global().set_gauge("risk_daily_pnl_usd", state.daily_pnl);
+
+        Ok(())
+    }
+
+    /// Why nothing may be submitted, if nothing may
+    pub fn execution_blocked(&self) -> Option<String> {
+        let limits = self
+            .limits
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.roll(current_timestamp());
+
+        if let Some(max_loss) = limits.max_daily_loss_usd {
+            if state.daily_pnl <= -max_loss {
+                return Some(format!(
+                    "daily realized loss ${:.2} reached the limit of ${:.2}",
+                    -state.daily_pnl, max_loss
+                ));
+            }
+        }
+        if let Some(max_gas) = limits.max_gas_usd_per_hour {
+            let hourly_gas = state.hourly_gas();
+            if hourly_gas >= max_gas {
+                return Some(format!(
+                    "gas spent in the last hour ${:.2} reached the limit of ${:.2}",
+                    hourly_gas, max_gas
+                ));
+            }
+        }
+
+        None
+    }
+
+    /// Why an opportunity may not be executed, if it may not
+    pub fn check_opportunity(&self, opportunity: &ArbitrageOpportunity) -> Option<String> {
+        let token = opportunity.token_path.first().copied()?;
+        let loan_usd = loan_usd(
+            token,
+            opportunity.flash_loan_amount(),
+            &opportunity.pinned_prices,
+        );
+
+        self.check(loan_usd, Some(opportunity.estimated_gas_cost))
+    }
+
+    /// Why a transaction may not be submitted, if it may not
+    pub fn check_transaction(&self, tx: &ArbitrageTransaction) -> Option<String> {
+        let token = tx.token_path.first().copied()?;
+        let loan_usd = loan_usd(token, tx.loan_amount, &tx.pinned_prices);
+        let gas_usd = tx
+            .pinned_prices
+            .eth_usd()
+            .map(|eth_price_usd| u256_to_decimal(tx.estimated_cost, 18) * eth_price_usd);
+
+        self.check(loan_usd, gas_usd)
+    }
+
+    /// Check a trade's flash loan and gas cost (in USD) against the limits
+    fn check(&self, loan_usd: Option<f64>, gas_usd: Option<f64>) -> Option<String> {
+        if let Some(reason) = self.execution_blocked() {
+            return Some(reason);
+        }
+
+        let limits = self
+            .limits
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        if let Some(max_loan) = limits.max_loan_usd {
+            match loan_usd {
+                Some(loan_usd) if loan_usd > max_loan => {
+                    return Some(format!(
+                        "flash loan of ${:.2} exceeds the limit of ${:.2}",
+                        loan_usd, max_loan
+                    ))
+                }
+                Some(_) => {}
+                None => return Some("flash loan cannot be priced".to_string()),
+            }
+        }
+        if let Some(max_gas) = limits.max_gas_usd_per_hour {
+            let hourly_gas = self
+                .state
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .hourly_gas();
+            match gas_usd {
+                Some(gas_usd) if hourly_gas + gas_usd > max_gas => {
+                    return Some(format!(
+                        "gas of ${:.2} would exceed the hourly limit of ${:.2} (${:.2} spent)",
+                        gas_usd, max_gas, hourly_gas
+                    ))
+                }
+                Some(_) => {}
+                None => return Some("gas cost cannot be priced".to_string()),
+            }
+        }
+
+        None
+    }
+
+    /// Record the outcome of a settled transaction: its gas and tip (in USD), whether it
+    /// succeeded, and its realized PnL (in USD)
+    pub fn record_outcome(&self, success: bool, gas_usd: Option<f64>, realized_pnl: Option<f64>) {
+        {
+            let now = current_timestamp();
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.roll(now);
+            if let Some(gas_usd) = gas_usd {
+                state.gas_spend.push_back((now, gas_usd));
+            }
+            if let Some(realized_pnl) = realized_pnl {
+                state.daily_pnl += realized_pnl;
+            }
+            metrics::global().set_gauge("risk_hourly_gas_usd", state.hourly_gas());
+            metrics::global().set_gauge("risk_daily_pnl_usd", state.daily_pnl);
+        }
+
+        if success {
+            self.state
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .consecutive_failures = 0;
+        } else {
+            self.record_failure("reverted");
+        }
+    }
+
+    /// Record a failed transaction, pausing scanning after too many in a row
+    pub fn record_failure(&self, reason: &str) {
+        let Some(max_failures) = self
+            .limits
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .max_consecutive_failures
+        else {
+            return;
+        };
+
+        let failures = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.consecutive_failures += 1;
+            let failures = state.consecutive_failures;
+            // Count afresh once scanning is resumed
+            if failures >= max_failures {
+                state.consecutive_failures = 0;
+            }
+            failures
+        };
+        metrics::global().set_gauge("risk_consecutive_failures", f64::from(failures));
+        if failures < max_failures {
+            warn!(
+                "Transaction failed ({}), {} of {} in a row",
+                reason, failures, max_failures
+            );
+            return;
+        }
+
+        runtime::controls().set_scanning_paused(true);
+        let message = format!(
+            "Scanning paused after {} failed transactions in a row (last: {}); resume it \
+             through the admin API",
+            failures, reason
+        );
+        match &*self.alert_manager.lock().unwrap_or_else(|e| e.into_inner()) {
+            Some(alert_manager) => {
+                alert_manager.raise(AlertSeverity::Critical, ALERT_SOURCE, message)
+            }
+            None => warn!("{}", message),
+        }
+    }
+}
+
+/// Price a flash loan of `amount` of `token` (in USD), from the trade's pinned prices
+fn loan_usd(token: Address, amount: U256, pinned_prices: &PinnedPrices) -> Option<f64> {
+    let decimals = tokens::global()
+        .token(token)
+        .map(|info| info.decimals)
+        .unwrap_or(18);
+
+    Some(u256_to_decimal(amount, decimals) * pinned_prices.usd(token)?)
+}
+
+/// Get the process-wide risk manager
+pub fn global() -> &'static RiskManager {
+    static MANAGER: OnceLock<RiskManager> = OnceLock::new();
+    MANAGER.get_or_init(RiskManager::default)
+}
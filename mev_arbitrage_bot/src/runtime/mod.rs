@@ -0,0 +1,515 @@
+//! Runtime Module
+//!
+//! This module is responsible for the lifecycle of the running bot. The `BotSupervisor` owns
+//! the subsystems of every chain and the long-running tasks driving them: it restarts a task
+//! that panics, reports a `Health` snapshot, and shuts down in order. Shutdown stops the tasks
+//! from taking new opportunities and the event listeners from feeding them, waits for in-flight
+//! transactions to settle, then closes the MEV-Share event streams and flushes storage.
+//!
+//! Tasks stop through cancellation tokens rather than being aborted: the shutdown token
+//! interrupts the awaits of the scan loops, listeners and event streams, and the settlement
+//! token those of the trade trackers and bundle followers once in-flight work is abandoned.
+//! A cancelled task returns from its next await point, so no lock or storage write is left
+//! half done.
+//!
+//! `controls()` holds the operator's runtime overrides, set through the admin API: whether
+//! scanning is paused, and a minimum profit threshold replacing the configured one.
+
+use log::{error, info, warn};
+use serde::Serialize;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tokio::task::{AbortHandle, JoinHandle};
+use tokio_util::sync::CancellationToken;
+
+use crate::alerts::AlertManager;
+use crate::blockchain::BlockchainEventListener;
+use crate::config::{AlertSeverity, Config};
+use crate::metrics;
+use crate::mev_share::MevShareClient;
+use crate::storage::Storage;
+
+/// Default time allowed for a graceful shutdown before remaining work is abandoned (in seconds)
+pub const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 120;
+
+/// Default number of times a panicking task is restarted before it is left failed
+pub const DEFAULT_MAX_TASK_RESTARTS: u32 = 5;
+
+/// Delay before the first restart of a panicked task, doubled on each further restart
+const INITIAL_RESTART_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest delay between restarts of a panicked task
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Time abandoned trade trackers get to return after the settlement token is cancelled
+const ABANDON_GRACE: Duration = Duration::from_secs(5);
+
+/// Count of transactions submitted but not yet settled, which shutdown waits for
+#[derive(Debug, Clone, Default)]
+pub struct InFlight {
+    count: Arc<AtomicUsize>,
+    settled: Arc<Notify>,
+}
+
+/// Marks one transaction in flight until dropped
+#[derive(Debug)]
+pub struct InFlightGuard {
+    in_flight: InFlight,
+}
+
+impl InFlight {
+    /// Mark a transaction in flight
+    pub fn enter(&self) -> InFlightGuard {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard {
+            in_flight: self.clone(),
+        }
+    }
+
+    /// Get the number of transactions in flight
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    /// Wait until no transaction is in flight
+    pub async fn drained(&self) {
+        loop {
+            let settled = self.settled.notified();
+            tokio::pin!(settled);
+            settled.as_mut().enable();
+            if self.count() == 0 {
+                return;
+            }
+            settled.await;
+        }
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.in_flight.count.fetch_sub(1, Ordering::SeqCst);
+        self.in_flight.settled.notify_waiters();
+    }
+}
+
+/// State of a supervised task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    /// Running normally
+    Running,
+
+    /// Panicked and waiting to be restarted
+    Restarting,
+
+    /// Returned, or stopped by shutdown
+    Stopped,
+
+    /// Panicked more often than allowed and left stopped
+    Failed,
+}
+
+/// Health of one supervised task
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskHealth {
+    /// Task name
+    pub name: String,
+
+    /// Current state
+    pub state: TaskState,
+
+    /// Number of restarts after a panic
+    pub restarts: u32,
+
+    /// Message of the last panic, if any
+    pub last_panic: Option<String>,
+}
+
+/// Snapshot of the running bot's health
+#[derive(Debug, Clone, Serialize)]
+pub struct Health {
+    /// Time since the supervisor was created (in seconds)
+    pub uptime_secs: u64,
+
+    /// Whether shutdown has started
+    pub shutting_down: bool,
+
+    /// Transactions submitted but not yet settled
+    pub in_flight_transactions: usize,
+
+    /// Health of every supervised task
+    pub tasks: Vec<TaskHealth>,
+}
+
+impl Health {
+    /// Whether the bot is running with every task up
+    pub fn is_healthy(&self) -> bool {
+        !self.shutting_down
+            && self
+                .tasks
+                .iter()
+                .all(|task| task.state == TaskState::Running)
+    }
+}
+
+/// Handle taking `Health` snapshots of the running bot, for tasks that outlive a borrow of the
+/// supervisor
+#[derive(Debug, Clone)]
+pub struct HealthMonitor {
+    started: Instant,
+    shutdown: CancellationToken,
+    in_flight: InFlight,
+    tasks: Arc<Mutex<Vec<Arc<Mutex<TaskHealth>>>>>,
+}
+
+impl HealthMonitor {
+    /// Take a snapshot of the bot's health, including tasks supervised after the handle was taken
+    pub fn health(&self) -> Health {
+        Health {
+            uptime_secs: self.started.elapsed().as_secs(),
+            shutting_down: self.shutdown.is_cancelled(),
+            in_flight_transactions: self.in_flight.count(),
+            tasks: self
+                .tasks
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .iter()
+                .map(|task| task.lock().unwrap_or_else(|e| e.into_inner()).clone())
+                .collect(),
+        }
+    }
+}
+
+/// Operator overrides of the running bot's behavior
+#[derive(Debug, Default)]
+pub struct RuntimeControls {
+    scanning_paused: AtomicBool,
+    min_profit_threshold: Mutex<Option<f64>>,
+}
+
+impl RuntimeControls {
+    /// Stop or restart scanning for opportunities; transactions in flight still settle
+    pub fn set_scanning_paused(&self, paused: bool) {
+        self.scanning_paused.store(paused, Ordering::Relaxed);
+    }
+
+    /// Whether scanning is paused
+    pub fn scanning_paused(&self) -> bool {
+        self.scanning_paused.load(Ordering::Relaxed)
+    }
+
+    /// Replace the configured minimum profit threshold of every strategy variant (in USD), or
+    /// go back to the configured thresholds with `None`
+    pub fn set_min_profit_threshold(&self, threshold: Option<f64>) {
+        *self
+            .min_profit_threshold
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = threshold;
+    }
+
+    /// The minimum profit threshold override (in USD), if one is set
+    pub fn min_profit_threshold(&self) -> Option<f64> {
+        *self
+            .min_profit_threshold
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Get the process-wide runtime controls
+pub fn controls() -> &'static RuntimeControls {
+    static CONTROLS: OnceLock<RuntimeControls> = OnceLock::new();
+    CONTROLS.get_or_init(RuntimeControls::default)
+}
+
+/// Subsystems of one chain that shutdown stops, closes or flushes
+pub struct ChainSubsystems {
+    /// Chain name, for logs
+    pub name: String,
+
+    /// Listener feeding new blocks to the chain's components
+    pub event_listener: Arc<dyn BlockchainEventListener>,
+
+    /// MEV-Share client, whose event streams are closed
+    pub mev_share_client: Arc<MevShareClient>,
+
+    /// Trade history storage, flushed last
+    pub storage: Option<Arc<Storage>>,
+}
+
+/// Aborts a task when dropped, so aborting a task's monitor stops the task too
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// A task under supervision
+struct SupervisedTask {
+    health: Arc<Mutex<TaskHealth>>,
+    monitor: JoinHandle<()>,
+}
+
+/// Owner of the running bot's subsystems and tasks
+pub struct BotSupervisor {
+    alert_manager: Arc<AlertManager>,
+    monitor: HealthMonitor,
+    shutdown_timeout: Duration,
+    max_task_restarts: u32,
+    shutdown: CancellationToken,
+    settlement: CancellationToken,
+    in_flight: InFlight,
+    chains: Vec<ChainSubsystems>,
+    tasks: Vec<SupervisedTask>,
+}
+
+impl BotSupervisor {
+    /// Create a supervisor with nothing to supervise yet
+    pub fn new(config: &Config, alert_manager: Arc<AlertManager>) -> Self {
+        let shutdown = CancellationToken::new();
+        let in_flight = InFlight::default();
+        Self {
+            alert_manager,
+            monitor: HealthMonitor {
+                started: Instant::now(),
+                shutdown: shutdown.clone(),
+                in_flight: in_flight.clone(),
+                tasks: Arc::default(),
+            },
+            shutdown_timeout: Duration::from_secs(
+                config
+                    .runtime
+                    .shutdown_timeout_secs
+                    .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT_SECS),
+            ),
+            max_task_restarts: config
+                .runtime
+                .max_task_restarts
+                .unwrap_or(DEFAULT_MAX_TASK_RESTARTS),
+            shutdown,
+            settlement: CancellationToken::new(),
+            in_flight,
+            chains: Vec::new(),
+            tasks: Vec::new(),
+        }
+    }
+
+    /// Get a token cancelled when shutdown starts, for work that takes on new opportunities
+    pub fn shutdown_token(&self) -> CancellationToken {
+        self.shutdown.child_token()
+    }
+
+    /// Get a token cancelled when shutdown abandons in-flight transactions at its timeout, for
+    /// work following submitted transactions to their outcome
+    pub fn settlement_token(&self) -> CancellationToken {
+        self.settlement.child_token()
+    }
+
+    /// Get the in-flight transaction count shutdown waits for
+    pub fn in_flight(&self) -> InFlight {
+        self.in_flight.clone()
+    }
+
+    /// Get a handle taking health snapshots
+    pub fn health_monitor(&self) -> HealthMonitor {
+        self.monitor.clone()
+    }
+
+    /// Take ownership of a chain's subsystems
+    pub fn add_chain(&mut self, chain: ChainSubsystems) {
+        self.chains.push(chain);
+    }
+
+    /// Run a task until it returns or shutdown stops it, restarting it after a panic
+    ///
+    /// `task` is called again for every restart. It should return soon after its cancellation
+    /// token is cancelled; the supervisor aborts it if it is still running at the shutdown
+    /// timeout.
+    pub fn supervise<F, Fut>(&mut self, name: impl Into<String>, task: F)
+    where
+        F: Fn(CancellationToken) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let health = Arc::new(Mutex::new(TaskHealth {
+            name: name.into(),
+            state: TaskState::Running,
+            restarts: 0,
+            last_panic: None,
+        }));
+
+        let monitor = tokio::spawn({
+            let health = health.clone();
+            let shutdown = self.shutdown_token();
+            let alert_manager = self.alert_manager.clone();
+            let max_restarts = self.max_task_restarts;
+            async move {
+                let name = health
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .name
+                    .clone();
+                let mut backoff = INITIAL_RESTART_BACKOFF;
+                loop {
+                    let mut handle = tokio::spawn(task(shutdown.child_token()));
+                    let _abort = AbortOnDrop(handle.abort_handle());
+                    let panic = match (&mut handle).await {
+                        Ok(()) => None,
+                        Err(e) if e.is_panic() => Some(panic_message(e.into_panic())),
+                        Err(_) => None,
+                    };
+
+                    let Some(panic) = panic else {
+                        set_state(&health, TaskState::Stopped);
+                        return;
+                    };
+
+                    metrics::global().increment_counter("task_panics", 1);
+                    let restart = {
+                        let mut state = health.lock().unwrap_or_else(|e| e.into_inner());
+                        state.last_panic = Some(panic.clone());
+                        if shutdown.is_cancelled() {
+                            state.state = TaskState::Stopped;
+                            return;
+                        }
+                        if state.restarts >= max_restarts {
+                            state.state = TaskState::Failed;
+                            None
+                        } else {
+                            state.state = TaskState::Restarting;
+                            state.restarts += 1;
+                            Some(state.restarts)
+                        }
+                    };
+                    let Some(restart) = restart else {
+                        alert_manager.raise(
+                            AlertSeverity::Critical,
+                            "runtime",
+                            format!(
+                                "{} panicked after {} restarts, leaving it stopped: {}",
+                                name, max_restarts, panic
+                            ),
+                        );
+                        return;
+                    };
+                    error!(
+                        "{} panicked, restarting in {:?} (restart {} of {}): {}",
+                        name, backoff, restart, max_restarts, panic
+                    );
+
+                    tokio::select! {
+                        _ = tokio::time::sleep(backoff) => {}
+                        _ = shutdown.cancelled() => {
+                            set_state(&health, TaskState::Stopped);
+                            return;
+                        }
+                    }
+                    backoff = (backoff * 2).min(MAX_RESTART_BACKOFF);
+                    set_state(&health, TaskState::Running);
+                    metrics::global().increment_counter("task_restarts", 1);
+                }
+            }
+        });
+
+        self.monitor
+            .tasks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(health.clone());
+        self.tasks.push(SupervisedTask { health, monitor });
+    }
+
+    /// Take a snapshot of the bot's health
+    pub fn health(&self) -> Health {
+        self.monitor.health()
+    }
+
+    /// Shut down in order, within the shutdown timeout
+    ///
+    /// Stops the tasks and event listeners, waits for in-flight transactions to settle, closes
+    /// the MEV-Share event streams and flushes storage. Work still running at the timeout is
+    /// abandoned with a warning, but the streams are closed and storage flushed regardless.
+    pub async fn shutdown(mut self) {
+        let deadline = tokio::time::Instant::now() + self.shutdown_timeout;
+        info!(
+            "Shutting down {} chains with {} transactions in flight",
+            self.chains.len(),
+            self.in_flight.count()
+        );
+        self.shutdown.cancel();
+
+        // Stop feeding new blocks and opportunities
+        for chain in &self.chains {
+            if let Err(e) = chain.event_listener.stop().await {
+                warn!("Failed to stop the event listener on {}: {}", chain.name, e);
+            }
+        }
+        for task in &mut self.tasks {
+            if tokio::time::timeout_at(deadline, &mut task.monitor)
+                .await
+                .is_err()
+            {
+                let mut health = task.health.lock().unwrap_or_else(|e| e.into_inner());
+                warn!(
+                    "{} did not stop before the shutdown timeout; aborting it",
+                    health.name
+                );
+                task.monitor.abort();
+                health.state = TaskState::Stopped;
+            }
+        }
+
+        // Let submitted transactions settle and record their outcome
+        if self.in_flight.count() > 0 {
+            info!(
+                "Waiting for {} in-flight transactions to settle",
+                self.in_flight.count()
+            );
+            if tokio::time::timeout_at(deadline, self.in_flight.drained())
+                .await
+                .is_err()
+            {
+                warn!(
+                    "Abandoning {} in-flight transactions at the shutdown timeout; they are \
+                     settled on the next startup",
+                    self.in_flight.count()
+                );
+            }
+        }
+
+        // Stop following submitted transactions, and let their trackers return before flushing
+        self.settlement.cancel();
+        if self.in_flight.count() > 0 {
+            let _ = tokio::time::timeout(ABANDON_GRACE, self.in_flight.drained()).await;
+        }
+
+        for chain in &self.chains {
+            chain.mev_share_client.close_streams();
+            if let Some(storage) = &chain.storage {
+                if let Err(e) = storage.flush() {
+                    warn!("Failed to flush storage on {}: {}", chain.name, e);
+                }
+            }
+        }
+
+        info!("Bot stopped: {:?}", self.health());
+    }
+}
+
+/// Set the state of a supervised task
+fn set_state(health: &Mutex<TaskHealth>, state: TaskState) {
+    health.lock().unwrap_or_else(|e| e.into_inner()).state = state;
+}
+
+/// Get the message of a task's panic
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "non-string panic payload".to_string())
+}
@@ -1,20 +1,45 @@
 //! Opportunity Scanner Module
 //!
 //! This module is responsible for monitoring DEX prices and identifying arbitrage opportunities.
+//! Each scan picks the block its quotes are read at and runs the registered strategies on it.
+//! Candidates must cover the gas of their route at the live gas price.
+
+mod pending;
+mod queue;
+mod tiers;
+mod triangular;
+mod trigger;
+
+pub use pending::{find_pending_routes, PendingRoute};
+pub use queue::OpportunityQueue;
+pub use tiers::{create_pair_tiers, pair_key, PairTiers, ScanTier};
+pub use triangular::{find_cycles, TriangularCycle};
+pub use trigger::{ScanRequest, ScanTrigger};
 
 use anyhow::Result;
 use async_trait::async_trait;
-use ethers::providers::Provider;
+use ethers::middleware::Middleware;
 use ethers::types::{Address, U256};
 use log::{debug, error, info, warn};
+use std::collections::HashSet;
+use std::fmt;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
-
-use crate::config::Config;
-use crate::dex::{DexInterfaces, DexType, TradeQuote};
-use crate::price::{PriceOracle, PriceOracleInterface};
-use crate::utils::validate_and_parse_address;
+use tokio_util::sync::CancellationToken;
+use tracing::{Instrument, Span};
+
+use crate::blockchain::RpcClient;
+use crate::config::{Config, FlashLoanProvider, QuoteBlock};
+use crate::dex::{dex_names, BlockTag, DexInterfaces, DexType};
+use crate::gas::GasOptimizer;
+use crate::latency::Latency;
+use crate::mempool::MempoolMonitor;
+use crate::price::{PinnedPrices, PriceOracleInterface};
+use crate::strategy::{
+    create_registry, ScanRound, StrategyContext, StrategyRegistry, StrategyVariant,
+};
+use crate::tokens;
 
 /// Represents an arbitrage opportunity between different DEXes
 #[derive(Debug, Clone)]
@@ -34,6 +59,9 @@ pub struct ArbitrageOpportunity {
     /// Token path for the arbitrage (e.g., [WETH, USDC, DAI, WETH])
     pub token_path: Vec<Address>,
 
+    /// DEX and pool used for each swap, one per consecutive token pair in the path
+    pub hops: Vec<(DexType, Address)>,
+
     /// Estimated profit in USD
     pub estimated_profit: f64,
 
@@ -48,6 +76,65 @@ pub struct ArbitrageOpportunity {
 
     /// Confidence score (0-100)
     pub confidence_score: u8,
+
+    /// Strategy variant assigned to handle the opportunity
+    pub variant: StrategyVariant,
+
+    /// Flash loan provider selected to fund the opportunity
+    pub flash_loan_provider: FlashLoanProvider,
+
+    /// Flash loan fee in USD
+    pub flash_loan_fee: f64,
+
+    /// Amount of the first token to borrow, as chosen by profit simulation
+    pub input_amount: Option<U256>,
+
+    /// Oracle prices the opportunity was evaluated with, reused by every later profit check
+    pub pinned_prices: PinnedPrices,
+
+    /// Block the opportunity's quotes were read at, reused by every later re-quote
+    pub quote_block: BlockTag,
+
+    /// Span following the opportunity through evaluation, building and execution, opened
+    /// within the span of the scan that found it
+    pub span: Span,
+
+    /// Time the opportunity has spent in the pipeline, counted from the start of its scan
+    pub latency: Latency,
+}
+
+impl ArbitrageOpportunity {
+    /// Get the name of the DEX used for each swap
+    pub fn dex_path(&self) -> Vec<String> {
+        dex_names(&self.hops)
+    }
+
+    /// Amount of the first token in the path to borrow
+    ///
+    /// This is the size chosen by profit simulation if the opportunity was sized, and otherwise
+    /// a multiple of the estimated profit set by the assigned variant.
+    pub fn flash_loan_amount(&self) -> U256 {
+        if let Some(amount) = self.input_amount {
+            return amount;
+        }
+
+        U256::from(
+            (self.estimated_profit * self.variant.sizing_multiplier) as u128 * 10u128.pow(18),
+        )
+    }
+}
+
+impl fmt::Display for ArbitrageOpportunity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} via {} (net profit ${:.2})",
+            self.id,
+            self.dex_path().join(" -> "),
+            tokens::path_label(&self.token_path),
+            self.net_profit
+        )
+    }
 }
 
 /// Interface for opportunity scanners
@@ -56,6 +143,10 @@ pub trait OpportunityScanner: Send + Sync {
     /// Scan for arbitrage opportunities
     async fn scan(&self) -> Result<Vec<ArbitrageOpportunity>>;
 
+    /// Scan for arbitrage opportunities through the given token pairs only, such as those whose
+    /// pools the latest block updated
+    async fn scan_pairs(&self, pairs: &[(Address, Address)]) -> Result<Vec<ArbitrageOpportunity>>;
+
     /// Start continuous scanning
     async fn start_continuous_scanning(&self) -> Result<()>;
 
@@ -67,231 +158,147 @@ pub trait OpportunityScanner: Send + Sync {
 #[derive(Clone)]
 pub struct OpportunityScannerImpl {
     config: Arc<Config>,
-    blockchain_client: Arc<Provider<ethers::providers::Http>>,
+    blockchain_client: Arc<RpcClient>,
     dex_interfaces: Arc<DexInterfaces>,
-    price_oracle: Arc<PriceOracle>,
-    is_scanning: Arc<RwLock<bool>>,
+    pair_tiers: Arc<PairTiers>,
+    strategies: Arc<StrategyRegistry>,
+    scanning: Arc<RwLock<Option<CancellationToken>>>,
 }
 
 /// Create a new opportunity scanner
 pub async fn create_scanner(
     config: &Arc<Config>,
-    blockchain_client: Arc<Provider<ethers::providers::Http>>,
+    blockchain_client: Arc<RpcClient>,
     dex_interfaces: Arc<DexInterfaces>,
-    price_oracle: Arc<PriceOracle>,
+    price_oracle: Arc<dyn PriceOracleInterface>,
+    gas_optimizer: Arc<dyn GasOptimizer>,
+    pair_tiers: Arc<PairTiers>,
+    mempool: Option<Arc<MempoolMonitor>>,
 ) -> Result<Arc<dyn OpportunityScanner>> {
+    let context = Arc::new(StrategyContext {
+        config: config.clone(),
+        dex_interfaces: dex_interfaces.clone(),
+        price_oracle,
+        gas_optimizer,
+        pair_tiers: pair_tiers.clone(),
+        mempool,
+    });
+    let strategies = create_registry(config, context)?;
+    info!("Strategies: {}", strategies.names().join(", "));
+
     let scanner = OpportunityScannerImpl {
         config: config.clone(),
         blockchain_client,
         dex_interfaces,
-        price_oracle,
-        is_scanning: Arc::new(RwLock::new(false)),
+        pair_tiers,
+        strategies: Arc::new(strategies),
+        scanning: Arc::new(RwLock::new(None)),
     };
 
     Ok(Arc::new(scanner))
 }
 
-#[async_trait]
-impl OpportunityScanner for OpportunityScannerImpl {
-    async fn scan(&self) -> Result<Vec<ArbitrageOpportunity>> {
-        info!("Scanning for arbitrage opportunities...");
-        let mut opportunities = Vec::new();
-
-        // Get the list of tokens we're interested in
-        let tokens = &self.config.flash_loan.tokens;
-        if tokens.is_empty() {
-            warn!("No tokens configured for scanning");
-            return Ok(Vec::new());
-        }
-
-        // For each pair of tokens, check for arbitrage opportunities
-        for i in 0..tokens.len() {
-            for j in 0..tokens.len() {
-                if i == j {
-                    continue; // Skip same token pairs
+impl OpportunityScannerImpl {
+    /// Pick the block a scan reads its quotes and reserves at
+    ///
+    /// Pinned scans read at the last block the pool cache was synced to, so cached reserves can
+    /// still be used, or at the chain head when the cache is not synced from blocks.
+    async fn quote_block(&self) -> BlockTag {
+        match self.config.arbitrage.quote_block {
+            QuoteBlock::Latest => BlockTag::Latest,
+            QuoteBlock::Pending => BlockTag::Pending,
+            QuoteBlock::Pinned => {
+                let synced_block = self.dex_interfaces.pool_cache().synced_block();
+                if synced_block > 0 {
+                    return BlockTag::Number(synced_block);
                 }
 
-                let token_a = match validate_and_parse_address(&tokens[i].address) {
-                    Ok(addr) => addr,
+                match self.blockchain_client.get_block_number().await {
+                    Ok(block_number) => BlockTag::Number(block_number.as_u64()),
                     Err(e) => {
-                        warn!("Invalid token address {}: {}", tokens[i].address, e);
-                        continue;
-                    }
-                };
-
-                let token_b = match validate_and_parse_address(&tokens[j].address) {
-                    Ok(addr) => addr,
-                    Err(e) => {
-                        warn!("Invalid token address {}: {}", tokens[j].address, e);
-                        continue;
-                    }
-                };
-
-                // Get quotes from all DEXes for this token pair
-                let input_amount = U256::from(10).pow(U256::from(tokens[i].decimals));
-                match self
-                    .dex_interfaces
-                    .get_quotes(token_a, token_b, input_amount)
-                    .await
-                {
-                    Ok(quotes) => {
-                        if quotes.len() < 2 {
-                            // Need at least 2 DEXes to compare
-                            continue;
-                        }
-
-                        // Find the best buy and sell prices
-                        let mut best_buy_quote: Option<TradeQuote> = None;
-                        let mut best_sell_quote: Option<TradeQuote> = None;
-
-                        for quote in &quotes {
-                            if best_buy_quote.is_none()
-                                || quote.output_amount
-                                    > best_buy_quote.as_ref().unwrap().output_amount
-                            {
-                                best_buy_quote = Some(quote.clone());
-                            }
-
-                            if best_sell_quote.is_none()
-                                || quote.output_amount
-                                    < best_sell_quote.as_ref().unwrap().output_amount
-                            {
-                                best_sell_quote = Some(quote.clone());
-                            }
-                        }
-
-                        // If we have both quotes, check for arbitrage opportunity
-                        if let (Some(buy_quote), Some(sell_quote)) =
-                            (best_buy_quote, best_sell_quote)
-                        {
-                            if buy_quote.output_amount > sell_quote.output_amount {
-                                // There's a potential arbitrage opportunity
-
-                                // Calculate profit in token B
-                                let profit_in_token_b = buy_quote
-                                    .output_amount
-                                    .saturating_sub(sell_quote.output_amount);
-
-                                // Convert profit to USD
-                                let token_b_price_usd = match PriceOracleInterface::get_price_usd(
-                                    &*self.price_oracle,
-                                    token_b,
-                                )
-                                .await
-                                {
-                                    Ok(price) => price,
-                                    Err(e) => {
-                                        warn!(
-                                            "Failed to get USD price for token {:?}: {}",
-                                            token_b, e
-                                        );
-                                        continue;
-                                    }
-                                };
-
-                                // Calculate profit in USD
-                                let decimals = tokens[j].decimals as u32;
-                                let profit_usd = (profit_in_token_b.as_u128() as f64
-                                    / 10f64.powi(decimals as i32))
-                                    * token_b_price_usd;
-
-                                // Calculate required loan amount
-                                let token_a_price_usd = match PriceOracleInterface::get_price_usd(
-                                    &*self.price_oracle,
-                                    token_a,
-                                )
-                                .await
-                                {
-                                    Ok(price) => price,
-                                    Err(e) => {
-                                        warn!(
-                                            "Failed to get USD price for token {:?}: {}",
-                                            token_a, e
-                                        );
-                                        continue;
-                                    }
-                                };
-
-                                let loan_amount_usd = (input_amount.as_u128() as f64
-                                    / 10f64.powi(tokens[i].decimals as i32))
-                                    * token_a_price_usd;
-
-                                // Estimate gas cost (this would be more accurate in a real implementation)
-                                let estimated_gas_cost = 0.01; // $0.01 for simplicity
-
-                                // Calculate net profit
-                                let net_profit = profit_usd - estimated_gas_cost;
-
-                                // Only consider opportunities with positive net profit
-                                if net_profit > 0.0 {
-                                    // Create a unique ID for this opportunity
-                                    let id = format!(
-                                        "{}_{}_{}_{}",
-                                        tokens[i].symbol,
-                                        tokens[j].symbol,
-                                        buy_quote.dex_type as u8,
-                                        sell_quote.dex_type as u8
-                                    );
-
-                                    // Get DEX names
-                                    let source_dex = format!("{:?}", buy_quote.dex_type);
-                                    let target_dex = format!("{:?}", sell_quote.dex_type);
-
-                                    // Create token path
-                                    let token_path = vec![token_a, token_b, token_a];
-
-                                    // Create the opportunity
-                                    let opportunity = ArbitrageOpportunity {
-                                        id,
-                                        timestamp: std::time::SystemTime::now()
-                                            .duration_since(std::time::UNIX_EPOCH)
-                                            .unwrap()
-                                            .as_secs(),
-                                        source_dex,
-                                        target_dex,
-                                        token_path,
-                                        estimated_profit: profit_usd,
-                                        required_loan_amount: loan_amount_usd,
-                                        estimated_gas_cost,
-                                        net_profit,
-                                        confidence_score: 80, // Arbitrary confidence score
-                                    };
-
-                                    info!(
-                                        "Found arbitrage opportunity: {} -> {} with profit: ${:.2}",
-                                        opportunity.source_dex,
-                                        opportunity.target_dex,
-                                        opportunity.net_profit
-                                    );
-
-                                    opportunities.push(opportunity);
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        warn!(
-                            "Failed to get quotes for token pair {:?} -> {:?}: {}",
-                            token_a, token_b, e
-                        );
-                        continue;
+                        warn!("Failed to get the block number to pin quotes to: {}", e);
+                        BlockTag::Latest
                     }
                 }
             }
         }
+    }
 
+    /// Run the strategies on every pair, or only on `pairs` (by `pair_key`)
+    ///
+    /// Only full sweeps count towards the scans dormant pairs wait for.
+    async fn run_scan(
+        &self,
+        pairs: Option<HashSet<(Address, Address)>>,
+    ) -> Result<Vec<ArbitrageOpportunity>> {
+        // Get the list of tokens we're interested in
+        if self.config.flash_loan.tokens.is_empty() {
+            warn!("No tokens configured for scanning");
+            return Ok(Vec::new());
+        }
+        let scan = if pairs.is_none() {
+            self.pair_tiers.begin_scan()
+        } else {
+            self.pair_tiers.last_scan()
+        };
+        let latency = Latency::start(Duration::from_millis(
+            self.config.arbitrage.latency_budget_ms,
+        ));
+
+        // The spans of the opportunities found are opened within the scan's
+        let opportunities = async {
+            // Every quote of the scan sees the same state
+            let quote_block = self.quote_block().await;
+            debug!("Quoting at {:?}", quote_block);
+            self.dex_interfaces.prefetch_reserves(quote_block).await;
+
+            // Every strategy searches the same block, and their opportunities are ranked together
+            self.strategies
+                .find_opportunities(&ScanRound {
+                    scan,
+                    quote_block,
+                    latency,
+                    pairs,
+                })
+                .await
+        }
+        .instrument(tracing::info_span!("scan", scan))
+        .await;
         debug!("Scan complete. Found {} opportunities", opportunities.len());
         Ok(opportunities)
     }
+}
+
+#[async_trait]
+impl OpportunityScanner for OpportunityScannerImpl {
+    async fn scan(&self) -> Result<Vec<ArbitrageOpportunity>> {
+        info!("Scanning for arbitrage opportunities...");
+        self.run_scan(None).await
+    }
+
+    async fn scan_pairs(&self, pairs: &[(Address, Address)]) -> Result<Vec<ArbitrageOpportunity>> {
+        debug!(
+            "Scanning {} changed pairs for arbitrage opportunities...",
+            pairs.len()
+        );
+        self.run_scan(Some(
+            pairs
+                .iter()
+                .map(|&(token_a, token_b)| pair_key(token_a, token_b))
+                .collect(),
+        ))
+        .await
+    }
 
     async fn start_continuous_scanning(&self) -> Result<()> {
-        let mut is_scanning = self.is_scanning.write().await;
-        if *is_scanning {
+        let mut scanning = self.scanning.write().await;
+        if scanning.is_some() {
             info!("Continuous scanning already running");
             return Ok(());
         }
 
-        *is_scanning = true;
+        let cancel = CancellationToken::new();
+        *scanning = Some(cancel.clone());
         info!("Starting continuous scanning for arbitrage opportunities");
 
         // Clone necessary references for the background task
@@ -301,9 +308,12 @@ impl OpportunityScanner for OpportunityScannerImpl {
         tokio::spawn(async move {
             let scanner = scanner;
 
-            while *scanner.is_scanning.read().await {
-                // Scan for opportunities
-                match scanner.scan().await {
+            while !cancel.is_cancelled() {
+                // Scan for opportunities, interrupted by stopping
+                let Some(scanned) = cancel.run_until_cancelled(scanner.scan()).await else {
+                    break;
+                };
+                match scanned {
                     Ok(opportunities) => {
                         if !opportunities.is_empty() {
                             info!(
@@ -340,7 +350,11 @@ impl OpportunityScanner for OpportunityScannerImpl {
                     debug!("Test mode: Sleeping for 10 seconds between scans");
                 }
 
-                tokio::time::sleep(tokio::time::Duration::from_millis(sleep_duration)).await;
+                cancel
+                    .run_until_cancelled(tokio::time::sleep(tokio::time::Duration::from_millis(
+                        sleep_duration,
+                    )))
+                    .await;
             }
 
             info!("Continuous scanning stopped");
@@ -350,13 +364,12 @@ impl OpportunityScanner for OpportunityScannerImpl {
     }
 
     async fn stop_continuous_scanning(&self) -> Result<()> {
-        let mut is_scanning = self.is_scanning.write().await;
-        if !*is_scanning {
+        let Some(cancel) = self.scanning.write().await.take() else {
             info!("Continuous scanning is not running");
             return Ok(());
-        }
+        };
 
-        *is_scanning = false;
+        cancel.cancel();
         info!("Stopping continuous scanning for arbitrage opportunities");
 
         Ok(())
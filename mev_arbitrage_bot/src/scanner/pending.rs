@@ -0,0 +1,135 @@
+//! Pending Route Module
+//!
+//! This module searches the pool state predicted after pending swaps for the routes those
+//! swaps open up: single-DEX triangular cycles and two-pool round trips across DEXes, each
+//! through at least one pool a pending swap moves.
+
+use ethers::types::{Address, U256};
+use std::collections::{HashMap, HashSet};
+
+use crate::dex::{get_amount_out, DexType, PoolState};
+use crate::scanner::find_cycles;
+
+/// A profitable route in the predicted pool state
+#[derive(Debug, Clone)]
+pub struct PendingRoute {
+    /// Tokens visited, starting and ending with the borrowed token
+    pub token_path: Vec<Address>,
+
+    /// DEX and pool used for each swap
+    pub hops: Vec<(DexType, Address)>,
+
+    /// Amount of the first token put in
+    pub amount_in: U256,
+
+    /// Amount of the first token returned by the last swap
+    pub amount_out: U256,
+}
+
+impl PendingRoute {
+    /// Get the profit in the first token
+    pub fn profit(&self) -> U256 {
+        self.amount_out.saturating_sub(self.amount_in)
+    }
+
+    /// Get the return on the input, in basis points
+    fn return_bps(&self) -> U256 {
+        self.profit()
+            .saturating_mul(U256::from(10_000))
+            .checked_div(self.amount_in)
+            .unwrap_or_default()
+    }
+}
+
+/// Find the profitable routes through the pools pending swaps touch, highest return on input
+/// first
+pub fn find_pending_routes(
+    pools: &[PoolState],
+    touched: &HashSet<Address>,
+    start_tokens: &[(Address, U256)],
+) -> Vec<PendingRoute> {
+    let mut routes: Vec<PendingRoute> = find_cycles(pools, start_tokens)
+        .into_iter()
+        .filter(|cycle| cycle.pools.iter().any(|pool| touched.contains(pool)))
+        .map(|cycle| PendingRoute {
+            hops: cycle
+                .pools
+                .iter()
+                .map(|&pool| (cycle.dex_type, pool))
+                .collect(),
+            token_path: cycle.token_path,
+            amount_in: cycle.amount_in,
+            amount_out: cycle.amount_out,
+        })
+        .collect();
+
+    // Round trips between a touched pool and the same pair on another DEX
+    let mut by_pair: HashMap<(Address, Address), Vec<&PoolState>> = HashMap::new();
+    for pool in pools {
+        by_pair
+            .entry((pool.token0, pool.token1))
+            .or_default()
+            .push(pool);
+    }
+    for pool in pools.iter().filter(|pool| touched.contains(&pool.address)) {
+        for other in &by_pair[&(pool.token0, pool.token1)] {
+            if other.dex_type == pool.dex_type {
+                continue;
+            }
+
+            for &(token_a, amount_in) in start_tokens {
+                if token_a != pool.token0 && token_a != pool.token1 {
+                    continue;
+                }
+                let token_b = if token_a == pool.token0 {
+                    pool.token1
+                } else {
+                    pool.token0
+                };
+
+                // Either direction can be the profitable one after the pending swap
+                for (first, second) in [(pool, *other), (*other, pool)] {
+                    let Some(amount_out) = round_trip(first, second, token_a, amount_in) else {
+                        continue;
+                    };
+                    if amount_out <= amount_in {
+                        continue;
+                    }
+
+                    routes.push(PendingRoute {
+                        token_path: vec![token_a, token_b, token_a],
+                        hops: vec![
+                            (first.dex_type, first.address),
+                            (second.dex_type, second.address),
+                        ],
+                        amount_in,
+                        amount_out,
+                    });
+                }
+            }
+        }
+    }
+
+    routes.sort_by_key(|route| std::cmp::Reverse(route.return_bps()));
+    routes
+}
+
+/// Swap `token_a` through `first` and back through `second`
+fn round_trip(
+    first: &PoolState,
+    second: &PoolState,
+    token_a: Address,
+    amount_in: U256,
+) -> Option<U256> {
+    let (reserve_in, reserve_out) = first.reserves_for(token_a)?;
+    let amount_b = get_amount_out(amount_in, reserve_in, reserve_out, first.fee);
+    let token_b = if token_a == first.token0 {
+        first.token1
+    } else {
+        first.token0
+    };
+
+    let (reserve_in, reserve_out) = second.reserves_for(token_b)?;
+    let amount_out = get_amount_out(amount_b, reserve_in, reserve_out, second.fee);
+    (!amount_out.is_zero()).then_some(amount_out)
+}
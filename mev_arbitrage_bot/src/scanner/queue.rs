@@ -0,0 +1,148 @@
+//! Opportunity Queue Module
+//!
+//! This module is responsible for holding evaluated opportunities between the scans that find
+//! them and their execution. Opportunities are taken best first, by net profit weighted by
+//! confidence, so the several opportunities scans find in one block can be executed side by
+//! side or merged into a bundle instead of only the best one. Each lives for the block it was
+//! found at: the first scan requested at a later block expires it, since its quotes no longer
+//! hold. An opportunity already taken in a block is not queued again in that block.
+
+use ethers::types::Address;
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use super::{pair_key, ArbitrageOpportunity};
+use crate::metrics;
+use crate::strategy::score;
+
+/// Largest number of opportunities held; the lowest scored are dropped beyond it
+const CAPACITY: usize = 256;
+
+/// An opportunity waiting to be executed
+struct Queued {
+    opportunity: ArbitrageOpportunity,
+    score: f64,
+}
+
+/// Opportunities of the current block
+#[derive(Default)]
+struct Entries {
+    /// Block the queued opportunities were found at
+    block_number: u64,
+
+    /// Queued opportunities, best score first
+    queued: Vec<Queued>,
+
+    /// Ids of the opportunities taken in the block
+    taken: HashSet<String>,
+}
+
+/// Priority queue of opportunities that expire after one block
+#[derive(Default)]
+pub struct OpportunityQueue {
+    entries: Mutex<Entries>,
+}
+
+impl OpportunityQueue {
+    /// Create an empty queue
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue the opportunities a scan requested at `block_number` found, replacing queued ones
+    /// with the same id
+    pub fn push(&self, opportunities: Vec<ArbitrageOpportunity>, block_number: u64) {
+        let mut entries = self.lock(block_number);
+        for opportunity in opportunities {
+            if entries.taken.contains(&opportunity.id) {
+                continue;
+            }
+            entries
+                .queued
+                .retain(|queued| queued.opportunity.id != opportunity.id);
+            entries.queued.push(Queued {
+                score: score(&opportunity),
+                opportunity,
+            });
+        }
+
+        entries.queued.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        entries.queued.truncate(CAPACITY);
+        metrics::global().set_gauge("opportunity_queue_size", entries.queued.len() as f64);
+    }
+
+    /// Take up to `max` of the best opportunities of `block_number` that trade through no
+    /// common token pair, so they can execute side by side
+    ///
+    /// Opportunities sharing a pair with a taken one are dropped, since the taken trade moves
+    /// the reserves they were quoted at.
+    pub fn take_independent(&self, block_number: u64, max: usize) -> Vec<ArbitrageOpportunity> {
+        let mut entries = self.lock(block_number);
+        let mut used_pairs: HashSet<(Address, Address)> = HashSet::new();
+        let mut taken = Vec::new();
+        let mut kept = Vec::new();
+
+        for queued in std::mem::take(&mut entries.queued) {
+            let pairs = traded_pairs(&queued.opportunity.token_path);
+            if pairs.iter().any(|pair| used_pairs.contains(pair)) {
+                metrics::global().increment_counter("opportunity_queue_conflicts", 1);
+                continue;
+            }
+            if taken.len() >= max {
+                kept.push(queued);
+                continue;
+            }
+
+            used_pairs.extend(pairs);
+            entries.taken.insert(queued.opportunity.id.clone());
+            taken.push(queued.opportunity);
+        }
+
+        entries.queued = kept;
+        metrics::global().set_gauge("opportunity_queue_size", entries.queued.len() as f64);
+        taken
+    }
+
+    /// Take every opportunity of `block_number`, best first
+    pub fn take_all(&self, block_number: u64) -> Vec<ArbitrageOpportunity> {
+        let mut entries = self.lock(block_number);
+        let taken: Vec<ArbitrageOpportunity> = std::mem::take(&mut entries.queued)
+            .into_iter()
+            .map(|queued| queued.opportunity)
+            .collect();
+        entries
+            .taken
+            .extend(taken.iter().map(|opportunity| opportunity.id.clone()));
+
+        metrics::global().set_gauge("opportunity_queue_size", 0.0);
+        taken
+    }
+
+    /// Lock the entries, expiring those of blocks before `block_number`
+    fn lock(&self, block_number: u64) -> std::sync::MutexGuard<'_, Entries> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if block_number > entries.block_number {
+            let expired = entries.queued.len();
+            if expired > 0 {
+                metrics::global().increment_counter("opportunity_queue_expired", expired as u64);
+            }
+            entries.queued.clear();
+            entries.taken.clear();
+            entries.block_number = block_number;
+        }
+
+        entries
+    }
+}
+
+/// Get the token pairs (by `pair_key`) swapped along a token path
+fn traded_pairs(token_path: &[Address]) -> Vec<(Address, Address)> {
+    token_path
+        .windows(2)
+        .map(|pair| pair_key(pair[0], pair[1]))
+        .collect()
+}
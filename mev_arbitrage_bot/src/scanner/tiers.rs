@@ -0,0 +1,277 @@
+//! Scan Tier Module
+//!
+//! This module tracks how often each token pair produces opportunities, when it last produced
+//! one that was executed, and how its trades settled and what they realized. Pairs that go `demote_after_days`
+//! without an executed opportunity drop to a dormant tier that is only scanned every
+//! `dormant_scan_interval` scans, and are promoted back as soon as they produce one again, so
+//! scan cost follows the value a pair brings.
+
+use anyhow::Result;
+use ethers::types::Address;
+use log::info;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::config::Config;
+use crate::metrics;
+use crate::storage::Storage;
+use crate::tokens;
+use crate::utils::current_timestamp;
+
+/// How often a pair is scanned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanTier {
+    /// Scanned every time
+    Active,
+
+    /// Scanned once every `dormant_scan_interval` scans
+    Dormant,
+}
+
+/// Activity of one token pair
+#[derive(Debug, Clone, Copy)]
+struct PairStats {
+    tier: ScanTier,
+    opportunities: u64,
+    executions: u64,
+    confirmed: u64,
+    failed: u64,
+    realized_pnl: f64,
+    last_executed: u64,
+}
+
+impl PairStats {
+    /// Stats of a pair with no trade history, which gets the full window from `now` before it
+    /// can be demoted
+    fn new(now: u64) -> Self {
+        Self {
+            tier: ScanTier::Active,
+            opportunities: 0,
+            executions: 0,
+            confirmed: 0,
+            failed: 0,
+            realized_pnl: 0.0,
+            last_executed: now,
+        }
+    }
+}
+
+/// Assigns token pairs to scan tiers by their recent yield
+pub struct PairTiers {
+    enabled: bool,
+    demote_after_seconds: u64,
+    dormant_scan_interval: u64,
+    started_at: u64,
+    scans: AtomicU64,
+    pairs: Mutex<HashMap<(Address, Address), PairStats>>,
+}
+
+/// Create the scan tiers, restoring each pair's trade history from storage
+pub fn create_pair_tiers(
+    config: &Arc<Config>,
+    storage: Option<&Arc<Storage>>,
+) -> Result<Arc<PairTiers>> {
+    let now = current_timestamp();
+    let tiers = PairTiers {
+        enabled: config.pair_tiers.enabled,
+        demote_after_seconds: config.pair_tiers.demote_after_days * 86_400,
+        dormant_scan_interval: config.pair_tiers.dormant_scan_interval.max(1),
+        started_at: now,
+        scans: AtomicU64::new(0),
+        pairs: Mutex::new(HashMap::new()),
+    };
+
+    if let Some(storage) = storage {
+        let mut pairs = tiers.pairs.lock().unwrap_or_else(|e| e.into_inner());
+        for record in storage.path_activity()? {
+            for key in pair_keys(&record.token_path) {
+                let stats = pairs.entry(key).or_insert(PairStats {
+                    last_executed: 0,
+                    ..PairStats::new(now)
+                });
+                stats.executions += record.trades;
+                stats.confirmed += record.confirmed;
+                stats.failed += record.failed;
+                stats.realized_pnl += record.realized_pnl;
+                stats.last_executed = stats.last_executed.max(record.last_trade);
+            }
+        }
+    }
+    tiers.update_tiers(now);
+
+    Ok(Arc::new(tiers))
+}
+
+/// Build the key identifying an unordered token pair
+pub fn pair_key(token_a: Address, token_b: Address) -> (Address, Address) {
+    if token_a <= token_b {
+        (token_a, token_b)
+    } else {
+        (token_b, token_a)
+    }
+}
+
+/// Get the keys of every distinct pair swapped along a token path
+fn pair_keys(token_path: &[Address]) -> Vec<(Address, Address)> {
+    let mut keys: Vec<_> = token_path
+        .windows(2)
+        .map(|pair| pair_key(pair[0], pair[1]))
+        .collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+impl PairTiers {
+    /// Start a scan, re-tiering pairs first, and get its sequence number
+    pub fn begin_scan(&self) -> u64 {
+        self.update_tiers(current_timestamp());
+        self.scans.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Get the sequence number of the last scan started
+    pub fn last_scan(&self) -> u64 {
+        self.scans.load(Ordering::Relaxed).saturating_sub(1)
+    }
+
+    /// Whether a pair is scanned in the scan with the given sequence number
+    ///
+    /// A pair is tracked from the first time it is scanned, so pairs that never produce an
+    /// opportunity are demoted too.
+    pub fn should_scan(&self, token_a: Address, token_b: Address, scan: u64) -> bool {
+        let mut pairs = self.pairs.lock().unwrap_or_else(|e| e.into_inner());
+        let stats = pairs
+            .entry(pair_key(token_a, token_b))
+            .or_insert_with(|| PairStats::new(self.started_at));
+
+        stats.tier == ScanTier::Active || scan.is_multiple_of(self.dormant_scan_interval)
+    }
+
+    /// Count an opportunity the scanner found for a pair
+    pub fn record_opportunity(&self, token_a: Address, token_b: Address) {
+        let mut pairs = self.pairs.lock().unwrap_or_else(|e| e.into_inner());
+        pairs
+            .entry(pair_key(token_a, token_b))
+            .or_insert_with(|| PairStats::new(self.started_at))
+            .opportunities += 1;
+    }
+
+    /// Record that an opportunity along a token path passed evaluation and was submitted,
+    /// promoting its pairs back to the active tier
+    pub fn record_execution(&self, token_path: &[Address]) {
+        let now = current_timestamp();
+        let mut pairs = self.pairs.lock().unwrap_or_else(|e| e.into_inner());
+        for key in pair_keys(token_path) {
+            let stats = pairs.entry(key).or_insert_with(|| PairStats::new(now));
+            stats.executions += 1;
+            stats.last_executed = now;
+            if stats.tier == ScanTier::Dormant {
+                stats.tier = ScanTier::Active;
+                info!(
+                    "Pair {} promoted to the active scan tier",
+                    tokens::path_label(&[key.0, key.1])
+                );
+            }
+        }
+        drop(pairs);
+
+        self.update_gauge();
+    }
+
+    /// Promote the pairs along a token path to the active tier ahead of an expected
+    /// opportunity, such as one a pending swap opens up, without counting an execution
+    ///
+    /// The pairs then stay active for a full demotion window.
+    pub fn promote(&self, token_path: &[Address]) {
+        let now = current_timestamp();
+        let mut pairs = self.pairs.lock().unwrap_or_else(|e| e.into_inner());
+        for key in pair_keys(token_path) {
+            let stats = pairs.entry(key).or_insert_with(|| PairStats::new(now));
+            stats.last_executed = stats.last_executed.max(now);
+            if stats.tier == ScanTier::Dormant {
+                stats.tier = ScanTier::Active;
+                info!(
+                    "Pair {} promoted to the active scan tier ahead of a pending swap",
+                    tokens::path_label(&[key.0, key.1])
+                );
+            }
+        }
+        drop(pairs);
+
+        self.update_gauge();
+    }
+
+    /// Record how a trade along a token path settled, adding its realized PnL to its pairs'
+    /// yield
+    pub fn record_outcome(&self, token_path: &[Address], success: bool, realized_pnl: Option<f64>) {
+        let mut pairs = self.pairs.lock().unwrap_or_else(|e| e.into_inner());
+        for key in pair_keys(token_path) {
+            if let Some(stats) = pairs.get_mut(&key) {
+                if success {
+                    stats.confirmed += 1;
+                } else {
+                    stats.failed += 1;
+                }
+                stats.realized_pnl += realized_pnl.unwrap_or_default();
+            }
+        }
+    }
+
+    /// Get the confirmed and failed trades of the pair along a token path that settled worst,
+    /// or `(0, 0)` if none of its pairs has settled a trade
+    pub fn settled_trades(&self, token_path: &[Address]) -> (u64, u64) {
+        let pairs = self.pairs.lock().unwrap_or_else(|e| e.into_inner());
+        pair_keys(token_path)
+            .iter()
+            .filter_map(|key| pairs.get(key))
+            .filter(|stats| stats.confirmed + stats.failed > 0)
+            .map(|stats| (stats.confirmed, stats.failed))
+            .min_by(|a, b| {
+                let rate = |(confirmed, failed): (u64, u64)| {
+                    confirmed as f64 / (confirmed + failed) as f64
+                };
+                rate(*a)
+                    .partial_cmp(&rate(*b))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .unwrap_or_default()
+    }
+
+    /// Demote active pairs without an execution in the demotion window
+    fn update_tiers(&self, now: u64) {
+        if !self.enabled {
+            return;
+        }
+
+        let mut pairs = self.pairs.lock().unwrap_or_else(|e| e.into_inner());
+        for (key, stats) in pairs.iter_mut() {
+            if stats.tier == ScanTier::Active
+                && now.saturating_sub(stats.last_executed) >= self.demote_after_seconds
+            {
+                stats.tier = ScanTier::Dormant;
+                info!(
+                    "Pair {} demoted to the dormant scan tier ({} opportunities, {} executions, \
+                     ${:.2} realized)",
+                    tokens::path_label(&[key.0, key.1]),
+                    stats.opportunities,
+                    stats.executions,
+                    stats.realized_pnl
+                );
+            }
+        }
+        drop(pairs);
+
+        self.update_gauge();
+    }
+
+    /// Publish the number of dormant pairs
+    fn update_gauge(&self) {
+        let pairs = self.pairs.lock().unwrap_or_else(|e| e.into_inner());
+        let dormant = pairs
+            .values()
+            .filter(|stats| stats.tier == ScanTier::Dormant)
+            .count();
+        metrics::global().set_gauge("dormant_pairs", dormant as f64);
+    }
+}
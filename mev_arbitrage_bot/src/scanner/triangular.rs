@@ -0,0 +1,156 @@
+//! Triangular Scan Module
+//!
+//! This module searches for A -> B -> C -> A cycles through three pools of the same DEX,
+//! quoting each swap from the reserves in the pool state cache. Such a cycle is profitable when
+//! the three pools disagree on the cross rate, which the two-DEX pair comparison cannot see.
+
+use ethers::types::{Address, U256};
+use std::collections::HashMap;
+
+use crate::dex::{get_amount_out, DexType, PoolState};
+
+/// A profitable cycle through three pools of one DEX
+#[derive(Debug, Clone)]
+pub struct TriangularCycle {
+    /// DEX of every pool in the cycle
+    pub dex_type: DexType,
+
+    /// Tokens visited, starting and ending with the borrowed token
+    pub token_path: Vec<Address>,
+
+    /// Pool used for each swap
+    pub pools: Vec<Address>,
+
+    /// Amount of the first token put in
+    pub amount_in: U256,
+
+    /// Amount of the first token returned by the last swap
+    pub amount_out: U256,
+}
+
+impl TriangularCycle {
+    /// Get the profit in the first token
+    pub fn profit(&self) -> U256 {
+        self.amount_out.saturating_sub(self.amount_in)
+    }
+}
+
+/// Pools of one DEX, indexed by token
+struct DexPools<'a> {
+    by_token: HashMap<Address, Vec<&'a PoolState>>,
+}
+
+impl<'a> DexPools<'a> {
+    /// Index pools by both of their tokens
+    fn new(pools: impl Iterator<Item = &'a PoolState>) -> Self {
+        let mut by_token: HashMap<Address, Vec<&'a PoolState>> = HashMap::new();
+        for pool in pools {
+            by_token.entry(pool.token0).or_default().push(pool);
+            by_token.entry(pool.token1).or_default().push(pool);
+        }
+
+        Self { by_token }
+    }
+
+    /// Get the pools trading a token, with the token on the other side of each
+    fn neighbours(&self, token: Address) -> impl Iterator<Item = (Address, &'a PoolState)> + '_ {
+        self.by_token
+            .get(&token)
+            .into_iter()
+            .flatten()
+            .map(move |&pool| {
+                let other = if pool.token0 == token {
+                    pool.token1
+                } else {
+                    pool.token0
+                };
+                (other, pool)
+            })
+    }
+}
+
+/// Swap an amount through a cached pool, or `None` if the pool returns nothing
+fn swap(pool: &PoolState, token_in: Address, amount_in: U256) -> Option<U256> {
+    let (reserve_in, reserve_out) = pool.reserves_for(token_in)?;
+    let amount_out = get_amount_out(amount_in, reserve_in, reserve_out, pool.fee);
+    (!amount_out.is_zero()).then_some(amount_out)
+}
+
+/// Find the profitable three-pool cycles within each DEX, highest return on input first
+///
+/// Cycles start from each of `start_tokens` (the tokens that can be borrowed) with the given
+/// input amount. A cycle through the same three pools is reported once, from the start token
+/// and direction with the highest profit relative to its input.
+pub fn find_cycles(pools: &[PoolState], start_tokens: &[(Address, U256)]) -> Vec<TriangularCycle> {
+    let mut by_dex: HashMap<DexType, Vec<&PoolState>> = HashMap::new();
+    for pool in pools {
+        by_dex.entry(pool.dex_type).or_default().push(pool);
+    }
+
+    let mut best: HashMap<(DexType, [Address; 3]), TriangularCycle> = HashMap::new();
+    for (dex_type, dex_pools) in by_dex {
+        let index = DexPools::new(dex_pools.into_iter());
+
+        for &(token_a, amount_in) in start_tokens {
+            for (token_b, first) in index.neighbours(token_a) {
+                let Some(amount_b) = swap(first, token_a, amount_in) else {
+                    continue;
+                };
+
+                for (token_c, second) in index.neighbours(token_b) {
+                    if token_c == token_a || second.address == first.address {
+                        continue;
+                    }
+                    let Some(amount_c) = swap(second, token_b, amount_b) else {
+                        continue;
+                    };
+
+                    for (token_end, third) in index.neighbours(token_c) {
+                        if token_end != token_a
+                            || third.address == first.address
+                            || third.address == second.address
+                        {
+                            continue;
+                        }
+                        let Some(amount_out) = swap(third, token_c, amount_c) else {
+                            continue;
+                        };
+                        if amount_out <= amount_in {
+                            continue;
+                        }
+
+                        let cycle = TriangularCycle {
+                            dex_type,
+                            token_path: vec![token_a, token_b, token_c, token_a],
+                            pools: vec![first.address, second.address, third.address],
+                            amount_in,
+                            amount_out,
+                        };
+
+                        let mut key = [first.address, second.address, third.address];
+                        key.sort();
+                        match best.get(&(dex_type, key)) {
+                            Some(known) if return_bps(known) >= return_bps(&cycle) => {}
+                            _ => {
+                                best.insert((dex_type, key), cycle);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut cycles: Vec<TriangularCycle> = best.into_values().collect();
+    cycles.sort_by_key(|cycle| std::cmp::Reverse(return_bps(cycle)));
+    cycles
+}
+
+/// Get the return of a cycle on its input, in basis points
+fn return_bps(cycle: &TriangularCycle) -> U256 {
+    cycle
+        .profit()
+        .saturating_mul(U256::from(10_000))
+        .checked_div(cycle.amount_in)
+        .unwrap_or_default()
+}
@@ -0,0 +1,146 @@
+//! Scan Trigger Module
+//!
+//! This module is responsible for deciding when the scanner runs and what it scans. Instead of
+//! scanning on a fixed interval, a scan of the token pairs whose pools a block updated, or a
+//! pending swap trades through, is requested as they change. A full sweep of every pair still
+//! runs every `sweep_interval_ms`, and whenever a block may have moved pools the cache does not
+//! follow. Requests made while a scan is running collapse into one more scan, and a block only
+//! requests a scan once, so quiet blocks and bursts of pool events no longer cost a full scan
+//! each.
+
+use ethers::types::Address;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+use super::pair_key;
+
+/// A scan to run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanRequest {
+    /// Newest block that requested a scan
+    pub block_number: u64,
+
+    /// Token pairs to scan, or `None` for a full sweep
+    pub pairs: Option<Vec<(Address, Address)>>,
+}
+
+/// Scans requested since the last one started
+#[derive(Debug)]
+struct Requested {
+    /// Token pairs that changed, by `pair_key`
+    pairs: HashSet<(Address, Address)>,
+
+    /// Whether a full sweep was requested
+    sweep: bool,
+
+    /// When the last full sweep started
+    last_sweep: Instant,
+}
+
+/// Requests scans as pool state changes, debounced per block
+#[derive(Debug)]
+pub struct ScanTrigger {
+    sweep_interval: Duration,
+
+    /// Newest block that requested a scan
+    latest_block: AtomicU64,
+    requested: Mutex<Requested>,
+    notify: Notify,
+}
+
+impl ScanTrigger {
+    /// Create a trigger whose first scan is a full sweep, and which sweeps again every
+    /// `sweep_interval`
+    pub fn new(sweep_interval: Duration) -> Self {
+        Self {
+            sweep_interval,
+            latest_block: AtomicU64::new(0),
+            requested: Mutex::new(Requested {
+                pairs: HashSet::new(),
+                sweep: true,
+                last_sweep: Instant::now(),
+            }),
+            notify: Notify::new(),
+        }
+    }
+
+    /// Request a scan of the token pairs whose pools a block's logs updated
+    ///
+    /// A block that already requested a scan, or an older one, only adds its pairs.
+    pub fn block_updated(&self, block_number: u64, pairs: &[(Address, Address)]) {
+        self.request(pairs, false);
+        self.notify_block(block_number);
+    }
+
+    /// Request a full sweep for a block that may have moved any pool
+    pub fn sweep_block(&self, block_number: u64) {
+        self.request(&[], true);
+        self.notify_block(block_number);
+    }
+
+    /// Request a scan of the token pairs a newly seen pending swap trades through
+    pub fn pending_updated(&self, pairs: &[(Address, Address)]) {
+        self.request(pairs, false);
+        self.notify.notify_one();
+    }
+
+    /// Wait for the next scan request, or the next full sweep when it is due
+    ///
+    /// Requests made since the last wait are answered at once, and any number of them by a
+    /// single scan.
+    pub async fn wait(&self) -> ScanRequest {
+        loop {
+            let sweep_at = self.lock().last_sweep + self.sweep_interval;
+            tokio::select! {
+                _ = self.notify.notified() => {}
+                _ = tokio::time::sleep_until(sweep_at) => self.request(&[], true),
+            }
+
+            let mut requested = self.lock();
+            let pairs = std::mem::take(&mut requested.pairs);
+            if requested.sweep {
+                requested.sweep = false;
+                requested.last_sweep = Instant::now();
+                return self.scan_request(None);
+            }
+            if !pairs.is_empty() {
+                return self.scan_request(Some(pairs.into_iter().collect()));
+            }
+        }
+    }
+
+    /// Add pairs, or a full sweep, to the next scan
+    fn request(&self, pairs: &[(Address, Address)], sweep: bool) {
+        let mut requested = self.lock();
+        requested.sweep |= sweep;
+        requested.pairs.extend(
+            pairs
+                .iter()
+                .map(|&(token_a, token_b)| pair_key(token_a, token_b)),
+        );
+    }
+
+    /// Wake the waiting scan for a block, unless the block already did
+    fn notify_block(&self, block_number: u64) {
+        if self.latest_block.fetch_max(block_number, Ordering::Relaxed) < block_number {
+            self.notify.notify_one();
+        }
+    }
+
+    /// Build the request of the scan to run
+    fn scan_request(&self, pairs: Option<Vec<(Address, Address)>>) -> ScanRequest {
+        ScanRequest {
+            block_number: self.latest_block.load(Ordering::Relaxed),
+            pairs,
+        }
+    }
+
+    /// Lock the requested scans
+    fn lock(&self) -> std::sync::MutexGuard<'_, Requested> {
+        self.requested.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
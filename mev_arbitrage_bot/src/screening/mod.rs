@@ -0,0 +1,476 @@
+//! Token Screening Module
+//!
+//! This module is responsible for keeping untradeable tokens (honeypots, tokens taxing or
+//! blocking transfers, known scams) out of the pools the bot trades. A token discovered in a
+//! new pair is checked against the configured blacklist feeds, then bought with ETH and sold
+//! back through the pair's DEX in an `eth_simulateV1` simulation of the latest block. Tokens
+//! that cannot be sold, or lose more than `max_round_trip_loss_bps` on the round trip, fail.
+//! Outcomes are cached in storage and retried after `rescreen_after_hours`.
+
+use anyhow::{Context, Result};
+use ethers::abi::{self, Token};
+use ethers::types::{Address, Bytes, U256};
+use log::{debug, info, warn};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::blockchain::{Chain, RpcClient};
+use crate::config::{Config, ConfigSection};
+use crate::metrics;
+use crate::storage::{Storage, TokenScreeningRecord};
+use crate::tokens;
+use crate::utils::{
+    current_timestamp, decimal_to_u256, u256_to_decimal, validate_and_parse_address,
+};
+
+/// Timeout of a blacklist feed request
+const FEED_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Token screening configuration (`[screening]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScreeningConfig {
+    /// Whether discovered tokens are screened; without screening they are added unchecked
+    pub enabled: bool,
+
+    /// URLs of blacklist feeds, each listing token addresses as text or JSON
+    pub blacklist_feeds: Vec<String>,
+
+    /// How often the blacklist feeds are fetched again (in seconds)
+    pub feed_refresh_interval_secs: u64,
+
+    /// ETH spent on the simulated buy
+    pub probe_amount_eth: f64,
+
+    /// Largest share of the probe the buy and sell may lose together (in basis points),
+    /// including both swap fees
+    pub max_round_trip_loss_bps: u32,
+
+    /// Whether a token that could not be simulated (e.g. the node lacks `eth_simulateV1`)
+    /// fails screening
+    pub require_simulation: bool,
+
+    /// How long a screening outcome is kept before the token is screened again (in hours)
+    pub rescreen_after_hours: u64,
+}
+
+impl Default for ScreeningConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            blacklist_feeds: Vec::new(),
+            feed_refresh_interval_secs: 3_600,
+            probe_amount_eth: 0.05,
+            max_round_trip_loss_bps: 1_000,
+            require_simulation: true,
+            rescreen_after_hours: 168,
+        }
+    }
+}
+
+impl ConfigSection for ScreeningConfig {
+    const NAME: &'static str = "screening";
+
+    fn validate(&self) -> Result<()> {
+        if !self.probe_amount_eth.is_finite() || self.probe_amount_eth <= 0.0 {
+            anyhow::bail!("Screening probe_amount_eth must be greater than zero");
+        }
+        if self.max_round_trip_loss_bps >= 10_000 {
+            anyhow::bail!("Screening max_round_trip_loss_bps must be below 10000");
+        }
+        if self.blacklist_feeds.iter().any(|url| url.trim().is_empty()) {
+            anyhow::bail!("Screening blacklist feed URLs must not be empty");
+        }
+
+        Ok(())
+    }
+}
+
+/// Addresses listed by the blacklist feeds, and when they were fetched
+#[derive(Debug, Default)]
+struct FeedBlacklist {
+    tokens: HashSet<Address>,
+    fetched_at: Option<u64>,
+}
+
+/// Screens tokens before their pools are added
+pub struct TokenScreener {
+    settings: ScreeningConfig,
+    blockchain_client: Arc<RpcClient>,
+    http_client: Client,
+    weth: Address,
+    blacklisted: HashSet<Address>,
+    feeds: tokio::sync::Mutex<FeedBlacklist>,
+    screenings: Mutex<HashMap<Address, TokenScreeningRecord>>,
+    storage: Option<Arc<Storage>>,
+}
+
+/// Create a token screener, restoring earlier screening outcomes from storage
+pub fn create_screener(
+    config: &Arc<Config>,
+    blockchain_client: Arc<RpcClient>,
+    storage: Option<Arc<Storage>>,
+) -> Result<Arc<TokenScreener>> {
+    let settings: ScreeningConfig = config.section()?;
+    let weth = validate_and_parse_address(Chain::from_id(config.ethereum.chain_id).weth_address())
+        .context("Invalid WETH address")?;
+    let http_client = Client::builder()
+        .timeout(FEED_TIMEOUT)
+        .build()
+        .context("Failed to build blacklist feed client")?;
+
+    let mut screenings = HashMap::new();
+    if let Some(storage) = &storage {
+        for record in storage.token_screenings()? {
+            screenings.insert(record.token, record);
+        }
+    }
+    if !screenings.is_empty() {
+        info!("Restored {} token screenings", screenings.len());
+    }
+
+    Ok(Arc::new(TokenScreener {
+        settings,
+        blockchain_client,
+        http_client,
+        weth,
+        blacklisted: config.arbitrage.pairs.blacklisted(&config.flash_loan),
+        feeds: tokio::sync::Mutex::new(FeedBlacklist::default()),
+        screenings: Mutex::new(screenings),
+        storage,
+    }))
+}
+
+/// Find every address in a feed, whether it lists them one per line, as CSV or as JSON
+fn parse_feed(body: &str) -> HashSet<Address> {
+    let bytes = body.as_bytes();
+    let mut tokens = HashSet::new();
+    let mut i = 0;
+    while i + 42 <= bytes.len() {
+        let candidate = &body[i..i + 42];
+        let bounded = bytes
+            .get(i + 42)
+            .is_none_or(|byte| !byte.is_ascii_hexdigit());
+        if candidate.starts_with("0x") && bounded {
+            if let Ok(address) = candidate.parse() {
+                tokens.insert(address);
+                i += 42;
+                continue;
+            }
+        }
+        i += 1;
+    }
+
+    tokens
+}
+
+/// Encode a call to a function by its signature
+fn encode_call(signature: &str, args: &[Token]) -> Bytes {
+    let mut data = ethers::utils::id(signature).to_vec();
+    data.extend(abi::encode(args));
+    data.into()
+}
+
+/// Address the simulated trades are made from
+fn probe_address() -> Address {
+    Address::from_slice(&ethers::utils::keccak256(b"token screening probe")[12..])
+}
+
+impl TokenScreener {
+    /// Whether a token may be traded, screening it if it has no current outcome
+    ///
+    /// `listed` is a configured token the token is paired with, and `router` the router of the
+    /// pair's DEX, which the simulated trades are routed through.
+    pub async fn screen(&self, token: Address, listed: Address, router: Address) -> bool {
+        if !self.settings.enabled {
+            return true;
+        }
+
+        let now = current_timestamp();
+        let cached = self
+            .screenings
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&token)
+            .filter(|record| {
+                now.saturating_sub(record.screened_at) < self.settings.rescreen_after_hours * 3_600
+            })
+            .cloned();
+        if let Some(record) = cached {
+            return record.passed;
+        }
+
+        let reason = match self.check(token, listed, router).await {
+            Ok(()) => None,
+            Err(e) => Some(format!("{:#}", e)),
+        };
+        let record = TokenScreeningRecord {
+            token,
+            passed: reason.is_none(),
+            reason,
+            screened_at: now,
+        };
+        match &record.reason {
+            Some(reason) => {
+                warn!(
+                    "Token {} failed screening: {}",
+                    tokens::token_label(token),
+                    reason
+                );
+                metrics::global().increment_counter("tokens_screened_out", 1);
+            }
+            None => info!("Token {} passed screening", tokens::token_label(token)),
+        }
+
+        if let Some(storage) = &self.storage {
+            if let Err(e) = storage.save_token_screening(&record) {
+                warn!(
+                    "Failed to persist screening of {}: {}",
+                    tokens::token_label(token),
+                    e
+                );
+            }
+        }
+        let passed = record.passed;
+        self.screenings
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(token, record);
+
+        passed
+    }
+
+    /// Check a token, failing with the reason it is not tradeable
+    async fn check(&self, token: Address, listed: Address, router: Address) -> Result<()> {
+        if self.blacklisted.contains(&token) {
+            anyhow::bail!("blacklisted in the configuration");
+        }
+        if self.feed_blacklist().await.contains(&token) {
+            anyhow::bail!("listed by a blacklist feed");
+        }
+
+        match self.simulate_round_trip(token, listed, router).await? {
+            Some(loss_bps) if loss_bps > f64::from(self.settings.max_round_trip_loss_bps) => {
+                anyhow::bail!(
+                    "round trip loses {:.0} bps, above {} bps",
+                    loss_bps,
+                    self.settings.max_round_trip_loss_bps
+                )
+            }
+            Some(loss_bps) => {
+                debug!(
+                    "Round trip through {} loses {:.0} bps",
+                    tokens::token_label(token),
+                    loss_bps
+                );
+                Ok(())
+            }
+            None if self.settings.require_simulation => {
+                anyhow::bail!("round trip could not be simulated")
+            }
+            None => {
+                warn!(
+                    "Admitting {} without a simulated round trip",
+                    tokens::token_label(token)
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Get the addresses of the blacklist feeds, fetching them again when they are due
+    ///
+    /// A feed that fails to load keeps the addresses it listed before.
+    async fn feed_blacklist(&self) -> HashSet<Address> {
+        let mut feeds = self.feeds.lock().await;
+        let now = current_timestamp();
+        let due = feeds.fetched_at.is_none_or(|fetched_at| {
+            now.saturating_sub(fetched_at) >= self.settings.feed_refresh_interval_secs
+        });
+        if due && !self.settings.blacklist_feeds.is_empty() {
+            for url in &self.settings.blacklist_feeds {
+                match self.fetch_feed(url).await {
+                    Ok(tokens) => {
+                        debug!("Blacklist feed {} lists {} tokens", url, tokens.len());
+                        feeds.tokens.extend(tokens);
+                    }
+                    Err(e) => warn!("Failed to fetch blacklist feed {}: {}", url, e),
+                }
+            }
+            feeds.fetched_at = Some(now);
+        }
+
+        feeds.tokens.clone()
+    }
+
+    async fn fetch_feed(&self, url: &str) -> Result<HashSet<Address>> {
+        let body = self
+            .http_client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        Ok(parse_feed(&body))
+    }
+
+    /// Simulate buying the token with ETH and selling it back to WETH on the latest block,
+    /// returning the share of the probe lost (in basis points), or `None` if the node cannot
+    /// simulate
+    async fn simulate_round_trip(
+        &self,
+        token: Address,
+        listed: Address,
+        router: Address,
+    ) -> Result<Option<f64>> {
+        let probe = probe_address();
+        let amount_in = decimal_to_u256(self.settings.probe_amount_eth, 18);
+        let mut buy_path = vec![self.weth];
+        if listed != self.weth {
+            buy_path.push(listed);
+        }
+        buy_path.push(token);
+        let sell_path: Vec<Address> = buy_path.iter().rev().copied().collect();
+        let path_tokens = |path: &[Address]| {
+            Token::Array(
+                path.iter()
+                    .map(|&address| Token::Address(address))
+                    .collect(),
+            )
+        };
+        let deadline = Token::Uint(U256::from(current_timestamp() + 3_600));
+
+        let buy = json!({
+            "from": probe,
+            "to": router,
+            "value": amount_in,
+            "input": encode_call(
+                "swapExactETHForTokensSupportingFeeOnTransferTokens(uint256,address[],address,uint256)",
+                &[
+                    Token::Uint(U256::zero()),
+                    path_tokens(&buy_path),
+                    Token::Address(probe),
+                    deadline.clone(),
+                ],
+            ),
+        });
+        let balance_of = |holder_token: Address| {
+            json!({
+                "from": probe,
+                "to": holder_token,
+                "input": encode_call("balanceOf(address)", &[Token::Address(probe)]),
+            })
+        };
+
+        // Learn what the buy delivers, taxes included, so the sell can spend all of it
+        let Some(bought) = self
+            .simulate(vec![buy.clone(), balance_of(token)], amount_in)
+            .await
+            .context("buy failed")?
+        else {
+            return Ok(None);
+        };
+        let bought = bought.last().copied().unwrap_or_default();
+        if bought.is_zero() {
+            anyhow::bail!("buy delivered no tokens");
+        }
+
+        let approve = json!({
+            "from": probe,
+            "to": token,
+            "input": encode_call(
+                "approve(address,uint256)",
+                &[Token::Address(router), Token::Uint(U256::MAX)],
+            ),
+        });
+        let sell = json!({
+            "from": probe,
+            "to": router,
+            "input": encode_call(
+                "swapExactTokensForTokensSupportingFeeOnTransferTokens(uint256,uint256,address[],address,uint256)",
+                &[
+                    Token::Uint(bought),
+                    Token::Uint(U256::zero()),
+                    path_tokens(&sell_path),
+                    Token::Address(probe),
+                    deadline,
+                ],
+            ),
+        });
+        let Some(returned) = self
+            .simulate(vec![buy, approve, sell, balance_of(self.weth)], amount_in)
+            .await
+            .context("sell failed")?
+        else {
+            return Ok(None);
+        };
+        let returned = u256_to_decimal(returned.last().copied().unwrap_or_default(), 18);
+
+        Ok(Some(
+            (1.0 - returned / self.settings.probe_amount_eth).max(0.0) * 10_000.0,
+        ))
+    }
+
+    /// Run calls in sequence on the latest block from a probe funded with twice `value`,
+    /// returning each call's output as a word, or `None` if the node cannot simulate
+    async fn simulate(&self, calls: Vec<Value>, value: U256) -> Result<Option<Vec<U256>>> {
+        let funding = value.saturating_mul(U256::from(2));
+        let params = json!({
+            "blockStateCalls": [{
+                "stateOverrides": { format!("{:?}", probe_address()): { "balance": funding } },
+                "calls": calls,
+            }],
+            "validation": false,
+        });
+        let blocks: Vec<Value> = match self
+            .blockchain_client
+            .request("eth_simulateV1", (params, "latest"))
+            .await
+        {
+            Ok(blocks) => blocks,
+            Err(e) => {
+                warn!("Failed to simulate a screening round trip: {}", e);
+                return Ok(None);
+            }
+        };
+        let Some(results) = blocks
+            .first()
+            .and_then(|block| block.get("calls"))
+            .and_then(Value::as_array)
+        else {
+            warn!("Screening simulation returned no call results");
+            return Ok(None);
+        };
+
+        let mut outputs = Vec::with_capacity(results.len());
+        for (i, result) in results.iter().enumerate() {
+            if result.get("status").and_then(Value::as_str) != Some("0x1") {
+                let error = result
+                    .get("error")
+                    .and_then(|error| error.get("message"))
+                    .and_then(Value::as_str)
+                    .unwrap_or("reverted");
+                anyhow::bail!("call {} of the round trip reverted: {}", i + 1, error);
+            }
+
+            let data = result
+                .get("returnData")
+                .and_then(Value::as_str)
+                .and_then(|data| hex::decode(data.trim_start_matches("0x")).ok())
+                .unwrap_or_default();
+            outputs.push(if data.len() >= 32 {
+                U256::from_big_endian(&data[..32])
+            } else {
+                U256::zero()
+            });
+        }
+
+        Ok(Some(outputs))
+    }
+}
@@ -0,0 +1,497 @@
+//! Transaction Simulation Module
+//!
+//! This module is responsible for simulating arbitrage transactions against the latest block
+//! before they are submitted, and rejecting those that revert or are no longer profitable.
+//! Candidates for the same block are simulated in parallel against one shared fork. What-if
+//! simulations can assume extra state, such as token balances and allowances or an executor
+//! contract's code, to try strategies on chains where the contract is not deployed.
+
+mod overrides;
+
+pub use overrides::{StateOverrides, TokenAllowance, TokenBalance};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::abi::{self, ParamType};
+use ethers::providers::{Middleware, RawCall};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{
+    spoof, Address, BlockId, BlockNumber, CallConfig, CallFrame, GethDebugBuiltInTracerConfig,
+    GethDebugBuiltInTracerType, GethDebugTracerConfig, GethDebugTracerType,
+    GethDebugTracingCallOptions, GethDebugTracingOptions, GethTrace, GethTraceFrame, NameOrAddress,
+    H256, U256,
+};
+use futures::future::join_all;
+use log::{debug, info, warn};
+use std::sync::{Arc, Mutex};
+
+use crate::blockchain::RpcClient;
+use crate::config::Config;
+use crate::price::{PriceOracle, PriceOracleInterface};
+use crate::runtime;
+use crate::transaction::ArbitrageTransaction;
+use crate::utils::{u256_to_decimal, validate_and_parse_address};
+pub(crate) use overrides::SlotFinder;
+
+/// ETH balance granted to the sender during simulation, so gas funding never causes a revert
+const SIMULATION_BALANCE_ETH: u64 = 1_000;
+
+/// Outcome of simulating an arbitrage transaction
+#[derive(Debug, Clone, Default)]
+pub struct SimulationResult {
+    /// Whether the transaction executed without reverting
+    pub success: bool,
+
+    /// Revert reason, if the transaction reverted
+    pub revert_reason: Option<String>,
+
+    /// Gas used by the transaction (only known when tracing is available)
+    pub gas_used: Option<U256>,
+
+    /// Profit reported by the `ArbitrageExecuted` event, in units of the flash-loaned token
+    pub profit: Option<U256>,
+
+    /// Simulated net profit in USD, after gas (only known when the profit is known)
+    pub net_profit_usd: Option<f64>,
+}
+
+/// Chain state shared by the simulations of one batch
+///
+/// Every simulation runs against the same block with the same overrides, and whether the node
+/// supports `debug_traceCall` is learned once instead of per candidate.
+struct SimulationFork {
+    block: BlockId,
+    state: spoof::State,
+    tracing: Mutex<Option<bool>>,
+}
+
+impl SimulationFork {
+    /// Create a fork of a block with the given state overrides
+    fn new(block: BlockId, state: spoof::State) -> Self {
+        Self {
+            block,
+            state,
+            tracing: Mutex::new(None),
+        }
+    }
+
+    /// Whether tracing is worth trying (unknown counts as yes)
+    fn tracing_available(&self) -> bool {
+        self.tracing
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .unwrap_or(true)
+    }
+
+    /// Remember whether tracing worked, unless an earlier simulation already found out
+    fn learn_tracing(&self, available: bool) {
+        self.tracing
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_or_insert(available);
+    }
+}
+
+/// Interface for transaction simulators
+#[async_trait]
+pub trait TransactionSimulator: Send + Sync {
+    /// Simulate a transaction sent from `from` against the latest block
+    async fn simulate(&self, tx: &ArbitrageTransaction, from: Address) -> Result<SimulationResult>;
+
+    /// Simulate a transaction and return an error if it reverts or falls below the profit threshold
+    async fn verify(&self, tx: &ArbitrageTransaction, from: Address) -> Result<SimulationResult>;
+
+    /// Verify several transactions sent from `from` in parallel against the same block
+    ///
+    /// Returns one outcome per transaction, in order; the outer error means the shared fork
+    /// could not be set up.
+    async fn verify_batch(
+        &self,
+        txs: &[ArbitrageTransaction],
+        from: Address,
+    ) -> Result<Vec<Result<SimulationResult>>>;
+
+    /// Simulate a transaction sent from `from` against the latest block, assuming the given
+    /// state on top of it
+    ///
+    /// Fails if a token balance or allowance cannot be overridden because its mapping slot is
+    /// not found.
+    async fn simulate_with_overrides(
+        &self,
+        tx: &ArbitrageTransaction,
+        from: Address,
+        overrides: &StateOverrides,
+    ) -> Result<SimulationResult>;
+}
+
+/// Implementation of the transaction simulator
+pub struct TransactionSimulatorImpl {
+    config: Arc<Config>,
+    blockchain_client: Arc<RpcClient>,
+    price_oracle: Arc<PriceOracle>,
+    slot_finder: SlotFinder,
+}
+
+/// Create a new transaction simulator
+pub async fn create_simulator(
+    config: &Arc<Config>,
+    blockchain_client: Arc<RpcClient>,
+    price_oracle: Arc<PriceOracle>,
+) -> Result<Arc<dyn TransactionSimulator>> {
+    let simulator = TransactionSimulatorImpl {
+        config: config.clone(),
+        slot_finder: SlotFinder::new(blockchain_client.clone()),
+        blockchain_client,
+        price_oracle,
+    };
+
+    Ok(Arc::new(simulator))
+}
+
+impl TransactionSimulatorImpl {
+    /// Build the state overrides applied to every simulation
+    fn state_overrides(&self, from: Address) -> spoof::State {
+        let balance = U256::from(SIMULATION_BALANCE_ETH).saturating_mul(U256::exp10(18));
+        spoof::balance(from, balance)
+    }
+
+    /// Fork the current block for a batch of simulations
+    async fn fork_current_block(&self, from: Address) -> Result<SimulationFork> {
+        let block_number = self
+            .blockchain_client
+            .get_block_number()
+            .await
+            .context("Failed to get the block to simulate against")?;
+
+        Ok(SimulationFork::new(
+            BlockId::Number(BlockNumber::Number(block_number)),
+            self.state_overrides(from),
+        ))
+    }
+
+    /// Simulate a transaction on a fork
+    async fn simulate_on(
+        &self,
+        fork: &SimulationFork,
+        tx: &ArbitrageTransaction,
+        from: Address,
+    ) -> Result<SimulationResult> {
+        let typed_tx: TypedTransaction = tx.request.clone().from(from).into();
+
+        let traced = if fork.tracing_available() {
+            match self.simulate_with_trace(&typed_tx, fork).await {
+                Ok(result) => {
+                    fork.learn_tracing(true);
+                    Some(result)
+                }
+                Err(e) => {
+                    // Not every node exposes the debug namespace; eth_call still catches reverts
+                    debug!("Tracing unavailable, falling back to eth_call: {}", e);
+                    fork.learn_tracing(false);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let mut result = match traced {
+            Some(result) => result,
+            None => self.simulate_with_call(&typed_tx, fork).await?,
+        };
+
+        if let (true, Some(profit)) = (result.success, result.profit) {
+            result.net_profit_usd = Some(
+                self.calculate_net_profit_usd(tx, profit, result.gas_used)
+                    .await?,
+            );
+        }
+
+        Ok(result)
+    }
+
+    /// Reject a simulation result that reverted or falls below the profit threshold
+    fn check_result(&self, result: SimulationResult) -> Result<SimulationResult> {
+        if !result.success {
+            return Err(anyhow::anyhow!(
+                "Simulated transaction reverted: {}",
+                result
+                    .revert_reason
+                    .clone()
+                    .unwrap_or_else(|| "unknown reason".to_string())
+            ));
+        }
+
+        let min_profit_threshold = runtime::controls()
+            .min_profit_threshold()
+            .unwrap_or(self.config.arbitrage.min_profit_threshold);
+        match result.net_profit_usd {
+            Some(net_profit) if net_profit < min_profit_threshold => {
+                return Err(anyhow::anyhow!(
+                    "Simulated net profit ${:.2} is below the threshold of ${:.2}",
+                    net_profit,
+                    min_profit_threshold
+                ));
+            }
+            Some(net_profit) => {
+                info!("Simulation passed with net profit ${:.2}", net_profit);
+            }
+            None => {
+                warn!("Simulation passed but the profit could not be determined");
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Simulate with `debug_traceCall`, which also yields gas usage and emitted logs
+    async fn simulate_with_trace(
+        &self,
+        typed_tx: &TypedTransaction,
+        fork: &SimulationFork,
+    ) -> Result<SimulationResult> {
+        let options = GethDebugTracingCallOptions {
+            tracing_options: GethDebugTracingOptions {
+                tracer: Some(GethDebugTracerType::BuiltInTracer(
+                    GethDebugBuiltInTracerType::CallTracer,
+                )),
+                tracer_config: Some(GethDebugTracerConfig::BuiltInTracer(
+                    GethDebugBuiltInTracerConfig::CallTracer(CallConfig {
+                        only_top_call: Some(false),
+                        with_log: Some(true),
+                    }),
+                )),
+                ..Default::default()
+            },
+            state_overrides: Some(fork.state.clone()),
+            block_overrides: None,
+        };
+
+        let trace = self
+            .blockchain_client
+            .debug_trace_call(typed_tx.clone(), Some(fork.block), options)
+            .await
+            .context("Failed to trace transaction (debug_traceCall)")?;
+
+        let frame = match trace {
+            GethTrace::Known(GethTraceFrame::CallTracer(frame)) => frame,
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Unexpected trace format from debug_traceCall"
+                ))
+            }
+        };
+
+        let contract = match typed_tx.to() {
+            Some(NameOrAddress::Address(to)) => Some(*to),
+            _ => None,
+        };
+
+        Ok(SimulationResult {
+            success: frame.error.is_none(),
+            revert_reason: frame.error.as_ref().map(|error| {
+                decode_revert_reason(frame.output.as_deref()).unwrap_or(error.clone())
+            }),
+            gas_used: Some(frame.gas_used),
+            profit: contract.and_then(|contract| find_reported_profit(&frame, contract)),
+            net_profit_usd: None,
+        })
+    }
+
+    /// Simulate with a plain `eth_call`, which only tells whether the transaction reverts
+    async fn simulate_with_call(
+        &self,
+        typed_tx: &TypedTransaction,
+        fork: &SimulationFork,
+    ) -> Result<SimulationResult> {
+        let result = self
+            .blockchain_client
+            .call_raw(typed_tx)
+            .block(fork.block)
+            .state(&fork.state)
+            .await;
+
+        Ok(match result {
+            Ok(_) => SimulationResult {
+                success: true,
+                ..Default::default()
+            },
+            Err(e) => SimulationResult {
+                success: false,
+                revert_reason: Some(e.to_string()),
+                ..Default::default()
+            },
+        })
+    }
+
+    /// Get the decimals of a configured token (defaults to 18)
+    fn token_decimals(&self, token: Address) -> u8 {
+        self.config
+            .flash_loan
+            .tokens
+            .iter()
+            .find(|token_config| {
+                validate_and_parse_address(&token_config.address).ok() == Some(token)
+            })
+            .map(|token_config| token_config.decimals)
+            .unwrap_or(18)
+    }
+
+    /// Convert the reported profit to USD and subtract the simulated gas cost
+    async fn calculate_net_profit_usd(
+        &self,
+        tx: &ArbitrageTransaction,
+        profit: U256,
+        gas_used: Option<U256>,
+    ) -> Result<f64> {
+        let profit_token = *tx
+            .token_path
+            .first()
+            .context("Transaction has an empty token path")?;
+
+        // Judge the trade with the prices it was evaluated with, not a later oracle refresh
+        let (price_usd, price_eth) = match (
+            tx.pinned_prices.usd(profit_token),
+            tx.pinned_prices.eth(profit_token),
+        ) {
+            (Some(price_usd), Some(price_eth)) => (price_usd, price_eth),
+            _ => {
+                warn!(
+                    "No pinned prices for {}; reading the oracle",
+                    crate::tokens::token_label(profit_token)
+                );
+                (
+                    self.price_oracle.get_price_usd(profit_token).await?,
+                    self.price_oracle.get_price_eth(profit_token).await?,
+                )
+            }
+        };
+        let profit_usd = u256_to_decimal(profit, self.token_decimals(profit_token)) * price_usd;
+
+        // Derive the ETH price from the profit token's USD and ETH prices
+        let gas_cost_usd = match gas_used {
+            Some(gas_used) if price_eth > 0.0 => {
+                let gas_cost = gas_used
+                    .saturating_mul(tx.estimated_gas_price)
+                    .saturating_add(tx.l1_data_fee);
+                u256_to_decimal(gas_cost, 18) * price_usd / price_eth
+            }
+            _ => 0.0,
+        };
+
+        Ok(profit_usd - gas_cost_usd)
+    }
+}
+
+#[async_trait]
+impl TransactionSimulator for TransactionSimulatorImpl {
+    async fn simulate(&self, tx: &ArbitrageTransaction, from: Address) -> Result<SimulationResult> {
+        let fork = SimulationFork::new(
+            BlockId::Number(BlockNumber::Latest),
+            self.state_overrides(from),
+        );
+        self.simulate_on(&fork, tx, from).await
+    }
+
+    async fn verify(&self, tx: &ArbitrageTransaction, from: Address) -> Result<SimulationResult> {
+        let result = self.simulate(tx, from).await?;
+        self.check_result(result)
+    }
+
+    async fn verify_batch(
+        &self,
+        txs: &[ArbitrageTransaction],
+        from: Address,
+    ) -> Result<Vec<Result<SimulationResult>>> {
+        // Pin one block so every candidate sees the same state, whenever its call lands
+        let fork = self.fork_current_block(from).await?;
+
+        let results = join_all(
+            txs.iter()
+                .map(|tx| async { self.check_result(self.simulate_on(&fork, tx, from).await?) }),
+        )
+        .await;
+
+        debug!(
+            "Simulated {} transactions against block {:?}",
+            txs.len(),
+            fork.block
+        );
+
+        Ok(results)
+    }
+
+    async fn simulate_with_overrides(
+        &self,
+        tx: &ArbitrageTransaction,
+        from: Address,
+        overrides: &StateOverrides,
+    ) -> Result<SimulationResult> {
+        let mut state = self.state_overrides(from);
+        self.slot_finder.apply(overrides, &mut state).await?;
+
+        let fork = SimulationFork::new(BlockId::Number(BlockNumber::Latest), state);
+        self.simulate_on(&fork, tx, from).await
+    }
+}
+
+/// Decode an `Error(string)` revert payload into its message
+fn decode_revert_reason(output: Option<&[u8]>) -> Option<String> {
+    let output = output?;
+    if output.len() < 4 || output[..4] != [0x08, 0xc3, 0x79, 0xa0] {
+        return None;
+    }
+
+    abi::decode(&[ParamType::String], &output[4..])
+        .ok()?
+        .into_iter()
+        .next()?
+        .into_string()
+}
+
+/// Topic of the `ArbitrageExecuted(address[],uint256,uint256,uint256,string[])` event
+fn arbitrage_executed_topic() -> H256 {
+    H256::from(ethers::utils::keccak256(
+        b"ArbitrageExecuted(address[],uint256,uint256,uint256,string[])",
+    ))
+}
+
+/// Find the profit reported by the executor contract's `ArbitrageExecuted` event
+fn find_reported_profit(frame: &CallFrame, contract: Address) -> Option<U256> {
+    // Events of reverted frames never took effect
+    if frame.error.is_some() {
+        return None;
+    }
+
+    let topic = arbitrage_executed_topic();
+    for log in frame.logs.iter().flatten() {
+        let (address, topics, data) = match (&log.address, &log.topics, &log.data) {
+            (Some(address), Some(topics), Some(data)) => (*address, topics, data),
+            _ => continue,
+        };
+
+        if address != contract || topics.first() != Some(&topic) {
+            continue;
+        }
+
+        let params = [
+            ParamType::Array(Box::new(ParamType::Address)),
+            ParamType::Uint(256),
+            ParamType::Uint(256),
+            ParamType::Uint(256),
+            ParamType::Array(Box::new(ParamType::String)),
+        ];
+
+        if let Ok(tokens) = abi::decode(&params, data) {
+            if let Some(profit) = tokens.get(3).and_then(|token| token.clone().into_uint()) {
+                return Some(profit);
+            }
+        }
+    }
+
+    frame
+        .calls
+        .iter()
+        .flatten()
+        .find_map(|call| find_reported_profit(call, contract))
+}
@@ -0,0 +1,265 @@
+//! State Overrides Module
+//!
+//! This module is responsible for the chain state what-if simulations assume. Besides ETH
+//! balances, account code and raw storage, overrides can grant ERC-20 balances and allowances:
+//! the storage slot of the token's `balanceOf` or `allowance` mapping is found by probing, once
+//! per token, which candidate slot makes the getter return a marker value. Solidity and Vyper
+//! mapping layouts are both tried, so the common tokens (including proxies such as USDC, whose
+//! storage lives at the proxy) can be funded without knowing their layout.
+
+use anyhow::{Context, Result};
+use ethers::abi::{self, Token};
+use ethers::providers::RawCall;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{spoof, Address, BlockId, BlockNumber, Bytes, TransactionRequest, H256, U256};
+use ethers::utils::keccak256;
+use futures::future::join_all;
+use log::debug;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::assets::ContractAbi;
+use crate::blockchain::RpcClient;
+use crate::tokens;
+
+/// Highest mapping slot probed
+const MAX_PROBED_SLOT: u64 = 50;
+
+/// Value written to a candidate slot; the slot is found when the getter returns it
+const PROBE_VALUE: u64 = 0x5eed_5107;
+
+/// Chain state to assume in a what-if simulation
+#[derive(Debug, Clone, Default)]
+pub struct StateOverrides {
+    /// ETH balances of accounts
+    pub balances: Vec<(Address, U256)>,
+
+    /// Code of accounts, e.g. the executor contract's runtime code where it is not deployed
+    pub code: Vec<(Address, Bytes)>,
+
+    /// Raw storage slots of accounts (address, slot, value)
+    pub storage: Vec<(Address, H256, H256)>,
+
+    /// ERC-20 balances
+    pub token_balances: Vec<TokenBalance>,
+
+    /// ERC-20 allowances
+    pub allowances: Vec<TokenAllowance>,
+}
+
+/// An ERC-20 balance to assume
+#[derive(Debug, Clone, Copy)]
+pub struct TokenBalance {
+    /// Token address
+    pub token: Address,
+
+    /// Account holding the balance
+    pub holder: Address,
+
+    /// Balance (in token units)
+    pub amount: U256,
+}
+
+/// An ERC-20 allowance to assume
+#[derive(Debug, Clone, Copy)]
+pub struct TokenAllowance {
+    /// Token address
+    pub token: Address,
+
+    /// Account whose tokens are spent
+    pub owner: Address,
+
+    /// Account allowed to spend them
+    pub spender: Address,
+
+    /// Allowance (in token units)
+    pub amount: U256,
+}
+
+/// Order in which a mapping's key and slot are hashed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MappingLayout {
+    /// `keccak256(key . slot)`
+    Solidity,
+
+    /// `keccak256(slot . key)`
+    Vyper,
+}
+
+/// Position of a mapping in a contract's storage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MappingSlot {
+    slot: u64,
+    layout: MappingLayout,
+}
+
+impl MappingSlot {
+    /// Get the storage slot of `mapping[key]`
+    fn entry(self, key: Address) -> H256 {
+        hash_entry(H256::from_low_u64_be(self.slot), key, self.layout)
+    }
+
+    /// Get the storage slot of `mapping[outer][inner]`
+    fn nested_entry(self, outer: Address, inner: Address) -> H256 {
+        hash_entry(self.entry(outer), inner, self.layout)
+    }
+}
+
+/// Which getter a mapping is probed through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum MappingKind {
+    /// `balanceOf(holder)`
+    Balance,
+
+    /// `allowance(owner, spender)`
+    Allowance,
+}
+
+/// Resolves overrides into a state override set, finding token mapping slots as needed
+pub(crate) struct SlotFinder {
+    blockchain_client: Arc<RpcClient>,
+    slots: Mutex<HashMap<(Address, MappingKind), MappingSlot>>,
+}
+
+impl SlotFinder {
+    /// Create a slot finder probing through a client
+    pub(crate) fn new(blockchain_client: Arc<RpcClient>) -> Self {
+        Self {
+            blockchain_client,
+            slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Apply overrides on top of a state override set
+    pub(super) async fn apply(
+        &self,
+        overrides: &StateOverrides,
+        state: &mut spoof::State,
+    ) -> Result<()> {
+        for &(address, balance) in &overrides.balances {
+            state.account(address).balance(balance);
+        }
+        for (address, code) in &overrides.code {
+            state.account(*address).code(code.clone());
+        }
+        for &(address, slot, value) in &overrides.storage {
+            state.account(address).store(slot, value);
+        }
+
+        for balance in &overrides.token_balances {
+            let mapping = self.find(balance.token, MappingKind::Balance).await?;
+            state
+                .account(balance.token)
+                .store(mapping.entry(balance.holder), u256_word(balance.amount));
+        }
+        for allowance in &overrides.allowances {
+            let mapping = self.find(allowance.token, MappingKind::Allowance).await?;
+            state.account(allowance.token).store(
+                mapping.nested_entry(allowance.owner, allowance.spender),
+                u256_word(allowance.amount),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Get the storage slot holding a token balance, e.g. to write it on a fork
+    #[cfg(feature = "testing")]
+    pub(crate) async fn balance_slot(&self, token: Address, holder: Address) -> Result<H256> {
+        let mapping = self.find(token, MappingKind::Balance).await?;
+        Ok(mapping.entry(holder))
+    }
+
+    /// Find a token's balance or allowance mapping, probing it on first use
+    async fn find(&self, token: Address, kind: MappingKind) -> Result<MappingSlot> {
+        if let Some(mapping) = self.lock().get(&(token, kind)) {
+            return Ok(*mapping);
+        }
+
+        let mapping = self.probe(token, kind).await?;
+        debug!(
+            "{:?} mapping of {} is at slot {} ({:?} layout)",
+            kind,
+            tokens::token_label(token),
+            mapping.slot,
+            mapping.layout
+        );
+        self.lock().insert((token, kind), mapping);
+
+        Ok(mapping)
+    }
+
+    /// Find the slot whose override the token's getter returns
+    async fn probe(&self, token: Address, kind: MappingKind) -> Result<MappingSlot> {
+        let owner = Address::repeat_byte(0x5e);
+        let spender = Address::repeat_byte(0x5f);
+        let erc20 = ContractAbi::Erc20.contract();
+        let data = match kind {
+            MappingKind::Balance => erc20.encode("balanceOf", owner)?,
+            MappingKind::Allowance => erc20.encode("allowance", (owner, spender))?,
+        };
+        let request: TypedTransaction = TransactionRequest::new().to(token).data(data).into();
+
+        for layout in [MappingLayout::Solidity, MappingLayout::Vyper] {
+            let candidates = (0..=MAX_PROBED_SLOT).map(|slot| MappingSlot { slot, layout });
+            let probes = candidates.map(|mapping| {
+                let request = &request;
+                async move {
+                    let key = match kind {
+                        MappingKind::Balance => mapping.entry(owner),
+                        MappingKind::Allowance => mapping.nested_entry(owner, spender),
+                    };
+                    let state = spoof::storage(token, key, u256_word(U256::from(PROBE_VALUE)));
+                    let output = self
+                        .blockchain_client
+                        .call_raw(request)
+                        .block(BlockId::Number(BlockNumber::Latest))
+                        .state(&state)
+                        .await
+                        .ok()?;
+
+                    (output.len() >= 32
+                        && U256::from_big_endian(&output[..32]) == PROBE_VALUE.into())
+                    .then_some(mapping)
+                }
+            });
+
+            if let Some(mapping) = join_all(probes).await.into_iter().flatten().next() {
+                return Ok(mapping);
+            }
+        }
+
+        Err(anyhow::anyhow!(
+            "No {:?} mapping found in the first {} slots",
+            kind,
+            MAX_PROBED_SLOT + 1
+        ))
+        .with_context(|| {
+            format!(
+                "Cannot override ERC-20 state of {}",
+                tokens::token_label(token)
+            )
+        })
+    }
+
+    /// Lock the found mapping slots
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<(Address, MappingKind), MappingSlot>> {
+        self.slots.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Hash a mapping key with the slot of the mapping
+fn hash_entry(base: H256, key: Address, layout: MappingLayout) -> H256 {
+    let (first, second) = match layout {
+        MappingLayout::Solidity => (Token::Address(key), Token::FixedBytes(base.0.to_vec())),
+        MappingLayout::Vyper => (Token::FixedBytes(base.0.to_vec()), Token::Address(key)),
+    };
+    H256(keccak256(abi::encode(&[first, second])))
+}
+
+/// Encode an amount as a storage word
+fn u256_word(value: U256) -> H256 {
+    let mut word = [0u8; 32];
+    value.to_big_endian(&mut word);
+    H256(word)
+}
@@ -0,0 +1,245 @@
+//! Analytics Views Module
+//!
+//! This module is responsible for the predefined SQL views over the trade history (daily PnL,
+//! per-pair statistics, gas efficiency, inclusion rates, profit attribution, lost races, bundle
+//! outcomes and realized slippage), so operators can query them
+//! directly with `sqlite3` or print them with the `report` command.
+
+use rusqlite::types::ValueRef;
+use rusqlite::Connection;
+
+/// Predefined analytics views: name, description and definition
+pub const ANALYTICS_VIEWS: [(&str, &str, &str); 8] = [
+    (
+        "daily_pnl",
+        "Trades, outcomes, estimated and realized PnL per UTC day",
+        "SELECT date(created_at, 'unixepoch') AS day, \
+         COUNT(*) AS trades, \
+         SUM(CASE WHEN status = 'confirmed' THEN 1 ELSE 0 END) AS confirmed, \
+         SUM(CASE WHEN status = 'reverted' THEN 1 ELSE 0 END) AS reverted, \
+         SUM(CASE WHEN status = 'failed' THEN 1 ELSE 0 END) AS failed, \
+         ROUND(SUM(estimated_profit), 2) AS estimated_profit_usd, \
+         ROUND(COALESCE(SUM(realized_pnl), 0.0), 2) AS realized_pnl_usd, \
+         ROUND(COALESCE(SUM(CAST(actual_cost AS REAL)), 0.0) / 1e18, 6) AS gas_cost_eth \
+         FROM trades GROUP BY day ORDER BY day DESC",
+    ),
+    (
+        "pair_stats",
+        "Opportunities, trades, success rate and PnL per token path",
+        "SELECT o.token_symbols AS pair, \
+         o.opportunities, \
+         ROUND(o.avg_net_profit, 2) AS avg_net_profit_usd, \
+         COALESCE(t.trades, 0) AS trades, \
+         COALESCE(t.confirmed, 0) AS confirmed, \
+         CASE WHEN COALESCE(t.trades, 0) = 0 THEN NULL \
+              ELSE ROUND(100.0 * t.confirmed / t.trades, 1) END AS success_pct, \
+         ROUND(COALESCE(t.realized_pnl, 0.0), 2) AS realized_pnl_usd \
+         FROM (SELECT token_symbols, COUNT(*) AS opportunities, AVG(net_profit) AS avg_net_profit \
+               FROM opportunities GROUP BY token_symbols) AS o \
+         LEFT JOIN (SELECT token_symbols, COUNT(*) AS trades, \
+                    SUM(CASE WHEN status = 'confirmed' THEN 1 ELSE 0 END) AS confirmed, \
+                    SUM(realized_pnl) AS realized_pnl \
+                    FROM trades GROUP BY token_symbols) AS t \
+         ON t.token_symbols = o.token_symbols \
+         ORDER BY realized_pnl_usd DESC, o.opportunities DESC",
+    ),
+    (
+        "gas_efficiency",
+        "Gas used and paid against realized PnL per route, for trades that reached the chain",
+        "SELECT token_symbols AS pair, dex_path, \
+         COUNT(*) AS trades, \
+         CAST(AVG(CAST(gas_used AS REAL)) AS INTEGER) AS avg_gas_used, \
+         CAST(AVG(CAST(estimated_gas AS REAL)) AS INTEGER) AS avg_gas_estimated, \
+         ROUND(SUM(CAST(actual_cost AS REAL)) / 1e18, 6) AS gas_cost_eth, \
+         ROUND(COALESCE(SUM(realized_pnl), 0.0), 2) AS realized_pnl_usd, \
+         ROUND(1e6 * COALESCE(SUM(realized_pnl), 0.0) / SUM(CAST(gas_used AS REAL)), 4) \
+             AS pnl_usd_per_mgas \
+         FROM trades WHERE gas_used IS NOT NULL \
+         GROUP BY token_symbols, dex_path ORDER BY pnl_usd_per_mgas DESC",
+    ),
+    (
+        "builder_inclusion",
+        "Submissions and on-chain inclusion rate per submission channel",
+        "SELECT CASE WHEN bundle_hash IS NOT NULL THEN 'bundle' \
+                     WHEN private_tx THEN 'private' \
+                     WHEN use_mev_share THEN 'mev_share' \
+                     ELSE 'public' END AS channel, \
+         COUNT(*) AS submitted, \
+         SUM(CASE WHEN block_number IS NOT NULL THEN 1 ELSE 0 END) AS included, \
+         ROUND(100.0 * SUM(CASE WHEN block_number IS NOT NULL THEN 1 ELSE 0 END) / COUNT(*), 1) \
+             AS inclusion_pct, \
+         SUM(CASE WHEN status = 'reverted' THEN 1 ELSE 0 END) AS reverted \
+         FROM trades WHERE status != 'built' AND status != 'failed' \
+         GROUP BY channel ORDER BY submitted DESC",
+    ),
+    (
+        "profit_attribution",
+        "Realized PnL split into spread capture, MEV refunds, gas and tips per UTC day and channel",
+        "SELECT date(created_at, 'unixepoch') AS day, \
+         CASE WHEN bundle_hash IS NOT NULL THEN 'bundle' \
+              WHEN private_tx THEN 'private' \
+              WHEN use_mev_share THEN 'mev_share' \
+              ELSE 'public' END AS channel, \
+         COUNT(*) AS trades, \
+         ROUND(SUM(spread_capture_usd), 2) AS spread_capture_usd, \
+         ROUND(SUM(mev_refund_usd), 2) AS mev_refund_usd, \
+         ROUND(SUM(gas_cost_usd), 2) AS gas_cost_usd, \
+         ROUND(SUM(tip_cost_usd), 2) AS tip_cost_usd, \
+         ROUND(SUM(realized_pnl), 2) AS realized_pnl_usd, \
+         CASE WHEN SUM(spread_capture_usd + mev_refund_usd) > 0 \
+              THEN ROUND(100.0 * SUM(mev_refund_usd) / SUM(spread_capture_usd + mev_refund_usd), 1) \
+              END AS refund_share_pct \
+         FROM trades WHERE spread_capture_usd IS NOT NULL \
+         GROUP BY day, channel ORDER BY day DESC, channel",
+    ),
+    (
+        "lost_race_stats",
+        "Detected opportunities taken by competitors per route: winning tips, delay and whether we bid",
+        "SELECT token_symbols AS pair, dex_path, \
+         COUNT(*) AS lost, \
+         COUNT(DISTINCT winner_contract) AS competitors, \
+         SUM(EXISTS (SELECT 1 FROM trades t WHERE t.opportunity_id = l.opportunity_id \
+                     AND t.created_at BETWEEN l.detected_at AND l.recorded_at \
+                     AND t.status != 'built' AND t.status != 'failed')) AS we_bid, \
+         ROUND(AVG(CAST(winner_priority_fee AS REAL)) / 1e9, 3) AS avg_winner_tip_gwei, \
+         ROUND(MAX(CAST(winner_priority_fee AS REAL)) / 1e9, 3) AS max_winner_tip_gwei, \
+         ROUND(AVG(block_number - detected_block), 2) AS avg_blocks_after_detection, \
+         ROUND(AVG(winner_tx_index), 1) AS avg_winner_tx_index, \
+         ROUND(SUM(estimated_profit), 2) AS missed_profit_usd \
+         FROM lost_races AS l \
+         GROUP BY token_symbols, dex_path ORDER BY missed_profit_usd DESC",
+    ),
+    (
+        "bundle_outcome_stats",
+        "Bundle submissions per outcome: relay handling, our tips and the tips that would have won",
+        "SELECT outcome, \
+         COUNT(*) AS submissions, \
+         SUM(CASE WHEN rebids > 0 THEN 1 ELSE 0 END) AS rebids, \
+         SUM(CASE WHEN simulated THEN 1 ELSE 0 END) AS simulated, \
+         ROUND(AVG(considered_by), 1) AS avg_builders_considering, \
+         ROUND(AVG(sealed_by), 1) AS avg_builders_sealing, \
+         ROUND(AVG(CAST(priority_fee AS REAL)) / 1e9, 3) AS avg_tip_gwei, \
+         ROUND(AVG(CAST(winner_priority_fee AS REAL)) / 1e9, 3) AS avg_winner_tip_gwei, \
+         ROUND(AVG(CAST(winning_tip AS REAL) - CAST(priority_fee AS REAL)) / 1e9, 3) \
+             AS avg_tip_shortfall_gwei \
+         FROM bundle_outcomes GROUP BY outcome ORDER BY submissions DESC",
+    ),
+    (
+        "realized_slippage",
+        "Estimated against realized profit of settled trades per UTC day and week",
+        "SELECT period, date(period_start, 'unixepoch') AS starting, trades, \
+         ROUND(estimated_usd, 2) AS estimated_profit_usd, \
+         ROUND(realized_usd, 2) AS realized_profit_usd, \
+         ROUND(slippage_usd, 2) AS slippage_usd, \
+         CASE WHEN estimated_usd > 0 THEN ROUND(100.0 * slippage_usd / estimated_usd, 1) \
+              END AS slippage_pct, \
+         ROUND(worst_slippage_usd, 2) AS worst_trade_slippage_usd \
+         FROM pnl_periods ORDER BY period, period_start DESC",
+    ),
+];
+
+/// Result of querying one analytics view
+#[derive(Debug, Clone)]
+pub struct ReportTable {
+    /// View name
+    pub name: String,
+
+    /// Column names
+    pub columns: Vec<String>,
+
+    /// Rows, with values formatted as text
+    pub rows: Vec<Vec<String>>,
+}
+
+impl ReportTable {
+    /// Render the table as aligned plain text
+    pub fn render(&self) -> String {
+        let widths: Vec<usize> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                self.rows
+                    .iter()
+                    .map(|row| row[i].len())
+                    .chain(std::iter::once(column.len()))
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        let format_row = |values: &[String]| {
+            values
+                .iter()
+                .zip(&widths)
+                .map(|(value, &width)| format!("{:<width$}", value, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+
+        let mut lines = vec![
+            format!("== {} ==", self.name),
+            format_row(&self.columns),
+            widths
+                .iter()
+                .map(|&width| "-".repeat(width))
+                .collect::<Vec<_>>()
+                .join("  "),
+        ];
+        lines.extend(self.rows.iter().map(|row| format_row(row)));
+        if self.rows.is_empty() {
+            lines.push("(no rows)".to_string());
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// (Re)create the analytics views so their definitions follow the running version
+pub fn create_views(connection: &Connection) -> rusqlite::Result<()> {
+    for (name, _, definition) in ANALYTICS_VIEWS {
+        connection.execute_batch(&format!(
+            "DROP VIEW IF EXISTS {name}; CREATE VIEW {name} AS {definition};"
+        ))?;
+    }
+
+    Ok(())
+}
+
+/// Query every row of an analytics view
+pub fn query_view(connection: &Connection, name: &str) -> rusqlite::Result<ReportTable> {
+    let mut statement = connection.prepare(&format!("SELECT * FROM {}", name))?;
+    let columns: Vec<String> = statement
+        .column_names()
+        .into_iter()
+        .map(String::from)
+        .collect();
+    let column_count = columns.len();
+
+    let rows = statement
+        .query_map([], |row| {
+            (0..column_count)
+                .map(|i| Ok(format_value(row.get_ref(i)?)))
+                .collect::<rusqlite::Result<Vec<_>>>()
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(ReportTable {
+        name: name.to_string(),
+        columns,
+        rows,
+    })
+}
+
+/// Format a SQLite value for display
+fn format_value(value: ValueRef) -> String {
+    match value {
+        ValueRef::Null => "-".to_string(),
+        ValueRef::Integer(value) => value.to_string(),
+        ValueRef::Real(value) => value.to_string(),
+        ValueRef::Text(text) => String::from_utf8_lossy(text).into_owned(),
+        ValueRef::Blob(blob) => format!("<{} bytes>", blob.len()),
+    }
+}
@@ -0,0 +1,1310 @@
+//! Storage Module
+//!
+//! This module is responsible for persisting identified opportunities, built transactions,
+//! execution results, realized PnL, the token deltas of settled trades, races lost to
+//! competitors, bundle outcomes, token screenings and the contracts the bot deployed to a SQLite
+//! database, and for querying them back.
+
+use anyhow::{Context, Result};
+use ethers::types::{Address, H256, U256};
+use log::info;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Row};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::analytics::BundleReport;
+use crate::competition::LostRace;
+use crate::config::{self, Config};
+use crate::dex::dex_names;
+use crate::pnl::{PnlPeriod, TradePnl};
+use crate::scanner::ArbitrageOpportunity;
+use crate::tokens;
+use crate::transaction::{ArbitrageTransaction, ProfitAttribution, TransactionResult};
+use crate::utils::{create_directory_if_not_exists, current_timestamp};
+
+mod analytics;
+
+pub use analytics::{ReportTable, ANALYTICS_VIEWS};
+
+/// Schema of the trade history database
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS opportunities (
+    row_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    id TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    source_dex TEXT NOT NULL,
+    target_dex TEXT NOT NULL,
+    token_path TEXT NOT NULL,
+    token_symbols TEXT NOT NULL,
+    estimated_profit REAL NOT NULL,
+    required_loan_amount REAL NOT NULL,
+    estimated_gas_cost REAL NOT NULL,
+    net_profit REAL NOT NULL,
+    confidence_score INTEGER NOT NULL,
+    variant TEXT NOT NULL,
+    config_hash TEXT,
+    pinned_prices TEXT
+);
+
+CREATE TABLE IF NOT EXISTS trades (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    opportunity_id TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    token_path TEXT NOT NULL,
+    token_symbols TEXT NOT NULL,
+    dex_path TEXT NOT NULL,
+    estimated_gas TEXT NOT NULL,
+    estimated_gas_price TEXT NOT NULL,
+    estimated_profit REAL NOT NULL,
+    use_mev_share INTEGER NOT NULL,
+    variant TEXT NOT NULL,
+    status TEXT NOT NULL,
+    tx_hash TEXT,
+    bundle_hash TEXT,
+    block_number INTEGER,
+    gas_used TEXT,
+    actual_cost TEXT,
+    realized_pnl REAL,
+    error TEXT,
+    config_hash TEXT,
+    updated_at INTEGER NOT NULL,
+    spread_capture_usd REAL,
+    mev_refund_usd REAL,
+    gas_cost_usd REAL,
+    tip_cost_usd REAL,
+    contract_profit_usd REAL,
+    private_tx INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS route_states (
+    route TEXT PRIMARY KEY,
+    consecutive_losses INTEGER NOT NULL,
+    suspended_until INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    quarantined_until INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS route_reverts (
+    route TEXT NOT NULL,
+    reverted_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS wallet_nonces (
+    address TEXT PRIMARY KEY,
+    next_nonce INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS contract_deployments (
+    chain_id INTEGER PRIMARY KEY,
+    address TEXT NOT NULL,
+    deployed_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS config_versions (
+    hash TEXT PRIMARY KEY,
+    snapshot TEXT NOT NULL,
+    first_seen INTEGER NOT NULL,
+    last_seen INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS lost_races (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    opportunity_id TEXT NOT NULL,
+    detected_at INTEGER NOT NULL,
+    detected_block INTEGER NOT NULL,
+    token_symbols TEXT NOT NULL,
+    dex_path TEXT NOT NULL,
+    estimated_profit REAL NOT NULL,
+    block_number INTEGER NOT NULL,
+    winner_tx_hash TEXT NOT NULL,
+    winner_sender TEXT NOT NULL,
+    winner_contract TEXT,
+    winner_tx_index INTEGER NOT NULL,
+    winner_priority_fee TEXT NOT NULL,
+    winner_pools TEXT NOT NULL,
+    recorded_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS trade_token_deltas (
+    tx_hash TEXT NOT NULL,
+    token TEXT NOT NULL,
+    token_symbol TEXT NOT NULL,
+    amount TEXT NOT NULL,
+    amount_decimal REAL,
+    amount_usd REAL,
+    recorded_at INTEGER NOT NULL,
+    PRIMARY KEY (tx_hash, token)
+);
+
+CREATE TABLE IF NOT EXISTS pnl_periods (
+    period TEXT NOT NULL,
+    period_start INTEGER NOT NULL,
+    trades INTEGER NOT NULL,
+    estimated_usd REAL NOT NULL,
+    realized_usd REAL NOT NULL,
+    slippage_usd REAL NOT NULL,
+    worst_slippage_usd REAL NOT NULL,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (period, period_start)
+);
+
+CREATE TABLE IF NOT EXISTS tx_replacements (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    first_tx_hash TEXT NOT NULL,
+    tx_hash TEXT NOT NULL,
+    replacement_tx_hash TEXT NOT NULL,
+    nonce INTEGER NOT NULL,
+    fee_per_gas TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS bundle_outcomes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    bundle_hash TEXT NOT NULL,
+    first_block INTEGER NOT NULL,
+    last_block INTEGER NOT NULL,
+    rebids INTEGER NOT NULL,
+    outcome TEXT NOT NULL,
+    simulated INTEGER,
+    considered_by INTEGER,
+    sealed_by INTEGER,
+    priority_fee TEXT NOT NULL,
+    winner_tx_hash TEXT,
+    winner_sender TEXT,
+    winner_priority_fee TEXT,
+    winning_tip TEXT,
+    recorded_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS token_screenings (
+    token TEXT PRIMARY KEY,
+    passed INTEGER NOT NULL,
+    reason TEXT,
+    screened_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_opportunities_timestamp ON opportunities (timestamp);
+CREATE INDEX IF NOT EXISTS idx_opportunities_id ON opportunities (id);
+CREATE INDEX IF NOT EXISTS idx_trades_created_at ON trades (created_at);
+CREATE INDEX IF NOT EXISTS idx_trades_tx_hash ON trades (tx_hash);
+CREATE INDEX IF NOT EXISTS idx_trades_opportunity_id ON trades (opportunity_id);
+CREATE INDEX IF NOT EXISTS idx_tx_replacements_first ON tx_replacements (first_tx_hash);
+CREATE INDEX IF NOT EXISTS idx_route_reverts_reverted_at ON route_reverts (reverted_at);
+"#;
+
+/// Columns added after the first release, created on databases that predate them
+const MIGRATIONS: [(&str, &str, &str); 10] = [
+    ("opportunities", "config_hash", "TEXT"),
+    ("trades", "config_hash", "TEXT"),
+    ("opportunities", "pinned_prices", "TEXT"),
+    ("trades", "spread_capture_usd", "REAL"),
+    ("trades", "mev_refund_usd", "REAL"),
+    ("trades", "gas_cost_usd", "REAL"),
+    ("trades", "tip_cost_usd", "REAL"),
+    ("trades", "contract_profit_usd", "REAL"),
+    ("trades", "private_tx", "INTEGER"),
+    ("route_states", "quarantined_until", "INTEGER"),
+];
+
+/// Columns of an opportunity row, besides its row key
+const OPPORTUNITY_COLUMNS: &str = "id, timestamp, source_dex, target_dex, token_path, \
+     token_symbols, estimated_profit, required_loan_amount, estimated_gas_cost, net_profit, \
+     confidence_score, variant, config_hash, pinned_prices";
+
+/// Lifecycle status of a recorded trade
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeStatus {
+    /// Transaction built but not yet submitted
+    Built,
+
+    /// Transaction (or its bundle) submitted
+    Submitted,
+
+    /// Submission failed (validation, simulation or relay error)
+    Failed,
+
+    /// Transaction included on-chain, but not yet deep enough to count as realized
+    Provisional,
+
+    /// Transaction confirmed on-chain and succeeded
+    Confirmed,
+
+    /// Transaction included on-chain but reverted
+    Reverted,
+}
+
+impl TradeStatus {
+    /// Get the name stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TradeStatus::Built => "built",
+            TradeStatus::Submitted => "submitted",
+            TradeStatus::Failed => "failed",
+            TradeStatus::Provisional => "provisional",
+            TradeStatus::Confirmed => "confirmed",
+            TradeStatus::Reverted => "reverted",
+        }
+    }
+}
+
+/// A stored opportunity
+#[derive(Debug, Clone)]
+pub struct OpportunityRecord {
+    /// Opportunity ID
+    pub id: String,
+
+    /// Timestamp when the opportunity was identified
+    pub timestamp: u64,
+
+    /// Source DEX
+    pub source_dex: String,
+
+    /// Target DEX
+    pub target_dex: String,
+
+    /// Token path
+    pub token_path: Vec<Address>,
+
+    /// Token path as symbols (e.g. `WETH -> USDC -> WETH`)
+    pub token_symbols: String,
+
+    /// Estimated profit in USD
+    pub estimated_profit: f64,
+
+    /// Net profit in USD after estimated gas
+    pub net_profit: f64,
+
+    /// Strategy variant that evaluated the opportunity
+    pub variant: String,
+
+    /// Hash of the configuration in effect when the opportunity was recorded
+    pub config_hash: Option<String>,
+
+    /// Oracle prices the opportunity was evaluated with, as JSON keyed by token address
+    pub pinned_prices: Option<String>,
+}
+
+/// A stored trade (built transaction and its outcome)
+#[derive(Debug, Clone)]
+pub struct TradeRecord {
+    /// Trade ID
+    pub id: i64,
+
+    /// ID of the opportunity the trade was built from
+    pub opportunity_id: String,
+
+    /// Timestamp when the trade was built
+    pub created_at: u64,
+
+    /// Token path as symbols (e.g. `WETH -> USDC -> WETH`)
+    pub token_symbols: String,
+
+    /// Strategy variant that handled the trade
+    pub variant: String,
+
+    /// Lifecycle status
+    pub status: String,
+
+    /// Transaction hash, once submitted
+    pub tx_hash: Option<H256>,
+
+    /// Bundle hash, if submitted as part of a bundle
+    pub bundle_hash: Option<String>,
+
+    /// Block the transaction was included in
+    pub block_number: Option<u64>,
+
+    /// Estimated profit in USD
+    pub estimated_profit: f64,
+
+    /// Realized profit in USD after gas
+    pub realized_pnl: Option<f64>,
+
+    /// Error message, if the trade failed
+    pub error: Option<String>,
+
+    /// Hash of the configuration in effect when the trade was built
+    pub config_hash: Option<String>,
+}
+
+/// Aggregated results of one strategy variant
+#[derive(Debug, Clone)]
+pub struct VariantStats {
+    /// Variant name
+    pub variant: String,
+
+    /// Number of trades built
+    pub trades: u64,
+
+    /// Number of trades confirmed successfully
+    pub confirmed: u64,
+
+    /// Number of trades that reverted or failed
+    pub failed: u64,
+
+    /// Total realized PnL in USD
+    pub realized_pnl: f64,
+}
+
+/// Persisted loss-throttling state of one route
+#[derive(Debug, Clone)]
+pub struct RouteStateRecord {
+    /// Route key
+    pub route: String,
+
+    /// Consecutive realized losses or reverts
+    pub consecutive_losses: u32,
+
+    /// Unix timestamp until which the route is suspended (0 if not suspended)
+    pub suspended_until: u64,
+
+    /// Unix timestamp until which the route is quarantined after reverts (0 if not quarantined)
+    pub quarantined_until: u64,
+}
+
+/// Trade activity of one token path
+#[derive(Debug, Clone)]
+pub struct PathActivityRecord {
+    /// Token path
+    pub token_path: Vec<Address>,
+
+    /// Number of trades built
+    pub trades: u64,
+
+    /// Number of trades confirmed successfully
+    pub confirmed: u64,
+
+    /// Number of trades that reverted or failed
+    pub failed: u64,
+
+    /// Unix timestamp of the most recent trade
+    pub last_trade: u64,
+
+    /// Total realized PnL in USD
+    pub realized_pnl: f64,
+}
+
+/// Outcome of screening a token before its pools were added
+#[derive(Debug, Clone)]
+pub struct TokenScreeningRecord {
+    /// Token address
+    pub token: Address,
+
+    /// Whether the token passed screening
+    pub passed: bool,
+
+    /// Why the token failed screening
+    pub reason: Option<String>,
+
+    /// Unix timestamp of the screening
+    pub screened_at: u64,
+}
+
+/// A configuration version seen at startup
+#[derive(Debug, Clone)]
+pub struct ConfigVersionRecord {
+    /// Configuration hash
+    pub hash: String,
+
+    /// Redacted configuration, as JSON
+    pub snapshot: String,
+}
+
+/// SQLite-backed trade history
+pub struct Storage {
+    connection: Mutex<Connection>,
+    config_hash: String,
+}
+
+/// Open (and migrate) the trade history database configured in `storage.path`
+pub async fn create_storage(config: &Arc<Config>) -> Result<Arc<Storage>> {
+    let path = &config.storage.path;
+    if let Some(parent) = Path::new(path).parent().and_then(|p| p.to_str()) {
+        if !parent.is_empty() {
+            create_directory_if_not_exists(parent)?;
+        }
+    }
+
+    let connection = Connection::open(path)
+        .with_context(|| format!("Failed to open storage database at {}", path))?;
+
+    // Readers (reports, observers) then never block the trading process's writes
+    let journal_mode: String =
+        connection.query_row("PRAGMA journal_mode=WAL", [], |row| row.get(0))?;
+    if !journal_mode.eq_ignore_ascii_case("wal") {
+        log::warn!("Storage journal mode is {} instead of WAL", journal_mode);
+    }
+
+    let storage = Storage::from_connection(connection, config::config_hash(config))?;
+
+    info!("Trade history storage opened at {}", path);
+
+    Ok(Arc::new(storage))
+}
+
+/// Attach read-only to the trade history database of a running bot
+///
+/// The schema and views are left as the owning process created them, and every write through
+/// the returned storage fails.
+pub async fn open_read_only(config: &Arc<Config>) -> Result<Arc<Storage>> {
+    let path = &config.storage.path;
+    let connection = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .with_context(|| format!("Failed to open storage database at {} read-only", path))?;
+    connection.busy_timeout(Duration::from_secs(5))?;
+
+    info!("Trade history storage attached read-only at {}", path);
+
+    Ok(Arc::new(Storage {
+        connection: Mutex::new(connection),
+        config_hash: config::config_hash(config),
+    }))
+}
+
+impl Storage {
+    /// Wrap an open connection, creating the schema if needed
+    ///
+    /// Records written through the storage are tagged with `config_hash`.
+    pub fn from_connection(connection: Connection, config_hash: String) -> Result<Self> {
+        connection
+            .execute_batch(SCHEMA)
+            .context("Failed to initialize storage schema")?;
+        migrate(&connection).context("Failed to migrate storage schema")?;
+        analytics::create_views(&connection).context("Failed to create analytics views")?;
+
+        Ok(Self {
+            connection: Mutex::new(connection),
+            config_hash,
+        })
+    }
+
+    /// Write everything committed so far from the write-ahead log into the database file
+    pub fn flush(&self) -> Result<()> {
+        self.with_connection(|conn| {
+            conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+        })
+    }
+
+    /// Run a closure with the locked connection
+    fn with_connection<T>(&self, f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T> {
+        let connection = self
+            .connection
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Storage connection mutex poisoned"))?;
+        f(&connection).context("Storage query failed")
+    }
+
+    /// Record an identified opportunity
+    ///
+    /// Every detection is a row of its own: the ID names the route, which is detected again
+    /// whenever its pools move out of line.
+    pub fn record_opportunity(&self, opportunity: &ArbitrageOpportunity) -> Result<()> {
+        self.with_connection(|conn| {
+            conn.execute(
+                &format!(
+                    "INSERT INTO opportunities ({}) \
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                    OPPORTUNITY_COLUMNS
+                ),
+                params![
+                    opportunity.id,
+                    opportunity.timestamp as i64,
+                    opportunity.source_dex,
+                    opportunity.target_dex,
+                    encode_path(&opportunity.token_path),
+                    tokens::path_label(&opportunity.token_path),
+                    opportunity.estimated_profit,
+                    opportunity.required_loan_amount,
+                    opportunity.estimated_gas_cost,
+                    opportunity.net_profit,
+                    opportunity.confidence_score,
+                    opportunity.variant.name,
+                    self.config_hash,
+                    opportunity.pinned_prices.to_json(),
+                ],
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Record a built transaction, returning the trade ID used for later updates
+    pub fn record_transaction(&self, tx: &ArbitrageTransaction) -> Result<i64> {
+        let now = current_timestamp() as i64;
+        self.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO trades (opportunity_id, created_at, token_path, token_symbols, \
+                 dex_path, estimated_gas, estimated_gas_price, estimated_profit, use_mev_share, \
+                 private_tx, variant, status, config_hash, updated_at) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?2)",
+                params![
+                    tx.opportunity_id,
+                    now,
+                    encode_path(&tx.token_path),
+                    tokens::path_label(&tx.token_path),
+                    tx.dex_path().join(","),
+                    tx.estimated_gas.to_string(),
+                    tx.estimated_gas_price.to_string(),
+                    tx.estimated_profit,
+                    tx.use_mev_share,
+                    tx.private.is_some(),
+                    tx.variant.name,
+                    TradeStatus::Built.as_str(),
+                    self.config_hash,
+                ],
+            )?;
+            Ok(conn.last_insert_rowid())
+        })
+    }
+
+    /// Mark a trade as submitted
+    pub fn mark_submitted(
+        &self,
+        trade_id: i64,
+        tx_hash: Option<H256>,
+        bundle_hash: Option<&str>,
+    ) -> Result<()> {
+        self.with_connection(|conn| {
+            conn.execute(
+                "UPDATE trades SET status = ?2, tx_hash = ?3, bundle_hash = ?4, updated_at = ?5 \
+                 WHERE id = ?1",
+                params![
+                    trade_id,
+                    TradeStatus::Submitted.as_str(),
+                    tx_hash.map(|hash| format!("{:?}", hash)),
+                    bundle_hash,
+                    current_timestamp() as i64,
+                ],
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Point a trade at the transaction that replaced its submitted one
+    pub fn replace_tx_hash(&self, tx_hash: H256, replacement: H256) -> Result<()> {
+        self.with_connection(|conn| {
+            conn.execute(
+                "UPDATE trades SET tx_hash = ?2, updated_at = ?3 WHERE tx_hash = ?1",
+                params![
+                    format!("{:?}", tx_hash),
+                    format!("{:?}", replacement),
+                    current_timestamp() as i64,
+                ],
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Record that `replacement` replaced `tx_hash`, of the chain started by `first_tx_hash`,
+    /// with the same nonce at `fee_per_gas` (in wei)
+    pub fn record_replacement(
+        &self,
+        first_tx_hash: H256,
+        tx_hash: H256,
+        replacement: H256,
+        nonce: U256,
+        fee_per_gas: U256,
+    ) -> Result<()> {
+        self.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO tx_replacements (first_tx_hash, tx_hash, replacement_tx_hash, \
+                 nonce, fee_per_gas, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    format!("{:?}", first_tx_hash),
+                    format!("{:?}", tx_hash),
+                    format!("{:?}", replacement),
+                    nonce.as_u64() as i64,
+                    fee_per_gas.to_string(),
+                    current_timestamp() as i64,
+                ],
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Mark a trade as failed before reaching the chain
+    pub fn mark_failed(&self, trade_id: i64, error: &str) -> Result<()> {
+        self.with_connection(|conn| {
+            conn.execute(
+                "UPDATE trades SET status = ?2, error = ?3, updated_at = ?4 WHERE id = ?1",
+                params![
+                    trade_id,
+                    TradeStatus::Failed.as_str(),
+                    error,
+                    current_timestamp() as i64,
+                ],
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Record that a submitted transaction was included, pending enough confirmations
+    pub fn record_inclusion(&self, result: &TransactionResult) -> Result<()> {
+        self.with_connection(|conn| {
+            conn.execute(
+                "UPDATE trades SET status = ?2, block_number = ?3, gas_used = ?4, \
+                 actual_cost = ?5, updated_at = ?6 WHERE tx_hash = ?1",
+                params![
+                    format!("{:?}", result.tx_hash),
+                    TradeStatus::Provisional.as_str(),
+                    result.block_number.map(|block| block as i64),
+                    result.gas_used.map(|gas| gas.to_string()),
+                    result.actual_cost.map(|cost| cost.to_string()),
+                    current_timestamp() as i64,
+                ],
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Record the final on-chain outcome of a submitted transaction, and its realized PnL split
+    /// by source (in USD) when it could be priced
+    pub fn record_result(
+        &self,
+        result: &TransactionResult,
+        attribution: Option<&ProfitAttribution>,
+    ) -> Result<()> {
+        let status = if result.success {
+            TradeStatus::Confirmed
+        } else {
+            TradeStatus::Reverted
+        };
+
+        self.with_connection(|conn| {
+            conn.execute(
+                "UPDATE trades SET status = ?2, block_number = ?3, gas_used = ?4, \
+                 actual_cost = ?5, realized_pnl = ?6, error = ?7, updated_at = ?8, \
+                 spread_capture_usd = ?9, mev_refund_usd = ?10, gas_cost_usd = ?11, \
+                 tip_cost_usd = ?12 WHERE tx_hash = ?1",
+                params![
+                    format!("{:?}", result.tx_hash),
+                    status.as_str(),
+                    result.block_number.map(|block| block as i64),
+                    result.gas_used.map(|gas| gas.to_string()),
+                    result.actual_cost.map(|cost| cost.to_string()),
+                    attribution.map(|a| a.realized_pnl()),
+                    result.error,
+                    current_timestamp() as i64,
+                    attribution.map(|a| a.spread_capture_usd),
+                    attribution.map(|a| a.mev_refund_usd),
+                    attribution.map(|a| a.gas_cost_usd),
+                    attribution.map(|a| a.tip_cost_usd),
+                ],
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Record the profit the contract's `ArbitrageExecuted` event reported for a trade (in USD)
+    pub fn record_contract_profit(&self, tx_hash: H256, profit_usd: f64) -> Result<()> {
+        self.with_connection(|conn| {
+            conn.execute(
+                "UPDATE trades SET contract_profit_usd = ?2 WHERE tx_hash = ?1",
+                params![format!("{:?}", tx_hash), profit_usd],
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Record the token deltas of a settled trade, and add it to its daily and weekly totals
+    /// when it could be priced
+    pub fn record_trade_pnl(&self, pnl: &TradePnl) -> Result<()> {
+        let tx_hash = format!("{:?}", pnl.tx_hash);
+        self.with_connection(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            for delta in &pnl.deltas {
+                tx.execute(
+                    "INSERT OR REPLACE INTO trade_token_deltas (tx_hash, token, token_symbol, \
+                     amount, amount_decimal, amount_usd, recorded_at) \
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![
+                        tx_hash,
+                        format!("{:?}", delta.token),
+                        tokens::token_label(delta.token),
+                        delta.amount.to_string(),
+                        delta.amount_decimal,
+                        delta.usd,
+                        pnl.settled_at as i64,
+                    ],
+                )?;
+            }
+
+            if let (Some(realized), Some(slippage)) = (pnl.realized_usd, pnl.slippage_usd()) {
+                for period in [PnlPeriod::Day, PnlPeriod::Week] {
+                    tx.execute(
+                        "INSERT INTO pnl_periods (period, period_start, trades, estimated_usd, \
+                         realized_usd, slippage_usd, worst_slippage_usd, updated_at) \
+                         VALUES (?1, ?2, 1, ?3, ?4, ?5, ?5, ?6) \
+                         ON CONFLICT (period, period_start) DO UPDATE SET \
+                         trades = trades + 1, \
+                         estimated_usd = estimated_usd + excluded.estimated_usd, \
+                         realized_usd = realized_usd + excluded.realized_usd, \
+                         slippage_usd = slippage_usd + excluded.slippage_usd, \
+                         worst_slippage_usd = MAX(worst_slippage_usd, excluded.slippage_usd), \
+                         updated_at = excluded.updated_at",
+                        params![
+                            period.as_str(),
+                            period.start(pnl.settled_at) as i64,
+                            pnl.estimated_profit,
+                            realized,
+                            slippage,
+                            current_timestamp() as i64,
+                        ],
+                    )?;
+                }
+            }
+
+            tx.commit()
+        })?;
+
+        Ok(())
+    }
+
+    /// Record an opportunity another sender's transaction took
+    pub fn record_lost_race(&self, race: &LostRace) -> Result<()> {
+        self.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO lost_races (opportunity_id, detected_at, detected_block, \
+                 token_symbols, dex_path, estimated_profit, block_number, winner_tx_hash, \
+                 winner_sender, winner_contract, winner_tx_index, winner_priority_fee, \
+                 winner_pools, recorded_at) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                params![
+                    race.opportunity_id,
+                    race.detected_at as i64,
+                    race.detected_block as i64,
+                    tokens::path_label(&race.token_path),
+                    dex_names(&race.hops).join(","),
+                    race.estimated_profit,
+                    race.block_number as i64,
+                    format!("{:?}", race.tx_hash),
+                    format!("{:?}", race.sender),
+                    race.contract.map(|contract| format!("{:?}", contract)),
+                    race.tx_index as i64,
+                    race.priority_fee.to_string(),
+                    race.pools
+                        .iter()
+                        .map(|&pool| tokens::pool_label(pool))
+                        .collect::<Vec<_>>()
+                        .join(" -> "),
+                    current_timestamp() as i64,
+                ],
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Record what happened to one submission of a bundle
+    pub fn record_bundle_outcome(&self, report: &BundleReport) -> Result<()> {
+        let stats = report.stats.as_ref();
+        let winner = report.winner.as_ref();
+        self.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO bundle_outcomes (bundle_hash, first_block, last_block, rebids, \
+                 outcome, simulated, considered_by, sealed_by, priority_fee, winner_tx_hash, \
+                 winner_sender, winner_priority_fee, winning_tip, recorded_at) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                params![
+                    report.bundle_hash,
+                    report.first_block as i64,
+                    report.last_block as i64,
+                    report.rebids,
+                    report.outcome.name(),
+                    stats.map(|stats| stats.is_simulated),
+                    stats.map(|stats| stats.considered_by_builders_at.len() as i64),
+                    stats.map(|stats| stats.sealed_by_builders_at.len() as i64),
+                    report.priority_fee.to_string(),
+                    winner.map(|winner| format!("{:?}", winner.tx_hash)),
+                    winner.map(|winner| format!("{:?}", winner.sender)),
+                    winner.map(|winner| winner.priority_fee.to_string()),
+                    report.winning_tip.map(|tip| tip.to_string()),
+                    current_timestamp() as i64,
+                ],
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Get the most recent opportunities
+    pub fn recent_opportunities(&self, limit: usize) -> Result<Vec<OpportunityRecord>> {
+        self.with_connection(|conn| {
+            let mut statement = conn.prepare(
+                "SELECT id, timestamp, source_dex, target_dex, token_path, token_symbols, \
+                 estimated_profit, net_profit, variant, config_hash, pinned_prices \
+                 FROM opportunities \
+                 ORDER BY timestamp DESC LIMIT ?1",
+            )?;
+            let rows = statement.query_map(params![limit as i64], |row| {
+                Ok(OpportunityRecord {
+                    id: row.get(0)?,
+                    timestamp: row.get::<_, i64>(1)? as u64,
+                    source_dex: row.get(2)?,
+                    target_dex: row.get(3)?,
+                    token_path: decode_path(&row.get::<_, String>(4)?),
+                    token_symbols: row.get(5)?,
+                    estimated_profit: row.get(6)?,
+                    net_profit: row.get(7)?,
+                    variant: row.get(8)?,
+                    config_hash: row.get(9)?,
+                    pinned_prices: row.get(10)?,
+                })
+            })?;
+            rows.collect()
+        })
+    }
+
+    /// Get the most recent trades
+    pub fn recent_trades(&self, limit: usize) -> Result<Vec<TradeRecord>> {
+        self.with_connection(|conn| {
+            let mut statement = conn.prepare(&format!(
+                "SELECT {} FROM trades ORDER BY id DESC LIMIT ?1",
+                TRADE_COLUMNS
+            ))?;
+            let rows = statement.query_map(params![limit as i64], trade_from_row)?;
+            rows.collect()
+        })
+    }
+
+    /// Get a trade by its transaction hash
+    pub fn trade_by_tx_hash(&self, tx_hash: H256) -> Result<Option<TradeRecord>> {
+        self.with_connection(|conn| {
+            conn.query_row(
+                &format!("SELECT {} FROM trades WHERE tx_hash = ?1", TRADE_COLUMNS),
+                params![format!("{:?}", tx_hash)],
+                trade_from_row,
+            )
+            .optional()
+        })
+    }
+
+    /// Get trades submitted (or still provisional) whose final outcome was never recorded
+    pub fn submitted_trades(&self) -> Result<Vec<TradeRecord>> {
+        self.with_connection(|conn| {
+            let mut statement = conn.prepare(&format!(
+                "SELECT {} FROM trades WHERE status IN (?1, ?2) ORDER BY id",
+                TRADE_COLUMNS
+            ))?;
+            let rows = statement.query_map(
+                params![
+                    TradeStatus::Submitted.as_str(),
+                    TradeStatus::Provisional.as_str()
+                ],
+                trade_from_row,
+            )?;
+            rows.collect()
+        })
+    }
+
+    /// Get the total realized PnL (in USD) of trades built since `since` (Unix seconds)
+    pub fn realized_pnl_since(&self, since: u64) -> Result<f64> {
+        self.with_connection(|conn| {
+            conn.query_row(
+                "SELECT COALESCE(SUM(realized_pnl), 0.0) FROM trades WHERE created_at >= ?1",
+                params![since as i64],
+                |row| row.get(0),
+            )
+        })
+    }
+
+    /// Get per-variant trade statistics, for evaluating strategy experiments
+    pub fn variant_stats(&self) -> Result<Vec<VariantStats>> {
+        self.with_connection(|conn| {
+            let mut statement = conn.prepare(
+                "SELECT variant, COUNT(*), \
+                 SUM(CASE WHEN status = 'confirmed' THEN 1 ELSE 0 END), \
+                 SUM(CASE WHEN status IN ('reverted', 'failed') THEN 1 ELSE 0 END), \
+                 COALESCE(SUM(realized_pnl), 0.0) \
+                 FROM trades GROUP BY variant ORDER BY variant",
+            )?;
+            let rows = statement.query_map([], |row| {
+                Ok(VariantStats {
+                    variant: row.get(0)?,
+                    trades: row.get::<_, i64>(1)? as u64,
+                    confirmed: row.get::<_, i64>(2)? as u64,
+                    failed: row.get::<_, i64>(3)? as u64,
+                    realized_pnl: row.get(4)?,
+                })
+            })?;
+            rows.collect()
+        })
+    }
+
+    /// Get the number of trades built in each UTC hour of the day
+    pub fn trade_hours(&self) -> Result<[u64; 24]> {
+        self.with_connection(|conn| {
+            let mut statement = conn
+                .prepare("SELECT (created_at % 86400) / 3600, COUNT(*) FROM trades GROUP BY 1")?;
+            let mut hours = [0; 24];
+            let rows = statement
+                .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)))?;
+            for row in rows {
+                let (hour, trades) = row?;
+                if let Some(count) = hours.get_mut(hour as usize) {
+                    *count = trades as u64;
+                }
+            }
+            Ok(hours)
+        })
+    }
+
+    /// Get per-token-path trade activity
+    pub fn path_activity(&self) -> Result<Vec<PathActivityRecord>> {
+        self.with_connection(|conn| {
+            let mut statement = conn.prepare(
+                "SELECT token_path, COUNT(*), \
+                 SUM(CASE WHEN status = 'confirmed' THEN 1 ELSE 0 END), \
+                 SUM(CASE WHEN status IN ('reverted', 'failed') THEN 1 ELSE 0 END), \
+                 MAX(created_at), COALESCE(SUM(realized_pnl), 0.0) \
+                 FROM trades GROUP BY token_path",
+            )?;
+            let rows = statement.query_map([], |row| {
+                Ok(PathActivityRecord {
+                    token_path: decode_path(&row.get::<_, String>(0)?),
+                    trades: row.get::<_, i64>(1)? as u64,
+                    confirmed: row.get::<_, i64>(2)? as u64,
+                    failed: row.get::<_, i64>(3)? as u64,
+                    last_trade: row.get::<_, i64>(4)? as u64,
+                    realized_pnl: row.get(5)?,
+                })
+            })?;
+            rows.collect()
+        })
+    }
+
+    /// Load the loss-throttling state of every route
+    pub fn route_states(&self) -> Result<Vec<RouteStateRecord>> {
+        self.with_connection(|conn| {
+            let mut statement = conn.prepare(
+                "SELECT route, consecutive_losses, suspended_until, quarantined_until \
+                 FROM route_states",
+            )?;
+            let rows = statement.query_map([], |row| {
+                Ok(RouteStateRecord {
+                    route: row.get(0)?,
+                    consecutive_losses: row.get(1)?,
+                    suspended_until: row.get::<_, i64>(2)? as u64,
+                    quarantined_until: row.get::<_, Option<i64>>(3)?.unwrap_or_default() as u64,
+                })
+            })?;
+            rows.collect()
+        })
+    }
+
+    /// Save the loss-throttling state of a route
+    pub fn save_route_state(&self, state: &RouteStateRecord) -> Result<()> {
+        self.with_connection(|conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO route_states (route, consecutive_losses, \
+                 suspended_until, updated_at, quarantined_until) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    state.route,
+                    state.consecutive_losses,
+                    state.suspended_until as i64,
+                    current_timestamp() as i64,
+                    state.quarantined_until as i64,
+                ],
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Record a revert on a route, dropping the reverts of every route before `expire_before`
+    pub fn record_route_revert(
+        &self,
+        route: &str,
+        reverted_at: u64,
+        expire_before: u64,
+    ) -> Result<()> {
+        self.with_connection(|conn| {
+            conn.execute(
+                "DELETE FROM route_reverts WHERE reverted_at < ?1",
+                params![expire_before as i64],
+            )?;
+            conn.execute(
+                "INSERT INTO route_reverts (route, reverted_at) VALUES (?1, ?2)",
+                params![route, reverted_at as i64],
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Load the reverts recorded since a Unix timestamp, oldest first
+    pub fn route_reverts_since(&self, since: u64) -> Result<Vec<(String, u64)>> {
+        self.with_connection(|conn| {
+            let mut statement = conn.prepare(
+                "SELECT route, reverted_at FROM route_reverts WHERE reverted_at > ?1 \
+                 ORDER BY reverted_at",
+            )?;
+            let rows = statement.query_map(params![since as i64], |row| {
+                Ok((row.get(0)?, row.get::<_, i64>(1)? as u64))
+            })?;
+            rows.collect()
+        })
+    }
+
+    /// Hash of the configuration records are tagged with
+    pub fn config_hash(&self) -> &str {
+        &self.config_hash
+    }
+
+    /// Record the configuration in effect, returning the previously seen version if it differs
+    pub fn record_config(&self, snapshot: &str) -> Result<Option<ConfigVersionRecord>> {
+        let now = current_timestamp() as i64;
+        self.with_connection(|conn| {
+            let previous = conn
+                .query_row(
+                    "SELECT hash, snapshot FROM config_versions ORDER BY last_seen DESC LIMIT 1",
+                    [],
+                    |row| {
+                        Ok(ConfigVersionRecord {
+                            hash: row.get(0)?,
+                            snapshot: row.get(1)?,
+                        })
+                    },
+                )
+                .optional()?;
+
+            conn.execute(
+                "INSERT INTO config_versions (hash, snapshot, first_seen, last_seen) \
+                 VALUES (?1, ?2, ?3, ?3) \
+                 ON CONFLICT (hash) DO UPDATE SET last_seen = ?3",
+                params![self.config_hash, snapshot, now],
+            )?;
+
+            Ok(previous.filter(|previous| previous.hash != self.config_hash))
+        })
+    }
+
+    /// Run one of the predefined analytics views
+    pub fn report(&self, view: &str) -> Result<ReportTable> {
+        if !ANALYTICS_VIEWS.iter().any(|(name, _, _)| *name == view) {
+            anyhow::bail!(
+                "Unknown report {}; available: {}",
+                view,
+                ANALYTICS_VIEWS
+                    .iter()
+                    .map(|(name, _, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+
+        self.with_connection(|conn| analytics::query_view(conn, view))
+    }
+
+    /// Load the outcome of every token screening
+    pub fn token_screenings(&self) -> Result<Vec<TokenScreeningRecord>> {
+        self.with_connection(|conn| {
+            let mut statement =
+                conn.prepare("SELECT token, passed, reason, screened_at FROM token_screenings")?;
+            let rows = statement.query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, bool>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, i64>(3)? as u64,
+                ))
+            })?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()
+        })
+        .map(|rows| {
+            rows.into_iter()
+                .filter_map(|(token, passed, reason, screened_at)| {
+                    Some(TokenScreeningRecord {
+                        token: token.parse().ok()?,
+                        passed,
+                        reason,
+                        screened_at,
+                    })
+                })
+                .collect()
+        })
+    }
+
+    /// Save the outcome of a token screening
+    pub fn save_token_screening(&self, record: &TokenScreeningRecord) -> Result<()> {
+        self.with_connection(|conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO token_screenings (token, passed, reason, screened_at) \
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    format!("{:?}", record.token),
+                    record.passed,
+                    record.reason,
+                    record.screened_at as i64,
+                ],
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Get the next nonce recorded for a wallet
+    pub fn wallet_nonce(&self, address: Address) -> Result<Option<u64>> {
+        self.with_connection(|conn| {
+            conn.query_row(
+                "SELECT next_nonce FROM wallet_nonces WHERE address = ?1",
+                params![format!("{:?}", address)],
+                |row| Ok(row.get::<_, i64>(0)? as u64),
+            )
+            .optional()
+        })
+    }
+
+    /// Record the next nonce of a wallet
+    pub fn save_wallet_nonce(&self, address: Address, next_nonce: u64) -> Result<()> {
+        self.with_connection(|conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO wallet_nonces (address, next_nonce, updated_at) \
+                 VALUES (?1, ?2, ?3)",
+                params![
+                    format!("{:?}", address),
+                    next_nonce as i64,
+                    current_timestamp() as i64,
+                ],
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Get the ArbitrageExecutor contract deployed by the bot on a chain
+    pub fn deployed_contract(&self, chain_id: u64) -> Result<Option<Address>> {
+        let address = self.with_connection(|conn| {
+            conn.query_row(
+                "SELECT address FROM contract_deployments WHERE chain_id = ?1",
+                params![chain_id as i64],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+        })?;
+
+        address
+            .map(|address| {
+                address
+                    .parse()
+                    .with_context(|| format!("Invalid deployed contract address {}", address))
+            })
+            .transpose()
+    }
+
+    /// Record the ArbitrageExecutor contract deployed by the bot on a chain
+    pub fn save_deployed_contract(&self, chain_id: u64, address: Address) -> Result<()> {
+        self.with_connection(|conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO contract_deployments (chain_id, address, deployed_at) \
+                 VALUES (?1, ?2, ?3)",
+                params![
+                    chain_id as i64,
+                    format!("{:?}", address),
+                    current_timestamp() as i64,
+                ],
+            )
+        })?;
+
+        Ok(())
+    }
+}
+
+/// Columns selected for a `TradeRecord`, in `trade_from_row` order
+const TRADE_COLUMNS: &str = "id, opportunity_id, created_at, token_symbols, variant, status, \
+                             tx_hash, bundle_hash, block_number, estimated_profit, \
+                             realized_pnl, error, config_hash";
+
+/// Build a `TradeRecord` from a row selected with `TRADE_COLUMNS`
+fn trade_from_row(row: &Row) -> rusqlite::Result<TradeRecord> {
+    Ok(TradeRecord {
+        id: row.get(0)?,
+        opportunity_id: row.get(1)?,
+        created_at: row.get::<_, i64>(2)? as u64,
+        token_symbols: row.get(3)?,
+        variant: row.get(4)?,
+        status: row.get(5)?,
+        tx_hash: row
+            .get::<_, Option<String>>(6)?
+            .and_then(|hash| hash.parse().ok()),
+        bundle_hash: row.get(7)?,
+        block_number: row.get::<_, Option<i64>>(8)?.map(|block| block as u64),
+        estimated_profit: row.get(9)?,
+        realized_pnl: row.get(10)?,
+        error: row.get(11)?,
+        config_hash: row.get(12)?,
+    })
+}
+
+/// Add columns missing from databases created before they were introduced, and give
+/// opportunities keyed by their route a row per detection
+fn migrate(connection: &Connection) -> rusqlite::Result<()> {
+    for (table, column, declaration) in MIGRATIONS {
+        if !has_column(connection, table, column)? {
+            connection.execute_batch(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                table, column, declaration
+            ))?;
+        }
+    }
+
+    if !has_column(connection, "opportunities", "row_id")? {
+        // The views and indexes of the old table would follow it when renamed; the schema and
+        // the views are created again for the new one
+        for (name, _, _) in ANALYTICS_VIEWS {
+            connection.execute_batch(&format!("DROP VIEW IF EXISTS {}", name))?;
+        }
+        let transaction = connection.unchecked_transaction()?;
+        transaction.execute_batch(
+            "DROP INDEX IF EXISTS idx_opportunities_timestamp; \
+             DROP INDEX IF EXISTS idx_opportunities_id; \
+             ALTER TABLE opportunities RENAME TO opportunities_by_route;",
+        )?;
+        transaction.execute_batch(SCHEMA)?;
+        transaction.execute_batch(&format!(
+            "INSERT INTO opportunities ({columns}) \
+             SELECT {columns} FROM opportunities_by_route ORDER BY timestamp; \
+             DROP TABLE opportunities_by_route;",
+            columns = OPPORTUNITY_COLUMNS
+        ))?;
+        transaction.commit()?;
+    }
+
+    Ok(())
+}
+
+/// Check whether a table has a column
+fn has_column(connection: &Connection, table: &str, column: &str) -> rusqlite::Result<bool> {
+    let mut statement = connection.prepare(&format!("PRAGMA table_info({})", table))?;
+    let names = statement
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(names.iter().any(|name| name == column))
+}
+
+/// Encode a token path as a comma-separated list of addresses
+fn encode_path(path: &[Address]) -> String {
+    path.iter()
+        .map(|address| format!("{:?}", address))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Decode a token path stored by `encode_path`
+fn decode_path(path: &str) -> Vec<Address> {
+    path.split(',')
+        .filter_map(|address| address.parse().ok())
+        .collect()
+}
@@ -0,0 +1,280 @@
+//! Backrun Strategy Module
+//!
+//! This module is responsible for the routes pending swaps open up. Each scan plays the pending
+//! swaps through the cached pool reserves, and the routes they make profitable are anticipated:
+//! their pairs are promoted so they are scanned on the block the swaps are mined in. Once one of
+//! a route's swaps reaches the pool cache, the route is re-quoted at the scan's quote block and
+//! becomes an opportunity if it still pays for its gas. Routes whose swaps stay unmined expire
+//! with them.
+
+use async_trait::async_trait;
+use ethers::types::{H256, U256};
+use log::{debug, info, warn};
+use std::sync::{Arc, Mutex};
+
+use super::{ScanRound, Strategy, StrategyContext, StrategyVariant};
+use crate::config::FlashLoanProvider;
+use crate::dex::{dex_names, BlockTag};
+use crate::logging;
+use crate::mempool::MempoolMonitor;
+use crate::metrics;
+use crate::scanner::{find_pending_routes, ArbitrageOpportunity, PendingRoute};
+use crate::tokens;
+use crate::trace::{self, TraceStage};
+use crate::utils::{current_timestamp, u256_to_decimal};
+
+/// A route anticipated after pending swaps
+#[derive(Debug, Clone)]
+struct AnticipatedRoute {
+    route: PendingRoute,
+
+    /// Pending swaps the route was anticipated after
+    swaps: Vec<H256>,
+
+    /// Unix timestamp the route was anticipated at
+    anticipated_at: u64,
+}
+
+/// Backruns the routes pending swaps make profitable
+pub struct BackrunStrategy {
+    context: Arc<StrategyContext>,
+    mempool: Arc<MempoolMonitor>,
+    anticipated: Mutex<Vec<AnticipatedRoute>>,
+}
+
+impl BackrunStrategy {
+    /// Create the strategy for the swaps pending in `mempool`
+    pub fn new(context: Arc<StrategyContext>, mempool: Arc<MempoolMonitor>) -> Self {
+        Self {
+            context,
+            mempool,
+            anticipated: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Find the routes that pending swaps make profitable and promote their pairs, so they
+    /// are scanned on the block the swaps are mined in
+    async fn anticipate(&self) {
+        let Some(mut state) = self.mempool.take_pending_state().await else {
+            return;
+        };
+        let blacklisted = self.context.blacklisted();
+        let overrides = self.context.dex_interfaces.overrides();
+        state.pools.retain(|pool| {
+            overrides.allows(pool.dex_type, pool.address)
+                && !blacklisted.contains(&pool.token0)
+                && !blacklisted.contains(&pool.token1)
+        });
+
+        let (start_tokens, decimals) = self.context.start_tokens();
+        let routes = find_pending_routes(&state.pools, &state.touched, &start_tokens);
+        for route in &routes {
+            let token_a = route.token_path[0];
+            let profit = u256_to_decimal(
+                route.profit(),
+                decimals.get(&token_a).copied().unwrap_or(18),
+            );
+            info!(
+                "Anticipated {} via {} for {:.6} {} after {} pending swaps",
+                tokens::path_label(&route.token_path),
+                dex_names(&route.hops).join(" -> "),
+                profit,
+                tokens::token_label(token_a),
+                state.swaps.len()
+            );
+            trace::record(TraceStage::Candidate, || {
+                format!(
+                    "anticipated {} via {} after pending {:?}",
+                    tokens::path_label(&route.token_path),
+                    dex_names(&route.hops).join(" -> "),
+                    state.swaps
+                )
+            });
+            self.context.pair_tiers.promote(&route.token_path);
+        }
+
+        metrics::global().increment_counter("anticipated_routes", routes.len() as u64);
+        let now = current_timestamp();
+        self.anticipated
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend(routes.into_iter().map(|route| AnticipatedRoute {
+                route,
+                swaps: state.swaps.clone(),
+                anticipated_at: now,
+            }));
+    }
+
+    /// Take the anticipated routes one of whose swaps has been mined, dropping the expired ones
+    async fn take_mined(&self) -> Vec<AnticipatedRoute> {
+        let anticipated =
+            std::mem::take(&mut *self.anticipated.lock().unwrap_or_else(|e| e.into_inner()));
+        let pool_cache = self.context.dex_interfaces.pool_cache();
+        let now = current_timestamp();
+
+        let mut mined = Vec::new();
+        let mut waiting = Vec::new();
+        for route in anticipated {
+            let mut applied = false;
+            for &swap in &route.swaps {
+                if pool_cache.has_applied_swap(swap).await {
+                    applied = true;
+                    break;
+                }
+            }
+
+            if applied {
+                mined.push(route);
+            } else if now.saturating_sub(route.anticipated_at) <= self.mempool.pending_ttl_secs() {
+                waiting.push(route);
+            }
+        }
+
+        self.anticipated
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend(waiting);
+        mined
+    }
+
+    /// Quote a route through the cached pools at a block, returning what it pays out in the
+    /// first token
+    async fn quote(&self, route: &PendingRoute, block: BlockTag) -> Option<U256> {
+        let pool_cache = self.context.dex_interfaces.pool_cache();
+        let mut amount = route.amount_in;
+        for (pair, &(_, pool)) in route.token_path.windows(2).zip(&route.hops) {
+            amount = pool_cache.quote(pool, pair[0], amount, block).await?;
+        }
+
+        Some(amount)
+    }
+
+    /// Turn a mined route into an opportunity if it still pays for its gas at the scan's block
+    async fn evaluate(
+        &self,
+        anticipated: &AnticipatedRoute,
+        round: &ScanRound,
+    ) -> Option<ArbitrageOpportunity> {
+        let quote_block = round.quote_block;
+        let route = &anticipated.route;
+        let token_a = route.token_path[0];
+        let label = tokens::path_label(&route.token_path);
+
+        let amount_out = self.quote(route, quote_block).await?;
+        if amount_out <= route.amount_in {
+            trace::record(TraceStage::Rejection, || {
+                format!("backrun {}: unprofitable once the swaps were mined", label)
+            });
+            return None;
+        }
+
+        let pinned_prices = match self.context.price_oracle.pin_prices(&[token_a]).await {
+            Ok(pinned_prices) => pinned_prices,
+            Err(e) => {
+                warn!(
+                    "Failed to get the USD price of {}: {}",
+                    tokens::token_label(token_a),
+                    e
+                );
+                return None;
+            }
+        };
+        let price_usd = pinned_prices.usd(token_a).unwrap_or_default();
+        let decimals = tokens::global()
+            .token(token_a)
+            .map_or(18, |info| info.decimals);
+        let profit_usd = u256_to_decimal(amount_out - route.amount_in, decimals) * price_usd;
+        let loan_amount_usd = u256_to_decimal(route.amount_in, decimals) * price_usd;
+
+        let estimated_gas_cost = match self
+            .context
+            .estimate_gas_cost(&route.hops, &pinned_prices)
+            .await
+        {
+            Ok(gas_cost) => gas_cost,
+            Err(e) => {
+                warn!("Failed to cost the gas of backrun {}: {}", label, e);
+                return None;
+            }
+        };
+        let net_profit = profit_usd - estimated_gas_cost;
+        if net_profit <= 0.0 {
+            trace::record(TraceStage::Rejection, || {
+                format!(
+                    "backrun {}: profit ${:.2} does not cover gas ${:.2}",
+                    label, profit_usd, estimated_gas_cost
+                )
+            });
+            return None;
+        }
+
+        let confidence = self
+            .context
+            .assess_confidence(&route.token_path, &route.hops, route.amount_in, quote_block)
+            .await;
+
+        let dex_path = dex_names(&route.hops);
+        let id = format!(
+            "backrun_{}_{}",
+            route
+                .token_path
+                .iter()
+                .map(|&token| tokens::token_label(token))
+                .collect::<Vec<_>>()
+                .join("_"),
+            route
+                .hops
+                .iter()
+                .map(|&(dex_type, _)| (dex_type as u8).to_string())
+                .collect::<Vec<_>>()
+                .join("_")
+        );
+        Some(ArbitrageOpportunity {
+            span: logging::opportunity_span(&id),
+            id,
+            timestamp: current_timestamp(),
+            source_dex: dex_path.first().cloned().unwrap_or_default(),
+            target_dex: dex_path.last().cloned().unwrap_or_default(),
+            token_path: route.token_path.clone(),
+            hops: route.hops.clone(),
+            estimated_profit: profit_usd,
+            required_loan_amount: loan_amount_usd,
+            estimated_gas_cost,
+            net_profit,
+            confidence_score: confidence.score(),
+            variant: StrategyVariant::default(),
+            flash_loan_provider: FlashLoanProvider::Aave,
+            flash_loan_fee: 0.0,
+            input_amount: Some(route.amount_in),
+            pinned_prices,
+            quote_block,
+            latency: round.latency,
+        })
+    }
+}
+
+#[async_trait]
+impl Strategy for BackrunStrategy {
+    fn name(&self) -> &'static str {
+        "backrun"
+    }
+
+    async fn find_opportunities(&self, round: &ScanRound) -> Vec<ArbitrageOpportunity> {
+        let mined = self.take_mined().await;
+        debug!("{} anticipated routes had their swaps mined", mined.len());
+
+        let mut opportunities = Vec::new();
+        for anticipated in &mined {
+            if let Some(opportunity) = self.evaluate(anticipated, round).await {
+                info!("Found backrun opportunity: {}", opportunity);
+                trace::record(TraceStage::Candidate, || opportunity.to_string());
+                opportunities.push(opportunity);
+            }
+        }
+
+        // Look a block ahead at the routes pending swaps open up
+        self.anticipate().await;
+
+        opportunities
+    }
+}
@@ -0,0 +1,159 @@
+//! Confidence Scoring Module
+//!
+//! This module is responsible for how likely an opportunity is to pay out what it was quoted
+//! at. Four factors, each between 0 and 1, are multiplied into its confidence score:
+//!
+//! - freshness: how many blocks the pool cache has synced past the quote block
+//! - depth: how large a share of each pool's input reserve the trade takes
+//! - competition: how many pending swaps trade through the route's pools
+//! - history: how often trades on the route's pairs confirmed, starting from a prior so a pair
+//!   without trades is neither trusted nor distrusted much
+
+use ethers::types::{Address, U256};
+use log::debug;
+use std::collections::HashSet;
+use std::fmt;
+
+use super::StrategyContext;
+use crate::dex::{get_amount_out, pool_key, BlockTag, DexType};
+use crate::tokens;
+use crate::utils::u256_to_decimal;
+
+/// Freshness kept per block the quote block is behind the synced head
+const STALE_BLOCK_DECAY: f64 = 0.85;
+
+/// Depth lost per unit of input reserve a hop takes (a hop taking 10% keeps half)
+const DEPTH_SENSITIVITY: f64 = 10.0;
+
+/// Depth of a route with a pool the cache does not hold the reserves of
+const UNKNOWN_DEPTH: f64 = 0.9;
+
+/// Competition kept per pending swap through the route's pools
+const COMPETING_SWAP_DECAY: f64 = 0.8;
+
+/// Success rate assumed for a pair before its trades settle
+const PRIOR_SUCCESS_RATE: f64 = 0.9;
+
+/// Number of trades the prior success rate counts as
+const PRIOR_TRADES: f64 = 5.0;
+
+/// Factors of an opportunity's confidence
+#[derive(Debug, Clone, Copy)]
+pub struct Confidence {
+    pub freshness: f64,
+    pub depth: f64,
+    pub competition: f64,
+    pub history: f64,
+}
+
+impl Confidence {
+    /// Get the confidence score (0-100)
+    pub fn score(&self) -> u8 {
+        let confidence = self.freshness * self.depth * self.competition * self.history;
+        (confidence * 100.0).round().clamp(0.0, 100.0) as u8
+    }
+}
+
+impl fmt::Display for Confidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (freshness {:.2}, depth {:.2}, competition {:.2}, history {:.2})",
+            self.score(),
+            self.freshness,
+            self.depth,
+            self.competition,
+            self.history
+        )
+    }
+}
+
+impl StrategyContext {
+    /// Assess the confidence of a route trading `amount_in` of its first token at a block
+    pub async fn assess_confidence(
+        &self,
+        token_path: &[Address],
+        hops: &[(DexType, Address)],
+        amount_in: U256,
+        quote_block: BlockTag,
+    ) -> Confidence {
+        let confidence = Confidence {
+            freshness: self.freshness(quote_block),
+            depth: self.depth(token_path, hops, amount_in, quote_block).await,
+            competition: self.competition(token_path, hops),
+            history: self.history(token_path),
+        };
+        debug!(
+            "Confidence of {}: {}",
+            tokens::path_label(token_path),
+            confidence
+        );
+
+        confidence
+    }
+
+    fn freshness(&self, quote_block: BlockTag) -> f64 {
+        let BlockTag::Number(number) = quote_block else {
+            return 1.0;
+        };
+        let behind = self
+            .dex_interfaces
+            .pool_cache()
+            .synced_block()
+            .saturating_sub(number);
+
+        STALE_BLOCK_DECAY.powi(behind.min(i32::MAX as u64) as i32)
+    }
+
+    /// Walk the trade through the cached pools, keeping the depth of the hop taking the largest
+    /// share of its pool
+    async fn depth(
+        &self,
+        token_path: &[Address],
+        hops: &[(DexType, Address)],
+        amount_in: U256,
+        quote_block: BlockTag,
+    ) -> f64 {
+        let pool_cache = self.dex_interfaces.pool_cache();
+        let mut amount = amount_in;
+        let mut largest_share: f64 = 0.0;
+        for (pair, &(_, pool)) in token_path.windows(2).zip(hops) {
+            let Some(state) = pool_cache.get_at(pool, quote_block).await else {
+                return UNKNOWN_DEPTH / (1.0 + DEPTH_SENSITIVITY * largest_share);
+            };
+            let Some((reserve_in, reserve_out)) = state.reserves_for(pair[0]) else {
+                return UNKNOWN_DEPTH / (1.0 + DEPTH_SENSITIVITY * largest_share);
+            };
+            if reserve_in.is_zero() {
+                return 0.0;
+            }
+
+            let share = u256_to_decimal(amount, 0) / u256_to_decimal(reserve_in, 0);
+            largest_share = largest_share.max(share);
+            amount = get_amount_out(amount, reserve_in, reserve_out, state.fee);
+        }
+
+        1.0 / (1.0 + DEPTH_SENSITIVITY * largest_share)
+    }
+
+    fn competition(&self, token_path: &[Address], hops: &[(DexType, Address)]) -> f64 {
+        let Some(mempool) = &self.mempool else {
+            return 1.0;
+        };
+        let pools: HashSet<_> = token_path
+            .windows(2)
+            .zip(hops)
+            .map(|(pair, &(dex_type, _))| pool_key(dex_type, pair[0], pair[1]))
+            .collect();
+        let competing = mempool.pending_swaps_touching(&pools);
+
+        COMPETING_SWAP_DECAY.powi(competing.min(i32::MAX as usize) as i32)
+    }
+
+    fn history(&self, token_path: &[Address]) -> f64 {
+        let (confirmed, failed) = self.pair_tiers.settled_trades(token_path);
+
+        (confirmed as f64 + PRIOR_SUCCESS_RATE * PRIOR_TRADES)
+            / ((confirmed + failed) as f64 + PRIOR_TRADES)
+    }
+}
@@ -0,0 +1,337 @@
+//! Cross-DEX Strategy Module
+//!
+//! This module is responsible for two-leg round trips across DEXes: each scanned pair is quoted
+//! on every DEX, and when one DEX pays more of the second token than another, the first token is
+//! borrowed, swapped where it buys the most and swapped back through the pool that quoted
+//! lowest. Candidates must cover the gas of their route at the live gas price.
+
+use async_trait::async_trait;
+use ethers::types::{Address, U256};
+use log::{info, warn};
+use std::sync::Arc;
+
+use super::{ScanRound, Strategy, StrategyContext, StrategyVariant};
+use crate::config::{FlashLoanProvider, TokenConfig};
+use crate::dex::TradeQuote;
+use crate::logging;
+use crate::scanner::ArbitrageOpportunity;
+use crate::tokens;
+use crate::trace::{self, TraceStage};
+use crate::utils::{decimal_to_u256, validate_and_parse_address};
+
+/// A directed token pair quoted by a scan: `token_a` is borrowed and swapped to `token_b`
+struct ScanPair<'a> {
+    token_a: &'a TokenConfig,
+    token_b: &'a TokenConfig,
+    address_a: Address,
+    address_b: Address,
+
+    /// Amount of `token_a` quoted
+    input_amount: U256,
+}
+
+/// Finds two-leg round trips between the quotes of different DEXes
+pub struct CrossDexStrategy {
+    context: Arc<StrategyContext>,
+}
+
+impl CrossDexStrategy {
+    /// Create the strategy
+    pub fn new(context: Arc<StrategyContext>) -> Self {
+        Self { context }
+    }
+
+    /// Get the pairs a scan quotes: the whitelisted pairs if any are configured, otherwise
+    /// every ordered pair of the flash loan tokens, without the blacklisted tokens
+    fn scan_pairs(&self) -> Vec<ScanPair<'_>> {
+        let flash_loan = &self.context.config.flash_loan;
+        let whitelist = &self.context.config.arbitrage.pairs.whitelist;
+        let candidates: Vec<(&TokenConfig, &TokenConfig, Option<f64>)> = if whitelist.is_empty() {
+            let tokens = &flash_loan.tokens;
+            tokens
+                .iter()
+                .enumerate()
+                .flat_map(|(i, token_a)| {
+                    tokens
+                        .iter()
+                        .enumerate()
+                        .filter(move |&(j, _)| i != j) // Skip same token pairs
+                        .map(move |(_, token_b)| (token_a, token_b, None))
+                })
+                .collect()
+        } else {
+            // Validated when the configuration is loaded
+            whitelist
+                .iter()
+                .filter_map(|pair| {
+                    Some((
+                        flash_loan.token(&pair.token_a)?,
+                        flash_loan.token(&pair.token_b)?,
+                        pair.input_amount,
+                    ))
+                })
+                .collect()
+        };
+
+        let blacklisted = self.context.blacklisted();
+        let mut pairs = Vec::new();
+        for (token_a, token_b, input_amount) in candidates {
+            let address_a = match validate_and_parse_address(&token_a.address) {
+                Ok(addr) => addr,
+                Err(e) => {
+                    warn!("Invalid token address {}: {}", token_a.address, e);
+                    continue;
+                }
+            };
+
+            let address_b = match validate_and_parse_address(&token_b.address) {
+                Ok(addr) => addr,
+                Err(e) => {
+                    warn!("Invalid token address {}: {}", token_b.address, e);
+                    continue;
+                }
+            };
+
+            if blacklisted.contains(&address_a) || blacklisted.contains(&address_b) {
+                continue;
+            }
+
+            pairs.push(ScanPair {
+                token_a,
+                token_b,
+                address_a,
+                address_b,
+                input_amount: decimal_to_u256(input_amount.unwrap_or(1.0), token_a.decimals),
+            });
+        }
+
+        pairs
+    }
+}
+
+#[async_trait]
+impl Strategy for CrossDexStrategy {
+    fn name(&self) -> &'static str {
+        "cross_dex"
+    }
+
+    async fn find_opportunities(&self, round: &ScanRound) -> Vec<ArbitrageOpportunity> {
+        let (scan, quote_block) = (round.scan, round.quote_block);
+        let mut opportunities = Vec::new();
+
+        for pair in self.scan_pairs() {
+            let (token_a, token_b) = (pair.address_a, pair.address_b);
+
+            // Targeted scans only quote the pairs that changed
+            if !round.includes(token_a, token_b) {
+                continue;
+            }
+
+            // Dormant pairs are only swept every few sweeps
+            if round.pairs.is_none() && !self.context.pair_tiers.should_scan(token_a, token_b, scan)
+            {
+                trace::record(TraceStage::Rejection, || {
+                    format!(
+                        "{} -> {}: dormant pair not scanned",
+                        tokens::token_label(token_a),
+                        tokens::token_label(token_b)
+                    )
+                });
+                continue;
+            }
+
+            // Get quotes from all DEXes for this token pair
+            let input_amount = pair.input_amount;
+            match self
+                .context
+                .dex_interfaces
+                .get_quotes(token_a, token_b, input_amount, quote_block)
+                .await
+            {
+                Ok(quotes) => {
+                    if quotes.len() < 2 {
+                        // Need at least 2 DEXes to compare
+                        trace::record(TraceStage::Rejection, || {
+                            format!(
+                                "{} -> {}: only {} DEX quotes",
+                                tokens::token_label(token_a),
+                                tokens::token_label(token_b),
+                                quotes.len()
+                            )
+                        });
+                        continue;
+                    }
+
+                    // Find the best buy and sell prices
+                    let mut best_buy_quote: Option<TradeQuote> = None;
+                    let mut best_sell_quote: Option<TradeQuote> = None;
+
+                    for quote in &quotes {
+                        if best_buy_quote.is_none()
+                            || quote.output_amount > best_buy_quote.as_ref().unwrap().output_amount
+                        {
+                            best_buy_quote = Some(quote.clone());
+                        }
+
+                        if best_sell_quote.is_none()
+                            || quote.output_amount < best_sell_quote.as_ref().unwrap().output_amount
+                        {
+                            best_sell_quote = Some(quote.clone());
+                        }
+                    }
+
+                    // If we have both quotes, check for arbitrage opportunity
+                    if let (Some(buy_quote), Some(sell_quote)) = (best_buy_quote, best_sell_quote) {
+                        if buy_quote.output_amount > sell_quote.output_amount {
+                            // There's a potential arbitrage opportunity
+
+                            // Calculate profit in token B
+                            let profit_in_token_b = buy_quote
+                                .output_amount
+                                .saturating_sub(sell_quote.output_amount);
+
+                            // Pin the prices of both tokens for every later check
+                            let pinned_prices = match self
+                                .context
+                                .price_oracle
+                                .pin_prices(&[token_a, token_b])
+                                .await
+                            {
+                                Ok(pinned_prices) => pinned_prices,
+                                Err(e) => {
+                                    warn!(
+                                        "Failed to get USD prices for {} and {}: {}",
+                                        tokens::token_label(token_a),
+                                        tokens::token_label(token_b),
+                                        e
+                                    );
+                                    continue;
+                                }
+                            };
+                            let token_a_price_usd = pinned_prices.usd(token_a).unwrap_or_default();
+                            let token_b_price_usd = pinned_prices.usd(token_b).unwrap_or_default();
+
+                            // Convert profit to USD
+
+                            // Calculate profit in USD
+                            let decimals = pair.token_b.decimals as u32;
+                            let profit_usd = (profit_in_token_b.as_u128() as f64
+                                / 10f64.powi(decimals as i32))
+                                * token_b_price_usd;
+
+                            // Calculate required loan amount
+                            let loan_amount_usd = (input_amount.as_u128() as f64
+                                / 10f64.powi(pair.token_a.decimals as i32))
+                                * token_a_price_usd;
+
+                            // Create the token path and the DEX and pool of each swap;
+                            // selling back goes through the pool that quoted lowest
+                            let token_path = vec![token_a, token_b, token_a];
+                            let hops = vec![
+                                (buy_quote.dex_type, buy_quote.pool()),
+                                (sell_quote.dex_type, sell_quote.pool()),
+                            ];
+
+                            // Cost the route's gas at the live gas price
+                            let estimated_gas_cost =
+                                match self.context.estimate_gas_cost(&hops, &pinned_prices).await {
+                                    Ok(gas_cost) => gas_cost,
+                                    Err(e) => {
+                                        warn!(
+                                            "Failed to cost the gas of {} -> {}: {}",
+                                            tokens::token_label(token_a),
+                                            tokens::token_label(token_b),
+                                            e
+                                        );
+                                        continue;
+                                    }
+                                };
+
+                            // Calculate net profit
+                            let net_profit = profit_usd - estimated_gas_cost;
+
+                            // Only consider opportunities with positive net profit
+                            if net_profit > 0.0 {
+                                // Create a unique ID for this opportunity
+                                let id = format!(
+                                    "{}_{}_{}_{}",
+                                    pair.token_a.symbol,
+                                    pair.token_b.symbol,
+                                    buy_quote.dex_type as u8,
+                                    sell_quote.dex_type as u8
+                                );
+
+                                let confidence = self
+                                    .context
+                                    .assess_confidence(
+                                        &token_path,
+                                        &hops,
+                                        input_amount,
+                                        quote_block,
+                                    )
+                                    .await;
+
+                                // Get DEX names
+                                let source_dex = buy_quote.dex_type.name().to_string();
+                                let target_dex = sell_quote.dex_type.name().to_string();
+
+                                // Create the opportunity
+                                let opportunity = ArbitrageOpportunity {
+                                    span: logging::opportunity_span(&id),
+                                    id,
+                                    timestamp: std::time::SystemTime::now()
+                                        .duration_since(std::time::UNIX_EPOCH)
+                                        .unwrap()
+                                        .as_secs(),
+                                    source_dex,
+                                    target_dex,
+                                    token_path,
+                                    hops,
+                                    estimated_profit: profit_usd,
+                                    required_loan_amount: loan_amount_usd,
+                                    estimated_gas_cost,
+                                    net_profit,
+                                    confidence_score: confidence.score(),
+                                    variant: StrategyVariant::default(),
+                                    flash_loan_provider: FlashLoanProvider::Aave,
+                                    flash_loan_fee: 0.0,
+                                    input_amount: None,
+                                    pinned_prices,
+                                    quote_block,
+                                    latency: round.latency,
+                                };
+
+                                info!("Found arbitrage opportunity: {}", opportunity);
+                                self.context.pair_tiers.record_opportunity(token_a, token_b);
+                                trace::record(TraceStage::Candidate, || opportunity.to_string());
+
+                                opportunities.push(opportunity);
+                            } else {
+                                trace::record(TraceStage::Rejection, || {
+                                    format!(
+                                        "{} -> {}: spread ${:.2} does not cover gas ${:.2}",
+                                        tokens::token_label(token_a),
+                                        tokens::token_label(token_b),
+                                        profit_usd,
+                                        estimated_gas_cost
+                                    )
+                                });
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to get quotes for token pair {} -> {}: {}",
+                        tokens::token_label(token_a),
+                        tokens::token_label(token_b),
+                        e
+                    );
+                    continue;
+                }
+            }
+        }
+
+        opportunities
+    }
+}
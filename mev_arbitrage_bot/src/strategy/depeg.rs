@@ -0,0 +1,286 @@
+//! Stablecoin Depeg Strategy Module
+//!
+//! This module is responsible for trading stablecoins back to their peg. Each ordered pair of
+//! the configured stablecoins is quoted at a trade size large enough to matter; when a DEX pays
+//! more than `min_deviation_bps` above par for the borrowed stablecoin, the other stablecoin is
+//! bought there and sold back through the pool paying the most for it, which sits closer to the
+//! peg. Round trips of one unit, as the cross-DEX strategy quotes them, are too small to ever
+//! cover the gas of a depeg trade.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use ethers::types::{Address, U256};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use super::{ScanRound, Strategy, StrategyContext, StrategyVariant};
+use crate::config::{ConfigSection, FlashLoanProvider, TokenConfig};
+use crate::dex::{BlockTag, TradeQuote};
+use crate::logging;
+use crate::scanner::ArbitrageOpportunity;
+use crate::tokens;
+use crate::trace::{self, TraceStage};
+use crate::utils::{
+    current_timestamp, decimal_to_u256, u256_to_decimal, validate_and_parse_address,
+};
+
+/// Stablecoin depeg strategy configuration (`[depeg]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DepegConfig {
+    /// Whether stablecoin pairs are searched for depegs
+    pub enabled: bool,
+
+    /// Symbols or addresses of the flash loan tokens treated as stablecoins
+    pub stablecoins: Vec<String>,
+
+    /// Smallest premium over par a DEX must pay to be traded against (in basis points)
+    pub min_deviation_bps: u32,
+
+    /// Amount of the borrowed stablecoin quoted (in whole units)
+    pub trade_size: f64,
+}
+
+impl Default for DepegConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            stablecoins: vec!["USDC".to_string(), "USDT".to_string(), "DAI".to_string()],
+            min_deviation_bps: 20,
+            trade_size: 10_000.0,
+        }
+    }
+}
+
+impl ConfigSection for DepegConfig {
+    const NAME: &'static str = "depeg";
+
+    fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.stablecoins.len() < 2 {
+            anyhow::bail!("Depeg strategy needs at least two stablecoins");
+        }
+        if !self.trade_size.is_finite() || self.trade_size <= 0.0 {
+            anyhow::bail!("Depeg trade_size must be greater than zero");
+        }
+
+        Ok(())
+    }
+}
+
+/// A configured stablecoin
+struct Stablecoin {
+    token: TokenConfig,
+    address: Address,
+}
+
+/// Trades stablecoins that trade off their peg on one DEX against another
+pub struct DepegStrategy {
+    context: Arc<StrategyContext>,
+    settings: DepegConfig,
+}
+
+impl DepegStrategy {
+    /// Create the strategy
+    pub fn new(context: Arc<StrategyContext>, settings: DepegConfig) -> Self {
+        Self { context, settings }
+    }
+
+    /// Get the configured stablecoins among the flash loan tokens, without the blacklisted ones
+    fn stablecoins(&self) -> Vec<Stablecoin> {
+        let blacklisted = self.context.blacklisted();
+        self.settings
+            .stablecoins
+            .iter()
+            .filter_map(|reference| {
+                let token = self.context.config.flash_loan.token(reference);
+                if token.is_none() {
+                    warn!("Depeg stablecoin {} is not a flash loan token", reference);
+                }
+                let token = token?.clone();
+                let address = validate_and_parse_address(&token.address).ok()?;
+                (!blacklisted.contains(&address)).then_some(Stablecoin { token, address })
+            })
+            .collect()
+    }
+
+    /// Get the quote paying the most, optionally excluding a pool
+    async fn best_quote(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+        quote_block: BlockTag,
+        excluded_pool: Option<Address>,
+    ) -> Option<TradeQuote> {
+        let quotes = match self
+            .context
+            .dex_interfaces
+            .get_quotes(token_in, token_out, amount_in, quote_block)
+            .await
+        {
+            Ok(quotes) => quotes,
+            Err(e) => {
+                warn!(
+                    "Failed to get quotes for {} -> {}: {}",
+                    tokens::token_label(token_in),
+                    tokens::token_label(token_out),
+                    e
+                );
+                return None;
+            }
+        };
+
+        quotes
+            .into_iter()
+            .filter(|quote| Some(quote.pool()) != excluded_pool)
+            .max_by_key(|quote| quote.output_amount)
+    }
+
+    /// Search one ordered pair: `sold` is borrowed and swapped to `bought` and back
+    async fn search_pair(
+        &self,
+        sold: &Stablecoin,
+        bought: &Stablecoin,
+        round: &ScanRound,
+    ) -> Option<ArbitrageOpportunity> {
+        let quote_block = round.quote_block;
+        let pair = format!("{} -> {}", sold.token.symbol, bought.token.symbol);
+        let amount_in = decimal_to_u256(self.settings.trade_size, sold.token.decimals);
+
+        let buy = self
+            .best_quote(sold.address, bought.address, amount_in, quote_block, None)
+            .await?;
+        let bought_amount = u256_to_decimal(buy.output_amount, bought.token.decimals);
+        let deviation_bps = (bought_amount / self.settings.trade_size - 1.0) * 10_000.0;
+        if deviation_bps < f64::from(self.settings.min_deviation_bps) {
+            trace::record(TraceStage::Rejection, || {
+                format!(
+                    "depeg {}: {:.1} bps over par, below {} bps",
+                    pair, deviation_bps, self.settings.min_deviation_bps
+                )
+            });
+            return None;
+        }
+
+        let sell = self
+            .best_quote(
+                bought.address,
+                sold.address,
+                buy.output_amount,
+                quote_block,
+                Some(buy.pool()),
+            )
+            .await?;
+        if sell.output_amount <= amount_in {
+            trace::record(TraceStage::Rejection, || {
+                format!(
+                    "depeg {}: {:.1} bps over par on {}, but no pool buys it back at a profit",
+                    pair,
+                    deviation_bps,
+                    buy.dex_type.name()
+                )
+            });
+            return None;
+        }
+
+        let pinned_prices = match self.context.price_oracle.pin_prices(&[sold.address]).await {
+            Ok(pinned_prices) => pinned_prices,
+            Err(e) => {
+                warn!(
+                    "Failed to get the USD price of {}: {}",
+                    sold.token.symbol, e
+                );
+                return None;
+            }
+        };
+        let price_usd = pinned_prices.usd(sold.address).unwrap_or_default();
+        let profit_usd =
+            u256_to_decimal(sell.output_amount - amount_in, sold.token.decimals) * price_usd;
+
+        let hops = vec![(buy.dex_type, buy.pool()), (sell.dex_type, sell.pool())];
+        let estimated_gas_cost = match self.context.estimate_gas_cost(&hops, &pinned_prices).await {
+            Ok(gas_cost) => gas_cost,
+            Err(e) => {
+                warn!("Failed to cost the gas of depeg {}: {}", pair, e);
+                return None;
+            }
+        };
+        let net_profit = profit_usd - estimated_gas_cost;
+        if net_profit <= 0.0 {
+            trace::record(TraceStage::Rejection, || {
+                format!(
+                    "depeg {}: profit ${:.2} does not cover gas ${:.2}",
+                    pair, profit_usd, estimated_gas_cost
+                )
+            });
+            return None;
+        }
+
+        let token_path = vec![sold.address, bought.address, sold.address];
+        let confidence = self
+            .context
+            .assess_confidence(&token_path, &hops, amount_in, quote_block)
+            .await;
+
+        let id = format!(
+            "depeg_{}_{}_{}_{}",
+            sold.token.symbol, bought.token.symbol, buy.dex_type as u8, sell.dex_type as u8
+        );
+        Some(ArbitrageOpportunity {
+            span: logging::opportunity_span(&id),
+            id,
+            timestamp: current_timestamp(),
+            source_dex: buy.dex_type.name().to_string(),
+            target_dex: sell.dex_type.name().to_string(),
+            token_path,
+            hops,
+            estimated_profit: profit_usd,
+            required_loan_amount: self.settings.trade_size * price_usd,
+            estimated_gas_cost,
+            net_profit,
+            confidence_score: confidence.score(),
+            variant: StrategyVariant::default(),
+            flash_loan_provider: FlashLoanProvider::Aave,
+            flash_loan_fee: 0.0,
+            input_amount: Some(amount_in),
+            pinned_prices,
+            quote_block,
+            latency: round.latency,
+        })
+    }
+}
+
+#[async_trait]
+impl Strategy for DepegStrategy {
+    fn name(&self) -> &'static str {
+        "depeg"
+    }
+
+    async fn find_opportunities(&self, round: &ScanRound) -> Vec<ArbitrageOpportunity> {
+        let stablecoins = self.stablecoins();
+
+        let mut opportunities = Vec::new();
+        for sold in &stablecoins {
+            for bought in stablecoins
+                .iter()
+                .filter(|bought| bought.address != sold.address)
+                .filter(|bought| round.includes(sold.address, bought.address))
+            {
+                if let Some(opportunity) = self.search_pair(sold, bought, round).await {
+                    info!("Found depeg opportunity: {}", opportunity);
+                    trace::record(TraceStage::Candidate, || opportunity.to_string());
+                    self.context
+                        .pair_tiers
+                        .record_opportunity(sold.address, bought.address);
+                    opportunities.push(opportunity);
+                }
+            }
+        }
+
+        opportunities
+    }
+}
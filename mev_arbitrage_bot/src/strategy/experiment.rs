@@ -0,0 +1,128 @@
+//! Strategy Experiment Module
+//!
+//! This module resolves the configured strategy parameter sets and randomly assigns
+//! opportunities to them, so parameter changes can be compared on live executions.
+
+use log::{info, warn};
+use rand::Rng;
+
+use crate::config::Config;
+
+/// Name of the variant used when experiments are disabled
+pub const DEFAULT_VARIANT: &str = "default";
+
+/// Default flash loan size as a multiple of the estimated profit
+const DEFAULT_SIZING_MULTIPLIER: f64 = 2.0;
+
+/// Resolved strategy parameters for one experiment variant
+#[derive(Debug, Clone)]
+pub struct StrategyVariant {
+    /// Variant name
+    pub name: String,
+
+    /// Minimum profit threshold (in USD)
+    pub min_profit_threshold: f64,
+
+    /// Flash loan size as a multiple of the estimated profit
+    pub sizing_multiplier: f64,
+
+    /// Multiplier applied to the optimized gas price
+    pub tip_multiplier: f64,
+}
+
+impl StrategyVariant {
+    /// Build the variant described by the base configuration
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            name: DEFAULT_VARIANT.to_string(),
+            min_profit_threshold: config.arbitrage.min_profit_threshold,
+            sizing_multiplier: DEFAULT_SIZING_MULTIPLIER,
+            tip_multiplier: 1.0,
+        }
+    }
+}
+
+impl Default for StrategyVariant {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_VARIANT.to_string(),
+            min_profit_threshold: 0.0,
+            sizing_multiplier: DEFAULT_SIZING_MULTIPLIER,
+            tip_multiplier: 1.0,
+        }
+    }
+}
+
+/// Assigns opportunities to strategy variants by weight
+#[derive(Debug, Clone)]
+pub struct ExperimentManager {
+    variants: Vec<(StrategyVariant, f64)>,
+    total_weight: f64,
+}
+
+impl ExperimentManager {
+    /// Create an experiment manager from the configuration
+    ///
+    /// Without enabled experiments (or without variants with a positive weight), every
+    /// opportunity is assigned the base configuration.
+    pub fn new(config: &Config) -> Self {
+        let base = StrategyVariant::from_config(config);
+
+        let mut variants: Vec<(StrategyVariant, f64)> = Vec::new();
+        if config.experiments.enabled {
+            for variant in &config.experiments.variants {
+                if variant.weight.is_nan() || variant.weight <= 0.0 {
+                    warn!(
+                        "Ignoring strategy variant {} with non-positive weight",
+                        variant.name
+                    );
+                    continue;
+                }
+
+                variants.push((
+                    StrategyVariant {
+                        name: variant.name.clone(),
+                        min_profit_threshold: variant
+                            .min_profit_threshold
+                            .unwrap_or(base.min_profit_threshold),
+                        sizing_multiplier: variant
+                            .sizing_multiplier
+                            .unwrap_or(base.sizing_multiplier),
+                        tip_multiplier: variant.tip_multiplier.unwrap_or(base.tip_multiplier),
+                    },
+                    variant.weight,
+                ));
+            }
+
+            info!(
+                "Strategy experiment enabled with {} variants",
+                variants.len()
+            );
+        }
+
+        if variants.is_empty() {
+            variants.push((base, 1.0));
+        }
+
+        let total_weight = variants.iter().map(|(_, weight)| weight).sum();
+
+        Self {
+            variants,
+            total_weight,
+        }
+    }
+
+    /// Randomly pick a variant, proportionally to the variant weights
+    pub fn assign(&self) -> StrategyVariant {
+        let mut remaining = rand::thread_rng().gen_range(0.0..self.total_weight);
+        for (variant, weight) in &self.variants {
+            if remaining < *weight {
+                return variant.clone();
+            }
+            remaining -= weight;
+        }
+
+        // Floating point rounding can leave a sliver past the last variant
+        self.variants[self.variants.len() - 1].0.clone()
+    }
+}
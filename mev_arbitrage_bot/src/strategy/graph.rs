@@ -0,0 +1,348 @@
+//! Token Graph Module
+//!
+//! This module models tokens as nodes and pools as weighted edges so that profitable
+//! circular paths can be found with a hop-limited Bellman-Ford search over negative log rates.
+
+use ethers::types::{Address, U256};
+use std::collections::HashMap;
+
+use crate::dex::{DexType, PoolInfo};
+use crate::utils::u256_to_decimal;
+
+/// Weights below this value are treated as profitable (guards against float noise)
+const PROFIT_EPSILON: f64 = -1e-9;
+
+/// A directed edge in the token graph, representing a swap through a single pool
+#[derive(Debug, Clone)]
+pub struct PoolEdge {
+    /// Input token
+    pub token_in: Address,
+
+    /// Output token
+    pub token_out: Address,
+
+    /// Pool address
+    pub pool: Address,
+
+    /// DEX type
+    pub dex_type: DexType,
+
+    /// Marginal exchange rate (output per input, adjusted for decimals and fee)
+    pub rate: f64,
+
+    /// Edge weight (-ln(rate))
+    pub weight: f64,
+}
+
+/// A path found in the token graph
+#[derive(Debug, Clone)]
+pub struct GraphPath {
+    /// Tokens visited, including the start token (and the end token for cycles)
+    pub tokens: Vec<Address>,
+
+    /// Edges traversed, one per consecutive token pair
+    pub edges: Vec<PoolEdge>,
+
+    /// Sum of the edge weights (negative for profitable cycles)
+    pub weight: f64,
+}
+
+impl GraphPath {
+    /// Get the product of the marginal rates along the path
+    pub fn rate(&self) -> f64 {
+        (-self.weight).exp()
+    }
+
+    /// Get the DEX and pool traversed by each edge, as in opportunities
+    pub fn hops(&self) -> Vec<(DexType, Address)> {
+        self.edges
+            .iter()
+            .map(|edge| (edge.dex_type, edge.pool))
+            .collect()
+    }
+}
+
+/// Directed graph of tokens connected by pools
+#[derive(Debug, Clone, Default)]
+pub struct TokenGraph {
+    edges: Vec<PoolEdge>,
+    outgoing: HashMap<Address, Vec<usize>>,
+}
+
+/// Best known way to reach a token at a given hop count
+#[derive(Debug, Clone, Copy)]
+struct LayerEntry {
+    weight: f64,
+    edge: usize,
+}
+
+impl TokenGraph {
+    /// Build a token graph from a list of pools
+    ///
+    /// Every ordered pair of tokens in a pool becomes an edge. Pools with a zero reserve on
+    /// either side are skipped since they cannot be traded through.
+    pub fn from_pools<F>(pools: &[PoolInfo], decimals: F) -> Self
+    where
+        F: Fn(Address) -> u8,
+    {
+        let mut graph = Self::default();
+
+        for pool in pools {
+            for i in 0..pool.tokens.len() {
+                for j in 0..pool.tokens.len() {
+                    if i == j {
+                        continue;
+                    }
+
+                    let (reserve_in, reserve_out) =
+                        match (pool.reserves.get(i), pool.reserves.get(j)) {
+                            (Some(&r_in), Some(&r_out)) => (r_in, r_out),
+                            _ => continue,
+                        };
+
+                    let token_in = pool.tokens[i];
+                    let token_out = pool.tokens[j];
+
+                    if let Some(rate) = marginal_rate(
+                        reserve_in,
+                        decimals(token_in),
+                        reserve_out,
+                        decimals(token_out),
+                        pool.fee,
+                    ) {
+                        graph.add_edge(PoolEdge {
+                            token_in,
+                            token_out,
+                            pool: pool.address,
+                            dex_type: pool.dex_type,
+                            rate,
+                            weight: -rate.ln(),
+                        });
+                    }
+                }
+            }
+        }
+
+        graph
+    }
+
+    /// Add an edge to the graph
+    pub fn add_edge(&mut self, edge: PoolEdge) {
+        let index = self.edges.len();
+        self.outgoing.entry(edge.token_in).or_default().push(index);
+        self.edges.push(edge);
+    }
+
+    /// Get the number of edges in the graph
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Get the number of tokens in the graph
+    pub fn token_count(&self) -> usize {
+        let mut tokens: Vec<Address> = self
+            .edges
+            .iter()
+            .flat_map(|edge| [edge.token_in, edge.token_out])
+            .collect();
+        tokens.sort();
+        tokens.dedup();
+        tokens.len()
+    }
+
+    /// Find profitable circular paths that start and end at `start`, using at most `max_hops` swaps
+    ///
+    /// The returned paths are sorted by weight, so the most profitable cycle comes first.
+    pub fn find_profitable_cycles(&self, start: Address, max_hops: usize) -> Vec<GraphPath> {
+        let layers = self.relax_layers(start, max_hops.saturating_sub(1));
+        let mut cycles = Vec::new();
+
+        // Close a cycle from every token reached in layers 1..max_hops-1 back to the start
+        for layer in 1..layers.len() {
+            for &token in layers[layer].keys() {
+                let prefix = match self.reconstruct(&layers, start, layer, token) {
+                    Some(prefix) => prefix,
+                    None => continue,
+                };
+
+                for &edge_index in self.outgoing.get(&token).into_iter().flatten() {
+                    let edge = &self.edges[edge_index];
+                    if edge.token_out != start
+                        || prefix.edges.iter().any(|used| used.pool == edge.pool)
+                    {
+                        continue;
+                    }
+
+                    let weight = prefix.weight + edge.weight;
+                    if weight < PROFIT_EPSILON {
+                        let mut cycle = prefix.clone();
+                        cycle.tokens.push(start);
+                        cycle.edges.push(edge.clone());
+                        cycle.weight = weight;
+                        cycles.push(cycle);
+                    }
+                }
+            }
+        }
+
+        sort_by_weight(&mut cycles);
+        cycles
+    }
+
+    /// Find the best simple paths from `from` to `to` using at most `max_hops` swaps
+    ///
+    /// At most one path is returned per hop count, sorted by weight (best rate first).
+    pub fn find_best_paths(&self, from: Address, to: Address, max_hops: usize) -> Vec<GraphPath> {
+        if from == to {
+            return self.find_profitable_cycles(from, max_hops);
+        }
+
+        let layers = self.relax_layers(from, max_hops);
+        let mut paths: Vec<GraphPath> = (1..layers.len())
+            .filter(|&layer| layers[layer].contains_key(&to))
+            .filter_map(|layer| self.reconstruct(&layers, from, layer, to))
+            .collect();
+
+        sort_by_weight(&mut paths);
+        paths
+    }
+
+    /// Run `hops` rounds of hop-limited Bellman-Ford relaxation from `start`
+    ///
+    /// Layer `k` holds the lowest-weight simple path to each token using exactly `k` edges.
+    /// The start token is never expanded again once left, so cycles are closed separately.
+    fn relax_layers(&self, start: Address, hops: usize) -> Vec<HashMap<Address, LayerEntry>> {
+        let mut layers: Vec<HashMap<Address, LayerEntry>> = vec![HashMap::new()];
+
+        for layer in 1..=hops {
+            let mut next: HashMap<Address, LayerEntry> = HashMap::new();
+
+            let frontier: Vec<(Address, f64)> = if layer == 1 {
+                vec![(start, 0.0)]
+            } else {
+                layers[layer - 1]
+                    .iter()
+                    .map(|(&token, entry)| (token, entry.weight))
+                    .collect()
+            };
+
+            for (token, weight) in frontier {
+                let prefix = if layer == 1 {
+                    None
+                } else {
+                    match self.reconstruct(&layers, start, layer - 1, token) {
+                        Some(prefix) => Some(prefix),
+                        None => continue,
+                    }
+                };
+
+                for &edge_index in self.outgoing.get(&token).into_iter().flatten() {
+                    let edge = &self.edges[edge_index];
+
+                    // Keep paths simple: never revisit a token or reuse a pool
+                    if edge.token_out == start {
+                        continue;
+                    }
+                    if let Some(prefix) = &prefix {
+                        if prefix.tokens.contains(&edge.token_out)
+                            || prefix.edges.iter().any(|used| used.pool == edge.pool)
+                        {
+                            continue;
+                        }
+                    }
+
+                    let candidate = weight + edge.weight;
+                    let improves = next
+                        .get(&edge.token_out)
+                        .is_none_or(|existing| candidate < existing.weight);
+                    if improves {
+                        next.insert(
+                            edge.token_out,
+                            LayerEntry {
+                                weight: candidate,
+                                edge: edge_index,
+                            },
+                        );
+                    }
+                }
+            }
+
+            if next.is_empty() {
+                break;
+            }
+            layers.push(next);
+        }
+
+        layers
+    }
+
+    /// Walk the predecessor edges back from `token` at `layer` to the start token
+    fn reconstruct(
+        &self,
+        layers: &[HashMap<Address, LayerEntry>],
+        start: Address,
+        layer: usize,
+        token: Address,
+    ) -> Option<GraphPath> {
+        let mut edges = Vec::with_capacity(layer);
+        let mut current = token;
+        let weight = layers.get(layer)?.get(&token)?.weight;
+
+        for k in (1..=layer).rev() {
+            let entry = layers[k].get(&current)?;
+            let edge = &self.edges[entry.edge];
+            edges.push(edge.clone());
+            current = edge.token_in;
+        }
+
+        if current != start {
+            return None;
+        }
+
+        edges.reverse();
+        let mut tokens = Vec::with_capacity(edges.len() + 1);
+        tokens.push(start);
+        tokens.extend(edges.iter().map(|edge| edge.token_out));
+
+        Some(GraphPath {
+            tokens,
+            edges,
+            weight,
+        })
+    }
+}
+
+/// Calculate the marginal output-per-input rate of a pool, net of its fee
+fn marginal_rate(
+    reserve_in: U256,
+    decimals_in: u8,
+    reserve_out: U256,
+    decimals_out: u8,
+    fee_bps: u32,
+) -> Option<f64> {
+    if reserve_in.is_zero() || reserve_out.is_zero() || fee_bps >= 10_000 {
+        return None;
+    }
+
+    let amount_in = u256_to_decimal(reserve_in, decimals_in);
+    let amount_out = u256_to_decimal(reserve_out, decimals_out);
+    if amount_in <= 0.0 || amount_out <= 0.0 {
+        return None;
+    }
+
+    let rate = amount_out / amount_in * (1.0 - fee_bps as f64 / 10_000.0);
+    if rate.is_finite() && rate > 0.0 {
+        Some(rate)
+    } else {
+        None
+    }
+}
+
+/// Sort paths by ascending weight (highest rate first)
+fn sort_by_weight(paths: &mut [GraphPath]) {
+    paths.sort_by(|a, b| {
+        a.weight
+            .partial_cmp(&b.weight)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
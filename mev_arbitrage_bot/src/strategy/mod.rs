@@ -1,27 +1,81 @@
 //! Arbitrage Strategy Engine Module
 //!
 //! This module is responsible for evaluating arbitrage opportunities and determining optimal trade paths.
+//! Each opportunity's gas is costed from its route at the live gas price and its pinned ETH price.
+//! The opportunities themselves are found by the strategies of the [`StrategyRegistry`], and
+//! are ranked by their net profit weighted by the confidence each strategy scored them with.
+
+mod backrun;
+mod confidence;
+mod cross_dex;
+mod depeg;
+mod experiment;
+mod graph;
+mod registry;
+mod route_guard;
+mod schedule;
+mod sizing;
+mod triangular;
+
+pub use backrun::BackrunStrategy;
+pub use confidence::Confidence;
+pub use cross_dex::CrossDexStrategy;
+pub use depeg::{DepegConfig, DepegStrategy};
+pub use experiment::{ExperimentManager, StrategyVariant};
+pub use graph::TokenGraph;
+pub use registry::{
+    create_registry, score, ScanRound, Strategy, StrategyContext, StrategyRegistry,
+};
+pub use route_guard::{create_route_guard, route_key, QuarantinedRoute, RouteGuard};
+pub use schedule::ExecutionSchedule;
+pub use sizing::{ConstantProductLeg, OptimalSizer, ProfitPoint, ProfitProfile};
+pub use triangular::TriangularStrategy;
 
 use anyhow::Result;
 use async_trait::async_trait;
 use ethers::types::{Address, U256};
+use futures::future::join_all;
+use std::collections::HashMap;
 use std::sync::Arc;
+use tracing::Instrument;
 
 use crate::config::Config;
-use crate::dex::{DexInterfaces, DexType};
-use crate::flash_loan::FlashLoanManager;
-use crate::price::{PriceOracle, PriceOracleInterface};
+use crate::config::FlashLoanProvider;
+use crate::dex::{BlockTag, DexInterfaces, DexType};
+use crate::flash_loan::FlashLoanRouter;
+use crate::gas::{gas_cost_usd, route_gas, GasOptimizer};
+use crate::latency::{self, Stage};
+use crate::price::PriceOracleInterface;
+use crate::risk;
+use crate::runtime;
 use crate::scanner::ArbitrageOpportunity;
+use crate::tokens;
+use crate::trace::{self, TraceStage};
+use crate::utils::{decimal_to_u256, u256_to_decimal};
+
+/// Maximum number of graph candidates that are re-quoted on-chain
+const MAX_CANDIDATE_PATHS: usize = 10;
 
 /// Interface for arbitrage strategy engines
 #[async_trait]
 pub trait StrategyEngine: Send + Sync {
+    /// Get the reason execution is currently disabled by the trading windows or the risk
+    /// limits, if it is
+    fn execution_blocked(&self) -> Option<String>;
+
     /// Evaluate a list of arbitrage opportunities and select the best one
     async fn evaluate_opportunities(
         &self,
         opportunities: Vec<ArbitrageOpportunity>,
     ) -> Option<ArbitrageOpportunity>;
 
+    /// Evaluate a list of arbitrage opportunities and rank the profitable ones by their net
+    /// profit weighted by confidence
+    async fn rank_opportunities(
+        &self,
+        opportunities: Vec<ArbitrageOpportunity>,
+    ) -> Vec<ArbitrageOpportunity>;
+
     /// Find the optimal trade path for a given token pair
     async fn find_optimal_path(
         &self,
@@ -36,29 +90,105 @@ pub trait StrategyEngine: Send + Sync {
 /// Implementation of the arbitrage strategy engine
 pub struct StrategyEngineImpl {
     config: Arc<Config>,
-    price_oracle: Arc<PriceOracle>,
+    price_oracle: Arc<dyn PriceOracleInterface>,
     dex_interfaces: Arc<DexInterfaces>,
-    flash_loan_manager: Arc<dyn FlashLoanManager>,
+    flash_loans: Arc<FlashLoanRouter>,
+    gas_optimizer: Arc<dyn GasOptimizer>,
+    experiments: ExperimentManager,
+    schedule: ExecutionSchedule,
 }
 
 /// Create a new arbitrage strategy engine
 pub async fn create_engine(
     config: &Arc<Config>,
-    price_oracle: Arc<PriceOracle>,
+    price_oracle: Arc<dyn PriceOracleInterface>,
     dex_interfaces: Arc<DexInterfaces>,
-    flash_loan_manager: Arc<dyn FlashLoanManager>,
+    flash_loans: Arc<FlashLoanRouter>,
+    gas_optimizer: Arc<dyn GasOptimizer>,
 ) -> Result<Arc<dyn StrategyEngine>> {
     let engine = StrategyEngineImpl {
         config: config.clone(),
         price_oracle,
         dex_interfaces,
-        flash_loan_manager,
+        flash_loans,
+        gas_optimizer,
+        experiments: ExperimentManager::new(config),
+        schedule: ExecutionSchedule::new(config),
     };
 
     Ok(Arc::new(engine))
 }
 
 impl StrategyEngineImpl {
+    /// Build a token graph from the pools of the enabled DEX interfaces
+    async fn build_token_graph(&self) -> Result<TokenGraph> {
+        let overrides = self.dex_interfaces.overrides();
+        let mut pools = Vec::new();
+        for interface in self.dex_interfaces.enabled_interfaces() {
+            match interface.get_pools().await {
+                Ok(dex_pools) => pools.extend(
+                    dex_pools
+                        .into_iter()
+                        .filter(|pool| !overrides.is_pool_blacklisted(pool.address)),
+                ),
+                Err(e) => log::warn!("Failed to get pools from {}: {}", interface.name(), e),
+            }
+        }
+
+        // Resolve decimals from the configured tokens, defaulting to 18
+        let mut decimals = HashMap::new();
+        for token_config in &self.config.flash_loan.tokens {
+            if let Ok(token_address) =
+                crate::utils::validate_and_parse_address(&token_config.address)
+            {
+                decimals.insert(token_address, token_config.decimals);
+            }
+        }
+
+        Ok(TokenGraph::from_pools(&pools, |token| {
+            decimals.get(&token).copied().unwrap_or(18)
+        }))
+    }
+
+    /// Pick the cheapest flash loan provider for an opportunity, returning it with its fee in USD
+    ///
+    /// A flash swap from the route's first pair costs nothing beyond the swap already quoted,
+    /// so it is preferred whenever the route allows one.
+    async fn select_flash_loan(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+    ) -> Result<(FlashLoanProvider, f64)> {
+        if self
+            .flash_loans
+            .flash_swap(&opportunity.token_path, &opportunity.hops)
+            .is_some()
+        {
+            return Ok((FlashLoanProvider::UniswapV2, 0.0));
+        }
+
+        let token = *opportunity
+            .token_path
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("Opportunity has an empty token path"))?;
+
+        let quote = self
+            .flash_loans
+            .quote(token, opportunity.flash_loan_amount())
+            .await?;
+        if quote.fee.is_zero() {
+            return Ok((quote.provider, 0.0));
+        }
+
+        let decimals = self.get_token_decimals(token).await?;
+        let price_usd = opportunity
+            .pinned_prices
+            .usd(token)
+            .ok_or_else(|| anyhow::anyhow!("No pinned price for {}", tokens::token_label(token)))?;
+        let fee_usd = crate::utils::u256_to_decimal(quote.fee, decimals) * price_usd;
+
+        Ok((quote.provider, fee_usd))
+    }
+
     /// Get the decimals for a token
     async fn get_token_decimals(&self, token: Address) -> Result<u8> {
         // In a real implementation, we would query the token contract
@@ -78,102 +208,482 @@ impl StrategyEngineImpl {
         Ok(18)
     }
 
-    /// Estimate gas cost for a trade path
-    async fn estimate_gas_cost(
+    /// Quote the opportunity's route leg by leg at the block it was found at, returning the final
+    /// output and the largest price impact of any leg (in basis points)
+    async fn quote_route(
         &self,
-        path_length: usize,
-        dex_types: Vec<crate::dex::DexType>,
+        opportunity: &ArbitrageOpportunity,
+        input_amount: U256,
+    ) -> Result<(U256, u32)> {
+        let mut amount = input_amount;
+        let mut max_price_impact = 0;
+        for (pair, &(dex_type, _)) in opportunity.token_path.windows(2).zip(&opportunity.hops) {
+            let interface = self
+                .dex_interfaces
+                .get_interface(dex_type)
+                .ok_or_else(|| anyhow::anyhow!("No interface for DEX {}", dex_type.name()))?;
+            let quote = interface
+                .get_quote(pair[0], pair[1], amount, opportunity.quote_block)
+                .await?;
+            amount = quote.output_amount;
+            max_price_impact = max_price_impact.max(quote.price_impact);
+        }
+
+        Ok((amount, max_price_impact))
+    }
+
+    /// Simulate the opportunity's route for an input size, returning the profit in the first token
+    async fn simulate_route(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        input: f64,
+        decimals: u8,
     ) -> Result<f64> {
-        // Base gas cost for a flash loan
-        let mut gas_cost = 0.005; // $0.005 base cost
-
-        // Add cost based on path length
-        gas_cost += match path_length {
-            2 => 0.001, // Direct path
-            3 => 0.002, // One intermediate token
-            _ => 0.004, // Multiple intermediate tokens
+        let input_amount = decimal_to_u256(input, decimals);
+        let (output_amount, _) = self.quote_route(opportunity, input_amount).await?;
+
+        Ok(u256_to_decimal(output_amount, decimals) - u256_to_decimal(input_amount, decimals))
+    }
+
+    /// Reject an opportunity if any leg, at the size it will trade, moves its pool's price by
+    /// more than the slippage tolerance
+    async fn check_price_impact(&self, opportunity: &ArbitrageOpportunity) -> Result<()> {
+        let (_, price_impact) = self
+            .quote_route(opportunity, opportunity.flash_loan_amount())
+            .await?;
+
+        // The tolerance is a percentage; impact is in basis points
+        let max_price_impact = self.config.arbitrage.slippage_tolerance * 100.0;
+        if f64::from(price_impact) > max_price_impact {
+            return Err(anyhow::anyhow!(
+                "price impact of {} bps exceeds the slippage tolerance of {:.0} bps",
+                price_impact,
+                max_price_impact
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Get the reserves of a two-swap route through constant-product pools, oriented per swap
+    ///
+    /// Returns `None` for any other route, or when a pool's reserves are unknown.
+    async fn constant_product_legs(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+    ) -> Option<(ConstantProductLeg, ConstantProductLeg)> {
+        if opportunity.hops.len() != 2 {
+            return None;
+        }
+
+        let mut legs = Vec::with_capacity(2);
+        for (pair, &(dex_type, _)) in opportunity.token_path.windows(2).zip(&opportunity.hops) {
+            if !matches!(dex_type, DexType::UniswapV2 | DexType::Sushiswap) {
+                return None;
+            }
+
+            let pool = self
+                .dex_interfaces
+                .get_interface(dex_type)?
+                .get_pool(pair[0], pair[1])
+                .await
+                .ok()??;
+            let index_in = pool.tokens.iter().position(|&token| token == pair[0])?;
+            let (&reserve_in, &reserve_out) = (
+                pool.reserves.get(index_in)?,
+                pool.reserves.get(1 - index_in)?,
+            );
+            legs.push(ConstantProductLeg {
+                reserve_in: u256_to_decimal(reserve_in, 0),
+                reserve_out: u256_to_decimal(reserve_out, 0),
+                fee_bps: pool.fee,
+            });
+        }
+
+        Some((legs[0], legs[1]))
+    }
+
+    /// Choose the input size of an opportunity from its simulated profit profile
+    ///
+    /// The profile must be concave, and the size solved for around the best grid point must
+    /// earn close to that point's profit; otherwise the quotes or the route math are
+    /// inconsistent. Two-pool constant-product routes are solved in closed form, and other
+    /// routes by ternary search between the grid points either side of the peak.
+    async fn size_opportunity(&self, opportunity: &ArbitrageOpportunity) -> Result<U256> {
+        if opportunity.hops.len() + 1 != opportunity.token_path.len() {
+            return Err(anyhow::anyhow!(
+                "{} swaps but {} DEXes",
+                opportunity.token_path.len().saturating_sub(1),
+                opportunity.hops.len()
+            ));
+        }
+
+        let sizing = &self.config.arbitrage.sizing;
+        let decimals = self.get_token_decimals(opportunity.token_path[0]).await?;
+        let grid = ProfitProfile::grid(sizing);
+
+        let profits = join_all(
+            grid.iter()
+                .map(|&input| self.simulate_route(opportunity, input, decimals)),
+        )
+        .await;
+        let points = grid
+            .iter()
+            .zip(profits)
+            .map(|(&input, profit)| {
+                Ok(ProfitPoint {
+                    input,
+                    profit: profit?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let profile = ProfitProfile::new(points);
+
+        if let Some(index) = profile.concavity_violation(sizing.concavity_tolerance) {
+            return Err(anyhow::anyhow!(
+                "profit profile is not concave at input {:.4} [{}]",
+                profile.points()[index].input,
+                profile.describe()
+            ));
+        }
+
+        let peak = profile
+            .peak_index()
+            .map(|index| profile.points()[index])
+            .ok_or_else(|| anyhow::anyhow!("empty sizing grid"))?;
+        if peak.profit <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "unprofitable at every size [{}]",
+                profile.describe()
+            ));
+        }
+
+        let closed_form = self
+            .constant_product_legs(opportunity)
+            .await
+            .and_then(|(first, second)| OptimalSizer::two_pool_optimum(first, second))
+            .map(|raw| raw / 10f64.powi(i32::from(decimals)));
+        let optimum = match closed_form {
+            Some(optimum) => optimum,
+            None => {
+                let (low, high) = profile
+                    .peak_bracket()
+                    .ok_or_else(|| anyhow::anyhow!("empty sizing grid"))?;
+                OptimalSizer::new(sizing)
+                    .ternary_search(low, high, |input| {
+                        self.simulate_route(opportunity, input, decimals)
+                    })
+                    .await?
+            }
         };
 
-        // Add cost based on DEX types (some DEXes are more gas-intensive)
-        for dex_type in dex_types {
-            gas_cost += match dex_type {
-                crate::dex::DexType::UniswapV2 => 0.001,
-                crate::dex::DexType::Sushiswap => 0.001,
-                crate::dex::DexType::Curve => 0.002, // Curve is typically more gas-intensive
-            };
+        let optimum_profit = self.simulate_route(opportunity, optimum, decimals).await?;
+        if optimum_profit < peak.profit * sizing.peak_tolerance {
+            return Err(anyhow::anyhow!(
+                "size {:.4} earns {:.6}, below {:.0}% of the grid peak {:.6} at {:.4} [{}]",
+                optimum,
+                optimum_profit,
+                sizing.peak_tolerance * 100.0,
+                peak.profit,
+                peak.input,
+                profile.describe()
+            ));
         }
 
-        // Apply a fixed multiplier for gas cost estimation
-        gas_cost *= 1.2; // Use a reasonable default multiplier
+        let chosen = if optimum_profit >= peak.profit {
+            optimum
+        } else {
+            peak.input
+        };
+        log::debug!(
+            "Sized {} at {:.4} {} by {} (grid peak {:.6} at {:.4})",
+            opportunity.id,
+            chosen,
+            tokens::token_label(opportunity.token_path[0]),
+            if closed_form.is_some() {
+                "closed form"
+            } else {
+                "ternary search"
+            },
+            peak.profit,
+            peak.input
+        );
+
+        Ok(decimal_to_u256(chosen, decimals))
+    }
 
-        Ok(gas_cost)
+    /// Estimate the gas cost (in USD) of a route funded by a flash loan provider, at the live
+    /// gas price
+    async fn estimate_gas_cost(
+        &self,
+        provider: FlashLoanProvider,
+        dex_types: Vec<DexType>,
+        eth_price_usd: f64,
+    ) -> Result<f64> {
+        let gas = route_gas(provider, dex_types);
+        gas_cost_usd(&*self.gas_optimizer, gas, eth_price_usd).await
+    }
+
+    /// Size, fund and cost an opportunity, returning it if it is still profitable and within
+    /// the risk limits
+    async fn evaluate_opportunity(
+        &self,
+        mut opportunity: ArbitrageOpportunity,
+    ) -> Option<ArbitrageOpportunity> {
+        // Quotes that took too long to find are likely stale already
+        if let Err(e) = opportunity.latency.check(Stage::Evaluation) {
+            log::debug!("Rejecting opportunity {}: {}", opportunity.id, e);
+            trace::record(TraceStage::Rejection, || {
+                format!("{}: {}", opportunity.id, e)
+            });
+            return None;
+        }
+
+        // A DEX or pool may have been taken out of the scan since the opportunity was found
+        let overrides = self.dex_interfaces.overrides();
+        if let Some(&(dex_type, pool)) = opportunity
+            .hops
+            .iter()
+            .find(|&&(dex_type, pool)| !overrides.allows(dex_type, pool))
+        {
+            log::debug!(
+                "Rejecting opportunity {}: {} pool {:?} is disabled",
+                opportunity.id,
+                dex_type.name(),
+                pool
+            );
+            trace::record(TraceStage::Rejection, || {
+                format!(
+                    "{}: {} pool {:?} is disabled",
+                    opportunity.id,
+                    dex_type.name(),
+                    pool
+                )
+            });
+            return None;
+        }
+
+        // Size the trade from its simulated profit profile before funding it
+        if self.config.arbitrage.sizing.enabled {
+            match self.size_opportunity(&opportunity).await {
+                Ok(amount) => opportunity.input_amount = Some(amount),
+                Err(e) => {
+                    log::warn!("Rejecting opportunity {}: {}", opportunity.id, e);
+                    trace::record(TraceStage::Rejection, || {
+                        format!("{}: sizing failed ({})", opportunity.id, e)
+                    });
+                    return None;
+                }
+            }
+        }
+
+        // Trades that move the price further than the slippage tolerance would revert
+        if let Err(e) = self.check_price_impact(&opportunity).await {
+            log::debug!("Rejecting opportunity {}: {}", opportunity.id, e);
+            trace::record(TraceStage::Rejection, || {
+                format!("{}: {}", opportunity.id, e)
+            });
+            return None;
+        }
+
+        // Fund the opportunity from the cheapest flash loan provider
+        match self.select_flash_loan(&opportunity).await {
+            Ok((provider, fee_usd)) => {
+                opportunity.flash_loan_provider = provider;
+                opportunity.flash_loan_fee = fee_usd;
+            }
+            Err(e) => {
+                log::debug!("Skipping opportunity {}: {}", opportunity.id, e);
+                trace::record(TraceStage::Rejection, || {
+                    format!("{}: no flash loan ({})", opportunity.id, e)
+                });
+                return None;
+            }
+        }
+
+        // Cost the route's gas at the live gas price and the pinned ETH price
+        let gas_cost = match opportunity.pinned_prices.eth_usd() {
+            Some(eth_price_usd) => {
+                self.estimate_gas_cost(
+                    opportunity.flash_loan_provider,
+                    opportunity
+                        .hops
+                        .iter()
+                        .map(|&(dex_type, _)| dex_type)
+                        .collect(),
+                    eth_price_usd,
+                )
+                .await
+            }
+            None => Err(anyhow::anyhow!("no pinned ETH price")),
+        };
+        let estimated_gas = match gas_cost {
+            Ok(gas_cost) => gas_cost,
+            Err(e) => {
+                log::debug!("Skipping opportunity {}: {}", opportunity.id, e);
+                trace::record(TraceStage::Rejection, || {
+                    format!("{}: gas cost unknown ({})", opportunity.id, e)
+                });
+                return None;
+            }
+        };
+
+        // Update gas cost and net profit
+        opportunity.estimated_gas_cost = estimated_gas;
+        opportunity.net_profit =
+            opportunity.estimated_profit - estimated_gas - opportunity.flash_loan_fee;
+
+        trace::record(TraceStage::Score, || {
+            format!(
+                "{}: net profit ${:.2} (gross ${:.2}, gas ${:.3}, {} flash loan fee ${:.2})",
+                opportunity.id,
+                opportunity.net_profit,
+                opportunity.estimated_profit,
+                opportunity.estimated_gas_cost,
+                opportunity.flash_loan_provider.as_str(),
+                opportunity.flash_loan_fee
+            )
+        });
+
+        // Trades beyond the risk limits are not executed, however profitable
+        if let Some(reason) = risk::global().check_opportunity(&opportunity) {
+            log::debug!("Skipping opportunity {}: {}", opportunity.id, reason);
+            trace::record(TraceStage::Rejection, || {
+                format!("{}: {}", opportunity.id, reason)
+            });
+            return None;
+        }
+
+        // Only include if still profitable after gas costs
+        if opportunity.net_profit > opportunity.variant.min_profit_threshold {
+            Some(opportunity)
+        } else {
+            trace::record(TraceStage::Rejection, || {
+                format!(
+                    "{}: net profit ${:.2} after costs below {} threshold ${:.2}",
+                    opportunity.id,
+                    opportunity.net_profit,
+                    opportunity.variant.name,
+                    opportunity.variant.min_profit_threshold
+                )
+            });
+            None
+        }
     }
 }
 
 #[async_trait]
 impl StrategyEngine for StrategyEngineImpl {
+    fn execution_blocked(&self) -> Option<String> {
+        self.schedule
+            .check()
+            .or_else(|| risk::global().execution_blocked())
+    }
+
     async fn evaluate_opportunities(
         &self,
         opportunities: Vec<ArbitrageOpportunity>,
     ) -> Option<ArbitrageOpportunity> {
-        if opportunities.is_empty() {
+        let mut evaluated_opportunities = self.rank_opportunities(opportunities).await;
+        if evaluated_opportunities.is_empty() {
             return None;
         }
 
-        // Filter out opportunities below the profit threshold
+        // Return the opportunity with the highest confidence-weighted net profit
+        let best_opportunity = evaluated_opportunities.remove(0);
+        log::info!(
+            "Selected best arbitrage opportunity: {} ({} hops) with net profit: ${:.2} at {}% \
+             confidence",
+            best_opportunity.dex_path().join(" -> "),
+            best_opportunity.token_path.len() - 1,
+            best_opportunity.net_profit,
+            best_opportunity.confidence_score
+        );
+
+        Some(best_opportunity)
+    }
+
+    async fn rank_opportunities(
+        &self,
+        opportunities: Vec<ArbitrageOpportunity>,
+    ) -> Vec<ArbitrageOpportunity> {
+        if opportunities.is_empty() {
+            return Vec::new();
+        }
+
+        // Nothing is executable outside the trading windows
+        if let Some(reason) = self.execution_blocked() {
+            log::debug!(
+                "Skipping {} opportunities: execution paused ({})",
+                opportunities.len(),
+                reason
+            );
+            trace::record(TraceStage::Rejection, || {
+                format!(
+                    "{} opportunities: execution paused ({})",
+                    opportunities.len(),
+                    reason
+                )
+            });
+            return Vec::new();
+        }
+
+        // Assign each opportunity to a strategy variant and filter by its profit threshold
         let profitable_opportunities: Vec<ArbitrageOpportunity> = opportunities
             .into_iter()
-            .filter(|op| op.net_profit > self.config.arbitrage.min_profit_threshold)
+            .map(|mut op| {
+                op.variant = self.experiments.assign();
+                // A threshold set through the admin API applies to every variant
+                if let Some(threshold) = runtime::controls().min_profit_threshold() {
+                    op.variant.min_profit_threshold = threshold;
+                }
+                op
+            })
+            .filter(|op| {
+                let profitable = op.net_profit > op.variant.min_profit_threshold;
+                if !profitable {
+                    trace::record(TraceStage::Rejection, || {
+                        format!(
+                            "{}: net profit ${:.2} below {} threshold ${:.2}",
+                            op.id, op.net_profit, op.variant.name, op.variant.min_profit_threshold
+                        )
+                    });
+                }
+                profitable
+            })
             .collect();
 
         if profitable_opportunities.is_empty() {
             log::info!("No profitable arbitrage opportunities found after filtering");
-            return None;
+            return Vec::new();
         }
 
-        // Calculate gas costs and adjust net profit
+        // Calculate gas costs and adjust net profit, each within the opportunity's span
         let mut evaluated_opportunities = Vec::new();
-        for mut opportunity in profitable_opportunities {
-            // Estimate gas cost based on the token path length
-            let estimated_gas = match opportunity.token_path.len() {
-                3 => 0.005, // Simple path
-                4 => 0.008, // Medium complexity
-                _ => 0.012, // Complex path
-            };
-
-            // Update gas cost and net profit
-            opportunity.estimated_gas_cost = estimated_gas;
-            opportunity.net_profit = opportunity.estimated_profit - estimated_gas;
-
-            // Only include if still profitable after gas costs
-            if opportunity.net_profit > self.config.arbitrage.min_profit_threshold {
+        for opportunity in profitable_opportunities {
+            let span = opportunity.span.clone();
+            if let Some(opportunity) =
+                latency::time_stage(Stage::Evaluation, || self.evaluate_opportunity(opportunity))
+                    .instrument(span)
+                    .await
+            {
                 evaluated_opportunities.push(opportunity);
             }
         }
 
         if evaluated_opportunities.is_empty() {
             log::info!("No profitable arbitrage opportunities found after gas cost evaluation");
-            return None;
+            return Vec::new();
         }
 
-        // Sort by net profit (descending)
+        // Sort by confidence-weighted net profit (descending)
         evaluated_opportunities.sort_by(|a, b| {
-            b.net_profit
-                .partial_cmp(&a.net_profit)
+            score(b)
+                .partial_cmp(&score(a))
                 .unwrap_or(std::cmp::Ordering::Equal)
         });
 
-        // Return the opportunity with the highest net profit
-        let best_opportunity = evaluated_opportunities.remove(0);
-        log::info!(
-            "Selected best arbitrage opportunity: {} -> {} via {} with net profit: ${:.2}",
-            best_opportunity.source_dex,
-            best_opportunity.target_dex,
-            best_opportunity.token_path.len() - 1,
-            best_opportunity.net_profit
-        );
-
-        Some(best_opportunity)
+        evaluated_opportunities
     }
 
     async fn find_optimal_path(
@@ -182,52 +692,39 @@ impl StrategyEngine for StrategyEngineImpl {
         to_token: Address,
     ) -> Result<Vec<Address>> {
         log::info!(
-            "Finding optimal path from {:?} to {:?}",
-            from_token,
-            to_token
+            "Finding optimal path from {} to {}",
+            tokens::token_label(from_token),
+            tokens::token_label(to_token)
         );
 
-        // Get all available DEX interfaces
-        let dex_interfaces = self.dex_interfaces.get_all_interfaces();
+        // Get the DEX interfaces not disabled at runtime
+        let dex_interfaces = self.dex_interfaces.enabled_interfaces();
         if dex_interfaces.is_empty() {
             return Err(anyhow::anyhow!("No DEX interfaces available"));
         }
 
-        // Define possible intermediate tokens
-        let mut intermediate_tokens = Vec::new();
-
-        // Add common tokens from config
-        for token_config in &self.config.flash_loan.tokens {
-            if let Ok(token_address) =
-                crate::utils::validate_and_parse_address(&token_config.address)
-            {
-                if token_address != from_token && token_address != to_token {
-                    intermediate_tokens.push(token_address);
-                }
-            }
-        }
-
-        // Define possible paths to check
-        let mut paths = Vec::new();
-
-        // Direct path
-        paths.push(vec![from_token, to_token]);
+        // Build the token graph from the pools known to each DEX
+        let graph = self.build_token_graph().await?;
+        log::debug!(
+            "Built token graph with {} tokens and {} pool edges",
+            graph.token_count(),
+            graph.edge_count()
+        );
 
-        // Single-hop paths through intermediate tokens
-        for &intermediate in &intermediate_tokens {
-            paths.push(vec![from_token, intermediate, to_token]);
-        }
+        // Search the graph for candidate paths (profitable cycles when from == to)
+        let max_hops = self.config.arbitrage.max_hops as usize;
+        let paths: Vec<Vec<Address>> = graph
+            .find_best_paths(from_token, to_token, max_hops)
+            .into_iter()
+            .take(MAX_CANDIDATE_PATHS)
+            .map(|path| path.tokens)
+            .collect();
 
-        // Two-hop paths through pairs of intermediate tokens
-        for i in 0..intermediate_tokens.len() {
-            for j in i + 1..intermediate_tokens.len() {
-                paths.push(vec![
-                    from_token,
-                    intermediate_tokens[i],
-                    intermediate_tokens[j],
-                    to_token,
-                ]);
-            }
+        if paths.is_empty() {
+            return Err(anyhow::anyhow!(
+                "No candidate path found within {} hops",
+                max_hops
+            ));
         }
 
         // Calculate expected profit for each path
@@ -241,11 +738,8 @@ impl StrategyEngine for StrategyEngineImpl {
             match self.calculate_expected_profit(&path, amount).await {
                 Ok(profit) => {
                     log::debug!(
-                        "Path {:?} has expected profit: ${:.2}",
-                        path.iter()
-                            .map(|&addr| format!("{:?}", addr))
-                            .collect::<Vec<_>>()
-                            .join(" -> "),
+                        "Path {} has expected profit: ${:.2}",
+                        tokens::path_label(&path),
                         profit
                     );
 
@@ -293,7 +787,7 @@ impl StrategyEngine for StrategyEngineImpl {
             // Get the best quote for this token pair
             let best_quote = match self
                 .dex_interfaces
-                .find_best_quote(token_in, token_out, current_amount)
+                .find_best_quote(token_in, token_out, current_amount, BlockTag::Latest)
                 .await?
             {
                 Some(quote) => quote,
@@ -375,8 +869,22 @@ impl StrategyEngine for StrategyEngineImpl {
 
         let profit_usd = profit_f64 * from_token_price;
 
-        // Estimate gas costs
-        let gas_cost = self.estimate_gas_cost(path.len(), dex_used).await?;
+        // Estimate gas costs, funded by the preferred flash loan provider
+        let provider = self
+            .config
+            .flash_loan
+            .providers
+            .first()
+            .copied()
+            .unwrap_or(FlashLoanProvider::Aave);
+        let from_token_price_eth =
+            PriceOracleInterface::get_price_eth(&*self.price_oracle, from_token).await?;
+        if from_token_price_eth <= 0.0 {
+            return Err(anyhow::anyhow!("Invalid ETH price for from_token"));
+        }
+        let gas_cost = self
+            .estimate_gas_cost(provider, dex_used, from_token_price / from_token_price_eth)
+            .await?;
 
         // Calculate net profit
         let net_profit = profit_usd - gas_cost;
@@ -0,0 +1,225 @@
+//! Strategy Registry Module
+//!
+//! This module is responsible for the strategies a scan runs. Each strategy searches for one
+//! kind of opportunity and scores what it finds with its net profit and confidence; the
+//! registry runs every enabled strategy on the same quote block, merges their opportunities,
+//! drops the ones several strategies found, and ranks the rest by their confidence-weighted
+//! net profit. Adding a strategy means implementing [`Strategy`] and registering it in
+//! [`create_registry`].
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::types::{Address, U256};
+use futures::future::join_all;
+use log::debug;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tracing::Instrument;
+
+use super::{BackrunStrategy, CrossDexStrategy, DepegConfig, DepegStrategy, TriangularStrategy};
+use crate::config::{Config, FlashLoanProvider};
+use crate::dex::{BlockTag, DexInterfaces, DexType};
+use crate::gas::{gas_cost_usd, route_gas, GasOptimizer};
+use crate::latency::{self, Latency, Stage};
+use crate::mempool::MempoolMonitor;
+use crate::metrics;
+use crate::price::{PinnedPrices, PriceOracleInterface};
+use crate::scanner::{pair_key, ArbitrageOpportunity, PairTiers};
+use crate::utils::{format_duration, measure_time_async, validate_and_parse_address};
+
+/// One scan the strategies search
+#[derive(Debug, Clone)]
+pub struct ScanRound {
+    /// Number of the scan, counted by the pair tiers
+    pub scan: u64,
+
+    /// Block every quote of the scan is read at
+    pub quote_block: BlockTag,
+
+    /// Clock of the scan's opportunities, started with the scan
+    pub latency: Latency,
+
+    /// Token pairs (by `pair_key`) the scan is limited to, or `None` for a full sweep
+    pub pairs: Option<HashSet<(Address, Address)>>,
+}
+
+impl ScanRound {
+    /// Whether the scan covers a token pair
+    pub fn includes(&self, token_a: Address, token_b: Address) -> bool {
+        self.pairs
+            .as_ref()
+            .is_none_or(|pairs| pairs.contains(&pair_key(token_a, token_b)))
+    }
+
+    /// Whether the scan covers any pair swapped along a token path
+    pub fn includes_path(&self, token_path: &[Address]) -> bool {
+        token_path
+            .windows(2)
+            .any(|pair| self.includes(pair[0], pair[1]))
+    }
+}
+
+/// What the strategies search with
+pub struct StrategyContext {
+    pub config: Arc<Config>,
+    pub dex_interfaces: Arc<DexInterfaces>,
+    pub price_oracle: Arc<dyn PriceOracleInterface>,
+    pub gas_optimizer: Arc<dyn GasOptimizer>,
+    pub pair_tiers: Arc<PairTiers>,
+    pub mempool: Option<Arc<MempoolMonitor>>,
+}
+
+impl StrategyContext {
+    /// Estimate the gas cost (in USD) of an Aave-funded route at the live gas price, pricing
+    /// ETH from the opportunity's pinned prices
+    pub async fn estimate_gas_cost(
+        &self,
+        hops: &[(DexType, Address)],
+        pinned_prices: &PinnedPrices,
+    ) -> Result<f64> {
+        let eth_price_usd = pinned_prices.eth_usd().context("no pinned ETH price")?;
+        let gas = route_gas(
+            FlashLoanProvider::Aave,
+            hops.iter().map(|&(dex_type, _)| dex_type),
+        );
+
+        gas_cost_usd(&*self.gas_optimizer, gas, eth_price_usd).await
+    }
+
+    /// Get one whole unit of each configured token as a cycle start, with the tokens' decimals
+    pub fn start_tokens(&self) -> (Vec<(Address, U256)>, HashMap<Address, u8>) {
+        let blacklisted = self.blacklisted();
+        let mut start_tokens = Vec::new();
+        let mut decimals = HashMap::new();
+        for token in &self.config.flash_loan.tokens {
+            if let Ok(address) = validate_and_parse_address(&token.address) {
+                if blacklisted.contains(&address) {
+                    continue;
+                }
+                start_tokens.push((address, U256::from(10).pow(U256::from(token.decimals))));
+                decimals.insert(address, token.decimals);
+            }
+        }
+
+        (start_tokens, decimals)
+    }
+
+    /// Get the addresses of the tokens the strategies skip
+    pub fn blacklisted(&self) -> HashSet<Address> {
+        self.config
+            .arbitrage
+            .pairs
+            .blacklisted(&self.config.flash_loan)
+    }
+}
+
+/// A way of finding arbitrage opportunities
+#[async_trait]
+pub trait Strategy: Send + Sync {
+    /// Name of the strategy, used in logs and metrics
+    fn name(&self) -> &'static str;
+
+    /// Find the opportunities of a scan, each scored with its net profit and confidence
+    async fn find_opportunities(&self, round: &ScanRound) -> Vec<ArbitrageOpportunity>;
+}
+
+/// Runs the registered strategies and merges what they find
+pub struct StrategyRegistry {
+    strategies: Vec<Box<dyn Strategy>>,
+}
+
+impl StrategyRegistry {
+    /// Create a registry without strategies
+    pub fn new() -> Self {
+        Self {
+            strategies: Vec::new(),
+        }
+    }
+
+    /// Add a strategy to every later scan
+    pub fn register(&mut self, strategy: Box<dyn Strategy>) {
+        self.strategies.push(strategy);
+    }
+
+    /// Get the names of the registered strategies
+    pub fn names(&self) -> Vec<&'static str> {
+        self.strategies
+            .iter()
+            .map(|strategy| strategy.name())
+            .collect()
+    }
+
+    /// Run every strategy on a scan and rank what they find, best score first
+    ///
+    /// An opportunity found by several strategies is kept once, with its best score.
+    pub async fn find_opportunities(&self, round: &ScanRound) -> Vec<ArbitrageOpportunity> {
+        let found = join_all(self.strategies.iter().map(|strategy| {
+            measure_time_async(|| strategy.find_opportunities(round))
+                .instrument(tracing::info_span!("strategy", name = strategy.name()))
+        }))
+        .await;
+
+        let mut opportunities = Vec::new();
+        for (strategy, (found, duration)) in self.strategies.iter().zip(found) {
+            debug!(
+                "{} found {} opportunities in {}",
+                strategy.name(),
+                found.len(),
+                format_duration(duration)
+            );
+            for _ in &found {
+                latency::record(Stage::Quote, duration);
+            }
+            metrics::global().increment_counter(
+                &format!("strategy_{}_opportunities", strategy.name()),
+                found.len() as u64,
+            );
+            opportunities.extend(found);
+        }
+
+        opportunities.sort_by(|a, b| {
+            score(b)
+                .partial_cmp(&score(a))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let mut seen = HashSet::new();
+        opportunities.retain(|opportunity| seen.insert(opportunity.id.clone()));
+
+        opportunities
+    }
+}
+
+impl Default for StrategyRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Score of an opportunity: its net profit (in USD) weighted by its confidence
+pub fn score(opportunity: &ArbitrageOpportunity) -> f64 {
+    opportunity.net_profit * f64::from(opportunity.confidence_score) / 100.0
+}
+
+/// Create a registry of the strategies the configuration enables
+///
+/// Cross-DEX round trips are always searched; triangular cycles and stablecoin depegs when
+/// enabled, and backruns when the mempool is watched.
+pub fn create_registry(config: &Config, context: Arc<StrategyContext>) -> Result<StrategyRegistry> {
+    let mut registry = StrategyRegistry::new();
+    registry.register(Box::new(CrossDexStrategy::new(context.clone())));
+
+    if config.arbitrage.triangular.enabled {
+        registry.register(Box::new(TriangularStrategy::new(context.clone())));
+    }
+
+    let depeg: DepegConfig = config.section()?;
+    if depeg.enabled {
+        registry.register(Box::new(DepegStrategy::new(context.clone(), depeg)));
+    }
+
+    if let Some(mempool) = context.mempool.clone() {
+        registry.register(Box::new(BackrunStrategy::new(context, mempool)));
+    }
+
+    Ok(registry)
+}
@@ -0,0 +1,324 @@
+//! Route Guard Module
+//!
+//! This module tracks the realized outcome of every route (DEX path and token path) and
+//! suspends a route for a cooldown window after consecutive losses or reverts, so a single
+//! misbehaving pair is skipped without stopping the rest of the bot.
+//!
+//! Reverts are also counted over the last hour, whatever happened in between: a route that
+//! reverts `max_reverts_per_hour` times is quarantined for `quarantine_seconds`, then
+//! re-enabled on its own. Reverts and quarantines are persisted, so a restart does not lift
+//! them.
+
+use anyhow::Result;
+use ethers::types::Address;
+use log::{info, warn};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::alerts::AlertManager;
+use crate::config::{AlertSeverity, Config};
+use crate::metrics;
+use crate::scanner::ArbitrageOpportunity;
+use crate::storage::{RouteStateRecord, Storage};
+use crate::trace::{self, TraceStage};
+use crate::utils::current_timestamp;
+
+/// Default number of reverts within an hour that quarantines a route
+pub const DEFAULT_MAX_REVERTS_PER_HOUR: u32 = 3;
+
+/// Default time a quarantined route is skipped (in seconds)
+pub const DEFAULT_QUARANTINE_SECONDS: u64 = 3600;
+
+/// Window reverts are counted over (in seconds)
+const REVERT_WINDOW_SECONDS: u64 = 3600;
+
+/// Loss-throttling state of one route
+#[derive(Debug, Clone, Default)]
+struct RouteState {
+    consecutive_losses: u32,
+    suspended_until: u64,
+    quarantined_until: u64,
+
+    /// Times of the reverts within the window, oldest first
+    reverts: Vec<u64>,
+}
+
+impl RouteState {
+    /// Forget the reverts that left the window
+    fn expire_reverts(&mut self, now: u64) {
+        let window_start = now.saturating_sub(REVERT_WINDOW_SECONDS);
+        self.reverts
+            .retain(|&reverted_at| reverted_at > window_start);
+    }
+}
+
+/// A route quarantined after repeated reverts
+#[derive(Debug, Clone, Serialize)]
+pub struct QuarantinedRoute {
+    /// Route key
+    pub route: String,
+
+    /// Unix timestamp at which the route is re-enabled
+    pub quarantined_until: u64,
+
+    /// Seconds until the route is re-enabled
+    pub remaining_seconds: u64,
+}
+
+/// Suspends routes that keep losing money
+pub struct RouteGuard {
+    enabled: bool,
+    max_consecutive_losses: u32,
+    cooldown_seconds: u64,
+
+    /// Reverts within an hour that quarantine a route (0 to never quarantine)
+    max_reverts_per_hour: u32,
+
+    quarantine_seconds: u64,
+    states: Mutex<HashMap<String, RouteState>>,
+    storage: Option<Arc<Storage>>,
+    alerts: Arc<AlertManager>,
+}
+
+/// Create a new route guard, restoring persisted route states from storage
+pub fn create_route_guard(
+    config: &Arc<Config>,
+    storage: Option<Arc<Storage>>,
+    alerts: Arc<AlertManager>,
+) -> Result<Arc<RouteGuard>> {
+    let mut states: HashMap<String, RouteState> = HashMap::new();
+    if let Some(storage) = &storage {
+        let now = current_timestamp();
+        for record in storage.route_states()? {
+            if record.suspended_until > now {
+                info!(
+                    "Route {} remains suspended for {}s",
+                    record.route,
+                    record.suspended_until - now
+                );
+            }
+            if record.quarantined_until > now {
+                info!(
+                    "Route {} remains quarantined for {}s",
+                    record.route,
+                    record.quarantined_until - now
+                );
+            }
+            states.insert(
+                record.route,
+                RouteState {
+                    consecutive_losses: record.consecutive_losses,
+                    suspended_until: record.suspended_until,
+                    quarantined_until: record.quarantined_until,
+                    reverts: Vec::new(),
+                },
+            );
+        }
+        for (route, reverted_at) in
+            storage.route_reverts_since(now.saturating_sub(REVERT_WINDOW_SECONDS))?
+        {
+            states.entry(route).or_default().reverts.push(reverted_at);
+        }
+    }
+
+    let guard = RouteGuard {
+        enabled: config.route_guard.enabled,
+        max_consecutive_losses: config.route_guard.max_consecutive_losses.max(1),
+        cooldown_seconds: config.route_guard.cooldown_seconds,
+        max_reverts_per_hour: config
+            .route_guard
+            .max_reverts_per_hour
+            .unwrap_or(DEFAULT_MAX_REVERTS_PER_HOUR),
+        quarantine_seconds: config
+            .route_guard
+            .quarantine_seconds
+            .unwrap_or(DEFAULT_QUARANTINE_SECONDS),
+        states: Mutex::new(states),
+        storage,
+        alerts,
+    };
+    guard.update_suspended_gauge(current_timestamp());
+
+    Ok(Arc::new(guard))
+}
+
+/// Build the key identifying a route
+pub fn route_key(dex_path: &[String], token_path: &[Address]) -> String {
+    let tokens = token_path
+        .iter()
+        .map(|address| format!("{:?}", address))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    format!("{}:{}", dex_path.join("->"), tokens)
+}
+
+impl RouteGuard {
+    /// Whether the opportunity's route may currently be executed
+    pub fn allows(&self, opportunity: &ArbitrageOpportunity) -> bool {
+        if !self.enabled {
+            return true;
+        }
+
+        let route = route_key(&opportunity.dex_path(), &opportunity.token_path);
+
+        let now = current_timestamp();
+        let states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(state) = states.get(&route) else {
+            return true;
+        };
+
+        if state.quarantined_until > now {
+            trace::record(TraceStage::Rejection, || {
+                format!(
+                    "{}: route {} quarantined after repeated reverts",
+                    opportunity.id, route
+                )
+            });
+            return false;
+        }
+        if state.suspended_until > now {
+            trace::record(TraceStage::Rejection, || {
+                format!("{}: route {} suspended after losses", opportunity.id, route)
+            });
+            return false;
+        }
+
+        true
+    }
+
+    /// Routes currently quarantined after repeated reverts, the first to be re-enabled first
+    pub fn quarantined(&self) -> Vec<QuarantinedRoute> {
+        let now = current_timestamp();
+        let states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+        let mut quarantined: Vec<QuarantinedRoute> = states
+            .iter()
+            .filter(|(_, state)| state.quarantined_until > now)
+            .map(|(route, state)| QuarantinedRoute {
+                route: route.clone(),
+                quarantined_until: state.quarantined_until,
+                remaining_seconds: state.quarantined_until - now,
+            })
+            .collect();
+        quarantined.sort_by_key(|route| route.quarantined_until);
+
+        quarantined
+    }
+
+    /// Record the on-chain outcome of a trade on a route
+    ///
+    /// A revert or a negative realized PnL counts as a loss; a profitable trade resets the
+    /// count. Once the count reaches the limit the route is suspended for the cooldown, and
+    /// until a profitable trade resets it, every further loss suspends it again. Independently,
+    /// a revert that brings the route's reverts within the hour to the limit quarantines it.
+    pub fn record_outcome(&self, route: &str, success: bool, realized_pnl: Option<f64>) {
+        if !self.enabled {
+            return;
+        }
+
+        let is_loss = !success || realized_pnl.is_some_and(|pnl| pnl < 0.0);
+        if !is_loss && realized_pnl.is_none() {
+            // Without a realized PnL there is nothing to learn from a successful trade
+            return;
+        }
+
+        let now = current_timestamp();
+        let (state, suspended, quarantined) = {
+            let mut states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+            let state = states.entry(route.to_string()).or_default();
+
+            let mut suspended = false;
+            if is_loss {
+                state.consecutive_losses += 1;
+                if state.consecutive_losses >= self.max_consecutive_losses {
+                    state.suspended_until = now + self.cooldown_seconds;
+                    suspended = true;
+                }
+            } else {
+                // A profitable trade does not forgive recent reverts
+                state.consecutive_losses = 0;
+                state.suspended_until = 0;
+            }
+
+            let mut quarantined = false;
+            if !success {
+                state.expire_reverts(now);
+                state.reverts.push(now);
+                if self.max_reverts_per_hour > 0
+                    && state.reverts.len() >= self.max_reverts_per_hour as usize
+                    && state.quarantined_until <= now
+                {
+                    state.quarantined_until = now + self.quarantine_seconds;
+                    quarantined = true;
+                }
+            }
+
+            (state.clone(), suspended, quarantined)
+        };
+
+        if suspended {
+            metrics::global().increment_counter("route_suspensions_total", 1);
+            self.alerts.raise(
+                AlertSeverity::Warning,
+                "route_guard",
+                format!(
+                    "Route {} suspended for {}s after {} consecutive losses",
+                    route, self.cooldown_seconds, state.consecutive_losses
+                ),
+            );
+        }
+        if quarantined {
+            metrics::global().increment_counter("route_quarantines_total", 1);
+            self.alerts.raise(
+                AlertSeverity::Warning,
+                "route_guard",
+                format!(
+                    "Route {} quarantined for {}s after {} reverts within an hour",
+                    route,
+                    self.quarantine_seconds,
+                    state.reverts.len()
+                ),
+            );
+        }
+        self.update_suspended_gauge(now);
+
+        if let Some(storage) = &self.storage {
+            if !success {
+                if let Err(e) = storage.record_route_revert(
+                    route,
+                    now,
+                    now.saturating_sub(REVERT_WINDOW_SECONDS),
+                ) {
+                    warn!("Failed to persist revert of route {}: {}", route, e);
+                }
+            }
+
+            let record = RouteStateRecord {
+                route: route.to_string(),
+                consecutive_losses: state.consecutive_losses,
+                suspended_until: state.suspended_until,
+                quarantined_until: state.quarantined_until,
+            };
+            if let Err(e) = storage.save_route_state(&record) {
+                warn!("Failed to persist state of route {}: {}", route, e);
+            }
+        }
+    }
+
+    /// Export the number of currently suspended and quarantined routes
+    fn update_suspended_gauge(&self, now: u64) {
+        let states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+        let suspended = states
+            .values()
+            .filter(|state| state.suspended_until > now)
+            .count();
+        let quarantined = states
+            .values()
+            .filter(|state| state.quarantined_until > now)
+            .count();
+
+        metrics::global().set_gauge("routes_suspended", suspended as f64);
+        metrics::global().set_gauge("routes_quarantined", quarantined as f64);
+    }
+}
@@ -0,0 +1,116 @@
+//! Execution Schedule Module
+//!
+//! This module decides whether execution is currently allowed, based on the configured
+//! recurring UTC blackout hours and one-off blackout events.
+
+use log::info;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::config::{BlackoutEventConfig, BlackoutHoursConfig, Config, Weekday};
+use crate::metrics;
+use crate::utils::current_timestamp;
+
+/// Seconds in a day
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Gates execution on the configured trading windows
+#[derive(Debug)]
+pub struct ExecutionSchedule {
+    blackout_hours: Vec<BlackoutHoursConfig>,
+    blackout_events: Vec<BlackoutEventConfig>,
+    paused: AtomicBool,
+}
+
+impl ExecutionSchedule {
+    /// Create an execution schedule from the configuration
+    pub fn new(config: &Config) -> Self {
+        Self {
+            blackout_hours: config.schedule.blackout_hours.clone(),
+            blackout_events: config.schedule.blackout_events.clone(),
+            paused: AtomicBool::new(false),
+        }
+    }
+
+    /// Get the reason execution is currently disabled, if it is
+    ///
+    /// Changes between paused and active are logged once and exported as a gauge.
+    pub fn check(&self) -> Option<String> {
+        let reason = self.blocked_reason(current_timestamp());
+
+        let paused = reason.is_some();
+        if self.paused.swap(paused, Ordering::Relaxed) != paused {
+            match &reason {
+                Some(reason) => info!("Execution paused: {}", reason),
+                None => info!("Execution resumed: trading window open"),
+            }
+            metrics::global().set_gauge("execution_paused", if paused { 1.0 } else { 0.0 });
+        }
+
+        reason
+    }
+
+    /// Get the reason execution is disabled at `now` (Unix seconds), if it is
+    pub fn blocked_reason(&self, now: u64) -> Option<String> {
+        if let Some(event) = self
+            .blackout_events
+            .iter()
+            .find(|event| event.start <= now && now < event.end)
+        {
+            return Some(format!("blackout event {}", event.name));
+        }
+
+        let hour = ((now % SECONDS_PER_DAY) / 3600) as u8;
+        let day = weekday(now);
+        self.blackout_hours
+            .iter()
+            .find(|window| window_contains(window, day, hour))
+            .map(|window| {
+                format!(
+                    "blackout hours {:02}:00-{:02}:00 UTC",
+                    window.start_hour, window.end_hour
+                )
+            })
+    }
+}
+
+/// Whether a blackout window covers the given UTC day and hour
+///
+/// A window that wraps past midnight belongs to the day it starts on.
+fn window_contains(window: &BlackoutHoursConfig, day: Weekday, hour: u8) -> bool {
+    let applies_on = |day: Weekday| window.days.is_empty() || window.days.contains(&day);
+
+    if window.start_hour < window.end_hour {
+        applies_on(day) && window.start_hour <= hour && hour < window.end_hour
+    } else if hour >= window.start_hour {
+        applies_on(day)
+    } else {
+        hour < window.end_hour && applies_on(previous_day(day))
+    }
+}
+
+/// Get the UTC day of the week of a Unix timestamp
+fn weekday(timestamp: u64) -> Weekday {
+    // 1970-01-01 was a Thursday
+    match (timestamp / SECONDS_PER_DAY) % 7 {
+        0 => Weekday::Thursday,
+        1 => Weekday::Friday,
+        2 => Weekday::Saturday,
+        3 => Weekday::Sunday,
+        4 => Weekday::Monday,
+        5 => Weekday::Tuesday,
+        _ => Weekday::Wednesday,
+    }
+}
+
+/// Get the day before a day of the week
+fn previous_day(day: Weekday) -> Weekday {
+    match day {
+        Weekday::Monday => Weekday::Sunday,
+        Weekday::Tuesday => Weekday::Monday,
+        Weekday::Wednesday => Weekday::Tuesday,
+        Weekday::Thursday => Weekday::Wednesday,
+        Weekday::Friday => Weekday::Thursday,
+        Weekday::Saturday => Weekday::Friday,
+        Weekday::Sunday => Weekday::Saturday,
+    }
+}
@@ -0,0 +1,165 @@
+//! Trade Sizing Module
+//!
+//! This module is responsible for choosing how much to borrow for an opportunity from its
+//! simulated profit at a grid of input sizes, and for rejecting profiles that are not concave,
+//! since round trips through constant-product and stable pools never are. The size itself is
+//! solved for between the grid points around the peak.
+
+use anyhow::Result;
+use futures::future::try_join;
+use std::future::Future;
+
+use crate::config::SizingConfig;
+
+/// Simulated profit at one input size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProfitPoint {
+    /// Input size (in units of the borrowed token)
+    pub input: f64,
+
+    /// Output minus input (in units of the borrowed token)
+    pub profit: f64,
+}
+
+/// Simulated profit across increasing input sizes
+#[derive(Debug, Clone, Default)]
+pub struct ProfitProfile {
+    points: Vec<ProfitPoint>,
+}
+
+impl ProfitProfile {
+    /// Create a profile from points sorted by increasing input
+    pub fn new(points: Vec<ProfitPoint>) -> Self {
+        Self { points }
+    }
+
+    /// Get the input sizes to simulate for a sizing configuration
+    pub fn grid(config: &SizingConfig) -> Vec<f64> {
+        (0..config.grid_points)
+            .map(|i| config.min_input * config.growth_factor.powi(i as i32))
+            .collect()
+    }
+
+    /// Get the simulated points
+    pub fn points(&self) -> &[ProfitPoint] {
+        &self.points
+    }
+
+    /// Get the index of the most profitable point
+    pub fn peak_index(&self) -> Option<usize> {
+        self.points
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| {
+                a.profit
+                    .partial_cmp(&b.profit)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .map(|(index, _)| index)
+    }
+
+    /// Find the first point where the marginal profit increases, if any
+    ///
+    /// Marginal profit may rise by `tolerance` of its previous magnitude to absorb rounding in
+    /// the quotes.
+    pub fn concavity_violation(&self, tolerance: f64) -> Option<usize> {
+        let slopes: Vec<f64> = self
+            .points
+            .windows(2)
+            .map(|pair| (pair[1].profit - pair[0].profit) / (pair[1].input - pair[0].input))
+            .collect();
+
+        slopes
+            .windows(2)
+            .position(|pair| pair[1] > pair[0] + tolerance * pair[0].abs().max(f64::EPSILON))
+            .map(|index| index + 1)
+    }
+
+    /// Get the inputs of the grid points either side of the peak, which bracket the optimum
+    /// of a concave profile
+    pub fn peak_bracket(&self) -> Option<(f64, f64)> {
+        let peak = self.peak_index()?;
+        let low = self.points[peak.saturating_sub(1)].input;
+        let high = self.points[(peak + 1).min(self.points.len() - 1)].input;
+        Some((low, high))
+    }
+
+    /// Describe the profile for diagnostics
+    pub fn describe(&self) -> String {
+        self.points
+            .iter()
+            .map(|point| format!("{:.4}:{:+.6}", point.input, point.profit))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// One swap through a constant-product pool, with reserves oriented for the swap
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConstantProductLeg {
+    /// Reserve of the token sold
+    pub reserve_in: f64,
+
+    /// Reserve of the token bought
+    pub reserve_out: f64,
+
+    /// Pool fee (in basis points)
+    pub fee_bps: u32,
+}
+
+/// Solves for the input size that maximizes a route's profit
+#[derive(Debug, Clone, Copy)]
+pub struct OptimalSizer {
+    iterations: usize,
+}
+
+impl OptimalSizer {
+    /// Create a sizer using the configured number of search iterations
+    pub fn new(config: &SizingConfig) -> Self {
+        Self {
+            iterations: config.search_iterations.max(1),
+        }
+    }
+
+    /// Solve for the profit-maximizing input of a round trip through two constant-product pools
+    ///
+    /// Two swaps compose into `out = a·x / (b + c·x)`, so profit `out - x` peaks at
+    /// `x = (√(a·b) - b) / c`. Amounts are in the units of the reserves; `None` means no input
+    /// is profitable.
+    pub fn two_pool_optimum(first: ConstantProductLeg, second: ConstantProductLeg) -> Option<f64> {
+        let gamma = |leg: &ConstantProductLeg| (10_000.0 - f64::from(leg.fee_bps)) / 10_000.0;
+        let (gamma1, gamma2) = (gamma(&first), gamma(&second));
+
+        let a = gamma1 * gamma2 * first.reserve_out * second.reserve_out;
+        let b = first.reserve_in * second.reserve_in;
+        let c = gamma1 * (second.reserve_in + gamma2 * first.reserve_out);
+        if a <= b || c <= 0.0 {
+            return None;
+        }
+
+        Some(((a * b).sqrt() - b) / c)
+    }
+
+    /// Find the input in `[low, high]` that maximizes a concave profit function by ternary search
+    ///
+    /// Both probes of each iteration are evaluated concurrently.
+    pub async fn ternary_search<F, Fut>(&self, low: f64, high: f64, profit: F) -> Result<f64>
+    where
+        F: Fn(f64) -> Fut,
+        Fut: Future<Output = Result<f64>>,
+    {
+        let (mut low, mut high) = (low, high);
+        for _ in 0..self.iterations {
+            let third = (high - low) / 3.0;
+            let (left, right) = (low + third, high - third);
+            let (left_profit, right_profit) = try_join(profit(left), profit(right)).await?;
+            if left_profit < right_profit {
+                low = left;
+            } else {
+                high = right;
+            }
+        }
+
+        Ok((low + high) / 2.0)
+    }
+}
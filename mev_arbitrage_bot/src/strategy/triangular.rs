@@ -0,0 +1,166 @@
+//! Triangular Strategy Module
+//!
+//! This module is responsible for single-DEX cycles through three pools (A -> B -> C -> A),
+//! searched in the cached pool reserves starting from one whole unit of each configured token.
+
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use std::sync::Arc;
+
+use super::{ScanRound, Strategy, StrategyContext, StrategyVariant};
+use crate::config::FlashLoanProvider;
+use crate::dex::pool_key;
+use crate::logging;
+use crate::scanner::{find_cycles, ArbitrageOpportunity};
+use crate::tokens;
+use crate::trace::{self, TraceStage};
+use crate::utils::{current_timestamp, u256_to_decimal};
+
+/// Finds profitable three-pool cycles within each DEX
+pub struct TriangularStrategy {
+    context: Arc<StrategyContext>,
+}
+
+impl TriangularStrategy {
+    /// Create the strategy
+    pub fn new(context: Arc<StrategyContext>) -> Self {
+        Self { context }
+    }
+}
+
+#[async_trait]
+impl Strategy for TriangularStrategy {
+    fn name(&self) -> &'static str {
+        "triangular"
+    }
+
+    async fn find_opportunities(&self, round: &ScanRound) -> Vec<ArbitrageOpportunity> {
+        let quote_block = round.quote_block;
+        let (start_tokens, decimals) = self.context.start_tokens();
+
+        let quarantine = self.context.dex_interfaces.quarantine();
+        let overrides = self.context.dex_interfaces.overrides();
+        let mut pools = self.context.dex_interfaces.pool_cache().pools().await;
+        let blacklisted = self.context.blacklisted();
+        pools.retain(|pool| {
+            !quarantine.is_quarantined(pool_key(pool.dex_type, pool.token0, pool.token1))
+                && overrides.allows(pool.dex_type, pool.address)
+                && !blacklisted.contains(&pool.token0)
+                && !blacklisted.contains(&pool.token1)
+        });
+        let mut cycles = find_cycles(&pools, &start_tokens);
+        cycles.retain(|cycle| round.includes_path(&cycle.token_path));
+        debug!(
+            "Found {} triangular cycles across {} cached pools",
+            cycles.len(),
+            pools.len()
+        );
+
+        let mut opportunities = Vec::new();
+        for cycle in cycles {
+            let token_a = cycle.token_path[0];
+            let pinned_prices = match self.context.price_oracle.pin_prices(&[token_a]).await {
+                Ok(pinned_prices) => pinned_prices,
+                Err(e) => {
+                    warn!(
+                        "Failed to get the USD price of {}: {}",
+                        tokens::token_label(token_a),
+                        e
+                    );
+                    continue;
+                }
+            };
+            let price_usd = pinned_prices.usd(token_a).unwrap_or_default();
+            let token_decimals = decimals.get(&token_a).copied().unwrap_or(18);
+
+            let profit_usd = u256_to_decimal(cycle.profit(), token_decimals) * price_usd;
+            let loan_amount_usd = u256_to_decimal(cycle.amount_in, token_decimals) * price_usd;
+
+            let hops: Vec<_> = cycle
+                .pools
+                .iter()
+                .map(|&pool| (cycle.dex_type, pool))
+                .collect();
+            let estimated_gas_cost =
+                match self.context.estimate_gas_cost(&hops, &pinned_prices).await {
+                    Ok(gas_cost) => gas_cost,
+                    Err(e) => {
+                        warn!(
+                            "Failed to cost the gas of {}: {}",
+                            tokens::path_label(&cycle.token_path),
+                            e
+                        );
+                        continue;
+                    }
+                };
+            let net_profit = profit_usd - estimated_gas_cost;
+            if net_profit <= 0.0 {
+                trace::record(TraceStage::Rejection, || {
+                    format!(
+                        "{} on {:?}: cycle profit ${:.2} does not cover gas ${:.2}",
+                        tokens::path_label(&cycle.token_path),
+                        cycle.dex_type,
+                        profit_usd,
+                        estimated_gas_cost
+                    )
+                });
+                continue;
+            }
+
+            let confidence = self
+                .context
+                .assess_confidence(&cycle.token_path, &hops, cycle.amount_in, quote_block)
+                .await;
+            let dex = cycle.dex_type.name().to_string();
+            let id = format!(
+                "tri_{}_{}",
+                cycle.dex_type as u8,
+                cycle
+                    .token_path
+                    .iter()
+                    .map(|&token| tokens::token_label(token))
+                    .collect::<Vec<_>>()
+                    .join("_")
+            );
+            let opportunity = ArbitrageOpportunity {
+                span: logging::opportunity_span(&id),
+                id,
+                timestamp: current_timestamp(),
+                source_dex: dex.clone(),
+                target_dex: dex,
+                hops,
+                token_path: cycle.token_path.clone(),
+                estimated_profit: profit_usd,
+                required_loan_amount: loan_amount_usd,
+                estimated_gas_cost,
+                net_profit,
+                confidence_score: confidence.score(),
+                variant: StrategyVariant::default(),
+                flash_loan_provider: FlashLoanProvider::Aave,
+                flash_loan_fee: 0.0,
+                input_amount: None,
+                pinned_prices,
+                quote_block,
+                latency: round.latency,
+            };
+            opportunities.push(opportunity);
+        }
+
+        opportunities.sort_by(|a, b| {
+            b.net_profit
+                .partial_cmp(&a.net_profit)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        opportunities.truncate(self.context.config.arbitrage.triangular.max_cycles_per_scan);
+
+        for opportunity in &opportunities {
+            info!("Found triangular arbitrage opportunity: {}", opportunity);
+            for pair in opportunity.token_path.windows(2) {
+                self.context.pair_tiers.record_opportunity(pair[0], pair[1]);
+            }
+            trace::record(TraceStage::Candidate, || opportunity.to_string());
+        }
+
+        opportunities
+    }
+}
@@ -0,0 +1,218 @@
+//! Mock DEX Module
+//!
+//! This module is responsible for a DEX interface over scripted constant-product pools. Quotes
+//! are computed from the reserves a test set, the same way the real interfaces compute them
+//! from cached reserves, and are the same at every block.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::types::{Address, U256};
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use crate::dex::{
+    constant_product_price_impact, get_amount_out, BlockTag, DexInterface, DexType, PoolInfo,
+    TradeQuote,
+};
+
+/// Fee of the pools added without one (in basis points)
+const DEFAULT_FEE_BPS: u32 = 30;
+
+/// DEX interface quoting scripted pools
+pub struct MockDexInterface {
+    dex_type: DexType,
+    factory_address: Address,
+    router_address: Address,
+    pools: Mutex<Vec<PoolInfo>>,
+
+    /// Pools whose reads fail, as a pool that stopped responding would
+    failing: Mutex<HashSet<Address>>,
+}
+
+impl MockDexInterface {
+    /// Create a mock DEX without pools
+    pub fn new(dex_type: DexType) -> Self {
+        Self {
+            dex_type,
+            factory_address: Address::zero(),
+            router_address: Address::zero(),
+            pools: Mutex::new(Vec::new()),
+            failing: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Set the router address the DEX reports
+    pub fn with_router(mut self, router_address: Address) -> Self {
+        self.router_address = router_address;
+        self
+    }
+
+    /// Add a pool with the default fee
+    pub fn with_pool(
+        self,
+        address: Address,
+        tokens: (Address, Address),
+        reserves: (U256, U256),
+    ) -> Self {
+        self.add_pool(address, tokens, reserves, DEFAULT_FEE_BPS);
+        self
+    }
+
+    /// Add a pool, or replace the one at the address
+    pub fn add_pool(
+        &self,
+        address: Address,
+        tokens: (Address, Address),
+        reserves: (U256, U256),
+        fee: u32,
+    ) {
+        let pool = PoolInfo {
+            address,
+            dex_type: self.dex_type,
+            tokens: vec![tokens.0, tokens.1],
+            reserves: vec![reserves.0, reserves.1],
+            fee,
+        };
+
+        let mut pools = self.lock_pools();
+        pools.retain(|existing| existing.address != address);
+        pools.push(pool);
+    }
+
+    /// Set a pool's reserves, in the order of its tokens
+    pub fn set_reserves(&self, address: Address, reserves: (U256, U256)) -> Result<()> {
+        let mut pools = self.lock_pools();
+        let pool = pools
+            .iter_mut()
+            .find(|pool| pool.address == address)
+            .context("Pool not found")?;
+        pool.reserves = vec![reserves.0, reserves.1];
+
+        Ok(())
+    }
+
+    /// Make a pool's reads fail, or succeed again
+    pub fn set_failing(&self, address: Address, failing: bool) {
+        let mut failing_pools = self.failing.lock().unwrap_or_else(|e| e.into_inner());
+        if failing {
+            failing_pools.insert(address);
+        } else {
+            failing_pools.remove(&address);
+        }
+    }
+
+    /// Get the pool trading a pair, failing if its reads are scripted to
+    fn pool(&self, token_a: Address, token_b: Address) -> Result<Option<PoolInfo>> {
+        let pool = self
+            .lock_pools()
+            .iter()
+            .find(|pool| {
+                (pool.tokens[0] == token_a && pool.tokens[1] == token_b)
+                    || (pool.tokens[0] == token_b && pool.tokens[1] == token_a)
+            })
+            .cloned();
+
+        match pool {
+            Some(pool) if self.is_failing(pool.address) => {
+                Err(anyhow::anyhow!("Pool {:?} is failing", pool.address))
+            }
+            pool => Ok(pool),
+        }
+    }
+
+    fn is_failing(&self, address: Address) -> bool {
+        self.failing
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(&address)
+    }
+
+    fn lock_pools(&self) -> std::sync::MutexGuard<'_, Vec<PoolInfo>> {
+        self.pools.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl DexInterface for MockDexInterface {
+    fn name(&self) -> &str {
+        self.dex_type.name()
+    }
+
+    fn dex_type(&self) -> DexType {
+        self.dex_type
+    }
+
+    fn factory_address(&self) -> Address {
+        self.factory_address
+    }
+
+    fn router_address(&self) -> Address {
+        self.router_address
+    }
+
+    async fn get_pools(&self) -> Result<Vec<PoolInfo>> {
+        Ok(self
+            .lock_pools()
+            .iter()
+            .filter(|pool| !self.is_failing(pool.address))
+            .cloned()
+            .collect())
+    }
+
+    async fn get_pool(&self, token_a: Address, token_b: Address) -> Result<Option<PoolInfo>> {
+        self.pool(token_a, token_b)
+    }
+
+    async fn get_reserves(&self, pool: Address, _block: BlockTag) -> Result<Vec<U256>> {
+        if self.is_failing(pool) {
+            return Err(anyhow::anyhow!("Pool {:?} is failing", pool));
+        }
+
+        self.lock_pools()
+            .iter()
+            .find(|existing| existing.address == pool)
+            .map(|existing| existing.reserves.clone())
+            .context("Pool not found")
+    }
+
+    async fn get_quote(
+        &self,
+        input_token: Address,
+        output_token: Address,
+        input_amount: U256,
+        _block: BlockTag,
+    ) -> Result<TradeQuote> {
+        let pool = self
+            .pool(input_token, output_token)?
+            .context("Pool not found")?;
+
+        let (reserve_in, reserve_out) = if pool.tokens[0] == input_token {
+            (pool.reserves[0], pool.reserves[1])
+        } else {
+            (pool.reserves[1], pool.reserves[0])
+        };
+        let output_amount = get_amount_out(input_amount, reserve_in, reserve_out, pool.fee);
+        let price_impact =
+            constant_product_price_impact(&pool, input_token, input_amount, output_amount);
+
+        Ok(TradeQuote {
+            input_token,
+            output_token,
+            input_amount,
+            output_amount,
+            price_impact,
+            path: vec![input_token, output_token],
+            pools: vec![pool.address],
+            dex_type: self.dex_type,
+        })
+    }
+
+    async fn find_best_path(
+        &self,
+        input_token: Address,
+        output_token: Address,
+        _input_amount: U256,
+    ) -> Result<Vec<Address>> {
+        Ok(vec![input_token, output_token])
+    }
+}
@@ -0,0 +1,97 @@
+//! Mock Gas Module
+//!
+//! This module is responsible for a gas price optimizer reporting the fees a test set. Every
+//! inclusion urgency gets the same fees, and race winners are outbid like the real optimizer
+//! outbids them.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use ethers::types::{Address, TransactionRequest, U256};
+use std::sync::Mutex;
+
+use crate::competition::{CompetingTips, CompetitionConfig};
+use crate::config::InclusionUrgency;
+use crate::gas::GasOptimizer;
+
+/// Fees a mock optimizer reports (in wei)
+#[derive(Debug, Clone, Copy, Default)]
+struct Fees {
+    base_fee: U256,
+    priority_fee: U256,
+    blob_base_fee: Option<U256>,
+    l1_data_fee: U256,
+}
+
+/// Gas price optimizer with scripted fees
+pub struct MockGasOptimizer {
+    fees: Mutex<Fees>,
+    competing_tips: CompetingTips,
+}
+
+impl MockGasOptimizer {
+    /// Create an optimizer reporting a base fee and priority fee (in wei)
+    pub fn new(base_fee: U256, priority_fee: U256) -> Self {
+        Self {
+            fees: Mutex::new(Fees {
+                base_fee,
+                priority_fee,
+                ..Fees::default()
+            }),
+            competing_tips: CompetingTips::new(&CompetitionConfig::default()),
+        }
+    }
+
+    /// Set the base fee and priority fee (in wei)
+    pub fn set_fees(&self, base_fee: U256, priority_fee: U256) {
+        let mut fees = self.lock();
+        fees.base_fee = base_fee;
+        fees.priority_fee = priority_fee;
+    }
+
+    /// Set the blob base fee (in wei), or none for a chain without blobs
+    pub fn set_blob_base_fee(&self, blob_base_fee: Option<U256>) {
+        self.lock().blob_base_fee = blob_base_fee;
+    }
+
+    /// Set the L1 data fee (in wei) charged on top of every transaction's execution gas
+    pub fn set_l1_data_fee(&self, l1_data_fee: U256) {
+        self.lock().l1_data_fee = l1_data_fee;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Fees> {
+        self.fees.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl GasOptimizer for MockGasOptimizer {
+    async fn get_optimal_gas_price(&self) -> Result<U256> {
+        let fees = *self.lock();
+        Ok(fees.base_fee.saturating_add(fees.priority_fee))
+    }
+
+    async fn get_eip1559_fee_data(&self, _urgency: InclusionUrgency) -> Result<(U256, U256)> {
+        let fees = *self.lock();
+        Ok((fees.base_fee, fees.priority_fee))
+    }
+
+    async fn get_blob_base_fee(&self) -> Result<Option<U256>> {
+        Ok(self.lock().blob_base_fee)
+    }
+
+    async fn get_l1_data_fee(&self, _tx: &TransactionRequest) -> Result<U256> {
+        Ok(self.lock().l1_data_fee)
+    }
+
+    async fn update_gas_price_estimate(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn record_winning_tip(&self, pools: &[Address], priority_fee: U256) {
+        self.competing_tips.record(pools, priority_fee);
+    }
+
+    fn competing_priority_fee(&self, pools: &[Address]) -> Option<U256> {
+        self.competing_tips.outbid(pools)
+    }
+}
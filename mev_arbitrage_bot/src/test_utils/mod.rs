@@ -0,0 +1,81 @@
+//! Test Utilities Module
+//!
+//! This module is responsible for the deterministic stand-ins unit tests run the scanner and
+//! strategies against, behind the `test-utils` feature. [`MockDexInterface`] quotes its pools'
+//! constant-product reserves, [`MockPriceOracle`] prices tokens from a table and
+//! [`MockGasOptimizer`] reports fixed fees, all scriptable while a test runs.
+//!
+//! The components still take a client, so [`offline_client`] provides one that reaches no
+//! node: any call through it is refused at once, and a test that depends on the chain fails
+//! instead of hanging. A test that needs a few chain reads scripts them on the mock provider
+//! behind [`mock_client`] instead. [`offline_config`] is the default configuration with every read the
+//! scan makes on its own (block numbers, storage, pair discovery) turned off.
+
+mod dex;
+mod gas;
+mod price;
+
+pub use dex::MockDexInterface;
+pub use gas::MockGasOptimizer;
+pub use price::MockPriceOracle;
+
+use anyhow::Result;
+use ethers::providers::{Http, MockProvider, Provider};
+use reqwest::Url;
+use std::sync::Arc;
+
+use crate::blockchain::{FailoverTransport, RpcClient};
+use crate::config::{self, Config, QuoteBlock};
+use crate::dex::{DexInterface, DexInterfaces};
+use crate::utils::Multicall;
+
+/// Address nothing listens on, so calls through the offline client are refused
+const OFFLINE_RPC_URL: &str = "http://127.0.0.1:1";
+
+/// Create a client whose every call fails without reaching a node
+pub fn offline_client() -> Arc<RpcClient> {
+    let url = Url::parse(OFFLINE_RPC_URL).expect("Offline RPC URL is valid");
+    Arc::new(Provider::new(FailoverTransport::single(Http::new(url))))
+}
+
+/// Create a client answering from the responses scripted on a mock provider
+///
+/// The provider answers the most recently pushed response first, and records the requests
+/// made so tests can assert on them.
+pub fn mock_client() -> (Arc<RpcClient>, MockProvider) {
+    let mock = MockProvider::new();
+    let client = Provider::new(FailoverTransport::single(mock.clone()));
+
+    (Arc::new(client), mock)
+}
+
+/// Build the configuration of a scan that reads nothing but its mocks
+pub fn offline_config() -> Config {
+    let mut config = config::create_default_config();
+
+    config.ethereum.rpc_url = OFFLINE_RPC_URL.to_string();
+    config.ethereum.use_websocket = Some(false);
+
+    // Pinned quotes read the block number; the mocks ignore the block anyway
+    config.arbitrage.quote_block = QuoteBlock::Latest;
+
+    config.storage.enabled = false;
+    config.dex.pair_discovery.enabled = false;
+    config.mev_share.enabled = false;
+
+    config
+}
+
+/// Collect mock DEX interfaces the way `dex::create_interfaces` collects the real ones
+pub fn mock_interfaces(
+    config: &Config,
+    interfaces: impl IntoIterator<Item = Arc<dyn DexInterface>>,
+) -> Result<Arc<DexInterfaces>> {
+    let multicall = Arc::new(Multicall::new(config, offline_client())?);
+    let mut dex_interfaces = DexInterfaces::new(config, multicall);
+    for interface in interfaces {
+        dex_interfaces.add_interface(interface);
+    }
+
+    Ok(Arc::new(dex_interfaces))
+}
@@ -0,0 +1,113 @@
+//! Mock Price Module
+//!
+//! This module is responsible for a price oracle answering from a table of USD prices a test
+//! sets. ETH prices are derived from the ETH price in USD, and prices never refresh, so every
+//! evaluation in a test pins the same values until the test changes them.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::types::Address;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::price::{PinnedPrices, PriceOracleInterface, PriceSource};
+use crate::tokens;
+
+/// Price oracle with scripted prices
+pub struct MockPriceOracle {
+    eth_price_usd: Mutex<f64>,
+    prices_usd: Mutex<HashMap<Address, f64>>,
+}
+
+impl MockPriceOracle {
+    /// Create an oracle without token prices, at an ETH price in USD
+    pub fn new(eth_price_usd: f64) -> Self {
+        Self {
+            eth_price_usd: Mutex::new(eth_price_usd),
+            prices_usd: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Set a token's USD price
+    pub fn with_price(self, token: Address, price_usd: f64) -> Self {
+        self.set_price(token, price_usd);
+        self
+    }
+
+    /// Set a token's USD price
+    pub fn set_price(&self, token: Address, price_usd: f64) {
+        self.lock_prices().insert(token, price_usd);
+    }
+
+    /// Remove a token's price, as if no source could price it
+    pub fn remove_price(&self, token: Address) {
+        self.lock_prices().remove(&token);
+    }
+
+    /// Set the ETH price in USD that ETH prices are derived from
+    pub fn set_eth_price(&self, eth_price_usd: f64) {
+        *self.eth_price_usd.lock().unwrap_or_else(|e| e.into_inner()) = eth_price_usd;
+    }
+
+    fn eth_price(&self) -> f64 {
+        *self.eth_price_usd.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock_prices(&self) -> std::sync::MutexGuard<'_, HashMap<Address, f64>> {
+        self.prices_usd.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl PriceOracleInterface for MockPriceOracle {
+    async fn get_price_usd(&self, token: Address) -> Result<f64> {
+        self.lock_prices().get(&token).copied().context(format!(
+            "Price not found for token: {}",
+            tokens::token_label(token)
+        ))
+    }
+
+    async fn get_price_eth(&self, token: Address) -> Result<f64> {
+        let eth_price = self.eth_price();
+        if eth_price == 0.0 {
+            return Err(anyhow::anyhow!("ETH price is zero"));
+        }
+
+        Ok(self.get_price_usd(token).await? / eth_price)
+    }
+
+    async fn get_price_in_token(&self, base_token: Address, quote_token: Address) -> Result<f64> {
+        let base_price_usd = self.get_price_usd(base_token).await?;
+        let quote_price_usd = self.get_price_usd(quote_token).await?;
+        if quote_price_usd == 0.0 {
+            return Err(anyhow::anyhow!("Quote token price is zero"));
+        }
+
+        Ok(base_price_usd / quote_price_usd)
+    }
+
+    async fn pin_prices(&self, tokens: &[Address]) -> Result<PinnedPrices> {
+        let mut pinned = PinnedPrices::default();
+        for &token in tokens {
+            pinned.pin(
+                token,
+                self.get_price_usd(token).await?,
+                self.get_price_eth(token).await?,
+            );
+        }
+
+        Ok(pinned)
+    }
+
+    async fn update_prices(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn add_price_source(&self, _source: PriceSource) -> Result<()> {
+        Ok(())
+    }
+
+    async fn remove_price_source(&self, _source: PriceSource) -> Result<()> {
+        Ok(())
+    }
+}
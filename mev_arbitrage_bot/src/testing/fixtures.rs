@@ -0,0 +1,72 @@
+//! Testing Fixtures Module
+//!
+//! This module is responsible for the mainnet addresses and configuration integration tests
+//! start from. The configuration is the default one pointed at the fork: the harness wallet
+//! signs, transactions are sent directly instead of through MEV-Share, and nothing is stored
+//! or discovered from past logs, so every test starts from the forked state alone.
+
+use ethers::types::Address;
+
+use crate::config::{self, Config};
+
+/// Wrapped Ether
+pub const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+
+/// USD Coin (a proxy; balances live in the proxy's storage)
+pub const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+
+/// Dai
+pub const DAI: &str = "0x6B175474E89094C44Da98b954EedeAC495271d0F";
+
+/// Uniswap V2 WETH/USDC pair
+pub const UNISWAP_WETH_USDC: &str = "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc";
+
+/// Sushiswap WETH/USDC pair
+pub const SUSHISWAP_WETH_USDC: &str = "0x397FF1542f962076d0BFE58eA045FfA2d347ACa0";
+
+/// Uniswap V2 WETH/DAI pair
+pub const UNISWAP_WETH_DAI: &str = "0xA478c2975Ab1Ea89e8196811F51A7B7Ade33eB11";
+
+/// Sushiswap WETH/DAI pair
+pub const SUSHISWAP_WETH_DAI: &str = "0xC3D03e4F041Fd4cD388c549Ee2A29a9E5075882f";
+
+/// Parse a fixture address
+///
+/// Panics on a malformed address, which only a typo in a fixture produces.
+pub fn address(address: &str) -> Address {
+    address
+        .parse()
+        .unwrap_or_else(|e| panic!("Invalid fixture address {}: {}", address, e))
+}
+
+/// Build the configuration of a bot running against a fork
+///
+/// `private_key` is the hex key of the harness wallet, which is also the contract owner.
+pub fn fork_config(
+    rpc_url: String,
+    ws_url: String,
+    private_key: String,
+    wallet_address: Address,
+) -> Config {
+    let mut config = config::create_default_config();
+
+    config.ethereum.rpc_url = rpc_url;
+    config.ethereum.ws_url = Some(ws_url);
+    config.ethereum.use_websocket = Some(false);
+    config.ethereum.private_key = Some(private_key);
+    config.ethereum.wallet_address = format!("{:?}", wallet_address);
+
+    // Send directly to the fork, which has no relays or builders
+    config.mev_share.enabled = false;
+
+    // Start every test from the forked state alone
+    config.storage.enabled = false;
+    config.dex.pair_discovery.enabled = false;
+    config.wallet.reconcile_on_startup = false;
+
+    // Deploy a fresh executor contract owned by the harness wallet
+    config.arbitrage.contract.contract_address = None;
+    config.arbitrage.contract.deploy_if_missing = true;
+
+    config
+}
@@ -0,0 +1,466 @@
+//! Testing Module
+//!
+//! This module is responsible for running the bot's pipeline end to end against a local Anvil
+//! fork of mainnet, behind the `testing` feature. The harness spawns Anvil forking
+//! `FORK_RPC_URL`, deploys a fresh ArbitrageExecutor owned by Anvil's first account and wires
+//! the components the way the bot does. The contract is deployed from the compiled artifact in
+//! `src/contract/bytecode/ArbitrageExecutor.bin` and verified before any test runs, so the
+//! harness fails to start on a placeholder artifact. Tests then seed pools out of line with each other and
+//! run the scanner, strategy engine, builder and executor once, getting back what was found and
+//! how each execution ended on the fork.
+//!
+//! Integration tests for a new DEX adapter start from [`TestHarness::start`], move the price of
+//! a pool the adapter reads with [`TestHarness::seed_pair`] (or [`TestHarness::fund`] and a
+//! swap of their own), and assert on the [`PipelineRun`].
+
+pub mod fixtures;
+
+use anyhow::{Context, Result};
+use ethers::providers::Middleware;
+use ethers::types::{Address, TransactionRequest, H256, U256};
+use ethers::utils::{Anvil, AnvilInstance};
+use log::{info, warn};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::assets::ContractAbi;
+use crate::blockchain::{self, RpcClient};
+use crate::config::Config;
+use crate::contract::{self, ContractManager, ContractManagerImpl};
+use crate::dex::{self, DexInterfaces};
+use crate::flash_loan::{self, FlashLoanRouter};
+use crate::gas::{self, GasOptimizer};
+use crate::mev_share;
+use crate::price::{self, PriceOracle, PriceOracleInterface};
+use crate::scanner::{self, ArbitrageOpportunity, OpportunityScanner};
+use crate::simulation::{self, SlotFinder, TransactionSimulator};
+use crate::strategy::{self, StrategyEngine};
+use crate::tokens;
+use crate::transaction::{self, TransactionBuilder, TransactionExecutor, TransactionResult};
+
+/// Time Anvil is given to fetch the fork's head and start listening
+const ANVIL_STARTUP_TIMEOUT_MS: u64 = 60_000;
+
+/// Time an executed transaction is waited for (Anvil mines it immediately)
+const EXECUTION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Where and how to fork
+#[derive(Debug, Clone)]
+pub struct HarnessOptions {
+    /// Archive RPC endpoint of the chain forked
+    pub fork_url: String,
+
+    /// Block to fork at (the head when unset); pin it for reproducible tests
+    pub fork_block: Option<u64>,
+
+    /// Path of the `anvil` binary (`anvil` on the PATH when unset)
+    pub anvil_path: Option<PathBuf>,
+}
+
+impl HarnessOptions {
+    /// Read the options from `FORK_RPC_URL`, `FORK_BLOCK_NUMBER` and `ANVIL_PATH`
+    ///
+    /// Returns `None` without a fork URL, so tests can skip where no archive node is available.
+    pub fn from_env() -> Option<Self> {
+        let fork_url = std::env::var("FORK_RPC_URL")
+            .ok()
+            .filter(|url| !url.is_empty())?;
+        let fork_block = std::env::var("FORK_BLOCK_NUMBER")
+            .ok()
+            .and_then(|block| block.parse().ok());
+        let anvil_path = std::env::var("ANVIL_PATH").ok().map(PathBuf::from);
+
+        Some(Self {
+            fork_url,
+            fork_block,
+            anvil_path,
+        })
+    }
+}
+
+/// Outcome of one pass of the pipeline
+#[derive(Debug)]
+pub struct PipelineRun {
+    /// Opportunities the scanner found
+    pub opportunities: Vec<ArbitrageOpportunity>,
+
+    /// The ranked opportunities that were built and executed, in rank order
+    pub executions: Vec<Execution>,
+}
+
+impl PipelineRun {
+    /// Get the executions that were included and succeeded
+    pub fn successful(&self) -> impl Iterator<Item = &TransactionResult> {
+        self.executions
+            .iter()
+            .filter_map(|execution| execution.result.as_ref().ok())
+            .filter(|result| result.success)
+    }
+}
+
+/// Execution of one opportunity
+#[derive(Debug)]
+pub struct Execution {
+    /// ID of the opportunity executed
+    pub opportunity_id: String,
+
+    /// Status of the transaction, or why it could not be built or sent
+    pub result: Result<TransactionResult>,
+}
+
+/// The bot's components running against an Anvil fork
+///
+/// Anvil is stopped when the harness is dropped.
+pub struct TestHarness {
+    anvil: AnvilInstance,
+    config: Arc<Config>,
+    wallet_address: Address,
+    contract_address: Address,
+    blockchain_client: Arc<RpcClient>,
+    contract_manager: Arc<ContractManagerImpl>,
+    dex_interfaces: Arc<DexInterfaces>,
+    price_oracle: Arc<PriceOracle>,
+    flash_loans: Arc<FlashLoanRouter>,
+    gas_optimizer: Arc<dyn GasOptimizer>,
+    simulator: Arc<dyn TransactionSimulator>,
+    tx_builder: Arc<dyn TransactionBuilder>,
+    tx_executor: Arc<dyn TransactionExecutor>,
+    scanner: Arc<dyn OpportunityScanner>,
+    strategy_engine: Arc<dyn StrategyEngine>,
+    slot_finder: SlotFinder,
+}
+
+impl TestHarness {
+    /// Spawn Anvil forking the chain, deploy the executor contract and create the components
+    pub async fn start(options: HarnessOptions) -> Result<Self> {
+        Self::start_with(options, |_| {}).await
+    }
+
+    /// Start the harness with changes to the fork configuration, e.g. enabling a DEX
+    pub async fn start_with(
+        options: HarnessOptions,
+        configure: impl FnOnce(&mut Config),
+    ) -> Result<Self> {
+        let anvil = spawn_anvil(&options)?;
+
+        // Anvil's first account signs everything and owns the contract
+        let key = anvil.keys().first().context("Anvil has no accounts")?;
+        let wallet_address = *anvil.addresses().first().context("Anvil has no accounts")?;
+        let mut config = fixtures::fork_config(
+            anvil.endpoint(),
+            anvil.ws_endpoint(),
+            format!("0x{}", hex::encode(key.to_bytes())),
+            wallet_address,
+        );
+        configure(&mut config);
+        let config = Arc::new(config);
+        tokens::global().register_config_tokens(&config);
+
+        let blockchain_client = blockchain::create_client(&config).await?;
+        blockchain::verify_deployments(&config, &blockchain_client).await?;
+        let mev_share_client = mev_share::create_client(&config).await?;
+
+        let contract_manager = contract::create_manager(&config, blockchain_client.clone()).await?;
+        let contract_address = contract::resolve_contract(&config, &contract_manager, None)
+            .await
+            .context("Failed to deploy the ArbitrageExecutor contract on the fork")?
+            .context("No ArbitrageExecutor contract on the fork")?;
+
+        // A contract without the executor's code would make every execution revert for
+        // reasons unrelated to the test
+        contract_manager
+            .verify_contract()
+            .await
+            .context("The ArbitrageExecutor contract on the fork is not usable")?;
+
+        let dex_interfaces =
+            dex::create_interfaces(&config, blockchain_client.clone(), None).await?;
+        let price_oracle = price::create_oracle(&config, dex_interfaces.clone(), None).await?;
+        let flash_loans = flash_loan::create_manager(&config, blockchain_client.clone()).await?;
+        let gas_optimizer = gas::create_optimizer(&config, blockchain_client.clone()).await?;
+        let simulator =
+            simulation::create_simulator(&config, blockchain_client.clone(), price_oracle.clone())
+                .await?;
+
+        let tx_builder = transaction::create_builder(
+            &config,
+            blockchain_client.clone(),
+            Some(contract_manager.clone()),
+            flash_loans.clone(),
+            gas_optimizer.clone(),
+        )
+        .await?;
+        let tx_executor = transaction::create_executor(
+            &config,
+            blockchain_client.clone(),
+            mev_share_client,
+            gas_optimizer.clone(),
+            simulator.clone(),
+            None,
+            CancellationToken::new(),
+        )
+        .await?;
+
+        let pair_tiers = scanner::create_pair_tiers(&config, None)?;
+        let scanner = scanner::create_scanner(
+            &config,
+            blockchain_client.clone(),
+            dex_interfaces.clone(),
+            price_oracle.clone(),
+            gas_optimizer.clone(),
+            pair_tiers,
+            None,
+        )
+        .await?;
+        let strategy_engine = strategy::create_engine(
+            &config,
+            price_oracle.clone(),
+            dex_interfaces.clone(),
+            flash_loans.clone(),
+            gas_optimizer.clone(),
+        )
+        .await?;
+
+        info!(
+            "Test harness forked {} at {}, executor contract {:?}",
+            options.fork_url,
+            blockchain_client.get_block_number().await?,
+            contract_address
+        );
+
+        Ok(Self {
+            anvil,
+            config,
+            wallet_address,
+            contract_address,
+            slot_finder: SlotFinder::new(blockchain_client.clone()),
+            blockchain_client,
+            contract_manager,
+            dex_interfaces,
+            price_oracle,
+            flash_loans,
+            gas_optimizer,
+            simulator,
+            tx_builder,
+            tx_executor,
+            scanner,
+            strategy_engine,
+        })
+    }
+
+    /// Set an account's balance of a token on the fork
+    pub async fn fund(&self, token: Address, holder: Address, amount: U256) -> Result<()> {
+        let slot = self.slot_finder.balance_slot(token, holder).await?;
+        let mut value = [0u8; 32];
+        amount.to_big_endian(&mut value);
+
+        self.blockchain_client
+            .request::<_, bool>("anvil_setStorageAt", (token, slot, H256(value)))
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to set the {} balance of {:?}",
+                    tokens::token_label(token),
+                    holder
+                )
+            })?;
+
+        Ok(())
+    }
+
+    /// Add tokens to a Uniswap V2 style pair's reserves, moving its price
+    ///
+    /// The pair's balance is raised by `extra_amount` and `sync` is called, so the reserve of
+    /// `token` grows without any swap: the pair now buys `token`'s counterpart cheaper than the
+    /// pools it was in line with.
+    pub async fn seed_pair(&self, pair: Address, token: Address, extra_amount: U256) -> Result<()> {
+        let erc20 = ContractAbi::Erc20.contract();
+        let output = self
+            .blockchain_client
+            .call(
+                &TransactionRequest::new()
+                    .to(token)
+                    .data(erc20.encode("balanceOf", pair)?)
+                    .into(),
+                None,
+            )
+            .await
+            .context("Failed to read the pair's balance")?;
+        let balance: U256 = erc20.decode_output("balanceOf", output)?;
+
+        self.fund(token, pair, balance + extra_amount).await?;
+
+        let sync = TransactionRequest::new()
+            .to(pair)
+            .data(ContractAbi::UniswapV2Pair.contract().encode("sync", ())?);
+        let tx_hash = self.tx_executor.send_wallet_transaction(sync).await?;
+        let result = self
+            .tx_executor
+            .wait_for_transaction(tx_hash, EXECUTION_TIMEOUT)
+            .await?;
+        if !result.success {
+            return Err(anyhow::anyhow!("sync of {:?} reverted", pair));
+        }
+
+        Ok(())
+    }
+
+    /// Mine a block on the fork
+    pub async fn mine(&self) -> Result<()> {
+        self.blockchain_client
+            .request::<_, ()>("evm_mine", ())
+            .await
+            .context("Failed to mine a block")
+    }
+
+    /// Run the pipeline once: refresh prices, scan, rank, then build and execute every ranked
+    /// opportunity in order, waiting for each before the next
+    pub async fn run_pipeline(&self) -> Result<PipelineRun> {
+        if let Err(e) = self.price_oracle.update_prices().await {
+            warn!("Failed to update prices on the fork: {}", e);
+        }
+
+        let opportunities = self.scanner.scan().await?;
+        let ranked = self
+            .strategy_engine
+            .rank_opportunities(opportunities.clone())
+            .await;
+
+        let mut executions = Vec::new();
+        for opportunity in ranked {
+            let result = self.execute(&opportunity).await;
+            if let Err(e) = &result {
+                warn!("Execution of {} failed: {:#}", opportunity.id, e);
+            }
+            executions.push(Execution {
+                opportunity_id: opportunity.id,
+                result,
+            });
+        }
+
+        Ok(PipelineRun {
+            opportunities,
+            executions,
+        })
+    }
+
+    /// Build, send and wait for one opportunity's transaction
+    async fn execute(&self, opportunity: &ArbitrageOpportunity) -> Result<TransactionResult> {
+        let transaction = self
+            .tx_builder
+            .build_arbitrage_transaction(opportunity)
+            .await?;
+        let tx_hash = self.tx_executor.execute_transaction(transaction).await?;
+
+        self.tx_executor
+            .wait_for_transaction(tx_hash, EXECUTION_TIMEOUT)
+            .await
+    }
+
+    /// Get the running Anvil instance
+    pub fn anvil(&self) -> &AnvilInstance {
+        &self.anvil
+    }
+
+    /// Get the fork configuration
+    pub fn config(&self) -> &Arc<Config> {
+        &self.config
+    }
+
+    /// Get the address of the harness wallet, which owns the contract
+    pub fn wallet_address(&self) -> Address {
+        self.wallet_address
+    }
+
+    /// Get the address of the deployed executor contract
+    pub fn contract_address(&self) -> Address {
+        self.contract_address
+    }
+
+    /// Get the client of the fork
+    pub fn blockchain_client(&self) -> &Arc<RpcClient> {
+        &self.blockchain_client
+    }
+
+    /// Get the contract manager
+    pub fn contract_manager(&self) -> &Arc<ContractManagerImpl> {
+        &self.contract_manager
+    }
+
+    /// Get the DEX interfaces
+    pub fn dex_interfaces(&self) -> &Arc<DexInterfaces> {
+        &self.dex_interfaces
+    }
+
+    /// Get the price oracle
+    pub fn price_oracle(&self) -> &Arc<PriceOracle> {
+        &self.price_oracle
+    }
+
+    /// Get the flash loan providers
+    pub fn flash_loans(&self) -> &Arc<FlashLoanRouter> {
+        &self.flash_loans
+    }
+
+    /// Get the gas price optimizer
+    pub fn gas_optimizer(&self) -> &Arc<dyn GasOptimizer> {
+        &self.gas_optimizer
+    }
+
+    /// Get the transaction simulator
+    pub fn simulator(&self) -> &Arc<dyn TransactionSimulator> {
+        &self.simulator
+    }
+
+    /// Get the transaction builder
+    pub fn tx_builder(&self) -> &Arc<dyn TransactionBuilder> {
+        &self.tx_builder
+    }
+
+    /// Get the transaction executor
+    pub fn tx_executor(&self) -> &Arc<dyn TransactionExecutor> {
+        &self.tx_executor
+    }
+
+    /// Get the opportunity scanner
+    pub fn scanner(&self) -> &Arc<dyn OpportunityScanner> {
+        &self.scanner
+    }
+
+    /// Get the strategy engine
+    pub fn strategy_engine(&self) -> &Arc<dyn StrategyEngine> {
+        &self.strategy_engine
+    }
+}
+
+/// Spawn Anvil forking the chain
+///
+/// `Anvil::spawn` panics when the binary cannot be run, so it is checked first for an error
+/// that says what to install.
+fn spawn_anvil(options: &HarnessOptions) -> Result<AnvilInstance> {
+    let path = options
+        .anvil_path
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("anvil"));
+    Command::new(&path)
+        .arg("--version")
+        .output()
+        .with_context(|| {
+            format!(
+                "Cannot run {} (install Foundry, or set ANVIL_PATH)",
+                path.display()
+            )
+        })?;
+
+    let mut anvil = Anvil::at(path)
+        .fork(options.fork_url.clone())
+        .timeout(ANVIL_STARTUP_TIMEOUT_MS);
+    if let Some(block) = options.fork_block {
+        anvil = anvil.fork_block_number(block);
+    }
+
+    Ok(anvil.spawn())
+}
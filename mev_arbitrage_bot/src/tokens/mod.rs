@@ -0,0 +1,138 @@
+//! Token Registry Module
+//!
+//! This module is responsible for mapping token and pool addresses to human-readable names,
+//! so logs and records show `WETH -> USDC -> WETH` rather than raw addresses.
+
+use ethers::types::Address;
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+use crate::config::Config;
+use crate::utils::validate_and_parse_address;
+
+/// A known token
+#[derive(Debug, Clone)]
+pub struct TokenInfo {
+    /// Token symbol
+    pub symbol: String,
+
+    /// Token decimals
+    pub decimals: u8,
+}
+
+/// Registry of known tokens and pools
+#[derive(Debug, Default)]
+pub struct TokenRegistry {
+    tokens: RwLock<HashMap<Address, TokenInfo>>,
+    pools: RwLock<HashMap<Address, String>>,
+}
+
+impl TokenRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the tokens listed in the configuration
+    pub fn register_config_tokens(&self, config: &Config) {
+        for token in &config.flash_loan.tokens {
+            if let Ok(address) = validate_and_parse_address(&token.address) {
+                self.register_token(address, &token.symbol, token.decimals);
+            }
+        }
+    }
+
+    /// Register a token
+    pub fn register_token(&self, address: Address, symbol: &str, decimals: u8) {
+        let mut tokens = self.tokens.write().unwrap_or_else(|e| e.into_inner());
+        tokens.insert(
+            address,
+            TokenInfo {
+                symbol: symbol.to_string(),
+                decimals,
+            },
+        );
+    }
+
+    /// Register a pool of a DEX, naming it after its tokens
+    ///
+    /// Tokens are named in on-chain order (sorted by address, as V2 pairs store them), so
+    /// the same pool always gets the same name whichever way it was looked up.
+    pub fn register_pool(&self, address: Address, dex: &str, tokens: &[Address]) {
+        let mut sorted = tokens.to_vec();
+        sorted.sort();
+        let name = format!(
+            "{} {}",
+            dex,
+            sorted
+                .iter()
+                .map(|&token| self.token_label(token))
+                .collect::<Vec<_>>()
+                .join("/")
+        );
+
+        let mut pools = self.pools.write().unwrap_or_else(|e| e.into_inner());
+        pools.insert(address, name);
+    }
+
+    /// Get a registered token
+    pub fn token(&self, address: Address) -> Option<TokenInfo> {
+        let tokens = self.tokens.read().unwrap_or_else(|e| e.into_inner());
+        tokens.get(&address).cloned()
+    }
+
+    /// Get the symbol of a token, or its address if unknown
+    pub fn token_label(&self, address: Address) -> String {
+        match self.token(address) {
+            Some(token) => token.symbol,
+            None => format!("{:?}", address),
+        }
+    }
+
+    /// Get the name and address of a pool, or just its address if unknown
+    pub fn pool_label(&self, address: Address) -> String {
+        let pools = self.pools.read().unwrap_or_else(|e| e.into_inner());
+        match pools.get(&address) {
+            Some(name) => format!("{} ({:?})", name, address),
+            None => format!("{:?}", address),
+        }
+    }
+
+    /// Get a token path as `SYMBOL -> SYMBOL -> ...`
+    pub fn path_label(&self, path: &[Address]) -> String {
+        path.iter()
+            .map(|&token| self.token_label(token))
+            .collect::<Vec<_>>()
+            .join(" -> ")
+    }
+}
+
+/// Get the process-wide token registry
+pub fn global() -> &'static TokenRegistry {
+    static REGISTRY: OnceLock<TokenRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(TokenRegistry::new)
+}
+
+/// Get the symbol of a token from the global registry, or its address if unknown
+pub fn token_label(address: Address) -> String {
+    global().token_label(address)
+}
+
+/// Get the name of a pool from the global registry, or its address if unknown
+pub fn pool_label(address: Address) -> String {
+    global().pool_label(address)
+}
+
+/// Get a token path as symbols from the global registry
+pub fn path_label(path: &[Address]) -> String {
+    global().path_label(path)
+}
+
+/// Order two tokens the way V2 pairs store them (token0 is the lower address)
+pub fn sort_tokens(token_a: Address, token_b: Address) -> (Address, Address) {
+    if token_a < token_b {
+        (token_a, token_b)
+    } else {
+        (token_b, token_a)
+    }
+}
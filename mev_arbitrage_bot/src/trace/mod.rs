@@ -0,0 +1,250 @@
+//! Block Trace Module
+//!
+//! This module is responsible for recording, for chosen block numbers, every decision the bot
+//! makes while that block is the chain head (pools updated, candidates generated, scores,
+//! rejections and the final action), in order, as one structured trace that can be queried
+//! afterwards to answer "why didn't we take that arb in block X".
+
+use log::{info, warn};
+use serde::Serialize;
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use crate::config::Config;
+use crate::utils::{current_timestamp_millis, write_to_file};
+
+/// Number of completed traces kept in memory by default
+const DEFAULT_MAX_TRACES: usize = 16;
+
+/// Kind of decision recorded in a trace
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceStage {
+    /// Pool state updated from the block's events
+    PoolUpdate,
+
+    /// Opportunity candidate generated by the scanner
+    Candidate,
+
+    /// Candidate evaluated and scored by the strategy engine
+    Score,
+
+    /// Candidate rejected, with the reason
+    Rejection,
+
+    /// Final action taken (built, submitted, failed or nothing)
+    Action,
+}
+
+/// One recorded decision
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceEvent {
+    /// Position of the event in the trace
+    pub sequence: usize,
+
+    /// Milliseconds since the trace started
+    pub elapsed_ms: u64,
+
+    /// Kind of decision
+    pub stage: TraceStage,
+
+    /// What was decided
+    pub message: String,
+}
+
+/// Every decision made while one block was the chain head
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockTrace {
+    /// Traced block number
+    pub block_number: u64,
+
+    /// Unix timestamp (milliseconds) when the block started being processed
+    pub started_at_ms: u64,
+
+    /// Whether the next block has arrived and the trace is final
+    pub complete: bool,
+
+    /// Recorded decisions, in order
+    pub events: Vec<TraceEvent>,
+}
+
+impl BlockTrace {
+    /// Serialize the trace as pretty-printed JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// State of the tracer behind its lock
+#[derive(Debug, Default)]
+struct TracerState {
+    requested: BTreeSet<u64>,
+    current: Option<BlockTrace>,
+    completed: VecDeque<BlockTrace>,
+}
+
+/// Records block traces for requested block numbers
+#[derive(Debug)]
+pub struct BlockTracer {
+    state: Mutex<TracerState>,
+    active: AtomicBool,
+    max_traces: usize,
+    output_dir: Mutex<Option<String>>,
+}
+
+impl Default for BlockTracer {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(TracerState::default()),
+            active: AtomicBool::new(false),
+            max_traces: DEFAULT_MAX_TRACES,
+            output_dir: Mutex::new(None),
+        }
+    }
+}
+
+impl BlockTracer {
+    /// Create a tracer with no requested blocks
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request the blocks listed in the configuration and set where finished traces are written
+    pub fn configure(&self, config: &Config) {
+        for &block_number in &config.debug.trace_blocks {
+            self.request(block_number);
+        }
+        *self.output_dir.lock().unwrap_or_else(|e| e.into_inner()) = config.debug.trace_dir.clone();
+    }
+
+    /// Request a trace of a future block
+    pub fn request(&self, block_number: u64) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.requested.insert(block_number);
+        info!("Tracing requested for block {}", block_number);
+    }
+
+    /// Mark a new block as the chain head, finishing the previous trace
+    ///
+    /// The finished trace is written out after the lock is released, so recording is never
+    /// held up by the file system.
+    pub fn begin_block(&self, block_number: u64) {
+        let finished = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+            let finished = state.current.take().map(|mut finished| {
+                finished.complete = true;
+                state.completed.push_back(finished.clone());
+                while state.completed.len() > self.max_traces {
+                    state.completed.pop_front();
+                }
+                finished
+            });
+
+            if state.requested.remove(&block_number) {
+                state.current = Some(BlockTrace {
+                    block_number,
+                    started_at_ms: current_timestamp_millis() as u64,
+                    complete: false,
+                    events: Vec::new(),
+                });
+                info!("Tracing block {}", block_number);
+            }
+
+            // Requests for blocks already passed can never be served
+            state
+                .requested
+                .retain(|&requested| requested > block_number);
+            self.active
+                .store(state.current.is_some(), Ordering::Relaxed);
+
+            finished
+        };
+
+        if let Some(finished) = finished {
+            info!(
+                "Trace of block {} complete ({} events)",
+                finished.block_number,
+                finished.events.len()
+            );
+            self.write_trace(&finished);
+        }
+    }
+
+    /// Whether the current block is being traced
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    /// Record a decision if the current block is being traced
+    ///
+    /// The message is only built when tracing is active, so this is free on untraced blocks.
+    pub fn record(&self, stage: TraceStage, message: impl FnOnce() -> String) {
+        if !self.is_active() {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(trace) = state.current.as_mut() {
+            let elapsed_ms =
+                (current_timestamp_millis() as u64).saturating_sub(trace.started_at_ms);
+            trace.events.push(TraceEvent {
+                sequence: trace.events.len(),
+                elapsed_ms,
+                stage,
+                message: message(),
+            });
+        }
+    }
+
+    /// Get the trace of a block, whether still in progress or complete
+    pub fn trace(&self, block_number: u64) -> Option<BlockTrace> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state
+            .current
+            .iter()
+            .chain(state.completed.iter())
+            .find(|trace| trace.block_number == block_number)
+            .cloned()
+    }
+
+    /// Get the block numbers with a trace available
+    pub fn traced_blocks(&self) -> Vec<u64> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state
+            .completed
+            .iter()
+            .chain(state.current.iter())
+            .map(|trace| trace.block_number)
+            .collect()
+    }
+
+    /// Write a finished trace to the output directory, if one is configured
+    fn write_trace(&self, trace: &BlockTrace) {
+        let output_dir = self.output_dir.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(output_dir) = output_dir.as_deref() else {
+            return;
+        };
+
+        let path = format!("{}/block-{}.json", output_dir, trace.block_number);
+        match write_to_file(&path, &trace.to_json()) {
+            Ok(()) => info!("Trace of block {} written to {}", trace.block_number, path),
+            Err(e) => warn!(
+                "Failed to write trace of block {}: {}",
+                trace.block_number, e
+            ),
+        }
+    }
+}
+
+/// Get the process-wide block tracer
+pub fn global() -> &'static BlockTracer {
+    static TRACER: OnceLock<BlockTracer> = OnceLock::new();
+    TRACER.get_or_init(BlockTracer::new)
+}
+
+/// Record a decision in the current block's trace, if it is being traced
+pub fn record(stage: TraceStage, message: impl FnOnce() -> String) {
+    global().record(stage, message);
+}
@@ -0,0 +1,130 @@
+//! Profit Attribution Module
+//!
+//! This module is responsible for splitting the outcome of a settled trade between where it
+//! came from: the spread the route captured, ETH refunded to the wallet by MEV-Share backrunners
+//! and builders, the base fee burned for its gas and the priority fee tipped to the builder.
+//! Comparing the parts shows whether the edge comes from detection or from relay kickbacks.
+
+use anyhow::{Context, Result};
+use ethers::middleware::Middleware;
+use ethers::types::U256;
+use std::sync::Arc;
+
+use crate::blockchain::RpcClient;
+use crate::transaction::TransactionResult;
+use crate::utils::u256_to_decimal;
+
+/// On-chain costs and refunds of an included transaction (in wei)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SettlementCosts {
+    /// Gas paid at the block's base fee
+    pub base_cost: U256,
+
+    /// Gas paid above the base fee, tipped to the builder
+    pub tip_cost: U256,
+
+    /// ETH paid to the wallet in the inclusion block, beyond what its own transactions spent
+    pub refund: U256,
+}
+
+/// Outcome of a settled trade split by source (in USD)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProfitAttribution {
+    /// Profit captured from the price difference along the route
+    pub spread_capture_usd: f64,
+
+    /// Refunds paid to the wallet for the trade
+    pub mev_refund_usd: f64,
+
+    /// Gas paid at the base fee
+    pub gas_cost_usd: f64,
+
+    /// Priority fee paid to the builder
+    pub tip_cost_usd: f64,
+}
+
+impl ProfitAttribution {
+    /// Price the settlement costs of a trade that captured `spread_capture_usd`
+    pub fn new(spread_capture_usd: f64, costs: &SettlementCosts, eth_price_usd: f64) -> Self {
+        let usd = |wei: U256| u256_to_decimal(wei, 18) * eth_price_usd;
+        Self {
+            spread_capture_usd,
+            mev_refund_usd: usd(costs.refund),
+            gas_cost_usd: usd(costs.base_cost),
+            tip_cost_usd: usd(costs.tip_cost),
+        }
+    }
+
+    /// Get the realized PnL: spread and refunds, less gas and tip
+    pub fn realized_pnl(&self) -> f64 {
+        self.spread_capture_usd + self.mev_refund_usd - self.gas_cost_usd - self.tip_cost_usd
+    }
+}
+
+/// Read the gas split and refund of an included transaction from its block
+///
+/// The refund is the wallet's balance change over the inclusion block plus everything its
+/// transactions in that block spent, shared evenly between them. MEV-Share pays backrun refunds
+/// in the block of the transaction they are for, so later builder refunds are not counted.
+pub async fn settlement_costs(
+    blockchain_client: &Arc<RpcClient>,
+    result: &TransactionResult,
+) -> Result<SettlementCosts> {
+    let block_number = result.block_number.context("Transaction is not included")?;
+    let actual_cost = result.actual_cost.unwrap_or_default();
+    let wallet = blockchain_client
+        .get_transaction(result.tx_hash)
+        .await?
+        .context("Transaction not found")?
+        .from;
+    let block = blockchain_client
+        .get_block_with_txs(block_number)
+        .await?
+        .with_context(|| format!("Block {} not found", block_number))?;
+
+    // Without a base fee (pre-London chains) the whole gas price goes to the block producer
+    let base_cost = match block.base_fee_per_gas {
+        Some(base_fee) => result
+            .gas_used
+            .unwrap_or_default()
+            .saturating_mul(base_fee)
+            .min(actual_cost),
+        None => U256::zero(),
+    };
+    let tip_cost = actual_cost - base_cost;
+
+    let mut spent = U256::zero();
+    let mut wallet_txs = 0u64;
+    for tx in block.transactions.iter().filter(|tx| tx.from == wallet) {
+        wallet_txs += 1;
+        let cost = if tx.hash == result.tx_hash {
+            actual_cost
+        } else {
+            let receipt = blockchain_client
+                .get_transaction_receipt(tx.hash)
+                .await?
+                .with_context(|| format!("Receipt of {:?} not found", tx.hash))?;
+            receipt
+                .gas_used
+                .unwrap_or_default()
+                .saturating_mul(receipt.effective_gas_price.unwrap_or_default())
+        };
+        spent = spent.saturating_add(cost).saturating_add(tx.value);
+    }
+
+    let balance_before = blockchain_client
+        .get_balance(wallet, Some((block_number - 1).into()))
+        .await?;
+    let balance_after = blockchain_client
+        .get_balance(wallet, Some(block_number.into()))
+        .await?;
+    let received = balance_after
+        .saturating_add(spent)
+        .saturating_sub(balance_before);
+
+    Ok(SettlementCosts {
+        base_cost,
+        tip_cost,
+        refund: received / U256::from(wallet_txs.max(1)),
+    })
+}
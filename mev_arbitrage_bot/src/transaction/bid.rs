@@ -0,0 +1,94 @@
+//! Bid Strategy Module
+//!
+//! This module is responsible for bidding the validator tips of bundle transactions. Instead of
+//! paying the market priority fee, a transaction bids a share of the profit it is expected to
+//! keep after the base fee, spread over its gas. A bundle that misses its blocks is re-signed
+//! with the tips raised by the escalation factor for each rebid, and at least to the tip that
+//! would have won the missed blocks. No tip exceeds
+//! `max_validator_tip`, and a bid never drops below the priority fee the transaction was
+//! priced at.
+
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::U256;
+
+use crate::config::Config;
+
+/// Wei per gwei
+const GWEI: u64 = 1_000_000_000;
+
+/// Bids the priority fees of bundle transactions from their expected profit
+#[derive(Debug, Clone, Copy)]
+pub struct BidStrategy {
+    profit_share: f64,
+    escalation_factor: f64,
+
+    /// Highest priority fee bid (in wei per gas)
+    max_tip: U256,
+
+    /// Highest max fee bid (in wei per gas)
+    max_gas_price: U256,
+}
+
+impl BidStrategy {
+    /// Create the configured bid strategy, or `None` when bidding is disabled
+    pub fn new(config: &Config) -> Option<Self> {
+        let bid = &config.mev_share.bid;
+        bid.enabled.then(|| Self {
+            profit_share: bid.profit_share,
+            escalation_factor: bid.escalation_factor,
+            max_tip: U256::from(config.mev_share.max_validator_tip)
+                .saturating_mul(U256::from(GWEI)),
+            max_gas_price: U256::from(config.gas.max_gas_price).saturating_mul(U256::from(GWEI)),
+        })
+    }
+
+    /// Get the priority fee of a transaction's first submission: its share of `expected_profit`
+    /// (in wei) over `gas`, but at least `floor`, capped at the max tip
+    pub fn priority_fee(&self, floor: U256, expected_profit: U256, gas: U256) -> U256 {
+        let share = scale(expected_profit, self.profit_share)
+            .checked_div(gas)
+            .unwrap_or_default();
+
+        share.max(floor).min(self.max_tip)
+    }
+
+    /// Reprice an EIP-1559 transaction, as first submitted, for rebid number `rebid`
+    ///
+    /// The tip grows by the escalation factor per rebid, to at least `floor` (the tip that would
+    /// have won the missed blocks), up to the max tip; the max fee moves with it up to the max
+    /// gas price. Returns `None` for other transaction types.
+    pub fn escalate(
+        &self,
+        tx: &TypedTransaction,
+        rebid: u32,
+        floor: U256,
+    ) -> Option<TypedTransaction> {
+        let TypedTransaction::Eip1559(request) = tx else {
+            return None;
+        };
+        let priority_fee = request.max_priority_fee_per_gas.unwrap_or_default();
+        let max_fee = request.max_fee_per_gas.unwrap_or_default();
+
+        let escalated = scale(priority_fee, self.escalation_factor.powi(rebid as i32))
+            .max(floor)
+            .min(self.max_tip)
+            .max(priority_fee);
+        let max_fee = max_fee
+            .saturating_sub(priority_fee)
+            .saturating_add(escalated)
+            .min(self.max_gas_price);
+
+        let mut request = request.clone();
+        request.max_fee_per_gas = Some(max_fee);
+        request.max_priority_fee_per_gas = Some(escalated.min(max_fee));
+        Some(TypedTransaction::Eip1559(request))
+    }
+}
+
+/// Multiply an amount by a non-negative factor, to a precision of 1e-6
+fn scale(amount: U256, factor: f64) -> U256 {
+    amount
+        .saturating_mul(U256::from((factor.max(0.0) * 1_000_000.0) as u64))
+        .checked_div(U256::from(1_000_000))
+        .unwrap_or_default()
+}
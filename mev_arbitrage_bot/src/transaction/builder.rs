@@ -1,20 +1,31 @@
 //! Transaction Builder Module
 //!
-//! This module is responsible for constructing transaction payloads.
+//! This module is responsible for constructing transaction payloads. Gas limits come from
+//! `eth_estimateGas` padded by a safety factor, and from per-route heuristics when the node
+//! cannot be reached.
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use ethers::abi::{AbiEncode, Token};
-use ethers::providers::Provider;
+use ethers::providers::{Middleware, ProviderError, RpcError};
+use ethers::types::transaction::eip2718::TypedTransaction;
 use ethers::types::{Address, Bytes, TransactionRequest, U256};
 use log::{debug, info, warn};
 use std::sync::Arc;
 
-use crate::config::Config;
-use crate::contract::ContractManager;
+use crate::blockchain::RpcClient;
+use crate::config::{Config, FlashLoanProvider};
+use crate::contract::{ArbitrageRoute, ContractManager, ExecutionLimits};
+use crate::dex::DexType;
+use crate::flash_loan::FlashLoanRouter;
+use crate::gas::{route_gas, GasOptimizer};
 use crate::scanner::ArbitrageOpportunity;
-use crate::transaction::ArbitrageTransaction;
-use crate::utils::validate_and_parse_address;
+use crate::tokens;
+use crate::transaction::{ArbitrageTransaction, PrivateTxConfig, PrivateTxOptions};
+use crate::utils::{current_timestamp, decimal_to_u256, validate_and_parse_address};
+
+/// Default safety factor applied to `eth_estimateGas` results
+pub const DEFAULT_GAS_ESTIMATE_MULTIPLIER: f64 = 1.2;
 
 /// Interface for transaction builders
 #[async_trait]
@@ -25,31 +36,195 @@ pub trait TransactionBuilder: Send + Sync {
         opportunity: &ArbitrageOpportunity,
     ) -> Result<ArbitrageTransaction>;
 
-    /// Estimate the gas cost for a transaction
+    /// Estimate the gas limit for a transaction sent from the wallet, padded by the safety factor
+    /// and capped at the configured gas limit
     async fn estimate_gas(&self, tx: &TransactionRequest) -> Result<U256>;
 
-    /// Build the calldata for a transaction
+    /// Build the calldata for a transaction executing within `limits`
     fn build_calldata(
         &self,
-        token_path: &[Address],
+        route: &ArbitrageRoute,
         amounts: &[U256],
-        dex_path: &[String],
+        limits: ExecutionLimits,
     ) -> Result<Bytes>;
 }
 
 /// Implementation of the transaction builder
 pub struct TransactionBuilderImpl {
     config: Arc<Config>,
-    blockchain_client: Arc<Provider<ethers::providers::Http>>,
+    blockchain_client: Arc<RpcClient>,
     wallet_address: Address,
     contract_manager: Option<Arc<dyn ContractManager>>,
+    flash_loans: Arc<FlashLoanRouter>,
+    gas_optimizer: Arc<dyn GasOptimizer>,
+    private_tx: Option<PrivateTxOptions>,
+}
+
+impl TransactionBuilderImpl {
+    /// Get the bounds the executor contract holds an opportunity's route to
+    ///
+    /// The transaction targets the next block and is followed for `transaction_timeout`
+    /// seconds, so it may not execute past that.
+    fn execution_limits(&self, opportunity: &ArbitrageOpportunity) -> ExecutionLimits {
+        ExecutionLimits {
+            // 0.5% = 50 basis points
+            slippage: U256::from((self.config.arbitrage.slippage_tolerance * 100.0) as u64),
+            min_profit: self.min_profit(opportunity),
+            deadline: U256::from(current_timestamp() + self.config.security.transaction_timeout),
+        }
+    }
+
+    /// Get the least profit an opportunity may realize on chain, in its first token
+    ///
+    /// This is the expected profit net of the flash loan fee, less the configured tolerance.
+    /// Without a pinned price of the token the guard is off (zero).
+    fn min_profit(&self, opportunity: &ArbitrageOpportunity) -> U256 {
+        let token = opportunity.token_path[0];
+        let Some(price_usd) = opportunity
+            .pinned_prices
+            .usd(token)
+            .filter(|&price| price > 0.0)
+        else {
+            debug!(
+                "No USD price of {} pinned for opportunity {}, sending without a minimum profit",
+                tokens::token_label(token),
+                opportunity.id
+            );
+            return U256::zero();
+        };
+
+        let expected_usd = opportunity.estimated_profit - opportunity.flash_loan_fee;
+        let min_profit_usd = expected_usd * (1.0 - self.config.arbitrage.profit_tolerance / 100.0);
+        if min_profit_usd <= 0.0 {
+            return U256::zero();
+        }
+
+        let decimals = tokens::global()
+            .token(token)
+            .map_or(18, |info| info.decimals);
+        decimal_to_u256(min_profit_usd / price_usd, decimals)
+    }
+
+    /// Build an arbitrage funded by a flash loan the executor contract takes from a provider
+    /// other than Aave
+    ///
+    /// The contract borrows from the provider's lender for the loan, and repays it from the
+    /// lender's callback.
+    async fn build_contract_flash_loan(
+        &self,
+        provider: FlashLoanProvider,
+        route: &ArbitrageRoute,
+        amount: U256,
+        limits: ExecutionLimits,
+    ) -> Result<TransactionRequest> {
+        let contract_manager = self
+            .contract_manager
+            .as_ref()
+            .context("Contract manager required for flash loans")?;
+        let lender = self
+            .flash_loans
+            .flash_lender(provider, route.initial_token(), amount)
+            .await?;
+
+        let request = contract_manager
+            .execute_flash_loan(provider, lender, amount, route, limits)
+            .await?;
+
+        Ok(request
+            .from(self.wallet_address)
+            .gas(U256::from(self.config.gas.gas_limit)))
+    }
+
+    /// Build a two-leg arbitrage funded by a flash swap from the route's first pair
+    ///
+    /// The executor contract starts the swap itself, borrowing what `amount_in` buys from the
+    /// pair; its `uniswapV2Call` runs the route from the second swap and repays the pair
+    /// `amount_in` of the route's first token.
+    async fn build_flash_swap(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        route: &ArbitrageRoute,
+        amount_in: U256,
+        limits: ExecutionLimits,
+    ) -> Result<TransactionRequest> {
+        let flash_swap = self
+            .flash_loans
+            .flash_swap(&opportunity.token_path, &opportunity.hops)
+            .with_context(|| {
+                format!(
+                    "Opportunity {} cannot be funded by a flash swap",
+                    opportunity.id
+                )
+            })?;
+        let contract_manager = self
+            .contract_manager
+            .as_ref()
+            .context("Contract manager required for flash swaps")?;
+
+        let request = contract_manager
+            .execute_flash_swap(flash_swap.pair, amount_in, route, limits)
+            .await?;
+
+        Ok(request
+            .from(self.wallet_address)
+            .gas(U256::from(self.config.gas.gas_limit)))
+    }
+
+    /// Ask the node how much gas a transaction sent from the wallet uses
+    ///
+    /// The configured gas limit is dropped from the request so that it doesn't cap the estimate.
+    async fn rpc_estimate_gas(&self, tx: &TransactionRequest) -> Result<U256, ProviderError> {
+        let mut request = tx.clone().from(self.wallet_address);
+        request.gas = None;
+        let typed_tx: TypedTransaction = request.into();
+
+        self.blockchain_client.estimate_gas(&typed_tx, None).await
+    }
+
+    /// Pad a gas estimate by the safety factor, capped at the configured gas limit
+    fn pad_gas_estimate(&self, gas: U256) -> Result<U256> {
+        let gas_limit = U256::from(self.config.gas.gas_limit);
+        if gas > gas_limit {
+            anyhow::bail!(
+                "Transaction needs {} gas, more than the gas limit of {}",
+                gas,
+                gas_limit
+            );
+        }
+
+        let multiplier = self
+            .config
+            .gas
+            .gas_estimate_multiplier
+            .unwrap_or(DEFAULT_GAS_ESTIMATE_MULTIPLIER);
+        let padded = U256::from((gas.as_u64() as f64 * multiplier) as u64);
+
+        Ok(padded.clamp(gas, gas_limit))
+    }
+
+    /// Estimate the gas limit of a route from its flash loan and swaps, for when the node can't
+    /// estimate it
+    fn heuristic_gas(&self, provider: FlashLoanProvider, hops: &[(DexType, Address)]) -> U256 {
+        let gas = route_gas(provider, hops.iter().map(|&(dex_type, _)| dex_type));
+        U256::from(gas).min(U256::from(self.config.gas.gas_limit))
+    }
+}
+
+/// Whether an estimation error is the node reporting that the transaction reverts, rather than
+/// a failure to get an estimate
+fn is_revert(error: &ProviderError) -> bool {
+    error
+        .as_error_response()
+        .is_some_and(|response| response.message.contains("revert"))
 }
 
 /// Create a new transaction builder
 pub async fn create_builder(
     config: &Arc<Config>,
-    blockchain_client: Arc<Provider<ethers::providers::Http>>,
+    blockchain_client: Arc<RpcClient>,
     contract_manager: Option<Arc<dyn ContractManager>>,
+    flash_loans: Arc<FlashLoanRouter>,
+    gas_optimizer: Arc<dyn GasOptimizer>,
 ) -> Result<Arc<dyn TransactionBuilder>> {
     // Parse the wallet address
     let wallet_address = match validate_and_parse_address(&config.ethereum.wallet_address) {
@@ -61,11 +236,16 @@ pub async fn create_builder(
         }
     };
 
+    let private_tx = config.section::<PrivateTxConfig>()?.options();
+
     let builder = TransactionBuilderImpl {
         config: config.clone(),
         blockchain_client,
         wallet_address,
         contract_manager,
+        flash_loans,
+        gas_optimizer,
+        private_tx,
     };
 
     Ok(Arc::new(builder))
@@ -77,48 +257,56 @@ impl TransactionBuilder for TransactionBuilderImpl {
         &self,
         opportunity: &ArbitrageOpportunity,
     ) -> Result<ArbitrageTransaction> {
-        // Determine the optimal token path
+        // Determine the route, one leg per swap
         let token_path = opportunity.token_path.clone();
-
-        // Determine the DEX path
-        let dex_path = vec![
-            opportunity.source_dex.clone(),
-            opportunity.target_dex.clone(),
-        ];
-
-        // Calculate the optimal amounts based on the opportunity
-        let flash_loan_amount =
-            U256::from((opportunity.estimated_profit * 2.0) as u128 * 10u128.pow(18));
+        let hops = opportunity.hops.clone();
+        let route =
+            ArbitrageRoute::new(&token_path, &hops, self.config.arbitrage.max_hops as usize)
+                .with_context(|| format!("Opportunity {} has an invalid route", opportunity.id))?;
+        debug!(
+            "Building {}-leg route for opportunity {}",
+            route.legs().len(),
+            opportunity.id
+        );
+
+        // Calculate the optimal amounts based on the opportunity and the variant's sizing
+        let flash_loan_amount = opportunity.flash_loan_amount();
         let amounts = vec![flash_loan_amount];
 
         // Create the modes for the flash loan (0 = no debt)
         let modes = vec![U256::from(0)];
 
-        // Calculate the slippage tolerance in basis points (0.5% = 50 basis points)
-        let slippage = U256::from((self.config.arbitrage.slippage_tolerance * 100.0) as u64);
+        // Revert on chain if the pools move against the route, or it lands too late
+        let limits = self.execution_limits(opportunity);
 
         // Build the calldata for the transaction
-        let calldata = self.build_calldata(&token_path, &amounts, &dex_path)?;
+        let calldata = self.build_calldata(&route, &amounts, limits)?;
 
         // Create the transaction request
-        let request = if let Some(contract_manager) = &self.contract_manager {
+        let request = if opportunity.flash_loan_provider == FlashLoanProvider::UniswapV2 {
+            // The route's first pair lends its output and is repaid from the second swap
+            self.build_flash_swap(opportunity, &route, flash_loan_amount, limits)
+                .await?
+        } else if opportunity.flash_loan_provider != FlashLoanProvider::Aave {
+            // The executor contract takes the loan itself and is called back by the lender
+            self.build_contract_flash_loan(
+                opportunity.flash_loan_provider,
+                &route,
+                flash_loan_amount,
+                limits,
+            )
+            .await?
+        } else if let Some(contract_manager) = &self.contract_manager {
             // Get the contract address
             if let Some(_contract_address) = contract_manager.get_contract_address() {
                 // Build the transaction using the contract manager
-                // Clone all vectors to avoid ownership issues
-                let token_path_first = vec![token_path[0]];
-                let amounts_clone = amounts.clone();
-                let token_path_clone = token_path.clone();
-                let dex_path_clone = dex_path.clone();
-
                 contract_manager
                     .execute_arbitrage(
-                        token_path_first, // Use the first token in the path as the flash loan asset
-                        amounts_clone,
+                        vec![route.initial_token()], // The first token in the path is the flash loan asset
+                        amounts.clone(),
                         modes,
-                        token_path_clone,
-                        dex_path_clone,
-                        slippage,
+                        &route,
+                        limits,
                     )
                     .await?
             } else {
@@ -144,41 +332,82 @@ impl TransactionBuilder for TransactionBuilderImpl {
                 .gas(U256::from(self.config.gas.gas_limit))
         };
 
-        // Estimate the gas cost
-        let estimated_gas = self.estimate_gas(&request).await?;
+        // Estimate the gas limit; a reverting transaction is dropped, and heuristics only stand
+        // in for an estimate the node failed to give
+        let estimated_gas = match self.rpc_estimate_gas(&request).await {
+            Ok(gas) => self.pad_gas_estimate(gas)?,
+            Err(e) if is_revert(&e) => {
+                anyhow::bail!(
+                    "Opportunity {} reverts in gas estimation: {}",
+                    opportunity.id,
+                    e
+                );
+            }
+            Err(e) => {
+                let gas = self.heuristic_gas(opportunity.flash_loan_provider, &hops);
+                warn!(
+                    "Failed to estimate gas for opportunity {}, using {} from the route: {}",
+                    opportunity.id, gas, e
+                );
+                gas
+            }
+        };
+        let request = request.gas(estimated_gas);
 
         // Estimate the gas price
         let estimated_gas_price = U256::from(self.config.gas.max_gas_price * 1_000_000_000); // Convert gwei to wei
 
+        // Quote the L1 data fee on rollups; a failed quote only underestimates the cost
+        let l1_data_fee = match self.gas_optimizer.get_l1_data_fee(&request).await {
+            Ok(l1_data_fee) => l1_data_fee,
+            Err(e) => {
+                warn!("Failed to quote the L1 data fee: {}", e);
+                U256::zero()
+            }
+        };
+
         // Estimate the total cost
-        let estimated_cost = estimated_gas.saturating_mul(estimated_gas_price);
+        let estimated_cost = estimated_gas
+            .saturating_mul(estimated_gas_price)
+            .saturating_add(l1_data_fee);
 
         Ok(ArbitrageTransaction {
+            opportunity_id: opportunity.id.clone(),
+            // The route is encoded for whichever entry point funds it
+            calldata: request.data.clone().unwrap_or_default(),
             request,
             estimated_gas,
             estimated_gas_price,
+            l1_data_fee,
             estimated_cost,
             estimated_profit: opportunity.estimated_profit,
+            loan_amount: flash_loan_amount,
             token_path,
-            dex_path,
-            calldata,
+            hops,
             use_mev_share: self.config.mev_share.enabled,
+            private: self.private_tx,
+            may_revert: false,
+            variant: opportunity.variant.clone(),
+            pinned_prices: opportunity.pinned_prices.clone(),
+            span: opportunity.span.clone(),
+            latency: opportunity.latency,
         })
     }
 
-    async fn estimate_gas(&self, _tx: &TransactionRequest) -> Result<U256> {
-        // This is a placeholder implementation
-        // In a real implementation, we would call the eth_estimateGas RPC method
+    async fn estimate_gas(&self, tx: &TransactionRequest) -> Result<U256> {
+        let gas = self
+            .rpc_estimate_gas(tx)
+            .await
+            .context("Failed to estimate gas")?;
 
-        // For now, just return the gas limit from the config
-        Ok(U256::from(self.config.gas.gas_limit))
+        self.pad_gas_estimate(gas)
     }
 
     fn build_calldata(
         &self,
-        token_path: &[Address],
+        route: &ArbitrageRoute,
         amounts: &[U256],
-        dex_path: &[String],
+        limits: ExecutionLimits,
     ) -> Result<Bytes> {
         if let Some(contract_manager) = &self.contract_manager {
             // Get the contract ABI
@@ -192,33 +421,19 @@ impl TransactionBuilder for TransactionBuilderImpl {
             // Create the modes for the flash loan (0 = no debt)
             let modes = vec![U256::from(0); amounts.len()];
 
-            // Calculate the slippage tolerance in basis points (0.5% = 50 basis points)
-            let slippage = U256::from((self.config.arbitrage.slippage_tolerance * 100.0) as u64);
-
             // Encode the function call
+            let [slippage, min_profit, deadline] = limits.tokens();
             let data = function
                 .encode_input(&[
-                    Token::Array(
-                        token_path
-                            .iter()
-                            .map(|&addr| Token::Address(addr))
-                            .collect(),
-                    ),
+                    Token::Array(vec![Token::Address(route.initial_token())]),
                     Token::Array(amounts.iter().map(|&amount| Token::Uint(amount)).collect()),
                     Token::Array(modes.iter().map(|&mode| Token::Uint(mode)).collect()),
-                    Token::Array(
-                        token_path
-                            .iter()
-                            .map(|&addr| Token::Address(addr))
-                            .collect(),
-                    ),
-                    Token::Array(
-                        dex_path
-                            .iter()
-                            .map(|dex| Token::String(dex.clone()))
-                            .collect(),
-                    ),
-                    Token::Uint(slippage),
+                    route.token_path_token(),
+                    route.dex_path_token(),
+                    route.pool_path_token(),
+                    slippage,
+                    min_profit,
+                    deadline,
                 ])
                 .context("Failed to encode executeArbitrage function call")?;
 
@@ -229,19 +444,9 @@ impl TransactionBuilder for TransactionBuilderImpl {
 
             // Create a placeholder calldata
             let tokens = vec![
-                Token::Array(
-                    token_path
-                        .iter()
-                        .map(|&addr| Token::Address(addr))
-                        .collect(),
-                ),
+                route.token_path_token(),
                 Token::Array(amounts.iter().map(|&amount| Token::Uint(amount)).collect()),
-                Token::Array(
-                    dex_path
-                        .iter()
-                        .map(|dex| Token::String(dex.clone()))
-                        .collect(),
-                ),
+                route.dex_path_token(),
             ];
 
             // Encode the function selector and parameters
@@ -0,0 +1,124 @@
+//! Bundle Composition Module
+//!
+//! This module is responsible for merging independent arbitrage transactions that target the
+//! same block into a single bundle, so relay overhead is paid once per block. The most
+//! profitable transaction is the core of the bundle; the others can be marked as allowed to
+//! revert, so an ancillary leg whose opportunity is gone doesn't keep the core from landing.
+//! Transactions conflict when they trade through the same pool, since the later one would
+//! execute against reserves the earlier one moved.
+
+use ethers::types::Address;
+use log::debug;
+use std::collections::HashSet;
+
+use crate::dex::{pool_key, PoolKey};
+use crate::transaction::ArbitrageTransaction;
+
+/// Default maximum number of transactions merged into one bundle
+pub const DEFAULT_MAX_BUNDLE_SIZE: usize = 4;
+
+/// A pool a transaction trades through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum TradedPool {
+    /// The pool's address
+    Address(Address),
+
+    /// The DEX and token pair of a hop that does not name its pool
+    Pair(PoolKey),
+}
+
+/// Selects non-conflicting arbitrage transactions for a merged bundle
+#[derive(Debug, Clone)]
+pub struct BundleComposer {
+    max_bundle_size: usize,
+    revert_tolerant_legs: bool,
+}
+
+impl BundleComposer {
+    /// Create a new bundle composer
+    ///
+    /// With `revert_tolerant_legs`, every transaction after the first is allowed to revert.
+    pub fn new(max_bundle_size: usize, revert_tolerant_legs: bool) -> Self {
+        Self {
+            max_bundle_size: max_bundle_size.max(1),
+            revert_tolerant_legs,
+        }
+    }
+
+    /// Select the transactions to merge into a bundle, in execution order
+    ///
+    /// Transactions are ranked by profit density (estimated profit per unit of gas) and added
+    /// greedily as long as they don't trade through a pool already used by the bundle. Routes
+    /// through different pools of the same token pair do not conflict.
+    /// Transactions already marked as allowed to revert are ranked after the others, so the
+    /// core of the bundle is always a leg that must succeed.
+    pub fn compose(&self, transactions: Vec<ArbitrageTransaction>) -> Vec<ArbitrageTransaction> {
+        let mut ranked = transactions;
+        ranked.sort_by(|a, b| {
+            a.may_revert.cmp(&b.may_revert).then_with(|| {
+                profit_density(b)
+                    .partial_cmp(&profit_density(a))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+        });
+
+        let mut used_pools: HashSet<TradedPool> = HashSet::new();
+        let mut bundle = Vec::new();
+
+        for tx in ranked {
+            if bundle.len() >= self.max_bundle_size {
+                break;
+            }
+
+            let pools = traded_pools(&tx);
+            if pools.iter().any(|pool| used_pools.contains(pool)) {
+                debug!(
+                    "Skipping transaction with profit {:.4} that conflicts with the bundle",
+                    tx.estimated_profit
+                );
+                continue;
+            }
+
+            used_pools.extend(pools);
+            bundle.push(tx);
+        }
+
+        if self.revert_tolerant_legs {
+            for tx in bundle.iter_mut().skip(1) {
+                tx.may_revert = true;
+            }
+        }
+
+        bundle
+    }
+}
+
+impl Default for BundleComposer {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_BUNDLE_SIZE, false)
+    }
+}
+
+/// Calculate the estimated profit per unit of gas of a transaction
+pub fn profit_density(tx: &ArbitrageTransaction) -> f64 {
+    if tx.estimated_gas.is_zero() {
+        return 0.0;
+    }
+
+    tx.estimated_profit / tx.estimated_gas.as_u128() as f64
+}
+
+/// Get the pools a transaction swaps through
+fn traded_pools(tx: &ArbitrageTransaction) -> Vec<TradedPool> {
+    tx.token_path
+        .windows(2)
+        .zip(&tx.hops)
+        .map(|(pair, &(dex_type, pool))| {
+            if pool.is_zero() {
+                TradedPool::Pair(pool_key(dex_type, pair[0], pair[1]))
+            } else {
+                TradedPool::Address(pool)
+            }
+        })
+        .collect()
+}
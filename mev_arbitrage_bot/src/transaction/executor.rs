@@ -5,17 +5,37 @@
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use ethers::middleware::{Middleware, SignerMiddleware};
-use ethers::providers::{Http, Provider};
 use ethers::signers::{LocalWallet, Signer};
-use ethers::types::{transaction::eip2718::TypedTransaction, Address, H256, U256};
+use ethers::types::{
+    transaction::eip2718::TypedTransaction, Address, BlockNumber, Bytes, Eip1559TransactionRequest,
+    TransactionRequest, H256, U256,
+};
 use log::{debug, error, info, warn};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
 
-use crate::config::Config;
+use crate::alerts::AlertManager;
+use crate::analytics::BundleAnalytics;
+use crate::blockchain::RpcClient;
+use crate::config::{Config, GasStrategy, InclusionUrgency};
 use crate::gas::GasOptimizer;
+use crate::metrics;
 use crate::mev_share::MevShareClient;
-use crate::transaction::{validate_transaction, ArbitrageTransaction, TransactionResult};
+use crate::risk;
+use crate::simulation::TransactionSimulator;
+use crate::storage::Storage;
+use crate::transaction::attribution::settlement_costs;
+use crate::transaction::inclusion::{BundleInclusionTracker, SubmittedBundle};
+use crate::transaction::reconcile::reconcile_wallet;
+use crate::transaction::replacement::{
+    ReplacementTracker, DEFAULT_MAX_REPLACEMENTS, REPLACEMENT_FEE_BUMP_PERCENT,
+};
+use crate::transaction::{
+    validate_transaction, ArbitrageTransaction, BidStrategy, NonceTracker, PrivateTxConfig,
+    PrivateTxOptions, PrivateTxSender, SettlementCosts, TokenFlowInspector, TransactionResult,
+};
+use crate::utils::{decimal_to_u256, u256_to_decimal};
 
 /// Interface for transaction executors
 #[async_trait]
@@ -23,35 +43,99 @@ pub trait TransactionExecutor: Send + Sync {
     /// Execute a transaction
     async fn execute_transaction(&self, tx: ArbitrageTransaction) -> Result<H256>;
 
+    /// Execute several independent transactions as one MEV-Share bundle, starting at a target block
+    async fn execute_bundle(
+        &self,
+        txs: Vec<ArbitrageTransaction>,
+        target_block: u64,
+    ) -> Result<String>;
+
     /// Get the status of a transaction
     async fn get_transaction_status(&self, tx_hash: H256) -> Result<TransactionResult>;
 
     /// Wait for a transaction to be confirmed
+    ///
+    /// A directly sent transaction still pending `replace_after_blocks` blocks after it was
+    /// sent is replaced at a bumped fee, up to `max_replacements` times; the returned status is
+    /// that of whichever transaction of the chain was included.
     async fn wait_for_transaction(
         &self,
         tx_hash: H256,
         timeout: Duration,
     ) -> Result<TransactionResult>;
 
+    /// Wait until an included transaction is `confirmations` blocks deep, counting its block
+    ///
+    /// The receipt is re-read each time, so a transaction reorged into another block is
+    /// followed there, and the returned status is the one at the required depth.
+    async fn wait_for_confirmations(
+        &self,
+        tx_hash: H256,
+        confirmations: u64,
+        timeout: Duration,
+    ) -> Result<TransactionResult>;
+
+    /// Read the gas split and refund of an included transaction, for profit attribution
+    async fn settlement_costs(&self, result: &TransactionResult) -> Result<SettlementCosts>;
+
     /// Cancel a pending transaction
     async fn cancel_transaction(&self, tx_hash: H256) -> Result<H256>;
+
+    /// Rebroadcast a pending transaction with the same nonce and call at a bumped fee
+    ///
+    /// `new_gas` is the gas price of a legacy transaction or the priority fee of an EIP-1559
+    /// one (in wei); the fees are raised by at least 20% regardless, within the max gas price.
+    /// Returns the hash of the replacement.
+    async fn replace_transaction(&self, tx_hash: H256, new_gas: U256) -> Result<H256>;
+
+    /// Sign a transaction that is not an arbitrage (e.g. an owner call to the contract) and send
+    /// it directly, priced for the gas strategy and with the next tracked nonce
+    async fn send_wallet_transaction(&self, request: TransactionRequest) -> Result<H256>;
+
+    /// Reconcile the wallet's nonce, balance and recent transactions against the chain
+    async fn reconcile_wallet(&self, alerts: &AlertManager) -> Result<()>;
+}
+
+/// Fees to price transactions with, fetched once per submission
+#[derive(Debug, Clone, Copy)]
+enum FeeQuote {
+    /// Legacy (type 0) transaction with a single gas price
+    Legacy { gas_price: U256 },
+
+    /// EIP-1559 (type 2) transaction priced from the base fee and a priority fee
+    Eip1559 { base_fee: U256, priority_fee: U256 },
 }
 
 /// Implementation of the transaction executor
 pub struct TransactionExecutorImpl {
     config: Arc<Config>,
-    blockchain_client: Arc<Provider<Http>>,
+    blockchain_client: Arc<RpcClient>,
     mev_share_client: Arc<MevShareClient>,
     gas_optimizer: Arc<dyn GasOptimizer>,
     wallet: Option<LocalWallet>,
+    token_flow_inspector: TokenFlowInspector,
+    simulator: Arc<dyn TransactionSimulator>,
+    nonce_tracker: Option<Arc<NonceTracker>>,
+    storage: Option<Arc<Storage>>,
+    inclusion_tracker: Arc<BundleInclusionTracker>,
+    bid_strategy: Option<BidStrategy>,
+    replacement_tracker: ReplacementTracker,
+    private_tx_sender: Option<PrivateTxSender>,
+    cancel: CancellationToken,
 }
 
 /// Create a new transaction executor
+///
+/// Waits for submitted transactions, and the bundles followed in the background, stop when
+/// `cancel` is cancelled.
 pub async fn create_executor(
     config: &Arc<Config>,
-    blockchain_client: Arc<Provider<Http>>,
+    blockchain_client: Arc<RpcClient>,
     mev_share_client: Arc<MevShareClient>,
     gas_optimizer: Arc<dyn GasOptimizer>,
+    simulator: Arc<dyn TransactionSimulator>,
+    storage: Option<Arc<Storage>>,
+    cancel: CancellationToken,
 ) -> Result<Arc<dyn TransactionExecutor>> {
     // Initialize the wallet if a private key is provided
     let wallet = if let Some(private_key) = &config.ethereum.private_key {
@@ -60,57 +144,387 @@ pub async fn create_executor(
         None
     };
 
+    let token_flow_inspector = TokenFlowInspector::new(config, blockchain_client.clone());
+    let nonce_tracker = wallet
+        .as_ref()
+        .map(|wallet| Arc::new(NonceTracker::new(wallet.address(), storage.clone())));
+    let inclusion_tracker = Arc::new(BundleInclusionTracker::new(
+        config,
+        blockchain_client.clone(),
+        mev_share_client.clone(),
+        nonce_tracker.clone(),
+        wallet
+            .clone()
+            .map(|wallet| wallet.with_chain_id(config.ethereum.chain_id)),
+        BundleAnalytics::new(
+            blockchain_client.clone(),
+            mev_share_client.clone(),
+            gas_optimizer.clone(),
+            storage.clone(),
+        ),
+        cancel.clone(),
+    ));
+    let private_tx = config.section::<PrivateTxConfig>()?;
+    let private_tx_sender = if private_tx.enabled {
+        Some(PrivateTxSender::new(config, &private_tx)?)
+    } else {
+        None
+    };
+
     let executor = TransactionExecutorImpl {
         config: config.clone(),
         blockchain_client,
         mev_share_client,
         gas_optimizer,
         wallet,
+        token_flow_inspector,
+        simulator,
+        nonce_tracker,
+        replacement_tracker: ReplacementTracker::new(storage.clone()),
+        storage,
+        inclusion_tracker,
+        bid_strategy: BidStrategy::new(config),
+        private_tx_sender,
+        cancel,
     };
 
     Ok(Arc::new(executor))
 }
 
-#[async_trait]
-impl TransactionExecutor for TransactionExecutorImpl {
-    async fn execute_transaction(&self, tx: ArbitrageTransaction) -> Result<H256> {
-        // Validate the transaction
-        validate_transaction(&tx).await?;
+impl TransactionExecutorImpl {
+    /// Scale a gas price by the transaction's variant tip multiplier, capped at the max gas price
+    fn apply_tip_multiplier(&self, gas_price: U256, tx: &ArbitrageTransaction) -> U256 {
+        if (tx.variant.tip_multiplier - 1.0).abs() < f64::EPSILON {
+            return gas_price;
+        }
+
+        let scaled = gas_price
+            .saturating_mul(U256::from(
+                (tx.variant.tip_multiplier * 100.0).max(0.0) as u64
+            ))
+            .checked_div(U256::from(100))
+            .unwrap_or_default();
+
+        let max_gas_price = U256::from(self.config.gas.max_gas_price * 1_000_000_000); // Convert gwei to wei
+        std::cmp::min(scaled, max_gas_price)
+    }
+
+    /// Get the fees for the configured gas strategy, tipping for an inclusion urgency
+    async fn fee_quote(&self, urgency: InclusionUrgency) -> Result<FeeQuote> {
+        match self.config.gas.strategy {
+            GasStrategy::Eip1559 => {
+                let (base_fee, priority_fee) =
+                    self.gas_optimizer.get_eip1559_fee_data(urgency).await?;
+                Ok(FeeQuote::Eip1559 {
+                    base_fee,
+                    priority_fee,
+                })
+            }
+            GasStrategy::Fixed | GasStrategy::Dynamic => Ok(FeeQuote::Legacy {
+                gas_price: self.gas_optimizer.get_optimal_gas_price().await?,
+            }),
+        }
+    }
+
+    /// Build a typed transaction of the strategy's type, scaling the tip by the variant's multiplier
+    ///
+    /// EIP-1559 transactions bid `base_fee * base_fee_multiplier + priority_fee` as their max fee,
+    /// capped at the max gas price, so they keep paying only the base fee they are included at.
+    /// Their priority fee is raised to outbid competitors that recently won races through the
+    /// route's pools.
+    fn priced_transaction(&self, tx: &ArbitrageTransaction, fees: FeeQuote) -> TypedTransaction {
+        match fees {
+            FeeQuote::Legacy { gas_price } => {
+                let mut typed_tx: TypedTransaction = tx.request.clone().into();
+                typed_tx.set_gas_price(self.apply_tip_multiplier(gas_price, tx));
+                typed_tx
+            }
+            FeeQuote::Eip1559 {
+                base_fee,
+                priority_fee,
+            } => {
+                let pools: Vec<Address> = tx.hops.iter().map(|&(_, pool)| pool).collect();
+                let priority_fee = match self.gas_optimizer.competing_priority_fee(&pools) {
+                    Some(competing) => priority_fee.max(competing),
+                    None => priority_fee,
+                };
+                let priority_fee = self.apply_tip_multiplier(priority_fee, tx);
+                let max_fee_per_gas = self.max_fee_per_gas(base_fee, priority_fee);
+
+                let mut request = eip1559_request(&tx.request);
+                request.max_fee_per_gas = Some(max_fee_per_gas);
+                request.max_priority_fee_per_gas = Some(priority_fee.min(max_fee_per_gas));
+                TypedTransaction::Eip1559(request)
+            }
+        }
+    }
+
+    /// Raise a bundle transaction's priority fee to its bid share of the expected profit
+    ///
+    /// The expected profit is the estimate less the gas at the base fee, in ETH at the trade's
+    /// pinned price. Without a bid strategy, a price or an EIP-1559 quote the fees are kept.
+    fn bid_transaction(
+        &self,
+        typed_tx: TypedTransaction,
+        tx: &ArbitrageTransaction,
+        fees: FeeQuote,
+    ) -> TypedTransaction {
+        let (Some(bid_strategy), FeeQuote::Eip1559 { base_fee, .. }, Some(eth_price_usd)) =
+            (&self.bid_strategy, fees, tx.pinned_prices.eth_usd())
+        else {
+            return typed_tx;
+        };
+        let TypedTransaction::Eip1559(mut request) = typed_tx else {
+            return typed_tx;
+        };
+
+        let base_cost_usd =
+            u256_to_decimal(base_fee.saturating_mul(tx.estimated_gas), 18) * eth_price_usd;
+        let expected_profit = decimal_to_u256(
+            ((tx.estimated_profit - base_cost_usd) / eth_price_usd).max(0.0),
+            18,
+        );
+        let floor = request.max_priority_fee_per_gas.unwrap_or_default();
+        let priority_fee = bid_strategy.priority_fee(floor, expected_profit, tx.estimated_gas);
+        let max_fee_per_gas = self.max_fee_per_gas(base_fee, priority_fee);
+        debug!(
+            "Bidding a priority fee of {} wei for {} (priced at {} wei)",
+            priority_fee, tx.opportunity_id, floor
+        );
+
+        request.max_fee_per_gas = Some(max_fee_per_gas);
+        request.max_priority_fee_per_gas = Some(priority_fee.min(max_fee_per_gas));
+        TypedTransaction::Eip1559(request)
+    }
+
+    /// Get the max fee bid for a priority fee: `base_fee * base_fee_multiplier + priority_fee`,
+    /// capped at the max gas price
+    fn max_fee_per_gas(&self, base_fee: U256, priority_fee: U256) -> U256 {
+        let max_gas_price = U256::from(self.config.gas.max_gas_price * 1_000_000_000); // Convert gwei to wei
+        base_fee
+            .saturating_mul(U256::from(
+                (self.config.gas.base_fee_multiplier * 100.0).max(0.0) as u64,
+            ))
+            .checked_div(U256::from(100))
+            .unwrap_or_default()
+            .saturating_add(priority_fee)
+            .min(max_gas_price)
+    }
+
+    /// Simulate the transaction and reject it if tokens flow to unexpected addresses
+    async fn check_token_flows(&self, tx: &ArbitrageTransaction, from: Address) -> Result<()> {
+        let request = tx.request.clone().from(from);
+
+        let report = match self.token_flow_inspector.inspect(&request).await {
+            Ok(report) => report,
+            Err(e) => {
+                // Not every node exposes the debug namespace; don't block execution on it
+                warn!("Skipping token flow check: {}", e);
+                return Ok(());
+            }
+        };
+
+        if let Some(reason) = &report.revert_reason {
+            return Err(anyhow::anyhow!(
+                "Simulated transaction reverted: {}",
+                reason
+            ));
+        }
+
+        if !report.is_clean() {
+            return Err(anyhow::anyhow!(
+                "Simulated transaction sends tokens to unexpected addresses: {}",
+                report.describe_unexpected()
+            ));
+        }
+
+        debug!(
+            "Token flow check passed ({} transfers)",
+            report.transfers.len()
+        );
+
+        Ok(())
+    }
+
+    /// Submit transactions as a bundle for the blocks from `target_block` and follow it until
+    /// it is included, rebidding for later blocks on a miss
+    async fn submit_bundle(
+        &self,
+        txs: &[ArbitrageTransaction],
+        target_block: u64,
+    ) -> Result<SubmittedBundle> {
+        if txs.is_empty() {
+            return Err(anyhow::anyhow!("Cannot execute an empty bundle"));
+        }
+        for tx in txs {
+            if let Some(reason) = risk::global().check_transaction(tx) {
+                return Err(anyhow::anyhow!(
+                    "Bundle leg {} refused by the risk limits: {}",
+                    tx.opportunity_id,
+                    reason
+                ));
+            }
+        }
 
         // Check if we have a wallet
         let wallet = self
             .wallet
             .as_ref()
-            .context("No wallet available for signing transactions")?;
+            .context("No wallet available for signing transactions")?
+            .clone()
+            .with_chain_id(self.config.ethereum.chain_id);
 
-        // Optimize gas price
-        let gas_price = self.gas_optimizer.get_optimal_gas_price().await?;
+        // Price every leg from the same fees
+        let fees = self.fee_quote(self.config.gas.fee_history.urgency).await?;
 
-        // Create a typed transaction
-        let mut typed_tx: TypedTransaction = tx.request.clone().into();
-        typed_tx.set_gas_price(gas_price);
+        // Bundle transactions execute back to back, so they take consecutive nonces from the
+        // next one, which bundles competing for the same blocks share
+        let first_nonce = match &self.nonce_tracker {
+            Some(tracker) => tracker.peek(&self.blockchain_client).await?,
+            None => {
+                self.blockchain_client
+                    .get_transaction_count(wallet.address(), Some(BlockNumber::Pending.into()))
+                    .await?
+            }
+        };
 
-        // Sign the transaction
-        let client_with_signer =
-            SignerMiddleware::new(self.blockchain_client.clone(), wallet.clone());
+        let bundle = self
+            .sign_and_send_bundle(&wallet, txs, fees, first_nonce, target_block)
+            .await?;
 
-        let tx_hash = if tx.use_mev_share {
-            // Send the transaction via MEV-Share
-            debug!("Sending transaction via MEV-Share");
-            self.mev_share_client.send_transaction(typed_tx).await?
-        } else {
-            // Send the transaction directly
-            debug!("Sending transaction directly");
-            let pending_tx = client_with_signer.send_transaction(typed_tx, None).await?;
-            pending_tx.tx_hash()
-        };
+        self.inclusion_tracker.clone().watch(bundle.clone());
+
+        Ok(bundle)
+    }
 
-        info!("Transaction sent: {}", tx_hash);
+    /// Sign bundle transactions with consecutive nonces, simulate the merged bundle and submit it
+    async fn sign_and_send_bundle(
+        &self,
+        wallet: &LocalWallet,
+        txs: &[ArbitrageTransaction],
+        fees: FeeQuote,
+        first_nonce: U256,
+        target_block: u64,
+    ) -> Result<SubmittedBundle> {
+        for tx in txs {
+            validate_transaction(tx).await?;
+        }
 
-        Ok(tx_hash)
+        // Simulate the legs in parallel against one block, then inspect their token flows
+        if self.config.security.simulate_transactions {
+            let results = self.simulator.verify_batch(txs, wallet.address()).await?;
+            for (index, (result, tx)) in results.into_iter().zip(txs).enumerate() {
+                // A reverted leg moves no tokens, and the relay still includes the rest
+                if let Err(e) = &result {
+                    if tx.may_revert {
+                        warn!(
+                            "Revert-tolerant bundle leg {} failed simulation: {}",
+                            index, e
+                        );
+                        continue;
+                    }
+                }
+                result.with_context(|| format!("Bundle leg {} failed simulation", index))?;
+                self.check_token_flows(tx, wallet.address()).await?;
+            }
+        }
+
+        let mut nonce = first_nonce;
+        let mut signed_txs: Vec<Bytes> = Vec::with_capacity(txs.len());
+        let mut tx_hashes = Vec::with_capacity(txs.len());
+        let mut reverting_tx_hashes = Vec::new();
+        let mut unsigned_txs = Vec::with_capacity(txs.len());
+        let mut priority_fee = U256::zero();
+        for tx in txs {
+            let mut typed_tx = self.bid_transaction(self.priced_transaction(tx, fees), tx, fees);
+            typed_tx.set_from(wallet.address());
+            typed_tx.set_nonce(nonce);
+            typed_tx.set_chain_id(self.config.ethereum.chain_id);
+
+            if let TypedTransaction::Eip1559(request) = &typed_tx {
+                priority_fee =
+                    priority_fee.max(request.max_priority_fee_per_gas.unwrap_or_default());
+            }
+
+            let signature = wallet.sign_transaction(&typed_tx).await?;
+            let tx_hash = typed_tx.hash(&signature);
+            signed_txs.push(typed_tx.rlp_signed(&signature));
+            tx_hashes.push(tx_hash);
+            unsigned_txs.push(typed_tx);
+            if tx.may_revert {
+                reverting_tx_hashes.push(tx_hash);
+            }
+            nonce += U256::one();
+        }
+
+        // The merged bundle is simulated too; the legs must still be profitable together
+        let (bundle_hash, last_block) = self
+            .inclusion_tracker
+            .submit(&signed_txs, &reverting_tx_hashes, target_block)
+            .await?;
+
+        info!(
+            "Bundle of {} transactions ({} allowed to revert) sent for blocks {}-{}: {}",
+            txs.len(),
+            reverting_tx_hashes.len(),
+            target_block,
+            last_block,
+            bundle_hash
+        );
+
+        Ok(SubmittedBundle {
+            bundle_hash,
+            signed_txs,
+            unsigned_txs,
+            first_tx_hashes: tx_hashes.clone(),
+            tx_hashes,
+            reverting_tx_hashes,
+            sender: wallet.address(),
+            first_nonce,
+            pools: txs
+                .iter()
+                .flat_map(|tx| tx.hops.iter().map(|&(_, pool)| pool))
+                .collect(),
+            priority_fee,
+            first_block: target_block,
+            last_block,
+        })
     }
 
-    async fn get_transaction_status(&self, tx_hash: H256) -> Result<TransactionResult> {
+    /// Sign a transaction and send it with `eth_sendPrivateTransaction`, returning its hash
+    async fn send_private(
+        &self,
+        client: &SignerMiddleware<Arc<RpcClient>, LocalWallet>,
+        mut typed_tx: TypedTransaction,
+        options: PrivateTxOptions,
+    ) -> Result<H256> {
+        let sender = self
+            .private_tx_sender
+            .as_ref()
+            .context("Private transactions are not enabled")?;
+
+        typed_tx.set_chain_id(self.config.ethereum.chain_id);
+        client.fill_transaction(&mut typed_tx, None).await?;
+        let signature = client.signer().sign_transaction(&typed_tx).await?;
+        let current_block = self.blockchain_client.get_block_number().await?.as_u64();
+
+        sender
+            .send(&typed_tx.rlp_signed(&signature), options, current_block)
+            .await
+    }
+
+    /// Replace a lingering transaction, bidding at least the current market fee
+    async fn speed_up(&self, tx_hash: H256) -> Result<H256> {
+        let new_gas = match self.fee_quote(InclusionUrgency::High).await? {
+            FeeQuote::Legacy { gas_price } => gas_price,
+            FeeQuote::Eip1559 { priority_fee, .. } => priority_fee,
+        };
+
+        self.replace_transaction(tx_hash, new_gas).await
+    }
+
+    /// Get the status of exactly one transaction hash
+    async fn transaction_status(&self, tx_hash: H256) -> Result<TransactionResult> {
         // Get the transaction receipt
         let receipt = self
             .blockchain_client
@@ -125,7 +539,10 @@ impl TransactionExecutor for TransactionExecutorImpl {
             Some(receipt) => {
                 let success = receipt.status.unwrap_or_default().as_u64() == 1;
                 let gas_used = receipt.gas_used;
-                let gas_price = tx.and_then(|tx| tx.gas_price);
+                // EIP-1559 transactions pay the effective price, not their max fee
+                let gas_price = receipt
+                    .effective_gas_price
+                    .or_else(|| tx.and_then(|tx| tx.gas_price));
                 let actual_cost =
                     gas_used.and_then(|gas| gas_price.map(|price| gas.saturating_mul(price)));
 
@@ -157,6 +574,132 @@ impl TransactionExecutor for TransactionExecutorImpl {
 
         Ok(result)
     }
+}
+
+#[async_trait]
+impl TransactionExecutor for TransactionExecutorImpl {
+    async fn execute_transaction(&self, tx: ArbitrageTransaction) -> Result<H256> {
+        if let Some(reason) = risk::global().check_transaction(&tx) {
+            return Err(anyhow::anyhow!("Refused by the risk limits: {}", reason));
+        }
+
+        // Bundles only land if the transaction does not revert, and are rebid on a miss
+        if tx.use_mev_share && self.config.mev_share.bundle_execution {
+            let target_block = self.blockchain_client.get_block_number().await?.as_u64() + 1;
+            let bundle = self
+                .submit_bundle(std::slice::from_ref(&tx), target_block)
+                .await?;
+            info!(
+                "Transaction sent as bundle {}: {:?} (strategy variant: {})",
+                bundle.bundle_hash, bundle.tx_hashes[0], tx.variant.name
+            );
+            return Ok(bundle.tx_hashes[0]);
+        }
+
+        // Validate the transaction
+        validate_transaction(&tx).await?;
+
+        // Check if we have a wallet
+        let wallet = self
+            .wallet
+            .as_ref()
+            .context("No wallet available for signing transactions")?;
+
+        // Simulate the transaction and inspect its token flows before sending anything
+        if self.config.security.simulate_transactions {
+            self.simulator.verify(&tx, wallet.address()).await?;
+            self.check_token_flows(&tx, wallet.address()).await?;
+        }
+
+        // Price the transaction for the gas strategy, tipping by the variant's multiplier
+        let fees = self.fee_quote(self.config.gas.fee_history.urgency).await?;
+        let mut typed_tx = self.priced_transaction(&tx, fees);
+
+        // Use the next locally tracked nonce
+        let nonce = match &self.nonce_tracker {
+            Some(tracker) => Some(tracker.reserve(&self.blockchain_client, 1).await?),
+            None => None,
+        };
+        if let Some(nonce) = nonce {
+            typed_tx.set_nonce(nonce);
+        }
+
+        // Sign the transaction
+        let client_with_signer =
+            SignerMiddleware::new(self.blockchain_client.clone(), wallet.clone());
+
+        let sent = if let Some(options) = tx.private {
+            // Keep the transaction out of the public mempool without bundling it
+            debug!("Sending transaction with eth_sendPrivateTransaction");
+            self.send_private(&client_with_signer, typed_tx, options)
+                .await
+        } else if tx.use_mev_share {
+            // Send the transaction via MEV-Share
+            debug!("Sending transaction via MEV-Share");
+            self.mev_share_client.send_transaction(typed_tx).await
+        } else {
+            // Send the transaction directly
+            debug!("Sending transaction directly");
+            client_with_signer
+                .send_transaction(typed_tx, None)
+                .await
+                .map(|pending_tx| pending_tx.tx_hash())
+                .map_err(Into::into)
+        };
+
+        // Hand the nonce back if nothing was sent with it
+        let tx_hash = match sent {
+            Ok(tx_hash) => tx_hash,
+            Err(e) => {
+                if let (Some(tracker), Some(nonce)) = (&self.nonce_tracker, nonce) {
+                    tracker.release(nonce, 1);
+                }
+                risk::global().record_failure(&format!("send failed: {}", e));
+                return Err(e);
+            }
+        };
+
+        info!(
+            "Transaction sent: {} (strategy variant: {})",
+            tx_hash, tx.variant.name
+        );
+
+        // Only public transactions are sped up; a replacement would expose a private one
+        if !tx.use_mev_share && tx.private.is_none() {
+            self.replacement_tracker.watch(tx_hash);
+        }
+
+        Ok(tx_hash)
+    }
+
+    async fn execute_bundle(
+        &self,
+        txs: Vec<ArbitrageTransaction>,
+        target_block: u64,
+    ) -> Result<String> {
+        let bundle = self.submit_bundle(&txs, target_block).await?;
+        Ok(bundle.bundle_hash)
+    }
+
+    async fn get_transaction_status(&self, tx_hash: H256) -> Result<TransactionResult> {
+        // A rebid with escalated tips re-signs bundle transactions under a new hash
+        let tx_hash = self.inclusion_tracker.current_hash(tx_hash);
+
+        // Any transaction of a replacement chain may be the one included
+        let Some(chain) = self.replacement_tracker.chain(tx_hash) else {
+            return self.transaction_status(tx_hash).await;
+        };
+        let mut latest = None;
+        for &hash in chain.iter().rev() {
+            let status = self.transaction_status(hash).await?;
+            if status.block_number.is_some() {
+                return Ok(status);
+            }
+            latest.get_or_insert(status);
+        }
+
+        latest.context("Empty replacement chain")
+    }
 
     async fn wait_for_transaction(
         &self,
@@ -164,24 +707,100 @@ impl TransactionExecutor for TransactionExecutorImpl {
         timeout: Duration,
     ) -> Result<TransactionResult> {
         let start_time = Instant::now();
+        let max_replacements = self
+            .config
+            .security
+            .max_replacements
+            .unwrap_or(DEFAULT_MAX_REPLACEMENTS);
+        let mut sent_at_block = None;
+
+        let waiting = async {
+            loop {
+                // Check if we've exceeded the timeout
+                if start_time.elapsed() > timeout {
+                    return Err(anyhow::anyhow!("Transaction timed out after {:?}", timeout));
+                }
 
-        loop {
-            // Check if we've exceeded the timeout
-            if start_time.elapsed() > timeout {
-                return Err(anyhow::anyhow!("Transaction timed out after {:?}", timeout));
+                // Get the transaction status
+                let status = self.get_transaction_status(tx_hash).await?;
+
+                // If the transaction is confirmed, return the status
+                if status.block_number.is_some() {
+                    self.replacement_tracker.forget(tx_hash);
+                    return Ok(status);
+                }
+
+                // Speed up a lingering transaction from the latest of its chain
+                if let (Some(replace_after), Some(_)) = (
+                    self.config.security.replace_after_blocks,
+                    self.replacement_tracker.chain(tx_hash),
+                ) {
+                    let head = self.blockchain_client.get_block_number().await?.as_u64();
+                    let sent_at = *sent_at_block.get_or_insert(head);
+                    if head >= sent_at + replace_after
+                        && self.replacement_tracker.replacements(tx_hash) < max_replacements
+                    {
+                        sent_at_block = Some(head);
+                        if let Err(e) = self.speed_up(status.tx_hash).await {
+                            warn!("Failed to speed up transaction {:?}: {}", status.tx_hash, e);
+                        }
+                    }
+                }
+
+                // Wait a bit before checking again
+                tokio::time::sleep(Duration::from_secs(1)).await;
             }
+        };
+        let result = self
+            .cancel
+            .run_until_cancelled(waiting)
+            .await
+            .unwrap_or_else(|| Err(anyhow::anyhow!("Cancelled while waiting for inclusion")));
+        if result.is_err() {
+            self.replacement_tracker.forget(tx_hash);
+        }
+
+        result
+    }
 
-            // Get the transaction status
-            let status = self.get_transaction_status(tx_hash).await?;
+    async fn wait_for_confirmations(
+        &self,
+        tx_hash: H256,
+        confirmations: u64,
+        timeout: Duration,
+    ) -> Result<TransactionResult> {
+        let start_time = Instant::now();
 
-            // If the transaction is confirmed, return the status
-            if status.block_number.is_some() {
-                return Ok(status);
+        let waiting = async {
+            loop {
+                if start_time.elapsed() > timeout {
+                    return Err(anyhow::anyhow!(
+                        "Transaction not {} blocks deep after {:?}",
+                        confirmations,
+                        timeout
+                    ));
+                }
+
+                // A reorg moves the receipt to another block or drops it until re-inclusion
+                let status = self.get_transaction_status(tx_hash).await?;
+                if let Some(block_number) = status.block_number {
+                    let head = self.blockchain_client.get_block_number().await?.as_u64();
+                    if head + 1 >= block_number + confirmations {
+                        return Ok(status);
+                    }
+                }
+
+                tokio::time::sleep(Duration::from_secs(1)).await;
             }
+        };
+        self.cancel
+            .run_until_cancelled(waiting)
+            .await
+            .unwrap_or_else(|| Err(anyhow::anyhow!("Cancelled while waiting for confirmations")))
+    }
 
-            // Wait a bit before checking again
-            tokio::time::sleep(Duration::from_secs(1)).await;
-        }
+    async fn settlement_costs(&self, result: &TransactionResult) -> Result<SettlementCosts> {
+        settlement_costs(&self.blockchain_client, result).await
     }
 
     async fn cancel_transaction(&self, tx_hash: H256) -> Result<H256> {
@@ -198,20 +817,30 @@ impl TransactionExecutor for TransactionExecutorImpl {
             .as_ref()
             .context("No wallet available for signing transactions")?;
 
-        // Create a cancellation transaction (same nonce, higher gas price, zero value, to self)
+        // Create a cancellation transaction (same nonce, higher fees, zero value, to self)
         let from_address = tx.from;
         let nonce = tx.nonce;
-        let gas_price = tx
-            .gas_price
-            .unwrap_or_default()
-            .saturating_mul(U256::from(120))
-            .checked_div(U256::from(100))
-            .unwrap_or_default(); // 20% higher
+        let bump = |fee: Option<U256>| {
+            fee.unwrap_or_default()
+                .saturating_mul(U256::from(120))
+                .checked_div(U256::from(100))
+                .unwrap_or_default() // 20% higher
+        };
 
-        // Create a legacy transaction
-        let mut cancel_tx = TypedTransaction::Legacy(Default::default());
+        // Replace an EIP-1559 transaction with one of the same type, bumping both fees
+        let mut cancel_tx = match tx.max_fee_per_gas {
+            Some(max_fee_per_gas) => TypedTransaction::Eip1559(
+                Eip1559TransactionRequest::new()
+                    .max_fee_per_gas(bump(Some(max_fee_per_gas)))
+                    .max_priority_fee_per_gas(bump(tx.max_priority_fee_per_gas)),
+            ),
+            None => {
+                let mut cancel_tx = TypedTransaction::Legacy(Default::default());
+                cancel_tx.set_gas_price(bump(tx.gas_price));
+                cancel_tx
+            }
+        };
         cancel_tx.set_nonce(nonce);
-        cancel_tx.set_gas_price(gas_price);
         cancel_tx.set_gas(U256::from(21000)); // Minimum gas for a simple transfer
         cancel_tx.set_to(ethers::types::NameOrAddress::Address(from_address));
         cancel_tx.set_value(U256::zero());
@@ -228,4 +857,181 @@ impl TransactionExecutor for TransactionExecutorImpl {
 
         Ok(cancel_tx_hash)
     }
+
+    async fn replace_transaction(&self, tx_hash: H256, new_gas: U256) -> Result<H256> {
+        let tx = self
+            .blockchain_client
+            .get_transaction(tx_hash)
+            .await?
+            .context("Transaction not found")?;
+        if tx.block_number.is_some() {
+            return Err(anyhow::anyhow!(
+                "Transaction {:?} is already included",
+                tx_hash
+            ));
+        }
+
+        let wallet = self
+            .wallet
+            .as_ref()
+            .context("No wallet available for signing transactions")?;
+
+        let max_gas_price = U256::from(self.config.gas.max_gas_price * 1_000_000_000); // Convert gwei to wei
+        let bump = |fee: U256| {
+            fee.saturating_mul(U256::from(REPLACEMENT_FEE_BUMP_PERCENT))
+                .checked_div(U256::from(100))
+                .unwrap_or_default()
+        };
+
+        // Keep the transaction's type, raising the tip and the max fee alike
+        let (mut replacement, fee_per_gas) = match tx.max_fee_per_gas {
+            Some(max_fee_per_gas) => {
+                let priority_fee = tx.max_priority_fee_per_gas.unwrap_or_default();
+                let new_priority_fee = new_gas.max(bump(priority_fee));
+                let new_max_fee = max_fee_per_gas
+                    .saturating_add(new_priority_fee - priority_fee)
+                    .max(bump(max_fee_per_gas));
+                if new_max_fee > max_gas_price {
+                    return Err(anyhow::anyhow!(
+                        "Replacing {:?} needs a max fee of {} wei, above the max gas price",
+                        tx_hash,
+                        new_max_fee
+                    ));
+                }
+
+                let request = Eip1559TransactionRequest::new()
+                    .max_fee_per_gas(new_max_fee)
+                    .max_priority_fee_per_gas(new_priority_fee)
+                    .access_list(tx.access_list.clone().unwrap_or_default());
+                (TypedTransaction::Eip1559(request), new_priority_fee)
+            }
+            None => {
+                let gas_price = new_gas.max(bump(tx.gas_price.unwrap_or_default()));
+                if gas_price > max_gas_price {
+                    return Err(anyhow::anyhow!(
+                        "Replacing {:?} needs a gas price of {} wei, above the max gas price",
+                        tx_hash,
+                        gas_price
+                    ));
+                }
+
+                let mut replacement = TypedTransaction::Legacy(Default::default());
+                replacement.set_gas_price(gas_price);
+                (replacement, gas_price)
+            }
+        };
+        replacement.set_from(tx.from);
+        replacement.set_nonce(tx.nonce);
+        replacement.set_gas(tx.gas);
+        if let Some(to) = tx.to {
+            replacement.set_to(to);
+        }
+        replacement.set_value(tx.value);
+        replacement.set_data(tx.input.clone());
+        replacement.set_chain_id(self.config.ethereum.chain_id);
+
+        let client_with_signer =
+            SignerMiddleware::new(self.blockchain_client.clone(), wallet.clone());
+        let pending_tx = client_with_signer
+            .send_transaction(replacement, None)
+            .await?;
+        let replacement_hash = pending_tx.tx_hash();
+
+        self.replacement_tracker
+            .record(tx_hash, replacement_hash, tx.nonce, fee_per_gas);
+        metrics::global().increment_counter("tx_replacements", 1);
+        info!(
+            "Transaction {:?} replaced by {:?} at {} wei per gas (nonce {})",
+            tx_hash, replacement_hash, fee_per_gas, tx.nonce
+        );
+
+        Ok(replacement_hash)
+    }
+
+    async fn send_wallet_transaction(&self, request: TransactionRequest) -> Result<H256> {
+        let wallet = self
+            .wallet
+            .as_ref()
+            .context("No wallet available for signing transactions")?;
+
+        let mut typed_tx = match self.fee_quote(self.config.gas.fee_history.urgency).await? {
+            FeeQuote::Legacy { gas_price } => {
+                let mut typed_tx: TypedTransaction = request.into();
+                typed_tx.set_gas_price(gas_price);
+                typed_tx
+            }
+            FeeQuote::Eip1559 {
+                base_fee,
+                priority_fee,
+            } => {
+                let max_fee_per_gas = self.max_fee_per_gas(base_fee, priority_fee);
+                let mut request = eip1559_request(&request);
+                request.max_fee_per_gas = Some(max_fee_per_gas);
+                request.max_priority_fee_per_gas = Some(priority_fee.min(max_fee_per_gas));
+                TypedTransaction::Eip1559(request)
+            }
+        };
+        typed_tx.set_from(wallet.address());
+        typed_tx.set_chain_id(self.config.ethereum.chain_id);
+
+        let nonce = match &self.nonce_tracker {
+            Some(tracker) => Some(tracker.reserve(&self.blockchain_client, 1).await?),
+            None => None,
+        };
+        if let Some(nonce) = nonce {
+            typed_tx.set_nonce(nonce);
+        }
+
+        let client_with_signer =
+            SignerMiddleware::new(self.blockchain_client.clone(), wallet.clone());
+        let sent = client_with_signer
+            .send_transaction(typed_tx, None)
+            .await
+            .map(|pending_tx| pending_tx.tx_hash());
+        match sent {
+            Ok(tx_hash) => {
+                info!("Wallet transaction sent: {:?}", tx_hash);
+                Ok(tx_hash)
+            }
+            Err(e) => {
+                if let (Some(tracker), Some(nonce)) = (&self.nonce_tracker, nonce) {
+                    tracker.release(nonce, 1);
+                }
+                Err(e.into())
+            }
+        }
+    }
+
+    async fn reconcile_wallet(&self, alerts: &AlertManager) -> Result<()> {
+        let Some(nonce_tracker) = &self.nonce_tracker else {
+            info!("No wallet configured; skipping wallet reconciliation");
+            return Ok(());
+        };
+
+        reconcile_wallet(
+            &self.config,
+            &self.blockchain_client,
+            nonce_tracker,
+            self,
+            &self.storage,
+            alerts,
+        )
+        .await
+    }
+}
+
+/// Convert a legacy transaction request into an EIP-1559 one, without fees
+fn eip1559_request(request: &TransactionRequest) -> Eip1559TransactionRequest {
+    Eip1559TransactionRequest {
+        from: request.from,
+        to: request.to.clone(),
+        gas: request.gas,
+        value: request.value,
+        data: request.data.clone(),
+        nonce: request.nonce,
+        access_list: Default::default(),
+        max_priority_fee_per_gas: None,
+        max_fee_per_gas: None,
+        chain_id: request.chain_id,
+    }
 }
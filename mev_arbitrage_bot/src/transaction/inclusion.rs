@@ -0,0 +1,510 @@
+//! Bundle Inclusion Module
+//!
+//! This module is responsible for submitting signed transactions as MEV-Share bundles that
+//! target a range of upcoming blocks, following each bundle until it is included, and
+//! resubmitting it for the following blocks when the range passes without inclusion.
+//!
+//! With a bid strategy, a rebid re-signs the transactions with escalated tips, which changes
+//! their hashes; `current_hash` follows a first submission's hash to its latest replacement.
+//! Every submission's outcome is explained by the bundle analytics, and a rebid bids at least
+//! the tip that would have won the blocks it missed.
+//!
+//! Each submission also goes to the builders configured in `[[builders]]`; an included bundle
+//! is credited to the builder whose extra data its block carries.
+
+use anyhow::{Context, Result};
+use ethers::middleware::Middleware;
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, BlockNumber, Bytes, H256, U256};
+use ethers::utils::keccak256;
+use log::{debug, info, warn};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
+
+use crate::analytics::BundleAnalytics;
+use crate::blockchain::RpcClient;
+use crate::config::Config;
+use crate::metrics;
+use crate::mev_share::{BundleInclusion, MevShareClient};
+use crate::transaction::{BidStrategy, NonceTracker};
+
+/// Default number of consecutive blocks a bundle submission targets
+pub const DEFAULT_BUNDLE_BLOCK_RANGE: u64 = 3;
+
+/// Default number of times a missed bundle is resubmitted
+pub const DEFAULT_MAX_BUNDLE_REBIDS: u32 = 2;
+
+/// How often the chain head is polled while a bundle is pending
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A submitted bundle and what is needed to resubmit it
+#[derive(Debug, Clone)]
+pub struct SubmittedBundle {
+    /// Relay hash of the latest submission
+    pub bundle_hash: String,
+
+    /// Signed transactions, in execution order
+    pub signed_txs: Vec<Bytes>,
+
+    /// The transactions as priced for the first submission, before signing
+    pub unsigned_txs: Vec<TypedTransaction>,
+
+    /// Hashes of the transactions of the first submission
+    pub first_tx_hashes: Vec<H256>,
+
+    /// Hashes of the signed transactions
+    pub tx_hashes: Vec<H256>,
+
+    /// Hashes of the transactions allowed to revert without dropping the bundle
+    pub reverting_tx_hashes: Vec<H256>,
+
+    /// Wallet that signed the transactions
+    pub sender: Address,
+
+    /// Nonce of the first transaction
+    pub first_nonce: U256,
+
+    /// Pools the transactions swap through
+    pub pools: Vec<Address>,
+
+    /// Highest priority fee per gas bid by the latest submission's EIP-1559 transactions (in
+    /// wei)
+    pub priority_fee: U256,
+
+    /// First block targeted by the latest submission
+    pub first_block: u64,
+
+    /// Last block targeted by the latest submission
+    pub last_block: u64,
+}
+
+/// A bundle's transactions re-signed for a rebid
+struct Rebid {
+    signed_txs: Vec<Bytes>,
+    tx_hashes: Vec<H256>,
+    reverting_tx_hashes: Vec<H256>,
+    priority_fee: U256,
+}
+
+/// Submits bundles for a range of blocks and rebids them until they are included
+pub struct BundleInclusionTracker {
+    blockchain_client: Arc<RpcClient>,
+    mev_share_client: Arc<MevShareClient>,
+    nonce_tracker: Option<Arc<NonceTracker>>,
+    block_range: u64,
+    max_rebids: u32,
+    cancel: CancellationToken,
+
+    /// Wallet and strategy rebids are re-signed with, when tips are bid
+    bidding: Option<(LocalWallet, BidStrategy)>,
+
+    /// Latest hash of each first-submission transaction re-signed by a rebid
+    replaced: Mutex<HashMap<H256, H256>>,
+
+    /// Explains the outcome of each submission
+    analytics: BundleAnalytics,
+}
+
+impl BundleInclusionTracker {
+    /// Create a tracker using the configured block range, rebid limit and bid strategy, which
+    /// stops following bundles when `cancel` is cancelled
+    ///
+    /// Rebids are only re-signed with escalated tips when `wallet` is given.
+    pub fn new(
+        config: &Config,
+        blockchain_client: Arc<RpcClient>,
+        mev_share_client: Arc<MevShareClient>,
+        nonce_tracker: Option<Arc<NonceTracker>>,
+        wallet: Option<LocalWallet>,
+        analytics: BundleAnalytics,
+        cancel: CancellationToken,
+    ) -> Self {
+        Self {
+            blockchain_client,
+            mev_share_client,
+            nonce_tracker,
+            block_range: config
+                .mev_share
+                .bundle_block_range
+                .unwrap_or(DEFAULT_BUNDLE_BLOCK_RANGE)
+                .max(1),
+            max_rebids: config
+                .mev_share
+                .max_bundle_rebids
+                .unwrap_or(DEFAULT_MAX_BUNDLE_REBIDS),
+            cancel,
+            bidding: wallet.zip(BidStrategy::new(config)),
+            replaced: Mutex::new(HashMap::new()),
+            analytics,
+        }
+    }
+
+    /// Get the latest hash of a submitted transaction, following rebids that re-signed it
+    pub fn current_hash(&self, tx_hash: H256) -> H256 {
+        self.replaced
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&tx_hash)
+            .copied()
+            .unwrap_or(tx_hash)
+    }
+
+    /// Simulate signed transactions as a bundle for `first_block` and the blocks after it, and
+    /// submit it if every transaction succeeds or is in `reverting_tx_hashes`
+    ///
+    /// Returns the relay's bundle hash and the last block targeted.
+    pub async fn submit(
+        &self,
+        signed_txs: &[Bytes],
+        reverting_tx_hashes: &[H256],
+        first_block: u64,
+    ) -> Result<(String, u64)> {
+        let last_block = first_block + self.block_range - 1;
+        let bundle = self.mev_share_client.create_bundle(
+            signed_txs.to_vec(),
+            first_block,
+            Some(last_block),
+            reverting_tx_hashes,
+        );
+
+        // The relay drops bundles with transactions reverting outside `reverting_tx_hashes`, but
+        // catch it before submitting
+        let simulation = self.mev_share_client.simulate_bundle(&bundle).await?;
+        if !simulation.success {
+            return Err(anyhow::anyhow!(
+                "Bundle simulation failed: {}",
+                simulation
+                    .error
+                    .unwrap_or_else(|| "unknown error".to_string())
+            ));
+        }
+
+        // The relay and the configured builders are sent the bundle at once
+        let (relayed, broadcast) = tokio::join!(
+            self.mev_share_client.send_bundle(bundle),
+            self.mev_share_client.builders().broadcast(
+                signed_txs,
+                reverting_tx_hashes,
+                first_block,
+                last_block
+            )
+        );
+
+        let mut builder_hash = None;
+        let mut accepted = HashSet::new();
+        for submission in broadcast {
+            match submission.result {
+                Ok(hash) => {
+                    metrics::global().increment_counter(
+                        &format!("builder_{}_bundles_sent", submission.builder),
+                        1,
+                    );
+                    builder_hash = builder_hash.or(hash);
+                    accepted.insert(submission.builder);
+                }
+                Err(e) => {
+                    debug!(
+                        "Builder {} rejected the bundle for block {}: {}",
+                        submission.builder, submission.block_number, e
+                    );
+                    metrics::global().increment_counter(
+                        &format!("builder_{}_bundle_errors", submission.builder),
+                        1,
+                    );
+                }
+            }
+        }
+
+        // A bundle only builders accepted is followed by their hash, or its first transaction's
+        let bundle_hash = match relayed {
+            Ok(bundle_hash) => bundle_hash,
+            Err(e) if !accepted.is_empty() => {
+                warn!("Relay rejected the bundle, but builders accepted it: {}", e);
+                builder_hash
+                    .or_else(|| {
+                        signed_txs
+                            .first()
+                            .map(|tx| format!("{:?}", H256::from(keccak256(tx))))
+                    })
+                    .unwrap_or_default()
+            }
+            Err(e) => return Err(e),
+        };
+        debug!(
+            "Bundle {} targets blocks {}-{} (and {} builders)",
+            bundle_hash,
+            first_block,
+            last_block,
+            accepted.len()
+        );
+
+        Ok((bundle_hash, last_block))
+    }
+
+    /// Follow a submitted bundle in the background, rebidding for the next blocks on a miss
+    pub fn watch(self: Arc<Self>, bundle: SubmittedBundle) {
+        tokio::spawn(
+            async move {
+                let bundle_hash = bundle.bundle_hash.clone();
+                match self.cancel.run_until_cancelled(self.follow(bundle)).await {
+                    Some(Ok(())) => {}
+                    Some(Err(e)) => warn!("Stopped following bundle: {}", e),
+                    None => debug!("Stopped following bundle {} at shutdown", bundle_hash),
+                }
+            }
+            .in_current_span(),
+        );
+    }
+
+    /// Wait for each targeted range to pass and resubmit until included or out of rebids
+    async fn follow(&self, mut bundle: SubmittedBundle) -> Result<()> {
+        let mut rebids = 0;
+        loop {
+            let head = self.wait_for_block(bundle.last_block).await?;
+
+            if self.is_included(&bundle).await? {
+                info!(
+                    "Bundle {} included by block {} after {} rebids",
+                    bundle.bundle_hash, head, rebids
+                );
+                metrics::global().increment_counter("bundles_included", 1);
+                if let Some(tracker) = &self.nonce_tracker {
+                    tracker.confirm(bundle.first_nonce, bundle.signed_txs.len() as u64);
+                }
+                if let Err(e) = self.reconcile_builder(&bundle).await {
+                    debug!(
+                        "Failed to find the builder of bundle {}: {}",
+                        bundle.bundle_hash, e
+                    );
+                }
+                self.analytics.record_inclusion(&bundle, rebids).await;
+                return Ok(());
+            }
+
+            // Another transaction with the same nonce landed; the bundle can never be included
+            let chain_nonce = self
+                .blockchain_client
+                .get_transaction_count(bundle.sender, Some(BlockNumber::Latest.into()))
+                .await?;
+            let nonce_used = chain_nonce > bundle.first_nonce;
+            let report = self
+                .analytics
+                .explain_miss(&bundle, rebids, nonce_used)
+                .await;
+            if nonce_used {
+                warn!(
+                    "Nonce {} of bundle {} was used by another transaction; giving up",
+                    bundle.first_nonce, bundle.bundle_hash
+                );
+                metrics::global().increment_counter("bundles_missed", 1);
+                self.resync_nonce().await;
+                return Ok(());
+            }
+
+            if rebids >= self.max_rebids {
+                warn!(
+                    "Bundle {} not included after {} submissions",
+                    bundle.bundle_hash,
+                    rebids + 1
+                );
+                self.give_up(&bundle).await;
+                return Ok(());
+            }
+
+            // Raise the tips for the rebid, keeping the nonces
+            let floor = report.winning_tip.unwrap_or_default();
+            let escalated = match self.escalate(&bundle, rebids + 1, floor).await {
+                Ok(escalated) => escalated,
+                Err(e) => {
+                    warn!("Not rebidding bundle {}: {}", bundle.bundle_hash, e);
+                    self.give_up(&bundle).await;
+                    return Ok(());
+                }
+            };
+            let (signed_txs, reverting_tx_hashes) = match &escalated {
+                Some(rebid) => (&rebid.signed_txs, &rebid.reverting_tx_hashes),
+                None => (&bundle.signed_txs, &bundle.reverting_tx_hashes),
+            };
+
+            match self.submit(signed_txs, reverting_tx_hashes, head + 1).await {
+                Ok((bundle_hash, last_block)) => {
+                    rebids += 1;
+                    if let Some(rebid) = escalated {
+                        let mut replaced = self.replaced.lock().unwrap_or_else(|e| e.into_inner());
+                        for (&first, &latest) in bundle.first_tx_hashes.iter().zip(&rebid.tx_hashes)
+                        {
+                            replaced.insert(first, latest);
+                        }
+                        bundle.signed_txs = rebid.signed_txs;
+                        bundle.tx_hashes = rebid.tx_hashes;
+                        bundle.reverting_tx_hashes = rebid.reverting_tx_hashes;
+                        bundle.priority_fee = rebid.priority_fee;
+                    }
+                    info!(
+                        "Rebid bundle {} as {} for blocks {}-{}",
+                        bundle.bundle_hash,
+                        bundle_hash,
+                        head + 1,
+                        last_block
+                    );
+                    metrics::global().increment_counter("bundle_rebids", 1);
+                    bundle.bundle_hash = bundle_hash;
+                    bundle.first_block = head + 1;
+                    bundle.last_block = last_block;
+                }
+                Err(e) => {
+                    // Usually the opportunity is gone and the bundle would now revert
+                    warn!("Not rebidding bundle {}: {}", bundle.bundle_hash, e);
+                    self.give_up(&bundle).await;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Re-sign a bundle's transactions with the tips of rebid number `rebid`, each at least
+    /// `floor`
+    ///
+    /// Returns `None` when tips are not bid or a transaction is not EIP-1559.
+    async fn escalate(
+        &self,
+        bundle: &SubmittedBundle,
+        rebid: u32,
+        floor: U256,
+    ) -> Result<Option<Rebid>> {
+        let Some((wallet, bid_strategy)) = &self.bidding else {
+            return Ok(None);
+        };
+
+        let mut signed_txs = Vec::with_capacity(bundle.unsigned_txs.len());
+        let mut tx_hashes = Vec::with_capacity(bundle.unsigned_txs.len());
+        let mut reverting_tx_hashes = Vec::new();
+        let mut priority_fee = U256::zero();
+        for (unsigned_tx, previous_hash) in bundle.unsigned_txs.iter().zip(&bundle.tx_hashes) {
+            let Some(typed_tx) = bid_strategy.escalate(unsigned_tx, rebid, floor) else {
+                return Ok(None);
+            };
+            if let TypedTransaction::Eip1559(request) = &typed_tx {
+                priority_fee =
+                    priority_fee.max(request.max_priority_fee_per_gas.unwrap_or_default());
+            }
+            let signature = wallet
+                .sign_transaction(&typed_tx)
+                .await
+                .context("Failed to re-sign a bundle transaction")?;
+            let tx_hash = typed_tx.hash(&signature);
+            if bundle.reverting_tx_hashes.contains(previous_hash) {
+                reverting_tx_hashes.push(tx_hash);
+            }
+            signed_txs.push(typed_tx.rlp_signed(&signature));
+            tx_hashes.push(tx_hash);
+        }
+
+        Ok(Some(Rebid {
+            signed_txs,
+            tx_hashes,
+            reverting_tx_hashes,
+            priority_fee,
+        }))
+    }
+
+    /// Wait until the chain head reaches `block_number`, returning the head
+    async fn wait_for_block(&self, block_number: u64) -> Result<u64> {
+        loop {
+            let head = self.blockchain_client.get_block_number().await?.as_u64();
+            if head >= block_number {
+                return Ok(head);
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Whether the bundle landed, by the relay's status or the first transaction's receipt
+    async fn is_included(&self, bundle: &SubmittedBundle) -> Result<bool> {
+        match self
+            .mev_share_client
+            .get_bundle_status(&bundle.bundle_hash)
+            .await
+        {
+            Ok(status) if BundleInclusion::from_status(&status) == BundleInclusion::Included => {
+                return Ok(true)
+            }
+            Ok(status) => debug!("Bundle {} status: {}", bundle.bundle_hash, status),
+            Err(e) => debug!(
+                "Failed to get status of bundle {}: {}",
+                bundle.bundle_hash, e
+            ),
+        }
+
+        // The relay's status can lag the chain
+        let Some(&tx_hash) = bundle.tx_hashes.first() else {
+            return Ok(false);
+        };
+        Ok(self
+            .blockchain_client
+            .get_transaction_receipt(tx_hash)
+            .await?
+            .is_some())
+    }
+
+    /// Find the block an included bundle landed in and the builder that built it, counting the
+    /// inclusion for the builder
+    async fn reconcile_builder(&self, bundle: &SubmittedBundle) -> Result<()> {
+        let Some(&tx_hash) = bundle.tx_hashes.first() else {
+            return Ok(());
+        };
+        let block_number = self
+            .blockchain_client
+            .get_transaction_receipt(tx_hash)
+            .await?
+            .and_then(|receipt| receipt.block_number)
+            .context("Bundle transaction is not included")?;
+        let block = self
+            .blockchain_client
+            .get_block(block_number)
+            .await?
+            .with_context(|| format!("Block {} not found", block_number))?;
+
+        let builder = self
+            .mev_share_client
+            .builders()
+            .builder_of(&block.extra_data)
+            .unwrap_or("other");
+        info!(
+            "Bundle {} landed in block {} built by {} ({})",
+            bundle.bundle_hash,
+            block_number,
+            builder,
+            String::from_utf8_lossy(&block.extra_data)
+        );
+        metrics::global().increment_counter(&format!("bundles_included_by_{}", builder), 1);
+
+        Ok(())
+    }
+
+    /// Stop following a bundle that will not be included and read the next nonce from the chain
+    async fn give_up(&self, bundle: &SubmittedBundle) {
+        metrics::global().increment_counter("bundles_missed", 1);
+        {
+            let mut replaced = self.replaced.lock().unwrap_or_else(|e| e.into_inner());
+            for tx_hash in &bundle.first_tx_hashes {
+                replaced.remove(tx_hash);
+            }
+        }
+        self.resync_nonce().await;
+    }
+
+    /// Adopt the chain's next nonce after a miss, so later bundles do not build on the missed
+    /// bundle's nonces
+    async fn resync_nonce(&self) {
+        if let Some(tracker) = &self.nonce_tracker {
+            if let Err(e) = tracker.resync(&self.blockchain_client).await {
+                warn!("Failed to resync the wallet nonce: {}", e);
+            }
+        }
+    }
+}
@@ -2,21 +2,48 @@
 //!
 //! This module is responsible for constructing and executing transaction payloads.
 
+mod attribution;
+mod bid;
 mod builder;
+mod bundle;
 mod executor;
-
+mod inclusion;
+mod nonce;
+mod private;
+mod reconcile;
+mod replacement;
+mod token_flow;
+
+pub use attribution::{ProfitAttribution, SettlementCosts};
+pub use bid::BidStrategy;
 pub use builder::{create_builder, TransactionBuilder};
+pub use bundle::{BundleComposer, DEFAULT_MAX_BUNDLE_SIZE};
 pub use executor::{create_executor, TransactionExecutor};
+pub use inclusion::SubmittedBundle;
+pub use nonce::NonceTracker;
+pub use private::{PrivateTxConfig, PrivateTxOptions, PrivateTxSender};
+pub use token_flow::{transfer_topic, TokenFlowInspector, TokenTransfer};
 
 use crate::contract::ContractManager;
+use crate::dex::{dex_names, DexType};
+use crate::latency::Latency;
+use crate::price::PinnedPrices;
+use crate::strategy::StrategyVariant;
 
 use anyhow::Result;
 use ethers::types::{Address, Bytes, TransactionRequest, H256, U256};
 use std::sync::Arc;
+use tracing::Span;
+
+/// Default number of confirmations before a trade's profit counts as realized
+pub const DEFAULT_MIN_CONFIRMATIONS: u64 = 2;
 
 /// Represents an arbitrage transaction
 #[derive(Debug, Clone)]
 pub struct ArbitrageTransaction {
+    /// ID of the opportunity the transaction was built from
+    pub opportunity_id: String,
+
     /// The transaction request
     pub request: TransactionRequest,
 
@@ -26,23 +53,55 @@ pub struct ArbitrageTransaction {
     /// The estimated gas price
     pub estimated_gas_price: U256,
 
-    /// The estimated total cost (gas * gas price)
+    /// The L1 data fee charged on rollups (zero elsewhere)
+    pub l1_data_fee: U256,
+
+    /// The estimated total cost (gas * gas price + L1 data fee)
     pub estimated_cost: U256,
 
     /// The estimated profit
     pub estimated_profit: f64,
 
+    /// Amount borrowed by the flash loan, in the first token of the path
+    pub loan_amount: U256,
+
     /// The token path
     pub token_path: Vec<Address>,
 
-    /// The DEX path
-    pub dex_path: Vec<String>,
+    /// DEX and pool used for each swap, one per consecutive token pair in the path
+    pub hops: Vec<(DexType, Address)>,
 
-    /// The calldata
+    /// The calldata of the request
     pub calldata: Bytes,
 
     /// Whether to use MEV-Share
     pub use_mev_share: bool,
+
+    /// How to send the transaction with `eth_sendPrivateTransaction` when it is not sent as a
+    /// bundle, instead of through MEV-Share or the public mempool
+    pub private: Option<PrivateTxOptions>,
+
+    /// Whether the transaction may revert without dropping the bundle it is part of
+    pub may_revert: bool,
+
+    /// Strategy variant that handled the opportunity
+    pub variant: StrategyVariant,
+
+    /// Oracle prices pinned when the opportunity was evaluated
+    pub pinned_prices: PinnedPrices,
+
+    /// Span of the opportunity the transaction was built from
+    pub span: Span,
+
+    /// Time the opportunity has spent in the pipeline, counted from the start of its scan
+    pub latency: Latency,
+}
+
+impl ArbitrageTransaction {
+    /// Get the name of the DEX used for each swap
+    pub fn dex_path(&self) -> Vec<String> {
+        dex_names(&self.hops)
+    }
 }
 
 /// Represents the result of a transaction execution
@@ -68,15 +127,26 @@ pub struct TransactionResult {
 }
 
 /// Validate a transaction before sending it
+///
+/// These are static checks only; execution is simulated separately by the `simulation` module.
 pub async fn validate_transaction(tx: &ArbitrageTransaction) -> Result<()> {
-    // This is a placeholder implementation
-    // In a real implementation, we would:
-    // 1. Check that the transaction has a valid gas limit
-    // 2. Check that the transaction has a valid gas price
-    // 3. Check that the transaction has a valid nonce
-    // 4. Check that the transaction has a valid to address
-    // 5. Check that the transaction has valid calldata
-
-    // For now, just return Ok
+    if tx.request.to.is_none() {
+        return Err(anyhow::anyhow!("Transaction has no recipient"));
+    }
+
+    if tx.request.gas.unwrap_or(tx.estimated_gas).is_zero() {
+        return Err(anyhow::anyhow!("Transaction has a zero gas limit"));
+    }
+
+    if tx.request.data.as_ref().is_none_or(|data| data.is_empty()) {
+        return Err(anyhow::anyhow!("Transaction has no calldata"));
+    }
+
+    if tx.token_path.len() < 2 {
+        return Err(anyhow::anyhow!(
+            "Transaction token path must contain at least two tokens"
+        ));
+    }
+
     Ok(())
 }
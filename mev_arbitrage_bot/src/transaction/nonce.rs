@@ -0,0 +1,159 @@
+//! Nonce Tracker Module
+//!
+//! This module is responsible for handing out wallet nonces locally, so back-to-back
+//! submissions don't race the node's pending count, and for persisting the next nonce so it
+//! can be reconciled against the chain after a restart.
+//!
+//! Bundles in flight compete for the same blocks and at most one of them lands, so each starts
+//! from the next nonce without reserving it; chaining them would leave every later bundle
+//! unincludable once an earlier one misses. An included bundle moves the next nonce past its
+//! own, and a missed one has the next nonce read from the chain again.
+
+use anyhow::Result;
+use ethers::middleware::Middleware;
+use ethers::types::{Address, BlockNumber, U256};
+use log::{debug, warn};
+use std::sync::{Arc, Mutex};
+
+use crate::blockchain::RpcClient;
+use crate::storage::Storage;
+
+/// Hands out consecutive nonces for the bot's wallet
+pub struct NonceTracker {
+    address: Address,
+    next: Mutex<Option<U256>>,
+    storage: Option<Arc<Storage>>,
+}
+
+impl NonceTracker {
+    /// Create a tracker for a wallet; the first reservation reads the pending nonce from the node
+    pub fn new(address: Address, storage: Option<Arc<Storage>>) -> Self {
+        Self {
+            address,
+            next: Mutex::new(None),
+            storage,
+        }
+    }
+
+    /// Wallet the nonces belong to
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Get the next nonce last recorded to storage, if any
+    pub fn stored(&self) -> Option<U256> {
+        let storage = self.storage.as_ref()?;
+        match storage.wallet_nonce(self.address) {
+            Ok(nonce) => nonce.map(U256::from),
+            Err(e) => {
+                warn!("Failed to load wallet nonce from storage: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Adopt `nonce` as the next nonce to hand out
+    pub fn set(&self, nonce: U256) {
+        *self.next.lock().unwrap_or_else(|e| e.into_inner()) = Some(nonce);
+        self.persist(nonce);
+    }
+
+    /// Reserve `count` consecutive nonces, returning the first
+    pub async fn reserve(&self, client: &RpcClient, count: u64) -> Result<U256> {
+        let current = *self.next.lock().unwrap_or_else(|e| e.into_inner());
+        let first = match current {
+            Some(nonce) => nonce,
+            None => {
+                client
+                    .get_transaction_count(self.address, Some(BlockNumber::Pending.into()))
+                    .await?
+            }
+        };
+
+        let next = {
+            let mut guard = self.next.lock().unwrap_or_else(|e| e.into_inner());
+            // Another reservation may have completed while the node was queried
+            let first = guard.map_or(first, |nonce| nonce.max(first));
+            let next = first + U256::from(count);
+            *guard = Some(next);
+            next
+        };
+        self.persist(next);
+
+        let first = next - U256::from(count);
+        debug!("Reserved nonces {}..{}", first, next);
+
+        Ok(first)
+    }
+
+    /// Get the nonce a bundle starts from: the next nonce, without reserving it
+    pub async fn peek(&self, client: &RpcClient) -> Result<U256> {
+        if let Some(nonce) = *self.next.lock().unwrap_or_else(|e| e.into_inner()) {
+            return Ok(nonce);
+        }
+
+        let pending = client
+            .get_transaction_count(self.address, Some(BlockNumber::Pending.into()))
+            .await?;
+        let mut guard = self.next.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(*guard.get_or_insert(pending))
+    }
+
+    /// Move the next nonce past `count` nonces from `first` that were included on chain
+    pub fn confirm(&self, first: U256, count: u64) {
+        let included = first + U256::from(count);
+        let mut guard = self.next.lock().unwrap_or_else(|e| e.into_inner());
+        if guard.is_none_or(|nonce| nonce < included) {
+            *guard = Some(included);
+            drop(guard);
+            self.persist(included);
+            debug!("Next nonce is {} after an inclusion", included);
+        }
+    }
+
+    /// Adopt the node's pending nonce as the next nonce, e.g. after a bundle missed
+    ///
+    /// Nothing changes if a nonce was reserved or confirmed while the node was queried, since
+    /// the node may not count it yet.
+    pub async fn resync(&self, client: &RpcClient) -> Result<()> {
+        let before = *self.next.lock().unwrap_or_else(|e| e.into_inner());
+        let pending = client
+            .get_transaction_count(self.address, Some(BlockNumber::Pending.into()))
+            .await?;
+
+        let mut guard = self.next.lock().unwrap_or_else(|e| e.into_inner());
+        if *guard != before {
+            return Ok(());
+        }
+        *guard = Some(pending);
+        drop(guard);
+        self.persist(pending);
+        if before != Some(pending) {
+            debug!("Resynced the next nonce from {:?} to {}", before, pending);
+        }
+
+        Ok(())
+    }
+
+    /// Give back nonces that were reserved but never sent
+    ///
+    /// Only the most recent reservation can be returned; otherwise the gap is left for the next
+    /// reconciliation to resolve.
+    pub fn release(&self, first: U256, count: u64) {
+        let mut guard = self.next.lock().unwrap_or_else(|e| e.into_inner());
+        if *guard == Some(first + U256::from(count)) {
+            *guard = Some(first);
+            drop(guard);
+            self.persist(first);
+        }
+    }
+
+    /// Record the next nonce to storage
+    fn persist(&self, next: U256) {
+        if let Some(storage) = &self.storage {
+            if let Err(e) = storage.save_wallet_nonce(self.address, next.as_u64()) {
+                warn!("Failed to record wallet nonce to storage: {}", e);
+            }
+        }
+    }
+}
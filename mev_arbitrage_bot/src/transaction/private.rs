@@ -0,0 +1,200 @@
+//! Private Transaction Module
+//!
+//! This module is responsible for sending transactions with `eth_sendPrivateTransaction`, as
+//! Alchemy and Flashbots Protect accept it: the transaction skips the public mempool, where it
+//! could be front-run, without the all-or-nothing inclusion of a bundle. It is a middle ground
+//! for transactions not sent as bundles, when MEV-Share is disabled or `bundle_execution` is off.
+//! In `fast` mode the transaction is shared with every builder at once for quicker inclusion.
+
+use anyhow::{Context, Result};
+use ethers::types::{Bytes, H256};
+use reqwest::{header, Client};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::blockchain;
+use crate::config::{Config, ConfigSection, HttpEndpointConfig};
+use crate::mev_share::{BundleSigner, FLASHBOTS_SIGNATURE_HEADER};
+
+/// Default number of blocks a private transaction may be included in
+pub const DEFAULT_PRIVATE_TX_MAX_BLOCKS: u64 = 25;
+
+/// Private transaction configuration (`[private_tx]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrivateTxConfig {
+    /// Whether transactions not sent as bundles are sent with `eth_sendPrivateTransaction`
+    pub enabled: bool,
+
+    /// Endpoint accepting `eth_sendPrivateTransaction` (defaults to `ethereum.rpc_url`)
+    pub url: Option<String>,
+
+    /// Whether the transaction is shared with every builder at once
+    pub fast: bool,
+
+    /// Blocks after submission the transaction may be included in before it is dropped
+    pub max_blocks: u64,
+}
+
+impl Default for PrivateTxConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: None,
+            fast: true,
+            max_blocks: DEFAULT_PRIVATE_TX_MAX_BLOCKS,
+        }
+    }
+}
+
+impl ConfigSection for PrivateTxConfig {
+    const NAME: &'static str = "private_tx";
+
+    fn validate(&self) -> Result<()> {
+        if self.max_blocks == 0 {
+            anyhow::bail!("Private transaction max_blocks must be greater than zero");
+        }
+        if let Some(url) = &self.url {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                anyhow::bail!("Private transaction url must start with http:// or https://");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl PrivateTxConfig {
+    /// Get the options new transactions are sent privately with, or `None` when disabled
+    pub fn options(&self) -> Option<PrivateTxOptions> {
+        self.enabled.then_some(PrivateTxOptions {
+            fast: self.fast,
+            max_blocks: self.max_blocks,
+        })
+    }
+}
+
+/// How one transaction is sent privately
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrivateTxOptions {
+    /// Whether the transaction is shared with every builder at once
+    pub fast: bool,
+
+    /// Blocks after submission the transaction may be included in
+    pub max_blocks: u64,
+}
+
+/// Preferences of `eth_sendPrivateTransaction`
+#[derive(Debug, Clone, Serialize)]
+struct Preferences {
+    fast: bool,
+}
+
+/// Parameters of `eth_sendPrivateTransaction`
+#[derive(Debug, Clone, Serialize)]
+struct SendPrivateTransactionParams<'a> {
+    tx: &'a Bytes,
+
+    #[serde(rename = "maxBlockNumber")]
+    max_block_number: String,
+
+    preferences: Preferences,
+}
+
+/// JSON-RPC request
+#[derive(Debug, Clone, Serialize)]
+struct RpcRequest<'a> {
+    jsonrpc: &'static str,
+    id: u64,
+    method: &'static str,
+    params: [SendPrivateTransactionParams<'a>; 1],
+}
+
+/// JSON-RPC response
+#[derive(Debug, Clone, Deserialize)]
+struct RpcResponse {
+    result: Option<H256>,
+    error: Option<RpcError>,
+}
+
+/// JSON-RPC error
+#[derive(Debug, Clone, Deserialize)]
+struct RpcError {
+    message: String,
+}
+
+/// Sends signed transactions with `eth_sendPrivateTransaction`
+pub struct PrivateTxSender {
+    http_client: Client,
+    url: String,
+    signer: BundleSigner,
+}
+
+impl PrivateTxSender {
+    /// Create a sender to the configured endpoint, signing requests with the MEV-Share signing
+    /// key (or an ephemeral one)
+    ///
+    /// Without a `url`, transactions go to `ethereum.rpc_url` with its HTTP settings.
+    pub fn new(config: &Config, settings: &PrivateTxConfig) -> Result<Self> {
+        let (url, endpoint) = match &settings.url {
+            Some(url) => (url.clone(), HttpEndpointConfig::default()),
+            None => (
+                config.ethereum.rpc_url.clone(),
+                config.ethereum.rpc_http.clone(),
+            ),
+        };
+        let signer = match &config.mev_share.signing_key {
+            Some(signing_key) => BundleSigner::from_key(signing_key)?,
+            None => BundleSigner::random(),
+        };
+
+        Ok(Self {
+            http_client: blockchain::http_client(&endpoint, Some(Duration::from_secs(10)))?,
+            url,
+            signer,
+        })
+    }
+
+    /// Send a signed transaction for inclusion by block `current_block + max_blocks`,
+    /// returning its hash
+    pub async fn send(
+        &self,
+        signed_tx: &Bytes,
+        options: PrivateTxOptions,
+        current_block: u64,
+    ) -> Result<H256> {
+        let request = RpcRequest {
+            jsonrpc: "2.0",
+            id: 1,
+            method: "eth_sendPrivateTransaction",
+            params: [SendPrivateTransactionParams {
+                tx: signed_tx,
+                max_block_number: format!("0x{:x}", current_block + options.max_blocks),
+                preferences: Preferences { fast: options.fast },
+            }],
+        };
+
+        // The signature covers the exact bytes sent, so serialize the body ourselves
+        let body = serde_json::to_vec(&request).context("Failed to serialize transaction")?;
+        let signature = self.signer.sign_payload(&body).await?;
+
+        let response = self
+            .http_client
+            .post(&self.url)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(FLASHBOTS_SIGNATURE_HEADER, signature)
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<RpcResponse>()
+            .await?;
+
+        if let Some(error) = response.error {
+            anyhow::bail!("Private transaction rejected: {}", error.message);
+        }
+        response
+            .result
+            .context("Private transaction endpoint returned no hash")
+    }
+}
@@ -0,0 +1,210 @@
+//! Wallet Reconciliation Module
+//!
+//! This module is responsible for bringing the local view of the wallet back in line with the
+//! chain on startup: adopting the correct next nonce, settling trades left submitted by a
+//! crash, checking the balance and surfacing outgoing transactions the bot did not send.
+
+use anyhow::Result;
+use ethers::middleware::Middleware;
+use ethers::types::{BlockNumber, H256, U256};
+use futures::future::join_all;
+use log::{info, warn};
+use std::sync::Arc;
+
+use crate::alerts::AlertManager;
+use crate::blockchain::RpcClient;
+use crate::config::{AlertSeverity, Config};
+use crate::metrics;
+use crate::storage::Storage;
+use crate::transaction::{NonceTracker, TransactionExecutor};
+use crate::utils::u256_to_decimal;
+
+/// Source name used for reconciliation alerts
+const ALERT_SOURCE: &str = "wallet";
+
+/// Number of blocks fetched concurrently while searching for unknown transactions
+const BLOCK_FETCH_CONCURRENCY: u64 = 10;
+
+/// Reconcile the nonce tracker, stored trades and balance of the wallet against the chain
+pub async fn reconcile_wallet(
+    config: &Config,
+    blockchain_client: &Arc<RpcClient>,
+    nonce_tracker: &NonceTracker,
+    executor: &dyn TransactionExecutor,
+    storage: &Option<Arc<Storage>>,
+    alerts: &AlertManager,
+) -> Result<()> {
+    let address = nonce_tracker.address();
+
+    let latest_nonce = blockchain_client
+        .get_transaction_count(address, Some(BlockNumber::Latest.into()))
+        .await?;
+    let pending_nonce = blockchain_client
+        .get_transaction_count(address, Some(BlockNumber::Pending.into()))
+        .await?;
+    if pending_nonce > latest_nonce {
+        info!(
+            "{} wallet transactions are still pending in the mempool",
+            pending_nonce - latest_nonce
+        );
+    }
+
+    match nonce_tracker.stored() {
+        Some(local_nonce) if local_nonce > pending_nonce => alerts.raise(
+            AlertSeverity::Warning,
+            ALERT_SOURCE,
+            format!(
+                "Local nonce {} is ahead of the chain ({}): {} reserved transactions never \
+                 reached the chain",
+                local_nonce,
+                pending_nonce,
+                local_nonce - pending_nonce
+            ),
+        ),
+        Some(local_nonce) if local_nonce < latest_nonce => alerts.raise(
+            AlertSeverity::Warning,
+            ALERT_SOURCE,
+            format!(
+                "Chain nonce {} is ahead of the local nonce {}: the wallet sent transactions \
+                 the bot did not record",
+                latest_nonce, local_nonce
+            ),
+        ),
+        Some(local_nonce) => info!("Local nonce {} matches the chain", local_nonce),
+        None => info!("No local nonce recorded for {:?}", address),
+    }
+
+    // The chain (including the mempool) is authoritative
+    nonce_tracker.set(pending_nonce);
+    metrics::global().set_gauge("wallet_nonce", pending_nonce.as_u64() as f64);
+    info!("Adopted next nonce {} for {:?}", pending_nonce, address);
+
+    if let Some(storage) = storage {
+        settle_submitted_trades(blockchain_client, executor, storage).await?;
+        find_unknown_transactions(config, blockchain_client, nonce_tracker, storage, alerts)
+            .await?;
+    } else {
+        info!("Trade history storage disabled; skipping search for unknown transactions");
+    }
+
+    let balance = blockchain_client.get_balance(address, None).await?;
+    check_balance(config, balance, alerts);
+
+    Ok(())
+}
+
+/// Record the outcome of trades that were submitted before the last shutdown
+async fn settle_submitted_trades(
+    blockchain_client: &Arc<RpcClient>,
+    executor: &dyn TransactionExecutor,
+    storage: &Storage,
+) -> Result<()> {
+    let trades = storage.submitted_trades()?;
+    if trades.is_empty() {
+        return Ok(());
+    }
+
+    info!("Settling {} trades left submitted", trades.len());
+    for trade in trades {
+        // Bundled trades have no transaction hash to look up
+        let Some(tx_hash) = trade.tx_hash else {
+            continue;
+        };
+
+        let status = executor.get_transaction_status(tx_hash).await?;
+        if status.block_number.is_some() {
+            info!(
+                "Trade {} ({}) was included in block {}",
+                trade.id,
+                trade.token_symbols,
+                status.block_number.unwrap_or_default()
+            );
+            storage.record_result(&status, None)?;
+        } else if blockchain_client.get_transaction(tx_hash).await?.is_none() {
+            warn!(
+                "Trade {} ({}) was dropped before inclusion: {:?}",
+                trade.id, trade.token_symbols, tx_hash
+            );
+            storage.mark_failed(trade.id, "Dropped before inclusion")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Alert on recent outgoing wallet transactions that are not in the trade history
+async fn find_unknown_transactions(
+    config: &Config,
+    blockchain_client: &Arc<RpcClient>,
+    nonce_tracker: &NonceTracker,
+    storage: &Storage,
+    alerts: &AlertManager,
+) -> Result<()> {
+    let address = nonce_tracker.address();
+    let latest_block = blockchain_client.get_block_number().await?.as_u64();
+    let first_block = latest_block.saturating_sub(config.wallet.reconcile_lookback_blocks) + 1;
+
+    let mut unknown: Vec<(u64, H256)> = Vec::new();
+    let mut block_number = first_block;
+    while block_number <= latest_block {
+        let last = (block_number + BLOCK_FETCH_CONCURRENCY - 1).min(latest_block);
+        let blocks = join_all(
+            (block_number..=last).map(|number| blockchain_client.get_block_with_txs(number)),
+        )
+        .await;
+
+        for block in blocks.into_iter().flatten().flatten() {
+            for tx in block.transactions.iter().filter(|tx| tx.from == address) {
+                if storage.trade_by_tx_hash(tx.hash)?.is_none() {
+                    unknown.push((tx.nonce.as_u64(), tx.hash));
+                }
+            }
+        }
+
+        block_number = last + 1;
+    }
+
+    if unknown.is_empty() {
+        info!(
+            "No unknown wallet transactions in blocks {}-{}",
+            first_block, latest_block
+        );
+        return Ok(());
+    }
+
+    alerts.raise(
+        AlertSeverity::Warning,
+        ALERT_SOURCE,
+        format!(
+            "{} outgoing wallet transactions in blocks {}-{} were not sent by the bot: {}",
+            unknown.len(),
+            first_block,
+            latest_block,
+            unknown
+                .iter()
+                .map(|(nonce, hash)| format!("{:?} (nonce {})", hash, nonce))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    );
+
+    Ok(())
+}
+
+/// Export the wallet balance and alert if it is too low to pay for gas
+fn check_balance(config: &Config, balance: U256, alerts: &AlertManager) {
+    let balance_eth = u256_to_decimal(balance, 18);
+    metrics::global().set_gauge("wallet_balance_eth", balance_eth);
+    info!("Wallet balance: {:.4} ETH", balance_eth);
+
+    if balance_eth < config.wallet.min_balance_eth {
+        alerts.raise(
+            AlertSeverity::Warning,
+            ALERT_SOURCE,
+            format!(
+                "Wallet balance {:.4} ETH is below the minimum of {} ETH",
+                balance_eth, config.wallet.min_balance_eth
+            ),
+        );
+    }
+}
@@ -0,0 +1,94 @@
+//! Replacement Tracker Module
+//!
+//! This module is responsible for following the replacement chains of directly sent
+//! transactions. A transaction that lingers in the mempool is rebroadcast with the same nonce
+//! at a bumped fee; until one of the chain is included any of them may be, so the status of a
+//! transaction is read across its whole chain. Every replacement is recorded to storage.
+
+use ethers::types::{H256, U256};
+use log::warn;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::storage::Storage;
+
+/// Replacements sent for one transaction when `max_replacements` is not configured
+pub const DEFAULT_MAX_REPLACEMENTS: u32 = 3;
+
+/// Percentage the fees of a replacement are raised by at least; nodes reject replacements that
+/// raise them by less than 10%
+pub const REPLACEMENT_FEE_BUMP_PERCENT: u64 = 120;
+
+/// Follows the replacement chains of directly sent transactions
+pub struct ReplacementTracker {
+    storage: Option<Arc<Storage>>,
+
+    /// Hashes of each chain, oldest first, keyed by the first hash
+    chains: Mutex<HashMap<H256, Vec<H256>>>,
+}
+
+impl ReplacementTracker {
+    /// Create a tracker recording replacements to `storage`
+    pub fn new(storage: Option<Arc<Storage>>) -> Self {
+        Self {
+            storage,
+            chains: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Start following a transaction sent directly, which makes it replaceable
+    pub fn watch(&self, tx_hash: H256) {
+        self.chains
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(tx_hash)
+            .or_insert_with(|| vec![tx_hash]);
+    }
+
+    /// Get the chain a transaction belongs to, oldest first, or `None` if it is not followed
+    pub fn chain(&self, tx_hash: H256) -> Option<Vec<H256>> {
+        let chains = self.chains.lock().unwrap_or_else(|e| e.into_inner());
+        chains
+            .get(&tx_hash)
+            .or_else(|| chains.values().find(|chain| chain.contains(&tx_hash)))
+            .cloned()
+    }
+
+    /// Get the number of replacements sent for a transaction
+    pub fn replacements(&self, tx_hash: H256) -> u32 {
+        self.chain(tx_hash)
+            .map_or(0, |chain| chain.len().saturating_sub(1) as u32)
+    }
+
+    /// Record that `replacement` replaced `tx_hash` with nonce `nonce` at `fee_per_gas` (in wei)
+    pub fn record(&self, tx_hash: H256, replacement: H256, nonce: U256, fee_per_gas: U256) {
+        let first = {
+            let mut chains = self.chains.lock().unwrap_or_else(|e| e.into_inner());
+            let first = chains
+                .iter()
+                .find(|(_, chain)| chain.contains(&tx_hash))
+                .map_or(tx_hash, |(&first, _)| first);
+            chains
+                .entry(first)
+                .or_insert_with(|| vec![first])
+                .push(replacement);
+            first
+        };
+
+        if let Some(storage) = &self.storage {
+            if let Err(e) =
+                storage.record_replacement(first, tx_hash, replacement, nonce, fee_per_gas)
+            {
+                warn!("Failed to record replacement of {:?}: {}", tx_hash, e);
+            }
+        }
+    }
+
+    /// Stop following the chain a transaction belongs to, once one of it is included
+    pub fn forget(&self, tx_hash: H256) {
+        self.chains
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|_, chain| !chain.contains(&tx_hash));
+    }
+}
@@ -0,0 +1,264 @@
+//! Token Flow Inspection Module
+//!
+//! This module is responsible for inspecting the token transfers produced by a simulated
+//! transaction and flagging value that flows to unexpected addresses.
+
+use anyhow::{Context, Result};
+use ethers::providers::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{
+    Address, BlockId, BlockNumber, CallConfig, CallFrame, GethDebugBuiltInTracerConfig,
+    GethDebugBuiltInTracerType, GethDebugTracerConfig, GethDebugTracerType,
+    GethDebugTracingCallOptions, GethDebugTracingOptions, GethTrace, GethTraceFrame, NameOrAddress,
+    TransactionRequest, H256, I256, U256,
+};
+use log::debug;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::blockchain::RpcClient;
+use crate::config::Config;
+use crate::tokens;
+use crate::utils::validate_and_parse_address;
+
+/// A single ERC20 transfer observed in a simulated transaction
+#[derive(Debug, Clone)]
+pub struct TokenTransfer {
+    /// Token contract that emitted the Transfer event
+    pub token: Address,
+
+    /// Sender of the tokens
+    pub from: Address,
+
+    /// Recipient of the tokens
+    pub to: Address,
+
+    /// Amount transferred
+    pub amount: U256,
+}
+
+/// Result of inspecting the token flows of a simulated transaction
+#[derive(Debug, Clone, Default)]
+pub struct TokenFlowReport {
+    /// All transfers observed, in execution order
+    pub transfers: Vec<TokenTransfer>,
+
+    /// Net balance change per token and holder
+    pub net_flows: HashMap<Address, HashMap<Address, I256>>,
+
+    /// Transfers whose recipient is not an expected participant
+    pub unexpected_transfers: Vec<TokenTransfer>,
+
+    /// Revert reason, if the simulated call failed
+    pub revert_reason: Option<String>,
+}
+
+impl TokenFlowReport {
+    /// Whether the simulated transaction succeeded and only moved tokens between expected parties
+    pub fn is_clean(&self) -> bool {
+        self.revert_reason.is_none() && self.unexpected_transfers.is_empty()
+    }
+
+    /// Get the net balance change of a holder for a token
+    pub fn net_flow(&self, token: Address, holder: Address) -> I256 {
+        self.net_flows
+            .get(&token)
+            .and_then(|holders| holders.get(&holder))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Describe the unexpected transfers for logging
+    pub fn describe_unexpected(&self) -> String {
+        self.unexpected_transfers
+            .iter()
+            .map(|transfer| {
+                format!(
+                    "{} of token {} from {:?} to {:?}",
+                    transfer.amount,
+                    tokens::token_label(transfer.token),
+                    transfer.from,
+                    transfer.to
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// Inspects simulated transactions for token flows to unexpected addresses
+pub struct TokenFlowInspector {
+    blockchain_client: Arc<RpcClient>,
+    expected_addresses: HashSet<Address>,
+}
+
+impl TokenFlowInspector {
+    /// Create a new inspector that trusts the wallet, lending pool, routers and configured pools
+    pub fn new(config: &Arc<Config>, blockchain_client: Arc<RpcClient>) -> Self {
+        let mut configured = vec![
+            config.ethereum.wallet_address.clone(),
+            config.flash_loan.aave_lending_pool.clone(),
+        ];
+
+        for dex in [
+            &config.dex.uniswap,
+            &config.dex.sushiswap,
+            &config.dex.curve,
+        ] {
+            configured.push(dex.router_address.clone());
+            configured.extend(dex.pools.iter().cloned());
+        }
+
+        if let Some(contract_address) = &config.arbitrage.contract.contract_address {
+            configured.push(contract_address.clone());
+        }
+
+        configured.extend(config.security.allowed_token_recipients.iter().cloned());
+
+        let expected_addresses = configured
+            .iter()
+            .filter_map(|address| validate_and_parse_address(address).ok())
+            .collect();
+
+        Self {
+            blockchain_client,
+            expected_addresses,
+        }
+    }
+
+    /// Simulate a transaction against the latest block and inspect its token flows
+    pub async fn inspect(&self, request: &TransactionRequest) -> Result<TokenFlowReport> {
+        let root = self.trace_call(request).await?;
+
+        // Contracts our transaction calls into (pools, routers, executor) may receive tokens,
+        // but token contracts themselves must never end up holding tokens
+        let mut call_targets = HashSet::new();
+        let mut transfers = Vec::new();
+        collect_frame(&root, &mut call_targets, &mut transfers);
+
+        let token_contracts: HashSet<Address> = transfers.iter().map(|t| t.token).collect();
+
+        let mut expected = self.expected_addresses.clone();
+        if let Some(from) = request.from {
+            expected.insert(from);
+        }
+        if let Some(NameOrAddress::Address(to)) = request.to {
+            expected.insert(to);
+        }
+        expected.extend(
+            call_targets
+                .into_iter()
+                .filter(|target| !token_contracts.contains(target)),
+        );
+
+        let mut report = TokenFlowReport {
+            revert_reason: root.error.clone(),
+            ..Default::default()
+        };
+
+        for transfer in transfers {
+            let amount = I256::from_raw(transfer.amount);
+            let holders = report.net_flows.entry(transfer.token).or_default();
+            *holders.entry(transfer.from).or_default() -= amount;
+            *holders.entry(transfer.to).or_default() += amount;
+
+            if !expected.contains(&transfer.to) {
+                report.unexpected_transfers.push(transfer.clone());
+            }
+            report.transfers.push(transfer);
+        }
+
+        debug!(
+            "Inspected {} token transfers ({} unexpected)",
+            report.transfers.len(),
+            report.unexpected_transfers.len()
+        );
+
+        Ok(report)
+    }
+
+    /// Run `debug_traceCall` with the call tracer and logs enabled
+    async fn trace_call(&self, request: &TransactionRequest) -> Result<CallFrame> {
+        let options = GethDebugTracingCallOptions {
+            tracing_options: GethDebugTracingOptions {
+                tracer: Some(GethDebugTracerType::BuiltInTracer(
+                    GethDebugBuiltInTracerType::CallTracer,
+                )),
+                tracer_config: Some(GethDebugTracerConfig::BuiltInTracer(
+                    GethDebugBuiltInTracerConfig::CallTracer(CallConfig {
+                        only_top_call: Some(false),
+                        with_log: Some(true),
+                    }),
+                )),
+                ..Default::default()
+            },
+            state_overrides: None,
+            block_overrides: None,
+        };
+
+        let typed_tx: TypedTransaction = request.clone().into();
+        let trace = self
+            .blockchain_client
+            .debug_trace_call(
+                typed_tx,
+                Some(BlockId::Number(BlockNumber::Latest)),
+                options,
+            )
+            .await
+            .context("Failed to trace transaction (debug_traceCall)")?;
+
+        match trace {
+            GethTrace::Known(GethTraceFrame::CallTracer(frame)) => Ok(frame),
+            _ => Err(anyhow::anyhow!(
+                "Unexpected trace format from debug_traceCall"
+            )),
+        }
+    }
+}
+
+/// Topic of the ERC20 `Transfer(address,address,uint256)` event
+pub fn transfer_topic() -> H256 {
+    H256::from(ethers::utils::keccak256(
+        b"Transfer(address,address,uint256)",
+    ))
+}
+
+/// Recursively collect call targets and ERC20 transfers from a call frame
+fn collect_frame(
+    frame: &CallFrame,
+    call_targets: &mut HashSet<Address>,
+    transfers: &mut Vec<TokenTransfer>,
+) {
+    // Logs of reverted frames never took effect
+    if frame.error.is_some() {
+        return;
+    }
+
+    if let Some(NameOrAddress::Address(to)) = frame.to {
+        call_targets.insert(to);
+    }
+
+    let topic = transfer_topic();
+    for log in frame.logs.iter().flatten() {
+        let (token, topics, data) = match (&log.address, &log.topics, &log.data) {
+            (Some(token), Some(topics), Some(data)) => (*token, topics, data),
+            _ => continue,
+        };
+
+        // ERC721 transfers index the token id instead of carrying an amount
+        if topics.len() != 3 || topics[0] != topic || data.len() != 32 {
+            continue;
+        }
+
+        transfers.push(TokenTransfer {
+            token,
+            from: Address::from(topics[1]),
+            to: Address::from(topics[2]),
+            amount: U256::from_big_endian(data),
+        });
+    }
+
+    for call in frame.calls.iter().flatten() {
+        collect_frame(call, call_targets, transfers);
+    }
+}
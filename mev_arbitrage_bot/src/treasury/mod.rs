@@ -0,0 +1,388 @@
+//! Treasury Module
+//!
+//! This module is responsible for watching the bot wallet's balances while the bot runs. The
+//! ETH balance and the balances of the configured flash loan tokens are checked periodically
+//! and exported as gauges. Below the gas reserve the bot stops submitting transactions, as a
+//! transaction the wallet cannot pay for would only fail, and alerts until it is topped up.
+//!
+//! With a sweep address configured, balances above their sweep target are transferred there, so
+//! accumulated profits do not sit in the hot wallet. Only the wallet is swept; tokens held by
+//! the ArbitrageExecutor contract are recovered with its owner functions.
+
+use anyhow::{Context, Result};
+use ethers::middleware::Middleware;
+use ethers::types::{Address, TransactionRequest, U256};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::alerts::AlertManager;
+use crate::assets::ContractAbi;
+use crate::blockchain::AlchemyProvider;
+use crate::config::{AlertSeverity, Config, ConfigSection};
+use crate::metrics;
+use crate::transaction::TransactionExecutor;
+use crate::utils::{decimal_to_u256, u256_to_decimal, validate_and_parse_address};
+
+/// Default interval between balance checks (in seconds)
+pub const DEFAULT_INTERVAL_SECS: u64 = 60;
+
+/// Default ETH balance below which no transactions are submitted
+pub const DEFAULT_MIN_GAS_BALANCE_ETH: f64 = 0.01;
+
+/// Key of the ETH balance in `sweep_targets`
+const ETH: &str = "ETH";
+
+/// Share of its target a balance must exceed it by to be swept, so profits are swept in
+/// batches rather than on every check
+const SWEEP_MARGIN: f64 = 0.1;
+
+/// Time a sweep is followed for before the next check
+const SWEEP_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Alert source of the treasury
+const ALERT_SOURCE: &str = "treasury";
+
+/// Wallet balance monitoring configuration (`[treasury]`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TreasuryConfig {
+    /// Whether the wallet's balances are monitored while the bot runs
+    pub enabled: bool,
+
+    /// Interval between balance checks (in seconds, defaults to 60)
+    pub interval_secs: Option<u64>,
+
+    /// ETH balance below which no transactions are submitted (defaults to 0.01)
+    pub min_gas_balance_eth: Option<f64>,
+
+    /// Cold address balances above their sweep target are transferred to
+    pub sweep_address: Option<String>,
+
+    /// Balance kept in the wallet per token symbol (`ETH` for ether), in token units
+    pub sweep_targets: BTreeMap<String, f64>,
+}
+
+impl ConfigSection for TreasuryConfig {
+    const NAME: &'static str = "treasury";
+
+    fn validate(&self) -> Result<()> {
+        if self.interval_secs == Some(0) {
+            anyhow::bail!("Treasury interval_secs must be greater than zero");
+        }
+
+        let min_gas_balance_eth = self
+            .min_gas_balance_eth
+            .unwrap_or(DEFAULT_MIN_GAS_BALANCE_ETH);
+        if min_gas_balance_eth < 0.0 {
+            anyhow::bail!("Treasury min_gas_balance_eth must not be negative");
+        }
+
+        match &self.sweep_address {
+            Some(address) => {
+                validate_and_parse_address(address).context("Invalid treasury sweep_address")?;
+            }
+            None if !self.sweep_targets.is_empty() => {
+                anyhow::bail!("Treasury sweep_targets need a sweep_address");
+            }
+            None => {}
+        }
+        for (symbol, target) in &self.sweep_targets {
+            if *target < 0.0 {
+                anyhow::bail!("Treasury sweep target for {} must not be negative", symbol);
+            }
+        }
+        if self
+            .sweep_targets
+            .get(ETH)
+            .is_some_and(|target| *target < min_gas_balance_eth)
+        {
+            anyhow::bail!("Treasury ETH sweep target must not be below min_gas_balance_eth");
+        }
+
+        Ok(())
+    }
+}
+
+/// A token whose wallet balance is monitored
+#[derive(Debug, Clone)]
+struct MonitoredToken {
+    address: Address,
+    symbol: String,
+    decimals: u8,
+
+    /// Balance kept in the wallet when sweeping
+    sweep_target: Option<U256>,
+}
+
+/// Monitor of the bot wallet's balances
+pub struct Treasury {
+    alchemy_provider: Arc<AlchemyProvider>,
+    tx_executor: Arc<dyn TransactionExecutor>,
+    alert_manager: Arc<AlertManager>,
+    wallet: Address,
+    interval: Duration,
+    tokens: Vec<MonitoredToken>,
+
+    /// Balance below which a warning is raised (in ETH)
+    min_balance_eth: f64,
+
+    /// Balance below which no transactions are submitted (in ETH)
+    min_gas_balance_eth: f64,
+
+    sweep_address: Option<Address>,
+
+    /// ETH kept in the wallet when sweeping
+    eth_sweep_target: Option<U256>,
+
+    gas_low: AtomicBool,
+    balance_low: AtomicBool,
+}
+
+/// Create a new wallet balance monitor
+pub fn create_treasury(
+    config: &Arc<Config>,
+    treasury_config: &TreasuryConfig,
+    alchemy_provider: Arc<AlchemyProvider>,
+    tx_executor: Arc<dyn TransactionExecutor>,
+    alert_manager: Arc<AlertManager>,
+) -> Result<Arc<Treasury>> {
+    let wallet = validate_and_parse_address(&config.ethereum.wallet_address)
+        .context("Wallet balance monitoring needs the wallet address")?;
+
+    let mut tokens = Vec::new();
+    for token in &config.flash_loan.tokens {
+        tokens.push(MonitoredToken {
+            address: validate_and_parse_address(&token.address)?,
+            symbol: token.symbol.clone(),
+            decimals: token.decimals,
+            sweep_target: treasury_config
+                .sweep_targets
+                .get(&token.symbol)
+                .map(|target| decimal_to_u256(*target, token.decimals)),
+        });
+    }
+    for symbol in treasury_config.sweep_targets.keys() {
+        if symbol != ETH && !tokens.iter().any(|token| &token.symbol == symbol) {
+            warn!(
+                "Treasury sweep target for {} ignored: not a configured flash loan token",
+                symbol
+            );
+        }
+    }
+
+    Ok(Arc::new(Treasury {
+        alchemy_provider,
+        tx_executor,
+        alert_manager,
+        wallet,
+        interval: Duration::from_secs(
+            treasury_config
+                .interval_secs
+                .unwrap_or(DEFAULT_INTERVAL_SECS),
+        ),
+        tokens,
+        min_balance_eth: config.wallet.min_balance_eth,
+        min_gas_balance_eth: treasury_config
+            .min_gas_balance_eth
+            .unwrap_or(DEFAULT_MIN_GAS_BALANCE_ETH),
+        sweep_address: treasury_config
+            .sweep_address
+            .as_deref()
+            .map(validate_and_parse_address)
+            .transpose()?,
+        eth_sweep_target: treasury_config
+            .sweep_targets
+            .get(ETH)
+            .map(|target| decimal_to_u256(*target, 18)),
+        gas_low: AtomicBool::new(false),
+        balance_low: AtomicBool::new(false),
+    }))
+}
+
+impl Treasury {
+    /// Check the balances at every interval until shutdown
+    pub async fn run(self: Arc<Self>, shutdown: CancellationToken) {
+        while !shutdown.is_cancelled() {
+            if let Some(Err(e)) = shutdown.run_until_cancelled(self.check()).await {
+                warn!("Wallet balance check failed: {:#}", e);
+            }
+
+            shutdown
+                .run_until_cancelled(tokio::time::sleep(self.interval))
+                .await;
+        }
+    }
+
+    /// Why transactions may not be submitted, if they may not
+    pub fn execution_blocked(&self) -> Option<String> {
+        self.gas_low.load(Ordering::Relaxed).then(|| {
+            format!(
+                "wallet balance below the gas reserve of {} ETH",
+                self.min_gas_balance_eth
+            )
+        })
+    }
+
+    /// Check the balances once, then sweep the ones above their target
+    async fn check(&self) -> Result<()> {
+        let balance = self
+            .alchemy_provider
+            .http()
+            .get_balance(self.wallet, None)
+            .await
+            .context("Failed to read the wallet balance")?;
+        self.check_gas_balance(u256_to_decimal(balance, 18));
+
+        let token_balances = if self.tokens.is_empty() {
+            HashMap::new()
+        } else {
+            self.alchemy_provider
+                .get_token_balances(
+                    self.wallet,
+                    self.tokens.iter().map(|token| token.address).collect(),
+                )
+                .await
+                .context("Failed to read the wallet token balances")?
+        };
+        for token in &self.tokens {
+            let balance = token_balances
+                .get(&token.address)
+                .copied()
+                .unwrap_or_default();
+            let balance = u256_to_decimal(balance, token.decimals);
+            metrics::global().set_gauge(
+                &format!("wallet_balance_{}", token.symbol.to_lowercase()),
+                balance,
+            );
+            debug!("Wallet balance: {:.4} {}", balance, token.symbol);
+        }
+
+        let Some(sweep_address) = self.sweep_address else {
+            return Ok(());
+        };
+        if let Some(target) = self.eth_sweep_target {
+            if let Some(excess) = sweepable(balance, target) {
+                let request = TransactionRequest::new().to(sweep_address).value(excess);
+                self.sweep(request, format!("{:.4} ETH", u256_to_decimal(excess, 18)))
+                    .await?;
+            }
+        }
+        for token in &self.tokens {
+            let balance = token_balances
+                .get(&token.address)
+                .copied()
+                .unwrap_or_default();
+            let Some(excess) = token
+                .sweep_target
+                .and_then(|target| sweepable(balance, target))
+            else {
+                continue;
+            };
+            let data = ContractAbi::Erc20
+                .contract()
+                .encode("transfer", (sweep_address, excess))?;
+            let request = TransactionRequest::new().to(token.address).data(data);
+            self.sweep(
+                request,
+                format!(
+                    "{:.4} {}",
+                    u256_to_decimal(excess, token.decimals),
+                    token.symbol
+                ),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Export the ETH balance, and refuse or resume submissions as it crosses the gas reserve
+    fn check_gas_balance(&self, balance_eth: f64) {
+        metrics::global().set_gauge("wallet_balance_eth", balance_eth);
+        debug!("Wallet balance: {:.4} ETH", balance_eth);
+
+        let gas_low = balance_eth < self.min_gas_balance_eth;
+        metrics::global().set_gauge("wallet_gas_low", if gas_low { 1.0 } else { 0.0 });
+        if gas_low && !self.gas_low.swap(true, Ordering::Relaxed) {
+            self.alert_manager.raise(
+                AlertSeverity::Critical,
+                ALERT_SOURCE,
+                format!(
+                    "Wallet balance {:.4} ETH is below the gas reserve of {} ETH; no transactions \
+                     are submitted until it is topped up",
+                    balance_eth, self.min_gas_balance_eth
+                ),
+            );
+        } else if !gas_low && self.gas_low.swap(false, Ordering::Relaxed) {
+            self.alert_manager.raise(
+                AlertSeverity::Info,
+                ALERT_SOURCE,
+                format!(
+                    "Wallet balance {:.4} ETH is back above the gas reserve; submissions resume",
+                    balance_eth
+                ),
+            );
+        }
+
+        let balance_low = balance_eth < self.min_balance_eth;
+        if balance_low && !self.balance_low.swap(true, Ordering::Relaxed) && !gas_low {
+            self.alert_manager.raise(
+                AlertSeverity::Warning,
+                ALERT_SOURCE,
+                format!(
+                    "Wallet balance {:.4} ETH is below the minimum of {} ETH",
+                    balance_eth, self.min_balance_eth
+                ),
+            );
+        } else if !balance_low {
+            self.balance_low.store(false, Ordering::Relaxed);
+        }
+    }
+
+    /// Send a sweep transfer and wait for it, so the next check sees the swept balance
+    async fn sweep(&self, request: TransactionRequest, amount: String) -> Result<()> {
+        let tx_hash = self
+            .tx_executor
+            .send_wallet_transaction(request)
+            .await
+            .with_context(|| format!("Failed to sweep {}", amount))?;
+        info!("Sweeping {} to the cold address in {:?}", amount, tx_hash);
+
+        let result = self
+            .tx_executor
+            .wait_for_transaction(tx_hash, SWEEP_TIMEOUT)
+            .await?;
+        if result.success {
+            self.alert_manager.raise(
+                AlertSeverity::Info,
+                ALERT_SOURCE,
+                format!("Swept {} to the cold address in {:?}", amount, tx_hash),
+            );
+        } else {
+            self.alert_manager.raise(
+                AlertSeverity::Warning,
+                ALERT_SOURCE,
+                format!("Sweep of {} in {:?} failed", amount, tx_hash),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// The part of a balance above its sweep target, if it exceeds the target by the sweep margin
+fn sweepable(balance: U256, target: U256) -> Option<U256> {
+    let threshold = target.saturating_add(
+        target
+            .saturating_mul(U256::from((SWEEP_MARGIN * 100.0) as u64))
+            .checked_div(U256::from(100))
+            .unwrap_or_default(),
+    );
+
+    (balance > threshold).then(|| balance - target)
+}
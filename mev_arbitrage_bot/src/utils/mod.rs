@@ -2,9 +2,14 @@
 //!
 //! This module contains utility functions for the MEV arbitrage bot.
 
+mod multicall;
+
+pub use multicall::{Call, Multicall, MAX_BATCH_CALLS, MULTICALL3_ADDRESS};
+
 use anyhow::Result;
 use ethers::types::{Address, U256};
-use log::{debug, error, info, warn};
+use log::debug;
+use rand::Rng;
 use std::str::FromStr;
 use std::time::{Duration, Instant};
 
@@ -156,40 +161,74 @@ where
     (result, duration)
 }
 
-/// Retry a function with exponential backoff
-pub async fn retry_with_backoff<F, Fut, T, E>(
+/// Exponential backoff between the attempts of a retried function
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backoff {
+    /// Attempts after the first one
+    pub max_retries: u32,
+
+    /// Delay before the first retry, doubled before each next one
+    pub initial: Duration,
+
+    /// Longest delay between two attempts
+    pub max: Duration,
+
+    /// Share of each delay added at random (0 to 1), so that callers failing together do not
+    /// retry together
+    pub jitter: f64,
+}
+
+impl Backoff {
+    /// Get the delay before a retry (the first retry is 0)
+    pub fn delay(&self, retry: u32) -> Duration {
+        let delay = self
+            .initial
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max);
+        if self.jitter <= 0.0 {
+            return delay;
+        }
+
+        delay.mul_f64(1.0 + rand::thread_rng().gen_range(0.0..=self.jitter))
+    }
+}
+
+/// Retry a function with exponential backoff while its errors are `retryable`
+///
+/// `retryable` is only asked while retries remain.
+pub async fn retry_with_backoff<F, Fut, T, E, P>(
     f: F,
-    max_retries: u32,
-    initial_backoff: Duration,
+    backoff: Backoff,
+    retryable: P,
 ) -> Result<T, E>
 where
     F: Fn() -> Fut,
     Fut: std::future::Future<Output = Result<T, E>>,
     E: std::fmt::Debug,
+    P: Fn(&E) -> bool,
 {
     let mut retries = 0;
-    let mut backoff = initial_backoff;
 
     loop {
         match f().await {
             Ok(result) => return Ok(result),
             Err(e) => {
-                if retries >= max_retries {
+                if retries >= backoff.max_retries || !retryable(&e) {
                     return Err(e);
                 }
 
-                warn!(
-                    "Retry {}/{} failed: {:?}. Retrying in {:?}...",
+                let delay = backoff.delay(retries);
+                debug!(
+                    "Attempt {}/{} failed: {:?}. Retrying in {:?}...",
                     retries + 1,
-                    max_retries,
+                    backoff.max_retries + 1,
                     e,
-                    backoff
+                    delay
                 );
 
-                tokio::time::sleep(backoff).await;
+                tokio::time::sleep(delay).await;
 
                 retries += 1;
-                backoff = backoff.saturating_mul(2);
             }
         }
     }
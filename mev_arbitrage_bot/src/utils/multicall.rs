@@ -0,0 +1,117 @@
+//! Multicall Module
+//!
+//! This module is responsible for batching contract reads through Multicall3: up to
+//! `MAX_BATCH_CALLS` reads go out as a single `aggregate3` `eth_call`, each allowed to fail on
+//! its own, so refreshing dozens of pools costs one RPC request instead of one per pool.
+
+use anyhow::{Context, Result};
+use ethers::abi::Token;
+use ethers::middleware::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, BlockId, Bytes, TransactionRequest};
+use std::sync::Arc;
+
+use crate::assets::ContractAbi;
+use crate::blockchain::RpcClient;
+use crate::config::Config;
+use crate::utils::validate_and_parse_address;
+
+/// Address Multicall3 is deployed at on most chains
+pub const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
+/// Most reads sent in one `aggregate3` call, keeping the call under node gas and size limits
+pub const MAX_BATCH_CALLS: usize = 150;
+
+/// A contract read to batch
+#[derive(Debug, Clone)]
+pub struct Call {
+    /// Contract called
+    pub target: Address,
+
+    /// Encoded call
+    pub data: Bytes,
+}
+
+/// Batches contract reads through a Multicall3 contract
+pub struct Multicall {
+    client: Arc<RpcClient>,
+    address: Address,
+}
+
+impl Multicall {
+    /// Create a batcher for the configured Multicall3 contract
+    pub fn new(config: &Config, client: Arc<RpcClient>) -> Result<Self> {
+        let address = validate_and_parse_address(
+            config
+                .ethereum
+                .multicall_address
+                .as_deref()
+                .unwrap_or(MULTICALL3_ADDRESS),
+        )
+        .context("Invalid Multicall3 address")?;
+
+        Ok(Self { client, address })
+    }
+
+    /// Get the client reads are sent through
+    pub fn client(&self) -> &Arc<RpcClient> {
+        &self.client
+    }
+
+    /// Execute reads at a block, in batches of at most `MAX_BATCH_CALLS`
+    ///
+    /// Returns the output of each call in order, or `None` for a call that reverted. Fails if
+    /// a batch itself fails, e.g. when no Multicall3 is deployed at the address.
+    pub async fn aggregate(&self, calls: &[Call], block: BlockId) -> Result<Vec<Option<Bytes>>> {
+        let aggregate3 = ContractAbi::Multicall3.abi().function("aggregate3")?;
+
+        let mut outputs = Vec::with_capacity(calls.len());
+        for batch in calls.chunks(MAX_BATCH_CALLS) {
+            let calls = batch
+                .iter()
+                .map(|call| {
+                    Token::Tuple(vec![
+                        Token::Address(call.target),
+                        Token::Bool(true),
+                        Token::Bytes(call.data.to_vec()),
+                    ])
+                })
+                .collect();
+            let request: TypedTransaction = TransactionRequest::new()
+                .to(self.address)
+                .data(aggregate3.encode_input(&[Token::Array(calls)])?)
+                .into();
+
+            let output = self
+                .client
+                .call(&request, Some(block))
+                .await
+                .context("Multicall3 aggregate3 call failed")?;
+            let results = aggregate3
+                .decode_output(&output)?
+                .into_iter()
+                .next()
+                .and_then(Token::into_array)
+                .context("Malformed aggregate3 output")?;
+            if results.len() != batch.len() {
+                anyhow::bail!(
+                    "aggregate3 returned {} results for {} calls",
+                    results.len(),
+                    batch.len()
+                );
+            }
+
+            for result in results {
+                let output = match result.into_tuple().as_deref() {
+                    Some([Token::Bool(true), Token::Bytes(data)]) => {
+                        Some(Bytes::from(data.clone()))
+                    }
+                    _ => None,
+                };
+                outputs.push(output);
+            }
+        }
+
+        Ok(outputs)
+    }
+}
@@ -0,0 +1,60 @@
+//! Fork Pipeline Tests
+//!
+//! These tests run the pipeline end to end on an Anvil fork of mainnet through the `testing`
+//! harness. They skip when `FORK_RPC_URL` is unset.
+
+#![cfg(feature = "testing")]
+
+use ethers::providers::Middleware;
+use ethers::types::U256;
+
+use mev_arbitrage_bot::testing::fixtures::{self, UNISWAP_WETH_USDC, USDC};
+use mev_arbitrage_bot::testing::{HarnessOptions, TestHarness};
+
+/// USDC added to the Uniswap WETH/USDC pair, enough to put it out of line with Sushiswap
+const SEEDED_USDC: u64 = 5_000_000 * 1_000_000;
+
+#[tokio::test]
+async fn deploys_the_executor_contract() {
+    let Some(options) = HarnessOptions::from_env() else {
+        eprintln!("FORK_RPC_URL is not set, skipping");
+        return;
+    };
+    let harness = TestHarness::start(options).await.expect("harness starts");
+
+    let code = harness
+        .blockchain_client()
+        .get_code(harness.contract_address(), None)
+        .await
+        .expect("contract code is read");
+    assert!(!code.is_empty(), "the executor contract has no code");
+}
+
+#[tokio::test]
+async fn executes_a_seeded_cross_dex_opportunity() {
+    let Some(options) = HarnessOptions::from_env() else {
+        eprintln!("FORK_RPC_URL is not set, skipping");
+        return;
+    };
+    let harness = TestHarness::start(options).await.expect("harness starts");
+
+    harness
+        .seed_pair(
+            fixtures::address(UNISWAP_WETH_USDC),
+            fixtures::address(USDC),
+            U256::from(SEEDED_USDC),
+        )
+        .await
+        .expect("pair is seeded");
+
+    let run = harness.run_pipeline().await.expect("pipeline runs");
+    assert!(
+        !run.opportunities.is_empty(),
+        "no opportunity found after seeding the pair"
+    );
+    assert!(
+        run.successful().next().is_some(),
+        "no execution succeeded: {:?}",
+        run.executions
+    );
+}
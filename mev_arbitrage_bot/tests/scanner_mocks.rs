@@ -0,0 +1,108 @@
+//! Scanner Mock Tests
+//!
+//! These tests run the scanner's strategies against the `test-utils` mocks: scripted pools,
+//! a price table and fixed gas fees, with no node behind any of them.
+
+#![cfg(feature = "test-utils")]
+
+use ethers::types::{Address, U256};
+use ethers::utils::parse_units;
+use std::sync::Arc;
+
+use mev_arbitrage_bot::dex::{DexInterface, DexType};
+use mev_arbitrage_bot::scanner::{self, ArbitrageOpportunity};
+use mev_arbitrage_bot::test_utils::{
+    mock_interfaces, offline_client, offline_config, MockDexInterface, MockGasOptimizer,
+    MockPriceOracle,
+};
+
+const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+
+fn address(address: &str) -> Address {
+    address.parse().expect("valid address")
+}
+
+fn amount(amount: u64, decimals: u32) -> U256 {
+    parse_units(amount, decimals).expect("valid amount").into()
+}
+
+/// A WETH/USDC pool holding 1000 WETH against the given USDC
+fn weth_usdc_pool(dex_type: DexType, pool: u64, usdc_reserve: u64) -> Arc<dyn DexInterface> {
+    Arc::new(MockDexInterface::new(dex_type).with_pool(
+        Address::from_low_u64_be(pool),
+        (address(WETH), address(USDC)),
+        (amount(1_000, 18), amount(usdc_reserve, 6)),
+    ))
+}
+
+/// Scan WETH and USDC only, against Uniswap and Sushiswap pools priced as given
+async fn scan(uniswap_usdc: u64, sushiswap_usdc: u64) -> Vec<ArbitrageOpportunity> {
+    let mut config = offline_config();
+    config
+        .flash_loan
+        .tokens
+        .retain(|token| token.symbol != "DAI");
+    let config = Arc::new(config);
+
+    let dex_interfaces = mock_interfaces(
+        &config,
+        [
+            weth_usdc_pool(DexType::UniswapV2, 1, uniswap_usdc),
+            weth_usdc_pool(DexType::Sushiswap, 2, sushiswap_usdc),
+        ],
+    )
+    .expect("mock interfaces are collected");
+    let price_oracle = Arc::new(
+        MockPriceOracle::new(2_000.0)
+            .with_price(address(WETH), 2_000.0)
+            .with_price(address(USDC), 1.0),
+    );
+    let gas_optimizer = Arc::new(MockGasOptimizer::new(amount(10, 9), amount(1, 9)));
+
+    let pair_tiers = scanner::create_pair_tiers(&config, None).expect("pair tiers are created");
+    let scanner = scanner::create_scanner(
+        &config,
+        offline_client(),
+        dex_interfaces,
+        price_oracle,
+        gas_optimizer,
+        pair_tiers,
+        None,
+    )
+    .await
+    .expect("scanner is created");
+
+    scanner.scan().await.expect("scan completes")
+}
+
+#[tokio::test]
+async fn finds_a_cross_dex_spread() {
+    // Sushiswap pays about 5% more USDC for WETH than Uniswap
+    let opportunities = scan(2_000_000, 2_100_000).await;
+
+    let opportunity = opportunities
+        .iter()
+        .find(|opportunity| {
+            opportunity.token_path == vec![address(WETH), address(USDC), address(WETH)]
+        })
+        .expect("the WETH -> USDC -> WETH spread is found");
+    assert_eq!(opportunity.source_dex, DexType::Sushiswap.name());
+    assert_eq!(opportunity.target_dex, DexType::UniswapV2.name());
+    assert!(opportunity.net_profit > 0.0);
+    assert!(opportunity.net_profit < opportunity.estimated_profit);
+}
+
+#[tokio::test]
+async fn ignores_pools_in_line() {
+    let opportunities = scan(2_000_000, 2_000_000).await;
+
+    assert!(
+        opportunities.is_empty(),
+        "no spread between pools at the same price: {:?}",
+        opportunities
+            .iter()
+            .map(|opportunity| opportunity.id.as_str())
+            .collect::<Vec<_>>()
+    );
+}